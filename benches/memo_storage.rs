@@ -0,0 +1,59 @@
+//! メモの格納方法による動的計画法の計算時間の比較
+//!
+//! 値の種類ごとに配列を分けたメモを用いる[`FitResult::fit`]と，`Vec<Vec<Option<_>>>`のメモを用いる
+//! [`calc_dp::CalcDP::calc_memo_all_par`]は，同じ要素を同じ順序で計算し，区間の評価値を並列に計算する点も等しい．
+//! ただし前者は加えて区間の評価値が有限であることの確認と計算量の記録を行う．
+//!
+//! `cargo bench --bench memo_storage`で実行する．
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{FitResult, Method};
+use cpd_tools::dp_tools::{calc_dp, CalcDpError, Memo};
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use process_param::Tau;
+
+
+/// `Vec<Vec<Option<_>>>`のメモを用いる型
+struct VecMemo;
+
+impl calc_dp::CalcTT<f64, HeteroscedasticMeanCost> for VecMemo {
+    fn calc_value(data: &HeteroscedasticMeanCost, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        data.segment_value(t_k_1, t_k)
+    }
+}
+
+impl calc_dp::CalcDP<f64, HeteroscedasticMeanCost> for VecMemo {
+    fn memo_all(&self) -> Memo<f64> {
+        Vec::new()
+    }
+}
+
+
+fn cost(n: usize) -> HeteroscedasticMeanCost {
+    let data = (0..n).map(|i| if i < n / 2 { 0.0 } else { 1.0 } + (i as f64 * 1.3).sin() * 0.5)
+                     .collect::<Vec<f64>>();
+    HeteroscedasticMeanCost::new(&data, &vec![0.5; n]).unwrap()
+}
+
+
+fn memo_storage(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memo_storage");
+    group.sample_size(10);
+    for n in [100, 200, 400] {
+        let data = cost(n);
+        let t_max = data.t_max();
+        group.bench_with_input(BenchmarkId::new("soa", n), &data, |b, data| {
+            b.iter(|| FitResult::fit(black_box(data), Method::Dp, None).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("vec", n), &data, |b, data| {
+            b.iter(|| <VecMemo as calc_dp::CalcDP<f64, _>>::calc_memo_all_par(black_box(data), &t_max).unwrap())
+        });
+    }
+    group.finish();
+}
+
+
+criterion_group!(benches, memo_storage);
+criterion_main!(benches);
@@ -0,0 +1,72 @@
+/*
+ * MATLAB/Octave から cpd_tools を呼び出す MEX 関数の例
+ *
+ * ビルド（MATLAB）:
+ *   mex -I../../include cpd_detect_mex.c -L../../target/release -lcpd_tools
+ * ビルド（Octave）:
+ *   mkoctfile --mex -I../../include cpd_detect_mex.c -L../../target/release -lcpd_tools
+ *
+ * 使い方:
+ *   [cps, value] = cpd_detect_mex(X, 'hetero_mean', 'dp', 'linear', 10);
+ *   cps = cpd_detect_mex(X, 'hetero_mean', 'dp2', 'num_change', 2);
+ *
+ * X は各列をコスト関数へ渡す系列とした行列．cps は各区間の最後の観測の位置（1始まりの添字と一致）．
+ */
+#include <string.h>
+
+#include "mex.h"
+#include "cpd_tools.h"
+
+#define NAME_LEN 64
+
+void mexFunction(int nlhs, mxArray *plhs[], int nrhs, const mxArray *prhs[])
+{
+    char cost[NAME_LEN], method_name[NAME_LEN], penalty_name[NAME_LEN], msg[256];
+    int method, penalty_kind, code;
+    size_t n_rows, n_cols, len = 0;
+    uint32_t *cps;
+    double value, *out;
+
+    if (nrhs != 5) {
+        mexErrMsgIdAndTxt("cpd_tools:nrhs", "Usage: cpd_detect_mex(X, cost, method, penalty_kind, penalty)");
+    }
+    if (!mxIsDouble(prhs[0]) || mxIsComplex(prhs[0])) {
+        mexErrMsgIdAndTxt("cpd_tools:input", "X must be a real double matrix.");
+    }
+    if (mxGetString(prhs[1], cost, NAME_LEN) || mxGetString(prhs[2], method_name, NAME_LEN)
+        || mxGetString(prhs[3], penalty_name, NAME_LEN)) {
+        mexErrMsgIdAndTxt("cpd_tools:input", "cost, method and penalty_kind must be strings.");
+    }
+
+    if (strcmp(method_name, "dp2") == 0) {
+        method = CPD_METHOD_DP2;
+    } else if (strcmp(method_name, "auto") == 0) {
+        method = CPD_METHOD_AUTO;
+    } else {
+        method = CPD_METHOD_DP;
+    }
+    penalty_kind = strcmp(penalty_name, "num_change") == 0 ? CPD_PENALTY_NUM_CHANGE : CPD_PENALTY_LINEAR;
+
+    n_rows = mxGetM(prhs[0]);
+    n_cols = mxGetN(prhs[0]);
+    /* 変化点は系列長未満であるため n_rows を上限とする */
+    cps = (uint32_t *)mxCalloc(n_rows > 0 ? n_rows : 1, sizeof(uint32_t));
+
+    code = cpd_detect_flat(mxGetPr(prhs[0]), n_rows, n_cols, cost, method, penalty_kind,
+                           mxGetScalar(prhs[4]), cps, n_rows, &len, &value);
+    if (code != CPD_OK) {
+        cpd_last_error(msg, sizeof(msg));
+        mxFree(cps);
+        mexErrMsgIdAndTxt("cpd_tools:detect", "cpd_detect_flat failed (code %d): %s", code, msg);
+    }
+
+    plhs[0] = mxCreateDoubleMatrix(1, len, mxREAL);
+    out = mxGetPr(plhs[0]);
+    for (size_t i = 0; i < len; i++) {
+        out[i] = (double)cps[i];
+    }
+    if (nlhs > 1) {
+        plhs[1] = mxCreateDoubleScalar(value);
+    }
+    mxFree(cps);
+}
@@ -0,0 +1,58 @@
+//! 設定ファイルに基づいて変化点検出を実行する例
+//!
+//! 一時ディレクトリにCSVファイルと設定ファイルを作成し，[`cpd_tools::config::run`]により検出する．
+//!
+//! ```sh
+//! cargo run --example config_run
+//! ```
+
+use cpd_tools::config::{self, RunSpec};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand_distr::{Distribution, Normal};
+
+const SPEC: &str = r#"
+method = "dp"
+penalty = { num_change = 1 }
+
+[data]
+path = "series.csv"
+columns = ["value", "variance"]
+
+[cost]
+name = "hetero_mean"
+
+[output]
+path = "result.csv"
+"#;
+
+fn main() {
+    let dir = std::env::temp_dir().join("cpd_tools_config_run");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // 計測器の不確かさ（分散）が観測ごとに異なる系列
+    let mut rng = StdRng::seed_from_u64(416);
+    let mut csv = "value,variance\n".to_owned();
+    for t in 0..150 {
+        let variance = if t % 3 == 0 { 4.0 } else { 0.25 };
+        let mu = if t < 90 { 10.0 } else { 11.5 };
+        let x = Normal::new(mu, f64::sqrt(variance)).unwrap().sample(&mut rng);
+        csv.push_str(&format!("{x},{variance}\n"));
+    }
+    std::fs::write(dir.join("series.csv"), csv).unwrap();
+    std::fs::write(dir.join("spec.toml"), SPEC).unwrap();
+
+    let spec = RunSpec::from_file(&dir.join("spec.toml")).unwrap();
+    let result = config::run(&spec).unwrap();
+
+    println!("change points: {:?}", result.change_points);
+    println!("{}", std::fs::read_to_string(dir.join("result.csv")).unwrap());
+
+    assert!(result.change_points[0].abs_diff(90) <= 3);
+}
+
+#[test]
+fn run_example() {
+    main();
+}
@@ -0,0 +1,46 @@
+//! 正規分布の平均変化を検出する例
+//!
+//! 平均が2回変化する系列を生成し，ペナルティにより変化点個数を選択して変化点を検出する．
+//!
+//! ```sh
+//! cargo run --example mean_shift
+//! ```
+
+use cpd_tools::cost::HeteroscedasticMeanCost;
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand_distr::{Distribution, Normal};
+
+fn main() {
+    // 平均 0 -> 3 -> 1 と変化する系列
+    let means = [(0.0, 100), (3.0, 80), (1.0, 120)];
+    let mut rng = StdRng::seed_from_u64(408);
+    let data = means.iter()
+                    .flat_map(|(mu, len)| {
+                        let normal = Normal::new(*mu, 1.0).unwrap();
+                        (0..*len).map(|_| normal.sample(&mut rng)).collect::<Vec<f64>>()
+                    })
+                    .collect::<Vec<f64>>();
+
+    // 分散が既知(=1)の平均変化
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    let beta = 3.0 * (data.len() as f64).ln();
+    let constraints = Constraints::default().with_max_k(10);
+    let result = detect(&cost, Method::Dp, &Penalty::Linear(beta), &constraints).unwrap();
+
+    println!("change points: {:?}", result.change_points);
+    for (t_k_1, t_k) in result.segments() {
+        println!("({t_k_1}, {t_k}]: mean = {:.3}", cost.mean(t_k_1, t_k).unwrap());
+    }
+
+    assert_eq!(result.num_change(), 2);
+    assert!(result.change_points[0].abs_diff(100) <= 3);
+    assert!(result.change_points[1].abs_diff(180) <= 3);
+}
+
+#[test]
+fn run_example() {
+    main();
+}
@@ -0,0 +1,30 @@
+//! 公開データ（ナイル川の年間流量）の平均変化を検出する例
+//!
+//! [`cpd_tools::datasets::fetch`]によりデータを取得し，1個の変化点を検出する．
+//! 1898年のアスワン・ダム建設の開始前後で流量の平均が変化したことが知られている．
+//! 初回の実行時のみネットワークに接続する．
+//!
+//! ```sh
+//! cargo run --example nile --features datasets
+//! ```
+
+use cpd_tools::cost::HeteroscedasticMeanCost;
+use cpd_tools::datasets;
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+fn main() {
+    let data = datasets::fetch("nile").unwrap();
+
+    // 隣接する差分から平均の変化に影響されにくい分散の推定値を求める
+    let diff_var = data.windows(2)
+                       .map(|w| (w[1] - w[0]).powi(2))
+                       .sum::<f64>() / (2.0 * (data.len() - 1) as f64);
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![diff_var; data.len()]).unwrap();
+    let result = detect(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+
+    let first_year = 1871;
+    for (t_k_1, t_k) in result.segments() {
+        println!("{}-{}: mean flow = {:.1}", first_year + t_k_1, first_year + t_k - 1, cost.mean(t_k_1, t_k).unwrap());
+    }
+    println!("change after {}", first_year + result.change_points[0] - 1);
+}
@@ -0,0 +1,47 @@
+//! 不良数の発生率の変化を検出する例
+//!
+//! [`cpd_tools::segment_cost`]によりポアソン分布に基づくコスト関数を定義し，
+//! 発生率が途中で上昇した不良数の系列から変化点を検出する．
+//!
+//! ```sh
+//! cargo run --example poisson_defects
+//! ```
+
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand_distr::{Distribution, Poisson};
+
+cpd_tools::segment_cost! {
+    /// ポアソン分布に基づくコスト関数
+    pub struct PoissonCost for u64 {
+        stat PoissonStat { n, sum } = |y| PoissonStat { n: 1.0, sum: *y as f64 };
+        loglik = |s| if s.sum > 0.0 { s.sum * (s.sum / s.n).ln() - s.sum } else { 0.0 };
+    }
+}
+
+fn main() {
+    // 1日あたりの不良数の平均が 2 -> 5 へ上昇
+    let mut rng = StdRng::seed_from_u64(411);
+    let before = Poisson::new(2.0).unwrap();
+    let after = Poisson::new(5.0).unwrap();
+    let data = (0..100).map(|t| if t < 60 { before.sample(&mut rng) } else { after.sample(&mut rng) } as u64)
+                       .collect::<Vec<u64>>();
+
+    let cost = PoissonCost::new(&data);
+    let result = detect(&cost, Method::Dp2, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+
+    println!("change point: {:?}", result.change_points);
+    for (t_k_1, t_k) in result.segments() {
+        let sum = data[(t_k_1 as usize)..(t_k as usize)].iter().sum::<u64>();
+        println!("({t_k_1}, {t_k}]: rate = {:.3}", sum as f64 / (t_k - t_k_1) as f64);
+    }
+
+    assert!(result.change_points[0].abs_diff(60) <= 5);
+}
+
+#[test]
+fn run_example() {
+    main();
+}
@@ -0,0 +1,64 @@
+/*
+ * cpd_tools C API
+ *
+ * 変化点検出を平坦な配列とエラーコードで呼び出すための宣言．
+ * 詳細はRust側の `cpd_tools::ffi` のドキュメントを参照．
+ */
+#ifndef CPD_TOOLS_H
+#define CPD_TOOLS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* エラーコード */
+#define CPD_OK                   0
+#define CPD_ERR_NULL_POINTER     1
+#define CPD_ERR_INVALID_ARGUMENT 2
+#define CPD_ERR_DETECTION        3
+#define CPD_ERR_BUFFER_TOO_SMALL 4
+#define CPD_ERR_PANIC            5
+
+/* 変化点検出の手法 */
+#define CPD_METHOD_DP   0
+#define CPD_METHOD_DP2  1
+#define CPD_METHOD_AUTO 2
+
+/* 変化点個数の決め方 */
+#define CPD_PENALTY_NUM_CHANGE 0
+#define CPD_PENALTY_LINEAR     1
+
+/*
+ * 変化点を検出する．
+ *
+ * data は列優先で格納した n_rows 行 n_cols 列の入力値．
+ * 変化点は out_change_points へ昇順に書き込まれ，その個数は out_len に格納される．
+ * CPD_ERR_BUFFER_TOO_SMALL の場合，out_len には必要な長さが格納される．
+ * out_value は不要な場合 NULL でよい．
+ */
+int cpd_detect_flat(const double *data,
+                    size_t n_rows,
+                    size_t n_cols,
+                    const char *cost,
+                    int method,
+                    int penalty_kind,
+                    double penalty,
+                    uint32_t *out_change_points,
+                    size_t out_capacity,
+                    size_t *out_len,
+                    double *out_value);
+
+/*
+ * 直前に発生したエラーのメッセージを buf へ NUL 終端で書き込む．
+ * 返り値は NUL 終端を除くメッセージ全体の長さ．
+ */
+size_t cpd_last_error(char *buf, size_t capacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CPD_TOOLS_H */
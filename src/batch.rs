@@ -0,0 +1,352 @@
+//! 複数の系列に対する変化点検出の一括実行
+
+use crate::config::{self, RunSpec, CostSpec};
+use crate::cost::{CostRegistry, CostInput};
+use crate::detect::{self, DetectionResult, Method, Penalty, Constraints};
+use crate::dp_tools::CalcDpError;
+use crate::error::Error;
+use crate::io;
+use crate::rng::RngConfig;
+use crate::stats::{permutation_test, benjamini_hochberg};
+
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+extern crate rayon;
+use rayon::prelude::*;
+
+extern crate glob;
+
+
+/// 一括実行における1系列分の結果
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    /// 入力ファイルのパス
+    pub path: PathBuf,
+    /// 検出結果．失敗した場合はそのError．
+    pub result: Result<DetectionResult, Error>,
+}
+
+
+/// パターンに一致する全てのファイルに対して変化点検出を並列に実行する
+///
+/// `spec`の入力データのパスはパターンに一致した各ファイルに置き換えて実行する．
+/// 一部のファイルで検出に失敗した場合も他のファイルの処理は継続し，失敗は[`BatchItem::result`]に格納される．
+///
+/// # 出力について
+/// `spec.output.path`を指定した場合はそのディレクトリへ出力する．
+/// 各入力に対する区間ごとの結果を`<入力ファイル名>.result.csv`として，全入力の要約を`summary.csv`として書き出す．
+///
+/// # 引数
+/// * `pattern` - 入力ファイルのglobパターン（例: `"data/*.csv"`）
+/// * `spec` - 変化点検出の実行内容
+///
+/// # 返り値
+/// * `items` - 入力ファイルのパスの昇順に並べた結果
+pub fn run_dir(pattern: &str, spec: &RunSpec) -> Result<Vec<BatchItem>, Error> {
+    run_dir_with_registry(pattern, spec, &CostRegistry::with_builtins())
+}
+
+
+/// 登録簿を指定して，パターンに一致する全てのファイルに対して変化点検出を並列に実行する
+///
+/// 詳細は[`run_dir`]を参照．
+///
+/// # 引数
+/// * `pattern` - 入力ファイルのglobパターン
+/// * `spec` - 変化点検出の実行内容
+/// * `registry` - コスト関数の登録簿
+pub fn run_dir_with_registry(pattern: &str, spec: &RunSpec, registry: &CostRegistry) -> Result<Vec<BatchItem>, Error> {
+    let mut paths = glob::glob(pattern).map_err(|e| CalcDpError::new(format!("Invalid glob pattern \"{pattern}\": {e}")).with_source(e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_file())
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    let out_dir = spec.output.path.clone();
+    if let Some(dir) = &out_dir {
+        std::fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+    }
+
+    let items = paths.into_par_iter()
+                     .map(|path| {
+                         let mut spec_i = spec.clone();
+                         spec_i.data.path = path.clone();
+                         spec_i.output.path = out_dir.as_ref().map(|dir| dir.join(result_file_name(&path)));
+                         let result = config::run_with_registry(&spec_i, registry);
+                         BatchItem { path, result }
+                     })
+                     .collect::<Vec<BatchItem>>();
+
+    if let Some(dir) = &out_dir {
+        io::write_text(&dir.join("summary.csv"), &summary_csv(&items))?;
+    }
+    Ok(items)
+}
+
+
+/// メモリ上の系列に対する検出内容（[`detect_many`]）
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectSpec {
+    /// コスト関数
+    pub cost: CostSpec,
+    /// 変化点検出の手法
+    pub method: Method,
+    /// 変化点個数の決め方
+    pub penalty: Penalty,
+    /// 変化点検出における制約
+    pub constraints: Constraints,
+}
+
+impl From<&RunSpec> for DetectSpec {
+    /// 実行内容のうち入力データと出力先以外を用いる
+    fn from(spec: &RunSpec) -> Self {
+        DetectSpec {
+            cost: spec.cost.clone(),
+            method: spec.method,
+            penalty: spec.penalty,
+            constraints: spec.constraints,
+        }
+    }
+}
+
+
+/// [`detect_many`]が返す1系列分の結果
+#[derive(Debug, Clone)]
+pub struct StreamItem {
+    /// 入力における系列の順番
+    pub index: usize,
+    /// 検出結果．失敗した場合はそのError．
+    pub result: Result<DetectionResult, Error>,
+}
+
+
+/// 検出が完了した順に結果を返すiterator（[`detect_many`]）
+///
+/// 全ての系列の結果を返すと終了する．途中で破棄した場合，未着手の系列の検出は行わない．
+#[derive(Debug)]
+pub struct DetectStream {
+    receiver: Receiver<StreamItem>,
+}
+
+impl Iterator for DetectStream {
+    type Item = StreamItem;
+
+    fn next(&mut self) -> Option<StreamItem> {
+        self.receiver.recv().ok()
+    }
+}
+
+
+/// 多数の系列に対して変化点検出を並列に実行し，完了した順に結果を返す
+///
+/// 系列は別のスレッドでrayonにより並列に処理するため，本関数は直ちに返る．
+/// 系列ごとの失敗（パニックを含む）は[`StreamItem::result`]に格納され，他の系列の処理は継続する．
+/// 結果の順番は完了順であるため，入力の順番は[`StreamItem::index`]により対応付ける．
+///
+/// # 引数
+/// * `series` - 系列の列．各系列はコスト関数に渡す列の組とする．
+/// * `spec` - 系列ごとの検出内容
+pub fn detect_many<I>(series: I, spec: &DetectSpec) -> DetectStream
+where
+    I: IntoIterator<Item = Vec<Vec<f64>>>,
+    I::IntoIter: Send + 'static,
+{
+    detect_many_with_registry(series, spec, &CostRegistry::with_builtins())
+}
+
+
+/// 登録簿を指定して，多数の系列に対して変化点検出を並列に実行し，完了した順に結果を返す
+///
+/// 詳細は[`detect_many`]を参照．
+///
+/// # 引数
+/// * `series` - 系列の列
+/// * `spec` - 系列ごとの検出内容
+/// * `registry` - コスト関数の登録簿
+pub fn detect_many_with_registry<I>(series: I, spec: &DetectSpec, registry: &CostRegistry) -> DetectStream
+where
+    I: IntoIterator<Item = Vec<Vec<f64>>>,
+    I::IntoIter: Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let series = series.into_iter();
+    let spec = spec.clone();
+    let registry = registry.clone();
+    thread::spawn(move || {
+        // 受信側が破棄された場合は送信に失敗し，以降の系列は処理しない
+        let _ = series.enumerate()
+                      .par_bridge()
+                      .try_for_each_with(sender, |sender, (index, columns)| {
+                          let result = panic::catch_unwind(AssertUnwindSafe(|| detect_columns(&columns, &spec, &registry)))
+                                           .unwrap_or_else(|_| Err(CalcDpError::new(format!("Panic occurred during detection of series {index}.")).into()));
+                          sender.send(StreamItem { index, result })
+                      });
+    });
+    DetectStream { receiver }
+}
+
+
+/// 1系列に対して変化点検出を実行する
+fn detect_columns(columns: &[Vec<f64>], spec: &DetectSpec, registry: &CostRegistry) -> Result<DetectionResult, Error> {
+    let input = CostInput {
+        columns,
+        params: &spec.cost.params,
+    };
+    let cost = registry.build(&spec.cost.name, &input).map_err(|e| Error::cost(&spec.cost.name, e))?;
+    Ok(detect::detect(&cost, spec.method, &spec.penalty, &spec.constraints)?)
+}
+
+
+/// 偽発見率の制御の設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FdrOptions {
+    /// 偽発見率の上限$ q $
+    pub q: f64,
+    /// 各系列の並べ替え検定における並べ替えの回数
+    pub n_perm: usize,
+    /// 乱数の設定．系列ごとに[`RngConfig::fork`]した設定を並べ替え検定に用いる．
+    pub rng: RngConfig,
+}
+
+impl Default for FdrOptions {
+    fn default() -> Self {
+        FdrOptions {
+            q: 0.05,
+            n_perm: 999,
+            rng: RngConfig::default(),
+        }
+    }
+}
+
+
+/// 偽発見率を制御した一括実行における1系列分の結果
+#[derive(Debug, Clone)]
+pub struct FdrItem {
+    /// 検出結果
+    pub item: BatchItem,
+    /// 並べ替え検定のp値．検出に失敗した場合は`None`．
+    pub p_value: Option<f64>,
+    /// Benjamini–Hochberg法により調整したp値
+    pub adjusted_p_value: Option<f64>,
+    /// 変化ありと報告するか
+    pub changed: bool,
+}
+
+
+/// 偽発見率を制御しつつ，パターンに一致する全てのファイルに対して変化点検出を実行する
+///
+/// [`run_dir_with_registry`]による検出に加えて，各系列について変化点の有無の並べ替え検定（[`permutation_test`]）を行い，
+/// Benjamini–Hochberg法で調整したp値が$ q $以下の系列のみを変化ありとする．
+/// 多数のセンサ等の系列を一度に監視する場合に，変化ありと報告する系列のうち誤りである割合を$ q $以下に抑える．
+///
+/// `spec.output.path`を指定した場合は，[`run_dir`]の出力に加えて`fdr.csv`へ系列ごとのp値と判定を書き出す．
+///
+/// # 引数
+/// * `pattern` - 入力ファイルのglobパターン
+/// * `spec` - 変化点検出の実行内容
+/// * `registry` - コスト関数の登録簿
+/// * `options` - 偽発見率の制御の設定
+pub fn run_dir_fdr(pattern: &str, spec: &RunSpec, registry: &CostRegistry, options: &FdrOptions) -> Result<Vec<FdrItem>, Error> {
+    if !(options.q > 0.0 && options.q < 1.0) {
+        return Err(CalcDpError::new(format!("q must be in (0, 1), but {} is given.", options.q)).into());
+    }
+    let items = run_dir_with_registry(pattern, spec, registry)?;
+
+    let p_values = items.par_iter()
+                        .enumerate()
+                        .map(|(i, item)| {
+                            if item.result.is_err() {
+                                return None;
+                            }
+                            let columns = io::read_csv_columns(&item.path, &spec.data.columns, spec.data.header, spec.data.delimiter).ok()?;
+                            let build = |columns: &[Vec<f64>]| registry.build(&spec.cost.name, &CostInput {
+                                columns,
+                                params: &spec.cost.params,
+                            });
+                            permutation_test(&columns, build, options.n_perm, options.rng.fork(i as u64))
+                                .ok()
+                                .map(|test| test.p_value)
+                        })
+                        .collect::<Vec<Option<f64>>>();
+
+    let tested = p_values.iter().filter_map(|p| *p).collect::<Vec<f64>>();
+    let mut adjusted = benjamini_hochberg(&tested).into_iter();
+    let fdr_items = items.into_iter()
+                         .zip(p_values)
+                         .map(|(item, p_value)| {
+                             let adjusted_p_value = p_value.and_then(|_| adjusted.next());
+                             FdrItem {
+                                 item,
+                                 p_value,
+                                 adjusted_p_value,
+                                 changed: adjusted_p_value.is_some_and(|p| p <= options.q),
+                             }
+                         })
+                         .collect::<Vec<FdrItem>>();
+
+    if let Some(dir) = &spec.output.path {
+        io::write_text(&dir.join("fdr.csv"), &fdr_csv(&fdr_items))?;
+    }
+    Ok(fdr_items)
+}
+
+
+/// 偽発見率を制御した一括実行の結果をCSV形式の文字列として出力
+///
+/// # 引数
+/// * `items` - 一括実行の結果
+pub fn fdr_csv(items: &[FdrItem]) -> String {
+    let mut csv = "path,p_value,adjusted_p_value,changed\n".to_owned();
+    for item in items {
+        let path = csv_quote(&item.item.path.display().to_string());
+        let p = item.p_value.map(|p| p.to_string()).unwrap_or_default();
+        let adj = item.adjusted_p_value.map(|p| p.to_string()).unwrap_or_default();
+        csv.push_str(&format!("{path},{p},{adj},{}\n", item.changed));
+    }
+    csv
+}
+
+
+/// 一括実行の結果の要約をCSV形式の文字列として出力
+///
+/// 変化点は空白区切りで1列に格納する．
+///
+/// # 引数
+/// * `items` - 一括実行の結果
+pub fn summary_csv(items: &[BatchItem]) -> String {
+    let mut csv = "path,status,num_change,value,change_points,error\n".to_owned();
+    for item in items {
+        let path = csv_quote(&item.path.display().to_string());
+        match &item.result {
+            Ok(res) => {
+                let cps = res.change_points.iter()
+                                           .map(|t| t.to_string())
+                                           .collect::<Vec<String>>()
+                                           .join(" ");
+                csv.push_str(&format!("{path},ok,{},{},{cps},\n", res.num_change(), res.value));
+            },
+            Err(e) => {
+                csv.push_str(&format!("{path},error,,,,{}\n", csv_quote(&e.to_string())));
+            },
+        }
+    }
+    csv
+}
+
+
+/// CSVのフィールドとして引用符で囲む
+fn csv_quote(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+
+/// 入力ファイルに対する結果の出力ファイル名
+fn result_file_name(path: &Path) -> String {
+    let name = path.file_name()
+                   .map(|name| name.to_string_lossy().into_owned())
+                   .unwrap_or_default();
+    format!("{name}.result.csv")
+}
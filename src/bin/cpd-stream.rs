@@ -0,0 +1,83 @@
+//! 標準入出力でJSON Lines形式の観測を逐次監視する
+//!
+//! ```sh
+//! sensor_feed | cpd-stream --mean 10 --sd 0.5 --drift 0.5 --threshold 5 | alert_handler
+//! ```
+//!
+//! 入出力の形式は[`cpd_tools::online::OnlineDetector::run_jsonl`]を参照．
+
+use cpd_tools::online::{OnlineDetector, StreamDetector, AlarmPolicy, PolicedDetector};
+
+const USAGE: &str = "Usage: cpd-stream --mean <MEAN> --sd <SD> [--drift <K>] [--threshold <H>] \
+                     [--consecutive <N>] [--rearm-interval <N>] [--release-level <LEVEL>]";
+
+
+fn main() {
+    if let Err(message) = run() {
+        eprintln!("{message}");
+        std::process::exit(1);
+    }
+}
+
+
+fn run() -> Result<(), String> {
+    let mut mean = None;
+    let mut sd = None;
+    let mut drift = 0.5;
+    let mut threshold = 5.0;
+    let mut policy = AlarmPolicy::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let target = match arg.as_str() {
+            "--mean" => &mut mean,
+            "--sd" => &mut sd,
+            "--drift" => {
+                drift = parse_value(&arg, args.next())?;
+                continue;
+            },
+            "--threshold" => {
+                threshold = parse_value(&arg, args.next())?;
+                continue;
+            },
+            "--consecutive" => {
+                policy.consecutive = parse_value(&arg, args.next())?;
+                continue;
+            },
+            "--rearm-interval" => {
+                policy.rearm_interval = parse_value(&arg, args.next())?;
+                continue;
+            },
+            "--release-level" => {
+                policy.release_level = Some(parse_value(&arg, args.next())?);
+                continue;
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            },
+            _ => return Err(format!("Unknown argument \"{arg}\".\n{USAGE}")),
+        };
+        *target = Some(parse_value(&arg, args.next())?);
+    }
+    let (mean, sd) = match (mean, sd) {
+        (Some(mean), Some(sd)) => (mean, sd),
+        _ => return Err(USAGE.to_owned()),
+    };
+
+    let detector = StreamDetector::new(mean, sd, drift, threshold).map_err(|e| e.to_string())?;
+    let mut detector = PolicedDetector::new(detector, policy).map_err(|e| e.to_string())?;
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    detector.run_jsonl(stdin.lock(), stdout.lock()).map_err(|e| e.to_string())
+}
+
+
+fn parse_value<T>(name: &str, value: Option<String>) -> Result<T, String>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = value.ok_or_else(|| format!("Missing value for {name}."))?;
+    value.parse::<T>().map_err(|e| format!("Invalid value \"{value}\" for {name}: {e}"))
+}
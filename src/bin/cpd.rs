@@ -0,0 +1,49 @@
+//! 設定ファイル（TOML）に従って変化点を検出する
+//!
+//! ```sh
+//! cpd spec.toml
+//! ```
+//!
+//! 設定ファイルの形式は[`cpd_tools::config::RunSpec`]を，利用できるコスト関数は`cpd --list-costs`を参照．
+//! 検出した変化点を1行に1個ずつ標準出力へ書き出す．
+
+use cpd_tools::config::{self, RunSpec};
+use cpd_tools::cost::CostRegistry;
+
+use std::path::Path;
+
+const USAGE: &str = "Usage: cpd <SPEC.toml> | cpd --list-costs";
+
+
+fn main() {
+    if let Err(message) = run() {
+        eprintln!("{message}");
+        std::process::exit(1);
+    }
+}
+
+
+fn run() -> Result<(), String> {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let path = match args.as_slice() {
+        [arg] if arg == "-h" || arg == "--help" => {
+            println!("{USAGE}");
+            return Ok(());
+        },
+        [arg] if arg == "--list-costs" => {
+            for name in CostRegistry::with_builtins().names() {
+                println!("{name}");
+            }
+            return Ok(());
+        },
+        [arg] if !arg.starts_with('-') => arg,
+        _ => return Err(USAGE.to_owned()),
+    };
+
+    let spec = RunSpec::from_file(Path::new(path)).map_err(|e| e.to_string())?;
+    let result = config::run(&spec).map_err(|e| e.to_string())?;
+    for tau in result.change_points {
+        println!("{tau}");
+    }
+    Ok(())
+}
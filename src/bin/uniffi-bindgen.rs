@@ -0,0 +1,5 @@
+//! Kotlin/Swiftのbindingを生成する`uniffi-bindgen`
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -0,0 +1,355 @@
+//! 観測時刻に基づく暦の注釈と休止期間
+//!
+//! 製造現場のデータは勤務シフト，曜日および週番号で整理されることが多い．
+//! 観測ごとの時刻（Unix時間）とUTCからの時差を与えると，変化点を現地時刻の暦で注釈する（[`DetectionResult::annotate`]）．
+//! また計画停止等の休止期間を登録すると，休止期間内に変化点を置かない制約をコスト関数に課す（[`Blackout`]）．
+
+use crate::cost::{SegmentCost, SegmentParameter};
+use crate::detect::DetectionResult;
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 1日の秒数
+const SECS_PER_DAY: i64 = 86_400;
+
+
+/// 曜日
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Weekday {
+    /// 月曜日
+    Monday,
+    /// 火曜日
+    Tuesday,
+    /// 水曜日
+    Wednesday,
+    /// 木曜日
+    Thursday,
+    /// 金曜日
+    Friday,
+    /// 土曜日
+    Saturday,
+    /// 日曜日
+    Sunday,
+}
+
+impl Weekday {
+    /// 月曜日を0とする番号から変換する
+    fn from_index(i: i64) -> Self {
+        match i.rem_euclid(7) {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+}
+
+
+/// 勤務シフト
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shift {
+    /// シフトの名前
+    pub name: String,
+    /// 現地時刻における開始時刻（0時からの分）．次のシフトの開始まで続き，最後のシフトは翌日の最初のシフトの開始まで続く．
+    pub start_minute: u32,
+}
+
+
+/// 変化点の暦の注釈
+///
+/// 変化点$ t $の注釈は，変化後の最初の観測（`data[t]`）の時刻に基づく．
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalendarAnnotation {
+    /// 変化点
+    pub change_point: Tau,
+    /// 変化後の最初の観測の時刻（Unix時間，秒）
+    pub timestamp: i64,
+    /// 現地時刻の年
+    pub year: i32,
+    /// 現地時刻の月（1から12）
+    pub month: u32,
+    /// 現地時刻の日（1から31）
+    pub day: u32,
+    /// 現地時刻の時（0から23）
+    pub hour: u32,
+    /// 現地時刻の分（0から59）
+    pub minute: u32,
+    /// 曜日
+    pub weekday: Weekday,
+    /// ISO 8601の週番号の年
+    pub iso_year: i32,
+    /// ISO 8601の週番号（1から53）
+    pub iso_week: u32,
+    /// 勤務シフトの名前．シフトを登録していない場合は`None`．
+    pub shift: Option<String>,
+    /// 休止期間内か
+    pub in_blackout: bool,
+}
+
+
+/// 観測ごとの時刻と暦の設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calendar {
+    timestamps: Vec<i64>,
+    utc_offset: i64,
+    shifts: Vec<Shift>,
+    blackouts: Vec<(i64, i64)>,
+}
+
+impl Calendar {
+    /// 観測ごとの時刻から作成する
+    ///
+    /// # 引数
+    /// * `timestamps` - 観測ごとの時刻（Unix時間，秒）．単調非減少であること．
+    /// * `utc_offset` - 現地時刻のUTCからの時差（秒）．例えば日本標準時は`9 * 3600`．
+    pub fn new(timestamps: Vec<i64>, utc_offset: i64) -> Result<Self, CalcDpError> {
+        if let Some(i) = timestamps.windows(2).position(|w| w[0] > w[1]) {
+            return Err(CalcDpError::new(format!("Timestamps must be non-decreasing, but timestamp {} is followed by {}.", timestamps[i], timestamps[i + 1])));
+        }
+        if utc_offset.abs() >= SECS_PER_DAY {
+            return Err(CalcDpError::new(format!("UTC offset (= {utc_offset} s) must be less than a day.")));
+        }
+        Ok(Calendar { timestamps, utc_offset, shifts: Vec::new(), blackouts: Vec::new() })
+    }
+
+
+    /// 勤務シフトを登録する
+    ///
+    /// # 引数
+    /// * `shifts` - 勤務シフト．開始時刻は1日未満かつ互いに異なること．
+    pub fn with_shifts(mut self, mut shifts: Vec<Shift>) -> Result<Self, CalcDpError> {
+        if let Some(s) = shifts.iter().find(|s| s.start_minute >= 24 * 60) {
+            return Err(CalcDpError::new(format!("Shift {} starts at minute {}, beyond a day.", s.name, s.start_minute)));
+        }
+        shifts.sort_by_key(|s| s.start_minute);
+        if shifts.windows(2).any(|w| w[0].start_minute == w[1].start_minute) {
+            return Err(CalcDpError::new("Shifts must start at distinct times."));
+        }
+        self.shifts = shifts;
+        Ok(self)
+    }
+
+
+    /// 休止期間$ [start, end) $を登録する
+    ///
+    /// # 引数
+    /// * `start` - 休止期間の開始時刻（Unix時間，秒）
+    /// * `end` - 休止期間の終了時刻（Unix時間，秒）．この時刻は含まない．
+    pub fn with_blackout(mut self, start: i64, end: i64) -> Result<Self, CalcDpError> {
+        if start >= end {
+            return Err(CalcDpError::new(format!("Blackout must end (= {end}) after it starts (= {start}).")));
+        }
+        self.blackouts.push((start, end));
+        Ok(self)
+    }
+
+
+    /// 観測の個数
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+
+    /// 観測が無いか
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+
+    /// 変化点が休止期間内か
+    ///
+    /// 変化後の最初の観測の時刻が休止期間に含まれる場合に`true`を返す．
+    ///
+    /// # 引数
+    /// * `t` - 変化点
+    pub fn is_blacked_out(&self, t: Tau) -> bool {
+        self.timestamps.get(t as usize)
+                       .is_some_and(|ts| self.blackouts.iter().any(|(s, e)| s <= ts && ts < e))
+    }
+
+
+    /// 変化点を暦で注釈する
+    ///
+    /// # 引数
+    /// * `t` - 変化点（$ 0 < t < T $）
+    pub fn annotate(&self, t: Tau) -> Result<CalendarAnnotation, CalcDpError> {
+        let timestamp = match self.timestamps.get(t as usize) {
+            Some(ts) if t > 0 => *ts,
+            _ => return Err(CalcDpError::new(format!("Change point {t} is out of range (0, {}).", self.timestamps.len()))),
+        };
+        let local = timestamp + self.utc_offset;
+        let days = local.div_euclid(SECS_PER_DAY);
+        let secs = local.rem_euclid(SECS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        // 1970年1月1日は木曜日
+        let weekday_index = (days + 3).rem_euclid(7);
+        // ISO 8601の週は同じ週の木曜日が属する年で数える
+        let thursday = days - weekday_index + 3;
+        let (iso_year, _, _) = civil_from_days(thursday);
+        let iso_week = ((thursday - days_from_civil(iso_year, 1, 1)) / 7 + 1) as u32;
+        let minute_of_day = (secs / 60) as u32;
+
+        Ok(CalendarAnnotation {
+            change_point: t,
+            timestamp,
+            year,
+            month,
+            day,
+            hour: minute_of_day / 60,
+            minute: minute_of_day % 60,
+            weekday: Weekday::from_index(weekday_index),
+            iso_year,
+            iso_week,
+            shift: self.shift_at(minute_of_day).map(|s| s.name.clone()),
+            in_blackout: self.is_blacked_out(t),
+        })
+    }
+
+
+    /// 休止期間内に変化点を置かない制約をコスト関数に課す
+    ///
+    /// # 引数
+    /// * `cost` - コスト関数．系列の長さは観測の個数と等しいこと．
+    pub fn blackout<'a, C: SegmentCost>(&self, cost: &'a C) -> Result<Blackout<'a, C>, CalcDpError> {
+        let t_max = cost.t_max();
+        if t_max as usize != self.timestamps.len() {
+            return Err(CalcDpError::new(format!("Calendar has {} timestamps, but the series has {t_max} points.", self.timestamps.len())));
+        }
+        let forbidden = (0..t_max).map(|t| t > 0 && self.is_blacked_out(t)).collect();
+        Ok(Blackout { cost, forbidden })
+    }
+
+
+    /// 0時からの分に対応する勤務シフト
+    fn shift_at(&self, minute_of_day: u32) -> Option<&Shift> {
+        self.shifts.iter()
+                   .rev()
+                   .find(|s| s.start_minute <= minute_of_day)
+                   .or(self.shifts.last())
+    }
+}
+
+
+/// 休止期間内に変化点を置かない制約を課したコスト関数
+///
+/// 休止期間内の時点を端とする区間の評価値を$ -\infty $とするため，動的計画法はその時点を変化点に選ばない．
+/// 作成は[`Calendar::blackout`]で行う．
+#[derive(Debug, Clone)]
+pub struct Blackout<'a, C> {
+    cost: &'a C,
+    forbidden: Vec<bool>,
+}
+
+impl<C> Blackout<'_, C> {
+    /// 時点を区間の端とできるか
+    fn allows(&self, t: Tau) -> bool {
+        !self.forbidden.get(t as usize).copied().unwrap_or(false)
+    }
+}
+
+impl<C: SegmentCost> SegmentCost for Blackout<'_, C> {
+    fn t_max(&self) -> Tau {
+        self.cost.t_max()
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        if self.allows(t_k_1) && self.allows(t_k) {
+            self.cost.segment_value(t_k_1, t_k)
+        } else {
+            self.check_segment(t_k_1, t_k)?;
+            Ok(f64::NEG_INFINITY)
+        }
+    }
+
+
+    fn segment_values(&self, pairs: &[(Tau, Tau)]) -> Result<Vec<f64>, CalcDpError> {
+        let mut values = self.cost.segment_values(pairs)?;
+        for ((t_k_1, t_k), val) in pairs.iter().zip(values.iter_mut()) {
+            if !(self.allows(*t_k_1) && self.allows(*t_k)) {
+                *val = f64::NEG_INFINITY;
+            }
+        }
+        Ok(values)
+    }
+
+
+    fn constant_time(&self) -> bool {
+        self.cost.constant_time()
+    }
+
+
+    fn n_parameters(&self) -> usize {
+        self.cost.n_parameters()
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        self.cost.min_identifiable_size()
+    }
+
+
+    fn rejects_short_segments(&self) -> bool {
+        self.cost.rejects_short_segments()
+    }
+
+
+    fn pruning_constant(&self) -> f64 {
+        self.cost.pruning_constant()
+    }
+}
+
+impl<C: SegmentParameter> SegmentParameter for Blackout<'_, C> {
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        self.cost.segment_parameter(t_k_1, t_k)
+    }
+}
+
+
+impl DetectionResult {
+    /// 各変化点を暦で注釈する
+    ///
+    /// # 引数
+    /// * `calendar` - 観測ごとの時刻と暦の設定
+    pub fn annotate(&self, calendar: &Calendar) -> Result<Vec<CalendarAnnotation>, CalcDpError> {
+        self.change_points.iter().map(|t| calendar.annotate(*t)).collect()
+    }
+}
+
+
+/// 1970年1月1日からの日数を（年, 月, 日）に変換する
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year as i32, month, day)
+}
+
+
+/// （年, 月, 日）を1970年1月1日からの日数に変換する
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
@@ -0,0 +1,238 @@
+//! 変化点の有無を判定する閾値の較正
+//!
+//! 変化点のない系列（帰無仮説）を繰り返し生成し，変化点1個の検定統計量
+//! $$ \Lambda = \max_{s} \left\{ f(0, s) + f(s, T) - f(0, T) \right\} $$
+//! の分布から，指定した誤警報確率を達成する閾値を求める．
+//! $ \Lambda $は[`crate::search::binseg`]における最初の分割の評価値の増加量と一致するため，
+//! 得られた閾値は[`crate::detect::Penalty::Linear`]のペナルティとして用いることができる．
+
+use crate::cost::{SegmentCost, HeteroscedasticMeanCost, CorrelationCost};
+use crate::dp_tools::CalcDpError;
+use crate::rng::RngConfig;
+use crate::search::best_split;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate rand;
+use rand::Rng;
+use rand::rngs::StdRng;
+
+extern crate rand_distr;
+use rand_distr::{Distribution, StandardNormal};
+
+extern crate rayon;
+use rayon::prelude::*;
+
+
+/// 帰無仮説（変化点なし）の下での系列の生成モデル
+pub trait NullModel {
+    /// 生成した系列に対するコスト関数
+    type Cost: SegmentCost;
+
+
+    /// 長さ`t_max`の系列を生成し，そのコスト関数を作成する
+    ///
+    /// # 引数
+    /// * `t_max` - 系列の長さ
+    /// * `rng` - 乱数生成器
+    fn simulate(&self, t_max: Tau, rng: &mut StdRng) -> Result<Self::Cost, CalcDpError>;
+}
+
+impl<C, F> NullModel for F
+where
+    C: SegmentCost,
+    F: Fn(Tau, &mut StdRng) -> Result<C, CalcDpError>,
+{
+    type Cost = C;
+
+    fn simulate(&self, t_max: Tau, rng: &mut StdRng) -> Result<C, CalcDpError> {
+        self(t_max, rng)
+    }
+}
+
+
+/// 分散が一定の正規分布（[`HeteroscedasticMeanCost`]に対応）
+///
+/// 平均の変化の検定統計量は平均に依存しないため，平均0の系列を生成する．
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaussianMeanNull {
+    /// 標準偏差
+    pub sd: f64,
+}
+
+impl NullModel for GaussianMeanNull {
+    type Cost = HeteroscedasticMeanCost;
+
+    fn simulate(&self, t_max: Tau, rng: &mut StdRng) -> Result<Self::Cost, CalcDpError> {
+        let data = (0..t_max).map(|_| {
+                                 let z: f64 = StandardNormal.sample(rng);
+                                 self.sd * z
+                             })
+                             .collect::<Vec<f64>>();
+        HeteroscedasticMeanCost::new(&data, &vec![self.sd * self.sd; t_max as usize])
+    }
+}
+
+
+/// 無相関な2変量正規分布（[`CorrelationCost`]に対応）
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct UncorrelatedNull;
+
+impl NullModel for UncorrelatedNull {
+    type Cost = CorrelationCost;
+
+    fn simulate(&self, t_max: Tau, rng: &mut StdRng) -> Result<Self::Cost, CalcDpError> {
+        let x = (0..t_max).map(|_| StandardNormal.sample(rng)).collect::<Vec<f64>>();
+        let y = (0..t_max).map(|_| StandardNormal.sample(rng)).collect::<Vec<f64>>();
+        CorrelationCost::new(&x, &y)
+    }
+}
+
+
+/// 観測された残差の移動ブロック・ブートストラップ
+///
+/// 残差から長さ`block_len`の連続したブロックを無作為に選んで連結し，帰無仮説の下での系列とする．
+/// ブロック内の自己相関が保存されるため，工程データのように残差が自己相関を持つ場合でも，
+/// 独立性を仮定した生成モデルより妥当な閾値が得られる．
+/// 残差は変化点を含まない区間から求め，平均を0としておく必要がある．
+#[derive(Debug, Clone)]
+pub struct BlockBootstrap<F> {
+    residuals: Vec<f64>,
+    block_len: usize,
+    build: F,
+}
+
+impl<C, F> BlockBootstrap<F>
+where
+    C: SegmentCost,
+    F: Fn(&[f64]) -> Result<C, CalcDpError>,
+{
+    /// 生成モデルを作成
+    ///
+    /// # 引数
+    /// * `residuals` - 残差
+    /// * `block_len` - ブロックの長さ（1以上，残差の長さ以下）．[`default_block_len`]を目安とする．
+    /// * `build` - 生成した系列からコスト関数を作成する関数
+    pub fn new(residuals: Vec<f64>, block_len: usize, build: F) -> Result<Self, CalcDpError> {
+        check_block_len(residuals.len(), block_len)?;
+        Ok(BlockBootstrap { residuals, block_len, build })
+    }
+
+
+    /// ブロックの長さ
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+}
+
+impl<C, F> NullModel for BlockBootstrap<F>
+where
+    C: SegmentCost,
+    F: Fn(&[f64]) -> Result<C, CalcDpError>,
+{
+    type Cost = C;
+
+    fn simulate(&self, t_max: Tau, rng: &mut StdRng) -> Result<C, CalcDpError> {
+        (self.build)(&moving_block_resample(&self.residuals, t_max as usize, self.block_len, rng)?)
+    }
+}
+
+
+/// ブロックの長さが1以上，残差の長さ以下であるか確認する
+///
+/// # 引数
+/// * `n` - 残差の長さ
+/// * `block_len` - ブロックの長さ
+fn check_block_len(n: usize, block_len: usize) -> Result<(), CalcDpError> {
+    if block_len == 0 || block_len > n {
+        return Err(CalcDpError::new(format!("Block length (= {block_len}) must be in [1, {n}].")));
+    }
+    Ok(())
+}
+
+
+/// 移動ブロック・ブートストラップにより長さ`len`の系列を生成する
+///
+/// # 引数
+/// * `residuals` - 残差（空でないこと）
+/// * `len` - 生成する系列の長さ
+/// * `block_len` - ブロックの長さ（1以上，残差の長さ以下）
+/// * `rng` - 乱数生成器
+pub fn moving_block_resample<R: Rng + ?Sized>(residuals: &[f64], len: usize, block_len: usize, rng: &mut R) -> Result<Vec<f64>, CalcDpError> {
+    check_block_len(residuals.len(), block_len)?;
+    let n_start = residuals.len() - block_len + 1;
+    let mut series = Vec::with_capacity(len + block_len);
+    while series.len() < len {
+        let start = rng.gen_range(0..n_start);
+        series.extend_from_slice(&residuals[start..(start + block_len)]);
+    }
+    series.truncate(len);
+    Ok(series)
+}
+
+
+/// 系列長に対するブロックの長さの目安$ \lceil n^{1/3} \rceil $
+///
+/// # 引数
+/// * `n` - 残差の長さ
+pub fn default_block_len(n: usize) -> usize {
+    ((n as f64).cbrt().ceil() as usize).clamp(1, n.max(1))
+}
+
+
+/// 変化点1個の検定統計量$ \Lambda $
+///
+/// 評価値が定義できる分割が存在しない場合は`f64::NEG_INFINITY`を返す．
+///
+/// # 引数
+/// * `cost` - コスト関数
+pub fn single_change_statistic<C: SegmentCost>(cost: &C) -> Result<f64, CalcDpError> {
+    Ok(best_split(cost, 0, cost.t_max(), 1)?.map_or(f64::NEG_INFINITY, |(_, gain)| gain))
+}
+
+
+/// 誤警報確率が`alpha`となる閾値を求める
+///
+/// 帰無仮説の下で生成した`n_sims`個の系列に対する$ \Lambda $の，上側`alpha`分位点を返す．
+/// 各試行の乱数は`rng`のseedと試行番号から決まるため，並列に計算しても結果は再現できる．
+///
+/// # 引数
+/// * `model` - 帰無仮説の下での系列の生成モデル
+/// * `t_max` - 系列の長さ（2以上）
+/// * `alpha` - 誤警報確率（0より大きく1未満）
+/// * `n_sims` - 試行回数
+/// * `rng` - 乱数の設定（`u64`のseedも与えられる）
+pub fn calibrate_threshold<N>(model: &N, t_max: Tau, alpha: f64, n_sims: usize, rng: impl Into<RngConfig>) -> Result<f64, CalcDpError>
+where
+    N: NullModel + Sync,
+{
+    if t_max < 2 {
+        return Err(CalcDpError::new(format!("Series length must be at least 2, but {t_max} is given.")));
+    }
+    if !(alpha > 0.0 && alpha < 1.0) {
+        return Err(CalcDpError::new(format!("alpha must be in (0, 1), but {alpha} is given.")));
+    }
+    // 上側分位点を求めるためには，少なくとも1個の試行が分位点を超える必要がある
+    if (n_sims as f64) * alpha < 1.0 {
+        return Err(CalcDpError::new(format!("n_sims (= {n_sims}) is too small for alpha = {alpha}; at least {} are required.", (1.0 / alpha).ceil())));
+    }
+
+    let rng = rng.into();
+    let stats = (0..n_sims).into_par_iter()
+                           .map(|i| {
+                               let mut rng = rng.trial(i as u64);
+                               single_change_statistic(&model.simulate(t_max, &mut rng)?)
+                           })
+                           .collect::<Result<Vec<f64>, CalcDpError>>()?;
+    Ok(upper_quantile(stats, alpha))
+}
+
+
+/// 上側`alpha`分位点
+pub(crate) fn upper_quantile(mut values: Vec<f64>, alpha: f64) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let n = values.len();
+    let idx = (((1.0 - alpha) * n as f64).ceil() as usize).clamp(1, n) - 1;
+    values[idx]
+}
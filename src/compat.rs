@@ -0,0 +1,5 @@
+//! 他のライブラリの命名に合わせたAPI
+//!
+//! 他の言語で書かれた解析手順を本crateへ移植する際に，呼び出し方の対応を取りやすくするための薄いwrapperを提供する．
+
+pub mod ruptures;
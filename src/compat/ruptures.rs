@@ -0,0 +1,292 @@
+//! Pythonの[ruptures](https://centre-borelli.github.io/ruptures-docs/)に合わせたAPI
+//!
+//! rupturesの`Dynp`，`Pelt`，`Binseg`と同じ名前の構造体を提供し，`fit`および`predict`を本crateの手法へ対応付ける．
+//!
+//! | ruptures | 本crate |
+//! |---|---|
+//! | `Dynp` | [`crate::detect::FitResult`]（動的計画法） |
+//! | `Pelt` | [`crate::detect::detect`]（[`Penalty::Linear`]による動的計画法．PELTと同じ最適解を与える．） |
+//! | `Binseg` | [`crate::search::binseg`] |
+//!
+//! # rupturesとの違い
+//! * `fit`は系列の代わりにコスト関数を受け取る．rupturesの`model="l2"`に相当するコスト関数として[`CostL2`]を提供する．
+//! * `min_size`は[`Dynp`]および[`Pelt`]では1または2のみ指定できる（[`Method::Dp`]および[`Method::Dp2`]に対応）．
+//! * `jump`（変化点候補の間引き）には対応しない．
+//! * rupturesのコストは最小化されるのに対し，本crateの評価値は最大化される．
+//!   [`CostL2`]の評価値はrupturesの`CostL2`の符号を反転したものであるため，ペナルティ`pen`は同じ値を用いることができる．
+//!
+//! # 例
+//! ```ignore
+//! use cpd_tools::compat::ruptures::{CostL2, Pelt, Predict};
+//!
+//! let cost = CostL2::new(&signal);
+//! let bkps = Pelt::new().fit(&cost)?.predict(Predict::Pen(10.0))?;
+//! ```
+
+use crate::cost::{SegmentCost, PrefixSum, impl_calc_tt};
+use crate::detect::{self, FitResult, Method, Penalty, Constraints, DetectionResult};
+use crate::dp_tools::CalcDpError;
+use crate::search;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+
+/// `predict`における変化点個数の決め方
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Predict {
+    /// 変化点1個あたりのペナルティ（rupturesの`pen`）
+    Pen(f64),
+    /// 変化点個数（rupturesの`n_bkps`）
+    NBkps(usize),
+}
+
+impl Predict {
+    /// 対応する[`Penalty`]
+    fn penalty(&self) -> Penalty {
+        match self {
+            Predict::Pen(pen) => Penalty::Linear(*pen),
+            Predict::NBkps(n) => Penalty::NumChange(*n as NumChg),
+        }
+    }
+}
+
+
+/// 2乗誤差に基づくコスト関数（rupturesの`CostL2`）
+///
+/// 評価値は区間内の偏差平方和の符号を反転した値$ -\sum (x_i - \bar{x})^2 $である．
+#[derive(Debug, Clone)]
+pub struct CostL2 {
+    t_max: Tau,
+    sum_x: PrefixSum,
+    sum_xx: PrefixSum,
+}
+
+impl CostL2 {
+    /// 系列からコスト関数を作成
+    ///
+    /// # 引数
+    /// * `signal` - 系列
+    pub fn new(signal: &[f64]) -> Self {
+        CostL2 {
+            t_max: signal.len() as Tau,
+            sum_x: PrefixSum::new(signal.iter().copied()),
+            sum_xx: PrefixSum::new(signal.iter().map(|x| x * x)),
+        }
+    }
+}
+
+impl SegmentCost for CostL2 {
+    fn t_max(&self) -> Tau {
+        self.t_max
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let n = (t_k - t_k_1) as f64;
+        let sx = self.sum_x.range(t_k_1, t_k);
+        let sxx = self.sum_xx.range(t_k_1, t_k);
+        // 桁落ちにより正となることを防ぐ
+        Ok((- (sxx - sx * sx / n)).min(0.0))
+    }
+
+
+    fn constant_time(&self) -> bool {
+        true
+    }
+}
+
+impl_calc_tt!(CostL2);
+
+
+/// 動的計画法による変化点検出（rupturesの`Dynp`）
+pub struct Dynp<'a, C> {
+    min_size: usize,
+    fitted: Option<FitResult<'a, C>>,
+}
+
+impl<'a, C: SegmentCost> Dynp<'a, C> {
+    /// `min_size = 2`で作成
+    pub fn new() -> Self {
+        Dynp { min_size: 2, fitted: None }
+    }
+
+
+    /// 各区間の最小の長さを指定（1または2）
+    ///
+    /// # 引数
+    /// * `min_size` - 各区間の最小の長さ
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+
+    /// 全ての変化点個数について最適な変化点を計算する
+    ///
+    /// # 引数
+    /// * `cost` - コスト関数
+    pub fn fit(mut self, cost: &'a C) -> Result<Self, CalcDpError> {
+        self.fitted = Some(FitResult::fit(cost, method_of(self.min_size)?, None)?);
+        Ok(self)
+    }
+
+
+    /// 変化点を求める
+    ///
+    /// # 引数
+    /// * `by` - 変化点個数の決め方
+    ///
+    /// # 返り値
+    /// * `bkps` - 各区間の終点（昇順）．rupturesと同様に最後の要素は系列の長さとなる．
+    pub fn predict(&self, by: Predict) -> Result<Vec<usize>, CalcDpError> {
+        let fitted = self.fitted.as_ref().ok_or_else(not_fitted)?;
+        Ok(breakpoints(&fitted.select(&by.penalty())?))
+    }
+}
+
+impl<C: SegmentCost> Default for Dynp<'_, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// ペナルティ付きの変化点検出（rupturesの`Pelt`）
+///
+/// 変化点個数は[`Predict::Pen`]によってのみ指定できる．
+pub struct Pelt<'a, C> {
+    min_size: usize,
+    cost: Option<&'a C>,
+}
+
+impl<'a, C: SegmentCost> Pelt<'a, C> {
+    /// `min_size = 2`で作成
+    pub fn new() -> Self {
+        Pelt { min_size: 2, cost: None }
+    }
+
+
+    /// 各区間の最小の長さを指定（1または2）
+    ///
+    /// # 引数
+    /// * `min_size` - 各区間の最小の長さ
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+
+    /// コスト関数を設定する
+    ///
+    /// # 引数
+    /// * `cost` - コスト関数
+    pub fn fit(mut self, cost: &'a C) -> Result<Self, CalcDpError> {
+        method_of(self.min_size)?;
+        self.cost = Some(cost);
+        Ok(self)
+    }
+
+
+    /// 変化点を求める
+    ///
+    /// # 引数
+    /// * `by` - 変化点個数の決め方．[`Predict::Pen`]のみ指定できる．
+    ///
+    /// # 返り値
+    /// * `bkps` - 各区間の終点（昇順）．最後の要素は系列の長さとなる．
+    pub fn predict(&self, by: Predict) -> Result<Vec<usize>, CalcDpError> {
+        let cost = self.cost.ok_or_else(not_fitted)?;
+        if let Predict::NBkps(_) = by {
+            return Err(CalcDpError::new("Pelt only supports prediction by penalty."));
+        }
+        let result = detect::detect(cost, method_of(self.min_size)?, &by.penalty(), &Constraints::default())?;
+        Ok(breakpoints(&result))
+    }
+}
+
+impl<C: SegmentCost> Default for Pelt<'_, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// 二分割法による変化点検出（rupturesの`Binseg`）
+pub struct Binseg<'a, C> {
+    min_size: usize,
+    cost: Option<&'a C>,
+}
+
+impl<'a, C: SegmentCost> Binseg<'a, C> {
+    /// `min_size = 2`で作成
+    pub fn new() -> Self {
+        Binseg { min_size: 2, cost: None }
+    }
+
+
+    /// 各区間の最小の長さを指定（1以上）
+    ///
+    /// # 引数
+    /// * `min_size` - 各区間の最小の長さ
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+
+    /// コスト関数を設定する
+    ///
+    /// # 引数
+    /// * `cost` - コスト関数
+    pub fn fit(mut self, cost: &'a C) -> Result<Self, CalcDpError> {
+        self.cost = Some(cost);
+        Ok(self)
+    }
+
+
+    /// 変化点を求める
+    ///
+    /// # 引数
+    /// * `by` - 変化点個数の決め方
+    ///
+    /// # 返り値
+    /// * `bkps` - 各区間の終点（昇順）．最後の要素は系列の長さとなる．
+    pub fn predict(&self, by: Predict) -> Result<Vec<usize>, CalcDpError> {
+        let cost = self.cost.ok_or_else(not_fitted)?;
+        let result = search::binseg(cost, self.min_size as Tau, &by.penalty(), &Constraints::default())?;
+        Ok(breakpoints(&result))
+    }
+}
+
+impl<C: SegmentCost> Default for Binseg<'_, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// `min_size`に対応する動的計画法の手法
+fn method_of(min_size: usize) -> Result<Method, CalcDpError> {
+    match min_size {
+        1 => Ok(Method::Dp),
+        2 => Ok(Method::Dp2),
+        _ => Err(CalcDpError::new(format!("min_size must be 1 or 2, but {min_size} is given."))),
+    }
+}
+
+
+/// `fit`を呼び出す前に`predict`を呼び出した場合のError
+fn not_fitted() -> CalcDpError {
+    CalcDpError::new("predict is called before fit.")
+}
+
+
+/// 検出結果をrupturesの形式（各区間の終点）へ変換
+fn breakpoints(result: &DetectionResult) -> Vec<usize> {
+    result.segments()
+          .into_iter()
+          .map(|(_, t_k)| t_k as usize)
+          .collect()
+}
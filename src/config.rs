@@ -0,0 +1,208 @@
+//! 設定ファイルに基づく変化点検出の実行
+//!
+//! 入力データ，コスト関数，手法，変化点個数の決め方，制約および出力先をTOML形式で記述し，
+//! [`run`]により変化点検出を実行する．
+//!
+//! # 設定ファイルの例
+//! ```toml
+//! method = "dp"
+//! penalty = { linear = 10.0 }
+//!
+//! [data]
+//! path = "series.csv"
+//! columns = ["value", "variance"]
+//!
+//! [cost]
+//! name = "hetero_mean"
+//!
+//! [constraints]
+//! max_k = 10
+//!
+//! [cache]
+//! dir = ".cpd_cache"
+//!
+//! [output]
+//! path = "result.csv"
+//! ```
+
+use crate::cost::{SegmentCost, CostRegistry, CostInput, CachedCost, CacheKey};
+use crate::detect::{self, Method, Penalty, Constraints, DetectionResult};
+use crate::dp_tools::CalcDpError;
+use crate::error::Error;
+use crate::io::{self, ColumnRef};
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+extern crate toml;
+
+
+/// 変化点検出の実行内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunSpec {
+    /// 入力データ
+    pub data: DataSpec,
+    /// コスト関数
+    pub cost: CostSpec,
+    /// 変化点検出の手法
+    #[serde(default = "default_method")]
+    pub method: Method,
+    /// 変化点個数の決め方
+    pub penalty: Penalty,
+    /// 変化点検出における制約
+    #[serde(default)]
+    pub constraints: Constraints,
+    /// 検出結果の出力先
+    #[serde(default)]
+    pub output: OutputSpec,
+    /// 評価値のキャッシュ
+    #[serde(default)]
+    pub cache: CacheSpec,
+}
+
+impl RunSpec {
+    /// TOML形式の文字列から読み込む
+    ///
+    /// # 引数
+    /// * `s` - TOML形式の文字列
+    pub fn from_toml_str(s: &str) -> Result<Self, Error> {
+        Ok(toml::from_str(s)?)
+    }
+
+
+    /// TOML形式の設定ファイルから読み込む
+    ///
+    /// 設定ファイル内の相対パスは設定ファイルが置かれたディレクトリを基準とする．
+    ///
+    /// # 引数
+    /// * `path` - 設定ファイルのパス
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let text = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let mut spec = Self::from_toml_str(&text)?;
+        if let Some(dir) = path.parent() {
+            spec.data.path = dir.join(&spec.data.path);
+            spec.output.path = spec.output.path.map(|p| dir.join(p));
+            spec.cache.dir = spec.cache.dir.map(|p| dir.join(p));
+        }
+        Ok(spec)
+    }
+}
+
+
+/// 入力データの指定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataSpec {
+    /// CSVファイルのパス
+    pub path: PathBuf,
+    /// コスト関数に渡す列（順番はコスト関数の定義に従う）
+    pub columns: Vec<ColumnRef>,
+    /// 1行目をヘッダ行として扱うか
+    #[serde(default = "default_header")]
+    pub header: bool,
+    /// 区切り文字
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+}
+
+
+/// コスト関数の指定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostSpec {
+    /// [`CostRegistry`]に登録されたコスト関数の名前
+    pub name: String,
+    /// コスト関数の設定値
+    #[serde(default)]
+    pub params: BTreeMap<String, f64>,
+}
+
+
+/// 検出結果の出力先の指定
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputSpec {
+    /// 区間ごとの範囲と評価値を出力するCSVファイルのパス．指定しない場合は出力しない．
+    pub path: Option<PathBuf>,
+}
+
+
+
+/// 評価値のキャッシュの指定
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheSpec {
+    /// キャッシュファイルを置くディレクトリ．指定しない場合はキャッシュを用いない．
+    ///
+    /// 同じ入力データとコスト関数の設定で再度実行した場合，計算済みの評価値を再利用する（[`CachedCost`]）．
+    pub dir: Option<PathBuf>,
+}
+
+
+fn default_method() -> Method {
+    Method::Dp
+}
+
+fn default_header() -> bool {
+    true
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+
+/// 本crateのコスト関数を用いて変化点検出を実行する
+///
+/// # 引数
+/// * `spec` - 変化点検出の実行内容
+pub fn run(spec: &RunSpec) -> Result<DetectionResult, Error> {
+    run_with_registry(spec, &CostRegistry::with_builtins())
+}
+
+
+/// 登録簿を指定して変化点検出を実行する
+///
+/// 利用者が定義したコスト関数を用いる場合は，登録簿に登録した上で本関数を用いる．
+///
+/// # 引数
+/// * `spec` - 変化点検出の実行内容
+/// * `registry` - コスト関数の登録簿
+pub fn run_with_registry(spec: &RunSpec, registry: &CostRegistry) -> Result<DetectionResult, Error> {
+    let columns = io::read_csv_columns(&spec.data.path, &spec.data.columns, spec.data.header, spec.data.delimiter)?;
+    let input = CostInput {
+        columns: &columns,
+        params: &spec.cost.params,
+    };
+    let cost = registry.build(&spec.cost.name, &input).map_err(|e| Error::cost(&spec.cost.name, e))?;
+
+    let result = match &spec.cache.dir {
+        Some(dir) => {
+            let key = CacheKey {
+                data: crate::cost::fingerprint(&columns),
+                config: crate::cost::cost_config(&spec.cost.name, &spec.cost.params),
+            };
+            let cost = CachedCost::open(cost, dir, key)?;
+            let result = detect::detect(&cost, spec.method, &spec.penalty, &spec.constraints)?;
+            cost.save()?;
+            write_output(spec, &result, &cost)?;
+            result
+        },
+        None => {
+            let result = detect::detect(&cost, spec.method, &spec.penalty, &spec.constraints)?;
+            write_output(spec, &result, &cost)?;
+            result
+        },
+    };
+    Ok(result)
+}
+
+
+/// 検出結果を出力先へ書き出す
+fn write_output<C: SegmentCost>(spec: &RunSpec, result: &DetectionResult, cost: &C) -> Result<(), CalcDpError> {
+    if let Some(path) = &spec.output.path {
+        io::write_text(path, &result.to_csv(cost)?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,306 @@
+//! 変化点検出に用いるコスト関数集
+//!
+//! # コスト関数の構造について
+//! 各コスト関数は計算に用いる系列データを内部に保持し，任意の区間$ (t_{k-1}, t_k] $に対する評価値を返す．
+//! 評価値は[`crate::dp_tools`]の動的計画法において最大化されるため，区間の対数尤度（またはそれに準ずる値）を返す．
+//! 区間$ (t_{k-1}, t_k] $はデータのインデックス`t_{k-1}..t_k`に対応する．
+//!
+//! また，推定に必要なデータ数に満たない等の理由で評価値を定義できない区間に対しては，
+//! 動的計画法で選択されないよう`f64::NEG_INFINITY`を返す．
+
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+mod correlation;
+pub use correlation::CorrelationCost;
+mod regression;
+pub use regression::RegressionCost;
+mod hetero_mean;
+pub use hetero_mean::HeteroscedasticMeanCost;
+mod l1;
+pub use l1::L1Cost;
+mod quantile;
+pub use quantile::QuantileCost;
+mod mv_normal;
+pub use mv_normal::{MultivariateNormalCost, Regularization};
+mod zip;
+pub use zip::ZipCost;
+mod negbin;
+pub use negbin::{NegBinomialCost, Dispersion};
+mod censored_exp;
+pub use censored_exp::CensoredExponentialCost;
+mod composite;
+pub use composite::Composite;
+mod registry;
+pub use registry::{CostRegistry, CostInput, CostConstructor};
+mod prefix;
+pub use prefix::PrefixCost;
+mod cache;
+pub use cache::{CachedCost, CacheKey, fingerprint, cost_config};
+mod dyn_cost;
+pub use dyn_cost::{DynCost, CalcTTCost, DynSegment};
+mod order_stat;
+use order_stat::OrderIndex;
+
+
+/// 系列データを保持し，任意の区間における評価値を計算できるコスト関数
+///
+/// 本crateのコスト関数は，本traitを通じて[`crate::dp_tools::calc_dp::CalcTT`]および[`crate::dp_tools::calc_dp_2::CalcTT`]を実装する．
+/// 動的計画法等では複数の区間の評価値を並列に計算するため，`Sync`であることを要求する．
+pub trait SegmentCost: Sync {
+    /// 系列の長さ（最後の時期）
+    fn t_max(&self) -> Tau;
+
+
+    /// 区間$ (t_{k-1}, t_k] $の評価値を計算する
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError>;
+
+
+    /// 複数の区間の評価値をまとめて計算する
+    ///
+    /// 動的計画法から[`crate::dp_tools::calc_dp::CalcTT::calc_values_batch`]を通じて呼び出される．
+    /// 既定では[`Self::segment_value`]を区間ごとに呼び出す．
+    ///
+    /// # 引数
+    /// * `pairs` - 区間$ (t_{k-1}, t_k] $の列
+    fn segment_values(&self, pairs: &[(Tau, Tau)]) -> Result<Vec<f64>, CalcDpError> {
+        pairs.iter()
+             .map(|(t_k_1, t_k)| self.segment_value(*t_k_1, *t_k))
+             .collect()
+    }
+
+
+    /// 評価値の計算量が区間の長さに依存しないか
+    ///
+    /// 累積和等により任意の区間の評価値を$ O(1) $で計算できる場合は`true`を返す．
+    /// 手法の自動選択（[`crate::detect::Method::Auto`]）に用いられる．既定では`false`．
+    fn constant_time(&self) -> bool {
+        false
+    }
+
+
+    /// 区間ごとに推定する母数の個数（分散等の局外母数を含む）
+    ///
+    /// 区間の最小の長さの提案（[`crate::preprocess::suggest_min_gap`]）に用いられる．既定では1．
+    fn n_parameters(&self) -> usize {
+        1
+    }
+
+
+    /// 区間ごとの母数を識別できる最小の区間の長さ
+    ///
+    /// これより短い区間では評価値が`f64::NEG_INFINITY`となるか，退化した推定値に基づく意味のない値となる．既定では1．
+    fn min_identifiable_size(&self) -> Tau {
+        1
+    }
+
+
+    /// [`Self::min_identifiable_size`]より短い区間の評価値を`f64::NEG_INFINITY`とするか
+    ///
+    /// `false`の場合，短い区間では退化した推定値に基づく意味のない値（NaNや発散した値）を返すため，
+    /// 動的計画法は区間の最小の長さが[`Self::min_identifiable_size`]以上でない限りエラーとする．既定では`true`．
+    fn rejects_short_segments(&self) -> bool {
+        true
+    }
+
+
+    /// 区間を分割した際の評価値の変化の下限$ K $
+    ///
+    /// 全ての$ s < t < T $について$ f(s, t) + f(t, T) \ge f(s, T) + K $を満たす定数であり，
+    /// PELTの枝刈り（[`crate::search::pelt`]および[`crate::dp_tools::calc_pelt::CalcPelt`]）に用いる．
+    /// 既定の0は，長さ1の区間を含む任意の区間で母数を最尤推定した対数尤度を評価値とする場合に成り立つ．
+    /// [`Self::min_identifiable_size`]が2以上のコスト関数は，短い区間への分割で評価値が`f64::NEG_INFINITY`
+    /// （または退化した値）となり不等式が成り立たないため，`f64::NEG_INFINITY`（枝刈りしない）を返すこと．
+    /// 大きく見積もると最適な変化点を除く恐れがあるため，不明な場合も`f64::NEG_INFINITY`を返す．
+    fn pruning_constant(&self) -> f64 {
+        0.0
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $が系列の範囲内か確認する
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    fn check_segment(&self, t_k_1: Tau, t_k: Tau) -> Result<(), CalcDpError> {
+        let t_max = self.t_max();
+        if t_k_1 >= t_k {
+            Err( CalcDpError::new(format!("Index tau_{{k}} (={t_k}) must be greater than tau_{{k-1}} (={t_k_1})")))
+        } else if t_k > t_max {
+            Err( CalcDpError::new(format!("Index tau_{{k}} (={t_k}) is out of range (t_max = {t_max}).")))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+
+/// 区間における母数を推定できるコスト関数
+///
+/// 隣接する区間の母数の差に基づく制約（[`crate::detect::detect_with_min_effect`]）に用いる．
+pub trait SegmentParameter: SegmentCost {
+    /// 区間$ (t_{k-1}, t_k] $における母数の推定値
+    ///
+    /// 複数の母数を持つ場合，区間の差は成分ごとの差の絶対値の最大値で評価される．
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError>;
+}
+
+
+/// 実行時に選択されるコスト関数
+pub type BoxedCost = Box<dyn SegmentCost + Send + Sync>;
+
+impl SegmentCost for BoxedCost {
+    fn t_max(&self) -> Tau {
+        self.as_ref().t_max()
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.as_ref().segment_value(t_k_1, t_k)
+    }
+
+
+    fn segment_values(&self, pairs: &[(Tau, Tau)]) -> Result<Vec<f64>, CalcDpError> {
+        self.as_ref().segment_values(pairs)
+    }
+
+
+    fn constant_time(&self) -> bool {
+        self.as_ref().constant_time()
+    }
+
+
+    fn n_parameters(&self) -> usize {
+        self.as_ref().n_parameters()
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        self.as_ref().min_identifiable_size()
+    }
+
+
+    fn rejects_short_segments(&self) -> bool {
+        self.as_ref().rejects_short_segments()
+    }
+
+
+    fn pruning_constant(&self) -> f64 {
+        self.as_ref().pruning_constant()
+    }
+}
+
+
+/// [`SegmentCost`]を実装した型に対して2種類の`CalcTT`を実装する
+///
+/// 実装される`CalcTT`は型自身を入力データとし，`f64`の評価値を返す．
+/// 加えて区間の評価値を変換した[`crate::dp_tools::OrdVal`]`<f64>`および[`crate::dp_tools::F64x2`]を評価値とする`CalcTT`も実装する．
+macro_rules! impl_calc_tt {
+    ($t:ty) => {
+        $crate::cost::impl_calc_tt!($t, f64);
+        $crate::cost::impl_calc_tt!($t, $crate::dp_tools::OrdVal<f64>);
+        $crate::cost::impl_calc_tt!($t, $crate::dp_tools::F64x2);
+    };
+    ($t:ty, $val:ty) => {
+        impl $crate::dp_tools::calc_dp::CalcTT<$val, $t> for $t {
+            fn calc_value(data: &$t, t_k_1: process_param::Tau, t_k: process_param::Tau) -> Result<$val, $crate::dp_tools::CalcDpError> {
+                $crate::cost::SegmentCost::segment_value(data, t_k_1, t_k).map(<$val>::from)
+            }
+
+            fn calc_values_batch(data: &$t, pairs: &[(process_param::Tau, process_param::Tau)]) -> Result<Vec<$val>, $crate::dp_tools::CalcDpError> {
+                Ok($crate::cost::SegmentCost::segment_values(data, pairs)?.into_iter().map(<$val>::from).collect())
+            }
+
+            fn check_single_point(data: &$t) -> Result<(), $crate::dp_tools::CalcDpError> {
+                $crate::cost::check_min_size(data, 1)
+            }
+        }
+
+        impl $crate::dp_tools::calc_dp_2::CalcTT<$val, $t> for $t {
+            fn calc_value(data: &$t, t_k_1: process_param::Tau, t_k: process_param::Tau) -> Result<$val, $crate::dp_tools::CalcDpError> {
+                $crate::cost::SegmentCost::segment_value(data, t_k_1, t_k).map(<$val>::from)
+            }
+
+            fn calc_values_batch(data: &$t, pairs: &[(process_param::Tau, process_param::Tau)]) -> Result<Vec<$val>, $crate::dp_tools::CalcDpError> {
+                Ok($crate::cost::SegmentCost::segment_values(data, pairs)?.into_iter().map(<$val>::from).collect())
+            }
+        }
+    };
+}
+pub(crate) use impl_calc_tt;
+
+impl_calc_tt!(BoxedCost);
+
+
+/// 系列の長さが一致するか確認する
+///
+/// # 引数
+/// * `name` - 確認対象の系列名
+/// * `len` - 確認対象の系列の長さ
+/// * `t_max` - 基準となる系列の長さ
+pub(crate) fn check_len(name: &str, len: usize, t_max: usize) -> Result<(), CalcDpError> {
+    if len != t_max {
+        Err( CalcDpError::new(format!("Length of {name} (= {len}) must be equal to the length of the series (= {t_max}).")))
+    } else {
+        Ok(())
+    }
+}
+
+
+/// 区間の最小の長さに対してコスト関数の評価値が定義されるか確認する
+///
+/// [`SegmentCost::rejects_short_segments`]が`false`のコスト関数について，`min_size`が[`SegmentCost::min_identifiable_size`]未満の場合はエラーとする．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `min_size` - 動的計画法で扱う区間の最小の長さ
+pub(crate) fn check_min_size<C: SegmentCost + ?Sized>(cost: &C, min_size: Tau) -> Result<(), CalcDpError> {
+    let required = cost.min_identifiable_size();
+    if cost.rejects_short_segments() || min_size >= required {
+        return Ok(());
+    }
+    Err(CalcDpError::new(format!("Cost is not well-defined on segments shorter than {required} points, but segments of length {min_size} are allowed; set Constraints::min_size to at least {required}.")))
+}
+
+
+/// 累積和を用いて任意の区間の総和を計算する
+#[derive(Debug, Clone)]
+pub(crate) struct PrefixSum {
+    cumsum: Vec<f64>,
+}
+
+impl PrefixSum {
+    /// 累積和を作成
+    ///
+    /// # 引数
+    /// * `values` - 累積和を計算する値
+    pub(crate) fn new<I: IntoIterator<Item = f64>>(values: I) -> Self {
+        let mut cumsum = vec![0.0];
+        let mut acc = 0.0;
+        for v in values {
+            acc += v;
+            cumsum.push(acc);
+        }
+        PrefixSum { cumsum }
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $の総和
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub(crate) fn range(&self, t_k_1: Tau, t_k: Tau) -> f64 {
+        self.cumsum[t_k as usize] - self.cumsum[t_k_1 as usize]
+    }
+}
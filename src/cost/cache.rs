@@ -0,0 +1,259 @@
+//! 評価値のファイルへの保存による再計算の省略
+
+use super::{SegmentCost, impl_calc_tt};
+use crate::dp_tools::CalcDpError;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// キャッシュファイルの先頭に置く識別子
+const MAGIC: &[u8; 8] = b"CPDCACH1";
+
+
+/// キャッシュの識別に用いるキー
+///
+/// 入力データのフィンガープリントとコスト関数の設定を表す文字列からなる．
+/// ファイル名はこれらのハッシュ値から決め，ファイル内にも両者を記録して読み込み時に照合する．
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    /// 入力データのフィンガープリント（[`fingerprint`]）
+    pub data: u64,
+    /// コスト関数の設定（名前と設定値等）．名前と設定値から作る場合は[`cost_config`]を用いる．
+    pub config: String,
+}
+
+impl CacheKey {
+    /// キャッシュファイルの名前
+    pub fn file_name(&self) -> String {
+        let mut h = Fnv1a::new();
+        h.write(&self.data.to_le_bytes());
+        h.write(self.config.as_bytes());
+        format!("{:016x}.cache", h.finish())
+    }
+}
+
+
+/// 入力データのフィンガープリント
+///
+/// 実行環境やRustのversionに依存しないよう，各値のビット表現に対するFNV-1aハッシュとする．
+///
+/// # 引数
+/// * `columns` - 列ごとの入力データ
+pub fn fingerprint(columns: &[Vec<f64>]) -> u64 {
+    let mut h = Fnv1a::new();
+    for column in columns {
+        h.write(&(column.len() as u64).to_le_bytes());
+        for v in column {
+            h.write(&v.to_bits().to_le_bytes());
+        }
+    }
+    h.finish()
+}
+
+
+/// コスト関数の名前と設定値を表す文字列
+///
+/// 名前と各設定値の名前には長さを前置し，設定値はビット表現で記録する．
+/// このため区切りとなる文字を名前に含む場合も，異なる設定が同じ文字列となることはない．
+///
+/// # 引数
+/// * `name` - コスト関数の名前
+/// * `params` - コスト関数の設定値
+pub fn cost_config(name: &str, params: &BTreeMap<String, f64>) -> String {
+    let mut config = format!("{}:{name}", name.len());
+    for (key, value) in params {
+        config.push_str(&format!(";{}:{key}={:016x}", key.len(), value.to_bits()));
+    }
+    config
+}
+
+
+/// 評価値をファイルに保存し，以降の実行で再利用するコスト関数
+///
+/// 計算済みの区間の評価値は[`CachedCost::save`]によりキャッシュファイルへ書き出す．
+/// 同じキーで[`CachedCost::open`]した場合はファイルから読み込み，元のコスト関数による計算を省略する．
+/// キャッシュファイルが存在しない，またはキーが一致しない場合は空のキャッシュから開始する．
+pub struct CachedCost<C> {
+    inner: C,
+    key: CacheKey,
+    path: PathBuf,
+    values: RwLock<HashMap<(Tau, Tau), f64>>,
+    dirty: AtomicBool,
+}
+
+impl<C: SegmentCost> CachedCost<C> {
+    /// キャッシュを読み込んでコスト関数を作成
+    ///
+    /// # 引数
+    /// * `inner` - 元のコスト関数
+    /// * `cache_dir` - キャッシュファイルを置くディレクトリ
+    /// * `key` - キャッシュのキー
+    pub fn open(inner: C, cache_dir: &Path, key: CacheKey) -> Result<Self, CalcDpError> {
+        let path = cache_dir.join(key.file_name());
+        let values = match std::fs::read(&path) {
+            Ok(bytes) => parse_cache(&bytes, &key).unwrap_or_default(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(CalcDpError::new(format!("Failed to read {}: {e}", path.display()))),
+        };
+        Ok(CachedCost { inner, key, path, values: RwLock::new(values), dirty: AtomicBool::new(false) })
+    }
+
+
+    /// 元のコスト関数
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+
+    /// キャッシュファイルのパス
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+
+    /// キャッシュされている区間の数
+    pub fn len(&self) -> usize {
+        self.values.read().map(|v| v.len()).unwrap_or(0)
+    }
+
+
+    /// キャッシュが空か
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+
+    /// キャッシュをファイルへ書き出す
+    ///
+    /// 新たに計算した評価値がない場合は何もしない．
+    pub fn save(&self) -> Result<(), CalcDpError> {
+        if !self.dirty.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        let values = self.values.read().map_err(|_| poisoned())?;
+        let mut bytes = Vec::with_capacity(32 + self.key.config.len() + 24 * values.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&self.key.data.to_le_bytes());
+        bytes.extend_from_slice(&(self.key.config.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(self.key.config.as_bytes());
+        for ((t_k_1, t_k), v) in values.iter() {
+            bytes.extend_from_slice(&(*t_k_1 as u64).to_le_bytes());
+            bytes.extend_from_slice(&(*t_k as u64).to_le_bytes());
+            bytes.extend_from_slice(&v.to_bits().to_le_bytes());
+        }
+        drop(values);
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| CalcDpError::new(format!("Failed to create {}: {e}", dir.display())).with_source(e))?;
+        }
+        // 書き込み途中のファイルを読み込まないよう，一時ファイルを経由する
+        let tmp = self.path.with_extension("cache.tmp");
+        std::fs::write(&tmp, &bytes).and_then(|_| std::fs::rename(&tmp, &self.path)).map_err(|e| CalcDpError::new(format!("Failed to write {}: {e}", self.path.display())).with_source(e))?;
+        self.dirty.store(false, Ordering::Release);
+        Ok(())
+    }
+}
+
+impl<C: SegmentCost> SegmentCost for CachedCost<C> {
+    fn t_max(&self) -> Tau {
+        self.inner.t_max()
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        if let Some(v) = self.values.read().map_err(|_| poisoned())?.get(&(t_k_1, t_k)) {
+            return Ok(*v);
+        }
+        let v = self.inner.segment_value(t_k_1, t_k)?;
+        self.values.write().map_err(|_| poisoned())?.insert((t_k_1, t_k), v);
+        self.dirty.store(true, Ordering::Release);
+        Ok(v)
+    }
+
+
+    fn constant_time(&self) -> bool {
+        self.inner.constant_time()
+    }
+
+
+    fn n_parameters(&self) -> usize {
+        self.inner.n_parameters()
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        self.inner.min_identifiable_size()
+    }
+
+
+    fn rejects_short_segments(&self) -> bool {
+        self.inner.rejects_short_segments()
+    }
+
+
+    fn pruning_constant(&self) -> f64 {
+        self.inner.pruning_constant()
+    }
+}
+
+impl_calc_tt!(CachedCost<super::BoxedCost>);
+
+
+/// キャッシュファイルの内容を読み込む．形式またはキーが一致しない場合は`None`を返す．
+fn parse_cache(bytes: &[u8], key: &CacheKey) -> Option<HashMap<(Tau, Tau), f64>> {
+    let read_u64 = |pos: usize| -> Option<u64> {
+        bytes.get(pos..(pos + 8)).map(|b| u64::from_le_bytes(b.try_into().expect("Slice of length 8")))
+    };
+    if bytes.get(0..8)? != MAGIC || read_u64(8)? != key.data {
+        return None;
+    }
+    let config_len = read_u64(16)? as usize;
+    let config_end = 24usize.checked_add(config_len)?;
+    if bytes.get(24..config_end)? != key.config.as_bytes() {
+        return None;
+    }
+    let records = &bytes[config_end..];
+    if !records.len().is_multiple_of(24) {
+        return None;
+    }
+    let mut values = HashMap::with_capacity(records.len() / 24);
+    for rec in records.chunks_exact(24) {
+        let t_k_1 = u64::from_le_bytes(rec[0..8].try_into().ok()?);
+        let t_k = u64::from_le_bytes(rec[8..16].try_into().ok()?);
+        let v = f64::from_bits(u64::from_le_bytes(rec[16..24].try_into().ok()?));
+        values.insert((Tau::try_from(t_k_1).ok()?, Tau::try_from(t_k).ok()?), v);
+    }
+    Some(values)
+}
+
+
+fn poisoned() -> CalcDpError {
+    CalcDpError::new("Cost cache lock is poisoned.")
+}
+
+
+/// FNV-1a（64bit）ハッシュ
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,88 @@
+//! 右打ち切りを含む寿命データのための指数分布に基づくコスト関数
+
+use super::{SegmentCost, SegmentParameter, PrefixSum, impl_calc_tt};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 右打ち切りを含む指数分布に基づくコスト関数
+///
+/// 各観測を(時間, 故障の有無)の組とし，故障していない観測は右打ち切りとして扱う．
+/// 区間ごとに故障率$ \lambda $を最尤推定$ \hat{\lambda} = d / T $（$ d $は故障数，$ T $は総観測時間）し，
+/// 対数尤度$ d \ln \hat{\lambda} - \hat{\lambda} T $を評価値とする．
+/// 稼働中の個体が多い故障時間の系列においても変化点を検出できる．
+#[derive(Debug, Clone)]
+pub struct CensoredExponentialCost {
+    t_max: Tau,
+    sum_time: PrefixSum,
+    sum_event: PrefixSum,
+}
+
+impl CensoredExponentialCost {
+    /// (時間, 故障の有無)の組からコスト関数を作成
+    ///
+    /// # 引数
+    /// * `data` - (時間, 故障の有無)の組．故障の有無が`false`の観測は右打ち切りとして扱う．時間は正の有限値である必要がある．
+    pub fn new(data: &[(f64, bool)]) -> Result<Self, CalcDpError> {
+        if let Some((time, _)) = data.iter().find(|(time, _)| !(time.is_finite() && *time > 0.0)) {
+            return Err(CalcDpError::new(format!("Observed time must be positive and finite, but {time} is given.")));
+        }
+
+        Ok(CensoredExponentialCost {
+            t_max: data.len() as Tau,
+            sum_time: PrefixSum::new(data.iter().map(|(time, _)| *time)),
+            sum_event: PrefixSum::new(data.iter().map(|(_, event)| if *event { 1.0 } else { 0.0 })),
+        })
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における故障率の最尤推定値
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn rate(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let d = self.sum_event.range(t_k_1, t_k);
+        let time = self.sum_time.range(t_k_1, t_k);
+        if d == 0.0 {
+            Ok(0.0)
+        } else {
+            Ok(d / time)
+        }
+    }
+}
+
+impl SegmentCost for CensoredExponentialCost {
+    fn t_max(&self) -> Tau {
+        self.t_max
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let lambda = self.rate(t_k_1, t_k)?;
+        let d = self.sum_event.range(t_k_1, t_k);
+        if d == 0.0 {
+            // 故障が無い場合は故障率0で尤度は1
+            Ok(0.0)
+        } else {
+            Ok(d * lambda.ln() - d)
+        }
+    }
+
+
+    fn constant_time(&self) -> bool {
+        true
+    }
+}
+
+impl_calc_tt!(CensoredExponentialCost);
+
+impl SegmentParameter for CensoredExponentialCost {
+    /// 故障率
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        Ok(vec![self.rate(t_k_1, t_k)?])
+    }
+}
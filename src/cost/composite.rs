@@ -0,0 +1,121 @@
+//! 複数のコスト関数を組み合わせたコスト関数
+
+use super::{SegmentCost, BoxedCost, impl_calc_tt};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 複数のコスト関数の重み付き和によるコスト関数
+///
+/// 1件のレコードが複数の列（例えば正規分布に従う計測値とポアソン分布に従う不良数）から成る場合に，
+/// 列ごとに作成したコスト関数を重み$ w_j $で足し合わせた$ \sum_j w_j f_j(t_k, t_{k-1}) $を評価値とする．
+/// これにより異なる種類のセンサを束ねた系列を一括して分割できる．
+///
+/// 構成するコスト関数は全て同じ長さの系列を保持している必要がある．
+pub struct Composite {
+    t_max: Tau,
+    components: Vec<(f64, BoxedCost)>,
+}
+
+impl Composite {
+    /// 重みとコスト関数の組からコスト関数を作成
+    ///
+    /// # 引数
+    /// * `components` - (重み, コスト関数)の組．重みは非負の有限値である必要がある．
+    pub fn new(components: Vec<(f64, BoxedCost)>) -> Result<Self, CalcDpError> {
+        let t_max = match components.first() {
+            Some((_, cost)) => cost.t_max(),
+            None => return Err(CalcDpError::new("Composite cost requires at least one component.")),
+        };
+
+        for (weight, cost) in components.iter() {
+            if !(weight.is_finite() && *weight >= 0.0) {
+                return Err(CalcDpError::new(format!("Weight must be non-negative and finite, but {weight} is given.")));
+            }
+            if cost.t_max() != t_max {
+                return Err(CalcDpError::new(format!("All components must have the same length (= {t_max}), but {} is given.", cost.t_max())));
+            }
+        }
+
+        Ok(Composite { t_max, components })
+    }
+
+
+    /// 構成するコスト関数の重み
+    pub fn weights(&self) -> Vec<f64> {
+        self.components.iter()
+                       .map(|(weight, _)| *weight)
+                       .collect()
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における構成するコスト関数ごとの評価値（重み付け前）
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn component_values(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        self.components.iter()
+                       .map(|(_, cost)| cost.segment_value(t_k_1, t_k))
+                       .collect()
+    }
+}
+
+impl std::fmt::Debug for Composite {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.debug_struct("Composite")
+         .field("t_max", &self.t_max)
+         .field("weights", &self.weights())
+         .finish()
+    }
+}
+
+impl SegmentCost for Composite {
+    fn t_max(&self) -> Tau {
+        self.t_max
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let vals = self.component_values(t_k_1, t_k)?;
+        // 重み0の成分が評価不能(-inf)の場合でもNaNとならないよう除外する
+        Ok(self.components.iter()
+                          .zip(vals)
+                          .filter(|((weight, _), _)| *weight > 0.0)
+                          .map(|((weight, _), val)| weight * val)
+                          .sum())
+    }
+
+
+    fn constant_time(&self) -> bool {
+        self.components.iter().all(|(_, cost)| cost.constant_time())
+    }
+
+
+    fn n_parameters(&self) -> usize {
+        self.components.iter().map(|(_, cost)| cost.n_parameters()).sum()
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        self.components.iter().map(|(_, cost)| cost.min_identifiable_size()).max().unwrap_or(1)
+    }
+
+
+    fn rejects_short_segments(&self) -> bool {
+        self.components.iter().all(|(_, cost)| cost.rejects_short_segments())
+    }
+
+
+    fn pruning_constant(&self) -> f64 {
+        self.components.iter()
+                       .filter(|(weight, _)| *weight > 0.0)
+                       .map(|(weight, cost)| weight * cost.pruning_constant())
+                       .sum()
+    }
+}
+
+impl_calc_tt!(Composite);
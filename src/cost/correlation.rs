@@ -0,0 +1,140 @@
+//! 2系列間の相関の変化を検出するためのコスト関数
+
+use super::{SegmentCost, SegmentParameter, PrefixSum, check_len, impl_calc_tt};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 相関係数の2乗の上限
+///
+/// 完全相関の場合に評価値が発散することを防ぐ．
+const MAX_R_SQUARED: f64 = 1.0 - 1e-12;
+
+
+/// 2系列間の相関係数の変化を検出するコスト関数
+///
+/// 区間ごとに2系列$ (x_i, y_i) $の標本相関係数$ r $を推定し，
+/// 2変量正規分布における相関係数のプロファイル対数尤度$ -\frac{n}{2} \ln (1 - r^2) $を評価値とする．
+/// 各系列の平均と分散は区間ごとに推定されるため，水準が安定したまま2系列の関係性のみが変化する場合を検出できる．
+///
+/// 相関係数の推定には3点以上のデータが必要であり，それより短い区間の評価値は`f64::NEG_INFINITY`となる．
+#[derive(Debug, Clone)]
+pub struct CorrelationCost {
+    t_max: Tau,
+    sum_x: PrefixSum,
+    sum_y: PrefixSum,
+    sum_xx: PrefixSum,
+    sum_yy: PrefixSum,
+    sum_xy: PrefixSum,
+}
+
+impl CorrelationCost {
+    /// 2系列からコスト関数を作成
+    ///
+    /// # 引数
+    /// * `x` - 1個目の系列．全て有限値である必要がある．
+    /// * `y` - 2個目の系列．全て有限値である必要がある．
+    pub fn new(x: &[f64], y: &[f64]) -> Result<Self, CalcDpError> {
+        check_len("y", y.len(), x.len())?;
+        if let Some(v) = x.iter().chain(y.iter()).find(|v| !v.is_finite()) {
+            return Err(CalcDpError::new(format!("Observation must be finite, but {v} is given.")));
+        }
+        Ok(CorrelationCost {
+            t_max: x.len() as Tau,
+            sum_x: PrefixSum::new(x.iter().copied()),
+            sum_y: PrefixSum::new(y.iter().copied()),
+            sum_xx: PrefixSum::new(x.iter().map(|v| v * v)),
+            sum_yy: PrefixSum::new(y.iter().map(|v| v * v)),
+            sum_xy: PrefixSum::new(x.iter().zip(y.iter()).map(|(a, b)| a * b)),
+        })
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における標本相関係数
+    ///
+    /// いずれかの系列が区間内で一定の場合，相関係数は0とする．
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn correlation(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let n = (t_k - t_k_1) as f64;
+        let sx = self.sum_x.range(t_k_1, t_k);
+        let sy = self.sum_y.range(t_k_1, t_k);
+        let cxx = self.sum_xx.range(t_k_1, t_k) - sx * sx / n;
+        let cyy = self.sum_yy.range(t_k_1, t_k) - sy * sy / n;
+        let cxy = self.sum_xy.range(t_k_1, t_k) - sx * sy / n;
+
+        if cxx <= 0.0 || cyy <= 0.0 {
+            Ok(0.0)
+        } else {
+            Ok((cxy / (cxx * cyy).sqrt()).clamp(-1.0, 1.0))
+        }
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における相関係数のFisherのz変換$ z = \tanh^{-1} r $
+    ///
+    /// $ z $の標準誤差は近似的に$ 1 / \sqrt{n - 3} $となる．
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn fisher_z(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let r_max = MAX_R_SQUARED.sqrt();
+        let r = self.correlation(t_k_1, t_k)?
+                    .clamp(-r_max, r_max);
+        Ok(r.atanh())
+    }
+}
+
+impl SegmentCost for CorrelationCost {
+    fn t_max(&self) -> Tau {
+        self.t_max
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let r = self.correlation(t_k_1, t_k)?;
+        let n = (t_k - t_k_1) as f64;
+        if n < 3.0 {
+            return Ok(f64::NEG_INFINITY);
+        }
+        let r_sq = (r * r).min(MAX_R_SQUARED);
+        Ok(- n / 2.0 * (1.0 - r_sq).ln())
+    }
+
+
+    fn constant_time(&self) -> bool {
+        true
+    }
+
+
+    /// 2系列の平均と分散，および相関係数
+    fn n_parameters(&self) -> usize {
+        5
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        3
+    }
+
+
+    /// 2点以下の区間の評価値は`f64::NEG_INFINITY`であり，相関に基づく評価値が分割により減少しないことも示されていないため，枝刈りしない
+    fn pruning_constant(&self) -> f64 {
+        f64::NEG_INFINITY
+    }
+}
+
+impl_calc_tt!(CorrelationCost);
+
+impl SegmentParameter for CorrelationCost {
+    /// 相関係数
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        Ok(vec![self.correlation(t_k_1, t_k)?])
+    }
+}
@@ -0,0 +1,116 @@
+//! トレイトオブジェクトとして扱えるコスト関数
+
+use super::{SegmentCost, impl_calc_tt};
+use crate::dp_tools::{calc_dp, CalcDpError, SeriesData};
+
+use std::marker::PhantomData;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 系列データを引数に取る，トレイトオブジェクトとして扱えるコスト関数
+///
+/// [`calc_dp::CalcTT`]は評価値の型と入力の型を型引数に持つ関連関数であり，`dyn`として扱えない．
+/// 本トレイトは系列データ（[`SeriesData`]）を引数に取るメソッドのみを持つため，`Box<dyn DynCost>`として保持し，
+/// 設定ファイル等から実行時に選んだコスト関数を型引数を伝播させずに扱える．
+///
+/// `CalcTT`からの変換は[`CalcTTCost`]，動的計画法への入力は[`DynSegment`]で行う．
+/// また`Fn(&SeriesData<f64>, Tau, Tau) -> Result<f64, CalcDpError>`を満たすクロージャも本トレイトを実装する．
+pub trait DynCost: Send + Sync {
+    /// 区間$ (t_{k-1}, t_k] $の評価値
+    ///
+    /// # 引数
+    /// * `data` - 系列データ
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    fn cost(&self, data: &SeriesData<f64>, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError>;
+}
+
+impl<F> DynCost for F
+where
+    F: Fn(&SeriesData<f64>, Tau, Tau) -> Result<f64, CalcDpError> + Send + Sync,
+{
+    fn cost(&self, data: &SeriesData<f64>, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self(data, t_k_1, t_k)
+    }
+}
+
+
+/// [`calc_dp::CalcTT`]を実装した型を[`DynCost`]として扱う
+///
+/// 型`T`は`SeriesData<f64>`を入力とし`f64`を評価値とする`CalcTT`を実装していること．
+pub struct CalcTTCost<T> {
+    // Send + Syncを型Tによらず満たすため，関数ポインタとして保持する
+    _calc: PhantomData<fn() -> T>,
+}
+
+impl<T: calc_dp::CalcTT<f64, SeriesData<f64>>> CalcTTCost<T> {
+    /// 型`T`の`CalcTT`をトレイトオブジェクトとして扱う
+    pub fn new() -> Self {
+        CalcTTCost { _calc: PhantomData }
+    }
+
+
+    /// 型`T`の`CalcTT`を`Box<dyn DynCost>`として返す
+    pub fn boxed() -> Box<dyn DynCost>
+    where
+        T: 'static,
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl<T: calc_dp::CalcTT<f64, SeriesData<f64>>> Default for CalcTTCost<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: calc_dp::CalcTT<f64, SeriesData<f64>>> DynCost for CalcTTCost<T> {
+    fn cost(&self, data: &SeriesData<f64>, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        T::calc_value(data, t_k_1, t_k)
+    }
+}
+
+
+/// [`DynCost`]と系列データの組
+///
+/// [`SegmentCost`]および[`calc_dp::CalcTT`]，[`crate::dp_tools::calc_dp_2::CalcTT`]を実装するため，
+/// [`crate::detect::detect`]等の動的計画法にそのまま与えられる．
+#[derive(Clone, Copy)]
+pub struct DynSegment<'a> {
+    cost: &'a dyn DynCost,
+    data: &'a SeriesData<f64>,
+}
+
+impl<'a> DynSegment<'a> {
+    /// コスト関数と系列データを組にする
+    ///
+    /// # 引数
+    /// * `cost` - コスト関数
+    /// * `data` - 系列データ
+    pub fn new(cost: &'a dyn DynCost, data: &'a SeriesData<f64>) -> Self {
+        DynSegment { cost, data }
+    }
+
+
+    /// 系列データ
+    pub fn data(&self) -> &'a SeriesData<f64> {
+        self.data
+    }
+}
+
+impl SegmentCost for DynSegment<'_> {
+    fn t_max(&self) -> Tau {
+        self.data.t_max()
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        self.cost.cost(self.data, t_k_1, t_k)
+    }
+}
+
+impl_calc_tt!(DynSegment<'_>);
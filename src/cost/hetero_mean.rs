@@ -0,0 +1,88 @@
+//! 観測ごとに既知の分散を持つ正規分布の平均変化を検出するためのコスト関数
+
+use super::{SegmentCost, SegmentParameter, PrefixSum, check_len, impl_calc_tt};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 観測ごとに既知の分散を持つ正規分布の平均変化を検出するコスト関数
+///
+/// 観測$ x_i $が既知の分散$ \sigma_i^2 $を持つ正規分布$ N(\mu, \sigma_i^2) $に従うとし，
+/// 区間ごとに重み$ w_i = 1 / \sigma_i^2 $による加重平均で$ \mu $を推定する．
+/// 評価値は対数尤度$ -\frac{1}{2} \sum w_i (x_i - \hat{\mu})^2 $である（定数項は除く）．
+/// 計測器ごとに不確かさが異なる場合，単純平均ではノイズの大きいセンサの影響が過大となることを防ぐ．
+#[derive(Debug, Clone)]
+pub struct HeteroscedasticMeanCost {
+    t_max: Tau,
+    sum_w: PrefixSum,
+    sum_wx: PrefixSum,
+    sum_wxx: PrefixSum,
+}
+
+impl HeteroscedasticMeanCost {
+    /// 観測値とその分散からコスト関数を作成
+    ///
+    /// # 引数
+    /// * `data` - 観測値．全て有限値である必要がある．
+    /// * `variances` - 各観測値の既知の分散．全て正の有限値である必要がある．
+    pub fn new(data: &[f64], variances: &[f64]) -> Result<Self, CalcDpError> {
+        check_len("variances", variances.len(), data.len())?;
+        if let Some(x) = data.iter().find(|x| !x.is_finite()) {
+            return Err(CalcDpError::new(format!("Observation must be finite, but {x} is given.")));
+        }
+        if let Some(v) = variances.iter().find(|v| !(v.is_finite() && **v > 0.0)) {
+            return Err(CalcDpError::new(format!("Variance must be positive and finite, but {v} is given.")));
+        }
+
+        let weights = variances.iter().map(|v| 1.0 / v).collect::<Vec<f64>>();
+        Ok(HeteroscedasticMeanCost {
+            t_max: data.len() as Tau,
+            sum_w: PrefixSum::new(weights.iter().copied()),
+            sum_wx: PrefixSum::new(weights.iter().zip(data.iter()).map(|(w, x)| w * x)),
+            sum_wxx: PrefixSum::new(weights.iter().zip(data.iter()).map(|(w, x)| w * x * x)),
+        })
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における平均の加重推定値
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn mean(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        Ok(self.sum_wx.range(t_k_1, t_k) / self.sum_w.range(t_k_1, t_k))
+    }
+}
+
+impl SegmentCost for HeteroscedasticMeanCost {
+    fn t_max(&self) -> Tau {
+        self.t_max
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let sw = self.sum_w.range(t_k_1, t_k);
+        let swx = self.sum_wx.range(t_k_1, t_k);
+        let swxx = self.sum_wxx.range(t_k_1, t_k);
+        // 桁落ちにより正となることを防ぐ
+        Ok((- 0.5 * (swxx - swx * swx / sw)).min(0.0))
+    }
+
+
+    fn constant_time(&self) -> bool {
+        true
+    }
+}
+
+impl_calc_tt!(HeteroscedasticMeanCost);
+
+impl SegmentParameter for HeteroscedasticMeanCost {
+    /// 平均の加重推定値
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        Ok(vec![self.mean(t_k_1, t_k)?])
+    }
+}
@@ -0,0 +1,88 @@
+//! 区間の中央値からの絶対偏差に基づくコスト関数
+
+use super::{SegmentCost, SegmentParameter, PrefixSum, OrderIndex, impl_calc_tt};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 区間の中央値からの絶対偏差(L1損失)に基づくコスト関数
+///
+/// 区間ごとに中央値$ \hat{m} $を推定し，評価値を$ -\sum |x_i - \hat{m}| $とする．
+/// これは尺度を1としたラプラス分布の対数尤度に相当する（定数項は除く）．
+/// 二乗誤差に基づくコスト関数では外れ値に平均の推定値が引きずられ，外れ値の前後に変化点を置きやすいが，
+/// 中央値は外れ値の影響を受けにくいため平均の変化を頑健に検出できる．
+///
+/// 任意の区間の中央値とそれ未満の値の総和を順序統計量の索引により$ O(\log n) $で計算する．
+/// 索引の作成には$ O(n \log n) $の時間と記憶領域を要する．
+/// 区間の長さが偶数の場合は，小さい方の中央値を用いる（絶対偏差の総和は2個の中央値の間で等しい）．
+#[derive(Debug, Clone)]
+pub struct L1Cost {
+    t_max: Tau,
+    sum_x: PrefixSum,
+    index: OrderIndex,
+}
+
+impl L1Cost {
+    /// 観測値からコスト関数を作成
+    ///
+    /// # 引数
+    /// * `data` - 観測値．全て有限値である必要がある．
+    pub fn new(data: &[f64]) -> Result<Self, CalcDpError> {
+        if let Some(x) = data.iter().find(|x| !x.is_finite()) {
+            return Err(CalcDpError::new(format!("Observation must be finite, but {x} is given.")));
+        }
+        Ok(L1Cost {
+            t_max: data.len() as Tau,
+            sum_x: PrefixSum::new(data.iter().copied()),
+            index: OrderIndex::new(data),
+        })
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における中央値
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn median(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let (median, _) = self.index.select(t_k_1 as usize, t_k as usize, median_rank(t_k_1, t_k));
+        Ok(median)
+    }
+}
+
+impl SegmentCost for L1Cost {
+    fn t_max(&self) -> Tau {
+        self.t_max
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let n = (t_k - t_k_1) as usize;
+        let r = median_rank(t_k_1, t_k);
+        let (median, sum_lo) = self.index.select(t_k_1 as usize, t_k as usize, r);
+        let sum_hi = self.sum_x.range(t_k_1, t_k) - sum_lo - median;
+        // 中央値未満のr個と，中央値を超えるn - r - 1個の絶対偏差
+        let dev = (r as f64 * median - sum_lo) + (sum_hi - (n - r - 1) as f64 * median);
+        // 桁落ちにより正となることを防ぐ
+        Ok((- dev).min(0.0))
+    }
+}
+
+impl_calc_tt!(L1Cost);
+
+impl SegmentParameter for L1Cost {
+    /// 中央値
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        Ok(vec![self.median(t_k_1, t_k)?])
+    }
+}
+
+
+/// 区間$ (t_{k-1}, t_k] $において中央値となる値の順位（0始まり）
+fn median_rank(t_k_1: Tau, t_k: Tau) -> usize {
+    (t_k - t_k_1 - 1) as usize / 2
+}
@@ -0,0 +1,214 @@
+//! 多変量正規分布の平均および共分散行列の変化を検出するためのコスト関数
+
+use super::{SegmentCost, SegmentParameter, impl_calc_tt};
+use crate::dp_tools::CalcDpError;
+use crate::linalg;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate ndarray;
+use ndarray::{Array2, Array3, Axis, s};
+
+
+/// 共分散行列の推定における正則化の設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Regularization {
+    /// リッジ項の係数．系列全体の共分散行列の対角成分の平均に乗じて，区間ごとの共分散行列の対角成分へ加える．
+    pub ridge: f64,
+    /// 条件数の上限．リッジ項を加えた共分散行列の条件数（推定値）がこれを超える区間は評価値を定義しない．
+    pub max_condition: f64,
+}
+
+impl Default for Regularization {
+    fn default() -> Self {
+        Regularization {
+            ridge: 1e-6,
+            max_condition: 1e12,
+        }
+    }
+}
+
+
+/// 多変量正規分布の平均および共分散行列の変化を検出するコスト関数
+///
+/// 区間ごとに平均ベクトル$ \hat{\mu} $と共分散行列$ \hat{\Sigma} $を最尤推定し，
+/// プロファイル対数尤度$ -\frac{n}{2} \ln \det \hat{\Sigma} $を評価値とする（定数項は除く）．
+///
+/// 系列の次元$ d $に比べて区間が短い場合，$ \hat{\Sigma} $は特異に近くなり行列式の対数が発散する．
+/// これを防ぐため$ \hat{\Sigma} $にリッジ項を加え，なお条件数が上限（[`Regularization::max_condition`]）を超える区間，
+/// またはCholesky分解できない区間の評価値は`f64::NEG_INFINITY`（実行不可能）とする．
+/// 評価値が`NaN`となって動的計画法の比較を損なうことはない．
+/// 条件数はCholesky分解の対角成分の比の2乗により推定する．
+#[derive(Debug, Clone)]
+pub struct MultivariateNormalCost {
+    dim: usize,
+    t_max: Tau,
+    sum_x: Array2<f64>,
+    sum_xx: Array3<f64>,
+    regularization: Regularization,
+    ridge: f64,
+}
+
+impl MultivariateNormalCost {
+    /// 既定の正則化の設定でコスト関数を作成
+    ///
+    /// # 引数
+    /// * `data` - 系列．行が時期，列が次元に対応する．
+    pub fn new(data: &Array2<f64>) -> Result<Self, CalcDpError> {
+        Self::with_regularization(data, Regularization::default())
+    }
+
+
+    /// 正則化の設定を指定してコスト関数を作成
+    ///
+    /// # 引数
+    /// * `data` - 系列．行が時期，列が次元に対応する．
+    /// * `regularization` - 共分散行列の推定における正則化の設定
+    pub fn with_regularization(data: &Array2<f64>, regularization: Regularization) -> Result<Self, CalcDpError> {
+        let (t_max, dim) = data.dim();
+        if dim == 0 {
+            return Err(CalcDpError::new("Series must have at least one dimension."));
+        }
+        if let Some(v) = data.iter().find(|v| !v.is_finite()) {
+            return Err(CalcDpError::new(format!("Data must be finite, but {v} is given.")));
+        }
+        if !(regularization.ridge >= 0.0 && regularization.ridge.is_finite()) {
+            return Err(CalcDpError::new(format!("ridge must be non-negative and finite, but {} is given.", regularization.ridge)));
+        }
+        if regularization.max_condition.is_nan() || regularization.max_condition < 1.0 {
+            return Err(CalcDpError::new(format!("max_condition must be at least 1, but {} is given.", regularization.max_condition)));
+        }
+
+        let mut sum_x = Array2::<f64>::zeros((t_max + 1, dim));
+        let mut sum_xx = Array3::<f64>::zeros((t_max + 1, dim, dim));
+        for (t, row) in data.outer_iter().enumerate() {
+            let outer = row.view().insert_axis(Axis(1)).dot(&row.view().insert_axis(Axis(0)));
+            let next_x = &sum_x.index_axis(Axis(0), t) + &row;
+            let next_xx = &sum_xx.index_axis(Axis(0), t) + &outer;
+            sum_x.index_axis_mut(Axis(0), t + 1).assign(&next_x);
+            sum_xx.index_axis_mut(Axis(0), t + 1).assign(&next_xx);
+        }
+
+        // 区間によらず同じ大きさのリッジ項を加えるため，系列全体の分散の平均を基準とする
+        let n = t_max.max(1) as f64;
+        let scale = (0..dim).map(|i| sum_xx[[t_max, i, i]] / n - (sum_x[[t_max, i]] / n).powi(2))
+                            .sum::<f64>() / dim as f64;
+        Ok(MultivariateNormalCost {
+            dim,
+            t_max: t_max as Tau,
+            sum_x,
+            sum_xx,
+            regularization,
+            ridge: regularization.ridge * scale.max(f64::MIN_POSITIVE),
+        })
+    }
+
+
+    /// 系列の次元
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+
+    /// 正則化の設定
+    pub fn regularization(&self) -> Regularization {
+        self.regularization
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における平均ベクトルの推定値
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn mean(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let n = (t_k - t_k_1) as f64;
+        let (a, b) = (t_k_1 as usize, t_k as usize);
+        let sx = &self.sum_x.slice(s![b, ..]) - &self.sum_x.slice(s![a, ..]);
+        Ok(sx.iter().map(|v| v / n).collect())
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $におけるリッジ項を加えた共分散行列の推定値（行優先）
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn covariance(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        let mean = self.mean(t_k_1, t_k)?;
+        let d = self.dim;
+        let n = (t_k - t_k_1) as f64;
+        let (a, b) = (t_k_1 as usize, t_k as usize);
+        let sxx = &self.sum_xx.slice(s![b, .., ..]) - &self.sum_xx.slice(s![a, .., ..]);
+        let mut cov = (0..(d * d)).map(|ij| sxx[[ij / d, ij % d]] / n - mean[ij / d] * mean[ij % d])
+                                  .collect::<Vec<f64>>();
+        for i in 0..d {
+            cov[i * d + i] += self.ridge;
+        }
+        Ok(cov)
+    }
+}
+
+impl SegmentCost for MultivariateNormalCost {
+    fn t_max(&self) -> Tau {
+        self.t_max
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let cov = self.covariance(t_k_1, t_k)?;
+        let d = self.dim;
+        let l = match linalg::cholesky(&cov, d) {
+            Some(l) => l,
+            None => return Ok(f64::NEG_INFINITY),
+        };
+        let diag = (0..d).map(|i| l[i * d + i]).collect::<Vec<f64>>();
+        let (lo, hi) = diag.iter().fold((f64::INFINITY, 0.0_f64), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+        if (hi / lo).powi(2) > self.regularization.max_condition {
+            return Ok(f64::NEG_INFINITY);
+        }
+        let log_det = 2.0 * diag.iter().map(|v| v.ln()).sum::<f64>();
+        let val = -((t_k - t_k_1) as f64) / 2.0 * log_det;
+        Ok(if val.is_finite() { val } else { f64::NEG_INFINITY })
+    }
+
+
+    fn constant_time(&self) -> bool {
+        true
+    }
+
+
+    /// 平均ベクトルと分散共分散行列
+    fn n_parameters(&self) -> usize {
+        self.dim + self.dim * (self.dim + 1) / 2
+    }
+
+
+    /// 分散共分散行列の標本推定値が正則となる長さ
+    fn min_identifiable_size(&self) -> Tau {
+        (self.dim + 1) as Tau
+    }
+
+
+    /// 正則化により短い区間でも分散共分散行列が正定値となり，特異に近い行列から発散した評価値を返す
+    fn rejects_short_segments(&self) -> bool {
+        false
+    }
+
+
+    /// 短い区間では発散した評価値を返し，分割による評価値の変化に下限がないため枝刈りしない
+    fn pruning_constant(&self) -> f64 {
+        f64::NEG_INFINITY
+    }
+}
+
+impl_calc_tt!(MultivariateNormalCost);
+
+impl SegmentParameter for MultivariateNormalCost {
+    /// 平均ベクトル
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        self.mean(t_k_1, t_k)
+    }
+}
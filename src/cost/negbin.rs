@@ -0,0 +1,216 @@
+//! 過分散な計数データのための負の二項分布に基づくコスト関数
+
+use super::{SegmentCost, SegmentParameter, PrefixSum, impl_calc_tt};
+use crate::dp_tools::CalcDpError;
+use crate::special::ln_gamma;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 区間ごとに推定する分散パラメータの探索範囲（対数スケール）
+const LN_R_MIN: f64 = -6.907_755_278_982_137; // ln(1e-3)
+const LN_R_MAX: f64 = 13.815_510_557_964_274; // ln(1e6)
+
+/// 黄金分割探索の反復回数
+const GOLDEN_ITER: usize = 60;
+
+
+/// 負の二項分布の分散パラメータ$ r $の扱い
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dispersion {
+    /// 全区間で共通の既知の値を用いる
+    Global(f64),
+    /// 区間ごとに最尤推定する
+    PerSegment,
+}
+
+
+/// 負の二項分布に基づくコスト関数
+///
+/// 観測$ y_i $が平均$ \mu $，分散$ \mu + \mu^2 / r $の負の二項分布に従うとし，
+/// 区間ごとに$ \mu $（および[`Dispersion::PerSegment`]の場合は$ r $）を最尤推定した対数尤度を評価値とする（$ \ln y_i! $の項は除く）．
+/// 実際の不良数等の計数データはポアソン分布に比べて過分散であることが多く，その影響を考慮した変化点を得られる．
+///
+/// # 計算量について
+/// [`Dispersion::Global`]の場合は累積和により$ O(1) $で評価値を計算する．
+/// [`Dispersion::PerSegment`]の場合は$ r $の推定に区間内の全データを用いた数値最適化を行うため，区間長を$ n $として$ O(n) $となる．
+#[derive(Debug, Clone)]
+pub struct NegBinomialCost {
+    data: Vec<u64>,
+    dispersion: Dispersion,
+    sum_y: PrefixSum,
+    /// [`Dispersion::Global`]の場合における$ \ln \Gamma(y_i + r) - \ln \Gamma(r) $の累積和
+    sum_ln_gamma: Option<PrefixSum>,
+}
+
+impl NegBinomialCost {
+    /// 計数データからコスト関数を作成
+    ///
+    /// # 引数
+    /// * `data` - 計数データ
+    /// * `dispersion` - 分散パラメータの扱い
+    pub fn new(data: &[u64], dispersion: Dispersion) -> Result<Self, CalcDpError> {
+        let sum_ln_gamma = match dispersion {
+            Dispersion::Global(r) => {
+                if !(r.is_finite() && r > 0.0) {
+                    return Err(CalcDpError::new(format!("Dispersion parameter must be positive and finite, but {r} is given.")));
+                }
+                let ln_gamma_r = ln_gamma(r);
+                Some(PrefixSum::new(data.iter().map(|y| ln_gamma(*y as f64 + r) - ln_gamma_r)))
+            },
+            Dispersion::PerSegment => None,
+        };
+
+        Ok(NegBinomialCost {
+            data: data.to_vec(),
+            dispersion,
+            sum_y: PrefixSum::new(data.iter().map(|y| *y as f64)),
+            sum_ln_gamma,
+        })
+    }
+
+
+    /// 分散パラメータの扱い
+    pub fn dispersion(&self) -> Dispersion {
+        self.dispersion
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における母数の最尤推定値
+    ///
+    /// [`Dispersion::PerSegment`]において過分散が見られない場合，$ r $は探索範囲の上限となる．
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    ///
+    /// # 返り値
+    /// * `(mu, r)` - 平均$ \hat{\mu} $と分散パラメータ$ \hat{r} $
+    pub fn estimate(&self, t_k_1: Tau, t_k: Tau) -> Result<(f64, f64), CalcDpError> {
+        let (mu, r, _) = self.fit(t_k_1, t_k)?;
+        Ok((mu, r))
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における最尤推定
+    ///
+    /// # 返り値
+    /// * `(mu, r, loglik)` - 母数の推定値と対数尤度
+    fn fit(&self, t_k_1: Tau, t_k: Tau) -> Result<(f64, f64, f64), CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let n = (t_k - t_k_1) as f64;
+        let s = self.sum_y.range(t_k_1, t_k);
+        let mu = s / n;
+
+        match (self.dispersion, &self.sum_ln_gamma) {
+            (Dispersion::Global(r), Some(sum_ln_gamma)) => {
+                let loglik = sum_ln_gamma.range(t_k_1, t_k) + profile_loglik_mu(n, s, mu, r);
+                Ok((mu, r, loglik))
+            },
+            _ => {
+                if s == 0.0 {
+                    return Ok((0.0, LN_R_MAX.exp(), 0.0));
+                }
+                let segment = &self.data[(t_k_1 as usize)..(t_k as usize)];
+                let loglik_r = |ln_r: f64| {
+                    let r = ln_r.exp();
+                    let ln_gamma_r = ln_gamma(r);
+                    segment.iter().map(|y| ln_gamma(*y as f64 + r) - ln_gamma_r).sum::<f64>()
+                        + profile_loglik_mu(n, s, mu, r)
+                };
+                let ln_r = golden_section_max(loglik_r, LN_R_MIN, LN_R_MAX);
+                Ok((mu, ln_r.exp(), loglik_r(ln_r)))
+            },
+        }
+    }
+}
+
+impl SegmentCost for NegBinomialCost {
+    fn t_max(&self) -> Tau {
+        self.data.len() as Tau
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let (_, _, loglik) = self.fit(t_k_1, t_k)?;
+        Ok(loglik)
+    }
+
+
+    /// 平均（[`Dispersion::PerSegment`]の場合は分散パラメータを含む）
+    fn n_parameters(&self) -> usize {
+        match self.dispersion {
+            Dispersion::Global(_) => 1,
+            Dispersion::PerSegment => 2,
+        }
+    }
+
+
+    /// [`Dispersion::PerSegment`]では過分散を推定するため2点以上を要する
+    fn min_identifiable_size(&self) -> Tau {
+        self.n_parameters() as Tau
+    }
+
+
+    /// [`Dispersion::PerSegment`]では1点の区間の評価値が定義できないため枝刈りしない
+    fn pruning_constant(&self) -> f64 {
+        match self.dispersion {
+            Dispersion::Global(_) => 0.0,
+            Dispersion::PerSegment => f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl_calc_tt!(NegBinomialCost);
+
+impl SegmentParameter for NegBinomialCost {
+    /// 平均（分散パラメータは含めない）
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        Ok(vec![self.estimate(t_k_1, t_k)?.0])
+    }
+}
+
+
+/// 対数尤度のうち$ \ln \Gamma $を含まない項 $ n r \ln \frac{r}{r + \mu} + S \ln \frac{\mu}{r + \mu} $
+///
+/// # 引数
+/// * `n` - 区間内のデータ数
+/// * `s` - 区間内の観測値の総和
+/// * `mu` - 平均
+/// * `r` - 分散パラメータ
+fn profile_loglik_mu(n: f64, s: f64, mu: f64, r: f64) -> f64 {
+    let term_mu = if s > 0.0 { s * (mu / (r + mu)).ln() } else { 0.0 };
+    n * r * (r / (r + mu)).ln() + term_mu
+}
+
+
+/// 黄金分割探索により単峰関数の最大値を与える点を求める
+///
+/// # 引数
+/// * `f` - 最大化する関数
+/// * `lower` - 探索範囲の下限
+/// * `upper` - 探索範囲の上限
+fn golden_section_max<F: Fn(f64) -> f64>(f: F, lower: f64, upper: f64) -> f64 {
+    let inv_phi = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let (mut a, mut b) = (lower, upper);
+    let mut c = b - inv_phi * (b - a);
+    let mut d = a + inv_phi * (b - a);
+    let (mut fc, mut fd) = (f(c), f(d));
+    for _ in 0..GOLDEN_ITER {
+        if fc >= fd {
+            b = d;
+            d = c;
+            fd = fc;
+            c = b - inv_phi * (b - a);
+            fc = f(c);
+        } else {
+            a = c;
+            c = d;
+            fc = fd;
+            d = a + inv_phi * (b - a);
+            fd = f(d);
+        }
+    }
+    if fc >= fd { c } else { d }
+}
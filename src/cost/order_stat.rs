@@ -0,0 +1,94 @@
+//! 任意の区間の順序統計量を求める索引
+
+/// 任意の区間の順序統計量を求める索引（Wavelet matrix）
+///
+/// 各観測値を系列全体での順位（同順位は時点順）に置き換え，順位の2進表現を上位の桁から順に用いて観測値を安定に並べ替えた列を段ごとに保持する．
+/// 各段では0の桁を持つ要素の個数と値の累積和を記録し，区間の$ r $番目に小さい値とそれより小さい値の総和を段数の手間で求める．
+#[derive(Debug, Clone)]
+pub(crate) struct OrderIndex {
+    /// 昇順に並べた観測値
+    sorted: Vec<f64>,
+    /// 段ごとの情報（上位の桁から順）
+    levels: Vec<Level>,
+}
+
+/// [`OrderIndex`]の1段
+#[derive(Debug, Clone)]
+struct Level {
+    /// 先頭から各位置までに，この段の桁が0である要素の個数
+    zeros: Vec<u32>,
+    /// 先頭から各位置までの，この段の桁が0である要素の値の総和
+    sum_zeros: Vec<f64>,
+    /// この段の桁が0である要素の総数
+    num_zeros: usize,
+}
+
+impl OrderIndex {
+    /// 索引を作成
+    ///
+    /// # 引数
+    /// * `data` - 観測値
+    pub(crate) fn new(data: &[f64]) -> Self {
+        let n = data.len();
+        let mut order = (0..n).collect::<Vec<usize>>();
+        order.sort_by(|a, b| data[*a].total_cmp(&data[*b]));
+        let sorted = order.iter().map(|i| data[*i]).collect::<Vec<f64>>();
+        let mut ranks = vec![0; n];
+        for (r, i) in order.into_iter().enumerate() {
+            ranks[i] = r;
+        }
+
+        let n_bits = (usize::BITS - n.saturating_sub(1).leading_zeros()).max(1);
+        let mut levels = Vec::with_capacity(n_bits as usize);
+        for bit in (0..n_bits).rev() {
+            let mut zeros = Vec::with_capacity(n + 1);
+            let mut sum_zeros = Vec::with_capacity(n + 1);
+            let (mut count, mut acc) = (0, 0.0);
+            zeros.push(count);
+            sum_zeros.push(acc);
+            for r in ranks.iter() {
+                if r >> bit & 1 == 0 {
+                    count += 1;
+                    acc += sorted[*r];
+                }
+                zeros.push(count);
+                sum_zeros.push(acc);
+            }
+            // 桁が0の要素を前に，1の要素を後ろに安定に並べ替える
+            let (lo, hi): (Vec<usize>, Vec<usize>) = ranks.iter().partition(|r| *r >> bit & 1 == 0);
+            ranks = lo.into_iter().chain(hi).collect();
+            levels.push(Level { zeros, sum_zeros, num_zeros: count as usize });
+        }
+        OrderIndex { sorted, levels }
+    }
+
+
+    /// 区間の$ r $番目（0始まり）に小さい値と，それより小さい$ r $個の値の総和
+    ///
+    /// # 引数
+    /// * `start` - 区間の先頭の位置
+    /// * `end` - 区間の末尾の次の位置
+    /// * `r` - 順位（`end - start`未満）
+    pub(crate) fn select(&self, start: usize, end: usize, r: usize) -> (f64, f64) {
+        let (mut start, mut end, mut r) = (start, end, r);
+        let mut rank = 0;
+        let mut sum_lo = 0.0;
+        for level in self.levels.iter() {
+            let (z_start, z_end) = (level.zeros[start] as usize, level.zeros[end] as usize);
+            let n_zeros = z_end - z_start;
+            rank <<= 1;
+            if r < n_zeros {
+                start = z_start;
+                end = z_end;
+            } else {
+                // 桁が0の要素は全て求める値より小さい
+                sum_lo += level.sum_zeros[end] - level.sum_zeros[start];
+                r -= n_zeros;
+                start = level.num_zeros + start - z_start;
+                end = level.num_zeros + end - z_end;
+                rank |= 1;
+            }
+        }
+        (self.sorted[rank], sum_lo)
+    }
+}
@@ -0,0 +1,168 @@
+//! 十分統計量の累積和に基づくコスト関数とその定義用マクロ
+
+use super::SegmentCost;
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 十分統計量の累積和により区間の評価値を計算するコスト関数
+///
+/// 区間$ (t_{k-1}, t_k] $の十分統計量を累積和の差として$ O(1) $で求め，そこから対数尤度を計算する．
+/// [`crate::segment_cost`]を用いることで，十分統計量と対数尤度の定義のみから本traitおよび関連するtraitを実装できる．
+pub trait PrefixCost: SegmentCost {
+    /// 区間の十分統計量
+    type Stat;
+
+
+    /// 区間$ (t_{k-1}, t_k] $の十分統計量
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    fn segment_stat(&self, t_k_1: Tau, t_k: Tau) -> Result<Self::Stat, CalcDpError>;
+
+
+    /// 十分統計量から区間の対数尤度を計算する
+    ///
+    /// # 引数
+    /// * `stat` - 区間の十分統計量
+    fn log_likelihood(stat: &Self::Stat) -> f64;
+}
+
+
+/// 十分統計量と対数尤度からコスト関数を定義する
+///
+/// 以下を生成する．
+/// * 十分統計量の構造体（全てのフィールドは`f64`）と，その成分ごとの加算・減算
+/// * コスト関数の構造体と，入力データから十分統計量の累積和を作成する`new`
+/// * [`crate::cost::PrefixCost`]および[`crate::cost::SegmentCost`]の実装
+/// * [`crate::dp_tools::calc_dp::CalcTT`]，[`crate::dp_tools::calc_dp_2::CalcTT`]および[`crate::dp_tools::calc_dp::DictTT`]の実装
+///
+/// `DictTT`で用いる評価値の表は，生成される`with_table`メソッドを呼び出すことで作成される．
+///
+/// # 例
+/// ```ignore
+/// segment_cost! {
+///     /// ポアソン分布に基づくコスト関数
+///     pub struct PoissonCost for u64 {
+///         stat PoissonStat { n, sum } = |y| PoissonStat { n: 1.0, sum: *y as f64 };
+///         loglik = |s| if s.sum > 0.0 { s.sum * (s.sum / s.n).ln() - s.sum } else { 0.0 };
+///     }
+/// }
+///
+/// let cost = PoissonCost::new(&[0, 2, 1, 5, 6, 4]);
+/// ```
+#[macro_export]
+macro_rules! segment_cost {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident for $input:ty {
+            stat $stat:ident { $($field:ident),+ $(,)? } = |$x:ident| $to_stat:expr;
+            loglik = |$s:ident| $loglik:expr;
+        }
+    ) => {
+        #[doc = concat!("[`", stringify!($name), "`]の区間における十分統計量")]
+        #[derive(Debug, Clone, Copy, Default, PartialEq)]
+        $vis struct $stat {
+            $(pub $field: f64,)+
+        }
+
+        impl std::ops::Add for $stat {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self {
+                $stat { $($field: self.$field + rhs.$field,)+ }
+            }
+        }
+
+        impl std::ops::Sub for $stat {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self {
+                $stat { $($field: self.$field - rhs.$field,)+ }
+            }
+        }
+
+        $(#[$meta])*
+        #[derive(Debug, Clone)]
+        $vis struct $name {
+            cumsum: Vec<$stat>,
+            table: Vec<Vec<f64>>,
+        }
+
+        impl $name {
+            /// 入力データからコスト関数を作成
+            pub fn new(data: &[$input]) -> Self {
+                let mut cumsum = Vec::with_capacity(data.len() + 1);
+                let mut acc = $stat::default();
+                cumsum.push(acc);
+                for $x in data.iter() {
+                    acc = acc + $to_stat;
+                    cumsum.push(acc);
+                }
+                $name { cumsum, table: Vec::new() }
+            }
+
+            /// `DictTT`で用いる評価値の表を作成
+            pub fn with_table(mut self) -> Result<Self, $crate::dp_tools::CalcDpError> {
+                let t_max = $crate::cost::SegmentCost::t_max(&self);
+                self.table = <Self as $crate::dp_tools::calc_dp::DictTT<f64, Self>>::calc_value_all(&self, &t_max)?;
+                Ok(self)
+            }
+        }
+
+        impl $crate::cost::PrefixCost for $name {
+            type Stat = $stat;
+
+            fn segment_stat(&self, t_k_1: $crate::__process_param::Tau, t_k: $crate::__process_param::Tau) -> Result<$stat, $crate::dp_tools::CalcDpError> {
+                $crate::cost::SegmentCost::check_segment(self, t_k_1, t_k)?;
+                Ok(self.cumsum[t_k as usize] - self.cumsum[t_k_1 as usize])
+            }
+
+            fn log_likelihood($s: &$stat) -> f64 {
+                $loglik
+            }
+        }
+
+        impl $crate::cost::SegmentCost for $name {
+            fn t_max(&self) -> $crate::__process_param::Tau {
+                (self.cumsum.len() - 1) as $crate::__process_param::Tau
+            }
+
+            fn segment_value(&self, t_k_1: $crate::__process_param::Tau, t_k: $crate::__process_param::Tau) -> Result<f64, $crate::dp_tools::CalcDpError> {
+                let stat = $crate::cost::PrefixCost::segment_stat(self, t_k_1, t_k)?;
+                Ok(<Self as $crate::cost::PrefixCost>::log_likelihood(&stat))
+            }
+
+            fn constant_time(&self) -> bool {
+                true
+            }
+        }
+
+        impl $crate::dp_tools::calc_dp::CalcTT<f64, $name> for $name {
+            fn calc_value(data: &$name, t_k_1: $crate::__process_param::Tau, t_k: $crate::__process_param::Tau) -> Result<f64, $crate::dp_tools::CalcDpError> {
+                $crate::cost::SegmentCost::segment_value(data, t_k_1, t_k)
+            }
+
+            fn calc_values_batch(data: &$name, pairs: &[($crate::__process_param::Tau, $crate::__process_param::Tau)]) -> Result<Vec<f64>, $crate::dp_tools::CalcDpError> {
+                $crate::cost::SegmentCost::segment_values(data, pairs)
+            }
+        }
+
+        impl $crate::dp_tools::calc_dp_2::CalcTT<f64, $name> for $name {
+            fn calc_value(data: &$name, t_k_1: $crate::__process_param::Tau, t_k: $crate::__process_param::Tau) -> Result<f64, $crate::dp_tools::CalcDpError> {
+                $crate::cost::SegmentCost::segment_value(data, t_k_1, t_k)
+            }
+
+            fn calc_values_batch(data: &$name, pairs: &[($crate::__process_param::Tau, $crate::__process_param::Tau)]) -> Result<Vec<f64>, $crate::dp_tools::CalcDpError> {
+                $crate::cost::SegmentCost::segment_values(data, pairs)
+            }
+        }
+
+        impl $crate::dp_tools::calc_dp::DictTT<f64, $name> for $name {
+            fn value_tt_all(&self) -> Vec<Vec<f64>> {
+                self.table.clone()
+            }
+        }
+    };
+}
@@ -0,0 +1,101 @@
+//! 指定した分位点の変化を検出するためのコスト関数
+
+use super::{SegmentCost, SegmentParameter, PrefixSum, OrderIndex, impl_calc_tt};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 分位点回帰の損失(pinball loss)に基づくコスト関数
+///
+/// 区間ごとに$ q $分位点$ \hat{\theta}_q $を推定し，評価値を
+/// $ -\sum \rho_q(x_i - \hat{\theta}_q) $，$ \rho_q(u) = u (q - \mathbb{1}[u < 0]) $とする．
+/// 平均が変わらずにばらつきや裾が変化した場合でも，例えば95%点の変化として検出できるため，
+/// 規格限界を超える割合の変化を監視する用途に適する．
+///
+/// 分位点には区間の$ \lceil q n \rceil $番目に小さい値を用いる（$ n $は区間の長さ）．
+/// $ q = 0.5 $の評価値は[`super::L1Cost`]のちょうど半分となる．
+#[derive(Debug, Clone)]
+pub struct QuantileCost {
+    /// 分位点の水準（$ 0 < q < 1 $）
+    q: f64,
+    t_max: Tau,
+    sum_x: PrefixSum,
+    index: OrderIndex,
+}
+
+impl QuantileCost {
+    /// 観測値と分位点の水準からコスト関数を作成
+    ///
+    /// # 引数
+    /// * `data` - 観測値．全て有限値である必要がある．
+    /// * `q` - 分位点の水準（$ 0 < q < 1 $）
+    pub fn new(data: &[f64], q: f64) -> Result<Self, CalcDpError> {
+        if !(q > 0.0 && q < 1.0) {
+            return Err(CalcDpError::new(format!("Quantile level must be in (0, 1), but {q} is given.")));
+        }
+        if let Some(x) = data.iter().find(|x| !x.is_finite()) {
+            return Err(CalcDpError::new(format!("Observation must be finite, but {x} is given.")));
+        }
+        Ok(QuantileCost {
+            q,
+            t_max: data.len() as Tau,
+            sum_x: PrefixSum::new(data.iter().copied()),
+            index: OrderIndex::new(data),
+        })
+    }
+
+
+    /// 分位点の水準
+    pub fn q(&self) -> f64 {
+        self.q
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における$ q $分位点
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn quantile(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let (quantile, _) = self.index.select(t_k_1 as usize, t_k as usize, self.rank(t_k_1, t_k));
+        Ok(quantile)
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $において分位点となる値の順位（0始まり）
+    fn rank(&self, t_k_1: Tau, t_k: Tau) -> usize {
+        let n = (t_k - t_k_1) as usize;
+        ((self.q * n as f64).ceil() as usize).clamp(1, n) - 1
+    }
+}
+
+impl SegmentCost for QuantileCost {
+    fn t_max(&self) -> Tau {
+        self.t_max
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let n = (t_k - t_k_1) as usize;
+        let r = self.rank(t_k_1, t_k);
+        let (quantile, sum_lo) = self.index.select(t_k_1 as usize, t_k as usize, r);
+        let sum_hi = self.sum_x.range(t_k_1, t_k) - sum_lo - quantile;
+        let loss = (1.0 - self.q) * (r as f64 * quantile - sum_lo)
+                   + self.q * (sum_hi - (n - r - 1) as f64 * quantile);
+        // 桁落ちにより正となることを防ぐ
+        Ok((- loss).min(0.0))
+    }
+}
+
+impl_calc_tt!(QuantileCost);
+
+impl SegmentParameter for QuantileCost {
+    /// $ q $分位点
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        Ok(vec![self.quantile(t_k_1, t_k)?])
+    }
+}
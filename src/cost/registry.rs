@@ -0,0 +1,238 @@
+//! 名前からコスト関数を作成するための登録簿
+
+use super::{BoxedCost, check_len, CorrelationCost, RegressionCost, HeteroscedasticMeanCost,
+            MultivariateNormalCost, Regularization, L1Cost, QuantileCost, ZipCost, NegBinomialCost, Dispersion, CensoredExponentialCost};
+use crate::dp_tools::CalcDpError;
+
+use std::collections::{BTreeMap, HashMap};
+
+extern crate ndarray;
+use ndarray::Array2;
+
+
+/// コスト関数の作成に用いる入力
+#[derive(Debug, Clone, Copy)]
+pub struct CostInput<'a> {
+    /// 系列データの各列
+    pub columns: &'a [Vec<f64>],
+    /// コスト関数の設定値
+    pub params: &'a BTreeMap<String, f64>,
+}
+
+impl<'a> CostInput<'a> {
+    /// 指定された列を取得
+    ///
+    /// # 引数
+    /// * `idx` - 列番号
+    pub fn column(&self, idx: usize) -> Result<&'a [f64], CalcDpError> {
+        match self.columns.get(idx) {
+            Some(column) => Ok(column),
+            None => Err(CalcDpError::new(format!("Column {idx} is required, but only {} columns are given.", self.columns.len()))),
+        }
+    }
+
+
+    /// 指定された列を計数データとして取得
+    ///
+    /// # 引数
+    /// * `idx` - 列番号
+    pub fn count_column(&self, idx: usize) -> Result<Vec<u64>, CalcDpError> {
+        self.column(idx)?.iter()
+                         .map(|v| {
+                             if v.is_finite() && *v >= 0.0 && v.fract() == 0.0 {
+                                 Ok(*v as u64)
+                             } else {
+                                 Err(CalcDpError::new(format!("Count data must be a non-negative integer, but {v} is given.")))
+                             }
+                         })
+                         .collect()
+    }
+
+
+    /// 設定値を取得
+    ///
+    /// # 引数
+    /// * `name` - 設定値の名前
+    pub fn param(&self, name: &str) -> Option<f64> {
+        self.params.get(name).copied()
+    }
+}
+
+
+/// コスト関数を作成する関数
+pub type CostConstructor = fn(&CostInput) -> Result<BoxedCost, CalcDpError>;
+
+
+/// 名前とコスト関数の作成方法を対応付けた登録簿
+///
+/// 設定ファイル等で文字列により指定されたコスト関数を作成するために用いる．
+/// 利用者が定義したコスト関数も[`Self::register`]または[`crate::register_costs`]により登録できる．
+#[derive(Debug, Clone, Default)]
+pub struct CostRegistry {
+    constructors: HashMap<String, CostConstructor>,
+}
+
+impl CostRegistry {
+    /// 空の登録簿を作成
+    pub fn new() -> Self {
+        CostRegistry::default()
+    }
+
+
+    /// 本crateのコスト関数を登録した登録簿を作成
+    ///
+    /// # 登録されるコスト関数
+    /// | 名前 | コスト関数 | 用いる列 | 設定値 |
+    /// |---|---|---|---|
+    /// | `correlation` | [`CorrelationCost`] | 0, 1列目 | |
+    /// | `regression` | [`RegressionCost`] | 0列目が応答変数，1列目以降が共変量 | `intercept`: 0以外なら切片を加える（既定値は1） |
+    /// | `hetero_mean` | [`HeteroscedasticMeanCost`] | 0列目が観測値，1列目が分散 | |
+    /// | `l1` | [`L1Cost`] | 0列目 | |
+    /// | `quantile` | [`QuantileCost`] | 0列目 | `q`: 分位点の水準（必須） |
+    /// | `mv_normal` | [`MultivariateNormalCost`] | 全ての列（各列が1次元） | `ridge`，`max_condition`: [`Regularization`]（省略した場合は既定値） |
+    /// | `zip` | [`ZipCost`] | 0列目 | |
+    /// | `negbin` | [`NegBinomialCost`] | 0列目 | `dispersion`: 指定した場合は全区間で共通の値，省略した場合は区間ごとに推定 |
+    /// | `censored_exp` | [`CensoredExponentialCost`] | 0列目が時間，1列目が故障の有無（0以外で故障） | |
+    pub fn with_builtins() -> Self {
+        let mut registry = CostRegistry::new();
+        for (name, constructor) in builtin_constructors() {
+            registry.constructors.insert(name.to_owned(), constructor);
+        }
+        registry
+    }
+
+
+    /// コスト関数を登録
+    ///
+    /// # 引数
+    /// * `name` - コスト関数の名前．既に登録されている名前は利用できない．
+    /// * `constructor` - コスト関数を作成する関数
+    pub fn register(&mut self, name: &str, constructor: CostConstructor) -> Result<(), CalcDpError> {
+        if self.constructors.contains_key(name) {
+            return Err(CalcDpError::new(format!("Cost function \"{name}\" is already registered.")));
+        }
+        self.constructors.insert(name.to_owned(), constructor);
+        Ok(())
+    }
+
+
+    /// 登録されているコスト関数の名前（昇順）
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self.constructors.keys()
+                                         .map(|name| name.as_str())
+                                         .collect::<Vec<&str>>();
+        names.sort();
+        names
+    }
+
+
+    /// 名前を指定してコスト関数を作成
+    ///
+    /// # 引数
+    /// * `name` - コスト関数の名前
+    /// * `input` - コスト関数の作成に用いる入力
+    pub fn build(&self, name: &str, input: &CostInput) -> Result<BoxedCost, CalcDpError> {
+        match self.constructors.get(name) {
+            Some(constructor) => constructor(input),
+            None => Err(CalcDpError::new(format!("Unknown cost function \"{name}\". Available: {}.", self.names().join(", ")))),
+        }
+    }
+}
+
+
+/// 複数のコスト関数をまとめて登録する
+///
+/// 最初に失敗した登録のErrorを返す．
+///
+/// # 例
+/// ```ignore
+/// let mut registry = CostRegistry::with_builtins();
+/// register_costs!(registry, "my_cost" => build_my_cost, "other" => build_other)?;
+/// ```
+#[macro_export]
+macro_rules! register_costs {
+    ($registry:expr, $($name:expr => $constructor:expr),+ $(,)?) => {{
+        let mut res: Result<(), $crate::dp_tools::CalcDpError> = Ok(());
+        $(
+            if res.is_ok() {
+                res = $registry.register($name, $constructor);
+            }
+        )+
+        res
+    }};
+}
+
+
+/// 本crateのコスト関数の作成方法
+fn builtin_constructors() -> Vec<(&'static str, CostConstructor)> {
+    vec![
+        ("correlation", |input| {
+            let x = input.column(0)?;
+            let y = input.column(1)?;
+            Ok(Box::new(CorrelationCost::new(x, y)?))
+        }),
+        ("regression", |input| {
+            let response = input.column(0)?;
+            let intercept = input.param("intercept").unwrap_or(1.0) != 0.0;
+            let covariates = &input.columns[1.min(input.columns.len())..];
+            for covariate in covariates {
+                check_len("covariate", covariate.len(), response.len())?;
+            }
+            let p = covariates.len() + usize::from(intercept);
+            let design = Array2::from_shape_fn((response.len(), p), |(t, j)| {
+                if intercept && j == 0 {
+                    1.0
+                } else {
+                    covariates[j - usize::from(intercept)][t]
+                }
+            });
+            Ok(Box::new(RegressionCost::new(response, design)?))
+        }),
+        ("hetero_mean", |input| {
+            let data = input.column(0)?;
+            let variances = input.column(1)?;
+            Ok(Box::new(HeteroscedasticMeanCost::new(data, variances)?))
+        }),
+        ("l1", |input| {
+            Ok(Box::new(L1Cost::new(input.column(0)?)?))
+        }),
+        ("quantile", |input| {
+            let q = input.param("q")
+                         .ok_or_else(|| CalcDpError::new("Parameter \"q\" is required for the quantile cost."))?;
+            Ok(Box::new(QuantileCost::new(input.column(0)?, q)?))
+        }),
+        ("mv_normal", |input| {
+            let first = input.column(0)?;
+            for column in input.columns {
+                check_len("column", column.len(), first.len())?;
+            }
+            let data = Array2::from_shape_fn((first.len(), input.columns.len()), |(t, j)| input.columns[j][t]);
+            let default = Regularization::default();
+            let regularization = Regularization {
+                ridge: input.param("ridge").unwrap_or(default.ridge),
+                max_condition: input.param("max_condition").unwrap_or(default.max_condition),
+            };
+            Ok(Box::new(MultivariateNormalCost::with_regularization(&data, regularization)?))
+        }),
+        ("zip", |input| {
+            Ok(Box::new(ZipCost::new(&input.count_column(0)?)))
+        }),
+        ("negbin", |input| {
+            let dispersion = match input.param("dispersion") {
+                Some(r) => Dispersion::Global(r),
+                None => Dispersion::PerSegment,
+            };
+            Ok(Box::new(NegBinomialCost::new(&input.count_column(0)?, dispersion)?))
+        }),
+        ("censored_exp", |input| {
+            let time = input.column(0)?;
+            let event = input.column(1)?;
+            check_len("event", event.len(), time.len())?;
+            let data = time.iter()
+                           .zip(event.iter())
+                           .map(|(t, e)| (*t, *e != 0.0))
+                           .collect::<Vec<(f64, bool)>>();
+            Ok(Box::new(CensoredExponentialCost::new(&data)?))
+        }),
+    ]
+}
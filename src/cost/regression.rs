@@ -0,0 +1,172 @@
+//! 共変量で調整した回帰モデルに基づくコスト関数
+
+use super::{SegmentCost, SegmentParameter, PrefixSum, check_len, impl_calc_tt};
+use crate::dp_tools::CalcDpError;
+use crate::linalg;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate ndarray;
+use ndarray::{Array2, Array3, Axis, s};
+
+
+/// 正規方程式に加えるリッジ項の係数
+///
+/// 区間内で共変量が多重共線性を持つ場合でも解が求まるよう，$ X^\top X $の対角成分の平均に乗じて加える．
+const RIDGE_SCALE: f64 = 1e-10;
+
+/// 残差平方和の下限
+const MIN_RSS: f64 = 1e-300;
+
+
+/// 共変量で調整した線形回帰モデルに基づくコスト関数
+///
+/// 区間ごとに応答変数$ y $を共変量行列$ X $（`design`）へ最小二乗法で回帰し，
+/// 残差に基づく正規分布のプロファイル対数尤度$ -\frac{n}{2} \ln (\mathrm{RSS} / n) $を評価値とする（定数項は除く）．
+/// 温度や送り速度のように既知の外生変数による変動を変化点として誤検出することを防ぐ．
+///
+/// 切片を含める場合は`design`に値が全て1の列を含めること．
+/// 区間内のデータ数が共変量の数以下の場合，評価値は`f64::NEG_INFINITY`となる．
+#[derive(Debug, Clone)]
+pub struct RegressionCost {
+    design: Array2<f64>,
+    sum_xx: Array3<f64>,
+    sum_xy: Array2<f64>,
+    sum_yy: PrefixSum,
+}
+
+impl RegressionCost {
+    /// 応答変数と共変量行列からコスト関数を作成
+    ///
+    /// # 引数
+    /// * `response` - 応答変数$ y $．全て有限値である必要がある．
+    /// * `design` - 共変量行列$ X $．行が時期，列が共変量に対応する．全て有限値である必要がある．
+    pub fn new(response: &[f64], design: Array2<f64>) -> Result<Self, CalcDpError> {
+        check_len("design", design.nrows(), response.len())?;
+        let (t_max, p) = design.dim();
+        if p == 0 {
+            return Err(CalcDpError::new("Design matrix must have at least one column."));
+        }
+        if let Some(v) = response.iter().chain(design.iter()).find(|v| !v.is_finite()) {
+            return Err(CalcDpError::new(format!("Response and covariates must be finite, but {v} is given.")));
+        }
+
+        let mut sum_xx = Array3::zeros((t_max + 1, p, p));
+        let mut sum_xy = Array2::zeros((t_max + 1, p));
+        for (t, (row, y)) in design.outer_iter().zip(response.iter()).enumerate() {
+            let outer = row.view().insert_axis(Axis(1)).dot(&row.view().insert_axis(Axis(0)));
+            let next_xx = &sum_xx.index_axis(Axis(0), t) + &outer;
+            let next_xy = &sum_xy.index_axis(Axis(0), t) + &(&row * *y);
+            sum_xx.index_axis_mut(Axis(0), t + 1).assign(&next_xx);
+            sum_xy.index_axis_mut(Axis(0), t + 1).assign(&next_xy);
+        }
+
+        Ok(RegressionCost {
+            design,
+            sum_xx,
+            sum_xy,
+            sum_yy: PrefixSum::new(response.iter().map(|y| y * y)),
+        })
+    }
+
+
+    /// 共変量行列
+    pub fn design(&self) -> &Array2<f64> {
+        &self.design
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における回帰係数の推定値
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn coefficients(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let (xx, xy) = self.segment_stats(t_k_1, t_k);
+        self.solve(&xx, &xy)
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における$ X^\top X $と$ X^\top y $
+    fn segment_stats(&self, t_k_1: Tau, t_k: Tau) -> (Vec<f64>, Vec<f64>) {
+        let (a, b) = (t_k_1 as usize, t_k as usize);
+        let xx = &self.sum_xx.slice(s![b, .., ..]) - &self.sum_xx.slice(s![a, .., ..]);
+        let xy = &self.sum_xy.slice(s![b, ..]) - &self.sum_xy.slice(s![a, ..]);
+        (xx.iter().copied().collect(), xy.to_vec())
+    }
+
+
+    /// リッジ項を加えた正規方程式を解く
+    fn solve(&self, xx: &[f64], xy: &[f64]) -> Result<Vec<f64>, CalcDpError> {
+        let p = xy.len();
+        let mean_diag = (0..p).map(|i| xx[i * p + i]).sum::<f64>() / p as f64;
+        let ridge = RIDGE_SCALE * mean_diag.max(1.0);
+        let mut a = xx.to_vec();
+        for i in 0..p {
+            a[i * p + i] += ridge;
+        }
+        match linalg::cholesky(&a, p) {
+            Some(l) => Ok(linalg::cholesky_solve(&l, p, xy)),
+            None => Err(CalcDpError::new("Failed to solve the normal equation of the regression.")),
+        }
+    }
+}
+
+impl SegmentCost for RegressionCost {
+    fn t_max(&self) -> Tau {
+        self.design.nrows() as Tau
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let n = (t_k - t_k_1) as usize;
+        let p = self.design.ncols();
+        if n <= p {
+            return Ok(f64::NEG_INFINITY);
+        }
+
+        let (xx, xy) = self.segment_stats(t_k_1, t_k);
+        let beta = self.solve(&xx, &xy)?;
+        // RSS = y'y - 2 b'X'y + b'X'X b
+        let b_xy = beta.iter().zip(xy.iter()).map(|(b, v)| b * v).sum::<f64>();
+        let b_xx_b = (0..p).map(|i| (0..p).map(|j| beta[i] * xx[i * p + j] * beta[j]).sum::<f64>())
+                           .sum::<f64>();
+        let rss = (self.sum_yy.range(t_k_1, t_k) - 2.0 * b_xy + b_xx_b).max(MIN_RSS);
+        let n = n as f64;
+        Ok(- n / 2.0 * (rss / n).ln())
+    }
+
+
+    fn constant_time(&self) -> bool {
+        true
+    }
+
+
+    /// 回帰係数と誤差分散
+    fn n_parameters(&self) -> usize {
+        self.design.ncols() + 1
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        (self.design.ncols() + 1) as Tau
+    }
+
+
+    /// 共変量の個数以下の区間の評価値は`f64::NEG_INFINITY`となるため，枝刈りしない
+    fn pruning_constant(&self) -> f64 {
+        f64::NEG_INFINITY
+    }
+}
+
+impl_calc_tt!(RegressionCost);
+
+impl SegmentParameter for RegressionCost {
+    /// 回帰係数
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        self.coefficients(t_k_1, t_k)
+    }
+}
@@ -0,0 +1,155 @@
+//! ゼロ過剰ポアソン分布に基づくコスト関数
+
+use super::{SegmentCost, SegmentParameter, PrefixSum, impl_calc_tt};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 切断ポアソン分布の母数推定におけるNewton法の最大反復回数
+const MAX_ITER: usize = 100;
+
+/// 切断ポアソン分布の母数推定における収束判定の閾値
+const TOLERANCE: f64 = 1e-12;
+
+
+/// ゼロ過剰ポアソン(Zero-inflated Poisson)分布に基づくコスト関数
+///
+/// 観測$ y_i $が確率$ \pi $で構造的な0，確率$ 1 - \pi $でポアソン分布$ Po(\lambda) $に従うとし，
+/// 区間ごとに$ \pi, \lambda $を最尤推定した対数尤度を評価値とする（$ \ln y_i! $の項は除く）．
+/// 稀な不良の個数のように0が過剰な計数データにおいて，構造的な0のみに起因する変化を誤検出することを防ぐ．
+///
+/// 0の割合がポアソン分布で説明できる場合は$ \hat{\pi} = 0 $となり，ポアソン分布の対数尤度と一致する．
+#[derive(Debug, Clone)]
+pub struct ZipCost {
+    t_max: Tau,
+    sum_y: PrefixSum,
+    num_zero: PrefixSum,
+}
+
+impl ZipCost {
+    /// 計数データからコスト関数を作成
+    ///
+    /// # 引数
+    /// * `data` - 計数データ
+    pub fn new(data: &[u64]) -> Self {
+        ZipCost {
+            t_max: data.len() as Tau,
+            sum_y: PrefixSum::new(data.iter().map(|y| *y as f64)),
+            num_zero: PrefixSum::new(data.iter().map(|y| if *y == 0 { 1.0 } else { 0.0 })),
+        }
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における母数の最尤推定値
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    ///
+    /// # 返り値
+    /// * `(pi, lambda)` - ゼロ過剰の確率$ \hat{\pi} $とポアソン分布の母数$ \hat{\lambda} $
+    pub fn estimate(&self, t_k_1: Tau, t_k: Tau) -> Result<(f64, f64), CalcDpError> {
+        let (pi, lambda, _) = self.fit(t_k_1, t_k)?;
+        Ok((pi, lambda))
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $における最尤推定
+    ///
+    /// # 返り値
+    /// * `(pi, lambda, loglik)` - 母数の推定値と対数尤度
+    fn fit(&self, t_k_1: Tau, t_k: Tau) -> Result<(f64, f64, f64), CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let n = (t_k - t_k_1) as f64;
+        let n_zero = self.num_zero.range(t_k_1, t_k);
+        let n_pos = n - n_zero;
+        let s = self.sum_y.range(t_k_1, t_k);
+
+        // 全て0の場合
+        if s == 0.0 {
+            return Ok((1.0, 0.0, 0.0));
+        }
+
+        // 正の観測値の平均が1の場合は切断ポアソン分布の母数が0に退化するためポアソン分布で扱う
+        let mean_pos = s / n_pos;
+        if mean_pos > 1.0 {
+            let lambda = truncated_poisson_mle(mean_pos);
+            let p_zero = (-lambda).exp();
+            if n_zero / n >= p_zero {
+                let pi = (n_zero / n - p_zero) / (1.0 - p_zero);
+                let loglik_zero = if n_zero > 0.0 { n_zero * (n_zero / n).ln() } else { 0.0 };
+                let loglik_pos = n_pos * (n_pos / n).ln()
+                                 + s * lambda.ln() - n_pos * lambda - n_pos * (1.0 - p_zero).ln();
+                return Ok((pi, lambda, loglik_zero + loglik_pos));
+            }
+        }
+
+        // ゼロ過剰が無い（pi = 0）場合はポアソン分布
+        let lambda = s / n;
+        Ok((0.0, lambda, s * lambda.ln() - s))
+    }
+}
+
+impl SegmentCost for ZipCost {
+    fn t_max(&self) -> Tau {
+        self.t_max
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let (_, _, loglik) = self.fit(t_k_1, t_k)?;
+        Ok(loglik)
+    }
+
+
+    /// 0の過剰の割合$ \pi $とポアソン分布の平均$ \lambda $
+    fn n_parameters(&self) -> usize {
+        2
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        2
+    }
+
+
+    /// 1点の区間の評価値は`f64::NEG_INFINITY`となり，分割により評価値が減少し得るため枝刈りしない
+    fn pruning_constant(&self) -> f64 {
+        f64::NEG_INFINITY
+    }
+}
+
+impl_calc_tt!(ZipCost);
+
+impl SegmentParameter for ZipCost {
+    /// 平均$ (1 - \pi) \lambda $
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        let (pi, lambda) = self.estimate(t_k_1, t_k)?;
+        Ok(vec![(1.0 - pi) * lambda])
+    }
+}
+
+
+/// 0で切断したポアソン分布の母数の最尤推定値
+///
+/// $ \lambda / (1 - e^{-\lambda}) = m $をNewton法により解く．
+///
+/// # 引数
+/// * `mean_pos` - 正の観測値の平均$ m (> 1) $
+fn truncated_poisson_mle(mean_pos: f64) -> f64 {
+    // g(λ) = λ - m(1 - e^{-λ}) は凸関数であり，λ = m から始めると正の解へ単調に収束する
+    let mut lambda = mean_pos;
+    for _ in 0..MAX_ITER {
+        let e = (-lambda).exp();
+        let g = lambda - mean_pos * (1.0 - e);
+        let dg = 1.0 - mean_pos * e;
+        let next = lambda - g / dg;
+        if (next - lambda).abs() <= TOLERANCE * lambda {
+            return next;
+        }
+        lambda = next;
+    }
+    lambda
+}
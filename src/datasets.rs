@@ -0,0 +1,118 @@
+//! 公開されている時系列データの取得
+//!
+//! 変化点検出の文献で用いられる公開データを取得し，ローカルに保存して再利用する．
+//! 例や動作確認において，実データに近い長さと性質の系列をすぐに試すために用いる．
+//!
+//! 取得したデータは[`cache_dir`]に保存し，2回目以降はネットワークに接続しない．
+//!
+//! 本moduleはfeature `datasets`を有効にした場合のみ利用できる．
+
+use crate::dp_tools::CalcDpError;
+use crate::error::Error;
+use crate::io::{self, ColumnRef};
+
+use std::path::{Path, PathBuf};
+
+extern crate ureq;
+
+
+/// 保存先のディレクトリを指定する環境変数
+pub const CACHE_DIR_ENV: &str = "CPD_TOOLS_DATASETS";
+
+
+/// 取得できるデータの情報
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatasetInfo {
+    /// [`fetch`]に与える名前
+    pub name: &'static str,
+    /// データの説明
+    pub description: &'static str,
+    /// 取得元のURL（CSV形式）
+    pub url: &'static str,
+    /// 値を格納した列
+    column: usize,
+}
+
+
+/// 取得できるデータの一覧
+const DATASETS: &[DatasetInfo] = &[
+    DatasetInfo {
+        name: "nile",
+        description: "Annual flow of the river Nile at Aswan, 1871-1970 (a mean shift around 1898).",
+        url: "https://vincentarelbundock.github.io/Rdatasets/csv/datasets/Nile.csv",
+        column: 2,
+    },
+    DatasetInfo {
+        name: "sunspot_month",
+        description: "Monthly mean relative sunspot numbers from 1749 (a long series with changing level and variance).",
+        url: "https://vincentarelbundock.github.io/Rdatasets/csv/datasets/sunspot.month.csv",
+        column: 2,
+    },
+];
+
+
+/// 取得できるデータの一覧
+pub fn list() -> &'static [DatasetInfo] {
+    DATASETS
+}
+
+
+/// 取得したデータの保存先
+///
+/// 環境変数[`CACHE_DIR_ENV`]が設定されていればそのディレクトリ，そうでなければ一時ディレクトリ内の`cpd_tools_datasets`とする．
+pub fn cache_dir() -> PathBuf {
+    match std::env::var_os(CACHE_DIR_ENV) {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::temp_dir().join("cpd_tools_datasets"),
+    }
+}
+
+
+/// データを取得する
+///
+/// 保存先（[`cache_dir`]）に取得済みであればそれを読み込み，無ければ取得元からダウンロードして保存する．
+///
+/// # 引数
+/// * `name` - データの名前（[`list`]を参照）
+///
+/// # 返り値
+/// * `values` - 時点順に並んだ系列
+pub fn fetch(name: &str) -> Result<Vec<f64>, Error> {
+    fetch_to(name, &cache_dir())
+}
+
+
+/// 保存先を指定してデータを取得する
+///
+/// 詳細は[`fetch`]を参照．
+///
+/// # 引数
+/// * `name` - データの名前
+/// * `dir` - 保存先のディレクトリ
+pub fn fetch_to(name: &str, dir: &Path) -> Result<Vec<f64>, Error> {
+    let info = DATASETS.iter()
+                       .find(|info| info.name == name)
+                       .ok_or_else(|| {
+                           let names = DATASETS.iter().map(|info| info.name).collect::<Vec<&str>>();
+                           CalcDpError::new(format!("Unknown dataset \"{name}\". Available: {}.", names.join(", ")))
+                       })?;
+    let path = dir.join(format!("{name}.csv"));
+    if !path.is_file() {
+        let text = download(info.url).map_err(|e| Error::io(&path, e))?;
+        std::fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+        // 書き込み途中のファイルを取得済みとみなさないよう，一時ファイルに書き込んでから置き換える
+        let partial = dir.join(format!("{name}.csv.part"));
+        std::fs::write(&partial, text).map_err(|e| Error::io(&partial, e))?;
+        std::fs::rename(&partial, &path).map_err(|e| Error::io(&path, e))?;
+    }
+    let mut columns = io::read_csv_columns(&path, &[ColumnRef::Index(info.column)], true, ',')?;
+    Ok(columns.remove(0))
+}
+
+
+/// 取得元からCSV形式の文字列をダウンロードする
+fn download(url: &str) -> Result<String, std::io::Error> {
+    ureq::get(url).call()
+                  .map_err(std::io::Error::other)?
+                  .into_string()
+}
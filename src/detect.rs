@@ -0,0 +1,910 @@
+//! コスト関数と動的計画法を用いた変化点検出
+//!
+//! [`crate::cost`]のコスト関数に対して[`crate::dp_tools`]の動的計画法を適用し，
+//! 変化点数の指定やペナルティによる選択を行った検出結果を得る．
+
+use crate::cost::{self, SegmentCost, SegmentParameter};
+use crate::dp_tools::{AddContext, CalcDpError, ErrorContext};
+use crate::dp_tools::dp_core::{self, Band, Layout, MinGap1, MinGap2};
+use crate::segment;
+
+use std::sync::Arc;
+use std::time::Instant;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+mod window;
+pub use window::{Window, detect_window};
+mod session;
+pub use session::{FitSession, WindowFit};
+mod interactive;
+pub use interactive::Session;
+mod auto;
+pub use auto::{detect_auto, choose_algorithm, Algorithm, AutoDetection, Selection};
+mod anytime;
+pub use anytime::{fit_with_deadline, fit_iter, FitIter, AnytimeResult, Stage};
+mod known;
+pub use known::detect_with_known;
+mod reconcile;
+pub use reconcile::{reconcile, Reconciliation};
+mod change_cost;
+pub use change_cost::{detect_with_change_cost, detect_with_prior, ChangeCost};
+mod render;
+mod profile;
+mod boundary;
+pub use boundary::{Boundary, Boundaries, WithContext};
+pub use profile::{ComputeStats, KStats};
+use profile::Counter;
+#[cfg(feature = "evcxr")]
+mod display;
+
+
+/// 動的計画法の計算に用いるメモ
+///
+/// 要素の位置は[`calc_dp::CalcDP`](crate::dp_tools::calc_dp::CalcDP)および[`calc_dp_2::CalcDP`](crate::dp_tools::calc_dp_2::CalcDP)と同じであり，値の種類ごとに別々の配列へ格納する．
+type Memo = dp_core::SoaMemo<f64>;
+
+
+/// 変化点検出の手法
+///
+/// 手法は今後追加しうるため，crate外で`match`する場合は`_`の分岐が必要となる．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Method {
+    /// 変化点間の最低間隔が1の動的計画法（[`calc_dp`](crate::dp_tools::calc_dp)）
+    Dp,
+    /// 変化点間の最低間隔が2の動的計画法（[`calc_dp_2`](crate::dp_tools::calc_dp_2)）
+    Dp2,
+    /// 系列長，変化点個数の上限およびコスト関数の性質から探索手法を自動選択する（[`detect_auto`]）
+    ///
+    /// [`FitResult`]等，動的計画法の計算結果そのものを用いる場合は[`Method::Dp`]として扱う．
+    Auto,
+}
+
+impl Method {
+    /// 系列長に対する変化点個数の最大値
+    ///
+    /// # 引数
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    pub fn max_k(&self, t_max: &Tau) -> NumChg {
+        if *t_max == 0 {
+            return 0;
+        }
+        match self {
+            Method::Dp | Method::Auto => (*t_max - 1) as NumChg,
+            Method::Dp2 => ((*t_max - 1) / 2) as NumChg,
+        }
+    }
+}
+
+impl std::str::FromStr for Method {
+    type Err = CalcDpError;
+
+    /// 設定ファイルと同じ名前（`"dp"`，`"dp2"`，`"auto"`）から変換
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dp" => Ok(Method::Dp),
+            "dp2" => Ok(Method::Dp2),
+            "auto" => Ok(Method::Auto),
+            _ => Err(CalcDpError::new(format!("Unknown method \"{s}\". Available: dp, dp2, auto."))),
+        }
+    }
+}
+
+
+/// 変化点個数の決め方
+///
+/// 情報量規準等による決め方を追加できるよう`#[non_exhaustive]`としている．
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Penalty {
+    /// 変化点個数を指定する
+    NumChange(NumChg),
+    /// 変化点1個あたりのペナルティ$ \beta $を指定し，$ \sum_{k} f(t_k, t_{k-1}) - \beta K $が最大となる変化点個数$ K $を選ぶ
+    Linear(f64),
+}
+
+
+/// 変化点検出における制約
+///
+/// 区間の長さの制約（`min_size`，`max_size`）は動的計画法（[`Method::Dp`]，[`Method::Dp2`]）で厳密に扱う．
+/// いずれかを指定した場合，一つ前の変化点の候補は$ [t - \ell_{max}, t - \ell_{min}] $に限られ，
+/// 計算量は$ O(K T^2) $から$ O(K T B) $（$ B = \ell_{max} - \ell_{min} + 1 $）となる．
+/// 得られる変化点は制約を満たす変化点の中での最適解である．
+/// 制約を満たす変化点が存在しない変化点個数の評価値は$ -\infty $となり，その個数の検出結果は`Err`となる．
+///
+/// 系列の端の扱い（`boundary`）は[`Boundary`]を参照．
+/// 観測範囲外のデータ（[`Boundary::Context`]）は[`detect`]および[`detect_with_min_effect`]が[`WithContext`]によりコスト関数へ反映する．
+/// それ以外の関数に与える場合はコスト関数を[`WithContext`]で包み，端を[`Boundary::Closed`]とした制約を与える．
+///
+/// 制約の種類は今後追加しうるため，crate外では[`Constraints::default`]に`with_*`メソッドで制約を加えて作成する．
+/// 設定ファイル等では指定しない制約を省略できる．
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct Constraints {
+    /// 変化点個数の上限．指定しない場合は手法における上限となる．
+    pub max_k: Option<NumChg>,
+    /// 区間の長さの最小値．指定しない場合は手法における最小値となる．
+    pub min_size: Option<Tau>,
+    /// 区間の長さの最大値．指定しない場合は上限を設けない．
+    pub max_size: Option<Tau>,
+    /// 系列の始端と終端の扱い
+    pub boundary: Boundaries,
+}
+
+impl Constraints {
+    /// 変化点個数の上限を指定する
+    ///
+    /// # 引数
+    /// * `max_k` - 変化点個数の上限
+    pub fn with_max_k(self, max_k: NumChg) -> Self {
+        Constraints { max_k: Some(max_k), ..self }
+    }
+
+
+    /// 区間の長さの最小値を指定する
+    ///
+    /// # 引数
+    /// * `min_size` - 区間の長さの最小値
+    pub fn with_min_size(self, min_size: Tau) -> Self {
+        Constraints { min_size: Some(min_size), ..self }
+    }
+
+
+    /// 区間の長さの最大値を指定する
+    ///
+    /// # 引数
+    /// * `max_size` - 区間の長さの最大値
+    pub fn with_max_size(self, max_size: Tau) -> Self {
+        Constraints { max_size: Some(max_size), ..self }
+    }
+
+
+    /// 系列の始端と終端の扱いを指定する
+    ///
+    /// # 引数
+    /// * `boundary` - 系列の始端と終端の扱い
+    pub fn with_boundary(self, boundary: Boundaries) -> Self {
+        Constraints { boundary, ..self }
+    }
+
+
+    /// 区間の長さの制約を指定したか
+    pub fn has_size_limits(&self) -> bool {
+        self.min_size.is_some() || self.max_size.is_some()
+    }
+
+
+    /// 動的計画法に課す区間の長さの範囲
+    ///
+    /// # 引数
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    ///
+    /// # 返り値
+    /// * `band` - 区間の長さの範囲．制約を指定しない場合は`None`．
+    fn band(&self, t_max: Tau) -> Result<Option<Band>, CalcDpError> {
+        if self.boundary.has_context() {
+            return Err(CalcDpError::new("Context boundaries must be applied to the cost with WithContext before fitting."));
+        }
+        if !self.has_size_limits() {
+            return Ok(None);
+        }
+        let min = self.min_size.unwrap_or(1);
+        let max = self.max_size.unwrap_or(t_max);
+        if min == 0 || min > max {
+            return Err(CalcDpError::new(format!("Segment length bounds must satisfy 1 <= min_size (= {min}) <= max_size (= {max}).")));
+        }
+        Ok(Some(Band {
+            min,
+            max,
+            open_start: self.boundary.start == Boundary::Open,
+            open_end: (self.boundary.end == Boundary::Open).then_some(t_max),
+        }))
+    }
+}
+
+
+/// 変化点の列（昇順）
+///
+/// 変化点はデータが切り替わる直前の時点として定義される．
+/// 空の場合は変化なし（変化点個数0，系列全体が1個の区間）を表す．
+pub type ChangePoints = Vec<Tau>;
+
+
+/// 変化点検出の結果
+///
+/// ペナルティによる選択等で変化点個数0となった場合も通常の結果として扱い，`change_points`は空となる．
+///
+/// 結果に含める値を追加できるよう`#[non_exhaustive]`としており，crate外では[`DetectionResult::new`]で作成する．
+/// JSON等への書き出しと読み込みでは値が保たれる．
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DetectionResult {
+    /// 変化点（昇順）．変化点はデータが切り替わる直前の時点として定義される．
+    pub change_points: ChangePoints,
+    /// 各区間の評価値の総和
+    pub value: f64,
+    /// 解析した範囲の始点．系列全体を解析した場合は0．
+    #[serde(default)]
+    pub start: Tau,
+    /// 解析した範囲の終点．系列全体を解析した場合は系列の長さ（最後の時期）．
+    pub t_max: Tau,
+    /// 手法を自動選択（[`Method::Auto`]）した場合に選んだ手法とその理由．それ以外の手法では`None`．
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<Selection>,
+}
+
+impl DetectionResult {
+    /// 系列全体（始点0）を解析した検出結果を作成する
+    ///
+    /// # 引数
+    /// * `change_points` - 変化点（昇順）
+    /// * `value` - 各区間の評価値の総和
+    /// * `t_max` - 系列の長さ（最後の時期）
+    pub fn new(change_points: ChangePoints, value: f64, t_max: Tau) -> Self {
+        DetectionResult { change_points, value, start: 0, t_max, selection: None }
+    }
+
+
+    /// 解析した範囲の始点を指定する
+    ///
+    /// # 引数
+    /// * `start` - 解析した範囲の始点
+    pub fn with_start(self, start: Tau) -> Self {
+        DetectionResult { start, ..self }
+    }
+
+
+    /// 変化点個数
+    pub fn num_change(&self) -> NumChg {
+        self.change_points.len() as NumChg
+    }
+
+
+    /// 変化点が無い（系列全体が1個の区間である）か
+    pub fn is_no_change(&self) -> bool {
+        self.change_points.is_empty()
+    }
+
+
+    /// 各区間の範囲$ (t_{k-1}, t_k] $
+    ///
+    /// # 返り値
+    /// * `segments` - (前の変化点, 後ろの変化点)の組．最初の区間は`start`から，最後の区間は`t_max`までとなる．
+    pub fn segments(&self) -> Vec<(Tau, Tau)> {
+        let starts = std::iter::once(self.start).chain(self.change_points.iter().copied());
+        let ends = self.change_points.iter().copied().chain(std::iter::once(self.t_max));
+        starts.zip(ends).collect()
+    }
+
+
+    /// 各時点（データのインデックス）が属する区間の番号
+    ///
+    /// 区間の番号は0から始まり，変化点$ t $の直後の観測`data[t]`から1増える．
+    /// 特徴量の表等に検出結果を結合するために用いる．
+    ///
+    /// # 引数
+    /// * `t_max` - 系列の長さ．解析した範囲（`start`から`t_max`）の外の時点は，範囲の前は最初の区間，後ろは最後の区間とする．
+    pub fn labels(&self, t_max: Tau) -> Vec<u32> {
+        let mut label = 0;
+        let mut next = self.change_points.iter().peekable();
+        (0..t_max).map(|t| {
+                      while next.next_if(|cp| **cp <= t).is_some() {
+                          label += 1;
+                      }
+                      label
+                  })
+                  .collect()
+    }
+
+
+    /// 各時点（データのインデックス）が変化後の最初の観測であれば1，それ以外は0とする指示ベクトル
+    ///
+    /// # 引数
+    /// * `t_max` - 系列の長さ．範囲外の変化点は無視する．
+    pub fn change_indicator(&self, t_max: Tau) -> Vec<u8> {
+        let mut indicator = vec![0; t_max as usize];
+        for t in self.change_points.iter().filter(|t| **t < t_max) {
+            indicator[*t as usize] = 1;
+        }
+        indicator
+    }
+
+
+    /// 区間ごとの範囲と評価値をCSV形式の文字列として出力
+    ///
+    /// # 引数
+    /// * `cost` - 評価値の計算に用いるコスト関数
+    pub fn to_csv<C: SegmentCost>(&self, cost: &C) -> Result<String, CalcDpError> {
+        let mut csv = "start,end,value\n".to_owned();
+        for (t_k_1, t_k) in self.segments() {
+            let val = cost.segment_value(t_k_1, t_k)?;
+            csv.push_str(&format!("{t_k_1},{t_k},{val}\n"));
+        }
+        Ok(csv)
+    }
+
+
+    /// 区間ごとの母数の推定値
+    ///
+    /// 変化点が無い場合は系列全体を1個の区間とした推定値のみを返す．
+    ///
+    /// # 引数
+    /// * `cost` - 推定に用いるコスト関数
+    ///
+    /// # 返り値
+    /// * `params` - [`Self::segments`]と同じ順番の母数の推定値
+    pub fn parameters<C: SegmentParameter>(&self, cost: &C) -> Result<Vec<Vec<f64>>, CalcDpError> {
+        self.segments()
+            .into_iter()
+            .map(|(t_k_1, t_k)| cost.segment_parameter(t_k_1, t_k))
+            .collect()
+    }
+}
+
+
+/// 利用者が与えた変化点（仮説）の評価
+///
+/// 「保守を行った14日目に変化した」のような仮説の変化点を，動的計画法による最適な変化点と比較する．
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HypothesisScore {
+    /// 仮説の変化点と各区間の評価値の総和
+    pub hypothesis: DetectionResult,
+    /// 計算済みの変化点個数$ k $ごとの（$ k $, 最適な評価値, 最適な評価値から仮説の評価値を引いた差）
+    pub gaps: Vec<(NumChg, f64, f64)>,
+}
+
+impl HypothesisScore {
+    /// 仮説と同じ変化点個数における最適な評価値との差（0以上）
+    ///
+    /// 仮説の変化点個数が計算済みの範囲を超える場合は`None`を返す．
+    pub fn gap(&self) -> Option<f64> {
+        let k = self.hypothesis.num_change();
+        self.gaps.iter().find(|(k_opt, _, _)| *k_opt == k).map(|(_, _, gap)| *gap)
+    }
+
+
+    /// 仮説の変化点が同じ変化点個数で最適か（評価値の差が`tolerance`以下か）
+    ///
+    /// # 引数
+    /// * `tolerance` - 評価値の差の許容値
+    pub fn is_optimal(&self, tolerance: f64) -> bool {
+        self.gap().is_some_and(|gap| gap <= tolerance)
+    }
+}
+
+
+/// 動的計画法による計算結果
+///
+/// 変化点個数$ k = 0, \dots, k_{max} $の全てについて最適な変化点を保持する．
+/// 変化点個数を変えて結果を取り出す場合も再計算は不要である．
+pub struct FitResult<'a, C> {
+    cost: &'a C,
+    method: Method,
+    k_max: NumChg,
+    memo: Arc<Memo>,
+    stats: Arc<ComputeStats>,
+}
+
+impl<'a, C: SegmentCost> FitResult<'a, C> {
+    /// 動的計画法により変化点個数$ k_{max} $までの最適な変化点を計算
+    ///
+    /// # 引数
+    /// * `cost` - コスト関数
+    /// * `method` - 変化点検出の手法
+    /// * `k_max` - 変化点個数の上限．`None`の場合は手法における上限となる．
+    pub fn fit(cost: &'a C, method: Method, k_max: Option<NumChg>) -> Result<Self, CalcDpError> {
+        Ok(Self::fit_until(cost, method, k_max, None, None)?.expect("No deadline is given"))
+    }
+
+
+    /// 区間の長さの制約を課して動的計画法により変化点個数$ k_{max} $までの最適な変化点を計算
+    ///
+    /// 制約の扱いは[`Constraints`]を参照．
+    ///
+    /// # 引数
+    /// * `cost` - コスト関数
+    /// * `method` - 変化点検出の手法
+    /// * `k_max` - 変化点個数の上限．`None`の場合は`constraints.max_k`，それも無い場合は手法における上限となる．
+    /// * `constraints` - 変化点検出における制約
+    pub fn fit_constrained(cost: &'a C, method: Method, k_max: Option<NumChg>, constraints: &Constraints) -> Result<Self, CalcDpError> {
+        let band = constraints.band(cost.t_max())?;
+        Ok(Self::fit_until(cost, method, k_max.or(constraints.max_k), band, None)?.expect("No deadline is given"))
+    }
+
+
+    /// 期限を指定して動的計画法を計算
+    ///
+    /// 各変化点個数の計算の前に期限を確認し，期限を過ぎた場合は`None`を返す．
+    ///
+    /// # 引数
+    /// * `cost` - コスト関数
+    /// * `method` - 変化点検出の手法
+    /// * `k_max` - 変化点個数の上限．`None`の場合は手法における上限となる．
+    /// * `band` - 区間の長さの範囲．`None`の場合は制限しない．
+    /// * `deadline` - 期限．`None`の場合は期限を設けない．
+    pub(crate) fn fit_until(cost: &'a C, method: Method, k_max: Option<NumChg>, band: Option<Band>, deadline: Option<Instant>) -> Result<Option<Self>, CalcDpError> {
+        let t_max = cost.t_max();
+        if t_max == 0 {
+            return Err(CalcDpError::new("Series must contain at least one point."));
+        }
+        let method = match method {
+            Method::Auto => Method::Dp,
+            m => m,
+        };
+        // Method::Dp2でも最初の区間は1点となり得るため，区間の長さの制限のみが最小の長さを定める
+        cost::check_min_size(cost, band.map_or(1, |b| b.min))?;
+        let k_lim = method.max_k(&t_max);
+        let k_max = match k_max {
+            Some(k) => k.min(k_lim),
+            None => k_lim,
+        };
+
+        let fitted = match method {
+            Method::Dp | Method::Auto => fill_all::<MinGap1, C>(cost, k_max, band, deadline),
+            Method::Dp2 => fill_all::<MinGap2, C>(cost, k_max, band, deadline),
+        }.add_context(|| ErrorContext::new("fitting change points").cost::<C>())?;
+        let (memo, stats) = match fitted {
+            Some(fitted) => fitted,
+            None => return Ok(None),
+        };
+
+        Ok(Some(FitResult { cost, method, k_max, memo: Arc::new(memo), stats: Arc::new(stats) }))
+    }
+
+
+    /// 計算に用いたコスト関数
+    pub fn cost(&self) -> &'a C {
+        self.cost
+    }
+
+
+    /// 変化点検出の手法
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+
+    /// 計算した変化点個数の上限
+    pub fn k_max(&self) -> NumChg {
+        self.k_max
+    }
+
+
+    /// 変化点個数ごとの計算時間，計算したメモの要素数およびメモの再利用率
+    pub fn compute_stats(&self) -> &ComputeStats {
+        &self.stats
+    }
+
+
+    /// 変化点個数$ k $における評価値の最大値
+    ///
+    /// 区間の長さの制約を満たす変化点が存在しない場合は$ -\infty $となる．
+    ///
+    /// # 引数
+    /// * `k` - 変化点個数
+    pub fn value(&self, k: NumChg) -> Result<f64, CalcDpError> {
+        match self.get_from_memo(&self.cost.t_max(), &k)? {
+            Some(v) => Ok(v.2),
+            None => Err(CalcDpError::new("Value has not calculated yet.")),
+        }
+    }
+
+
+    /// 変化点個数$ k = 0, \dots, k_{max} $の評価値の最大値
+    ///
+    /// 添字が変化点個数に対応する．各値は[`Self::value`]と同じである．
+    pub fn values_by_k(&self) -> Result<Vec<f64>, CalcDpError> {
+        let values = self.memo.last_values();
+        if values.len() != self.k_max as usize + 1 {
+            return Err(CalcDpError::new(format!("Memo holds {} rows, but k_max is {}.", values.len(), self.k_max)));
+        }
+        values.into_iter()
+              .map(|v| v.ok_or_else(|| CalcDpError::new("Value has not calculated yet.")))
+              .collect()
+    }
+
+
+    /// 変化点個数$ k $における最適な変化点（昇順）
+    ///
+    /// # 引数
+    /// * `k` - 変化点個数
+    pub fn change_points(&self, k: NumChg) -> Result<Vec<Tau>, CalcDpError> {
+        if self.value(k)? == f64::NEG_INFINITY {
+            return Err(CalcDpError::new(format!("No segmentation with k (= {k}) change points satisfies the segment length constraints.")));
+        }
+        let mut now_t = self.cost.t_max();
+        let mut now_k = k;
+        let mut change_points = Vec::with_capacity(k as usize);
+        while now_k > 0 {
+            let context = || ErrorContext::new("tracing back change points").at(now_t, now_k).cost::<C>();
+            match self.get_from_memo(&now_t, &now_k).add_context(context)? {
+                Some((t_k_1, _, _)) => {
+                    change_points.push(t_k_1);
+                    now_t = t_k_1;
+                    now_k -= 1;
+                },
+                None => return Err(CalcDpError::new("Uncalculated value exist.").with_context(context())),
+            }
+        }
+        change_points.reverse();
+        Ok(change_points)
+    }
+
+
+    /// 変化点個数$ k $における検出結果
+    ///
+    /// # 引数
+    /// * `k` - 変化点個数
+    pub fn result(&self, k: NumChg) -> Result<DetectionResult, CalcDpError> {
+        self.check_k(k)?;
+        Ok(DetectionResult::new(self.change_points(k)?, self.value(k)?, self.cost.t_max()))
+    }
+
+
+    /// 変化点が無い場合の検出結果
+    ///
+    /// 系列全体を1個の区間とした評価値を持ち，変化点は空となる．
+    /// [`Self::result`]に0を与えた場合と同じである．
+    pub fn no_change(&self) -> Result<DetectionResult, CalcDpError> {
+        self.result(0)
+    }
+
+
+    /// 変化点個数の決め方に従って検出結果を選択
+    ///
+    /// ペナルティにより変化点個数0が選ばれた場合は，変化点が空の検出結果を返す．
+    ///
+    /// # 引数
+    /// * `penalty` - 変化点個数の決め方
+    pub fn select(&self, penalty: &Penalty) -> Result<DetectionResult, CalcDpError> {
+        match penalty {
+            Penalty::NumChange(k) => self.result(*k),
+            Penalty::Linear(beta) => {
+                let values = self.values_by_k()?;
+                let mut best_k = 0;
+                let mut best_val = values[0];
+                for (k, val) in values.iter().enumerate().skip(1) {
+                    let val = val - beta * k as f64;
+                    if val > best_val {
+                        best_k = k;
+                        best_val = val;
+                    }
+                }
+                self.result(best_k as NumChg)
+            },
+        }
+    }
+
+
+    /// 利用者が与えた変化点（仮説）の評価値を求め，変化点個数ごとの最適な評価値との差を返す
+    ///
+    /// 評価値は各区間の評価値の総和であり，[`calc_dp::DictToFunc::evaluate`](crate::dp_tools::calc_dp::DictToFunc::evaluate)で区間の評価値の和を目的関数とする場合と同じである．
+    /// 区間の長さの制約は確認しないため，制約を満たさない仮説では差が負となることがある．
+    ///
+    /// # 引数
+    /// * `change_points` - 仮説の変化点（狭義単調増加かつ$ (0, T) $の範囲内）
+    pub fn score_hypothesis(&self, change_points: &[Tau]) -> Result<HypothesisScore, CalcDpError> {
+        let t_max = self.cost.t_max();
+        segment::segment_bounds(change_points, t_max as usize)?;
+
+        let mut hypothesis = DetectionResult::new(change_points.to_vec(), 0.0, t_max);
+        hypothesis.value = hypothesis.segments()
+                                     .into_iter()
+                                     .map(|(t_k_1, t_k)| self.cost.segment_value(t_k_1, t_k))
+                                     .sum::<Result<f64, CalcDpError>>()?;
+        let gaps = self.values_by_k()?
+                       .into_iter()
+                       .enumerate()
+                       .map(|(k, optimum)| (k as NumChg, optimum, optimum - hypothesis.value))
+                       .collect();
+        Ok(HypothesisScore { hypothesis, gaps })
+    }
+
+
+    /// 変化点個数が計算済みの範囲内か確認
+    fn check_k(&self, k: NumChg) -> Result<(), CalcDpError> {
+        if k > self.k_max {
+            Err(CalcDpError::new(format!("The number of change point k (= {k}) must not exceed k_max (= {}).", self.k_max)))
+        } else {
+            Ok(())
+        }
+    }
+
+
+    /// メモから値を取得
+    fn get_from_memo(&self, t: &Tau, k: &NumChg) -> Result<Option<(Tau, NumChg, f64)>, CalcDpError> {
+        self.check_k(*k)?;
+        match self.method {
+            Method::Dp | Method::Auto => dp_core::get::<MinGap1, _>(*t, *k, &*self.memo),
+            Method::Dp2 => dp_core::get::<MinGap2, _>(*t, *k, &*self.memo),
+        }
+    }
+}
+
+
+/// コスト関数と制約を指定して変化点を検出する
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `method` - 変化点検出の手法
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+pub fn detect<C: SegmentCost>(cost: &C, method: Method, penalty: &Penalty, constraints: &Constraints) -> Result<DetectionResult, CalcDpError> {
+    if constraints.boundary.has_context() {
+        let observed = WithContext::new(cost, &constraints.boundary)?;
+        let resolved = Constraints { boundary: constraints.boundary.resolved(), ..*constraints };
+        return detect_observed(&observed, method, penalty, &resolved);
+    }
+    detect_observed(cost, method, penalty, constraints)
+}
+
+
+/// 観測範囲外のデータを反映した後のコスト関数に対する変化点検出（[`detect`]）
+fn detect_observed<C: SegmentCost>(cost: &C, method: Method, penalty: &Penalty, constraints: &Constraints) -> Result<DetectionResult, CalcDpError> {
+    if method == Method::Auto {
+        return Ok(detect_auto(cost, penalty, constraints)?.result);
+    }
+    let k_max = match penalty {
+        Penalty::NumChange(k) => match constraints.max_k {
+            Some(max_k) if *k > max_k => return Err(CalcDpError::new(format!("The number of change point k (= {k}) must not exceed max_k (= {max_k})."))),
+            _ => Some(*k),
+        },
+        Penalty::Linear(_) => constraints.max_k,
+    };
+    FitResult::fit_constrained(cost, method, k_max, constraints)?.select(penalty)
+}
+
+
+/// 隣接する区間の母数の差に下限を課して変化点を検出する
+///
+/// 統計的には有意であっても実務上は無視できる微小な変化を除くため，
+/// 隣接する区間の母数（[`SegmentParameter`]）の差が全て`min_effect`以上となる検出結果のみを候補とする．
+/// 候補は動的計画法で求めた各変化点個数の最適解から選ぶため，制約を満たさない変化点を除いた後の変化点の位置も再度最適化される．
+/// 変化点個数を指定した場合は，その個数以下で制約を満たす最大の変化点個数の結果を返す．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `method` - 変化点検出の手法
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+/// * `min_effect` - 隣接する区間の母数の差の下限
+pub fn detect_with_min_effect<C: SegmentParameter>(cost: &C, method: Method, penalty: &Penalty, constraints: &Constraints, min_effect: f64) -> Result<DetectionResult, CalcDpError> {
+    if constraints.boundary.has_context() {
+        let observed = WithContext::new(cost, &constraints.boundary)?;
+        let resolved = Constraints { boundary: constraints.boundary.resolved(), ..*constraints };
+        return detect_with_min_effect_observed(&observed, method, penalty, &resolved, min_effect);
+    }
+    detect_with_min_effect_observed(cost, method, penalty, constraints, min_effect)
+}
+
+
+/// 観測範囲外のデータを反映した後のコスト関数に対する変化点検出（[`detect_with_min_effect`]）
+fn detect_with_min_effect_observed<C: SegmentParameter>(cost: &C, method: Method, penalty: &Penalty, constraints: &Constraints, min_effect: f64) -> Result<DetectionResult, CalcDpError> {
+    let k_max = match penalty {
+        Penalty::NumChange(k) => match constraints.max_k {
+            Some(max_k) if *k > max_k => return Err(CalcDpError::new(format!("The number of change point k (= {k}) must not exceed max_k (= {max_k})."))),
+            _ => Some(*k),
+        },
+        Penalty::Linear(_) => constraints.max_k,
+    };
+    FitResult::fit_constrained(cost, method, k_max, constraints)?.select_with_min_effect(penalty, min_effect)
+}
+
+
+impl<C: SegmentParameter> FitResult<'_, C> {
+    /// 隣接する区間の母数の差に下限を課して検出結果を選択
+    ///
+    /// 詳細は[`detect_with_min_effect`]を参照．
+    ///
+    /// # 引数
+    /// * `penalty` - 変化点個数の決め方
+    /// * `min_effect` - 隣接する区間の母数の差の下限
+    pub fn select_with_min_effect(&self, penalty: &Penalty, min_effect: f64) -> Result<DetectionResult, CalcDpError> {
+        let (k_lim, beta) = match penalty {
+            Penalty::NumChange(k) => {
+                self.check_k(*k)?;
+                (*k, None)
+            },
+            Penalty::Linear(beta) => (self.k_max, Some(*beta)),
+        };
+
+        let mut best: Option<(NumChg, f64)> = None;
+        for k in (0..=k_lim).rev() {
+            // 区間の長さの制約を満たす変化点が存在しない個数は除く
+            if self.value(k)? == f64::NEG_INFINITY {
+                continue;
+            }
+            let cps = self.change_points(k)?;
+            if !self.satisfies_min_effect(&cps, min_effect)? {
+                continue;
+            }
+            match beta {
+                None => return self.result(k),
+                Some(beta) => {
+                    let val = self.value(k)? - beta * k as f64;
+                    if best.is_none_or(|(_, v)| val >= v) {
+                        best = Some((k, val));
+                    }
+                },
+            }
+        }
+        // 変化点個数0は常に制約を満たす
+        self.result(best.map_or(0, |(k, _)| k))
+    }
+
+
+    /// 隣接する区間の母数の差が全て下限以上か確認
+    fn satisfies_min_effect(&self, change_points: &[Tau], min_effect: f64) -> Result<bool, CalcDpError> {
+        let bounds = std::iter::once(0).chain(change_points.iter().copied())
+                                       .chain(std::iter::once(self.cost.t_max()))
+                                       .collect::<Vec<Tau>>();
+        let params = bounds.windows(2)
+                           .map(|w| self.cost.segment_parameter(w[0], w[1]))
+                           .collect::<Result<Vec<Vec<f64>>, CalcDpError>>()?;
+        Ok(params.windows(2).all(|p| {
+            let diff = p[0].iter().zip(p[1].iter())
+                                  .map(|(a, b)| (a - b).abs())
+                                  .fold(0.0, f64::max);
+            diff >= min_effect
+        }))
+    }
+}
+
+
+/// 残差に対する変化点検出の結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResidualDetection {
+    /// 残差に対する検出結果
+    pub result: DetectionResult,
+    /// 区間ごとの平均
+    pub segments: Vec<ResidualSegment>,
+}
+
+
+/// 残差に対する変化点検出における区間ごとの平均
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ResidualSegment {
+    /// 前の変化点$ t_{k-1} $
+    pub start: Tau,
+    /// 後ろの変化点$ t_k $
+    pub end: Tau,
+    /// 観測値の平均
+    pub raw_mean: f64,
+    /// 予測値の平均
+    pub predicted_mean: f64,
+    /// 残差（観測値 - 予測値）の平均
+    pub residual_mean: f64,
+}
+
+
+/// 利用者のモデルによる予測値を差し引いた残差から変化点を検出する
+///
+/// 設定値の推移等，既知の変動を表すモデルを差し引くことで，モデルからの乖離の変化のみを検出する．
+/// 結果には区間ごとに観測値と残差の両方の平均を付与する．
+///
+/// # 引数
+/// * `data` - 観測値
+/// * `model` - 観測値から予測値（`data`と同じ長さ）を求める関数
+/// * `build` - 残差からコスト関数を作成する関数
+/// * `method` - 変化点検出の手法
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+pub fn detect_on_residuals<C, M, B>(data: &[f64], model: M, build: B, method: Method, penalty: &Penalty, constraints: &Constraints) -> Result<ResidualDetection, CalcDpError>
+where
+    C: SegmentCost,
+    M: FnOnce(&[f64]) -> Vec<f64>,
+    B: FnOnce(&[f64]) -> Result<C, CalcDpError>,
+{
+    let predictions = model(data);
+    if predictions.len() != data.len() {
+        return Err(CalcDpError::new(format!("Length of predictions (= {}) must be equal to the length of the series (= {}).", predictions.len(), data.len())));
+    }
+    let residuals = data.iter()
+                        .zip(predictions.iter())
+                        .map(|(x, p)| x - p)
+                        .collect::<Vec<f64>>();
+
+    let cost = build(&residuals)?;
+    let result = detect(&cost, method, penalty, constraints)?;
+
+    let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+    let segments = result.segments()
+                         .into_iter()
+                         .map(|(start, end)| {
+                             let range = (start as usize)..(end as usize);
+                             ResidualSegment {
+                                 start,
+                                 end,
+                                 raw_mean: mean(&data[range.clone()]),
+                                 predicted_mean: mean(&predictions[range.clone()]),
+                                 residual_mean: mean(&residuals[range]),
+                             }
+                         })
+                         .collect();
+    Ok(ResidualDetection { result, segments })
+}
+
+
+/// 変化点個数$ k_{max} $までの動的計画法のメモを計算する
+///
+/// 各変化点個数の計算の前に期限を確認し，期限を過ぎた場合は`None`を返す．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `k_max` - 変化点個数の上限（手法における上限以下）
+/// * `band` - 区間の長さの範囲．`None`の場合は制限しない．
+/// * `deadline` - 期限．`None`の場合は期限を設けない．
+fn fill_all<L: Layout, C: SegmentCost>(cost: &C, k_max: NumChg, band: Option<Band>, deadline: Option<Instant>) -> Result<Option<(Memo, ComputeStats)>, CalcDpError> {
+    let t_max = cost.t_max();
+    // 上限を超える変化点個数の行は利用しないため確保しない
+    let mut memo = Memo::allocate::<L>(t_max, k_max)?;
+    let mut stats = ComputeStats::default();
+    for k in 0..=k_max {
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            return Ok(None);
+        }
+        let counter = Counter::default();
+        let started = Instant::now();
+        calc_memo::<L, C>(t_max, k, &mut memo, cost, band, &counter)?;
+        stats.per_k.push(counter.finish(k, started.elapsed()));
+    }
+    Ok(Some((memo, stats)))
+}
+
+
+/// 動的計画法でメモを計算する
+///
+/// 評価値の計算は[`calc_dp::CalcDP::calc_memo`](crate::dp_tools::calc_dp::CalcDP::calc_memo)と同じであり，加えて計算量を`counter`へ記録する．
+/// 区間の長さを制限した場合，制約を満たす変化点が存在しない要素は評価値を$ -\infty $とする．
+/// 評価値が$ -\infty $の一つ前の変化点は候補から除くため，その区間の評価値は計算しない．
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点個数
+/// * `memo` - 動的計画法の計算に用いるメモ
+/// * `cost` - コスト関数
+/// * `band` - 区間の長さの範囲．`None`の場合は制限しない．
+/// * `counter` - 計算量の記録
+fn calc_memo<L: Layout, C: SegmentCost>(t: Tau, k: NumChg, memo: &mut Memo, cost: &C, band: Option<Band>, counter: &Counter) -> Result<(Tau, NumChg, f64), CalcDpError> {
+    let terminal = |t| {
+        let val = match band {
+            Some(band) if !band.admits(0, t) => {
+                counter.record(0, 0);
+                f64::NEG_INFINITY
+            },
+            _ => {
+                counter.record(0, 1);
+                dp_core::finite_or_infeasible(cost.segment_value(0, t)?, 0, t)?
+            },
+        };
+        Ok((0, 0, val))
+    };
+    let evaluate = |prevs: dp_core::Candidates<(Tau, NumChg, f64)>, t, k| {
+        let lookups = prevs.len();
+        let prevs = match band {
+            Some(_) => prevs.into_iter()
+                            .filter(|(_, prev)| prev.2 != f64::NEG_INFINITY)
+                            .collect::<Vec<_>>(),
+            None => prevs,
+        };
+        counter.record(lookups, prevs.len());
+        if prevs.is_empty() {
+            return Ok(vec![(0, k, f64::NEG_INFINITY)]);
+        }
+        let pairs = prevs.iter().map(|(i, _)| (*i, t)).collect::<Vec<(Tau, Tau)>>();
+        let vals_tt = dp_core::eval_batches_par(&pairs, |batch| cost.segment_values(batch))?;
+        prevs.into_iter()
+             .zip(vals_tt)
+             .map(|((i, prev), val_tt)| Ok((i, k, prev.2 + dp_core::finite_or_infeasible(val_tt, i, t)?)))
+             .collect()
+    };
+    dp_core::fill_banded::<L, f64, _, _, _>(t, k, memo, band, &terminal, &evaluate)
+}
@@ -0,0 +1,206 @@
+//! 計算時間の上限を指定した変化点検出
+
+use super::{FitResult, Method, Penalty, Constraints, DetectionResult};
+use crate::cost::SegmentCost;
+use crate::dp_tools::CalcDpError;
+use crate::rng::RngConfig;
+use crate::search;
+
+use std::time::{Duration, Instant};
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// Wild binary segmentationで無作為に選ぶ区間の数
+const WBS_INTERVALS: usize = 500;
+/// Wild binary segmentationの乱数の設定（結果を再現できるよう固定する）
+const WBS_RNG: RngConfig = RngConfig { seed: 0 };
+
+
+/// 段階的な探索の各段階
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// 二分割法（[`search::binseg`]）
+    Binseg,
+    /// Wild binary segmentation（[`search::wbs`]）
+    Wbs,
+    /// 厳密解（ペナルティのみの場合は[`search::pelt`]，それ以外は動的計画法）
+    Exact,
+}
+
+
+/// 計算時間の上限を指定した変化点検出の結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnytimeResult {
+    /// 期限までに得られた最良の検出結果
+    pub result: DetectionResult,
+    /// 最良の検出結果を得た段階
+    pub stage: Stage,
+    /// 大域的な最適解であるか．`false`の場合は近似解である．
+    pub exact: bool,
+}
+
+
+/// 計算時間の上限を指定して変化点を検出する
+///
+/// 二分割法，Wild binary segmentation，厳密解の順に計算し，期限までに得られた最良の検出結果を返す．
+/// 最良の検出結果は$ \sum_k f(t_{k-1}, t_k) - \beta K $（変化点個数を指定した場合は評価値の総和）により比較する．
+/// 期限の確認は段階の間，および厳密解の計算における各時点または各変化点個数の計算の前に行うため，
+/// 期限をわずかに超えて返る場合がある．ただし二分割法の結果は期限によらず必ず計算する．
+///
+/// 区間の長さの制約（[`Constraints::min_size`]，[`Constraints::max_size`]）を指定した場合は，
+/// 近似解が制約を満たさない可能性があるため厳密解のみを計算し，期限によらずその結果を返す．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `method` - 変化点検出の手法（区間の最小の長さを定める．[`Method::Auto`]は[`Method::Dp`]として扱う．）
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+/// * `budget` - 計算時間の上限
+pub fn fit_with_deadline<C>(cost: &C, method: Method, penalty: &Penalty, constraints: &Constraints, budget: Duration) -> Result<AnytimeResult, CalcDpError>
+where
+    C: SegmentCost,
+{
+    let deadline = Instant::now() + budget;
+    let mut iter = fit_iter(cost, method, penalty, constraints);
+    let (mut best, _) = iter.step(None)?.expect("The first stage ignores the deadline");
+    while Instant::now() < deadline {
+        match iter.step(Some(deadline))? {
+            Some((res, _)) => best = res,
+            None => break,
+        }
+    }
+    Ok(best)
+}
+
+
+/// 段階的に改善される検出結果を順に返すiteratorを作成する
+///
+/// [`fit_with_deadline`]と同じ段階を順に計算し，それまでの最良の結果を改善した段階でのみ結果を返す．
+/// 最後の段階は厳密解であるため，最後に返す結果は常に大域的な最適解となる．
+/// GUIやサーバにおいて，厳密解の計算を続けながら途中の結果を利用者へ逐次提示する用途を想定している．
+///
+/// 区間の長さの制約を指定した場合は厳密解の段階のみとなる．
+/// 各段階の計算は[`Iterator::next`]の呼び出し時に行う．計算に失敗した場合は`Err`を返し，以降は`None`を返す．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `method` - 変化点検出の手法（区間の最小の長さを定める．[`Method::Auto`]は[`Method::Dp`]として扱う．）
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+pub fn fit_iter<'a, C>(cost: &'a C, method: Method, penalty: &Penalty, constraints: &Constraints) -> FitIter<'a, C>
+where
+    C: SegmentCost,
+{
+    let first = if constraints.has_size_limits() { Stage::Exact } else { Stage::Binseg };
+    FitIter { cost, method, penalty: *penalty, constraints: *constraints, next: Some(first), best: None }
+}
+
+
+/// 段階的に改善される検出結果のiterator（[`fit_iter`]）
+pub struct FitIter<'a, C> {
+    cost: &'a C,
+    method: Method,
+    penalty: Penalty,
+    constraints: Constraints,
+    next: Option<Stage>,
+    best: Option<(f64, AnytimeResult)>,
+}
+
+impl<C: SegmentCost> FitIter<'_, C> {
+    /// 次に計算する段階．全ての段階を終えた場合は`None`．
+    pub fn next_stage(&self) -> Option<Stage> {
+        self.next
+    }
+
+
+    /// 区間の最小の長さ
+    fn min_size(&self) -> Tau {
+        match self.method {
+            Method::Dp2 => 2,
+            Method::Dp | Method::Auto => 1,
+        }
+    }
+
+
+    /// 検出結果の良さ
+    fn objective(&self, result: &DetectionResult) -> f64 {
+        match self.penalty {
+            Penalty::NumChange(_) => result.value,
+            Penalty::Linear(beta) => result.value - beta * result.num_change() as f64,
+        }
+    }
+
+
+    /// 次の段階を計算し，その時点の最良の結果と，その段階で結果が改善されたかを返す
+    ///
+    /// 全ての段階を終えた場合，または期限を過ぎて計算を中断した場合は`None`を返す．
+    fn step(&mut self, deadline: Option<Instant>) -> Result<Option<(AnytimeResult, bool)>, CalcDpError> {
+        let stage = match self.next {
+            Some(stage) => stage,
+            None => return Ok(None),
+        };
+        let min_size = self.min_size();
+        let band = self.constraints.band(self.cost.t_max())?;
+        let result = match stage {
+            Stage::Binseg => Some(search::binseg(self.cost, min_size, &self.penalty, &self.constraints)?),
+            Stage::Wbs => Some(search::wbs(self.cost, min_size, &self.penalty, &self.constraints, WBS_INTERVALS, WBS_RNG)?),
+            Stage::Exact => match (self.penalty, self.constraints.max_k) {
+                (Penalty::Linear(beta), None) if band.is_none() => search::pelt_until(self.cost, min_size, beta, deadline)?.map(|r| r.result),
+                (Penalty::NumChange(k), _) => FitResult::fit_until(self.cost, self.method, Some(k), band, deadline)?
+                                                  .map(|fit| fit.select(&self.penalty))
+                                                  .transpose()?,
+                (Penalty::Linear(_), max_k) => FitResult::fit_until(self.cost, self.method, max_k, band, deadline)?
+                                                   .map(|fit| fit.select(&self.penalty))
+                                                   .transpose()?,
+            },
+        };
+        let result = match result {
+            Some(result) => result,
+            None => {
+                self.next = None;
+                return Ok(None);
+            },
+        };
+
+        self.next = match stage {
+            Stage::Binseg => Some(Stage::Wbs),
+            Stage::Wbs => Some(Stage::Exact),
+            Stage::Exact => None,
+        };
+        let objective = self.objective(&result);
+        let exact = stage == Stage::Exact;
+        // 厳密解は目的関数の値が等しい場合も採用し，最適であることを示す
+        let improved = match &self.best {
+            Some((best, _)) => objective > *best || exact,
+            None => true,
+        };
+        if improved {
+            self.best = Some((objective, AnytimeResult { result, stage, exact }));
+        }
+        Ok(self.best.as_ref().map(|(_, res)| (res.clone(), improved)))
+    }
+}
+
+impl<C: SegmentCost> Iterator for FitIter<'_, C> {
+    type Item = Result<AnytimeResult, CalcDpError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.step(None) {
+                Ok(Some((res, true))) => return Some(Ok(res)),
+                Ok(Some((_, false))) => continue,
+                Ok(None) => return None,
+                Err(e) => {
+                    self.next = None;
+                    return Some(Err(e));
+                },
+            }
+        }
+    }
+}
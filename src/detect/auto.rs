@@ -0,0 +1,172 @@
+//! 系列とコスト関数の性質に基づく探索手法の自動選択
+
+use super::{FitResult, Method, Penalty, Constraints, DetectionResult};
+use crate::cost::SegmentCost;
+use crate::dp_tools::CalcDpError;
+use crate::search::{self, best_split};
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 動的計画法を選ぶ評価値計算の回数（$ (k_{max} + 1) T^2 $）の上限
+const DP_EVALUATION_BUDGET: f64 = 2.0e8;
+/// 評価値の計算量が区間の長さに依存するコスト関数に対して，厳密な手法を選ぶ系列長の上限
+const EXACT_LENGTH_LIMIT: Tau = 2_000;
+/// 二分割法の結果を局所的に改善する反復の上限
+const MAX_REFINE_ITER: usize = 10;
+
+
+/// 自動選択された探索手法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    /// 動的計画法（[`Method::Dp`]）
+    Dp,
+    /// PELT（[`search::pelt`]）
+    Pelt,
+    /// 二分割法（[`search::binseg`]）の後，各変化点を前後の変化点の間で最適な位置へ移動する
+    BinsegRefine,
+}
+
+
+/// 自動選択した手法とその理由
+///
+/// [`Method::Auto`]による検出結果（[`DetectionResult::selection`]）に含まれる．
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selection {
+    /// 選択した手法
+    pub algorithm: Algorithm,
+    /// 選択の理由
+    pub reason: String,
+    /// 大域的な最適解が保証されるか
+    pub exact: bool,
+}
+
+
+/// 手法を自動選択した変化点検出の結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutoDetection {
+    /// 検出結果．[`DetectionResult::selection`]は本構造体の`algorithm`，`reason`および`exact`と等しい．
+    pub result: DetectionResult,
+    /// 選択した手法
+    pub algorithm: Algorithm,
+    /// 選択の理由
+    pub reason: String,
+    /// 大域的な最適解が保証されるか
+    pub exact: bool,
+}
+
+
+/// 系列長，変化点個数の上限およびコスト関数の性質から探索手法を選ぶ
+///
+/// 以下の順に判定する．
+/// 1. 区間の長さの制約を指定した場合: 制約を厳密に扱える動的計画法（計算量は$ O(K T B) $．[`Constraints`]を参照．）
+/// 2. ペナルティにより変化点個数を決め，個数の上限が無い場合: 評価値を$ O(1) $で計算できるか系列が短ければPELT
+/// 3. 動的計画法の評価値計算の回数$ (k_{max} + 1) T^2 $が上限以内の場合: 動的計画法
+/// 4. それ以外: 二分割法と局所的な改善
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+///
+/// # 返り値
+/// * `(algorithm, reason)` - 選択した手法とその理由
+pub fn choose_algorithm<C: SegmentCost>(cost: &C, penalty: &Penalty, constraints: &Constraints) -> (Algorithm, String) {
+    let t_max = cost.t_max();
+    let constant_time = cost.constant_time();
+    if constraints.has_size_limits() {
+        return (Algorithm::Dp, format!("Segment length bounds are enforced by banded DP (min_size: {:?}, max_size: {:?}, T = {t_max}).", constraints.min_size, constraints.max_size));
+    }
+    if let (Penalty::Linear(_), None) = (penalty, constraints.max_k) {
+        if constant_time || t_max <= EXACT_LENGTH_LIMIT {
+            return (Algorithm::Pelt, format!("Penalized search without bound on K (T = {t_max}, constant-time cost: {constant_time})."));
+        }
+    }
+
+    let k_lim = Method::Dp.max_k(&t_max);
+    let k_max = match penalty {
+        Penalty::NumChange(k) => *k,
+        Penalty::Linear(_) => constraints.max_k.unwrap_or(k_lim),
+    }.min(k_lim);
+    let evaluations = (k_max as f64 + 1.0) * (t_max as f64).powi(2);
+    if evaluations <= DP_EVALUATION_BUDGET && (constant_time || t_max <= EXACT_LENGTH_LIMIT) {
+        (Algorithm::Dp, format!("Exact DP is affordable ((K + 1) T^2 = {evaluations:.3e}, K = {k_max}, T = {t_max})."))
+    } else {
+        (Algorithm::BinsegRefine, format!("Exact search is too expensive ((K + 1) T^2 = {evaluations:.3e}, K = {k_max}, T = {t_max}, constant-time cost: {constant_time})."))
+    }
+}
+
+
+/// 探索手法を自動選択して変化点を検出する
+///
+/// 手法の選択は[`choose_algorithm`]に従い，変化点間の最低間隔は1とする．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+pub fn detect_auto<C: SegmentCost>(cost: &C, penalty: &Penalty, constraints: &Constraints) -> Result<AutoDetection, CalcDpError> {
+    // 動的計画法以外を選ぶ場合も，反映されない制約（観測範囲外のデータ）を検出する
+    constraints.band(cost.t_max())?;
+    let (algorithm, reason) = choose_algorithm(cost, penalty, constraints);
+    let mut result = match (algorithm, penalty) {
+        (Algorithm::Pelt, Penalty::Linear(beta)) => search::pelt(cost, 1, *beta)?.result,
+        (Algorithm::Dp, _) | (Algorithm::Pelt, _) => {
+            let k_max = match penalty {
+                Penalty::NumChange(k) => Some(*k),
+                Penalty::Linear(_) => constraints.max_k,
+            };
+            if let (Penalty::NumChange(k), Some(max_k)) = (penalty, constraints.max_k) {
+                if *k > max_k {
+                    return Err(CalcDpError::new(format!("The number of change point k (= {k}) must not exceed max_k (= {max_k}).")));
+                }
+            }
+            FitResult::fit_constrained(cost, Method::Dp, k_max, constraints)?.select(penalty)?
+        },
+        (Algorithm::BinsegRefine, _) => refine(cost, search::binseg(cost, 1, penalty, constraints)?)?,
+    };
+    let exact = algorithm != Algorithm::BinsegRefine;
+    result.selection = Some(Selection { algorithm, reason: reason.clone(), exact });
+    Ok(AutoDetection {
+        result,
+        algorithm,
+        reason,
+        exact,
+    })
+}
+
+
+/// 各変化点を前後の変化点の間で評価値が最大となる位置へ移動する操作を，変化しなくなるまで繰り返す
+fn refine<C: SegmentCost>(cost: &C, mut result: DetectionResult) -> Result<DetectionResult, CalcDpError> {
+    let k = result.change_points.len();
+    for _ in 0..MAX_REFINE_ITER {
+        let mut moved = false;
+        for i in 0..k {
+            let start = if i == 0 { result.start } else { result.change_points[i - 1] };
+            let end = if i + 1 == k { result.t_max } else { result.change_points[i + 1] };
+            if let Some((t, _)) = best_split(cost, start, end, 1)? {
+                let now = result.change_points[i];
+                let gain = |s: Tau| -> Result<f64, CalcDpError> {
+                    Ok(cost.segment_value(start, s)? + cost.segment_value(s, end)?)
+                };
+                if t != now && gain(t)? > gain(now)? {
+                    result.change_points[i] = t;
+                    moved = true;
+                }
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+    result.value = result.segments()
+                         .into_iter()
+                         .map(|(t_k_1, t_k)| cost.segment_value(t_k_1, t_k))
+                         .sum::<Result<f64, CalcDpError>>()?;
+    Ok(result)
+}
@@ -0,0 +1,176 @@
+//! 系列の端の扱い
+//!
+//! 動的計画法は最初の区間が時点0から，最後の区間が系列の最後の時期まで続くものとして区間を定める．
+//! 観測の開始前または終了後も同じ状態が続いている場合は，端の区間を開いたもの（[`Boundary::Open`]）とするか，
+//! 観測範囲外のデータ（[`Boundary::Context`]）を端の区間の評価値に含める．
+
+use crate::cost::{SegmentCost, SegmentParameter};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 系列の端の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Boundary {
+    /// 端で区間が始まる（終わる）
+    #[default]
+    Closed,
+    /// 端の区間は観測範囲外から続いている可能性がある
+    ///
+    /// 端に接する区間は観測範囲内の長さが短くてもよいため，区間の長さの制約（[`super::Constraints::min_size`]，[`super::Constraints::max_size`]）を課さない．
+    Open,
+    /// 観測範囲外に指定した個数のデータがあり，端の区間の評価値はそれを含めて計算する
+    ///
+    /// コスト関数は観測範囲外のデータを含めた系列に対して作成する（[`WithContext`]を参照）．
+    /// 変化点は観測範囲外には置かない．
+    Context(Tau),
+}
+
+impl Boundary {
+    /// 観測範囲外のデータの個数
+    fn context_len(&self) -> Tau {
+        match self {
+            Boundary::Context(n) => *n,
+            Boundary::Closed | Boundary::Open => 0,
+        }
+    }
+}
+
+
+/// 系列の始端と終端の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Boundaries {
+    /// 始端（時点0）の扱い
+    pub start: Boundary,
+    /// 終端（最後の時期）の扱い
+    pub end: Boundary,
+}
+
+impl Boundaries {
+    /// 観測範囲外のデータを含むか
+    pub fn has_context(&self) -> bool {
+        matches!(self.start, Boundary::Context(_)) || matches!(self.end, Boundary::Context(_))
+    }
+
+
+    /// 観測範囲外のデータを評価値に含めた後の扱い（端は閉じたものとする）
+    pub(super) fn resolved(&self) -> Self {
+        let close = |b: Boundary| match b {
+            Boundary::Context(_) => Boundary::Closed,
+            b => b,
+        };
+        Boundaries { start: close(self.start), end: close(self.end) }
+    }
+}
+
+
+/// 観測範囲外のデータを端の区間の評価値に含めるコスト関数
+///
+/// 元のコスト関数は，始端の前の[`Boundary::Context`]個のデータ，観測範囲のデータ，終端の後のデータを連結した系列に対して作成する．
+/// 本コスト関数の時点は観測範囲の始点を0とし，最初の区間は始端の前のデータを，最後の区間は終端の後のデータを含めて評価する．
+#[derive(Debug)]
+pub struct WithContext<'a, C> {
+    cost: &'a C,
+    pre: Tau,
+    post: Tau,
+}
+
+// 参照のみを保持するため，コスト関数の型によらず複製できる
+impl<C> Clone for WithContext<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for WithContext<'_, C> {}
+
+impl<'a, C: SegmentCost> WithContext<'a, C> {
+    /// 端の扱いを指定してコスト関数を作成する
+    ///
+    /// # 引数
+    /// * `cost` - 観測範囲外のデータを含めた系列に対するコスト関数
+    /// * `boundaries` - 系列の始端と終端の扱い
+    pub fn new(cost: &'a C, boundaries: &Boundaries) -> Result<Self, CalcDpError> {
+        let (pre, post) = (boundaries.start.context_len(), boundaries.end.context_len());
+        let total = cost.t_max();
+        if pre.checked_add(post).is_none_or(|n| n >= total) {
+            return Err(CalcDpError::new(format!("Context before ({pre}) and after ({post}) the observations leave no observation in the series of length {total}.")));
+        }
+        Ok(WithContext { cost, pre, post })
+    }
+
+
+    /// 元のコスト関数
+    pub fn cost(&self) -> &'a C {
+        self.cost
+    }
+
+
+    /// 区間を元の系列の時点に変換する．端に接する区間は観測範囲外のデータまで広げる．
+    fn extend(&self, t_k_1: Tau, t_k: Tau) -> Result<(Tau, Tau), CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let start = if t_k_1 == 0 { 0 } else { t_k_1 + self.pre };
+        let end = if t_k == self.t_max() { self.cost.t_max() } else { t_k + self.pre };
+        Ok((start, end))
+    }
+}
+
+impl<C: SegmentCost> SegmentCost for WithContext<'_, C> {
+    fn t_max(&self) -> Tau {
+        self.cost.t_max() - self.pre - self.post
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let (start, end) = self.extend(t_k_1, t_k)?;
+        self.cost.segment_value(start, end)
+    }
+
+
+    fn segment_values(&self, pairs: &[(Tau, Tau)]) -> Result<Vec<f64>, CalcDpError> {
+        let extended = pairs.iter()
+                            .map(|(t_k_1, t_k)| self.extend(*t_k_1, *t_k))
+                            .collect::<Result<Vec<(Tau, Tau)>, CalcDpError>>()?;
+        self.cost.segment_values(&extended)
+    }
+
+
+    fn constant_time(&self) -> bool {
+        self.cost.constant_time()
+    }
+
+
+    fn n_parameters(&self) -> usize {
+        self.cost.n_parameters()
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        self.cost.min_identifiable_size()
+    }
+
+
+    fn rejects_short_segments(&self) -> bool {
+        self.cost.rejects_short_segments()
+    }
+
+
+    fn pruning_constant(&self) -> f64 {
+        self.cost.pruning_constant()
+    }
+}
+
+impl<C: SegmentParameter> SegmentParameter for WithContext<'_, C> {
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        let (start, end) = self.extend(t_k_1, t_k)?;
+        self.cost.segment_parameter(start, end)
+    }
+}
+
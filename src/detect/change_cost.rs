@@ -0,0 +1,174 @@
+//! 変化点の位置に依存する変化のコスト
+
+use super::{detect_observed, Method, Penalty, Constraints, DetectionResult, WithContext};
+use crate::cost::{SegmentCost, SegmentParameter};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 変化点の位置ごとのコスト（変化のコスト）を課したコスト関数
+///
+/// 時点$ t $に変化点を置くと評価値から$ c(t) $を差し引く．ペナルティ（[`Penalty::Linear`]）が全ての位置に一様な罰則を課すのに対し，
+/// 監査中等の変化が起こりにくい時期に大きなコストを与えることで，領域知識を変化点の位置に反映できる．
+/// $ c(t) = +\infty $の時点には変化点を置かず，負の値はその時点の変化点を優遇する．
+///
+/// 各変化点はちょうど1個の区間の始点となるため，変化のコストは区間$ (t_{k-1}, t_k] $（$ t_{k-1} > 0 $）の評価値から差し引く．
+pub struct ChangeCost<'a, C, F> {
+    cost: &'a C,
+    cost_of_change: F,
+}
+
+impl<'a, C: SegmentCost, F: Fn(Tau) -> f64 + Sync> ChangeCost<'a, C, F> {
+    /// 変化のコストを課したコスト関数を作成する
+    ///
+    /// # 引数
+    /// * `cost` - 元のコスト関数
+    /// * `cost_of_change` - 時点$ t $に変化点を置くコスト$ c(t) $
+    pub fn new(cost: &'a C, cost_of_change: F) -> Self {
+        ChangeCost { cost, cost_of_change }
+    }
+
+
+    /// 元のコスト関数
+    pub fn cost(&self) -> &'a C {
+        self.cost
+    }
+
+
+    /// 区間の始点に置いた変化点のコスト
+    fn charge(&self, t_k_1: Tau) -> f64 {
+        if t_k_1 == 0 { 0.0 } else { (self.cost_of_change)(t_k_1) }
+    }
+}
+
+impl<C: SegmentCost, F: Fn(Tau) -> f64 + Sync> SegmentCost for ChangeCost<'_, C, F> {
+    fn t_max(&self) -> Tau {
+        self.cost.t_max()
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        Ok(self.cost.segment_value(t_k_1, t_k)? - self.charge(t_k_1))
+    }
+
+
+    fn segment_values(&self, pairs: &[(Tau, Tau)]) -> Result<Vec<f64>, CalcDpError> {
+        let mut values = self.cost.segment_values(pairs)?;
+        for ((t_k_1, _), val) in pairs.iter().zip(values.iter_mut()) {
+            *val -= self.charge(*t_k_1);
+        }
+        Ok(values)
+    }
+
+
+    fn constant_time(&self) -> bool {
+        self.cost.constant_time()
+    }
+
+
+    fn n_parameters(&self) -> usize {
+        self.cost.n_parameters()
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        self.cost.min_identifiable_size()
+    }
+
+
+    fn rejects_short_segments(&self) -> bool {
+        self.cost.rejects_short_segments()
+    }
+
+
+    /// 分割により変化のコスト$ c(t) $が加わるため，元のコスト関数の定数から$ \max_t c(t) $（負の場合は0）を差し引く
+    fn pruning_constant(&self) -> f64 {
+        let max_charge = (1..self.t_max()).map(|t| self.charge(t))
+                                          .fold(0.0, f64::max);
+        self.cost.pruning_constant() - max_charge
+    }
+}
+
+impl<C: SegmentParameter, F: Fn(Tau) -> f64 + Sync> SegmentParameter for ChangeCost<'_, C, F> {
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        self.cost.segment_parameter(t_k_1, t_k)
+    }
+}
+
+
+/// 変化点の位置ごとのコストを課して変化点を検出する
+///
+/// 変化点の選択には変化のコストを差し引いた評価値（[`ChangeCost`]）を用いる．
+/// 検出結果の評価値は他の検出結果と比較できるよう，変化のコストを含まない各区間の評価値の総和とする．
+/// 観測範囲外のデータ（[`super::Boundary::Context`]）を与えた場合，時点は観測範囲の始点を0として`cost_of_change`に渡す．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `method` - 変化点検出の手法
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+/// * `cost_of_change` - 時点$ t $に変化点を置くコスト$ c(t) $
+pub fn detect_with_change_cost<C, F>(cost: &C, method: Method, penalty: &Penalty, constraints: &Constraints, cost_of_change: F) -> Result<DetectionResult, CalcDpError>
+where
+    C: SegmentCost,
+    F: Fn(Tau) -> f64 + Sync,
+{
+    if constraints.boundary.has_context() {
+        let observed = WithContext::new(cost, &constraints.boundary)?;
+        let resolved = Constraints { boundary: constraints.boundary.resolved(), ..*constraints };
+        return detect_charged(&observed, method, penalty, &resolved, cost_of_change);
+    }
+    detect_charged(cost, method, penalty, constraints, cost_of_change)
+}
+
+
+/// 観測範囲外のデータを反映した後のコスト関数に対する変化点検出（[`detect_with_change_cost`]）
+fn detect_charged<C, F>(cost: &C, method: Method, penalty: &Penalty, constraints: &Constraints, cost_of_change: F) -> Result<DetectionResult, CalcDpError>
+where
+    C: SegmentCost,
+    F: Fn(Tau) -> f64 + Sync,
+{
+    let mut result = detect_observed(&ChangeCost::new(cost, cost_of_change), method, penalty, constraints)?;
+    result.value = result.segments()
+                         .into_iter()
+                         .map(|(t_k_1, t_k)| cost.segment_value(t_k_1, t_k))
+                         .sum::<Result<f64, CalcDpError>>()?;
+    Ok(result)
+}
+
+
+/// 変化点の位置の事前分布を与えて変化点を検出する
+///
+/// 時点$ t $に変化点が生じる相対的な起こりやすさ$ w_t $を与え，変化のコストを$ c(t) = -\log(w_t / \bar{w}) $として
+/// [`detect_with_change_cost`]を適用する．$ \bar{w} $は変化点を置ける時点$ 1, \dots, T-1 $における$ w_t $の平均であり，
+/// 一様な$ w_t $では[`super::detect`]と同じ結果となる．
+/// $ w_t = 0 $の時点には変化点を置かない．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `method` - 変化点検出の手法
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+/// * `prior` - 時点ごとの変化点の起こりやすさ（長さ$ T $の非負の値．添字0の値は用いない）
+pub fn detect_with_prior<C: SegmentCost>(cost: &C, method: Method, penalty: &Penalty, constraints: &Constraints, prior: &[f64]) -> Result<DetectionResult, CalcDpError> {
+    let observed = if constraints.boundary.has_context() {
+        WithContext::new(cost, &constraints.boundary)?.t_max()
+    } else {
+        cost.t_max()
+    };
+    if prior.len() != observed as usize {
+        return Err(CalcDpError::new(format!("Prior has {} weights, but the series has {observed} observed points.", prior.len())));
+    }
+    if let Some(w) = prior.iter().find(|w| !(w.is_finite() && **w >= 0.0)) {
+        return Err(CalcDpError::new(format!("Prior weights must be finite and non-negative, but {w} is given.")));
+    }
+    let candidates = prior.get(1..).unwrap_or(&[]);
+    let mean = candidates.iter().sum::<f64>() / candidates.len().max(1) as f64;
+    if candidates.is_empty() || mean == 0.0 {
+        return Err(CalcDpError::new("Prior must give a positive weight to at least one candidate change point."));
+    }
+    let log_costs = prior.iter().map(|w| -(w / mean).ln()).collect::<Vec<f64>>();
+    detect_with_change_cost(cost, method, penalty, constraints, |t| log_costs[t as usize])
+}
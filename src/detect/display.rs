@@ -0,0 +1,59 @@
+//! evcxr（Rustのノートブック環境）における検出結果の表示
+
+use super::DetectionResult;
+
+use std::fmt::Write;
+
+
+/// SVGの幅（ピクセル）
+const SVG_WIDTH: f64 = 600.0;
+/// SVGの高さ（ピクセル）
+const SVG_HEIGHT: f64 = 48.0;
+/// 区間の塗り分けに用いる色
+const COLORS: [&str; 2] = ["#9ecae1", "#fdae6b"];
+
+
+impl DetectionResult {
+    /// 区間の一覧表と区間分割の図をHTML形式の文字列として出力
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(html, "<div><p>{} change points, value = {}</p>", self.num_change(), self.value);
+        html.push_str(&self.to_svg());
+        html.push_str("<table><thead><tr><th>segment</th><th>start</th><th>end</th><th>length</th></tr></thead><tbody>");
+        for (i, (t_k_1, t_k)) in self.segments().into_iter().enumerate() {
+            let _ = write!(html, "<tr><td>{i}</td><td>{t_k_1}</td><td>{t_k}</td><td>{}</td></tr>", t_k - t_k_1);
+        }
+        html.push_str("</tbody></table></div>");
+        html
+    }
+
+
+    /// 区間分割を時間軸上に塗り分けた図をSVG形式の文字列として出力
+    pub fn to_svg(&self) -> String {
+        let span = (self.t_max - self.start).max(1) as f64;
+        let x = |t: f64| (t - self.start as f64) / span * SVG_WIDTH;
+        let bar = SVG_HEIGHT - 16.0;
+
+        let mut svg = format!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="{SVG_WIDTH}" height="{SVG_HEIGHT}" font-size="10">"#);
+        for (i, (t_k_1, t_k)) in self.segments().into_iter().enumerate() {
+            let (x0, x1) = (x(t_k_1 as f64), x(t_k as f64));
+            let _ = write!(svg, r#"<rect x="{x0:.1}" y="0" width="{:.1}" height="{bar}" fill="{}"><title>({t_k_1}, {t_k}]</title></rect>"#,
+                           x1 - x0, COLORS[i % COLORS.len()]);
+        }
+        for t in self.change_points.iter() {
+            let xt = x(*t as f64);
+            let _ = write!(svg, r#"<line x1="{xt:.1}" y1="0" x2="{xt:.1}" y2="{bar}" stroke="black"/>"#);
+            let _ = write!(svg, r#"<text x="{xt:.1}" y="{}" text-anchor="middle">{t}</text>"#, SVG_HEIGHT - 4.0);
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+
+    /// evcxrのノートブックにおける表示
+    ///
+    /// evcxrは本メソッドを自動的に呼び出し，[`DetectionResult::to_html`]の出力を表示する．
+    pub fn evcxr_display(&self) {
+        println!("EVCXR_BEGIN_CONTENT text/html\n{}\nEVCXR_END_CONTENT", self.to_html());
+    }
+}
@@ -0,0 +1,177 @@
+//! 計算結果を保持し，条件を変えた問い合わせに繰り返し応答するセッション
+
+use super::{FitResult, Method, Penalty, Constraints, DetectionResult, Memo, Window};
+use super::window::resolve_range_with;
+use crate::cost::SegmentCost;
+use crate::dp_tools::{CalcDpError, QueryPolicy};
+
+use std::collections::HashMap;
+use std::ops::RangeBounds;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+
+/// 範囲（始点, 終点）ごとに保持する，計算した変化点個数の上限と動的計画法のメモ
+type FitCache = HashMap<(Tau, Tau), (NumChg, Arc<Memo>)>;
+
+
+/// データと計算結果を保持し，変化点個数，ペナルティ，範囲および制約を変えた問い合わせに応答するセッション
+///
+/// ノートブックやGUIに組み込み，利用者の操作に応じて繰り返し結果を求める用途を想定している．
+/// コスト関数（データと累積和等の統計量）はセッションが所有し，範囲ごとの動的計画法の計算結果を保持する．
+///
+/// # 計算量
+/// 範囲の長さを$ W $，変化点個数の上限を$ k_{max} $とする．
+/// * [`Session::segment_value`]: 累積和に基づくコスト関数では$ O(1) $
+/// * 初めて問い合わせる範囲，またはより大きな$ k_{max} $を要する問い合わせ: 動的計画法の$ O(k_{max} W^2) $
+/// * 計算済みの範囲に対する問い合わせ: 変化点個数の選択と変化点の復元のみで$ O(k_{max}) $
+///
+/// 保持する計算結果は範囲ごとに$ O(k_{max} W) $のメモリを要するため，不要になった場合は[`Session::clear_cache`]で解放する．
+///
+/// # 範囲外の問い合わせ
+/// 既定では系列を超える範囲の問い合わせはErrorとなる．
+/// [`Session::with_query_policy`]で[`QueryPolicy::ClampToRange`]を指定すると，範囲を系列内に収めて応答する．
+pub struct Session<C> {
+    cost: C,
+    method: Method,
+    policy: QueryPolicy,
+    fits: Mutex<FitCache>,
+}
+
+impl<C: SegmentCost> Session<C> {
+    /// セッションを作成
+    ///
+    /// # 引数
+    /// * `cost` - 系列全体のコスト関数
+    /// * `method` - 変化点検出の手法
+    pub fn new(cost: C, method: Method) -> Self {
+        Session {
+            cost,
+            method,
+            policy: QueryPolicy::Strict,
+            fits: Mutex::new(HashMap::new()),
+        }
+    }
+
+
+    /// 系列全体のコスト関数
+    pub fn cost(&self) -> &C {
+        &self.cost
+    }
+
+
+    /// 変化点検出の手法
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+
+    /// 系列を超える範囲の問い合わせの扱いを指定する
+    ///
+    /// # 引数
+    /// * `policy` - 範囲外の問い合わせの扱い
+    pub fn with_query_policy(self, policy: QueryPolicy) -> Self {
+        Session { policy, ..self }
+    }
+
+
+    /// 系列を超える範囲の問い合わせの扱い
+    pub fn query_policy(&self) -> QueryPolicy {
+        self.policy
+    }
+
+
+    /// 系列の長さ
+    pub fn t_max(&self) -> Tau {
+        self.cost.t_max()
+    }
+
+
+    /// 範囲の評価値
+    ///
+    /// # 引数
+    /// * `range` - 対象とする範囲（データのインデックス）
+    pub fn segment_value<R: RangeBounds<Tau>>(&self, range: R) -> Result<f64, CalcDpError> {
+        let (start, end) = resolve_range_with(&range, self.t_max(), self.policy)?;
+        self.cost.segment_value(start, end)
+    }
+
+
+    /// 系列全体に対して変化点を検出する
+    ///
+    /// # 引数
+    /// * `penalty` - 変化点個数の決め方
+    /// * `constraints` - 変化点検出における制約
+    pub fn detect(&self, penalty: &Penalty, constraints: &Constraints) -> Result<DetectionResult, CalcDpError> {
+        self.detect_window(.., penalty, constraints)
+    }
+
+
+    /// 範囲を指定して変化点を検出する
+    ///
+    /// 検出結果は元の系列の時点で表される．
+    ///
+    /// # 引数
+    /// * `range` - 対象とする範囲（データのインデックス）
+    /// * `penalty` - 変化点個数の決め方
+    /// * `constraints` - 変化点検出における制約
+    pub fn detect_window<R: RangeBounds<Tau>>(&self, range: R, penalty: &Penalty, constraints: &Constraints) -> Result<DetectionResult, CalcDpError> {
+        let (start, end) = resolve_range_with(&range, self.t_max(), self.policy)?;
+        let window = Window::new(&self.cost, start..end)?;
+        let k_lim = self.method.max_k(&window.t_max());
+        let k_max = match penalty {
+            Penalty::NumChange(k) => match constraints.max_k {
+                Some(max_k) if *k > max_k => return Err(CalcDpError::new(format!("The number of change point k (= {k}) must not exceed max_k (= {max_k})."))),
+                _ => *k,
+            },
+            Penalty::Linear(_) => constraints.max_k.unwrap_or(k_lim),
+        }.min(k_lim);
+
+        let memo = self.memo(&window, k_max)?;
+        let fit = FitResult {
+            cost: &window,
+            method: self.method,
+            k_max,
+            memo,
+            stats: Arc::default(),
+        };
+        Ok(window.to_original(fit.select(penalty)?))
+    }
+
+
+    /// 計算結果を保持している範囲
+    pub fn cached_windows(&self) -> Vec<(Tau, Tau)> {
+        let mut windows = self.lock().keys().copied().collect::<Vec<(Tau, Tau)>>();
+        windows.sort_unstable();
+        windows
+    }
+
+
+    /// 保持している計算結果を全て破棄する
+    pub fn clear_cache(&self) {
+        self.lock().clear();
+    }
+
+
+    /// 範囲に対する動的計画法のメモを取得し，未計算であれば計算して保持する
+    fn memo(&self, window: &Window<'_, C>, k_max: NumChg) -> Result<Arc<Memo>, CalcDpError> {
+        let key = (window.start(), window.end());
+        if let Some((cached_k, memo)) = self.lock().get(&key) {
+            if *cached_k >= k_max {
+                return Ok(Arc::clone(memo));
+            }
+        }
+        // 計算中はロックを保持しない
+        let fit = FitResult::fit(window, self.method, Some(k_max))?;
+        self.lock().insert(key, (fit.k_max, Arc::clone(&fit.memo)));
+        Ok(fit.memo)
+    }
+
+
+    fn lock(&self) -> MutexGuard<'_, FitCache> {
+        // 保持しているのは計算済みの結果のみであり，パニック後も整合性は保たれる
+        self.fits.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,99 @@
+//! 既知の変化点を固定した変化点検出
+
+use super::{FitResult, Method, DetectionResult, Window};
+use crate::cost::SegmentCost;
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+extern crate rayon;
+use rayon::prelude::*;
+
+
+/// 既知の変化点を固定し，残りの変化点を最適に配置する
+///
+/// 既知の変化点で区切った各区間に対して独立に動的計画法を並列に計算し，
+/// 追加する変化点`extra_k`個の各区間への割り当てを，評価値の総和が最大となるよう決める．
+/// 検出結果の変化点は既知の変化点と追加した変化点を合わせた昇順の列であり，評価値は系列全体の評価値の総和である．
+///
+/// # 引数
+/// * `cost` - 系列全体のコスト関数
+/// * `method` - 変化点検出の手法
+/// * `known` - 既知の変化点（狭義単調増加かつ$ (0, T) $の範囲内）
+/// * `extra_k` - 追加する変化点の個数
+pub fn detect_with_known<C: SegmentCost>(cost: &C, method: Method, known: &[Tau], extra_k: NumChg) -> Result<DetectionResult, CalcDpError> {
+    let t_max = cost.t_max();
+    if let Some(t) = known.iter().find(|t| **t == 0 || **t >= t_max) {
+        return Err(CalcDpError::new(format!("Known change point {t} is out of range (0, {t_max}).")));
+    }
+    if known.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(CalcDpError::new(format!("Known change points must be strictly increasing, but {known:?} is given.")));
+    }
+
+    let bounds = std::iter::once(0).chain(known.iter().copied())
+                                   .chain(std::iter::once(t_max))
+                                   .collect::<Vec<Tau>>();
+    let windows = bounds.windows(2)
+                        .map(|w| Window::new(cost, w[0]..w[1]))
+                        .collect::<Result<Vec<_>, CalcDpError>>()?;
+    let fits = windows.par_iter()
+                      .map(|window| {
+                          let fit = FitResult::fit(window, method, Some(extra_k))?;
+                          let values = fit.values_by_k()?;
+                          Ok((fit, values))
+                      })
+                      .collect::<Result<Vec<_>, CalcDpError>>()?;
+
+    let allocation = allocate(&fits.iter().map(|(_, values)| values.as_slice()).collect::<Vec<_>>(), extra_k)
+        .ok_or_else(|| CalcDpError::new(format!("No segmentation adds {extra_k} change points between the known change points {known:?}.")))?;
+
+    let mut change_points = Vec::with_capacity(known.len() + extra_k as usize);
+    let mut value = 0.0;
+    for ((window, (fit, _)), k) in windows.iter().zip(&fits).zip(allocation) {
+        let res = window.to_original(fit.result(k)?);
+        if window.start() > 0 {
+            change_points.push(window.start());
+        }
+        change_points.extend(res.change_points);
+        value += res.value;
+    }
+    Ok(DetectionResult::new(change_points, value, t_max))
+}
+
+
+/// 変化点個数ごとの各区間の評価値から，総和が最大となる変化点個数の割り当てを求める
+///
+/// # 引数
+/// * `values` - 区間ごとの，変化点個数$ k $の評価値（添字が$ k $）
+/// * `total` - 割り当てる変化点個数の合計
+///
+/// # 返り値
+/// * `allocation` - 区間ごとの変化点個数．合計が`total`となる有限の評価値の割り当てが無い場合は`None`．
+fn allocate(values: &[&[f64]], total: NumChg) -> Option<Vec<NumChg>> {
+    let total = total as usize;
+    // best[i][j]: 最初のi区間に変化点をj個割り当てた場合の評価値の総和の最大値と，i番目の区間への割り当て
+    let mut best = vec![vec![(f64::NEG_INFINITY, 0); total + 1]; values.len() + 1];
+    best[0][0].0 = 0.0;
+    for (i, vals) in values.iter().enumerate() {
+        for j in 0..=total {
+            for (k, v) in vals.iter().enumerate().take(j + 1) {
+                let cand = best[i][j - k].0 + v;
+                if cand > best[i + 1][j].0 {
+                    best[i + 1][j] = (cand, k);
+                }
+            }
+        }
+    }
+    if !best[values.len()][total].0.is_finite() {
+        return None;
+    }
+    let mut allocation = vec![0; values.len()];
+    let mut j = total;
+    for i in (0..values.len()).rev() {
+        let k = best[i + 1][j].1;
+        allocation[i] = k as NumChg;
+        j -= k;
+    }
+    Some(allocation)
+}
@@ -0,0 +1,116 @@
+//! 動的計画法の計算量の記録
+
+use std::cell::Cell;
+use std::time::Duration;
+
+extern crate process_param;
+use process_param::NumChg;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 変化点個数ごとの計算量
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KStats {
+    /// 変化点個数
+    pub k: NumChg,
+    /// この変化点個数の計算に要した時間
+    pub elapsed: Duration,
+    /// 新たに計算したメモの要素数（再帰的に計算した$ k $未満の要素を含む）
+    pub cells: usize,
+    /// 評価値を計算した区間の数
+    pub segments: usize,
+    /// 一つ前の変化点の候補としてメモを参照した回数
+    pub lookups: usize,
+    /// 参照したメモの要素が計算済みであった回数
+    pub hits: usize,
+}
+
+impl KStats {
+    /// メモを参照した際に計算済みであった割合．参照しなかった場合は`None`．
+    pub fn hit_rate(&self) -> Option<f64> {
+        (self.lookups > 0).then(|| self.hits as f64 / self.lookups as f64)
+    }
+}
+
+
+/// 動的計画法の計算量の記録（[`super::FitResult::compute_stats`]）
+///
+/// 性能に関する問題の報告や，制約（[`super::Constraints`]）による計算量の変化の確認に用いる．
+/// 計算済みのメモを再利用して作成した結果では空となる．
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ComputeStats {
+    /// 変化点個数の昇順に並べた計算量
+    pub per_k: Vec<KStats>,
+}
+
+impl ComputeStats {
+    /// 計算に要した時間の合計
+    pub fn elapsed(&self) -> Duration {
+        self.per_k.iter().map(|s| s.elapsed).sum()
+    }
+
+
+    /// 計算したメモの要素数の合計
+    pub fn cells(&self) -> usize {
+        self.per_k.iter().map(|s| s.cells).sum()
+    }
+
+
+    /// 評価値を計算した区間の数の合計
+    pub fn segments(&self) -> usize {
+        self.per_k.iter().map(|s| s.segments).sum()
+    }
+
+
+    /// 全体でメモを参照した際に計算済みであった割合．参照しなかった場合は`None`．
+    pub fn hit_rate(&self) -> Option<f64> {
+        let lookups = self.per_k.iter().map(|s| s.lookups).sum::<usize>();
+        let hits = self.per_k.iter().map(|s| s.hits).sum::<usize>();
+        (lookups > 0).then(|| hits as f64 / lookups as f64)
+    }
+}
+
+
+/// 1個の変化点個数の計算における計数
+#[derive(Default)]
+pub(super) struct Counter {
+    cells: Cell<usize>,
+    segments: Cell<usize>,
+    lookups: Cell<usize>,
+}
+
+impl Counter {
+    /// 新たに計算した要素を記録する
+    ///
+    /// # 引数
+    /// * `lookups` - 参照した一つ前の変化点の候補の数
+    /// * `segments` - 評価値を計算した区間の数
+    pub(super) fn record(&self, lookups: usize, segments: usize) {
+        self.cells.set(self.cells.get() + 1);
+        self.lookups.set(self.lookups.get() + lookups);
+        self.segments.set(self.segments.get() + segments);
+    }
+
+
+    /// 計数を変化点個数ごとの計算量とする
+    ///
+    /// 最初に計算した要素（期数$ T $，変化点個数$ k $）以外は，参照時にメモに無かったため計算した要素である．
+    ///
+    /// # 引数
+    /// * `k` - 変化点個数
+    /// * `elapsed` - 計算に要した時間
+    pub(super) fn finish(self, k: NumChg, elapsed: Duration) -> KStats {
+        let cells = self.cells.get();
+        let lookups = self.lookups.get();
+        KStats {
+            k,
+            elapsed,
+            cells,
+            segments: self.segments.get(),
+            lookups,
+            hits: lookups.saturating_sub(cells.saturating_sub(1)),
+        }
+    }
+}
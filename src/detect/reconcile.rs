@@ -0,0 +1,93 @@
+//! 定期的な再解析における変化点の安定化
+
+use super::DetectionResult;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 前回と今回の検出結果を照合した結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reconciliation {
+    /// 照合後の検出結果．対応する変化点は前回の時点に置き換えている．
+    pub result: DetectionResult,
+    /// 前回の時点を維持した変化点（前回の時点, 今回の時点）
+    pub kept: Vec<(Tau, Tau)>,
+    /// 今回新たに検出した変化点
+    pub added: Vec<Tau>,
+    /// 今回の結果で対応する変化点が無く，取り下げた前回の変化点
+    pub withdrawn: Vec<Tau>,
+}
+
+impl Reconciliation {
+    /// 前回から報告する変化点が変わったか
+    pub fn is_changed(&self) -> bool {
+        !self.added.is_empty() || !self.withdrawn.is_empty()
+    }
+}
+
+
+/// 前回の検出結果と照合し，前回報告した変化点の時点を維持する
+///
+/// 系列が伸びるたびに再解析すると，同じ変化に対する変化点が1時点ずつ前後に揺れ，警報が繰り返されることがある．
+/// 本関数は今回の変化点と前回の変化点を距離の近い組から順に1対1で対応付け，距離が`tolerance`以下であれば前回の時点を採用する．
+/// 距離が`tolerance`を超える（今回の結果が前回と強く矛盾する）場合のみ，前回の変化点を取り下げて今回の変化点を採用する．
+///
+/// 照合後の評価値（`value`）は今回の結果の値のままとする．維持した変化点における評価値が必要な場合は，
+/// コスト関数により再計算すること（例: [`super::FitResult::score_hypothesis`]）．
+///
+/// # 引数
+/// * `previous` - 前回の検出結果
+/// * `new` - 今回の検出結果
+/// * `tolerance` - 同じ変化とみなす変化点の時点の差の上限
+pub fn reconcile(previous: &DetectionResult, new: &DetectionResult, tolerance: Tau) -> Reconciliation {
+    // 今回の解析範囲外となった前回の変化点は照合しない
+    let prev = previous.change_points.iter()
+                                     .copied()
+                                     .filter(|t| *t > new.start && *t < new.t_max)
+                                     .collect::<Vec<Tau>>();
+    let mut pairs = prev.iter()
+                        .enumerate()
+                        .flat_map(|(i, p)| new.change_points.iter()
+                                                            .enumerate()
+                                                            .map(move |(j, n)| (p.abs_diff(*n), i, j)))
+                        .filter(|(d, _, _)| *d <= tolerance)
+                        .collect::<Vec<(Tau, usize, usize)>>();
+    pairs.sort();
+
+    let mut prev_match = vec![None; prev.len()];
+    let mut new_matched = vec![false; new.change_points.len()];
+    for (_, i, j) in pairs {
+        if prev_match[i].is_none() && !new_matched[j] {
+            prev_match[i] = Some(j);
+            new_matched[j] = true;
+        }
+    }
+
+    let kept = prev.iter()
+                   .zip(&prev_match)
+                   .filter_map(|(p, m)| m.map(|j| (*p, new.change_points[j])))
+                   .collect::<Vec<(Tau, Tau)>>();
+    let added = new.change_points.iter()
+                                 .zip(&new_matched)
+                                 .filter(|(_, matched)| !**matched)
+                                 .map(|(n, _)| *n)
+                                 .collect::<Vec<Tau>>();
+    let withdrawn = previous.change_points.iter()
+                                          .copied()
+                                          .filter(|p| !kept.iter().any(|(k, _)| k == p))
+                                          .collect::<Vec<Tau>>();
+
+    let mut change_points = kept.iter().map(|(p, _)| *p).chain(added.iter().copied()).collect::<Vec<Tau>>();
+    change_points.sort();
+    change_points.dedup();
+    Reconciliation {
+        result: DetectionResult { change_points, ..new.clone() },
+        kept,
+        added,
+        withdrawn,
+    }
+}
@@ -0,0 +1,56 @@
+//! 検出結果の文字による描画
+
+use super::DetectionResult;
+use crate::dp_tools::CalcDpError;
+
+
+/// 低い順に並べたスパークラインの文字
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+
+impl DetectionResult {
+    /// 系列をスパークラインとして描画し，その下の行に変化点の位置を示す
+    ///
+    /// 系列は幅`width`の列に区切り，列ごとの平均を8段階の文字で表す．
+    /// 変化点を含む列には2行目に`^`を表示する．
+    /// 端末やCIのログ等，描画環境が無い場所で検出結果を簡単に確認する用途を想定している．
+    ///
+    /// # 引数
+    /// * `data` - 検出に用いた系列（系列全体）
+    /// * `width` - 描画する文字数．範囲の長さより大きい場合は範囲の長さとなる．
+    pub fn render_ascii(&self, data: &[f64], width: usize) -> Result<String, CalcDpError> {
+        let (start, end) = (self.start as usize, self.t_max as usize);
+        if end > data.len() {
+            return Err(CalcDpError::new(format!("Length of data (= {}) is shorter than t_max of the result (= {end}).", data.len())));
+        }
+        if width == 0 {
+            return Err(CalcDpError::new("width must be at least 1."));
+        }
+        let values = &data[start..end];
+        let width = width.min(values.len());
+        // 列jは範囲内のインデックス[j * len / width, (j + 1) * len / width)を表す
+        let column_of = |i: usize| i * width / values.len();
+
+        let means = (0..width).map(|j| {
+                                  let lo = j * values.len() / width;
+                                  let hi = (j + 1) * values.len() / width;
+                                  values[lo..hi].iter().sum::<f64>() / (hi - lo) as f64
+                              })
+                              .collect::<Vec<f64>>();
+        let (min, max) = means.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), m| (lo.min(*m), hi.max(*m)));
+        let spark = means.iter()
+                         .map(|m| {
+                             let level = if max > min { (m - min) / (max - min) } else { 0.5 };
+                             BLOCKS[((level * BLOCKS.len() as f64) as usize).min(BLOCKS.len() - 1)]
+                         })
+                         .collect::<String>();
+
+        let mut marks = vec![' '; width];
+        for t in self.change_points.iter() {
+            // 変化点t_kの直後の観測（インデックスt_k）から新しい区間となる
+            marks[column_of(*t as usize - start)] = '^';
+        }
+        let marks = marks.into_iter().collect::<String>();
+        Ok(format!("{spark}\n{}", marks.trim_end()))
+    }
+}
@@ -0,0 +1,162 @@
+//! 系列全体の統計量を再利用した範囲ごとの変化点検出
+
+use super::{FitResult, ComputeStats, Method, Penalty, DetectionResult, Memo, Window};
+use crate::cost::SegmentCost;
+use crate::dp_tools::CalcDpError;
+
+use std::ops::RangeBounds;
+use std::sync::Arc;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+
+/// 1つの系列に対して，範囲を変えながら繰り返し変化点検出を行うためのセッション
+///
+/// コスト関数が系列全体について計算した累積和等の統計量を全ての範囲で共有するため，
+/// 範囲ごとの計算は範囲の長さ$ W $のみに依存する（動的計画法の$ O(k_{max} W^2) $）．
+/// GUI等で系列の一部を拡大して解析し直す用途を想定している．
+#[derive(Debug)]
+pub struct FitSession<'a, C> {
+    cost: &'a C,
+    method: Method,
+}
+
+impl<C> Clone for FitSession<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for FitSession<'_, C> {}
+
+impl<'a, C: SegmentCost> FitSession<'a, C> {
+    /// セッションを作成
+    ///
+    /// # 引数
+    /// * `cost` - 系列全体のコスト関数
+    /// * `method` - 変化点検出の手法
+    pub fn new(cost: &'a C, method: Method) -> Self {
+        FitSession { cost, method }
+    }
+
+
+    /// 系列全体のコスト関数
+    pub fn cost(&self) -> &'a C {
+        self.cost
+    }
+
+
+    /// 変化点検出の手法
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+
+    /// 範囲を指定して動的計画法を計算する
+    ///
+    /// 範囲はデータのインデックスで指定する（[`Window::new`]を参照）．
+    ///
+    /// # 引数
+    /// * `range` - 対象とする範囲
+    /// * `k_max` - 変化点個数の上限．`None`の場合は手法における上限となる．
+    pub fn analyze_window<R: RangeBounds<Tau>>(&self, range: R, k_max: Option<NumChg>) -> Result<WindowFit<'a, C>, CalcDpError> {
+        let window = Window::new(self.cost, range)?;
+        let (k_max, memo, stats) = {
+            let fit = FitResult::fit(&window, self.method, k_max)?;
+            (fit.k_max, fit.memo, fit.stats)
+        };
+        Ok(WindowFit {
+            window,
+            method: self.method,
+            k_max,
+            memo,
+            stats,
+        })
+    }
+}
+
+
+/// 範囲に対する動的計画法の計算結果
+///
+/// 変化点個数を変えて結果を取り出す場合も再計算は不要である．
+/// 結果の変化点は元の系列の時点で表される．
+#[derive(Debug)]
+pub struct WindowFit<'a, C> {
+    window: Window<'a, C>,
+    method: Method,
+    k_max: NumChg,
+    memo: Arc<Memo>,
+    stats: Arc<ComputeStats>,
+}
+
+// 計算結果のメモは複製せず共有する
+impl<C> Clone for WindowFit<'_, C> {
+    fn clone(&self) -> Self {
+        WindowFit {
+            window: self.window,
+            method: self.method,
+            k_max: self.k_max,
+            memo: Arc::clone(&self.memo),
+            stats: Arc::clone(&self.stats),
+        }
+    }
+}
+
+impl<'a, C: SegmentCost> WindowFit<'a, C> {
+    /// 対象とした範囲
+    pub fn window(&self) -> &Window<'a, C> {
+        &self.window
+    }
+
+
+    /// 計算した変化点個数の上限
+    pub fn k_max(&self) -> NumChg {
+        self.k_max
+    }
+
+
+    /// 変化点個数ごとの計算量（[`FitResult::compute_stats`]）
+    pub fn compute_stats(&self) -> &ComputeStats {
+        &self.stats
+    }
+
+
+    /// 変化点個数$ k $における評価値の最大値
+    ///
+    /// # 引数
+    /// * `k` - 変化点個数
+    pub fn value(&self, k: NumChg) -> Result<f64, CalcDpError> {
+        self.fit().value(k)
+    }
+
+
+    /// 変化点個数$ k $における検出結果
+    ///
+    /// # 引数
+    /// * `k` - 変化点個数
+    pub fn result(&self, k: NumChg) -> Result<DetectionResult, CalcDpError> {
+        Ok(self.window.to_original(self.fit().result(k)?))
+    }
+
+
+    /// 変化点個数の決め方に従って検出結果を選択
+    ///
+    /// # 引数
+    /// * `penalty` - 変化点個数の決め方
+    pub fn select(&self, penalty: &Penalty) -> Result<DetectionResult, CalcDpError> {
+        Ok(self.window.to_original(self.fit().select(penalty)?))
+    }
+
+
+    /// 範囲内の時点で表される計算結果
+    fn fit(&self) -> FitResult<'_, Window<'a, C>> {
+        FitResult {
+            cost: &self.window,
+            method: self.method,
+            k_max: self.k_max,
+            memo: Arc::clone(&self.memo),
+            stats: Arc::clone(&self.stats),
+        }
+    }
+}
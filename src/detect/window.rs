@@ -0,0 +1,192 @@
+//! 系列の一部の範囲に対する変化点検出
+
+use super::{detect, Method, Penalty, Constraints, DetectionResult};
+use crate::cost::{SegmentCost, SegmentParameter};
+use crate::dp_tools::{CalcDpError, QueryPolicy};
+
+use std::ops::{Bound, RangeBounds};
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// コスト関数の一部の範囲
+///
+/// 範囲の始点を0とする時点で元のコスト関数を評価する．
+/// 元のコスト関数が保持する累積和等をそのまま用いるため，範囲を切り出す際にデータの複製や再計算は行わない．
+#[derive(Debug)]
+pub struct Window<'a, C> {
+    cost: &'a C,
+    start: Tau,
+    end: Tau,
+}
+
+// 参照のみを保持するため，コスト関数の型によらず複製できる
+impl<C> Clone for Window<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Window<'_, C> {}
+
+impl<'a, C: SegmentCost> Window<'a, C> {
+    /// 範囲を指定してコスト関数を切り出す
+    ///
+    /// 範囲はデータのインデックスで指定する．例えば`100..500`はスライス`data[100..500]`と同じ観測を対象とする．
+    ///
+    /// # 引数
+    /// * `cost` - 元のコスト関数
+    /// * `range` - 対象とする範囲
+    pub fn new<R: RangeBounds<Tau>>(cost: &'a C, range: R) -> Result<Self, CalcDpError> {
+        let (start, end) = resolve_range(&range, cost.t_max())?;
+        Ok(Window { cost, start, end })
+    }
+
+
+    /// 元のコスト関数
+    pub fn cost(&self) -> &'a C {
+        self.cost
+    }
+
+
+    /// 元の系列における範囲の始点
+    pub fn start(&self) -> Tau {
+        self.start
+    }
+
+
+    /// 元の系列における範囲の終点
+    pub fn end(&self) -> Tau {
+        self.end
+    }
+
+
+    /// 範囲内の検出結果を元の系列の時点に変換する
+    ///
+    /// # 引数
+    /// * `result` - 本範囲に対する検出結果
+    pub fn to_original(&self, result: DetectionResult) -> DetectionResult {
+        DetectionResult {
+            change_points: result.change_points.iter().map(|t| t + self.start).collect(),
+            value: result.value,
+            start: self.start + result.start,
+            t_max: self.start + result.t_max,
+            selection: result.selection,
+        }
+    }
+}
+
+impl<C: SegmentCost> SegmentCost for Window<'_, C> {
+    fn t_max(&self) -> Tau {
+        self.end - self.start
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        self.cost.segment_value(self.start + t_k_1, self.start + t_k)
+    }
+
+
+    fn segment_values(&self, pairs: &[(Tau, Tau)]) -> Result<Vec<f64>, CalcDpError> {
+        let shifted = pairs.iter()
+                           .map(|(t_k_1, t_k)| {
+                               self.check_segment(*t_k_1, *t_k)?;
+                               Ok((self.start + t_k_1, self.start + t_k))
+                           })
+                           .collect::<Result<Vec<(Tau, Tau)>, CalcDpError>>()?;
+        self.cost.segment_values(&shifted)
+    }
+
+
+    fn constant_time(&self) -> bool {
+        self.cost.constant_time()
+    }
+
+
+    fn n_parameters(&self) -> usize {
+        self.cost.n_parameters()
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        self.cost.min_identifiable_size()
+    }
+
+
+    fn rejects_short_segments(&self) -> bool {
+        self.cost.rejects_short_segments()
+    }
+
+
+    fn pruning_constant(&self) -> f64 {
+        self.cost.pruning_constant()
+    }
+}
+
+impl<C: SegmentParameter> SegmentParameter for Window<'_, C> {
+    fn segment_parameter(&self, t_k_1: Tau, t_k: Tau) -> Result<Vec<f64>, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        self.cost.segment_parameter(self.start + t_k_1, self.start + t_k)
+    }
+}
+
+
+/// 系列の一部の範囲に対して変化点を検出する
+///
+/// 検出結果の変化点，`start`および`t_max`は元の系列の時点で表される．
+///
+/// # 引数
+/// * `cost` - 系列全体のコスト関数
+/// * `range` - 対象とする範囲（例: `100..5000`）
+/// * `method` - 変化点検出の手法
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+pub fn detect_window<C, R>(cost: &C, range: R, method: Method, penalty: &Penalty, constraints: &Constraints) -> Result<DetectionResult, CalcDpError>
+where
+    C: SegmentCost,
+    R: RangeBounds<Tau>,
+{
+    let window = Window::new(cost, range)?;
+    Ok(window.to_original(detect(&window, method, penalty, constraints)?))
+}
+
+
+/// 範囲を始点と終点の組に変換する
+///
+/// # 引数
+/// * `range` - 範囲
+/// * `t_max` - 系列の長さ
+pub(crate) fn resolve_range<R: RangeBounds<Tau>>(range: &R, t_max: Tau) -> Result<(Tau, Tau), CalcDpError> {
+    resolve_range_with(range, t_max, QueryPolicy::Strict)
+}
+
+
+/// 系列を超える範囲の扱いを指定して，範囲を始点と終点の組に変換する
+///
+/// [`QueryPolicy::ClampToRange`]では，終点を$ [1, T] $に，始点を$ [0, 終点 - 1] $に収める．
+/// 始点が終点以上である範囲はいずれの場合もErrorとする．
+///
+/// # 引数
+/// * `range` - 範囲
+/// * `t_max` - 系列の長さ
+/// * `policy` - 系列を超える範囲の扱い
+pub(crate) fn resolve_range_with<R: RangeBounds<Tau>>(range: &R, t_max: Tau, policy: QueryPolicy) -> Result<(Tau, Tau), CalcDpError> {
+    let start = match range.start_bound() {
+        Bound::Included(s) => *s,
+        Bound::Excluded(s) => s.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(e) => e.saturating_add(1),
+        Bound::Excluded(e) => *e,
+        Bound::Unbounded => t_max,
+    };
+    if start >= end || (end > t_max && policy == QueryPolicy::Strict) {
+        return Err(CalcDpError::new(format!("Invalid range {start}..{end} for the series of length {t_max}.")));
+    }
+    let end = policy.apply(end, 1, t_max)?;
+    let start = policy.apply(start, 0, end - 1)?;
+    Ok((start, end))
+}
@@ -2,22 +2,354 @@
 
 pub mod calc_dp;
 pub mod calc_dp_2;
+pub mod calc_dp_spaced;
+pub mod calc_dp_penalized;
+pub mod calc_pelt;
+pub(crate) mod dp_core;
+mod table;
+pub use table::CostTable;
+mod series;
+pub use series::{SeriesData, ContextSlice};
+mod ord_val;
+pub use ord_val::OrdVal;
+mod double_double;
+pub use double_double::F64x2;
+
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+extern crate rayon;
+use rayon::prelude::*;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+
+/// メモの要素（`一つ前の期数`, `現在の変化点個数`, `現時点での評価値`）
+pub type MemoEntry<Val> = (Tau, NumChg, Val);
+/// メモの1行（変化点個数ごと）
+pub type MemoRow<Val> = Vec<Option<MemoEntry<Val>>>;
+/// 動的計画法のメモ
+pub type Memo<Val> = Vec<MemoRow<Val>>;
+/// 評価値の計算に用いる変数を持つメモの要素（`一つ前の期数`, `現在の変化点個数`, `計算に用いる変数`, `現時点での評価値`）
+pub type VariMemoEntry<Vari, Val> = (Tau, NumChg, Vari, Val);
+/// 評価値の計算に用いる変数を持つメモの1行（変化点個数ごと）
+pub type VariMemoRow<Vari, Val> = Vec<Option<VariMemoEntry<Vari, Val>>>;
+/// 評価値の計算に用いる変数を持つ動的計画法のメモ
+pub type VariMemo<Vari, Val> = Vec<VariMemoRow<Vari, Val>>;
 
 
 /// `cpd_tools::calc_dp`に関するError
+///
+/// `message`はエラーが生じた箇所の説明であり，`context`はエラーが伝播した計算の段階を内側から順に記録する．
+/// 動的計画法や探索の各段階は，期数や区間，コスト関数の型名を含む[`ErrorContext`]を自動的に追加する．
+/// 入出力やコスト関数等の別のエラーが原因の場合，そのエラーは[`std::error::Error::source`]から参照できる．
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct CalcDpError {
     pub message: String,
+    pub context: Vec<ErrorContext>,
+    source: Option<Arc<dyn std::error::Error + Send + Sync>>,
+}
+
+impl CalcDpError {
+    /// 説明からエラーを作成する
+    ///
+    /// # 引数
+    /// * `message` - エラーの説明
+    pub fn new(message: impl Into<String>) -> Self {
+        CalcDpError { message: message.into(), context: Vec::new(), source: None }
+    }
+
+
+    /// 原因となったエラーを記録する
+    ///
+    /// # 引数
+    /// * `source` - 原因となったエラー
+    pub fn with_source<E: std::error::Error + Send + Sync + 'static>(mut self, source: E) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+
+    /// エラーが伝播した計算の段階を追加する
+    ///
+    /// # 引数
+    /// * `context` - 計算の段階と位置
+    pub fn with_context(mut self, context: ErrorContext) -> Self {
+        self.context.push(context);
+        self
+    }
 }
 
 impl std::fmt::Display for CalcDpError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        write!(f, "{}", self.message)
+        write!(f, "{}", self.message)?;
+        for context in &self.context {
+            write!(f, " (while {context})")?;
+        }
+        Ok(())
     }
 }
 
 impl std::error::Error for CalcDpError {
-    fn description(&self) -> &str {
-        &self.message
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<std::io::Error> for CalcDpError {
+    fn from(e: std::io::Error) -> Self {
+        CalcDpError::new(format!("I/O error: {e}")).with_source(e)
+    }
+}
+
+impl From<std::num::ParseFloatError> for CalcDpError {
+    fn from(e: std::num::ParseFloatError) -> Self {
+        CalcDpError::new(format!("Failed to parse a number: {e}")).with_source(e)
+    }
+}
+
+impl From<std::num::ParseIntError> for CalcDpError {
+    fn from(e: std::num::ParseIntError) -> Self {
+        CalcDpError::new(format!("Failed to parse an integer: {e}")).with_source(e)
+    }
+}
+
+
+/// エラーが生じた計算の段階と位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ErrorContext {
+    /// 計算の段階（例: `"filling DP memo"`）
+    pub stage: &'static str,
+    /// 期数$ t $
+    pub t: Option<Tau>,
+    /// 変化点個数$ k $
+    pub k: Option<NumChg>,
+    /// 区間$ (t_{k-1}, t_k] $
+    pub segment: Option<(Tau, Tau)>,
+    /// コスト関数の型名
+    pub cost: Option<&'static str>,
+}
+
+impl ErrorContext {
+    /// 計算の段階を指定して作成する
+    ///
+    /// # 引数
+    /// * `stage` - 計算の段階
+    pub fn new(stage: &'static str) -> Self {
+        ErrorContext { stage, t: None, k: None, segment: None, cost: None }
+    }
+
+
+    /// 期数を記録する
+    ///
+    /// # 引数
+    /// * `t` - 期数
+    pub fn time(mut self, t: Tau) -> Self {
+        self.t = Some(t);
+        self
+    }
+
+
+    /// 期数と変化点個数を記録する
+    ///
+    /// # 引数
+    /// * `t` - 期数
+    /// * `k` - 変化点個数
+    pub fn at(mut self, t: Tau, k: NumChg) -> Self {
+        self.t = Some(t);
+        self.k = Some(k);
+        self
+    }
+
+
+    /// 区間を記録する
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn segment(mut self, t_k_1: Tau, t_k: Tau) -> Self {
+        self.segment = Some((t_k_1, t_k));
+        self
+    }
+
+
+    /// コスト関数の型名を記録する
+    pub fn cost<C: ?Sized>(mut self) -> Self {
+        self.cost = Some(std::any::type_name::<C>());
+        self
+    }
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.stage)?;
+        let mut place = Vec::new();
+        if let Some(t) = self.t {
+            place.push(format!("t = {t}"));
+        }
+        if let Some(k) = self.k {
+            place.push(format!("k = {k}"));
+        }
+        if let Some((t_k_1, t_k)) = self.segment {
+            place.push(format!("segment ({t_k_1}, {t_k}]"));
+        }
+        if let Some(cost) = self.cost {
+            place.push(format!("cost {cost}"));
+        }
+        if !place.is_empty() {
+            write!(f, " at {}", place.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+
+/// `Result`のエラーへ計算の段階を追加する
+pub(crate) trait AddContext<T> {
+    /// エラーの場合のみ`context`を呼び出して段階を追加する
+    fn add_context<F: FnOnce() -> ErrorContext>(self, context: F) -> Result<T, CalcDpError>;
+}
+
+impl<T> AddContext<T> for Result<T, CalcDpError> {
+    fn add_context<F: FnOnce() -> ErrorContext>(self, context: F) -> Result<T, CalcDpError> {
+        self.map_err(|e| e.with_context(context()))
     }
 }
+
+
+/// 評価値の表（`DictTT::calc_value_all_with`）の作成方法
+///
+/// 表の各要素は`CalcTT::calc_value`の1回の呼び出しで計算され，要素間で浮動小数点数の集約は行わない．
+/// また並列に計算した行も行番号の順に格納する．
+/// このため`calc_value`が同じ入力に対して同じ値を返す限り，いずれの方法でもビット単位で同一の表が得られる．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableStrategy {
+    /// 行ごとにrayonで並列に計算する．作業の分割はスレッド数および実行時の負荷に依存する．
+    #[default]
+    Parallel,
+    /// 1スレッドで順に計算する
+    Serial,
+    /// 指定した行数ごとの固定の塊に分け，塊ごとに並列に計算する
+    ///
+    /// 作業の分割がスレッド数に依存しないため，計算の手順を固定する必要がある環境で用いる．
+    FixedChunks(usize),
+}
+
+
+/// 計算済みの範囲を超える時点を問い合わせた場合の扱い
+///
+/// 既定では範囲外の問い合わせをErrorとする．
+/// 描画や報告書の作成等で，計算済みの範囲をわずかに超える時点を問い合わせても結果を得たい場合は[`QueryPolicy::ClampToRange`]を用いる．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueryPolicy {
+    /// 範囲外の時点はErrorとする
+    #[default]
+    Strict,
+    /// 範囲外の時点は最も近い範囲内の時点に置き換える
+    ClampToRange,
+}
+
+impl QueryPolicy {
+    /// 時点を範囲$ [lo, hi] $に対して確認する
+    ///
+    /// # 引数
+    /// * `t` - 問い合わせた時点
+    /// * `lo` - 範囲の下限
+    /// * `hi` - 範囲の上限
+    ///
+    /// # 返り値
+    /// * `t` - 範囲内の時点．[`QueryPolicy::ClampToRange`]では範囲外の時点を最も近い端点に置き換える．
+    pub fn apply(self, t: Tau, lo: Tau, hi: Tau) -> Result<Tau, CalcDpError> {
+        if lo > hi {
+            return Err(CalcDpError::new(format!("Range [{lo}, {hi}] is empty.")));
+        }
+        match self {
+            QueryPolicy::Strict if t < lo || t > hi => Err(CalcDpError::new(format!("Time {t} is out of range [{lo}, {hi}]."))),
+            QueryPolicy::Strict => Ok(t),
+            QueryPolicy::ClampToRange => Ok(t.clamp(lo, hi)),
+        }
+    }
+}
+
+
+/// 区間ごとの計算から表を作成する
+///
+/// いずれかの区間の計算がエラーとなった場合，他のスレッドも次の区間の計算前にそれを検知して計算を打ち切り，
+/// 最初に記録したエラーを返す．並列に計算する場合，どの区間のエラーが返るかは実行ごとに異なりうる．
+///
+/// # 引数
+/// * `n_rows` - 行数
+/// * `row` - 行番号（前の変化点）から行に含まれる後ろの変化点の範囲を返す関数
+/// * `cell` - 区間$ (t_{k-1}, t_k] $の評価値を計算する関数
+/// * `strategy` - 表の作成方法
+pub(crate) fn build_table<Val, R, F>(n_rows: Tau, row: R, cell: F, strategy: TableStrategy) -> Result<Vec<Vec<Val>>, CalcDpError>
+where
+    Val: Send,
+    R: Fn(Tau) -> RangeInclusive<Tau> + Sync,
+    F: Fn(Tau, Tau) -> Result<Val, CalcDpError> + Sync,
+{
+    let failed = AtomicBool::new(false);
+    let first_error = Mutex::new(None);
+    let calc_row = |t_k_1: Tau| -> Option<Vec<Val>> {
+        let cells = row(t_k_1);
+        let mut vals = Vec::with_capacity(cells.size_hint().0);
+        for t_k in cells {
+            if failed.load(Ordering::Relaxed) {
+                return None;
+            }
+            match cell(t_k_1, t_k).add_context(|| ErrorContext::new("building cost table").segment(t_k_1, t_k)) {
+                Ok(val) => vals.push(val),
+                Err(e) => {
+                    if !failed.swap(true, Ordering::Relaxed) {
+                        *first_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(e);
+                    }
+                    return None;
+                },
+            }
+        }
+        Some(vals)
+    };
+
+    let table = match strategy {
+        TableStrategy::Parallel => (0..n_rows).into_par_iter().map(calc_row).collect::<Option<Vec<Vec<Val>>>>(),
+        TableStrategy::Serial => (0..n_rows).map(calc_row).collect(),
+        TableStrategy::FixedChunks(size) => {
+            if size == 0 {
+                return Err(CalcDpError::new("Chunk size must be at least 1."));
+            }
+            let rows = (0..n_rows).collect::<Vec<Tau>>();
+            rows.par_chunks(size)
+                .map(|chunk| chunk.iter().map(|t| calc_row(*t)).collect::<Option<Vec<Vec<Val>>>>())
+                .collect::<Option<Vec<Vec<Vec<Val>>>>>()
+                .map(|chunks| chunks.into_iter().flatten().collect())
+        },
+    };
+    match first_error.into_inner().unwrap_or_else(|e| e.into_inner()) {
+        Some(e) => Err(e),
+        None => table.ok_or_else(|| CalcDpError::new("Table construction was aborted without an error.")),
+    }
+}
+
+
+/// 表の全ての区間を計算し，エラーとなった区間を列挙する
+///
+/// [`build_table`]と異なり最初のエラーで打ち切らない．結果は$ (t_{k-1}, t_k) $の昇順に並ぶ．
+///
+/// # 引数
+/// * `n_rows` - 行数
+/// * `row` - 行番号（前の変化点）から行に含まれる後ろの変化点の範囲を返す関数
+/// * `cell` - 区間$ (t_{k-1}, t_k] $の評価値を計算する関数
+pub(crate) fn table_errors<Val, R, F>(n_rows: Tau, row: R, cell: F) -> Vec<(Tau, Tau, CalcDpError)>
+where
+    R: Fn(Tau) -> RangeInclusive<Tau> + Sync,
+    F: Fn(Tau, Tau) -> Result<Val, CalcDpError> + Sync,
+{
+    (0..n_rows).into_par_iter()
+               .flat_map_iter(|t_k_1| row(t_k_1).filter_map(|t_k| cell(t_k_1, t_k).err().map(|e| (t_k_1, t_k, e)))
+                                                .collect::<Vec<_>>())
+               .collect()
+}
@@ -4,16 +4,21 @@
 //! 2個の連続した変化点$ t_k, t_{k-1} $が与えられたとき，データ$ \bm{X} $から評価値を計算する関数$ f(t_k, t_{k-1} | \bm{X}) $が定義される場合を想定．
 //! 更に，データ全体に対する評価値が各変化点間の評価値の総和$ \sum_{k=1}^{K} f(t_k, t_{k-1}) $を利用して計算される場合も扱う．
 
-use super::CalcDpError;
+use super::{CalcDpError, CostTable, TableStrategy, build_table, table_errors};
+use super::{MemoEntry, MemoRow, Memo, VariMemoEntry, VariMemoRow, VariMemo};
+use super::calc_dp_spaced;
+use super::dp_core::{self, MinGap1};
 
 use std::fmt::Debug;
+use std::marker::PhantomData;
 
-extern crate rayon;
-use rayon::prelude::*;
 
 extern crate process_param;
 use process_param::{Tau, NumChg};
 
+extern crate rayon;
+use rayon::prelude::*;
+
 
 /// 変化点の順序を確認する
 ///
@@ -21,13 +26,7 @@ use process_param::{Tau, NumChg};
 /// * `t_k_1` - 前の変化点 $t_{k-1}$
 /// * `t_k` - 後ろの変化点 $t_k$
 pub fn order_change_point(t_k_1: &Tau, t_k: &Tau) -> Result<(), CalcDpError> {
-    if t_k_1 >= t_k {
-        Err( CalcDpError{
-            message: format!("Index tau_{{k}} (={t_k}) must be greater than tau_{{k-1}} (={t_k_1}")
-        })
-    } else {
-        Ok(())
-    }
+    calc_dp_spaced::order_change_point::<1>(t_k_1, t_k)
 }
 
 
@@ -36,11 +35,46 @@ pub trait CalcTT<Val, Ipt> where
 {
     /// 2個の変化点間の評価値を計算する関数$ f(t_k, t_{k-1} | \bm{X}) $
     ///
+    /// 区間外の観測を参照する場合は，`data`に余白を持つ[`super::SeriesData`]を用いる．
+    ///
     /// # 引数
     /// * `data` - 計算に用いるデータ$ \bm{X} $
     /// * `t_k_1` - 前の変化点 $t_{k-1}$
     /// * `t_k` - 後ろの変化点 $t_k$
     fn calc_value(data: &Ipt, t_k_1: Tau, t_k: Tau) -> Result<Val, CalcDpError>;
+
+
+    /// 複数の区間の評価値をまとめて計算する
+    ///
+    /// 動的計画法では，期数$ t_k $を共有する多数の区間の評価値を本関数でまとめて求める．
+    /// ベクトル化やGPU等により前処理をまとめて行える場合に実装する．
+    /// 既定では[`Self::calc_value`]を区間ごとに呼び出す．
+    ///
+    /// # 引数
+    /// * `data` - 計算に用いるデータ$ \bm{X} $
+    /// * `pairs` - 区間$ (t_{k-1}, t_k] $の列
+    ///
+    /// # 返り値
+    /// * `vals` - `pairs`と同じ順番の評価値
+    fn calc_values_batch(data: &Ipt, pairs: &[(Tau, Tau)]) -> Result<Vec<Val>, CalcDpError> {
+        pairs.iter()
+             .map(|(t_k_1, t_k)| Self::calc_value(data, *t_k_1, *t_k))
+             .collect()
+    }
+
+
+    /// 1点のみの区間$ (t - 1, t] $で評価値が定義されるか確認する
+    ///
+    /// 本moduleの動的計画法は変化点の最小間隔が1であり，1点のみの区間の評価値もメモの計算に用いる．
+    /// 分散等を区間ごとに推定する評価値は1点の区間で定義されず，NaN等がメモへ混入して意味のない変化点が得られる．
+    /// そのような評価値では，最小間隔2の[`super::calc_dp_2`]や区間の長さの制約を用いるよう促すエラーを返すよう実装する．
+    /// [`CalcDP::calc_memo_all`]の最初に呼び出される．既定では常に`Ok(())`を返す．
+    ///
+    /// # 引数
+    /// * `data` - 計算に用いるデータ$ \bm{X} $
+    fn check_single_point(_data: &Ipt) -> Result<(), CalcDpError> {
+        Ok(())
+    }
 }
 
 
@@ -68,25 +102,19 @@ pub trait DictTT<Val, Ipt>: CalcTT<Val, Ipt> where
     /// * `t_k` - 後ろの変化点 $t_k$
     fn value_tt(&self, t_k_1: Tau, t_k: Tau) -> Result<Val, CalcDpError> {
         order_change_point(&t_k_1, &t_k)?;
+        let (i, j) = dp_core::table_position::<MinGap1>(t_k_1, t_k)?;
 
         // 1個目の変化点確認
         let vals_all = self.value_tt_all();
-        let vals_tau_k_1 = if vals_all.len() < (t_k_1 as usize) {
-                return Err( CalcDpError{
-                    message: format!("Index tau_{{k - 1}} (={t_k_1}) is out of range.")
-                })
-            } else {
-                &vals_all[t_k_1 as usize]
-            };
+        let vals_tau_k_1 = match vals_all.get(i) {
+            Some(v) => v,
+            None => return Err( CalcDpError::new(format!("Index tau_{{k - 1}} (={t_k_1}) is out of range."))),
+        };
 
         // 2個目の変化点確認
-        let index_tt = t_k - t_k_1 - 1;
-        if vals_tau_k_1.len() < (index_tt as usize) {
-            Err( CalcDpError{
-                message: format!("Index tau_{{k}} (={t_k}) must be greater than tau_{{k-1}} (={t_k_1})")
-            })
-        } else {
-            Ok(vals_tau_k_1[index_tt as usize].clone())
+        match vals_tau_k_1.get(j) {
+            Some(v) => Ok(v.clone()),
+            None => Err( CalcDpError::new(format!("Index tau_{{k}} (={t_k}) is out of range."))),
         }
     }
 
@@ -103,13 +131,53 @@ pub trait DictTT<Val, Ipt>: CalcTT<Val, Ipt> where
     /// ## 返り値の構造について
     /// 配列のインデックスについては，1個目の要素数が変化点，2個目の要素数が変化点からの経過時間を示す．ただし，変化点はデータが切り替わる直前の時点として定義される．
     /// 例えば，2個の連続する変化点$ t_k, t_{k-1} $に対してその間の値$ f(t_k, t_{k-1}) $を得る場合，スライスのインデックスは`[t_{k-1}][t_k - (t_{k-1} + 1)]`となる．
+    ///
+    /// 行ごとにrayonで並列に計算する（[`TableStrategy::Parallel`]）．
+    /// 作成方法によらず同一の表が得られる（[`TableStrategy`]を参照）．
     fn calc_value_all(data: &Ipt, t_max: &Tau) -> Result<Vec<Vec<Val>>, CalcDpError> {
-        (0..*t_max).into_par_iter()
-                   .map(
-                       |t_k_1| ((t_k_1 + 1)..=*t_max).map(
-                           |t_k| Self::calc_value(data, t_k_1, t_k)
-                                                    ).collect()
-                  ).collect()
+        Self::calc_value_all_with(data, t_max, TableStrategy::Parallel)
+    }
+
+
+    /// 作成方法を指定して，2個の変化点間の評価値を格納した2次元配列を作成
+    ///
+    /// 返り値の構造は[`Self::calc_value_all`]と同じである．
+    ///
+    /// # 引数
+    /// * `data` - 計算に用いるデータ$ \bm{X} $
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    /// * `strategy` - 表の作成方法
+    fn calc_value_all_with(data: &Ipt, t_max: &Tau, strategy: TableStrategy) -> Result<Vec<Vec<Val>>, CalcDpError> {
+        build_table(dp_core::table_rows::<MinGap1>(*t_max)?,
+                    |t_k_1| dp_core::table_row::<MinGap1>(t_k_1, *t_max),
+                    |t_k_1, t_k| Self::calc_value(data, t_k_1, t_k),
+                    strategy)
+    }
+
+
+    /// 評価値の表の全ての区間を計算し，エラーとなった区間とそのエラーを返す
+    ///
+    /// [`Self::calc_value_all`]は最初のエラーで計算を打ち切るため，コスト関数の不具合を調べる際に用いる．
+    /// 全ての区間を計算するため，エラーが無い場合も[`Self::calc_value_all`]と同じ計算量を要する．
+    ///
+    /// # 引数
+    /// * `data` - 計算に用いるデータ$ \bm{X} $
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    ///
+    /// # 返り値
+    /// * `errors` - `(t_k_1, t_k, エラー)`の列．区間の昇順に並ぶ．
+    fn calc_value_errors(data: &Ipt, t_max: &Tau) -> Result<Vec<(Tau, Tau, CalcDpError)>, CalcDpError> {
+        Ok(table_errors(dp_core::table_rows::<MinGap1>(*t_max)?,
+                        |t_k_1| dp_core::table_row::<MinGap1>(t_k_1, *t_max),
+                        |t_k_1, t_k| Self::calc_value(data, t_k_1, t_k)))
+    }
+
+
+    /// 格納した評価値を[`CostTable`]として返す
+    ///
+    /// [`CalcDP::calc_memo_all_from_table`]に用いる．
+    fn cost_table(&self) -> Result<CostTable<Val>, CalcDpError> {
+        CostTable::new(self.value_tt_all())
     }
 }
 
@@ -126,6 +194,25 @@ pub trait DictToFunc<'a, Val, Ipt>: DictTT<Val, Ipt> where
     /// # 引数
     /// * `change_points` - 計算対象の変化点群
     fn evaluate(&self, change_points: &[Tau]) -> Result<Val, CalcDpError>;
+
+
+    /// 複数の変化点群に対する評価関数の値をまとめて返す
+    ///
+    /// WBSや安定性選択，ブートストラップ等で多数の候補を比較する場合を想定し，候補ごとの[`Self::evaluate`]をrayonで並列に計算する．
+    /// 各スレッドは`self`を共有して参照するため，格納した評価値の表を複製しない．
+    ///
+    /// # 引数
+    /// * `candidates` - 計算対象の変化点群の列
+    ///
+    /// # 返り値
+    /// * `vals` - `candidates`と同じ順番の評価関数の値
+    fn evaluate_batch(&self, candidates: &[Vec<Tau>]) -> Result<Vec<Val>, CalcDpError> where
+        Self: Sync
+    {
+        candidates.par_iter()
+                  .map(|change_points| self.evaluate(change_points))
+                  .collect()
+    }
     
 
     /// 変化点群から評価値の合計を計算する
@@ -136,17 +223,31 @@ pub trait DictToFunc<'a, Val, Ipt>: DictTT<Val, Ipt> where
     /// # 引数
     /// * `change_points` - 計算対象の変化点群
     fn sum_frol_cp(&self, change_points: &[Tau]) -> Result<Val, CalcDpError> {
-        // イテレータを用意
-        let mut cp_copy = change_points.to_vec();
-        cp_copy.insert(0,0);
-        cp_copy.pop();
-        let comb_cp = cp_copy.iter().zip(change_points.iter());
-        
-        let vals_tt = comb_cp.map(|(t_k_1, t_k)| self.value_tt(*t_k_1, *t_k))
-                             .collect::<Result<Vec<Val>, CalcDpError>>()?;
-        let val = vals_tt.into_iter()
-                         .sum();
-        Ok(val)
+        // 区間(t_{k-1}, t_k]の組を複製せずに作る
+        std::iter::once(0).chain(change_points.iter().copied())
+                          .zip(change_points.iter().copied())
+                          .map(|(t_k_1, t_k)| self.value_tt(t_k_1, t_k))
+                          .sum()
+    }
+
+
+    /// 変化点群から評価値の合計を並列に計算する
+    ///
+    /// 変化点個数が非常に大きい場合に，区間ごとの評価値の取得をrayonで並列に行う．
+    /// 評価値は区間の順に並べてから合計するため，[`Self::sum_frol_cp`]と同じ値となる．
+    ///
+    /// # 引数
+    /// * `change_points` - 計算対象の変化点群
+    fn sum_frol_cp_par(&self, change_points: &[Tau]) -> Result<Val, CalcDpError> where
+        Self: Sync
+    {
+        let vals_tt = (0..change_points.len()).into_par_iter()
+                                              .map(|i| {
+                                                  let t_k_1 = if i == 0 { 0 } else { change_points[i - 1] };
+                                                  self.value_tt(t_k_1, change_points[i])
+                                              })
+                                              .collect::<Result<Vec<Val>, CalcDpError>>()?;
+        Ok(vals_tt.into_iter().sum())
     }
 
         
@@ -158,8 +259,76 @@ pub trait DictToFunc<'a, Val, Ipt>: DictTT<Val, Ipt> where
     /// # 返り値
     /// * `k_max` - 変化点個数の最大値
     fn calc_max_k(t_max: &Tau) -> NumChg {
-        // 天井関数の代わりに整数の割り算では余りが切り捨てられることを利用
-        (*t_max - 1) as NumChg
+        dp_core::max_k::<MinGap1>(*t_max)
+    }
+}
+
+
+/// `CalcTT`のみを実装した型`T`に対し，評価値の表を保持して`DictTT`および`DictToFunc`を提供する
+///
+/// `DictTT`と`DictToFunc`は本構造体に対して任意の`T`についてまとめて実装されるため，
+/// `calc_value`を実装するだけで表に基づく評価や全探索を利用できる．
+/// 表は[`Self::new`]で一度だけ作成する．`DictToFunc::evaluate`は区間の評価値の総和（[`DictToFunc::sum_frol_cp`]）を返す．
+///
+/// # 利用するジェネリクス型
+/// * `T` - `CalcTT`を実装した型
+/// * `Val` - 計算結果の値の型
+pub struct DictCache<T, Val> {
+    table: Vec<Vec<Val>>,
+    // 型Tは関連関数のみを用いるため，Send + Syncを型Tによらず満たす関数ポインタとして保持する
+    _calc: PhantomData<fn() -> T>,
+}
+
+impl<T, Val> DictCache<T, Val> {
+    /// 評価値の表を作成する
+    ///
+    /// # 引数
+    /// * `data` - 計算に用いるデータ$ \bm{X} $
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    pub fn new<Ipt>(data: &Ipt, t_max: &Tau) -> Result<Self, CalcDpError>
+    where
+        T: CalcTT<Val, Ipt>,
+        Val: Clone + std::marker::Send + Debug,
+        Ipt: std::marker::Sync,
+    {
+        let table = <Self as DictTT<Val, Ipt>>::calc_value_all(data, t_max)?;
+        Ok(DictCache { table, _calc: PhantomData })
+    }
+}
+
+impl<T: CalcTT<Val, Ipt>, Val, Ipt> CalcTT<Val, Ipt> for DictCache<T, Val> {
+    fn calc_value(data: &Ipt, t_k_1: Tau, t_k: Tau) -> Result<Val, CalcDpError> {
+        T::calc_value(data, t_k_1, t_k)
+    }
+
+
+    fn calc_values_batch(data: &Ipt, pairs: &[(Tau, Tau)]) -> Result<Vec<Val>, CalcDpError> {
+        T::calc_values_batch(data, pairs)
+    }
+
+
+    fn check_single_point(data: &Ipt) -> Result<(), CalcDpError> {
+        T::check_single_point(data)
+    }
+}
+
+impl<T, Val, Ipt> DictTT<Val, Ipt> for DictCache<T, Val> where
+    T: CalcTT<Val, Ipt>,
+    Val: Clone + std::marker::Send + Debug,
+    Ipt: std::marker::Sync
+{
+    fn value_tt_all(&self) -> Vec<Vec<Val>> {
+        self.table.clone()
+    }
+}
+
+impl<T, Val, Ipt> DictToFunc<'_, Val, Ipt> for DictCache<T, Val> where
+    T: CalcTT<Val, Ipt>,
+    Val: std::iter::Sum + Clone + std::marker::Send + Debug,
+    Ipt: std::marker::Sync
+{
+    fn evaluate(&self, change_points: &[Tau]) -> Result<Val, CalcDpError> {
+        self.sum_frol_cp(change_points)
     }
 }
 
@@ -167,8 +336,9 @@ pub trait DictToFunc<'a, Val, Ipt>: DictTT<Val, Ipt> where
 /// 動的計画法で評価値を計算する
 ///
 /// # 計算に用いるメモについて
-/// ([`Tau`], [`NumChg`], `Val`)を要素とする2次元ベクトル
+/// ([`Tau`], [`NumChg`], `Val`)を要素とする2次元ベクトル．
 /// 順に(`一つ前の期数`, `現在の変化点個数`, `現時点での評価値`)で成り立つ．
+/// 2次元ベクトルの各軸については，1次元目が変化点個数，2次元目が時期である．
 pub trait CalcDP<Val, Ipt>: CalcTT<Val, Ipt> where
     Val: std::iter::Sum + std::cmp::PartialOrd + Clone + Debug,
 {
@@ -177,59 +347,90 @@ pub trait CalcDP<Val, Ipt>: CalcTT<Val, Ipt> where
     /// # 引数
     /// * `data` - 計算に必要な入力値
     /// * `t_max` - 変化点の最大値（最後の時期）
-    fn calc_memo_all(data: &Ipt, t_max: &Tau) -> Result<Vec<Vec<Option<(Tau, NumChg, Val)>>>, CalcDpError> {
-        let mut memo = (0..*t_max).map(|i| vec![None; (t_max - i) as usize] )
-                                  .collect::<Vec<Vec<Option<(Tau, NumChg, Val)>>>>();
-        
-        // メモを計算
-        for k in 0..*t_max { 
-            Self::calc_memo(t_max, &k, &mut memo, data)?;
-        };
-        
-        Ok(memo)
+    fn calc_memo_all(data: &Ipt, t_max: &Tau) -> Result<Memo<Val>, CalcDpError> {
+        Self::check_single_point(data)?;
+        calc_dp_spaced::build_memo::<_, 1, _>(t_max, |t, k, memo| Self::calc_memo(t, k, memo, data))
+    }
+
+
+    /// 区間の評価値を並列に計算して，すべての評価値を格納したメモを作成
+    ///
+    /// 一つ前の変化点の候補が多い期数では，区間の評価値をrayonで並列に計算する．
+    /// 最大値の選択は候補の順に行うため，評価値が等しい候補の選び方を含めて[`Self::calc_memo_all`]と同じメモが得られる．
+    ///
+    /// # 引数
+    /// * `data` - 計算に必要な入力値
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    fn calc_memo_all_par(data: &Ipt, t_max: &Tau) -> Result<Memo<Val>, CalcDpError> where
+        Val: std::marker::Send,
+        Ipt: std::marker::Sync
+    {
+        Self::check_single_point(data)?;
+        calc_dp_spaced::build_memo::<_, 1, _>(t_max, |t, k, memo| Self::calc_memo_par(t, k, memo, data))
+    }
+
+
+    /// 事前に計算した評価値の表を用いて，すべての評価値を格納したメモを作成
+    ///
+    /// [`Self::calc_value`]を呼び出さずに，[`Self::calc_memo_all`]と同じメモを作成する．
+    /// 同じ系列に対して動的計画法を繰り返す場合，表の$ O(T^2) $の記憶領域と引き換えに評価値の計算を省略できる．
+    ///
+    /// # 引数
+    /// * `table` - 評価値の表
+    /// * `t_max` - 変化点の最大値（最後の時期）．表の`t_max`以下とする．
+    fn calc_memo_all_from_table(table: &CostTable<Val>, t_max: &Tau) -> Result<Memo<Val>, CalcDpError> {
+        calc_dp_spaced::memo_from_table::<_, 1>(table, t_max)
     }
 
 
     /// 動的計画法の計算に用いたメモを返す
     ///
     /// # 注意
-    /// [`Self::calc_memo_all`]の返り値を返してください．
+    /// [`Self::calc_memo_all`]または[`Self::calc_memo_all_par`]の返り値を返してください．
     /// 計算コストを考慮して，`struct`の要素としてメモを保持する状況を想定しています．
-    fn memo_all(&self) -> Vec<Vec<Option<(Tau, NumChg, Val)>>>;
+    fn memo_all(&self) -> Memo<Val>;
 
 
     /// 評価値の推移を取得
     ///
     /// 指定された変化点と変化回数から，その評価値等を計算に用いた中間地点の評価値等とともに出力する．
+    /// `k`が0（変化なし）の場合は区間$ (0, t] $の要素`(0, 0, 評価値)`のみを返す．
     ///
     /// # 引数
     /// * `t` - 計算する期数
     /// * `k` - 計算する変化点個数
-    fn get_value_history(&self, t: &Tau, k: &NumChg) -> Result<Vec<(Tau, NumChg, Val)>, CalcDpError> {
-        let mut now_t = *t;
-        let mut now_k = *k;
-        let memo = self.memo_all();
-        let mut res = Vec::new();
-
-        while now_t > 0 {
-            let memo_tk;
-            match Self::get_from_memo(&now_t, &now_k, &memo)? {
-                None => {
-                    // 値が設定されていない場合はエラーとなる．
-                    return Err(CalcDpError{
-                        message: "Uncalculated value exist.".to_owned()
-                    });
-                },
-                Some(v) => memo_tk = v,
-            };
-            
-            now_t = memo_tk.0;
-            if memo_tk.1 != 0 {
-                now_k = memo_tk.1 - 1;
-            };
-            res.push(memo_tk);
-        }
-        Ok(res)
+    fn get_value_history(&self, t: &Tau, k: &NumChg) -> Result<Vec<MemoEntry<Val>>, CalcDpError> {
+        dp_core::history::<MinGap1, Val, _>(*t, *k, &self.memo_all())
+    }
+
+
+    /// 最適な変化点を取得
+    ///
+    /// [`Self::get_value_history`]の結果を遡り，変化点を昇順に並べて返す．
+    /// 評価値も必要な場合は[`Self::get_change_points_with_value`]を用いる．
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    ///
+    /// # 返り値
+    /// * `change_points` - 昇順に並んだ`k`個の変化点
+    fn get_change_points(&self, t: &Tau, k: &NumChg) -> Result<Vec<Tau>, CalcDpError> {
+        self.get_change_points_with_value(t, k).map(|(change_points, _)| change_points)
+    }
+
+
+    /// 最適な変化点とその評価値を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    ///
+    /// # 返り値
+    /// * `change_points` - 昇順に並んだ`k`個の変化点
+    /// * `value` - 期数`t`までの評価値（[`Self::get_value`]と同じ）
+    fn get_change_points_with_value(&self, t: &Tau, k: &NumChg) -> Result<(Vec<Tau>, Val), CalcDpError> {
+        dp_core::change_points::<MinGap1, Val, _>(*t, *k, &self.memo_all())
     }
 
 
@@ -243,9 +444,7 @@ pub trait CalcDP<Val, Ipt>: CalcTT<Val, Ipt> where
     fn get_value(&self, t: &Tau, k: &NumChg) -> Result<Val, CalcDpError> {
         match Self::get_from_memo(t, k, &self.memo_all())? {
             Some(v) => Ok(v.2),
-            None => Err(CalcDpError{
-                message: "Value has not calculated yet.".to_owned()
-            }),
+            None => Err(CalcDpError::new("Value has not calculated yet.")),
         }
     }
 
@@ -256,38 +455,34 @@ pub trait CalcDP<Val, Ipt>: CalcTT<Val, Ipt> where
     /// * `t` - 計算する期数
     /// * `k` - 計算する変化点個数
     /// * `memo` - 動的計画法の計算に用いるメモ
-    fn check_idx_memo(t: &Tau, k: &NumChg, memo: &[Vec<Option<(Tau, NumChg, Val)>>]) -> Result<(), CalcDpError> {
-        if (*t as usize) > memo.len() {
-            return Err(CalcDpError{
-                message: format!("Time step t = {t} is out of range.")
-            });
-        }
-
-        if k >= t {
-            return Err(CalcDpError{
-                message: format!("The number of change point k (= {k}) must be less than the time step t (= {t}).")
-            });
-        }
+    fn check_idx_memo(t: &Tau, k: &NumChg, memo: &[MemoRow<Val>]) -> Result<(), CalcDpError> {
+        dp_core::check::<MinGap1, _>(*t, *k, memo).map(|_| ())
+    }
 
-        if *t == 0 {
-            return Err(CalcDpError{
-                message: "Time step must be greater than 0".to_owned()
-            });
-        }
 
-        Ok(())
+    /// メモから値を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    /// * `memo` - 動的計画法の計算に用いるメモ
+    fn get_from_memo(t: &Tau, k: &NumChg, memo: &[MemoRow<Val>]) -> Result<Option<MemoEntry<Val>>, CalcDpError> {
+        dp_core::get::<MinGap1, _>(*t, *k, memo)
     }
 
 
-    /// メモから値を取得
+    /// 添字を検査せずにメモから値を取得
+    ///
+    /// [`Self::check_idx_memo`]による検査はdebugビルドでのみ行う．
+    /// 動的計画法の内側のループ等で，添字がメモの範囲内であることを事前に一度確認した上で用いる．
+    /// 範囲外の添字に対する結果は保証しない（releaseビルドではパニックするか誤った値を返す）．
     ///
     /// # 引数
     /// * `t` - 計算する期数
     /// * `k` - 計算する変化点個数
     /// * `memo` - 動的計画法の計算に用いるメモ
-    fn get_from_memo(t: &Tau, k: &NumChg, memo: &[Vec<Option<(Tau, NumChg, Val)>>]) -> Result<Option<(Tau, NumChg, Val)>, CalcDpError> {
-        Self::check_idx_memo(t, k, memo)?;
-        Ok( memo[*k as usize][(*t-*k-1) as usize].clone() )
+    fn get_from_memo_unchecked(t: &Tau, k: &NumChg, memo: &[MemoRow<Val>]) -> Option<MemoEntry<Val>> {
+        dp_core::get_unchecked::<MinGap1, _>(*t, *k, memo)
     }
 
 
@@ -295,14 +490,10 @@ pub trait CalcDP<Val, Ipt>: CalcTT<Val, Ipt> where
     ///
     /// # 引数
     /// * `t` - 計算する期数
-    /// * `k` - 計算する変化点個数
-    /// * `eval` - 評価値
+    /// * `val` - メモの要素（変化点個数は要素のものを用いる）
     /// * `memo` - 動的計画法の計算に用いるメモ
-    fn set_from_memo(t: &Tau, val: (Tau, NumChg, Val), memo: &mut [Vec<Option<(Tau, NumChg, Val)>>]) -> Result<(Tau, NumChg, Val), CalcDpError> {
-        let k = val.1;
-        Self::check_idx_memo(&t, &k, memo)?;
-        memo[k as usize][(t-k-1) as usize] = Some(val.clone());
-        Ok(val)
+    fn set_from_memo(t: &Tau, val: MemoEntry<Val>, memo: &mut [MemoRow<Val>]) -> Result<MemoEntry<Val>, CalcDpError> {
+        dp_core::set::<MinGap1, Val, _>(*t, val, memo)
     }
 
     
@@ -313,58 +504,41 @@ pub trait CalcDP<Val, Ipt>: CalcTT<Val, Ipt> where
     /// * `k` - 計算する変化点個数
     /// * `memo` - 動的計画法の計算に用いるメモ
     /// * `data` - 計算に必要な入力値
-    fn calc_memo(t: &Tau, k: &NumChg, memo: &mut [Vec<Option<(Tau, NumChg, Val)>>], data: &Ipt) -> Result<(Tau, NumChg, Val), CalcDpError> {
-        Self::check_idx_memo(t, k, memo)?;
-        
-        // k=0なら再帰の末尾．別処理
-        if *k == 0 {
-            return match Self::get_from_memo(t, k, memo)? {
-                Some(v) => Ok(v),
-                None => {
-                    let eval = Self::calc_value(data, 0, *t)?;
-                    let res_tk = (0, 0, eval);
-                    Self::set_from_memo(t, res_tk, memo)
-                },
-            }
-        }
+    fn calc_memo(t: &Tau, k: &NumChg, memo: &mut [MemoRow<Val>], data: &Ipt) -> Result<MemoEntry<Val>, CalcDpError> {
+        calc_dp_spaced::fill_memo::<_, 1, _, _>(t, k, memo,
+                                                |t| Self::calc_value(data, 0, t),
+                                                |pairs| dp_core::eval_batches(pairs, |batch| Self::calc_values_batch(data, batch)))
+    }
 
-        // k>0の場合
-        // ひとつ前の変化点$ \tau_{k-1} $ごとに評価値を計算
-        let mut vals = Vec::with_capacity((t - k) as usize);
-
-        for i in *k..*t {
-            let max_k_1 = {
-                let tpl_mk1 = match Self::get_from_memo(&i, &(*k-1), memo)? {
-                    Some(v) => v,
-                    None => Self::calc_memo(&i, &(*k-1), memo, data)?,
-                };
-                tpl_mk1.2
-            };
-            let val_tt = Self::calc_value(data, i, *t)?;
-            let eval:Val = [max_k_1, val_tt].into_iter()
-                                            .sum();
-            let res_tk = (i, *k, eval);
-            vals.push(res_tk);
-        }
 
-        // 評価値最大のものを選択
-        let op_max_val = vals.iter()
-                             .reduce(|acc, val| {
-                                if acc.2 <= val.2 {
-                                    val
-                                } else {
-                                    acc
-                                }
-                            });
-        let max_val;
-        match op_max_val {
-            Some(v) => max_val = v,
-            None => return Err( CalcDpError{
-                message: "Failed to compute dynamic programming memo.".to_owned()
-            }),
-        };
-       
-        Self::set_from_memo(t, max_val.clone(), memo)
+    /// 区間の評価値を並列に計算しながら，動的計画法を用いて評価値を計算する
+    ///
+    /// 結果は[`Self::calc_memo`]と一致する．
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    /// * `memo` - 動的計画法の計算に用いるメモ
+    /// * `data` - 計算に必要な入力値
+    fn calc_memo_par(t: &Tau, k: &NumChg, memo: &mut [MemoRow<Val>], data: &Ipt) -> Result<MemoEntry<Val>, CalcDpError> where
+        Val: std::marker::Send,
+        Ipt: std::marker::Sync
+    {
+        calc_dp_spaced::fill_memo::<_, 1, _, _>(t, k, memo,
+                                                |t| Self::calc_value(data, 0, t),
+                                                |pairs| dp_core::eval_batches_par(pairs, |batch| Self::calc_values_batch(data, batch)))
+    }
+
+
+    /// Kの最大値を計算
+    ///
+    /// # 引数
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    ///
+    /// # 返り値
+    /// * `k_max` - 変化点個数の最大値
+    fn calc_max_k(t_max: &Tau) -> NumChg {
+        dp_core::max_k::<MinGap1>(*t_max)
     }
 }
 
@@ -402,15 +576,44 @@ pub trait CalcDPWithVari<Val, Vari, Ipt> where
     /// # 引数
     /// * `data` - 計算に必要な入力値
     /// * `t_max` - 変化点の最大値（最後の時期）
-    fn calc_memo_all(data: &Ipt, t_max: &Tau) -> Result<Vec<Vec<Option<(Tau, NumChg, Vari, Val)>>>, CalcDpError> {
-        let mut memo = (0..*t_max).map(|i| vec![None; (t_max - i) as usize] )
-                                  .collect::<Vec<Vec<Option<(Tau, NumChg, Vari, Val)>>>>();
-        
+    fn calc_memo_all(data: &Ipt, t_max: &Tau) -> Result<VariMemo<Vari, Val>, CalcDpError> {
+        let k_max = Self::calc_max_k(t_max);
+        let mut memo = dp_core::allocate::<MinGap1, _>(*t_max, k_max)?;
+
         // メモを計算
-        for k in 0..*t_max { 
-            Self::calc_memo(t_max, &k, &mut memo, data)?;
-        };
-        
+        if *t_max > 0 {
+            for k in 0..=k_max {
+                Self::calc_memo(t_max, &k, &mut memo, data)?;
+            }
+        }
+
+        Ok(memo)
+    }
+
+
+    /// 候補ごとの評価値を並列に計算して，すべての評価値を格納したメモを作成
+    ///
+    /// 一つ前の変化点の候補が多い期数では，候補ごとの評価値をrayonで並列に計算する．
+    /// 最大値の選択は候補の順に行うため，評価値が等しい候補の選び方を含めて[`Self::calc_memo_all`]と同じメモが得られる．
+    ///
+    /// # 引数
+    /// * `data` - 計算に必要な入力値
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    fn calc_memo_all_par(data: &Ipt, t_max: &Tau) -> Result<VariMemo<Vari, Val>, CalcDpError> where
+        Val: std::marker::Send,
+        Vari: std::marker::Send,
+        Ipt: std::marker::Sync
+    {
+        let k_max = Self::calc_max_k(t_max);
+        let mut memo = dp_core::allocate::<MinGap1, _>(*t_max, k_max)?;
+
+        // メモを計算
+        if *t_max > 0 {
+            for k in 0..=k_max {
+                Self::calc_memo_par(t_max, &k, &mut memo, data)?;
+            }
+        }
+
         Ok(memo)
     }
 
@@ -418,9 +621,9 @@ pub trait CalcDPWithVari<Val, Vari, Ipt> where
     /// 動的計画法の計算に用いたメモを返す
     ///
     /// # 注意
-    /// [`Self::calc_memo_all`]の返り値を返してください．
+    /// [`Self::calc_memo_all`]または[`Self::calc_memo_all_par`]の返り値を返してください．
     /// 計算コストを考慮して，`struct`の要素としてメモを保持する状況を想定しています．
-    fn memo_all(&self) -> Vec<Vec<Option<(Tau, NumChg, Vari, Val)>>>;
+    fn memo_all(&self) -> VariMemo<Vari, Val>;
 
 
     /// 評価値の推移を遡る形で取得
@@ -432,31 +635,8 @@ pub trait CalcDPWithVari<Val, Vari, Ipt> where
     /// # 引数
     /// * `t` - 計算する期数
     /// * `k` - 計算する変化点数
-    fn get_value_history(&self, t: &Tau, k: &NumChg) -> Result<Vec<(Tau, NumChg, Vari, Val)>, CalcDpError> {
-        let mut now_t = *t;
-        let mut now_k = *k;
-        let memo = self.memo_all();
-        let mut res = Vec::new();
-
-        while now_t > 0 {
-            let memo_tk;
-            match Self::get_from_memo(&now_t, &now_k, &memo)? {
-                None => {
-                    // 値が設定されていない場合はエラーとなる．
-                    return Err(CalcDpError{
-                        message: "Uncalculated value exist.".to_owned()
-                    });
-                },
-                Some(v) => memo_tk = v,
-            };
-            
-            now_t = memo_tk.0;
-            if memo_tk.1 != 0 {
-                now_k = memo_tk.1 - 1;
-            };
-            res.push(memo_tk);
-        }
-        Ok(res)
+    fn get_value_history(&self, t: &Tau, k: &NumChg) -> Result<Vec<VariMemoEntry<Vari, Val>>, CalcDpError> {
+        dp_core::history::<MinGap1, Val, _>(*t, *k, &self.memo_all())
     }
 
 
@@ -469,33 +649,8 @@ pub trait CalcDPWithVari<Val, Vari, Ipt> where
     /// # 引数
     /// * `t` - 計算する期数
     /// * `k` - 計算する変化点数
-    fn get_value_history_forward(&self, t: &Tau, k: &NumChg) -> Result<Vec<(Tau, NumChg, Vari, Val)>, CalcDpError> {
-        let mut now_t = *t;
-        let mut now_k = *k + 1; // 参照時に-1して利用するため
-        let memo = self.memo_all();
-        let mut res = Vec::new(); // このベクタには逆順にアイテムを追加する．
-
-        while now_t > 0 {
-            let memo_tk;
-            match Self::get_from_memo(&now_t, &(now_k - 1), &memo)? {
-                None => {
-                    // 値が設定されていない場合はエラーとなる．
-                    return Err(CalcDpError{
-                        message: "Uncalculated value exist.".to_owned()
-                    });
-                },
-                Some(v) => {
-                    let (next_t, next_k, vari, val) = v;
-                    memo_tk = (now_t, now_k, vari, val);
-                    now_t = next_t;
-                    now_k = next_k;
-                },
-            };
-            res.push(memo_tk);
-        }
-        res.reverse(); // 変化点数に対して昇順になるよう順番を逆転．
-
-        Ok(res)
+    fn get_value_history_forward(&self, t: &Tau, k: &NumChg) -> Result<Vec<VariMemoEntry<Vari, Val>>, CalcDpError> {
+        dp_core::history_forward::<MinGap1, Vari, Val>(*t, *k, &self.memo_all())
     }
 
 
@@ -509,9 +664,7 @@ pub trait CalcDPWithVari<Val, Vari, Ipt> where
     fn get_value(&self, t: &Tau, k: &NumChg) -> Result<Val, CalcDpError> {
         match Self::get_from_memo(t, k, &self.memo_all())? {
             Some(v) => Ok(v.3),
-            None => Err(CalcDpError{
-                message: "Value has not calculated yet.".to_owned()
-            }),
+            None => Err(CalcDpError::new("Value has not calculated yet.")),
         }
     }
 
@@ -526,9 +679,7 @@ pub trait CalcDPWithVari<Val, Vari, Ipt> where
     fn get_variable(&self, t: &Tau, k: &NumChg) -> Result<Vari, CalcDpError> {
         match Self::get_from_memo(t, k, &self.memo_all())? {
             Some(v) => Ok(v.2),
-            None => Err(CalcDpError{
-                message: "Value has not calculated yet.".to_owned()
-            }),
+            None => Err(CalcDpError::new("Value has not calculated yet.")),
         }
     }
 
@@ -539,38 +690,34 @@ pub trait CalcDPWithVari<Val, Vari, Ipt> where
     /// * `t` - 計算する期数
     /// * `k` - 計算する変化点個数
     /// * `memo` - 動的計画法の計算に用いるメモ
-    fn check_idx_memo(t: &Tau, k: &NumChg, memo: &[Vec<Option<(Tau, NumChg, Vari, Val)>>]) -> Result<(), CalcDpError> {
-        if (*t as usize) > memo.len() {
-            return Err(CalcDpError{
-                message: format!("Time step t = {t} is out of range.")
-            });
-        }
-
-        if k >= t {
-            return Err(CalcDpError{
-                message: format!("The number of change point k (= {k}) must be less than the time step t (= {t}).")
-            });
-        }
+    fn check_idx_memo(t: &Tau, k: &NumChg, memo: &[VariMemoRow<Vari, Val>]) -> Result<(), CalcDpError> {
+        dp_core::check::<MinGap1, _>(*t, *k, memo).map(|_| ())
+    }
 
-        if *t == 0 {
-            return Err(CalcDpError{
-                message: "Time step must be greater than 0".to_owned()
-            });
-        }
 
-        Ok(())
+    /// メモから値を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    /// * `memo` - 動的計画法の計算に用いるメモ
+    fn get_from_memo(t: &Tau, k: &NumChg, memo: &[VariMemoRow<Vari, Val>]) -> Result<Option<VariMemoEntry<Vari, Val>>, CalcDpError> {
+        dp_core::get::<MinGap1, _>(*t, *k, memo)
     }
 
 
-    /// メモから値を取得
+    /// 添字を検査せずにメモから値を取得
+    ///
+    /// [`Self::check_idx_memo`]による検査はdebugビルドでのみ行う．
+    /// 動的計画法の内側のループ等で，添字がメモの範囲内であることを事前に一度確認した上で用いる．
+    /// 範囲外の添字に対する結果は保証しない（releaseビルドではパニックするか誤った値を返す）．
     ///
     /// # 引数
     /// * `t` - 計算する期数
     /// * `k` - 計算する変化点個数
     /// * `memo` - 動的計画法の計算に用いるメモ
-    fn get_from_memo(t: &Tau, k: &NumChg, memo: &[Vec<Option<(Tau, NumChg, Vari, Val)>>]) -> Result<Option<(Tau, NumChg, Vari, Val)>, CalcDpError> {
-        Self::check_idx_memo(t, k, memo)?;
-        Ok( memo[*k as usize][(*t-*k-1) as usize].clone() )
+    fn get_from_memo_unchecked(t: &Tau, k: &NumChg, memo: &[VariMemoRow<Vari, Val>]) -> Option<VariMemoEntry<Vari, Val>> {
+        dp_core::get_unchecked::<MinGap1, _>(*t, *k, memo)
     }
 
 
@@ -578,14 +725,10 @@ pub trait CalcDPWithVari<Val, Vari, Ipt> where
     ///
     /// # 引数
     /// * `t` - 計算する期数
-    /// * `k` - 計算する変化点個数
-    /// * `eval` - 評価値
+    /// * `val` - メモの要素（変化点個数は要素のものを用いる）
     /// * `memo` - 動的計画法の計算に用いるメモ
-    fn set_from_memo(t: &Tau, val: (Tau, NumChg, Vari, Val), memo: &mut [Vec<Option<(Tau, NumChg, Vari, Val)>>]) -> Result<(Tau, NumChg, Vari, Val), CalcDpError> {
-        let k = val.1;
-        Self::check_idx_memo(&t, &k, memo)?;
-        memo[k as usize][(t-k-1) as usize] = Some(val.clone());
-        Ok(val)
+    fn set_from_memo(t: &Tau, val: VariMemoEntry<Vari, Val>, memo: &mut [VariMemoRow<Vari, Val>]) -> Result<VariMemoEntry<Vari, Val>, CalcDpError> {
+        dp_core::set::<MinGap1, Val, _>(*t, val, memo)
     }
 
     
@@ -596,57 +739,47 @@ pub trait CalcDPWithVari<Val, Vari, Ipt> where
     /// * `k` - 計算する変化点個数
     /// * `memo` - 動的計画法の計算に用いるメモ
     /// * `data` - 計算に必要な入力値
-    fn calc_memo(t: &Tau, k: &NumChg, memo: &mut [Vec<Option<(Tau, NumChg, Vari, Val)>>], data: &Ipt) -> Result<(Tau, NumChg, Vari, Val), CalcDpError> {
-        Self::check_idx_memo(t, k, memo)?;
-        
-        // k=0なら再帰の末尾．別処理
-        if *k == 0 {
-            return match Self::get_from_memo(t, k, memo)? {
-                Some(v) => Ok(v),
-                None => {
-                    let (vari_0t, val_0t) = Self::calc_value_terminal(data, t)?;
-                    let res_tk = (0, 0, vari_0t, val_0t);
-                    Self::set_from_memo(t, res_tk, memo)
-                },
-            }
-        }
-
-        // k>0の場合
-        // ひとつ前の変化点$ \tau_{k-1} $ごとに評価値を計算
-        let mut vals = Vec::with_capacity((t - k) as usize);
-
-        for i in *k..*t {
-            let tpl_mk1 = match Self::get_from_memo(&i, &(*k-1), memo)? {
-                Some(v) => v,
-                None => Self::calc_memo(&i, &(*k-1), memo, data)?,
-            };
-            let vari_tk1 = tpl_mk1.2;
-            let max_k_1 = tpl_mk1.3;
-            let (vari_tt, val_tt) = Self::calc_value(data, &i, t, &vari_tk1)?;
-            let eval:Val = [max_k_1, val_tt].into_iter()
+    fn calc_memo(t: &Tau, k: &NumChg, memo: &mut [VariMemoRow<Vari, Val>], data: &Ipt) -> Result<VariMemoEntry<Vari, Val>, CalcDpError> {
+        let terminal = |t| {
+            let (vari_0t, val_0t) = Self::calc_value_terminal(data, &t)?;
+            Ok((0, 0, vari_0t, dp_core::comparable(val_0t, 0, t)?))
+        };
+        // 区間の評価値が一つ前の変化点の変数に依存するため，候補ごとに計算する
+        let evaluate = |prevs, t, k| dp_core::map_candidates(prevs, |i, prev: (Tau, NumChg, Vari, Val)| {
+            let (vari_tt, val_tt) = Self::calc_value(data, &i, &t, &prev.2)?;
+            let eval: Val = [prev.3, dp_core::comparable(val_tt, i, t)?].into_iter()
                                             .sum();
-            let res_tk = (i, *k, vari_tt, eval);
-            vals.push(res_tk);
-        }
+            Ok((i, k, vari_tt, eval))
+        });
+        dp_core::fill::<MinGap1, Val, _, _, _>(*t, *k, memo, &terminal, &evaluate)
+    }
+
 
-        // 評価値最大のものを選択
-        let op_max_val = vals.iter()
-                             .reduce(|acc, val| {
-                                if acc.3 <= val.3 {
-                                    val
-                                } else {
-                                    acc
-                                }
-                            });
-        let max_val;
-        match op_max_val {
-            Some(v) => max_val = v,
-            None => return Err( CalcDpError{
-                message: "Failed to compute dynamic programming memo.".to_owned()
-            }),
+    /// 候補ごとの評価値を並列に計算しながら，動的計画法を用いて評価値を計算する
+    ///
+    /// 結果は[`Self::calc_memo`]と一致する．
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    /// * `memo` - 動的計画法の計算に用いるメモ
+    /// * `data` - 計算に必要な入力値
+    fn calc_memo_par(t: &Tau, k: &NumChg, memo: &mut [VariMemoRow<Vari, Val>], data: &Ipt) -> Result<VariMemoEntry<Vari, Val>, CalcDpError> where
+        Val: std::marker::Send,
+        Vari: std::marker::Send,
+        Ipt: std::marker::Sync
+    {
+        let terminal = |t| {
+            let (vari_0t, val_0t) = Self::calc_value_terminal(data, &t)?;
+            Ok((0, 0, vari_0t, dp_core::comparable(val_0t, 0, t)?))
         };
-       
-        Self::set_from_memo(t, max_val.clone(), memo)
+        let evaluate = |prevs, t, k| dp_core::map_candidates_par(prevs, |i, prev: VariMemoEntry<Vari, Val>| {
+            let (vari_tt, val_tt) = Self::calc_value(data, &i, &t, &prev.2)?;
+            let eval: Val = [prev.3, dp_core::comparable(val_tt, i, t)?].into_iter()
+                                            .sum();
+            Ok((i, k, vari_tt, eval))
+        });
+        dp_core::fill::<MinGap1, Val, _, _, _>(*t, *k, memo, &terminal, &evaluate)
     }
 
         
@@ -658,7 +791,6 @@ pub trait CalcDPWithVari<Val, Vari, Ipt> where
     /// # 返り値
     /// * `k_max` - 変化点個数の最大値
     fn calc_max_k(t_max: &Tau) -> NumChg {
-        // 天井関数の代わりに整数の割り算では余りが切り捨てられることを利用
-        (*t_max - 1) as NumChg
+        dp_core::max_k::<MinGap1>(*t_max)
     }
 }
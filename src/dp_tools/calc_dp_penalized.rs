@@ -0,0 +1,102 @@
+//! 変化点個数を固定しないペナルティ付きの動的計画法(Optimal partitioning)を用いた評価値計算のためのプログラム集
+//!
+//! # 想定する問題
+//! 変化点1個ごとにペナルティ$ p = -\beta $を加えた$ \sum_{k=1}^{K+1} f(t_{k-1}, t_k) - \beta K $を最大とする変化点を，
+//! 変化点個数$ K $を固定せずに求める．区間の最小の長さは[`super::calc_dp`]と同じく1とする．
+//!
+//! [`super::calc_dp::CalcDP`]は変化点個数ごとにメモを計算するため，ペナルティ付きの最適解を得るには全ての$ k $を計算する必要がある．
+//! 本moduleは時点$ t $についての1回の走査（$ O(T^2) $回の評価値の計算）で最適解を求める．
+//! 候補の枝刈りにより計算量を抑える場合は[`super::calc_pelt`]を用いる．
+
+use super::CalcDpError;
+use super::calc_dp::CalcTT;
+use super::dp_core;
+
+use std::fmt::Debug;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+
+/// ペナルティ付きの最適な評価値を変化点個数を固定せずに計算する
+///
+/// $ F(t) = \max_{s < t} \{ F(s) + f(s, t) + p [s > 0] \} $，$ F(0) = 0 $を$ t = 1, \dots, T $の順に計算する．
+/// 全ての候補$ s $を調べるため，区間の評価値に対する仮定を必要としない．
+///
+/// 評価値の型`Val`は減算を要求しないため，ペナルティ$ \beta $は符号を反転した$ p = -\beta $として与える．
+///
+/// # 計算に用いるメモについて
+/// ([`Tau`], [`NumChg`], `Val`)を要素とし，時点$ t $の値を`memo[t]`に格納する1次元ベクトル．
+/// 順に(`一つ前の変化点`, `変化点個数`, `ペナルティを含む評価値`$ F(t) $)で成り立つ．
+/// `memo[0]`は`(0, 0, 0)`とする．
+///
+/// # 利用するジェネリクス型
+/// * `Val` - 計算結果の値の型
+/// * `Ipt` - 計算に用いるデータの型
+pub trait CalcDpPenalized<Val, Ipt>: CalcTT<Val, Ipt> where
+    Val: std::iter::Sum + std::cmp::PartialOrd + Clone + std::marker::Send + Debug,
+    Ipt: std::marker::Sync
+{
+    /// すべての時点の評価値を格納したメモを作成
+    ///
+    /// 1点のみの区間の評価値を用いるため，最初に[`CalcTT::check_single_point`]を呼び出す．
+    ///
+    /// # 引数
+    /// * `data` - 計算に必要な入力値
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    /// * `penalty` - 変化点1個ごとに加える評価値$ p = -\beta $
+    fn calc_memo_all(data: &Ipt, t_max: &Tau, penalty: &Val) -> Result<Vec<(Tau, NumChg, Val)>, CalcDpError> {
+        Self::check_single_point(data)?;
+        dp_core::penalized(*t_max, penalty, None, "penalized DP", |batch| Self::calc_values_batch(data, batch))
+    }
+
+
+    /// 動的計画法の計算に用いたメモを返す
+    ///
+    /// # 注意
+    /// [`Self::calc_memo_all`]の返り値を返してください．
+    fn memo_all(&self) -> Vec<(Tau, NumChg, Val)>;
+
+
+    /// ペナルティを含む評価値$ F(t) $を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    fn get_value(&self, t: &Tau) -> Result<Val, CalcDpError> {
+        dp_core::penalized_change_points(*t, &self.memo_all()).map(|(_, value)| value)
+    }
+
+
+    /// 最適な変化点個数を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    fn get_num_changes(&self, t: &Tau) -> Result<NumChg, CalcDpError> {
+        self.get_change_points(t).map(|change_points| change_points.len() as NumChg)
+    }
+
+
+    /// 最適な変化点を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    ///
+    /// # 返り値
+    /// * `change_points` - 期数`t`までの区間における，昇順に並んだ変化点
+    fn get_change_points(&self, t: &Tau) -> Result<Vec<Tau>, CalcDpError> {
+        self.get_change_points_with_value(t).map(|(change_points, _)| change_points)
+    }
+
+
+    /// 最適な変化点とペナルティを含む評価値を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    ///
+    /// # 返り値
+    /// * `change_points` - 期数`t`までの区間における，昇順に並んだ変化点
+    /// * `value` - ペナルティを含む評価値$ F(t) $
+    fn get_change_points_with_value(&self, t: &Tau) -> Result<(Vec<Tau>, Val), CalcDpError> {
+        dp_core::penalized_change_points(*t, &self.memo_all())
+    }
+}
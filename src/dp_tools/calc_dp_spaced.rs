@@ -0,0 +1,329 @@
+//! 変化点の最低間隔を型引数とする動的計画法(DP)を用いた評価値計算のためのプログラム集
+//!
+//! # 想定する問題
+//! 2個の連続した変化点$ t_k, t_{k-1} $（$ t_{k-1} > 0 $）の間にデータが$ M $個以上，すなわち$ t_k - t_{k-1} \ge M $であるとする．
+//! 最初の区間$ (0, t_1] $のみは長さ1以上であればよい．
+//! 評価値$ f(t_k, t_{k-1} | \bm{X}) $とその総和については[`super::calc_dp`]と同じである．
+//!
+//! [`super::calc_dp::CalcDP`]および[`super::calc_dp_2::CalcDP`]は，本moduleの関数を$ M = 1, 2 $で呼び出す薄い層であり，
+//! それぞれ[`CalcDpSpaced`]の$ M = 1, 2 $とメモの配置を含めて同じ結果を返す．
+//! 評価値の計算に用いる変数を持つ動的計画法は，引き続き[`super::calc_dp::CalcDPWithVari`]等を用いる．
+
+use super::{CalcDpError, CostTable, MemoEntry, MemoRow, Memo};
+use super::calc_dp::CalcTT;
+use super::dp_core::{self, MinGap};
+
+use std::fmt::Debug;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+
+/// 最低間隔`M`に対して変化点の順序を確認する
+///
+/// # 引数
+/// * `t_k_1` - 前の変化点 $t_{k-1}$
+/// * `t_k` - 後ろの変化点 $t_k$
+pub fn order_change_point<const M: usize>(t_k_1: &Tau, t_k: &Tau) -> Result<(), CalcDpError> {
+    // 最初の区間のみ長さ1を許す
+    let gap = if *t_k_1 == 0 { 1 } else { <MinGap<M> as dp_core::Layout>::MIN_GAP };
+    if t_k.checked_sub(*t_k_1).is_some_and(|d| d >= gap) {
+        Ok(())
+    } else {
+        Err(CalcDpError::new(format!("Index tau_{{k}} (={t_k}) must be at least tau_{{k-1}} + {gap} (= {t_k_1}+{gap}).")))
+    }
+}
+
+
+/// 最低間隔`M`のメモを確保し，変化点個数ごとに最後の期数の値を計算する
+///
+/// # 引数
+/// * `t_max` - 変化点の最大値（最後の時期）
+/// * `calc_memo` - 期数，変化点個数およびメモから，メモの値を計算する関数
+pub(crate) fn build_memo<E, const M: usize, F>(t_max: &Tau, mut calc_memo: F) -> Result<Vec<Vec<Option<E>>>, CalcDpError>
+where
+    E: Clone,
+    F: FnMut(&Tau, &NumChg, &mut [Vec<Option<E>>]) -> Result<E, CalcDpError>,
+{
+    let k_max = dp_core::max_k::<MinGap<M>>(*t_max);
+    let mut memo = dp_core::allocate::<MinGap<M>, _>(*t_max, k_max)?;
+
+    // メモを計算
+    if *t_max > 0 {
+        for k in 0..=k_max {
+            calc_memo(t_max, &k, &mut memo)?;
+        }
+    }
+
+    Ok(memo)
+}
+
+
+/// 最低間隔`M`の動的計画法を用いて評価値を計算する
+///
+/// 期数`t`に至る区間の評価値は`eval`でまとめて計算する．
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点個数
+/// * `memo` - 動的計画法の計算に用いるメモ
+/// * `terminal` - 区間$ (0, t] $の評価値を計算する関数
+/// * `eval` - 区間$ (t_{k-1}, t_k] $の列から評価値の列を計算する関数
+pub(crate) fn fill_memo<Val, const M: usize, T, B>(t: &Tau, k: &NumChg, memo: &mut [MemoRow<Val>], terminal: T, eval: B) -> Result<MemoEntry<Val>, CalcDpError>
+where
+    Val: std::iter::Sum + PartialOrd + Clone + Debug,
+    T: Fn(Tau) -> Result<Val, CalcDpError>,
+    B: Fn(&[(Tau, Tau)]) -> Result<Vec<Val>, CalcDpError>,
+{
+    let terminal = |t| Ok((0, 0, dp_core::comparable(terminal(t)?, 0, t)?));
+    let evaluate = |prevs: dp_core::Candidates<MemoEntry<Val>>, t, k| {
+        let pairs = prevs.iter().map(|(i, _)| (*i, t)).collect::<Vec<(Tau, Tau)>>();
+        let vals_tt = eval(&pairs)?;
+        Ok(prevs.into_iter()
+                .zip(vals_tt)
+                .map(|((i, prev), val_tt)| (i, k, [prev.2, val_tt].into_iter().sum()))
+                .collect())
+    };
+    dp_core::fill::<MinGap<M>, Val, _, _, _>(*t, *k, memo, &terminal, &evaluate)
+}
+
+
+/// 事前に計算した評価値の表を用いて，最低間隔`M`のすべての評価値を格納したメモを作成
+///
+/// # 引数
+/// * `table` - 評価値の表
+/// * `t_max` - 変化点の最大値（最後の時期）．表の`t_max`以下とする．
+pub(crate) fn memo_from_table<Val, const M: usize>(table: &CostTable<Val>, t_max: &Tau) -> Result<Memo<Val>, CalcDpError>
+where
+    Val: std::iter::Sum + PartialOrd + Clone + Debug,
+{
+    table.check_t_max(*t_max)?;
+    let terminal = |t| Ok((0, 0, dp_core::comparable(table.get(0, t)?.clone(), 0, t)?));
+    let evaluate = |prevs: dp_core::Candidates<MemoEntry<Val>>, t, k| {
+        prevs.into_iter()
+             .map(|(i, prev)| Ok((i, k, [prev.2, dp_core::comparable(table.get(i, t)?.clone(), i, t)?].into_iter().sum())))
+             .collect()
+    };
+    build_memo::<_, M, _>(t_max, |t, k, memo| dp_core::fill::<MinGap<M>, Val, _, _, _>(*t, *k, memo, &terminal, &evaluate))
+}
+
+
+/// 最低間隔`M`の動的計画法で評価値を計算する
+///
+/// 区間の評価値は[`CalcTT::calc_value`]で計算するため，[`super::calc_dp::CalcDP`]を実装した型は
+/// `impl<const M: usize> CalcDpSpaced<Val, Ipt, M> for T {...}`により全ての最低間隔で本traitを利用できる．
+///
+/// # 計算に用いるメモについて
+/// ([`Tau`], [`NumChg`], `Val`)を要素とする2次元ベクトル．
+/// 順に(`一つ前の期数`, `現在の変化点個数`, `現時点での評価値`)で成り立つ．
+/// 1次元目が変化点個数$ k $，2次元目が時期であり，期数$ t $の値は`memo[k][t - (M k + 1)]`に格納される．
+/// ただし$ M = 2 $では[`super::calc_dp_2`]と同じく`memo[k][t - 2 k]`に格納される．
+///
+/// # 利用するジェネリクス型
+/// * `Val` - 計算結果の値の型
+/// * `Ipt` - 計算に用いるデータの型
+/// * `M` - 2個目以降の区間の最小の長さ（1以上）
+pub trait CalcDpSpaced<Val, Ipt, const M: usize>: CalcTT<Val, Ipt> where
+    Val: std::iter::Sum + std::cmp::PartialOrd + Clone + Debug,
+{
+    /// 動的計画法によりすべての評価値を格納したメモを作成
+    ///
+    /// `M = 1`の場合のみ，1点のみの区間の評価値を用いるため最初に[`CalcTT::check_single_point`]を呼び出す．
+    ///
+    /// # 引数
+    /// * `data` - 計算に必要な入力値
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    fn calc_memo_all(data: &Ipt, t_max: &Tau) -> Result<Memo<Val>, CalcDpError> {
+        if M == 1 {
+            Self::check_single_point(data)?;
+        }
+        build_memo::<_, M, _>(t_max, |t, k, memo| Self::calc_memo(t, k, memo, data))
+    }
+
+
+    /// 区間の評価値を並列に計算して，すべての評価値を格納したメモを作成
+    ///
+    /// 評価値が等しい候補の選び方を含めて[`Self::calc_memo_all`]と同じメモが得られる．
+    ///
+    /// # 引数
+    /// * `data` - 計算に必要な入力値
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    fn calc_memo_all_par(data: &Ipt, t_max: &Tau) -> Result<Memo<Val>, CalcDpError> where
+        Val: std::marker::Send,
+        Ipt: std::marker::Sync
+    {
+        if M == 1 {
+            Self::check_single_point(data)?;
+        }
+        build_memo::<_, M, _>(t_max, |t, k, memo| Self::calc_memo_par(t, k, memo, data))
+    }
+
+
+    /// 事前に計算した評価値の表を用いて，すべての評価値を格納したメモを作成
+    ///
+    /// [`CalcTT::calc_value`]を呼び出さずに，[`Self::calc_memo_all`]と同じメモを作成する．
+    ///
+    /// # 引数
+    /// * `table` - 評価値の表
+    /// * `t_max` - 変化点の最大値（最後の時期）．表の`t_max`以下とする．
+    fn calc_memo_all_from_table(table: &CostTable<Val>, t_max: &Tau) -> Result<Memo<Val>, CalcDpError> {
+        memo_from_table::<_, M>(table, t_max)
+    }
+
+
+    /// 動的計画法の計算に用いたメモを返す
+    ///
+    /// # 注意
+    /// [`Self::calc_memo_all`]または[`Self::calc_memo_all_par`]の返り値を返してください．
+    fn memo_all(&self) -> Memo<Val>;
+
+
+    /// 評価値の推移を取得
+    ///
+    /// 指定された変化点と変化回数から，その評価値等を計算に用いた中間地点の評価値等とともに出力する．
+    /// `k`が0（変化なし）の場合は区間$ (0, t] $の要素`(0, 0, 評価値)`のみを返す．
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    fn get_value_history(&self, t: &Tau, k: &NumChg) -> Result<Vec<MemoEntry<Val>>, CalcDpError> {
+        dp_core::history::<MinGap<M>, Val, _>(*t, *k, &self.memo_all())
+    }
+
+
+    /// 最適な変化点を取得
+    ///
+    /// [`Self::get_value_history`]の結果を遡り，変化点を昇順に並べて返す．
+    /// 評価値も必要な場合は[`Self::get_change_points_with_value`]を用いる．
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    ///
+    /// # 返り値
+    /// * `change_points` - 昇順に並んだ`k`個の変化点
+    fn get_change_points(&self, t: &Tau, k: &NumChg) -> Result<Vec<Tau>, CalcDpError> {
+        self.get_change_points_with_value(t, k).map(|(change_points, _)| change_points)
+    }
+
+
+    /// 最適な変化点とその評価値を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    ///
+    /// # 返り値
+    /// * `change_points` - 昇順に並んだ`k`個の変化点
+    /// * `value` - 期数`t`までの評価値（[`Self::get_value`]と同じ）
+    fn get_change_points_with_value(&self, t: &Tau, k: &NumChg) -> Result<(Vec<Tau>, Val), CalcDpError> {
+        dp_core::change_points::<MinGap<M>, Val, _>(*t, *k, &self.memo_all())
+    }
+
+
+    /// 評価値を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    fn get_value(&self, t: &Tau, k: &NumChg) -> Result<Val, CalcDpError> {
+        match Self::get_from_memo(t, k, &self.memo_all())? {
+            Some(v) => Ok(v.2),
+            None => Err(CalcDpError::new("Value has not calculated yet.")),
+        }
+    }
+
+
+    /// memoに対してインデックスtおよびkが正しいか確認
+    ///
+    /// 期数$ t $に$ k $個の変化点を置くには$ t \ge M k + 1 $を要する．
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    /// * `memo` - 動的計画法の計算に用いるメモ
+    fn check_idx_memo(t: &Tau, k: &NumChg, memo: &[MemoRow<Val>]) -> Result<(), CalcDpError> {
+        dp_core::check::<MinGap<M>, _>(*t, *k, memo).map(|_| ())
+    }
+
+
+    /// メモから値を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    /// * `memo` - 動的計画法の計算に用いるメモ
+    fn get_from_memo(t: &Tau, k: &NumChg, memo: &[MemoRow<Val>]) -> Result<Option<MemoEntry<Val>>, CalcDpError> {
+        dp_core::get::<MinGap<M>, _>(*t, *k, memo)
+    }
+
+
+    /// 添字を検査せずにメモから値を取得
+    ///
+    /// [`Self::check_idx_memo`]による検査はdebugビルドでのみ行う．
+    /// 範囲外の添字に対する結果は保証しない（releaseビルドではパニックするか誤った値を返す）．
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    /// * `memo` - 動的計画法の計算に用いるメモ
+    fn get_from_memo_unchecked(t: &Tau, k: &NumChg, memo: &[MemoRow<Val>]) -> Option<MemoEntry<Val>> {
+        dp_core::get_unchecked::<MinGap<M>, _>(*t, *k, memo)
+    }
+
+
+    /// メモに値をセット
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `val` - メモの要素（変化点個数は要素のものを用いる）
+    /// * `memo` - 動的計画法の計算に用いるメモ
+    fn set_from_memo(t: &Tau, val: MemoEntry<Val>, memo: &mut [MemoRow<Val>]) -> Result<MemoEntry<Val>, CalcDpError> {
+        dp_core::set::<MinGap<M>, Val, _>(*t, val, memo)
+    }
+
+
+    /// 動的計画法を用いて評価値を計算する
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    /// * `memo` - 動的計画法の計算に用いるメモ
+    /// * `data` - 計算に必要な入力値
+    fn calc_memo(t: &Tau, k: &NumChg, memo: &mut [MemoRow<Val>], data: &Ipt) -> Result<MemoEntry<Val>, CalcDpError> {
+        fill_memo::<_, M, _, _>(t, k, memo,
+                                |t| Self::calc_value(data, 0, t),
+                                |pairs| dp_core::eval_batches(pairs, |batch| Self::calc_values_batch(data, batch)))
+    }
+
+
+    /// 区間の評価値を並列に計算しながら，動的計画法を用いて評価値を計算する
+    ///
+    /// 結果は[`Self::calc_memo`]と一致する．
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    /// * `k` - 計算する変化点個数
+    /// * `memo` - 動的計画法の計算に用いるメモ
+    /// * `data` - 計算に必要な入力値
+    fn calc_memo_par(t: &Tau, k: &NumChg, memo: &mut [MemoRow<Val>], data: &Ipt) -> Result<MemoEntry<Val>, CalcDpError> where
+        Val: std::marker::Send,
+        Ipt: std::marker::Sync
+    {
+        fill_memo::<_, M, _, _>(t, k, memo,
+                                |t| Self::calc_value(data, 0, t),
+                                |pairs| dp_core::eval_batches_par(pairs, |batch| Self::calc_values_batch(data, batch)))
+    }
+
+
+    /// Kの最大値$ \lfloor (t_{max} - 1) / M \rfloor $を計算
+    ///
+    /// # 引数
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    ///
+    /// # 返り値
+    /// * `k_max` - 変化点個数の最大値
+    fn calc_max_k(t_max: &Tau) -> NumChg {
+        dp_core::max_k::<MinGap<M>>(*t_max)
+    }
+}
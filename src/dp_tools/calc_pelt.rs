@@ -0,0 +1,99 @@
+//! PELT(Pruned exact linear time)による枝刈りを用いたペナルティ付き評価値計算のためのプログラム集
+//!
+//! # 想定する問題
+//! 変化点の個数を固定せず，変化点1個ごとにペナルティ$ p $（通常は$ p = -\beta < 0 $）を加えた
+//! $ \sum_{k=1}^{K+1} f(t_{k-1}, t_k) + K p $を最大とする変化点を求める．
+//! 区間の最小の長さは[`super::calc_dp`]と同じく1とする．
+//!
+//! 変化点個数ごとにメモを持つ[`super::calc_dp::CalcDP`]の$ O(T^2 K) $に対し，
+//! 最適とならない直前の変化点の候補を枝刈りするため，多くの系列でほぼ$ O(T) $回の評価値の計算で済む．
+//! 枝刈りを行わない計算は[`super::calc_dp_penalized`]，評価値が`f64`のコスト関数については[`crate::search::pelt`]も参照．
+
+use super::CalcDpError;
+use crate::cost::SegmentCost;
+use super::calc_dp::CalcTT;
+use super::dp_core;
+
+use std::fmt::Debug;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+
+/// PELTにより最適なペナルティ付きの評価値を計算する
+///
+/// # 枝刈りについて
+/// 評価値の総和を$ F(t) = \max_{s < t} \{ F(s) + f(s, t) + p [s > 0] \} $，$ F(0) = 0 $により計算する．
+/// 全ての$ s < t < T $について区間を分割した際の評価値が
+/// $ f(s, t) + f(t, T) \ge f(s, T) + K $を満たす定数$ K $が存在するとき，
+/// $ F(s) + f(s, t) + p [s > 0] < F(t) + p + K $となった候補$ s $は時点$ t $以降で最適とならないため除く．
+/// $ K $はコスト関数の性質であるため，入力値の[`SegmentCost::pruning_constant`]を用いる．
+/// 評価値が`f64::NEG_INFINITY`となった候補（区間が短すぎる場合等）は，以降の時点で有限の評価値を持ち得るため除かない．
+///
+/// # 計算に用いるメモについて
+/// ([`Tau`], [`NumChg`], `Val`)を要素とし，時点$ t $の値を`memo[t]`に格納する1次元ベクトル．
+/// 順に(`一つ前の変化点`, `変化点個数`, `ペナルティを含む評価値`$ F(t) $)で成り立つ．
+/// `memo[0]`は`(0, 0, 0)`とする．
+///
+/// # 利用するジェネリクス型
+/// * `Val` - 計算結果の値の型
+/// * `Ipt` - 計算に用いるコスト関数の型
+pub trait CalcPelt<Val, Ipt>: CalcTT<Val, Ipt> where
+    Val: std::iter::Sum + std::cmp::PartialOrd + Clone + std::marker::Send + Debug + From<f64>,
+    Ipt: SegmentCost
+{
+    /// PELTによりすべての時点の評価値を格納したメモを作成
+    ///
+    /// 1点のみの区間の評価値を用いるため，最初に[`CalcTT::check_single_point`]を呼び出す．
+    ///
+    /// # 引数
+    /// * `data` - 計算に必要な入力値
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    /// * `penalty` - 変化点1個ごとに加える評価値$ p $
+    fn calc_memo_all(data: &Ipt, t_max: &Tau, penalty: &Val) -> Result<Vec<(Tau, NumChg, Val)>, CalcDpError> {
+        Self::check_single_point(data)?;
+        let pruning = dp_core::Pruning::new(data.pruning_constant());
+        dp_core::penalized(*t_max, penalty, Some(&pruning), "PELT", |batch| Self::calc_values_batch(data, batch))
+    }
+
+
+    /// PELTの計算に用いたメモを返す
+    ///
+    /// # 注意
+    /// [`Self::calc_memo_all`]の返り値を返してください．
+    fn memo_all(&self) -> Vec<(Tau, NumChg, Val)>;
+
+
+    /// ペナルティを含む評価値$ F(t) $を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    fn get_value(&self, t: &Tau) -> Result<Val, CalcDpError> {
+        dp_core::penalized_change_points(*t, &self.memo_all()).map(|(_, value)| value)
+    }
+
+
+    /// 最適な変化点を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    ///
+    /// # 返り値
+    /// * `change_points` - 期数`t`までの区間における，昇順に並んだ変化点
+    fn get_change_points(&self, t: &Tau) -> Result<Vec<Tau>, CalcDpError> {
+        self.get_change_points_with_value(t).map(|(change_points, _)| change_points)
+    }
+
+
+    /// 最適な変化点とペナルティを含む評価値を取得
+    ///
+    /// # 引数
+    /// * `t` - 計算する期数
+    ///
+    /// # 返り値
+    /// * `change_points` - 期数`t`までの区間における，昇順に並んだ変化点
+    /// * `value` - ペナルティを含む評価値$ F(t) $
+    fn get_change_points_with_value(&self, t: &Tau) -> Result<(Vec<Tau>, Val), CalcDpError> {
+        dp_core::penalized_change_points(*t, &self.memo_all())
+    }
+}
@@ -0,0 +1,95 @@
+//! 倍精度浮動小数点数2個による拡張精度（double-double）の評価値
+
+use std::cmp::Ordering;
+use std::iter::Sum;
+use std::ops::Add;
+
+
+/// 倍精度浮動小数点数2個の和$ hi + lo $で表す拡張精度（約106ビット）の評価値
+///
+/// 動的計画法では区間の評価値の和をメモへ累積するため，系列が数百万点に及ぶと丸め誤差の累積により
+/// 僅差の候補の大小が入れ替わり，異なる変化点が選ばれることがある．
+/// 評価値の型に本型を用いると，区間の評価値は`f64`のまま，累積のみをTwoSumによる誤差のない加算で行う．
+/// 本crateのコスト関数は本型を評価値とする`CalcTT`を実装する．
+///
+/// $ hi $は$ hi + lo $を`f64`へ丸めた値であり，$ |lo| $は$ hi $の最下位桁の半分以下となるよう正規化する．
+/// 無限大を含む和は$ lo = 0 $とし，評価値を定義できない区間（$ -\infty $）も扱える．
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct F64x2 {
+    hi: f64,
+    lo: f64,
+}
+
+impl F64x2 {
+    /// 上位と下位の成分から作成する
+    ///
+    /// # 引数
+    /// * `hi` - 上位の成分
+    /// * `lo` - 下位の成分
+    pub fn new(hi: f64, lo: f64) -> Self {
+        let (hi, lo) = two_sum(hi, lo);
+        F64x2 { hi, lo }
+    }
+
+
+    /// 上位の成分
+    pub fn hi(&self) -> f64 {
+        self.hi
+    }
+
+
+    /// 下位の成分
+    pub fn lo(&self) -> f64 {
+        self.lo
+    }
+
+
+    /// `f64`へ丸めた値
+    pub fn to_f64(&self) -> f64 {
+        self.hi
+    }
+}
+
+impl From<f64> for F64x2 {
+    fn from(val: f64) -> Self {
+        F64x2 { hi: val, lo: 0.0 }
+    }
+}
+
+impl PartialOrd for F64x2 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.hi.partial_cmp(&other.hi)? {
+            Ordering::Equal => self.lo.partial_cmp(&other.lo),
+            ord => Some(ord),
+        }
+    }
+}
+
+impl Add for F64x2 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let (s, e) = two_sum(self.hi, other.hi);
+        let (hi, lo) = two_sum(s, e + self.lo + other.lo);
+        F64x2 { hi, lo }
+    }
+}
+
+impl Sum for F64x2 {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(F64x2::default(), |acc, v| acc + v)
+    }
+}
+
+
+/// 誤差のない加算（Knuth TwoSum）
+///
+/// $ a + b = s + e $を満たす$ s = fl(a + b) $と丸め誤差$ e $を返す．和が有限でない場合は$ e = 0 $とする．
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    if !s.is_finite() {
+        return (s, 0.0);
+    }
+    let bb = s - a;
+    (s, (a - (s - bb)) + (b - bb))
+}
@@ -0,0 +1,988 @@
+//! 区間の最小の長さによらない動的計画法の共通部分
+//!
+//! [`super::calc_dp`]および[`super::calc_dp_2`]は，区間の最小の長さ$ g $（それぞれ1および2）のみが異なる．
+//! 任意の$ g $は[`super::calc_dp_spaced`]が扱う．
+//! メモおよび評価値の表の配置，添字の検査，漸化式の計算ならびに遡りは本moduleに集約し，
+//! 各moduleのtraitは[`Layout`]を指定して本moduleの関数を呼び出す．
+//!
+//! # メモの配置
+//! 変化点個数$ k $の行は期数$ g k + r $から始まり，期数$ t $の値は`memo[k][t - (g k + r)]`に格納される．
+//! ここで$ r $は[`Layout::ROW_START`]である．
+//! 2個目以降の区間の長さは$ g $以上，最初の区間の長さは1以上であるため，$ k $個の変化点を置ける最小の期数は$ g k + 1 $となる．
+//!
+//! # 区間の長さの制限
+//! 区間の長さを[`Band`]の範囲に制限する場合（[`fill_banded`]），期数$ t $における一つ前の変化点の候補は
+//! $ [t - \ell_{max}, t - \ell_{min}] $に限られる．
+//! 候補の個数は$ B = \ell_{max} - \ell_{min} + 1 $以下となり，計算量は$ O(K T^2) $から$ O(K T B) $となる．
+//!
+//! # ペナルティ付きの問題
+//! 変化点個数を固定しない[`super::calc_dp_penalized`]および[`super::calc_pelt`]は，
+//! 時点$ t $の値を`memo[t]`に格納する1次元のメモを用いる（[`penalized`]）．
+//! [`crate::search::pelt`]も同じ計算（[`penalized_search`]）を用いる．
+
+use super::{AddContext, CalcDpError, ErrorContext};
+use crate::index::{self, Index};
+use crate::search::PruneStats;
+
+use std::fmt::Debug;
+use std::ops::{Range, RangeInclusive};
+use std::time::Instant;
+
+extern crate rayon;
+use rayon::prelude::*;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+#[cfg(feature = "trace")]
+extern crate log;
+
+
+/// 区間の最小の長さとメモの配置
+pub(crate) trait Layout {
+    /// 2個目以降の区間の最小の長さ$ g $
+    const MIN_GAP: Tau;
+    /// メモの各行の先頭の期数$ g k + r $における$ r $
+    const ROW_START: Tau;
+}
+
+
+/// 区間の最小の長さ$ g = M $の配置（[`super::calc_dp_spaced`]）
+///
+/// 行$ k $は$ k $個の変化点を置ける最小の期数$ M k + 1 $から始まる．
+/// ただし$ M = 2 $は[`super::calc_dp_2`]のメモと揃えるため期数$ 2 k $から始まり，行の先頭（$ k \ge 1 $）は用いない．
+pub(crate) struct MinGap<const M: usize>;
+
+impl<const M: usize> Layout for MinGap<M> {
+    const MIN_GAP: Tau = {
+        assert!(M >= 1, "Minimum spacing must be at least 1.");
+        M as Tau
+    };
+    const ROW_START: Tau = if M == 2 { 0 } else { 1 };
+}
+
+
+/// [`super::calc_dp`]の配置
+pub(crate) type MinGap1 = MinGap<1>;
+
+
+/// [`super::calc_dp_2`]の配置
+pub(crate) type MinGap2 = MinGap<2>;
+
+
+/// 動的計画法のメモの要素
+pub(crate) trait Entry<Val> {
+    /// 一つ前の変化点
+    fn prev(&self) -> Tau;
+    /// 変化点個数
+    fn k(&self) -> NumChg;
+    /// 評価値
+    fn value(&self) -> &Val;
+}
+
+impl<Val> Entry<Val> for (Tau, NumChg, Val) {
+    fn prev(&self) -> Tau {
+        self.0
+    }
+
+    fn k(&self) -> NumChg {
+        self.1
+    }
+
+    fn value(&self) -> &Val {
+        &self.2
+    }
+}
+
+/// 評価値の計算に用いる変数を含むメモの要素
+pub(crate) type VariEntry<Vari, Val> = (Tau, NumChg, Vari, Val);
+
+impl<Vari, Val> Entry<Val> for VariEntry<Vari, Val> {
+    fn prev(&self) -> Tau {
+        self.0
+    }
+
+    fn k(&self) -> NumChg {
+        self.1
+    }
+
+    fn value(&self) -> &Val {
+        &self.3
+    }
+}
+
+
+/// 期数$ t $までに置ける変化点個数の最大値$ \lfloor (t - 1) / g \rfloor $
+///
+/// # 引数
+/// * `t` - 期数
+pub(crate) fn max_k<L: Layout>(t: Tau) -> NumChg {
+    (t.saturating_sub(1) / L::MIN_GAP) as NumChg
+}
+
+
+/// 変化点個数`k`の行の先頭の期数
+fn row_start<L: Layout>(k: NumChg) -> Result<Tau, CalcDpError> {
+    index::add(index::mul(k, L::MIN_GAP)?, L::ROW_START)
+}
+
+
+/// メモにおける期数`t`，変化点個数`k`の位置
+///
+/// # 引数
+/// * `t` - 期数
+/// * `k` - 変化点個数
+pub(crate) fn position<L: Layout>(t: Tau, k: NumChg) -> Result<(usize, usize), CalcDpError> {
+    Ok((index::to_usize(k)?, index::to_usize(index::sub(t, row_start::<L>(k)?)?)?))
+}
+
+
+/// 各行（変化点個数）の要素数
+///
+/// # 引数
+/// * `t_max` - 変化点の最大値（最後の時期）
+/// * `k_lim` - 確保する変化点個数の上限．これを超える行は要素数0とする．
+fn row_lens<L: Layout>(t_max: Tau, k_lim: NumChg) -> Result<Vec<usize>, CalcDpError> {
+    if t_max == 0 {
+        return Ok(Vec::new());
+    }
+    (0..=max_k::<L>(t_max)).map(|k| {
+                               if k > k_lim {
+                                   return Ok(0);
+                               }
+                               let len = index::add(index::sub(t_max, row_start::<L>(k)?)?, 1u8)?;
+                               index::to_usize(len)
+                           })
+                           .collect()
+}
+
+
+/// 空のメモを確保する
+///
+/// # 引数
+/// * `t_max` - 変化点の最大値（最後の時期）
+/// * `k_lim` - 確保する変化点個数の上限．これを超える行は空とする．
+pub(crate) fn allocate<L: Layout, E: Clone>(t_max: Tau, k_lim: NumChg) -> Result<Vec<Vec<Option<E>>>, CalcDpError> {
+    Ok(row_lens::<L>(t_max, k_lim)?.into_iter()
+                                   .map(|len| vec![None; len])
+                                   .collect())
+}
+
+
+/// メモの格納方法
+///
+/// 添字`(i, j)`は[`position`]が返す（行，列）の位置である．
+/// [`check`]，[`get`]および[`set`]は本traitを通じてメモを参照するため，動的計画法の計算はメモの格納方法によらない．
+pub(crate) trait MemoStorage<E> {
+    /// 行`i`の要素数．確保していない行は0とする．
+    fn row_len(&self, i: usize) -> usize;
+
+    /// 位置`(i, j)`の要素．計算していない場合は`None`．
+    fn get_at(&self, i: usize, j: usize) -> Option<E>;
+
+    /// 位置`(i, j)`に要素をセットする
+    fn set_at(&mut self, i: usize, j: usize, val: E);
+}
+
+impl<E: Clone> MemoStorage<E> for [Vec<Option<E>>] {
+    fn row_len(&self, i: usize) -> usize {
+        self.get(i).map_or(0, |row| row.len())
+    }
+
+    fn get_at(&self, i: usize, j: usize) -> Option<E> {
+        self[i][j].clone()
+    }
+
+    fn set_at(&mut self, i: usize, j: usize, val: E) {
+        self[i][j] = Some(val);
+    }
+}
+
+impl<E: Clone> MemoStorage<E> for Vec<Vec<Option<E>>> {
+    fn row_len(&self, i: usize) -> usize {
+        self.as_slice().row_len(i)
+    }
+
+    fn get_at(&self, i: usize, j: usize) -> Option<E> {
+        self.as_slice().get_at(i, j)
+    }
+
+    fn set_at(&mut self, i: usize, j: usize, val: E) {
+        self.as_mut_slice().set_at(i, j, val)
+    }
+}
+
+
+/// 一つ前の変化点，変化点個数および評価値を別々の配列に格納したメモ
+///
+/// 要素ごとに`Option<(Tau, NumChg, Val)>`を持つ場合，判別子と整列のための詰め物により要素の大きさが増え，
+/// 評価値のみを走査する場合も他の値を読み込む．本型は全ての行を連結した上で値の種類ごとに配列を分け，
+/// 計算済みであるかは1要素1bitのビット列に記録する．行`i`の要素は配列の`offsets[i]..offsets[i + 1]`に位置する．
+#[derive(Debug, Clone)]
+pub(crate) struct SoaMemo<Val> {
+    offsets: Vec<usize>,
+    filled: Vec<u64>,
+    prev: Vec<Tau>,
+    k: Vec<NumChg>,
+    val: Vec<Val>,
+}
+
+impl<Val: Clone + Default> SoaMemo<Val> {
+    /// 空のメモを確保する
+    ///
+    /// 行の構成は[`allocate`]と同じである．
+    ///
+    /// # 引数
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    /// * `k_lim` - 確保する変化点個数の上限．これを超える行は空とする．
+    pub(crate) fn allocate<L: Layout>(t_max: Tau, k_lim: NumChg) -> Result<Self, CalcDpError> {
+        let mut offsets = vec![0];
+        for len in row_lens::<L>(t_max, k_lim)? {
+            offsets.push(offsets[offsets.len() - 1] + len);
+        }
+        let n = offsets[offsets.len() - 1];
+        Ok(SoaMemo {
+            offsets,
+            filled: vec![0; n.div_ceil(u64::BITS as usize)],
+            prev: vec![0; n],
+            k: vec![0; n],
+            val: vec![Val::default(); n],
+        })
+    }
+}
+
+impl<Val: Clone> SoaMemo<Val> {
+    /// 配列の位置`idx`の要素が計算済みか
+    fn is_filled(&self, idx: usize) -> bool {
+        self.filled[idx / u64::BITS as usize] >> (idx % u64::BITS as usize) & 1 == 1
+    }
+
+
+    /// 確保した各行の最後の期数（`t_max`）における評価値
+    ///
+    /// 変化点個数の昇順に並び，確保していない行は含まない．計算していない要素は`None`とする．
+    /// 各行の最後の要素のみを評価値の配列から読み込むため，ペナルティによる変化点個数の選択等で全ての変化点個数を走査する場合に用いる．
+    pub(crate) fn last_values(&self) -> Vec<Option<Val>> {
+        self.offsets.windows(2)
+                    .filter(|w| w[1] > w[0])
+                    .map(|w| self.is_filled(w[1] - 1).then(|| self.val[w[1] - 1].clone()))
+                    .collect()
+    }
+}
+
+impl<Val: Clone> MemoStorage<(Tau, NumChg, Val)> for SoaMemo<Val> {
+    fn row_len(&self, i: usize) -> usize {
+        match (self.offsets.get(i), self.offsets.get(i + 1)) {
+            (Some(start), Some(end)) => end - start,
+            _ => 0,
+        }
+    }
+
+    fn get_at(&self, i: usize, j: usize) -> Option<(Tau, NumChg, Val)> {
+        let idx = self.offsets[i] + j;
+        self.is_filled(idx).then(|| (self.prev[idx], self.k[idx], self.val[idx].clone()))
+    }
+
+    fn set_at(&mut self, i: usize, j: usize, val: (Tau, NumChg, Val)) {
+        let idx = self.offsets[i] + j;
+        self.filled[idx / u64::BITS as usize] |= 1 << (idx % u64::BITS as usize);
+        self.prev[idx] = val.0;
+        self.k[idx] = val.1;
+        self.val[idx] = val.2;
+    }
+}
+
+
+/// memoに対してインデックスtおよびkが正しいか確認
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点個数
+/// * `memo` - 動的計画法の計算に用いるメモ
+pub(crate) fn check<L: Layout, E>(t: Tau, k: NumChg, memo: &(impl MemoStorage<E> + ?Sized)) -> Result<(usize, usize), CalcDpError> {
+    if t == 0 {
+        return Err(CalcDpError::new("Time step must be greater than 0"));
+    }
+
+    let k_lim = max_k::<L>(t);
+    if k > k_lim {
+        return Err(CalcDpError::new(format!("The number of change point k (= {k}) must not exceed {k_lim} at time step t (= {t}).")));
+    }
+
+    let (i, j) = position::<L>(t, k)?;
+    if j >= memo.row_len(i) {
+        return Err(CalcDpError::new(format!("Time step t = {t} is out of range.")));
+    }
+    Ok((i, j))
+}
+
+
+/// メモから値を取得
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点個数
+/// * `memo` - 動的計画法の計算に用いるメモ
+pub(crate) fn get<L: Layout, E: Clone>(t: Tau, k: NumChg, memo: &(impl MemoStorage<E> + ?Sized)) -> Result<Option<E>, CalcDpError> {
+    let (i, j) = check::<L, E>(t, k, memo)?;
+    Ok(memo.get_at(i, j))
+}
+
+
+/// 添字を検査せずにメモから値を取得
+///
+/// [`check`]および桁あふれを検査する添字の計算はdebugビルドでのみ行う．
+/// 動的計画法の内側のループのように，呼び出し側で`(t, k)`がメモの範囲内であることを一度確認した後の参照に用いる．
+/// 範囲外の添字を与えた場合，releaseビルドでは誤った位置の値を返すか，配列の範囲外参照としてパニックする．
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点個数
+/// * `memo` - 動的計画法の計算に用いるメモ
+pub(crate) fn get_unchecked<L: Layout, E: Clone>(t: Tau, k: NumChg, memo: &(impl MemoStorage<E> + ?Sized)) -> Option<E> {
+    debug_assert!(check::<L, E>(t, k, memo).is_ok(), "Memo index (t = {t}, k = {k}) is out of range.");
+    let j = t.widen() - k.widen() * L::MIN_GAP.widen() - L::ROW_START.widen();
+    memo.get_at(k.widen() as usize, j as usize)
+}
+
+
+/// メモに値をセット
+///
+/// 変化点個数は`val`のものを用いる．
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `val` - メモの要素
+/// * `memo` - 動的計画法の計算に用いるメモ
+pub(crate) fn set<L: Layout, Val, E: Entry<Val> + Clone>(t: Tau, val: E, memo: &mut (impl MemoStorage<E> + ?Sized)) -> Result<E, CalcDpError> {
+    let (i, j) = check::<L, E>(t, val.k(), memo)?;
+    memo.set_at(i, j, val.clone());
+    Ok(val)
+}
+
+
+/// 期数`t`に`k`個目の変化点を置く場合の，一つ前の変化点の候補
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点個数（1以上）
+pub(crate) fn candidates<L: Layout>(t: Tau, k: NumChg) -> Result<Range<Tau>, CalcDpError> {
+    let lo = index::add(index::mul(index::sub(k, 1u8)?, L::MIN_GAP)?, 1u8)?;
+    let hi = index::add(index::sub(t, L::MIN_GAP)?, 1u8)?;
+    Ok(lo..hi)
+}
+
+
+/// 区間の長さの範囲$ [\ell_{min}, \ell_{max}] $
+///
+/// 最初の区間を含む全ての区間に課す．2個目以降の区間には[`Layout::MIN_GAP`]による下限も課される．
+/// 系列の端が開いている場合，その端に接する区間には課さない．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Band {
+    /// 区間の長さの最小値$ \ell_{min} $（1以上）
+    pub(crate) min: Tau,
+    /// 区間の長さの最大値$ \ell_{max} $
+    pub(crate) max: Tau,
+    /// 始端（時点0）が開いているか
+    pub(crate) open_start: bool,
+    /// 終端が開いている場合の最後の時期
+    pub(crate) open_end: Option<Tau>,
+}
+
+impl Band {
+    /// 区間$ (t_{k-1}, t_k] $が範囲内か
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub(crate) fn admits(&self, t_k_1: Tau, t_k: Tau) -> bool {
+        let len = t_k - t_k_1;
+        (t_k_1 == 0 && self.open_start) || self.open_end == Some(t_k) || (self.min <= len && len <= self.max)
+    }
+}
+
+
+/// 区間の長さを制限した場合の，一つ前の変化点の候補
+///
+/// [`candidates`]のうち，区間$ (i, t] $が`band`の範囲内（[`Band::admits`]）であり，
+/// かつ始端が閉じている場合は$ k $個の区間で$ i $までを覆える（$ i \le k \ell_{max} $）ものに限る．
+/// 候補が無い場合は空の範囲を返す．
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点個数（1以上）
+/// * `band` - 区間の長さの範囲．`None`の場合は制限しない．
+pub(crate) fn banded_candidates<L: Layout>(t: Tau, k: NumChg, band: Option<Band>) -> Result<Range<Tau>, CalcDpError> {
+    let range = candidates::<L>(t, k)?;
+    let band = match band {
+        Some(band) => band,
+        None => return Ok(range),
+    };
+    let (mut lo, mut hi) = (range.start, range.end);
+    if band.open_end != Some(t) {
+        lo = lo.max(t.saturating_sub(band.max));
+        hi = hi.min(t.checked_sub(band.min).map_or(0, |i| i + 1));
+    }
+    if !band.open_start {
+        hi = hi.min(index::mul(band.max, k).map_or(range.end, |i| i.saturating_add(1)));
+    }
+    Ok(lo..hi.max(lo))
+}
+
+
+/// 一つ前の変化点の候補をこの個数以上含む場合に，候補ごとの評価値を並列に計算する
+///
+/// 区間の評価値をまとめて計算する場合（[`eval_batches`]）は，1回にまとめる区間の個数の上限でもある．
+pub(crate) const PAR_CUTOFF: usize = 256;
+
+
+/// 一つ前の変化点の候補
+///
+/// 候補となる変化点と，そこまでの$ k - 1 $個の変化点に対するメモの要素の組．
+pub(crate) type Candidates<E> = Vec<(Tau, E)>;
+
+
+/// 動的計画法を用いて評価値を計算する
+///
+/// 必要な$ k - 1 $の値がメモにない場合は再帰的に計算する．
+/// 最大値の選択は候補の順に逐次行うため，`evaluate`を並列に計算しても結果は変わらない．
+/// 評価値が等しい候補が複数ある場合は，一つ前の変化点が最も後ろのものを選ぶ．
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点個数
+/// * `memo` - 動的計画法の計算に用いるメモ
+/// * `terminal` - $ k = 0 $における期数`t`の要素を計算する関数
+/// * `evaluate` - 一つ前の変化点の候補から，候補ごとに期数`t`，変化点個数`k`の要素を計算する関数
+pub(crate) fn fill<L, Val, E, T, S>(t: Tau, k: NumChg, memo: &mut (impl MemoStorage<E> + ?Sized), terminal: &T, evaluate: &S) -> Result<E, CalcDpError>
+where
+    L: Layout,
+    Val: PartialOrd,
+    E: Entry<Val> + Clone,
+    T: Fn(Tau) -> Result<E, CalcDpError>,
+    S: Fn(Candidates<E>, Tau, NumChg) -> Result<Vec<E>, CalcDpError>,
+{
+    fill_banded::<L, Val, E, T, S>(t, k, memo, None, terminal, evaluate)
+}
+
+
+/// 区間の長さを制限して動的計画法を用いて評価値を計算する
+///
+/// 一つ前の変化点の候補を[`banded_candidates`]に限る以外は[`fill`]と同じである．
+/// 候補が無い場合も`evaluate`を空の候補で呼び出すため，実行不可能であることを表す要素は`evaluate`が返す．
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点個数
+/// * `memo` - 動的計画法の計算に用いるメモ
+/// * `band` - 区間の長さの範囲．`None`の場合は制限しない．
+/// * `terminal` - $ k = 0 $における期数`t`の要素を計算する関数
+/// * `evaluate` - 一つ前の変化点の候補から，候補ごとに期数`t`，変化点個数`k`の要素を計算する関数
+pub(crate) fn fill_banded<L, Val, E, T, S>(t: Tau, k: NumChg, memo: &mut (impl MemoStorage<E> + ?Sized), band: Option<Band>, terminal: &T, evaluate: &S) -> Result<E, CalcDpError>
+where
+    L: Layout,
+    Val: PartialOrd,
+    E: Entry<Val> + Clone,
+    T: Fn(Tau) -> Result<E, CalcDpError>,
+    S: Fn(Candidates<E>, Tau, NumChg) -> Result<Vec<E>, CalcDpError>,
+{
+    let context = || ErrorContext::new("filling DP memo").at(t, k);
+    // 以降の参照は，この検査により範囲内であることが保証される
+    check::<L, E>(t, k, memo).add_context(context)?;
+
+    // k=0なら再帰の末尾．別処理
+    if k == 0 {
+        return match get_unchecked::<L, E>(t, k, memo) {
+            Some(v) => Ok(v),
+            None => set::<L, Val, E>(t, terminal(t).add_context(context)?, memo),
+        }
+    }
+
+    // ひとつ前の変化点$ \tau_{k-1} $の値を確定させる．再帰はメモを更新するため逐次に行う．
+    // 候補$ i \in [g (k - 1) + 1, t - g] $は行$ k - 1 $の範囲内であり，行$ k $が確保されていれば行$ k - 1 $も確保されている．
+    let range = banded_candidates::<L>(t, k, band).add_context(context)?;
+    let mut prevs = Vec::with_capacity(range.len());
+    for i in range {
+        let prev = match get_unchecked::<L, E>(i, k - 1, memo) {
+            Some(v) => v,
+            None => fill_banded::<L, Val, E, T, S>(i, k - 1, memo, band, terminal, evaluate)?,
+        };
+        prevs.push((i, prev));
+    }
+
+    // 評価値最大のものを選択
+    let mut max_val: Option<E> = None;
+    for cand in evaluate(prevs, t, k).add_context(context)? {
+        if max_val.as_ref().is_none_or(|acc| acc.value() <= cand.value()) {
+            max_val = Some(cand);
+        }
+    }
+
+    match max_val {
+        Some(v) => set::<L, Val, E>(t, v, memo),
+        None => Err(CalcDpError::new("Failed to compute dynamic programming memo.").with_context(context())),
+    }
+}
+
+
+/// 候補ごとに要素を計算する
+///
+/// 候補の順に逐次計算する．並列に計算する場合は[`map_candidates_par`]を用いる．
+///
+/// # 引数
+/// * `prevs` - 一つ前の変化点の候補
+/// * `step` - 候補となる変化点とその要素から要素を計算する関数
+pub(crate) fn map_candidates<E, F>(prevs: Candidates<E>, step: F) -> Result<Vec<E>, CalcDpError>
+where
+    F: Fn(Tau, E) -> Result<E, CalcDpError>,
+{
+    prevs.into_iter().map(|(i, prev)| step(i, prev)).collect()
+}
+
+
+/// 候補ごとに要素を並列に計算する
+///
+/// 候補が[`PAR_CUTOFF`]個以上の場合はrayonで並列に計算し，それ未満では[`map_candidates`]と同じく逐次計算する．
+/// 結果は候補の順に並ぶ．
+///
+/// # 引数
+/// * `prevs` - 一つ前の変化点の候補
+/// * `step` - 候補となる変化点とその要素から要素を計算する関数
+pub(crate) fn map_candidates_par<E, F>(prevs: Candidates<E>, step: F) -> Result<Vec<E>, CalcDpError>
+where
+    E: Send,
+    F: Fn(Tau, E) -> Result<E, CalcDpError> + Sync,
+{
+    if prevs.len() < PAR_CUTOFF {
+        map_candidates(prevs, step)
+    } else {
+        prevs.into_par_iter().map(|(i, prev)| step(i, prev)).collect()
+    }
+}
+
+
+/// 区間の評価値を`batch`で計算し，個数と比較可能かを確認する
+///
+/// # 引数
+/// * `chunk` - 区間$ (t_{k-1}, t_k] $の列
+/// * `batch` - 区間の列から評価値の列を計算する関数
+fn eval_chunk<Val, F>(chunk: &[(Tau, Tau)], batch: &F) -> Result<Vec<Val>, CalcDpError>
+where
+    Val: PartialOrd + Debug,
+    F: Fn(&[(Tau, Tau)]) -> Result<Vec<Val>, CalcDpError>,
+{
+    let vals = batch(chunk)?;
+    if vals.len() != chunk.len() {
+        return Err(CalcDpError::new(format!("Batch evaluation returned {} values for {} segments.", vals.len(), chunk.len())));
+    }
+    vals.into_iter()
+        .zip(chunk)
+        .map(|(val, (t_k_1, t_k))| comparable(val, *t_k_1, *t_k))
+        .collect()
+}
+
+
+/// 区間の評価値をまとめて計算する
+///
+/// 区間を[`PAR_CUTOFF`]個ずつに分け，それぞれを`batch`の1回の呼び出しで逐次計算する．結果は区間の順に並ぶ．
+/// 並列に計算する場合は[`eval_batches_par`]を用いる．
+///
+/// # 引数
+/// * `pairs` - 区間$ (t_{k-1}, t_k] $の列
+/// * `batch` - 区間の列から評価値の列を計算する関数
+pub(crate) fn eval_batches<Val, F>(pairs: &[(Tau, Tau)], batch: F) -> Result<Vec<Val>, CalcDpError>
+where
+    Val: PartialOrd + Debug,
+    F: Fn(&[(Tau, Tau)]) -> Result<Vec<Val>, CalcDpError>,
+{
+    let mut vals = Vec::with_capacity(pairs.len());
+    for chunk in pairs.chunks(PAR_CUTOFF) {
+        vals.extend(eval_chunk(chunk, &batch)?);
+    }
+    Ok(vals)
+}
+
+
+/// 区間の評価値をまとめて並列に計算する
+///
+/// [`eval_batches`]と同じく区間を[`PAR_CUTOFF`]個ずつに分け，複数に分かれる場合はrayonで並列に計算する．
+/// 結果は区間の順に並び，[`eval_batches`]と一致する．
+///
+/// # 引数
+/// * `pairs` - 区間$ (t_{k-1}, t_k] $の列
+/// * `batch` - 区間の列から評価値の列を計算する関数
+pub(crate) fn eval_batches_par<Val, F>(pairs: &[(Tau, Tau)], batch: F) -> Result<Vec<Val>, CalcDpError>
+where
+    Val: Send + PartialOrd + Debug,
+    F: Fn(&[(Tau, Tau)]) -> Result<Vec<Val>, CalcDpError> + Sync,
+{
+    if pairs.len() <= PAR_CUTOFF {
+        eval_chunk(pairs, &batch)
+    } else {
+        let chunks = pairs.par_chunks(PAR_CUTOFF)
+                          .map(|chunk| eval_chunk(chunk, &batch))
+                          .collect::<Result<Vec<Vec<Val>>, CalcDpError>>()?;
+        Ok(chunks.into_iter().flatten().collect())
+    }
+}
+
+
+/// 区間$ (t_{k-1}, t_k] $の評価値が比較可能か確認する
+///
+/// 自身と比較できない値（浮動小数点数のNaN等）がメモへ混入すると，[`fill`]における`PartialOrd`による最大値の選択が意味を失い，
+/// 誤った変化点が得られる．そのような値は区間を示すエラーとする．
+///
+/// # 引数
+/// * `val` - 評価値
+/// * `t_k_1` - 前の変化点 $t_{k-1}$
+/// * `t_k` - 後ろの変化点 $t_k$
+pub(crate) fn comparable<Val: PartialOrd + Debug>(val: Val, t_k_1: Tau, t_k: Tau) -> Result<Val, CalcDpError> {
+    if val.partial_cmp(&val).is_none() {
+        Err(CalcDpError::new(format!("Cost returned an incomparable value ({val:?}) for segment ({t_k_1}, {t_k}].")))
+    } else {
+        Ok(val)
+    }
+}
+
+
+/// 区間$ (t_{k-1}, t_k] $の`f64`の評価値を確認する
+///
+/// [`comparable`]に加えて$ +\infty $もエラーとする．
+/// 評価値を定義できない区間を表す$ -\infty $は許す．
+///
+/// # 引数
+/// * `val` - 評価値
+/// * `t_k_1` - 前の変化点 $t_{k-1}$
+/// * `t_k` - 後ろの変化点 $t_k$
+pub(crate) fn finite_or_infeasible(val: f64, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+    if val == f64::INFINITY {
+        Err(CalcDpError::new(format!("Cost returned +inf for segment ({t_k_1}, {t_k}].")))
+    } else {
+        comparable(val, t_k_1, t_k)
+    }
+}
+
+
+/// 評価値の推移を遡る形で取得
+///
+/// 順番は変化点数に対して降順．
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点数
+/// * `memo` - 動的計画法の計算に用いるメモ
+pub(crate) fn history<L: Layout, Val, E: Entry<Val> + Clone>(t: Tau, k: NumChg, memo: &[Vec<Option<E>>]) -> Result<Vec<E>, CalcDpError> {
+    let mut now_t = t;
+    let mut now_k = k;
+    let mut res = Vec::new();
+
+    while now_t > 0 {
+        let context = || ErrorContext::new("tracing back change points").at(now_t, now_k);
+        let memo_tk = match get::<L, E>(now_t, now_k, memo).add_context(context)? {
+            Some(v) => v,
+            // 値が設定されていない場合はエラーとなる．
+            None => return Err(CalcDpError::new("Uncalculated value exist.").with_context(context())),
+        };
+
+        now_t = memo_tk.prev();
+        if memo_tk.k() != 0 {
+            now_k = memo_tk.k() - 1;
+        }
+        res.push(memo_tk);
+    }
+    Ok(res)
+}
+
+
+/// 最適な変化点とその評価値をメモから遡って取得
+///
+/// [`history`]の各要素の一つ前の変化点を昇順に並べ替えたものを変化点とする．
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点数
+/// * `memo` - 動的計画法の計算に用いるメモ
+///
+/// # 返り値
+/// * `change_points` - 昇順に並んだ$ k $個の変化点
+/// * `value` - 期数`t`までの評価値
+pub(crate) fn change_points<L: Layout, Val: Clone, E: Entry<Val> + Clone>(t: Tau, k: NumChg, memo: &[Vec<Option<E>>]) -> Result<(Vec<Tau>, Val), CalcDpError> {
+    let hist = history::<L, Val, E>(t, k, memo)?;
+    let value = match hist.first() {
+        Some(v) => v.value().clone(),
+        None => return Err(CalcDpError::new(format!("No change points exist up to t = {t}."))),
+    };
+    // 最初の区間の要素（k = 0）の一つ前の期数は0であり，変化点ではない
+    let change_points = hist.iter()
+                            .rev()
+                            .filter(|v| v.k() != 0)
+                            .map(|v| v.prev())
+                            .collect();
+    Ok((change_points, value))
+}
+
+
+/// 評価値の推移を前方から順に取得
+///
+/// 順番は変化点数に対して昇順．
+/// 各要素の期数は一つ前の変化点ではなく区間の最終時点（$ \tau_k $）とする．
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `k` - 計算する変化点数
+/// * `memo` - 動的計画法の計算に用いるメモ
+pub(crate) fn history_forward<L, Vari, Val>(t: Tau, k: NumChg, memo: &[Vec<Option<VariEntry<Vari, Val>>>]) -> Result<Vec<VariEntry<Vari, Val>>, CalcDpError>
+where
+    L: Layout,
+    Vari: Clone,
+    Val: Clone,
+{
+    let mut now_t = t;
+    let mut now_k = index::add(k, 1u8)?; // 参照時に-1して利用するため
+    let mut res = Vec::new(); // このベクタには逆順にアイテムを追加する．
+
+    while now_t > 0 {
+        let context = || ErrorContext::new("tracing back change points").at(now_t, now_k - 1);
+        match get::<L, _>(now_t, now_k - 1, memo).add_context(context)? {
+            None => {
+                // 値が設定されていない場合はエラーとなる．
+                return Err(CalcDpError::new("Uncalculated value exist.").with_context(context()));
+            },
+            Some((next_t, next_k, vari, val)) => {
+                res.push((now_t, now_k, vari, val));
+                now_t = next_t;
+                now_k = next_k;
+            },
+        }
+    }
+    res.reverse(); // 変化点数に対して昇順になるよう順番を逆転．
+
+    Ok(res)
+}
+
+
+/// 評価値の表の行数
+///
+/// # 引数
+/// * `t_max` - 変化点の最大値（最後の時期）
+pub(crate) fn table_rows<L: Layout>(t_max: Tau) -> Result<Tau, CalcDpError> {
+    index::add(index::sub(t_max, L::MIN_GAP)?, 1u8)
+}
+
+
+/// 評価値の表の行`t_k_1`における後ろの変化点の範囲
+///
+/// # 引数
+/// * `t_k_1` - 前の変化点 $t_{k-1}$
+/// * `t_max` - 変化点の最大値（最後の時期）
+pub(crate) fn table_row<L: Layout>(t_k_1: Tau, t_max: Tau) -> RangeInclusive<Tau> {
+    (t_k_1 + L::MIN_GAP)..=t_max
+}
+
+
+/// 評価値の表における区間$ (t_{k-1}, t_k] $の位置
+///
+/// # 引数
+/// * `t_k_1` - 前の変化点 $t_{k-1}$
+/// * `t_k` - 後ろの変化点 $t_k$
+pub(crate) fn table_position<L: Layout>(t_k_1: Tau, t_k: Tau) -> Result<(usize, usize), CalcDpError> {
+    let offset = index::sub(index::sub(t_k, t_k_1)?, L::MIN_GAP)?;
+    Ok((index::to_usize(t_k_1)?, index::to_usize(offset)?))
+}
+
+
+/// ペナルティ付きの評価値の計算における枝刈りの設定
+pub(crate) struct Pruning<Val> {
+    /// 枝刈りに用いる定数$ K $
+    pub bound: Val,
+    /// 評価値が定義できないことを表す値．この値となった候補からは枝刈りの判断をせず，この値となった時点は候補に加えない．
+    pub infeasible: Val,
+}
+
+impl<Val: From<f64>> Pruning<Val> {
+    /// 評価値が定義できないことを`f64::NEG_INFINITY`で表す設定
+    ///
+    /// # 引数
+    /// * `bound` - 枝刈りに用いる定数$ K $
+    pub(crate) fn new(bound: f64) -> Self {
+        Pruning { bound: Val::from(bound), infeasible: Val::from(f64::NEG_INFINITY) }
+    }
+}
+
+
+/// ペナルティ付きの評価値の計算の設定
+pub(crate) struct PenalizedSearch<'a, Val> {
+    /// 各区間の最小の長さ（1以上）
+    pub min_size: Tau,
+    /// 枝刈りの設定．`None`の場合は枝刈りしない．
+    pub pruning: Option<&'a Pruning<Val>>,
+    /// 期限．`None`の場合は期限を設けない．
+    pub deadline: Option<Instant>,
+}
+
+
+/// ペナルティ付きの評価値の計算結果
+pub(crate) struct PenalizedMemo<Val> {
+    /// 時点$ t $の(`一つ前の変化点`, `変化点個数`, $ F(t) $)．最小の長さを満たす候補が無い時点は`None`とする．
+    pub memo: Vec<Option<(Tau, NumChg, Val)>>,
+    /// 枝刈りの集計
+    pub stats: PruneStats,
+}
+
+
+/// ペナルティ付きの評価値を時点順に計算する
+///
+/// $ F(t) = \max_{s < t} \{ F(s) + f(s, t) + p [s > 0] \} $，$ F(0) = 0 $を$ t = 1, \dots, T $の順に計算し，
+/// `memo[t]`に(`一つ前の変化点`, `変化点個数`, $ F(t) $)を格納する．
+/// 区間の長さが`min_size`未満となる候補$ s $は時点$ t $では評価せず，以降の時点のために残す．
+/// `pruning`を与えた場合は，$ F(s) + f(s, t) + p [s > 0] < F(t) + p + K $となった候補$ s $を以降の時点の候補から除く．
+/// 短い区間等で評価値が定義できない候補は，以降の時点で定義できる可能性があるため除かない．
+///
+/// feature `trace`を有効にした場合，枝刈りした候補と用いた不等式を`log`のtraceレベルで出力する．
+///
+/// # 引数
+/// * `t_max` - 変化点の最大値（最後の時期）
+/// * `penalty` - 変化点1個ごとに加える評価値$ p $
+/// * `search` - 区間の最小の長さ，枝刈りおよび期限の設定
+/// * `stage` - エラーに記録する計算の段階
+/// * `batch` - 区間の列から評価値の列を計算する関数
+///
+/// # 返り値
+/// 期限を過ぎた場合は`None`を返す．
+pub(crate) fn penalized_search<Val, F>(t_max: Tau, penalty: &Val, search: &PenalizedSearch<Val>, stage: &'static str, batch: F) -> Result<Option<PenalizedMemo<Val>>, CalcDpError>
+where
+    Val: std::iter::Sum + PartialOrd + Clone + Send + Debug,
+    F: Fn(&[(Tau, Tau)]) -> Result<Vec<Val>, CalcDpError> + Sync,
+{
+    if t_max == 0 {
+        return Err(CalcDpError::new("Series must contain at least one point."));
+    }
+    if search.min_size == 0 {
+        return Err(CalcDpError::new("Minimum segment length must be at least 1."));
+    }
+
+    let mut memo: Vec<Option<(Tau, NumChg, Val)>> = Vec::with_capacity(index::to_usize(t_max)? + 1);
+    memo.push(Some((0, 0, std::iter::empty::<Val>().sum())));
+    let mut candidates: Vec<Tau> = vec![0];
+    let mut stats = PruneStats::default();
+    for t in 1..=t_max {
+        if search.deadline.is_some_and(|d| Instant::now() >= d) {
+            return Ok(None);
+        }
+        let context = || ErrorContext::new(stage).time(t);
+        // 候補は昇順に並ぶため，最小の長さを満たす候補は先頭から連続する
+        let eligible = candidates.partition_point(|s| *s + search.min_size <= t);
+        if eligible == 0 {
+            memo.push(None);
+            continue;
+        }
+        let pairs = candidates[..eligible].iter().map(|s| (*s, t)).collect::<Vec<(Tau, Tau)>>();
+        let vals_tt = eval_batches_par(&pairs, &batch).add_context(context)?;
+        stats.evaluations += eligible as u64;
+        let values = candidates.iter()
+                               .zip(vals_tt)
+                               .map(|(s, val_tt)| {
+                                   if *s == 0 {
+                                       Ok(val_tt)
+                                   } else {
+                                       let (_, _, f_s) = memo[index::to_usize(*s)?].as_ref().expect("Only feasible time points become candidates");
+                                       Ok([f_s.clone(), val_tt, penalty.clone()].into_iter().sum())
+                                   }
+                               })
+                               .collect::<Result<Vec<Val>, CalcDpError>>()
+                               .add_context(context)?;
+
+        let mut best = 0;
+        for (i, v) in values.iter().enumerate().skip(1) {
+            if *v > values[best] {
+                best = i;
+            }
+        }
+        let prev = candidates[best];
+        let k = match &memo[index::to_usize(prev)?] {
+            Some((_, k_prev, _)) if prev > 0 => index::add(*k_prev, 1u8).add_context(context)?,
+            _ => 0,
+        };
+        let f_t = values[best].clone();
+
+        // 以降の時点で最適とならない候補を除く
+        match search.pruning {
+            Some(pruning) => {
+                let threshold: Val = [f_t.clone(), penalty.clone(), pruning.bound.clone()].into_iter().sum();
+                let before = candidates.len();
+                let mut values = values.into_iter();
+                candidates.retain(|_s| {
+                    // 最小の長さを満たさず評価していない候補は残す
+                    let Some(v) = values.next() else {
+                        return true;
+                    };
+                    let prune = v != pruning.infeasible && v < threshold;
+                    #[cfg(feature = "trace")]
+                    if prune {
+                        log::trace!(target: "cpd_tools::pelt", "prune s={_s} at t={t}: F(s) + f(s, t) + p = {v:?} < F(t) + p + K = {threshold:?}");
+                    }
+                    !prune
+                });
+                stats.pruned += (before - candidates.len()) as u64;
+                if f_t != pruning.infeasible {
+                    candidates.push(t);
+                }
+            },
+            None => candidates.push(t),
+        }
+        stats.max_candidates = stats.max_candidates.max(candidates.len());
+        memo.push(Some((prev, k, f_t)));
+    }
+    stats.final_candidates = candidates.len();
+
+    Ok(Some(PenalizedMemo { memo, stats }))
+}
+
+
+/// 区間の最小の長さを1としてペナルティ付きの評価値を時点順に計算する
+///
+/// [`penalized_search`]を期限を設けずに呼び出し，全ての時点の値を格納したメモを返す．
+///
+/// # 引数
+/// * `t_max` - 変化点の最大値（最後の時期）
+/// * `penalty` - 変化点1個ごとに加える評価値$ p $
+/// * `pruning` - 枝刈りの設定．`None`の場合は枝刈りしない．
+/// * `stage` - エラーに記録する計算の段階
+/// * `batch` - 区間の列から評価値の列を計算する関数
+pub(crate) fn penalized<Val, F>(t_max: Tau, penalty: &Val, pruning: Option<&Pruning<Val>>, stage: &'static str, batch: F) -> Result<Vec<(Tau, NumChg, Val)>, CalcDpError>
+where
+    Val: std::iter::Sum + PartialOrd + Clone + Send + Debug,
+    F: Fn(&[(Tau, Tau)]) -> Result<Vec<Val>, CalcDpError> + Sync,
+{
+    let search = PenalizedSearch { min_size: 1, pruning, deadline: None };
+    let found = penalized_search(t_max, penalty, &search, stage, batch)?.expect("No deadline is given");
+    // 区間の最小の長さが1の場合，時点0は常に候補となるため全ての時点の値が定まる
+    Ok(found.memo.into_iter().map(|entry| entry.expect("Every time point has a candidate")).collect())
+}
+
+
+/// ペナルティ付きのメモから最適な変化点を遡って取得
+///
+/// # 引数
+/// * `t` - 計算する期数
+/// * `memo` - [`penalized`]で作成したメモ
+///
+/// # 返り値
+/// * `change_points` - 期数`t`までの区間における，昇順に並んだ変化点
+/// * `value` - ペナルティを含む評価値$ F(t) $
+pub(crate) fn penalized_change_points<Val: Clone>(t: Tau, memo: &[(Tau, NumChg, Val)]) -> Result<(Vec<Tau>, Val), CalcDpError> {
+    let (mut now_t, _, value) = match memo.get(index::to_usize(t)?) {
+        Some(v) => v.clone(),
+        None => return Err(CalcDpError::new(format!("Index t (={t}) is out of range of the memo."))),
+    };
+    let mut change_points = Vec::new();
+    while now_t > 0 {
+        change_points.push(now_t);
+        now_t = match memo.get(index::to_usize(now_t)?) {
+            Some(v) => v.0,
+            None => return Err(CalcDpError::new(format!("Index t (={now_t}) is out of range of the memo."))),
+        };
+    }
+    change_points.reverse();
+    Ok((change_points, value))
+}
@@ -0,0 +1,74 @@
+//! 全順序を持つ評価値
+
+use std::cmp::Ordering;
+use std::iter::Sum;
+use std::ops::Add;
+
+
+/// `total_cmp`による全順序で比較する浮動小数点数の評価値
+///
+/// `f64`の`PartialOrd`ではNaNがいずれの値とも比較できないため，NaNを含むと動的計画法（[`super::calc_dp::CalcDP::calc_memo`]等）における
+/// 最大値の選択が候補の順序に依存した無意味な結果となる．
+/// 本型は[`f64::total_cmp`]で比較するため常に比較でき，選択は候補の順序によらず定まる．
+/// 全順序では正のNaNは$ +\infty $より大きく，負のNaNは$ -\infty $より小さい．また$ -0 $は$ +0 $より小さい．
+///
+/// 本crateのコスト関数は`f64`に加えて`OrdVal<f64>`を評価値とする`CalcTT`を実装する．
+/// `PartialOrd`のみを実装した独自の評価値の型も引き続き用いることができる．
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrdVal<T>(pub T);
+
+impl<T> OrdVal<T> {
+    /// 内部の値
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for OrdVal<T> {
+    fn from(val: T) -> Self {
+        OrdVal(val)
+    }
+}
+
+
+/// 浮動小数点数の型に対して`OrdVal`の比較および演算を実装する
+macro_rules! impl_ord_val {
+    ($t:ty) => {
+        impl PartialEq for OrdVal<$t> {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl Eq for OrdVal<$t> {}
+
+        impl PartialOrd for OrdVal<$t> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for OrdVal<$t> {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+
+        impl Add for OrdVal<$t> {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                OrdVal(self.0 + other.0)
+            }
+        }
+
+        impl Sum for OrdVal<$t> {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                OrdVal(iter.map(|v| v.0).sum())
+            }
+        }
+    };
+}
+
+impl_ord_val!(f64);
+impl_ord_val!(f32);
@@ -0,0 +1,131 @@
+//! 区間の前後の余白を参照できる系列データ
+
+use super::CalcDpError;
+use crate::index;
+
+use std::ops::Range;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// `CalcTT::calc_value`の入力として用いる系列データ
+///
+/// 区間$ (t_{k-1}, t_k] $（データのインデックス`t_{k-1}..t_k`）に加えて，前後`context`個の観測を余白として参照できる．
+/// カーネル法や自己回帰モデルのように，区間の端の評価に区間外の観測を要するコスト関数を想定している．
+/// 余白は系列の端で切り詰めるため，区間外の添字を直接計算する必要はない．
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeriesData<T> {
+    values: Vec<T>,
+    context: Tau,
+}
+
+impl<T> SeriesData<T> {
+    /// 余白を持たない系列データを作成
+    ///
+    /// # 引数
+    /// * `values` - 系列
+    pub fn new(values: Vec<T>) -> Result<Self, CalcDpError> {
+        Self::with_context(values, 0)
+    }
+
+
+    /// 余白の長さを指定して系列データを作成
+    ///
+    /// # 引数
+    /// * `values` - 系列
+    /// * `context` - 区間の前後に参照する観測の個数
+    pub fn with_context(values: Vec<T>, context: Tau) -> Result<Self, CalcDpError> {
+        Tau::try_from(values.len()).map_err(|_| CalcDpError::new(format!("Series of length {} is too long.", values.len())))?;
+        Ok(SeriesData { values, context })
+    }
+
+
+    /// 系列の長さ（最後の時期）
+    pub fn t_max(&self) -> Tau {
+        // 長さはwith_contextで確認済み
+        self.values.len() as Tau
+    }
+
+
+    /// 区間の前後に参照する観測の個数
+    pub fn context(&self) -> Tau {
+        self.context
+    }
+
+
+    /// 系列全体
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $の観測
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn segment(&self, t_k_1: Tau, t_k: Tau) -> Result<&[T], CalcDpError> {
+        Ok(&self.values[self.range(t_k_1, t_k)?])
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $の観測と前後の余白
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn with_margin(&self, t_k_1: Tau, t_k: Tau) -> Result<ContextSlice<'_, T>, CalcDpError> {
+        let Range { start, end } = self.range(t_k_1, t_k)?;
+        let context = index::to_usize(self.context)?;
+        let first = start.saturating_sub(context);
+        let last = end.saturating_add(context).min(self.values.len());
+        Ok(ContextSlice {
+            before: &self.values[first..start],
+            segment: &self.values[start..end],
+            after: &self.values[end..last],
+        })
+    }
+
+
+    /// 区間に対応するデータのインデックス
+    fn range(&self, t_k_1: Tau, t_k: Tau) -> Result<Range<usize>, CalcDpError> {
+        if t_k_1 >= t_k || t_k > self.t_max() {
+            return Err(CalcDpError::new(format!("Segment ({t_k_1}, {t_k}] is out of the series (t_max = {}).", self.t_max())));
+        }
+        Ok(index::to_usize(t_k_1)?..index::to_usize(t_k)?)
+    }
+}
+
+
+/// 区間の観測と前後の余白（[`SeriesData::with_margin`]）
+///
+/// 余白は系列の端で切り詰められるため，`before`および`after`は`context`個より短い場合がある．
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContextSlice<'a, T> {
+    /// 区間の直前の観測（時間順）
+    pub before: &'a [T],
+    /// 区間の観測
+    pub segment: &'a [T],
+    /// 区間の直後の観測（時間順）
+    pub after: &'a [T],
+}
+
+impl<T> ContextSlice<'_, T> {
+    /// 前後の余白を含む観測の個数
+    pub fn len(&self) -> usize {
+        self.before.len() + self.segment.len() + self.after.len()
+    }
+
+
+    /// 観測を含まないか（区間は1個以上の観測を含むため，常に`false`）
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+
+    /// 前後の余白を含む観測を時間順に返す
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.before.iter().chain(self.segment).chain(self.after)
+    }
+}
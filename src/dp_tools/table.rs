@@ -0,0 +1,113 @@
+//! 事前に計算した評価値の表
+
+use super::{CalcDpError, QueryPolicy, TableStrategy, build_table};
+use crate::index;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 任意の区間$ (t_{k-1}, t_k] $の評価値を格納した表
+///
+/// 構造は[`super::calc_dp::DictTT::value_tt_all`]と同じであり，区間の評価値は`[t_{k-1}][t_k - (t_{k-1} + 1)]`に格納される．
+/// 長さ1の区間も含むため，[`super::calc_dp`]および[`super::calc_dp_2`]のいずれの動的計画法にも用いることができる．
+///
+/// 表の作成には$ O(T^2) $の記憶領域を要する．
+/// 一方，同じ系列に対して動的計画法を繰り返す場合は，`CalcDP::calc_memo_all_from_table`により評価値の再計算を省略できる．
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostTable<Val> {
+    values: Vec<Vec<Val>>,
+}
+
+impl<Val> CostTable<Val> {
+    /// 2次元配列から表を作成
+    ///
+    /// # 引数
+    /// * `values` - 評価値を格納した2次元配列（[`super::calc_dp::DictTT::value_tt_all`]と同じ構造）
+    pub fn new(values: Vec<Vec<Val>>) -> Result<Self, CalcDpError> {
+        let t_max = values.len();
+        for (t_k_1, row) in values.iter().enumerate() {
+            if row.len() != t_max - t_k_1 {
+                return Err(CalcDpError::new(format!("Row {t_k_1} of the table has {} values (expected {}).", row.len(), t_max - t_k_1)));
+            }
+        }
+        Tau::try_from(t_max).map_err(|_| CalcDpError::new(format!("Table with {t_max} rows is too large.")))?;
+        Ok(CostTable { values })
+    }
+
+
+    /// 区間ごとの評価値を計算して表を作成
+    ///
+    /// # 引数
+    /// * `t_max` - 変化点の最大値（最後の時期）
+    /// * `strategy` - 表の作成方法
+    /// * `f` - 区間$ (t_{k-1}, t_k] $の評価値を計算する関数（例: `CalcTT::calc_value`）
+    pub fn from_fn<F>(t_max: Tau, strategy: TableStrategy, f: F) -> Result<Self, CalcDpError>
+    where
+        Val: Send,
+        F: Fn(Tau, Tau) -> Result<Val, CalcDpError> + Sync,
+    {
+        let values = build_table(t_max, |t_k_1| (t_k_1 + 1)..=t_max, f, strategy)?;
+        Ok(CostTable { values })
+    }
+
+
+    /// 表が対象とする系列の長さ（最後の時期）
+    pub fn t_max(&self) -> Tau {
+        // 行数はnewで確認済み
+        self.values.len() as Tau
+    }
+
+
+    /// 区間$ (t_{k-1}, t_k] $の評価値
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    pub fn get(&self, t_k_1: Tau, t_k: Tau) -> Result<&Val, CalcDpError> {
+        let position = index::sub(t_k, t_k_1).and_then(|d| index::sub(d, 1u8))
+                                             .and_then(|j| Ok((index::to_usize(t_k_1)?, index::to_usize(j)?)));
+        position.ok()
+                .and_then(|(i, j)| self.values.get(i)?.get(j))
+                .ok_or_else(|| CalcDpError::new(format!("Segment ({t_k_1}, {t_k}] is not in the table (t_max = {}).", self.values.len())))
+    }
+
+
+    /// 範囲外の問い合わせの扱いを指定して，区間$ (t_{k-1}, t_k] $の評価値を取得
+    ///
+    /// [`QueryPolicy::ClampToRange`]では，$ t_k $を$ [1, T] $に，$ t_{k-1} $を$ [0, t_k - 1] $に収めた区間の評価値を返す．
+    ///
+    /// # 引数
+    /// * `t_k_1` - 前の変化点 $t_{k-1}$
+    /// * `t_k` - 後ろの変化点 $t_k$
+    /// * `policy` - 範囲外の問い合わせの扱い
+    pub fn get_with(&self, t_k_1: Tau, t_k: Tau, policy: QueryPolicy) -> Result<&Val, CalcDpError> {
+        if policy == QueryPolicy::Strict {
+            return self.get(t_k_1, t_k);
+        }
+        let t_k = policy.apply(t_k, 1, self.t_max())?;
+        let t_k_1 = policy.apply(t_k_1, 0, t_k - 1)?;
+        self.get(t_k_1, t_k)
+    }
+
+
+    /// 評価値を格納した2次元配列
+    pub fn values(&self) -> &[Vec<Val>] {
+        &self.values
+    }
+
+
+    /// 評価値を格納した2次元配列を取り出す
+    pub fn into_values(self) -> Vec<Vec<Val>> {
+        self.values
+    }
+
+
+    /// `t_max`までの区間を含むか確認する
+    pub(crate) fn check_t_max(&self, t_max: Tau) -> Result<(), CalcDpError> {
+        if index::to_usize(t_max)? > self.values.len() {
+            return Err(CalcDpError::new(format!("t_max (= {t_max}) exceeds the table (t_max = {}).", self.values.len())));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+//! 本crateの高水準の処理が返すError
+
+use crate::dp_tools::CalcDpError;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+extern crate thiserror;
+
+extern crate toml;
+
+
+/// 設定ファイルに基づく実行や一括実行等の高水準の処理が返すError
+///
+/// 動的計画法のtraitやコスト関数等の低水準の処理は[`CalcDpError`]を返し，本型へは`?`により[`Error::Dp`]として変換される．
+/// 読み込みや設定の誤り等，失敗の原因により対処が異なるものは別のvariantとして区別する．
+#[derive(Debug, Clone, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// 変化点検出の計算におけるError
+    #[error(transparent)]
+    Dp(#[from] CalcDpError),
+    /// ファイルの読み書きにおけるError
+    #[error("Failed to access {}: {source}", path.display())]
+    Io {
+        /// 読み書きしようとしたパス
+        path: PathBuf,
+        /// 原因となったError
+        #[source]
+        source: Arc<std::io::Error>,
+    },
+    /// 設定ファイルの解釈におけるError
+    #[error("Failed to parse run specification: {0}")]
+    Config(#[from] toml::de::Error),
+    /// コスト関数の構築におけるError
+    #[error("Failed to build cost function \"{name}\": {source}")]
+    Cost {
+        /// コスト関数の名前
+        name: String,
+        /// 原因となったError
+        #[source]
+        source: CalcDpError,
+    },
+    /// 処理が途中で打ち切られた
+    #[error("Operation was cancelled.")]
+    Cancelled,
+}
+
+impl Error {
+    /// パスを添えてファイルの読み書きにおけるErrorを作成する
+    ///
+    /// # 引数
+    /// * `path` - 読み書きしようとしたパス
+    /// * `source` - 原因となったError
+    pub(crate) fn io(path: &Path, source: std::io::Error) -> Self {
+        Error::Io { path: path.to_path_buf(), source: Arc::new(source) }
+    }
+
+
+    /// 名前を添えてコスト関数の構築におけるErrorを作成する
+    ///
+    /// # 引数
+    /// * `name` - コスト関数の名前
+    /// * `source` - 原因となったError
+    pub(crate) fn cost(name: &str, source: CalcDpError) -> Self {
+        Error::Cost { name: name.to_owned(), source }
+    }
+}
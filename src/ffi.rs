@@ -0,0 +1,174 @@
+//! C言語から呼び出すためのAPI
+//!
+//! MATLAB/OctaveのMEX等から利用することを想定し，入出力は平坦な配列，結果は整数のエラーコードで返す．
+//! 宣言は`include/cpd_tools.h`を，MEXからの利用例は`bindings/matlab/cpd_detect_mex.c`を参照．
+//!
+//! Errorの詳細は[`cpd_last_error`]により取得できる．
+
+use crate::cost::{CostRegistry, CostInput};
+use crate::detect::{self, Method, Penalty, Constraints, DetectionResult};
+use crate::dp_tools::CalcDpError;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ffi::{c_char, c_int, CStr};
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+
+/// 正常終了
+pub const CPD_OK: c_int = 0;
+/// 必須のポインタがNULL
+pub const CPD_ERR_NULL_POINTER: c_int = 1;
+/// 引数の値が不正
+pub const CPD_ERR_INVALID_ARGUMENT: c_int = 2;
+/// 変化点検出の計算に失敗
+pub const CPD_ERR_DETECTION: c_int = 3;
+/// 出力先の配列の長さが不足
+pub const CPD_ERR_BUFFER_TOO_SMALL: c_int = 4;
+/// 内部でpanicが発生
+pub const CPD_ERR_PANIC: c_int = 5;
+
+/// [`Method::Dp`]
+pub const CPD_METHOD_DP: c_int = 0;
+/// [`Method::Dp2`]
+pub const CPD_METHOD_DP2: c_int = 1;
+/// [`Method::Auto`]
+pub const CPD_METHOD_AUTO: c_int = 2;
+
+/// [`Penalty::NumChange`]
+pub const CPD_PENALTY_NUM_CHANGE: c_int = 0;
+/// [`Penalty::Linear`]
+pub const CPD_PENALTY_LINEAR: c_int = 1;
+
+
+thread_local! {
+    /// 直前に発生したErrorのメッセージ
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+
+/// 変化点を検出する
+///
+/// # 引数
+/// * `data` - 列優先（MATLABと同じ）で格納した`n_rows`行`n_cols`列の入力値．各列がコスト関数へ渡す系列となる．
+/// * `n_rows` - 系列の長さ
+/// * `n_cols` - 列数
+/// * `cost` - [`CostRegistry::with_builtins`]に登録されたコスト関数の名前（NUL終端のUTF-8文字列）
+/// * `method` - [`CPD_METHOD_DP`]または[`CPD_METHOD_DP2`]
+/// * `penalty_kind` - [`CPD_PENALTY_NUM_CHANGE`]または[`CPD_PENALTY_LINEAR`]
+/// * `penalty` - 変化点個数（非負の整数値）または変化点1個あたりのペナルティ
+/// * `out_change_points` - 変化点の出力先（昇順）．Cからは`uint32_t`の配列として扱う．
+/// * `out_capacity` - `out_change_points`の長さ
+/// * `out_len` - 変化点個数の出力先．[`CPD_ERR_BUFFER_TOO_SMALL`]の場合は必要な長さが格納される．
+/// * `out_value` - 各区間の評価値の総和の出力先．不要な場合はNULL．
+///
+/// # 返り値
+/// * `code` - エラーコード（`CPD_OK`，`CPD_ERR_*`）
+///
+/// # Safety
+/// * `data`は`n_rows * n_cols`個の`double`を指すこと
+/// * `cost`はNUL終端の文字列を指すこと
+/// * `out_change_points`は`out_capacity`個の`uint32_t`を書き込める領域を指すこと（`out_capacity`が0の場合はNULLでもよい）
+/// * `out_len`は書き込み可能であること
+#[no_mangle]
+pub unsafe extern "C" fn cpd_detect_flat(data: *const f64,
+                                         n_rows: usize,
+                                         n_cols: usize,
+                                         cost: *const c_char,
+                                         method: c_int,
+                                         penalty_kind: c_int,
+                                         penalty: f64,
+                                         out_change_points: *mut Tau,
+                                         out_capacity: usize,
+                                         out_len: *mut usize,
+                                         out_value: *mut f64) -> c_int {
+    if data.is_null() && n_rows * n_cols > 0 || cost.is_null() || out_len.is_null() || out_change_points.is_null() && out_capacity > 0 {
+        return fail(CPD_ERR_NULL_POINTER, "Required pointer is NULL.".to_owned());
+    }
+    let values = if n_rows * n_cols > 0 { std::slice::from_raw_parts(data, n_rows * n_cols) } else { &[] };
+    let cost = match CStr::from_ptr(cost).to_str() {
+        Ok(s) => s,
+        Err(e) => return fail(CPD_ERR_INVALID_ARGUMENT, format!("Cost name is not valid UTF-8: {e}")),
+    };
+    let method = match method {
+        CPD_METHOD_DP => Method::Dp,
+        CPD_METHOD_DP2 => Method::Dp2,
+        CPD_METHOD_AUTO => Method::Auto,
+        _ => return fail(CPD_ERR_INVALID_ARGUMENT, format!("Unknown method code {method}.")),
+    };
+    let penalty = match penalty_kind {
+        CPD_PENALTY_NUM_CHANGE if penalty >= 0.0 && penalty.fract() == 0.0 => Penalty::NumChange(penalty as NumChg),
+        CPD_PENALTY_LINEAR if !penalty.is_nan() => Penalty::Linear(penalty),
+        _ => return fail(CPD_ERR_INVALID_ARGUMENT, format!("Invalid penalty (kind = {penalty_kind}, value = {penalty}).")),
+    };
+
+    let result = std::panic::catch_unwind(|| detect_columns(values, n_rows, n_cols, cost, method, &penalty));
+    let result = match result {
+        Ok(Ok(res)) => res,
+        Ok(Err(e)) => return fail(CPD_ERR_DETECTION, e.to_string()),
+        Err(_) => return fail(CPD_ERR_PANIC, "Panic occurred during detection.".to_owned()),
+    };
+
+    let cps = &result.change_points;
+    *out_len = cps.len();
+    if !out_value.is_null() {
+        *out_value = result.value;
+    }
+    if cps.len() > out_capacity {
+        return fail(CPD_ERR_BUFFER_TOO_SMALL, format!("{} change points do not fit in the buffer of length {out_capacity}.", cps.len()));
+    }
+    if !cps.is_empty() {
+        std::ptr::copy_nonoverlapping(cps.as_ptr(), out_change_points, cps.len());
+    }
+    CPD_OK
+}
+
+
+/// 直前に発生したErrorのメッセージを取得する
+///
+/// メッセージは呼び出したthreadごとに保持される．
+///
+/// # 引数
+/// * `buf` - NUL終端の文字列の出力先．`capacity`に収まらない場合は切り詰める．
+/// * `capacity` - `buf`の長さ
+///
+/// # 返り値
+/// * `len` - NUL終端を除くメッセージ全体の長さ（バイト数）
+///
+/// # Safety
+/// `buf`は`capacity`バイトを書き込める領域を指すこと（`capacity`が0の場合はNULLでもよい）．
+#[no_mangle]
+pub unsafe extern "C" fn cpd_last_error(buf: *mut c_char, capacity: usize) -> usize {
+    LAST_ERROR.with(|last| {
+        let msg = last.borrow();
+        if !buf.is_null() && capacity > 0 {
+            let n = msg.len().min(capacity - 1);
+            std::ptr::copy_nonoverlapping(msg.as_ptr() as *const c_char, buf, n);
+            *buf.add(n) = 0;
+        }
+        msg.len()
+    })
+}
+
+
+/// 列優先の配列から変化点を検出する
+fn detect_columns(values: &[f64], n_rows: usize, n_cols: usize, cost: &str, method: Method, penalty: &Penalty) -> Result<DetectionResult, CalcDpError> {
+    let columns = (0..n_cols).map(|j| values[(j * n_rows)..((j + 1) * n_rows)].to_vec())
+                             .collect::<Vec<Vec<f64>>>();
+    let params = BTreeMap::new();
+    let input = CostInput {
+        columns: &columns,
+        params: &params,
+    };
+    let cost = CostRegistry::with_builtins().build(cost, &input)?;
+    detect::detect(&cost, method, penalty, &Constraints::default())
+}
+
+
+/// Errorのメッセージを保持してエラーコードを返す
+fn fail(code: c_int, message: String) -> c_int {
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    code
+}
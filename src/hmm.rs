@@ -0,0 +1,344 @@
+//! 隠れマルコフモデルによる検出結果の照合
+//!
+//! 動的計画法による変化点検出の結果の妥当性を確かめるため，コスト関数と同じ分布族の出力を持つ隠れマルコフモデルを当てはめ，
+//! Viterbiアルゴリズムによる状態の系列から得られる変化点と比較する．
+//! 両者が大きく食い違う場合は，コスト関数の仮定（分布族や独立性）またはペナルティの設定を見直す手掛かりとなる．
+//!
+//! 本moduleはfeature `hmm`を有効にした場合のみ利用できる．
+
+use crate::detect::DetectionResult;
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 出力分布の族
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Emission {
+    /// 状態ごとに平均の異なる正規分布（分散は全状態で共通）
+    Gaussian,
+    /// 状態ごとに平均の異なるポアソン分布
+    Poisson,
+    /// 状態ごとに平均の異なる指数分布
+    Exponential,
+}
+
+impl Emission {
+    /// [`crate::cost::CostRegistry`]のコスト関数の名前に対応する出力分布の族
+    ///
+    /// 対応する族がない場合は`None`を返す．
+    ///
+    /// # 引数
+    /// * `name` - コスト関数の名前
+    pub fn for_cost(name: &str) -> Option<Self> {
+        match name {
+            "hetero_mean" => Some(Emission::Gaussian),
+            "zip" | "negbin" => Some(Emission::Poisson),
+            "censored_exp" => Some(Emission::Exponential),
+            _ => None,
+        }
+    }
+}
+
+
+/// 隠れマルコフモデル
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hmm {
+    /// 出力分布の族
+    pub emission: Emission,
+    /// 初期状態の確率
+    pub initial: Vec<f64>,
+    /// 遷移確率（行が遷移元，列が遷移先）
+    pub transition: Vec<Vec<f64>>,
+    /// 状態ごとの出力分布の平均
+    pub means: Vec<f64>,
+    /// 出力分布の分散（[`Emission::Gaussian`]のみ）
+    pub variance: f64,
+    /// 当てはめ後の対数尤度
+    pub log_likelihood: f64,
+}
+
+
+/// 最小の分散．全観測が同じ値の場合にも対数尤度が有限となるようにする．
+const MIN_VARIANCE: f64 = 1e-12;
+
+
+impl Hmm {
+    /// Baum–Welchアルゴリズムにより隠れマルコフモデルを当てはめる
+    ///
+    /// 状態の平均の初期値は観測値の分位点とし，遷移確率の初期値は状態に留まる確率を0.9とする．
+    ///
+    /// # 引数
+    /// * `data` - 系列データ
+    /// * `n_states` - 状態の数
+    /// * `emission` - 出力分布の族
+    /// * `max_iter` - 反復の上限
+    pub fn fit(data: &[f64], n_states: usize, emission: Emission, max_iter: usize) -> Result<Self, CalcDpError> {
+        if n_states == 0 || data.len() < n_states {
+            return Err(CalcDpError::new(format!("Number of states must be between 1 and the series length ({}), but {n_states} is given.", data.len())));
+        }
+        if let Some(v) = data.iter().find(|v| !emission.supports(**v)) {
+            return Err(CalcDpError::new(format!("{v} is not a valid observation for {emission:?} emission.")));
+        }
+
+        let mut sorted = data.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let means = (0..n_states).map(|s| sorted[((2 * s + 1) * sorted.len()) / (2 * n_states)])
+                                 .map(|m| emission.clamp_mean(m))
+                                 .collect::<Vec<f64>>();
+        let stay = if n_states > 1 { 0.9 } else { 1.0 };
+        let transition = (0..n_states).map(|i| (0..n_states).map(|j| if i == j { stay } else { (1.0 - stay) / (n_states - 1) as f64 })
+                                                            .collect())
+                                      .collect();
+        let total_mean = data.iter().sum::<f64>() / data.len() as f64;
+        let variance = (data.iter().map(|y| (y - total_mean).powi(2)).sum::<f64>() / data.len() as f64).max(MIN_VARIANCE);
+        let mut hmm = Hmm {
+            emission,
+            initial: vec![1.0 / n_states as f64; n_states],
+            transition,
+            means,
+            variance,
+            log_likelihood: f64::NEG_INFINITY,
+        };
+
+        for _ in 0..max_iter {
+            let previous = hmm.log_likelihood;
+            hmm.step(data);
+            if (hmm.log_likelihood - previous).abs() <= 1e-8 * hmm.log_likelihood.abs().max(1.0) {
+                break;
+            }
+        }
+        Ok(hmm)
+    }
+
+
+    /// 状態の数
+    pub fn n_states(&self) -> usize {
+        self.means.len()
+    }
+
+
+    /// Viterbiアルゴリズムにより最も確からしい状態の系列を求める
+    ///
+    /// # 引数
+    /// * `data` - 系列データ
+    pub fn viterbi(&self, data: &[f64]) -> Vec<usize> {
+        let n = self.n_states();
+        if data.is_empty() {
+            return Vec::new();
+        }
+        let log_a = self.transition.iter()
+                                   .map(|row| row.iter().map(|p| p.ln()).collect::<Vec<f64>>())
+                                   .collect::<Vec<Vec<f64>>>();
+        let mut delta = (0..n).map(|s| self.initial[s].ln() + self.log_emission(s, data[0]))
+                              .collect::<Vec<f64>>();
+        let mut back = Vec::with_capacity(data.len());
+        for y in &data[1..] {
+            let (next, from): (Vec<f64>, Vec<usize>) = (0..n).map(|j| {
+                                                                 let (best, val) = (0..n).map(|i| (i, delta[i] + log_a[i][j]))
+                                                                                         .fold((0, f64::NEG_INFINITY), |acc, cur| if cur.1 > acc.1 { cur } else { acc });
+                                                                 (val + self.log_emission(j, *y), best)
+                                                             })
+                                                             .unzip();
+            delta = next;
+            back.push(from);
+        }
+
+        let mut state = (0..n).fold(0, |best, s| if delta[s] > delta[best] { s } else { best });
+        let mut states = vec![state; data.len()];
+        for (t, from) in back.iter().enumerate().rev() {
+            state = from[state];
+            states[t] = state;
+        }
+        states
+    }
+
+
+    /// 状態$ s $における観測$ y $の対数尤度（定数項を含む）
+    fn log_emission(&self, s: usize, y: f64) -> f64 {
+        let m = self.means[s];
+        match self.emission {
+            Emission::Gaussian => -0.5 * ((y - m).powi(2) / self.variance + (2.0 * std::f64::consts::PI * self.variance).ln()),
+            Emission::Poisson => y * m.ln() - m - ln_factorial(y),
+            Emission::Exponential => -m.ln() - y / m,
+        }
+    }
+
+
+    /// Baum–Welchアルゴリズムの1反復（スケーリングした前向き・後ろ向きアルゴリズム）
+    fn step(&mut self, data: &[f64]) {
+        let (n, len) = (self.n_states(), data.len());
+        let emit = data.iter()
+                       .map(|y| {
+                           let logs = (0..n).map(|s| self.log_emission(s, *y)).collect::<Vec<f64>>();
+                           let max = logs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                           (logs.iter().map(|l| (l - max).exp()).collect::<Vec<f64>>(), max)
+                       })
+                       .collect::<Vec<(Vec<f64>, f64)>>();
+
+        // 前向き
+        let mut alpha = vec![vec![0.0; n]; len];
+        let mut scale = vec![0.0; len];
+        for t in 0..len {
+            for j in 0..n {
+                let prior = if t == 0 {
+                    self.initial[j]
+                } else {
+                    (0..n).map(|i| alpha[t - 1][i] * self.transition[i][j]).sum::<f64>()
+                };
+                alpha[t][j] = prior * emit[t].0[j];
+            }
+            scale[t] = alpha[t].iter().sum::<f64>().max(f64::MIN_POSITIVE);
+            alpha[t].iter_mut().for_each(|a| *a /= scale[t]);
+        }
+        self.log_likelihood = scale.iter().zip(&emit).map(|(c, (_, max))| c.ln() + max).sum();
+
+        // 後ろ向き
+        let mut beta = vec![vec![1.0; n]; len];
+        for t in (0..(len - 1)).rev() {
+            for i in 0..n {
+                beta[t][i] = (0..n).map(|j| self.transition[i][j] * emit[t + 1].0[j] * beta[t + 1][j]).sum::<f64>() / scale[t + 1];
+            }
+        }
+
+        // 更新
+        let gamma = (0..len).map(|t| (0..n).map(|s| alpha[t][s] * beta[t][s]).collect::<Vec<f64>>())
+                            .collect::<Vec<Vec<f64>>>();
+        let mut xi = vec![vec![0.0; n]; n];
+        for t in 0..(len - 1) {
+            for i in 0..n {
+                for j in 0..n {
+                    xi[i][j] += alpha[t][i] * self.transition[i][j] * emit[t + 1].0[j] * beta[t + 1][j] / scale[t + 1];
+                }
+            }
+        }
+        self.initial = gamma[0].clone();
+        for (row, counts) in self.transition.iter_mut().zip(&xi) {
+            let total = counts.iter().sum::<f64>();
+            if total > 0.0 {
+                *row = counts.iter().map(|x| x / total).collect();
+            }
+        }
+        let mut sq = 0.0;
+        for s in 0..n {
+            let w = gamma.iter().map(|g| g[s]).sum::<f64>();
+            if w > 0.0 {
+                let m = gamma.iter().zip(data).map(|(g, y)| g[s] * y).sum::<f64>() / w;
+                self.means[s] = self.emission.clamp_mean(m);
+            }
+            sq += gamma.iter().zip(data).map(|(g, y)| g[s] * (y - self.means[s]).powi(2)).sum::<f64>();
+        }
+        self.variance = (sq / len as f64).max(MIN_VARIANCE);
+    }
+}
+
+impl Emission {
+    /// 観測値が分布の台に含まれるか
+    fn supports(&self, y: f64) -> bool {
+        match self {
+            Emission::Gaussian => y.is_finite(),
+            Emission::Poisson => y.is_finite() && y >= 0.0 && y.fract() == 0.0,
+            Emission::Exponential => y.is_finite() && y >= 0.0,
+        }
+    }
+
+
+    /// 平均を分布の母数として有効な範囲へ丸める
+    fn clamp_mean(&self, m: f64) -> f64 {
+        match self {
+            Emission::Gaussian => m,
+            Emission::Poisson | Emission::Exponential => m.max(1e-8),
+        }
+    }
+}
+
+
+/// $ \ln y! $
+fn ln_factorial(y: f64) -> f64 {
+    (1..=(y as u64)).map(|i| (i as f64).ln()).sum()
+}
+
+
+/// 状態の系列が切り替わる時点
+///
+/// # 引数
+/// * `states` - 時点ごとの状態
+pub fn change_points_of(states: &[usize]) -> Vec<Tau> {
+    states.windows(2)
+          .enumerate()
+          .filter(|(_, w)| w[0] != w[1])
+          .map(|(t, _)| (t + 1) as Tau)
+          .collect()
+}
+
+
+/// 動的計画法と隠れマルコフモデルの変化点の照合結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrossCheck {
+    /// 当てはめた隠れマルコフモデル
+    pub hmm: Hmm,
+    /// Viterbiアルゴリズムによる状態の系列
+    pub states: Vec<usize>,
+    /// 隠れマルコフモデルによる変化点
+    pub hmm_change_points: Vec<Tau>,
+    /// 時点の差が許容範囲内で対応した（動的計画法, 隠れマルコフモデル）の変化点の組
+    pub matched: Vec<(Tau, Tau)>,
+    /// 動的計画法のみで検出された変化点
+    pub only_dp: Vec<Tau>,
+    /// 隠れマルコフモデルのみで検出された変化点
+    pub only_hmm: Vec<Tau>,
+}
+
+impl CrossCheck {
+    /// 全ての変化点が対応したか
+    pub fn agrees(&self) -> bool {
+        self.only_dp.is_empty() && self.only_hmm.is_empty()
+    }
+}
+
+
+/// 動的計画法による検出結果を隠れマルコフモデルのViterbiアルゴリズムによる分割と照合する
+///
+/// 変化点は時点の差が`tolerance`以下のものを，近い組から順に1対1で対応させる．
+///
+/// # 引数
+/// * `data` - 系列データ
+/// * `result` - 動的計画法による検出結果
+/// * `emission` - 出力分布の族（コスト関数と同じ族とする．[`Emission::for_cost`]を参照）
+/// * `n_states` - 隠れマルコフモデルの状態の数
+/// * `tolerance` - 変化点が対応するとみなす時点の差の上限
+pub fn cross_check(data: &[f64], result: &DetectionResult, emission: Emission, n_states: usize, tolerance: Tau) -> Result<CrossCheck, CalcDpError> {
+    if result.start != 0 || result.t_max as usize != data.len() {
+        return Err(CalcDpError::new(format!("Detection result covers ({}, {}], but the series has {} points.", result.start, result.t_max, data.len())));
+    }
+    let hmm = Hmm::fit(data, n_states, emission, 200)?;
+    let states = hmm.viterbi(data);
+    let hmm_change_points = change_points_of(&states);
+
+    let mut pairs = result.change_points.iter()
+                                        .flat_map(|a| hmm_change_points.iter().map(move |b| (*a, *b)))
+                                        .filter(|(a, b)| a.abs_diff(*b) <= tolerance)
+                                        .collect::<Vec<(Tau, Tau)>>();
+    pairs.sort_by_key(|(a, b)| (a.abs_diff(*b), *a));
+    let mut matched: Vec<(Tau, Tau)> = Vec::new();
+    for (a, b) in pairs {
+        if matched.iter().all(|(x, y)| *x != a && *y != b) {
+            matched.push((a, b));
+        }
+    }
+    matched.sort_unstable();
+    let only_dp = result.change_points.iter()
+                                      .filter(|a| matched.iter().all(|(x, _)| x != *a))
+                                      .copied()
+                                      .collect();
+    let only_hmm = hmm_change_points.iter()
+                                    .filter(|b| matched.iter().all(|(_, y)| y != *b))
+                                    .copied()
+                                    .collect();
+    Ok(CrossCheck { hmm, states, hmm_change_points, matched, only_dp, only_hmm })
+}
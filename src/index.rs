@@ -0,0 +1,93 @@
+//! 変化点の添字に対する桁あふれを起こさない計算
+//!
+//! [`Tau`](process_param::Tau)および[`NumChg`](process_param::NumChg)の幅は`process_param`で定められる．
+//! 動的計画法のメモの位置（例えば$ t - 2k + 1 $）等の計算は，より幅の広い整数型[`Wide`]へ拡張した上で検査付きで行い，
+//! 桁あふれまたは負の値となる場合はパニックではなく[`CalcDpError`]を返す．
+//!
+//! 扱える系列の長さおよび変化点個数の上限は[`Tau`](process_param::Tau)および[`NumChg`](process_param::NumChg)の最大値であり，
+//! 本crateでは変更できない．
+
+use crate::dp_tools::CalcDpError;
+
+
+/// 添字の計算に用いる整数型
+pub type Wide = u64;
+
+
+/// 添字として用いる符号なし整数型
+pub trait Index: Copy + std::fmt::Display {
+    /// [`Wide`]へ拡張する
+    fn widen(self) -> Wide;
+
+
+    /// [`Wide`]から元の型へ戻す
+    ///
+    /// # 引数
+    /// * `v` - 変換する値
+    fn narrow(v: Wide) -> Result<Self, CalcDpError>;
+}
+
+macro_rules! impl_index {
+    ($($t:ty),+) => {
+        $(
+            impl Index for $t {
+                fn widen(self) -> Wide {
+                    Wide::from(self)
+                }
+
+                fn narrow(v: Wide) -> Result<Self, CalcDpError> {
+                    <$t>::try_from(v).map_err(|_| CalcDpError::new(format!("Index {v} does not fit in {}.", stringify!($t))))
+                }
+            }
+        )+
+    };
+}
+
+impl_index!(u8, u16, u32, u64);
+
+
+/// 検査付きの加算$ a + b $
+///
+/// # 引数
+/// * `a` - 左辺
+/// * `b` - 右辺
+pub fn add<T: Index, U: Index>(a: T, b: U) -> Result<T, CalcDpError> {
+    checked(a, b, "+", Wide::checked_add)
+}
+
+
+/// 検査付きの減算$ a - b $
+///
+/// # 引数
+/// * `a` - 左辺
+/// * `b` - 右辺
+pub fn sub<T: Index, U: Index>(a: T, b: U) -> Result<T, CalcDpError> {
+    checked(a, b, "-", Wide::checked_sub)
+}
+
+
+/// 検査付きの乗算$ a b $
+///
+/// # 引数
+/// * `a` - 左辺
+/// * `b` - 右辺
+pub fn mul<T: Index, U: Index>(a: T, b: U) -> Result<T, CalcDpError> {
+    checked(a, b, "*", Wide::checked_mul)
+}
+
+
+/// 配列の添字（`usize`）へ変換する
+///
+/// # 引数
+/// * `a` - 変換する値
+pub fn to_usize<T: Index>(a: T) -> Result<usize, CalcDpError> {
+    usize::try_from(a.widen()).map_err(|_| CalcDpError::new(format!("Index {a} exceeds the addressable range.")))
+}
+
+
+fn checked<T: Index, U: Index>(a: T, b: U, op: &str, f: fn(Wide, Wide) -> Option<Wide>) -> Result<T, CalcDpError> {
+    match f(a.widen(), b.widen()) {
+        Some(v) => T::narrow(v),
+        None => Err(CalcDpError::new(format!("Index arithmetic {a} {op} {b} overflowed."))),
+    }
+}
@@ -0,0 +1,170 @@
+//! 系列データおよび検出結果の入出力
+
+use crate::dp_tools::CalcDpError;
+
+use std::fmt::Write;
+use std::path::Path;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// CSVファイルの列の指定方法
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ColumnRef {
+    /// 0始まりの列番号
+    Index(usize),
+    /// ヘッダ行に記載された列名
+    Name(String),
+}
+
+impl std::fmt::Display for ColumnRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ColumnRef::Index(idx) => write!(f, "{idx}"),
+            ColumnRef::Name(name) => write!(f, "\"{name}\""),
+        }
+    }
+}
+
+
+/// CSVファイルから指定した列を読み込む
+///
+/// 空行および`#`で始まる行は読み飛ばす．
+///
+/// # 引数
+/// * `path` - CSVファイルのパス
+/// * `columns` - 読み込む列
+/// * `header` - 1行目をヘッダ行として扱うか
+/// * `delimiter` - 区切り文字
+///
+/// # 返り値
+/// * `data` - 列ごとの値
+pub fn read_csv_columns(path: &Path, columns: &[ColumnRef], header: bool, delimiter: char) -> Result<Vec<Vec<f64>>, CalcDpError> {
+    let text = std::fs::read_to_string(path).map_err(|e| CalcDpError::new(format!("Failed to read {}: {e}", path.display())).with_source(e))?;
+    parse_csv_columns(&text, columns, header, delimiter).map_err(|e| CalcDpError::new(format!("{}: {}", path.display(), e.message)).with_source(e))
+}
+
+
+/// CSV形式の文字列から指定した列を読み込む
+///
+/// # 引数
+/// * `text` - CSV形式の文字列
+/// * `columns` - 読み込む列
+/// * `header` - 1行目をヘッダ行として扱うか
+/// * `delimiter` - 区切り文字
+pub fn parse_csv_columns(text: &str, columns: &[ColumnRef], header: bool, delimiter: char) -> Result<Vec<Vec<f64>>, CalcDpError> {
+    let mut lines = text.lines()
+                        .enumerate()
+                        .filter(|(_, line)| !(line.trim().is_empty() || line.starts_with('#')));
+
+    // 列名を列番号へ変換
+    let names = if header {
+        match lines.next() {
+            Some((_, line)) => line.split(delimiter).map(|name| name.trim().to_owned()).collect(),
+            None => Vec::new(),
+        }
+    } else {
+        Vec::new()
+    };
+    let indices = columns.iter()
+                         .map(|column| match column {
+                             ColumnRef::Index(idx) => Ok(*idx),
+                             ColumnRef::Name(name) => names.iter()
+                                                           .position(|n| n == name)
+                                                           .ok_or_else(|| CalcDpError::new(format!("Column {column} is not found in the header."))),
+                         })
+                         .collect::<Result<Vec<usize>, CalcDpError>>()?;
+
+    let mut data = vec![Vec::new(); indices.len()];
+    for (line_no, line) in lines {
+        let fields = line.split(delimiter).collect::<Vec<&str>>();
+        for (values, idx) in data.iter_mut().zip(indices.iter()) {
+            let field = fields.get(*idx).ok_or_else(|| CalcDpError::new(format!("Line {}: column {idx} does not exist.", line_no + 1)))?;
+            let value = field.trim().parse::<f64>().map_err(|e| CalcDpError::new(format!("Line {}: failed to parse \"{}\" as a number ({e}).", line_no + 1, field.trim())).with_source(e))?;
+            values.push(value);
+        }
+    }
+    Ok(data)
+}
+
+
+/// 文字列をファイルへ書き込む
+///
+/// # 引数
+/// * `path` - 書き込み先のパス
+/// * `content` - 書き込む文字列
+pub fn write_text(path: &Path, content: &str) -> Result<(), CalcDpError> {
+    std::fs::write(path, content).map_err(|e| CalcDpError::new(format!("Failed to write {}: {e}", path.display())).with_source(e))
+}
+
+
+
+/// バイト列をファイルへ書き込む
+///
+/// # 引数
+/// * `path` - 書き込み先のパス
+/// * `content` - 書き込むバイト列
+pub fn write_bytes(path: &Path, content: &[u8]) -> Result<(), CalcDpError> {
+    std::fs::write(path, content).map_err(|e| CalcDpError::new(format!("Failed to write {}: {e}", path.display())).with_source(e))
+}
+
+
+/// 動的計画法のメモの要素（直前の変化点, 変化点個数, 最適値）
+type MemoEntry = Option<(Tau, NumChg, f64)>;
+
+
+/// バックトレースの図において，各段階で表示する他の候補の最大数
+const MAX_ALTERNATIVES: usize = 3;
+
+
+/// 動的計画法のメモから最適な変化点を復元する経路をDOT形式（Graphviz）の文字列として出力
+///
+/// 節点$ (t, k) $は時点$ t $までを$ k $個の変化点で分割した部分問題を表し，その最適値$ F(t, k) $を併記する．
+/// 選ばれた経路は太線で，各段階で選ばれなかった直前の変化点の候補は選ばれた変化点に近い順に最大3個を破線で示す．
+/// 動的計画法の再帰の学習やデバッグに用いる．
+///
+/// メモは[`crate::dp_tools::calc_dp`]の形式（`memo[k][t - k - 1]`）とする．
+///
+/// # 引数
+/// * `memo` - 動的計画法の計算に用いたメモ
+/// * `t` - 復元を開始する時点（通常は系列の長さ）
+/// * `k` - 変化点個数
+pub fn export_backtrace_dot(memo: &[Vec<MemoEntry>], t: Tau, k: NumChg) -> Result<String, CalcDpError> {
+    let get = |t: Tau, k: NumChg| -> MemoEntry {
+        if t <= k {
+            return None;
+        }
+        memo.get(k as usize)?.get((t - k - 1) as usize).copied().flatten()
+    };
+    let node = |t: Tau, k: NumChg| format!("\"t{t}_k{k}\"");
+
+    let mut dot = "digraph backtrace {\n    rankdir=RL;\n    node [shape=box, fontname=\"monospace\"];\n".to_owned();
+    let mut now = (t, k);
+    loop {
+        let (now_t, now_k) = now;
+        let (t_k_1, _, value) = get(now_t, now_k).ok_or_else(|| CalcDpError::new(format!("Memo for t = {now_t}, k = {now_k} has not been calculated.")))?;
+        let _ = writeln!(dot, "    {} [label=\"({now_t}, {now_k})\\nF = {value}\", style=bold];", node(now_t, now_k));
+        if now_k == 0 {
+            let _ = writeln!(dot, "    {} -> \"origin\" [label=\"(0, {now_t}]\", penwidth=2];", node(now_t, now_k));
+            break;
+        }
+
+        let mut alternatives = (now_k..now_t).filter(|t_alt| *t_alt != t_k_1)
+                                             .filter_map(|t_alt| get(t_alt, now_k - 1).map(|(_, _, v)| (t_alt, v)))
+                                             .collect::<Vec<(Tau, f64)>>();
+        alternatives.sort_by_key(|(t_alt, _)| t_alt.abs_diff(t_k_1));
+        for (t_alt, v) in alternatives.into_iter().take(MAX_ALTERNATIVES) {
+            let _ = writeln!(dot, "    {} [label=\"({t_alt}, {})\\nF = {v}\", color=gray, fontcolor=gray];", node(t_alt, now_k - 1), now_k - 1);
+            let _ = writeln!(dot, "    {} -> {} [label=\"({t_alt}, {now_t}]\", style=dashed, color=gray, fontcolor=gray];", node(now_t, now_k), node(t_alt, now_k - 1));
+        }
+        let _ = writeln!(dot, "    {} -> {} [label=\"({t_k_1}, {now_t}]\", penwidth=2];", node(now_t, now_k), node(t_k_1, now_k - 1));
+        now = (t_k_1, now_k - 1);
+    }
+    dot.push_str("    \"origin\" [label=\"0\", shape=circle];\n}\n");
+    Ok(dot)
+}
@@ -1,3 +1,42 @@
 //! 変化点検出(Change point detection)手法のプログラム作成のためのツール集
 
 pub mod dp_tools;
+pub mod index;
+pub mod cost;
+pub mod detect;
+pub mod config;
+pub mod batch;
+pub mod io;
+pub mod search;
+pub mod online;
+pub mod calibrate;
+pub mod preprocess;
+pub mod stats;
+pub mod segment;
+pub mod calendar;
+pub mod panel;
+#[cfg(feature = "hmm")]
+pub mod hmm;
+pub mod compat;
+#[cfg(feature = "datasets")]
+pub mod datasets;
+pub mod ffi;
+#[cfg(feature = "r")]
+pub mod r;
+#[cfg(feature = "uniffi")]
+pub mod mobile;
+
+mod error;
+mod linalg;
+mod special;
+mod rng;
+
+pub use error::Error;
+pub use rng::RngConfig;
+
+// マクロから参照するための再公開
+#[doc(hidden)]
+pub use process_param as __process_param;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
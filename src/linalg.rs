@@ -0,0 +1,166 @@
+//! コスト関数等の計算に用いる線形代数の補助関数
+//!
+//! 行列は行優先(row-major)の1次元スライスとして扱う．
+
+
+/// 対称正定値行列のCholesky分解$ A = L L^\top $
+///
+/// 正定値でない場合は`None`を返す．
+///
+/// # 引数
+/// * `a` - 分解する$ n \times n $行列
+/// * `n` - 行列の次元
+///
+/// # 返り値
+/// * `l` - 下三角行列$ L $
+pub(crate) fn cholesky(a: &[f64], n: usize) -> Option<Vec<f64>> {
+    let mut l = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..=i {
+            let s = (0..j).map(|m| l[i * n + m] * l[j * n + m])
+                          .sum::<f64>();
+            if i == j {
+                let d = a[i * n + i] - s;
+                if d <= 0.0 || !d.is_finite() {
+                    return None;
+                }
+                l[i * n + i] = d.sqrt();
+            } else {
+                l[i * n + j] = (a[i * n + j] - s) / l[j * n + j];
+            }
+        }
+    }
+    Some(l)
+}
+
+
+/// Cholesky分解の結果を用いて連立方程式$ L L^\top x = b $を解く
+///
+/// # 引数
+/// * `l` - [`cholesky`]で得た下三角行列
+/// * `n` - 行列の次元
+/// * `b` - 右辺のベクトル
+pub(crate) fn cholesky_solve(l: &[f64], n: usize, b: &[f64]) -> Vec<f64> {
+    // 前進代入 L z = b
+    let mut z = vec![0.0; n];
+    for i in 0..n {
+        let s = (0..i).map(|m| l[i * n + m] * z[m])
+                      .sum::<f64>();
+        z[i] = (b[i] - s) / l[i * n + i];
+    }
+    // 後退代入 L^T x = z
+    let mut x = vec![0.0; n];
+    for i in (0..n).rev() {
+        let s = ((i + 1)..n).map(|m| l[m * n + i] * x[m])
+                            .sum::<f64>();
+        x[i] = (z[i] - s) / l[i * n + i];
+    }
+    x
+}
+
+
+
+/// 最小二乗法による回帰係数$ \hat{\beta} = (X^\top X)^{-1} X^\top y $
+///
+/// 数値的な安定のため，$ X^\top X $の対角成分に微小なリッジ項を加える．
+/// 正規方程式が解けない場合は`None`を返す．
+///
+/// # 引数
+/// * `x` - $ m \times p $の計画行列
+/// * `y` - 目的変数（長さ$ m $）
+/// * `p` - 説明変数の数
+pub(crate) fn least_squares(x: &[f64], y: &[f64], p: usize) -> Option<Vec<f64>> {
+    let m = y.len();
+    let mut xx = vec![0.0; p * p];
+    let mut xy = vec![0.0; p];
+    for r in 0..m {
+        let row = &x[(r * p)..((r + 1) * p)];
+        for i in 0..p {
+            xy[i] += row[i] * y[r];
+            for j in 0..=i {
+                xx[i * p + j] += row[i] * row[j];
+            }
+        }
+    }
+    for i in 0..p {
+        for j in 0..i {
+            xx[j * p + i] = xx[i * p + j];
+        }
+    }
+    let mean_diag = (0..p).map(|i| xx[i * p + i]).sum::<f64>() / p as f64;
+    for i in 0..p {
+        xx[i * p + i] += 1e-10 * mean_diag.max(1.0);
+    }
+    cholesky(&xx, p).map(|l| cholesky_solve(&l, p, &xy))
+}
+
+
+/// Jacobi法による対称行列の固有値分解$ A = V \Lambda V^\top $
+///
+/// 固有値は降順に並べ，固有ベクトルの符号は絶対値最大の成分が正となるように揃える．
+///
+/// # 引数
+/// * `a` - 分解する$ n \times n $対称行列
+/// * `n` - 行列の次元
+///
+/// # 返り値
+/// * `values` - 固有値（降順）
+/// * `vectors` - 固有ベクトルを列に並べた行列$ V $
+pub(crate) fn symmetric_eigen(a: &[f64], n: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut a = a.to_vec();
+    let mut v = vec![0.0; n * n];
+    for i in 0..n {
+        v[i * n + i] = 1.0;
+    }
+    let norm = a.iter().map(|x| x * x).sum::<f64>();
+    for _ in 0..100 {
+        let off = (0..n).flat_map(|i| (0..n).filter(move |j| *j != i).map(move |j| (i, j)))
+                        .map(|(i, j)| a[i * n + j] * a[i * n + j])
+                        .sum::<f64>();
+        if off <= 1e-30 * norm || off == 0.0 {
+            break;
+        }
+        for p in 0..n {
+            for q in (p + 1)..n {
+                let apq = a[p * n + q];
+                if apq == 0.0 {
+                    continue;
+                }
+                let theta = (a[q * n + q] - a[p * n + p]) / (2.0 * apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                // A <- J^T A J
+                for k in 0..n {
+                    let (akp, akq) = (a[k * n + p], a[k * n + q]);
+                    a[k * n + p] = c * akp - s * akq;
+                    a[k * n + q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[p * n + k], a[q * n + k]);
+                    a[p * n + k] = c * apk - s * aqk;
+                    a[q * n + k] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (v[k * n + p], v[k * n + q]);
+                    v[k * n + p] = c * vkp - s * vkq;
+                    v[k * n + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    let mut order = (0..n).collect::<Vec<usize>>();
+    order.sort_by(|i, j| a[j * n + j].total_cmp(&a[i * n + i]));
+    let values = order.iter().map(|i| a[i * n + i]).collect::<Vec<f64>>();
+    let mut vectors = vec![0.0; n * n];
+    for (col, i) in order.iter().enumerate() {
+        let largest = (0..n).map(|k| v[k * n + i])
+                            .fold(0.0_f64, |m, x| if x.abs() > m.abs() { x } else { m });
+        let sign = if largest < 0.0 { -1.0 } else { 1.0 };
+        for k in 0..n {
+            vectors[k * n + col] = sign * v[k * n + i];
+        }
+    }
+    (values, vectors)
+}
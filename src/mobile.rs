@@ -0,0 +1,121 @@
+//! UniFFIによるKotlin/Swiftからの呼び出し
+//!
+//! `uniffi` featureを有効にした場合のみ利用できる．
+//! 携帯端末上で収集した短い系列をその場で解析することを想定し，系列を受け取って検出結果を返す関数のみを公開する．
+//!
+//! Kotlin/Swiftのbindingは`uniffi-bindgen`により生成する．
+//! ```sh
+//! cargo build --release --features uniffi
+//! cargo run --features uniffi-cli --bin uniffi-bindgen -- generate --library target/release/libcpd_tools.so --language kotlin --out-dir out
+//! ```
+
+use crate::cost::{CostRegistry, CostInput};
+use crate::detect::{self, Method, Penalty, Constraints};
+use crate::dp_tools::CalcDpError;
+
+use std::collections::BTreeMap;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+extern crate uniffi;
+
+
+/// 変化点検出の手法（[`Method`]に対応）
+#[derive(Debug, Clone, Copy, uniffi::Enum)]
+pub enum MobileMethod {
+    Dp,
+    Dp2,
+    Auto,
+}
+
+impl From<MobileMethod> for Method {
+    fn from(method: MobileMethod) -> Self {
+        match method {
+            MobileMethod::Dp => Method::Dp,
+            MobileMethod::Dp2 => Method::Dp2,
+            MobileMethod::Auto => Method::Auto,
+        }
+    }
+}
+
+
+/// 変化点個数の決め方（[`Penalty`]に対応）
+#[derive(Debug, Clone, Copy, uniffi::Enum)]
+pub enum MobilePenalty {
+    NumChange { k: NumChg },
+    Linear { beta: f64 },
+}
+
+impl From<MobilePenalty> for Penalty {
+    fn from(penalty: MobilePenalty) -> Self {
+        match penalty {
+            MobilePenalty::NumChange { k } => Penalty::NumChange(k),
+            MobilePenalty::Linear { beta } => Penalty::Linear(beta),
+        }
+    }
+}
+
+
+/// 変化点検出の結果（[`crate::detect::DetectionResult`]に対応）
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MobileResult {
+    /// 変化点（昇順）
+    pub change_points: Vec<Tau>,
+    /// 各区間の評価値の総和
+    pub value: f64,
+}
+
+
+/// 変化点検出のError
+#[derive(Debug, Clone, uniffi::Error)]
+pub enum MobileError {
+    Detection { message: String },
+}
+
+impl std::fmt::Display for MobileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            MobileError::Detection { message } => write!(f, "{message}"),
+        }
+    }
+}
+
+impl From<CalcDpError> for MobileError {
+    fn from(e: CalcDpError) -> Self {
+        MobileError::Detection { message: e.to_string() }
+    }
+}
+
+
+/// 変化点を検出する
+///
+/// # 引数
+/// * `columns` - コスト関数へ渡す列ごとの値
+/// * `cost` - [`CostRegistry::with_builtins`]に登録されたコスト関数の名前
+/// * `method` - 変化点検出の手法
+/// * `penalty` - 変化点個数の決め方
+#[uniffi::export]
+pub fn detect_series(columns: Vec<Vec<f64>>, cost: String, method: MobileMethod, penalty: MobilePenalty) -> Result<MobileResult, MobileError> {
+    let params = BTreeMap::new();
+    let input = CostInput {
+        columns: &columns,
+        params: &params,
+    };
+    let cost = CostRegistry::with_builtins().build(&cost, &input)?;
+    let result = detect::detect(&cost, method.into(), &penalty.into(), &Constraints::default())?;
+    Ok(MobileResult {
+        change_points: result.change_points,
+        value: result.value,
+    })
+}
+
+
+/// 登録されているコスト関数の名前
+#[uniffi::export]
+pub fn cost_names() -> Vec<String> {
+    CostRegistry::with_builtins().names()
+                                 .into_iter()
+                                 .map(|name| name.to_owned())
+                                 .collect()
+}
@@ -0,0 +1,137 @@
+//! 逐次的に観測される系列に対する変化点検出
+//!
+//! 観測を1つずつ受け取り，その都度検出統計量と警報の有無を返す検出器を扱う．
+//! 検出器は[`OnlineDetector`]を実装し，[`OnlineDetector::run_jsonl`]によりJSON Lines形式の入出力で動作させることができる．
+
+use crate::dp_tools::CalcDpError;
+
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+extern crate serde_json;
+
+mod cusum;
+pub use cusum::StreamDetector;
+mod policy;
+pub use policy::{AlarmPolicy, PolicedDetector};
+mod arl;
+pub use arl::{estimate_arl, SimulationModel, GaussianShift, ArlEstimate, RunLengthStats, MAX_RUN_LENGTH};
+
+
+/// 観測1つ分の検出結果
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    /// 検出統計量
+    pub statistic: f64,
+    /// 検出統計量が閾値を超えたか
+    pub exceeded: bool,
+    /// 変化点の推定値（変化前の最後の観測の番号）．変化の兆候がない場合は`None`．
+    pub change_point: Option<u64>,
+}
+
+
+/// 逐次的な変化点検出器
+///
+/// 観測の番号は検出器の作成時または[`OnlineDetector::reset`]の呼び出し時から数えた1始まりの番号とする．
+pub trait OnlineDetector {
+    /// 観測を1つ追加する
+    ///
+    /// # 引数
+    /// * `x` - 観測値
+    fn update(&mut self, x: f64) -> Step;
+
+
+    /// 検出統計量を初期状態に戻す
+    fn reset(&mut self);
+
+
+    /// 警報の後に監視を再開する
+    ///
+    /// 既定では[`OnlineDetector::reset`]と同じである．
+    /// 警報の履歴に依存する検出器（[`PolicedDetector`]等）は，履歴を保持したまま検出統計量のみを初期化する．
+    fn rearm(&mut self) {
+        self.reset();
+    }
+
+
+    /// JSON Lines形式で観測を読み込み，検出結果を書き出す
+    ///
+    /// 入力の各行は`{"t": ..., "x": ...}`の形式とする．
+    /// `t`は任意のJSONの値（時刻等）で，省略した場合は行番号（1始まり）となる．
+    /// 空行は読み飛ばす．
+    ///
+    /// 出力の各行は`{"t": ..., "statistic": ..., "alarm": ..., "cp": ...}`の形式とする．
+    /// `cp`は警報時のみ変化前の最後の観測の`t`を，それ以外では`null`を出力する．
+    /// 警報の後は[`OnlineDetector::rearm`]により監視を再開する．
+    ///
+    /// # 引数
+    /// * `input` - 入力
+    /// * `output` - 出力先
+    fn run_jsonl<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> Result<(), CalcDpError>
+    where
+        Self: Sized
+    {
+        // 変化点の候補となり得る観測の番号と`t`
+        let mut history: VecDeque<(u64, serde_json::Value)> = VecDeque::new();
+        let mut idx = 0;
+        for (line_no, line) in input.lines().enumerate() {
+            let line = line.map_err(|e| CalcDpError::new(format!("Failed to read input: {e}")).with_source(e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str::<InputRecord>(&line).map_err(|e| CalcDpError::new(format!("Line {}: {e}", line_no + 1)).with_source(e))?;
+            let t = record.t.unwrap_or_else(|| serde_json::Value::from(line_no + 1));
+
+            idx += 1;
+            history.push_back((idx, t.clone()));
+            let step = self.update(record.x);
+
+            let cp = if step.exceeded {
+                let cp = step.change_point
+                             .and_then(|c| history.iter().find(|(i, _)| *i == c))
+                             .map(|(_, t)| t.clone())
+                             .unwrap_or(serde_json::Value::Null);
+                // 初期化後の番号0は警報を出した観測に対応する
+                self.rearm();
+                history.clear();
+                history.push_back((0, t.clone()));
+                idx = 0;
+                cp
+            } else {
+                // 候補より前の観測は不要
+                let keep_from = step.change_point.unwrap_or(idx);
+                while history.front().is_some_and(|(i, _)| *i < keep_from) {
+                    history.pop_front();
+                }
+                serde_json::Value::Null
+            };
+
+            let record = OutputRecord { t, statistic: step.statistic, alarm: step.exceeded, cp };
+            let text = serde_json::to_string(&record).map_err(|e| CalcDpError::new(format!("Failed to serialize output: {e}")).with_source(e))?;
+            writeln!(output, "{text}").map_err(|e| CalcDpError::new(format!("Failed to write output: {e}")).with_source(e))?;
+        }
+        output.flush().map_err(|e| CalcDpError::new(format!("Failed to write output: {e}")).with_source(e))
+    }
+}
+
+
+/// JSON Lines形式の入力1行
+#[derive(Deserialize)]
+struct InputRecord {
+    #[serde(default)]
+    t: Option<serde_json::Value>,
+    x: f64,
+}
+
+
+/// JSON Lines形式の出力1行
+#[derive(Serialize)]
+struct OutputRecord {
+    t: serde_json::Value,
+    statistic: f64,
+    alarm: bool,
+    cp: serde_json::Value,
+}
@@ -0,0 +1,146 @@
+//! 平均連長(ARL: Average run length)の推定
+
+use super::OnlineDetector;
+use crate::dp_tools::CalcDpError;
+use crate::rng::RngConfig;
+
+extern crate rand;
+use rand::Rng;
+use rand::rngs::StdRng;
+
+extern crate rand_distr;
+use rand_distr::{Distribution, Normal};
+
+extern crate rayon;
+use rayon::prelude::*;
+
+
+/// 1回の試行で生成する観測数の上限
+///
+/// 上限までに警報が出なかった試行は上限の値を連長として扱う（打ち切り）．
+pub const MAX_RUN_LENGTH: u64 = 1_000_000;
+
+
+/// ARLの推定に用いる系列の生成モデル
+pub trait SimulationModel {
+    /// 管理状態の観測を生成する
+    fn in_control<R: Rng + ?Sized>(&self, rng: &mut R) -> f64;
+
+
+    /// 変化後（管理外れ状態）の観測を生成する
+    fn out_of_control<R: Rng + ?Sized>(&self, rng: &mut R) -> f64;
+}
+
+
+/// 正規分布の平均の変化
+#[derive(Debug, Clone, Copy)]
+pub struct GaussianShift {
+    normal: Normal<f64>,
+    shift: f64,
+}
+
+impl GaussianShift {
+    /// 生成モデルを作成
+    ///
+    /// # 引数
+    /// * `mean` - 管理状態の平均
+    /// * `sd` - 標準偏差（正の値）
+    /// * `shift` - 変化後の平均の変化量
+    pub fn new(mean: f64, sd: f64, shift: f64) -> Result<Self, CalcDpError> {
+        if !(mean.is_finite() && sd.is_finite() && sd > 0.0 && shift.is_finite()) {
+            return Err(CalcDpError::new(format!("Mean and shift must be finite and standard deviation must be positive, but ({mean}, {sd}, {shift}) is given.")));
+        }
+        let normal = Normal::new(mean, sd).map_err(|e| CalcDpError::new(format!("Invalid normal distribution N({mean}, {sd}^2): {e}")).with_source(e))?;
+        Ok(GaussianShift { normal, shift })
+    }
+}
+
+impl SimulationModel for GaussianShift {
+    fn in_control<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.normal.sample(rng)
+    }
+
+
+    fn out_of_control<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.normal.sample(rng) + self.shift
+    }
+}
+
+
+/// 連長の推定結果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunLengthStats {
+    /// 平均連長
+    pub mean: f64,
+    /// 平均連長の標準誤差
+    pub std_error: f64,
+    /// [`MAX_RUN_LENGTH`]で打ち切った試行の数．0でない場合，平均連長は過小評価となる．
+    pub censored: usize,
+}
+
+
+/// ARLの推定結果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArlEstimate {
+    /// 管理状態における平均連長（ARL0）．誤警報までの平均観測数．
+    pub in_control: RunLengthStats,
+    /// 監視開始時点から管理外れ状態である場合の平均連長（ARL1）．検出までの平均観測数．
+    pub out_of_control: RunLengthStats,
+}
+
+
+/// モンテカルロ法により逐次検出器のARLを推定する
+///
+/// 各試行では検出器を初期化した上で，警報が出るまで生成モデルから観測を与える．
+/// 各試行の乱数は`rng`のseedと試行番号から決まるため，並列に計算しても結果は再現できる．
+/// 管理状態と管理外れ状態の試行には[`RngConfig::fork`]により分けた乱数の設定を用いる．
+///
+/// # 引数
+/// * `detector` - 逐次検出器（試行ごとに複製して用いる）
+/// * `model` - 系列の生成モデル
+/// * `n_sims` - 試行回数（2以上）
+/// * `rng` - 乱数の設定（`u64`のseedも与えられる）
+pub fn estimate_arl<D, M>(detector: &D, model: &M, n_sims: usize, rng: impl Into<RngConfig>) -> Result<ArlEstimate, CalcDpError>
+where
+    D: OnlineDetector + Clone + Sync,
+    M: SimulationModel + Sync,
+{
+    if n_sims < 2 {
+        return Err(CalcDpError::new(format!("The number of simulations must be at least 2, but {n_sims} is given.")));
+    }
+    let rng = rng.into();
+    let in_control = simulate(detector, n_sims, rng.fork(0), |rng| model.in_control(rng));
+    let out_of_control = simulate(detector, n_sims, rng.fork(1), |rng| model.out_of_control(rng));
+    Ok(ArlEstimate { in_control, out_of_control })
+}
+
+
+/// 連長を繰り返し計算し，その平均と標準誤差を求める
+fn simulate<D, F>(detector: &D, n_sims: usize, rng: RngConfig, sample: F) -> RunLengthStats
+where
+    D: OnlineDetector + Clone + Sync,
+    F: Fn(&mut StdRng) -> f64 + Sync,
+{
+    let lengths = (0..n_sims).into_par_iter()
+                             .map(|i| {
+                                 let mut rng = rng.trial(i as u64);
+                                 let mut det = detector.clone();
+                                 det.reset();
+                                 (1..=MAX_RUN_LENGTH).find(|_| det.update(sample(&mut rng)).exceeded)
+                             })
+                             .collect::<Vec<Option<u64>>>();
+
+    let censored = lengths.iter().filter(|l| l.is_none()).count();
+    let lengths = lengths.iter()
+                         .map(|l| l.unwrap_or(MAX_RUN_LENGTH) as f64)
+                         .collect::<Vec<f64>>();
+    let n = lengths.len() as f64;
+    let mean = lengths.iter().sum::<f64>() / n;
+    let var = lengths.iter().map(|l| (l - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    RunLengthStats {
+        mean,
+        std_error: (var / n).sqrt(),
+        censored,
+    }
+}
+
@@ -0,0 +1,154 @@
+//! 累積和(CUSUM)による平均変化の逐次検出
+
+use super::{OnlineDetector, Step};
+use crate::detect::DetectionResult;
+use crate::dp_tools::CalcDpError;
+
+
+/// 両側CUSUMによる平均変化の逐次検出器
+///
+/// 管理状態の平均$ \mu_0 $と標準偏差$ \sigma $が既知の系列に対し，標準化した観測$ z_t = (x_t - \mu_0) / \sigma $から
+/// $$ S^+_t = \max(0, S^+_{t-1} + z_t - k), \quad S^-_t = \max(0, S^-_{t-1} - z_t - k) $$
+/// を計算し，$ \max(S^+_t, S^-_t) $が閾値$ h $を超えた時点で変化を検出する．
+/// 変化点は，閾値を超えた側の累積和が最後に0であった観測として推定する．
+#[derive(Debug, Clone)]
+pub struct StreamDetector {
+    mean: f64,
+    sd: f64,
+    drift: f64,
+    threshold: f64,
+    t: u64,
+    pos: f64,
+    neg: f64,
+    pos_start: u64,
+    neg_start: u64,
+    origin: u64,
+    last_change_point: Option<u64>,
+}
+
+impl StreamDetector {
+    /// 検出器を作成
+    ///
+    /// # 引数
+    /// * `mean` - 管理状態の平均$ \mu_0 $
+    /// * `sd` - 管理状態の標準偏差$ \sigma $（正の値）
+    /// * `drift` - 参照値$ k $（非負の値）．検出したい変化量（標準偏差単位）の半分とすることが多い．
+    /// * `threshold` - 閾値$ h $（正の値）
+    pub fn new(mean: f64, sd: f64, drift: f64, threshold: f64) -> Result<Self, CalcDpError> {
+        if !(mean.is_finite() && sd.is_finite() && sd > 0.0) {
+            return Err(CalcDpError::new(format!("Mean must be finite and standard deviation must be positive, but ({mean}, {sd}) is given.")));
+        }
+        if !(drift.is_finite() && drift >= 0.0 && threshold > 0.0) {
+            return Err(CalcDpError::new(format!("Drift must be non-negative and threshold must be positive, but ({drift}, {threshold}) is given.")));
+        }
+        Ok(StreamDetector { mean, sd, drift, threshold, t: 0, pos: 0.0, neg: 0.0, pos_start: 0, neg_start: 0, origin: 0, last_change_point: None })
+    }
+
+
+    /// オフラインの検出結果から監視を再開する検出器を作成
+    ///
+    /// 管理状態の平均は最後の区間の標本平均，標準偏差は全区間の区間内偏差から求めた併合標準偏差とする．
+    /// 監視は`data`の直後の観測から再開するものとし，観測の番号に[`StreamDetector::origin`]を加えると`data`と共通の時点となる．
+    ///
+    /// # 引数
+    /// * `result` - `data`に対する検出結果
+    /// * `data` - 検出に用いた系列
+    /// * `drift` - 参照値$ k $
+    /// * `threshold` - 閾値$ h $
+    pub fn from_fit(result: &DetectionResult, data: &[f64], drift: f64, threshold: f64) -> Result<Self, CalcDpError> {
+        if result.t_max as usize != data.len() {
+            return Err(CalcDpError::new(format!("Length of data (= {}) must be equal to t_max of the result (= {}).", data.len(), result.t_max)));
+        }
+        let segments = result.segments();
+        let mut ss = 0.0;
+        let mut dof = 0;
+        let mut last_mean = 0.0;
+        for (t_k_1, t_k) in segments.iter() {
+            let seg = &data[(*t_k_1 as usize)..(*t_k as usize)];
+            let mean = seg.iter().sum::<f64>() / seg.len() as f64;
+            ss += seg.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+            dof += seg.len() - 1;
+            last_mean = mean;
+        }
+        if dof == 0 {
+            return Err(CalcDpError::new("Segments are too short to estimate the standard deviation."));
+        }
+
+        let mut detector = Self::new(last_mean, (ss / dof as f64).sqrt(), drift, threshold)?;
+        detector.origin = data.len() as u64;
+        detector.last_change_point = result.change_points.last().map(|t| *t as u64);
+        Ok(detector)
+    }
+
+
+    /// 管理状態の平均
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+
+    /// 管理状態の標準偏差
+    pub fn sd(&self) -> f64 {
+        self.sd
+    }
+
+
+    /// 参照値$ k $
+    pub fn drift(&self) -> f64 {
+        self.drift
+    }
+
+
+    /// 閾値$ h $
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+
+    /// 監視開始前の観測数．[`StreamDetector::from_fit`]で作成した場合は検出に用いた系列の長さ，それ以外は0．
+    pub fn origin(&self) -> u64 {
+        self.origin
+    }
+
+
+    /// 監視開始前の最後の変化点．[`StreamDetector::from_fit`]で作成した場合のみ値を持つ．
+    pub fn last_change_point(&self) -> Option<u64> {
+        self.last_change_point
+    }
+}
+
+impl OnlineDetector for StreamDetector {
+    fn update(&mut self, x: f64) -> Step {
+        self.t += 1;
+        let z = (x - self.mean) / self.sd;
+
+        self.pos = (self.pos + z - self.drift).max(0.0);
+        if self.pos == 0.0 {
+            self.pos_start = self.t;
+        }
+        self.neg = (self.neg - z - self.drift).max(0.0);
+        if self.neg == 0.0 {
+            self.neg_start = self.t;
+        }
+
+        let (statistic, start) = if self.pos >= self.neg {
+            (self.pos, self.pos_start)
+        } else {
+            (self.neg, self.neg_start)
+        };
+        Step {
+            statistic,
+            exceeded: statistic > self.threshold,
+            change_point: if statistic > 0.0 { Some(start) } else { None },
+        }
+    }
+
+
+    fn reset(&mut self) {
+        self.t = 0;
+        self.pos = 0.0;
+        self.neg = 0.0;
+        self.pos_start = 0;
+        self.neg_start = 0;
+    }
+}
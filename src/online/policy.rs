@@ -0,0 +1,122 @@
+//! 逐次検出器の警報の出し方の制御
+
+use super::{OnlineDetector, Step};
+use crate::dp_tools::CalcDpError;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 警報の出し方
+///
+/// 検出器が閾値を超えた時点で即座に警報を出すのではなく，以下の条件を加えることで誤警報を抑える．
+/// いずれも検出の遅れと引き換えになる．
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlarmPolicy {
+    /// 警報を出すために必要な，閾値を連続して超えた回数（1以上）
+    pub consecutive: u32,
+    /// 警報の後，次の警報を出さない観測の数
+    pub rearm_interval: u64,
+    /// 警報の後，検出統計量がこの値を下回るまで次の警報を出さない（ヒステリシス）．`None`の場合は条件を課さない．
+    pub release_level: Option<f64>,
+}
+
+impl Default for AlarmPolicy {
+    fn default() -> Self {
+        AlarmPolicy {
+            consecutive: 1,
+            rearm_interval: 0,
+            release_level: None,
+        }
+    }
+}
+
+
+/// 警報の出し方を適用した逐次検出器
+///
+/// 返り値の[`Step::exceeded`]は警報の有無を表す．
+/// 検出統計量と変化点の推定値は元の検出器の値をそのまま返す．
+#[derive(Debug, Clone)]
+pub struct PolicedDetector<D> {
+    detector: D,
+    policy: AlarmPolicy,
+    /// 閾値を連続して超えた回数
+    run: u32,
+    /// 直前の警報からの観測数
+    since_alarm: Option<u64>,
+    /// ヒステリシスにより警報が抑制されている状態か
+    latched: bool,
+}
+
+impl<D: OnlineDetector> PolicedDetector<D> {
+    /// 検出器に警報の出し方を適用する
+    ///
+    /// # 引数
+    /// * `detector` - 逐次検出器
+    /// * `policy` - 警報の出し方
+    pub fn new(detector: D, policy: AlarmPolicy) -> Result<Self, CalcDpError> {
+        if policy.consecutive == 0 {
+            return Err(CalcDpError::new("The number of consecutive exceedances must be at least 1."));
+        }
+        Ok(PolicedDetector { detector, policy, run: 0, since_alarm: None, latched: false })
+    }
+
+
+    /// 元の検出器
+    pub fn detector(&self) -> &D {
+        &self.detector
+    }
+
+
+    /// 警報の出し方
+    pub fn policy(&self) -> &AlarmPolicy {
+        &self.policy
+    }
+
+
+    /// 元の検出器を取り出す
+    pub fn into_inner(self) -> D {
+        self.detector
+    }
+}
+
+impl<D: OnlineDetector> OnlineDetector for PolicedDetector<D> {
+    fn update(&mut self, x: f64) -> Step {
+        let step = self.detector.update(x);
+
+        if let Some(n) = self.since_alarm.as_mut() {
+            *n += 1;
+        }
+        self.run = if step.exceeded { self.run.saturating_add(1) } else { 0 };
+        if let Some(level) = self.policy.release_level {
+            if self.latched && step.statistic < level {
+                self.latched = false;
+            }
+        }
+
+        let suppressed = self.latched || self.since_alarm.is_some_and(|n| n <= self.policy.rearm_interval);
+        let alarm = !suppressed && self.run >= self.policy.consecutive;
+        if alarm {
+            self.run = 0;
+            self.since_alarm = Some(0);
+            self.latched = self.policy.release_level.is_some();
+        }
+        Step { exceeded: alarm, ..step }
+    }
+
+
+    fn reset(&mut self) {
+        self.detector.reset();
+        self.run = 0;
+        self.since_alarm = None;
+        self.latched = false;
+    }
+
+
+    /// 元の検出器のみを再開し，直前の警報からの観測数とヒステリシスの状態は保持する
+    fn rearm(&mut self) {
+        self.detector.rearm();
+        self.run = 0;
+    }
+}
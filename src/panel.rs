@@ -0,0 +1,275 @@
+//! 複数の系列（パネル）に共通する変化点の検出
+//!
+//! 同じ時期に観測した複数の系列（例えば同型の複数の機械）において，全系列に共通する時期に変化が生じる場合を扱う．
+//! 系列ごとの評価値の増加量を系列間で集約（[`Aggregation`]）し，変化点1個あたりに系列全体で1つのペナルティ（群ペナルティ）を課す．
+//! 個々の系列では有意とならない弱い変化でも，多数の系列で同時に生じていれば検出できる．
+//!
+//! 系列ごとに異なる変化点を許し，隣接する系列（[`Adjacency`]）が変化点を共有しやすくする場合は[`detect_graph`]を用いる．
+
+use crate::cost::SegmentCost;
+use crate::detect::{Penalty, Constraints, DetectionResult};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+mod graph;
+pub use graph::{detect_graph, Adjacency, GraphConfig, GraphResult};
+
+
+/// 系列ごとの評価値の増加量の集約方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation {
+    /// 増加量の和．正規分布の平均の変化では，系列ごとの標準化したCUSUM統計量のL2ノルムの2乗の半分に相当する．
+    ///
+    /// 多数の系列で同時に生じる弱い変化の検出に適する．
+    L2,
+    /// 増加量の最大値（L∞ノルム）．一部の系列のみで生じる強い変化の検出に適する．
+    LInf,
+}
+
+impl Aggregation {
+    fn aggregate(&self, gains: &[f64]) -> f64 {
+        match self {
+            Aggregation::L2 => gains.iter().sum(),
+            Aggregation::LInf => gains.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+
+/// 群ペナルティの既定値
+///
+/// 変化がなければ系列ごとの増加量は自由度1の$ \chi^2 $分布の半分に従うものとし，
+/// 分割点の選択による多重性を$ x = 2 \ln T $として上側の確率の評価から定める．
+/// * [`Aggregation::L2`] - 自由度$ N $の$ \chi^2 $分布に対するLaurent–Massartの不等式から$ N/2 + \sqrt{N x} + x $
+/// * [`Aggregation::LInf`] - $ N $個の最大値に対して$ \ln N + x $
+///
+/// # 引数
+/// * `n_series` - 系列の数$ N $
+/// * `t_max` - 系列の長さ$ T $
+/// * `aggregation` - 集約方法
+pub fn group_penalty(n_series: usize, t_max: Tau, aggregation: Aggregation) -> f64 {
+    let n = n_series.max(1) as f64;
+    let x = 2.0 * (t_max as f64).max(1.0).ln();
+    match aggregation {
+        Aggregation::L2 => n / 2.0 + (n * x).sqrt() + x,
+        Aggregation::LInf => n.ln() + x,
+    }
+}
+
+
+/// 同じ長さの複数の系列のコスト関数
+///
+/// 評価値は全系列の評価値の和であり，[`crate::detect::detect`]へ与えると全系列に共通する変化点を動的計画法で求められる．
+/// この場合は[`Aggregation::L2`]に相当し，[`Penalty::Linear`]のペナルティが群ペナルティとなる．
+#[derive(Debug)]
+pub struct Panel<'a, C> {
+    series: &'a [C],
+    t_max: Tau,
+}
+
+impl<C> Clone for Panel<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Panel<'_, C> {}
+
+impl<'a, C: SegmentCost> Panel<'a, C> {
+    /// 系列ごとのコスト関数からパネルを作成する
+    ///
+    /// # 引数
+    /// * `series` - 系列ごとのコスト関数（全て同じ長さ）
+    pub fn new(series: &'a [C]) -> Result<Self, CalcDpError> {
+        let t_max = match series.first() {
+            Some(cost) => cost.t_max(),
+            None => return Err(CalcDpError::new("Panel must contain at least one series.")),
+        };
+        if let Some((i, cost)) = series.iter().enumerate().find(|(_, cost)| cost.t_max() != t_max) {
+            return Err(CalcDpError::new(format!("Series {i} has length {}, but the panel has length {t_max}.", cost.t_max())));
+        }
+        Ok(Panel { series, t_max })
+    }
+
+
+    /// 系列ごとのコスト関数
+    pub fn series(&self) -> &'a [C] {
+        self.series
+    }
+
+
+    /// 区間$ (a, b] $を$ s $で分割した場合の系列ごとの評価値の増加量．評価値が定義できない区間を生じる場合は`None`．
+    fn gains(&self, a: Tau, s: Tau, b: Tau) -> Result<Option<Vec<f64>>, CalcDpError> {
+        let mut gains = Vec::with_capacity(self.series.len());
+        for cost in self.series {
+            let left = cost.segment_value(a, s)?;
+            let right = cost.segment_value(s, b)?;
+            if left == f64::NEG_INFINITY || right == f64::NEG_INFINITY {
+                return Ok(None);
+            }
+            gains.push(left + right - cost.segment_value(a, b)?);
+        }
+        Ok(Some(gains))
+    }
+}
+
+impl<C: SegmentCost> SegmentCost for Panel<'_, C> {
+    fn t_max(&self) -> Tau {
+        self.t_max
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.series.iter().map(|cost| cost.segment_value(t_k_1, t_k)).sum()
+    }
+
+
+    fn constant_time(&self) -> bool {
+        self.series.iter().all(|cost| cost.constant_time())
+    }
+
+
+    fn n_parameters(&self) -> usize {
+        self.series.iter().map(|cost| cost.n_parameters()).sum()
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        self.series.iter().map(|cost| cost.min_identifiable_size()).max().unwrap_or(1)
+    }
+
+
+    fn rejects_short_segments(&self) -> bool {
+        self.series.iter().all(|cost| cost.rejects_short_segments())
+    }
+
+
+    fn pruning_constant(&self) -> f64 {
+        self.series.iter().map(|cost| cost.pruning_constant()).sum()
+    }
+}
+
+
+/// パネルからの変化点検出の結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PanelResult {
+    /// 全系列に共通する変化点の検出結果．評価値は全系列の評価値の和．
+    pub result: DetectionResult,
+    /// 変化点ごとの集約した増加量
+    pub statistics: Vec<f64>,
+    /// 変化点ごとの系列ごとの増加量（前後の変化点に挟まれた区間を分割した場合）
+    pub gains: Vec<Vec<f64>>,
+}
+
+
+/// 系列ごとの増加量を集約した二分割法により，全系列に共通する変化点を検出する
+///
+/// 現在の各区間について集約した増加量が最大となる分割点を求め，それが最も大きい区間を分割する操作を繰り返す．
+/// 分割の停止条件は[`crate::search::binseg`]と同じく`penalty`に従い，[`Penalty::Linear`]では集約した増加量を群ペナルティ$ \beta $と比較する．
+/// 群ペナルティの目安は[`group_penalty`]で求められる．
+///
+/// # 引数
+/// * `panel` - パネル
+/// * `aggregation` - 集約方法
+/// * `min_size` - 各区間の最小の長さ（1以上）
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+pub fn detect_panel<C>(panel: &Panel<C>, aggregation: Aggregation, min_size: Tau, penalty: &Penalty, constraints: &Constraints) -> Result<PanelResult, CalcDpError>
+where
+    C: SegmentCost,
+{
+    let t_max = panel.t_max();
+    if t_max == 0 {
+        return Err(CalcDpError::new("Series must contain at least one point."));
+    }
+    if min_size == 0 {
+        return Err(CalcDpError::new("Minimum segment length must be at least 1."));
+    }
+    let (k_target, beta) = match penalty {
+        Penalty::NumChange(k) => {
+            if let Some(max_k) = constraints.max_k {
+                if *k > max_k {
+                    return Err(CalcDpError::new(format!("The number of change point k (= {k}) must not exceed max_k (= {max_k}).")));
+                }
+            }
+            (Some(*k), None)
+        },
+        Penalty::Linear(beta) => (None, Some(*beta)),
+    };
+    let k_lim = match (k_target, constraints.max_k) {
+        (Some(k), _) => k,
+        (None, Some(max_k)) => max_k,
+        (None, None) => NumChg::MAX,
+    };
+
+    let mut segments = vec![(0, t_max, best_split(panel, aggregation, 0, t_max, min_size)?)];
+    let mut change_points = Vec::new();
+    while (change_points.len() as NumChg) < k_lim {
+        let best = segments.iter()
+                           .enumerate()
+                           .filter_map(|(i, (_, _, split))| split.map(|(s, stat)| (i, s, stat)))
+                           .fold(None, |acc: Option<(usize, Tau, f64)>, cand| match acc {
+                               Some(a) if a.2 >= cand.2 => Some(a),
+                               _ => Some(cand),
+                           });
+        let (idx, s, stat) = match best {
+            Some(b) => b,
+            None => break,
+        };
+        if beta.is_some_and(|beta| stat <= beta) {
+            break;
+        }
+
+        let (start, end, _) = segments.swap_remove(idx);
+        segments.push((start, s, best_split(panel, aggregation, start, s, min_size)?));
+        segments.push((s, end, best_split(panel, aggregation, s, end, min_size)?));
+        change_points.push(s);
+    }
+
+    if let Some(k) = k_target {
+        if (change_points.len() as NumChg) < k {
+            return Err(CalcDpError::new(format!("Only {} change points can be found (k = {k}).", change_points.len())));
+        }
+    }
+
+    change_points.sort_unstable();
+    let bounds = std::iter::once(0).chain(change_points.iter().copied())
+                                   .chain(std::iter::once(t_max))
+                                   .collect::<Vec<Tau>>();
+    let gains = bounds.windows(3)
+                      .map(|w| Ok(panel.gains(w[0], w[1], w[2])?.unwrap_or_default()))
+                      .collect::<Result<Vec<Vec<f64>>, CalcDpError>>()?;
+    let statistics = gains.iter().map(|g| aggregation.aggregate(g)).collect();
+    let value = bounds.windows(2)
+                      .map(|w| panel.segment_value(w[0], w[1]))
+                      .sum::<Result<f64, CalcDpError>>()?;
+    Ok(PanelResult {
+        result: DetectionResult::new(change_points, value, t_max),
+        statistics,
+        gains,
+    })
+}
+
+
+/// 区間$ (start, end] $で集約した増加量が最大となる分割点
+fn best_split<C: SegmentCost>(panel: &Panel<C>, aggregation: Aggregation, start: Tau, end: Tau, min_size: Tau) -> Result<Option<(Tau, f64)>, CalcDpError> {
+    let mut split: Option<(Tau, f64)> = None;
+    if end - start >= 2 * min_size {
+        for s in (start + min_size)..=(end - min_size) {
+            let stat = match panel.gains(start, s, end)? {
+                Some(gains) => aggregation.aggregate(&gains),
+                None => continue,
+            };
+            if split.is_none_or(|(_, best)| best < stat) {
+                split = Some((s, stat));
+            }
+        }
+    }
+    Ok(split)
+}
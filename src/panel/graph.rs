@@ -0,0 +1,268 @@
+//! 系列間の隣接関係を考慮したパネルの変化点検出
+
+use super::Panel;
+use crate::cost::SegmentCost;
+use crate::detect::{self, Method, Penalty, Constraints, DetectionResult};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 系列間の隣接関係（無向グラフ）
+///
+/// 例えば生産ラインで前後に並ぶ設備のように，変化点を共有しやすい系列の組を辺として与える．
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Adjacency {
+    neighbours: Vec<Vec<(usize, f64)>>,
+}
+
+impl Adjacency {
+    /// 重みが全て1の辺から隣接関係を作成する
+    ///
+    /// # 引数
+    /// * `n_series` - 系列の数
+    /// * `edges` - 辺（系列の番号の組）
+    pub fn new(n_series: usize, edges: &[(usize, usize)]) -> Result<Self, CalcDpError> {
+        let weighted = edges.iter().map(|(i, j)| (*i, *j, 1.0)).collect::<Vec<_>>();
+        Self::with_weights(n_series, &weighted)
+    }
+
+
+    /// 重み付きの辺から隣接関係を作成する
+    ///
+    /// # 引数
+    /// * `n_series` - 系列の数
+    /// * `edges` - 辺（系列の番号の組と重み）．重みは非負．
+    pub fn with_weights(n_series: usize, edges: &[(usize, usize, f64)]) -> Result<Self, CalcDpError> {
+        let mut neighbours = vec![Vec::new(); n_series];
+        for &(i, j, w) in edges {
+            if i >= n_series || j >= n_series || i == j {
+                return Err(CalcDpError::new(format!("Edge ({i}, {j}) is invalid for {n_series} series.")));
+            }
+            if w.is_nan() || w < 0.0 || w.is_infinite() {
+                return Err(CalcDpError::new(format!("Edge weight must be non-negative and finite, but {w} is given for ({i}, {j}).")));
+            }
+            neighbours[i].push((j, w));
+            neighbours[j].push((i, w));
+        }
+        Ok(Adjacency { neighbours })
+    }
+
+
+    /// 系列の数
+    pub fn n_series(&self) -> usize {
+        self.neighbours.len()
+    }
+
+
+    /// 系列`i`に隣接する系列と辺の重み
+    ///
+    /// # 引数
+    /// * `i` - 系列の番号
+    pub fn neighbours(&self, i: usize) -> &[(usize, f64)] {
+        &self.neighbours[i]
+    }
+}
+
+
+/// 隣接関係を考慮した検出の設定
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GraphConfig {
+    /// 変化点1個あたりのペナルティ$ \beta $
+    pub penalty: f64,
+    /// 平滑化の強さ$ \lambda $．隣接する系列で一致する変化点の組1個あたりの報酬．
+    pub smoothing: f64,
+    /// 変化点が一致するとみなす時点の差の上限
+    pub tolerance: Tau,
+    /// 各区間の最小の長さ．`2 * tolerance`より大きくする．
+    ///
+    /// 各系列の変化点の間隔が`2 * tolerance`より大きければ，1個の変化点と一致する隣接する系列の変化点は高々1個となる．
+    /// そのため報酬は変化点1個あたり$ \lambda \sum_j w_{ij} $を超えず，隣接する系列の変化点の周囲に変化点が密集することはない．
+    pub min_size: Tau,
+    /// 系列ごとの動的計画法による更新を全系列に行う回数の上限
+    pub max_sweeps: usize,
+    /// 系列ごとの変化点検出の手法
+    pub method: Method,
+}
+
+impl Default for GraphConfig {
+    fn default() -> Self {
+        GraphConfig {
+            penalty: 0.0,
+            smoothing: 0.0,
+            tolerance: 0,
+            min_size: 1,
+            max_sweeps: 20,
+            method: Method::Dp,
+        }
+    }
+}
+
+
+/// 隣接関係を考慮した検出の結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphResult {
+    /// 系列ごとの検出結果．評価値は平滑化の報酬を含まない．
+    pub results: Vec<DetectionResult>,
+    /// 目的関数の値 $ \sum_i (f_i - \beta K_i) + \lambda \sum_{(i, j)} w_{ij} M_{ij} $
+    pub objective: f64,
+    /// 行った更新の回数
+    pub sweeps: usize,
+    /// いずれの系列の変化点も変わらなくなって終了したか
+    pub converged: bool,
+}
+
+
+/// 隣接する系列が変化点を共有しやすくなるよう平滑化して，系列ごとの変化点を検出する
+///
+/// 系列$ i $の変化点の評価値$ f_i $，変化点個数$ K_i $，隣接する系列$ i, j $の変化点のうち時点の差が`tolerance`以下の組の数$ M_{ij} $について，
+/// $$ \sum_i (f_i - \beta K_i) + \lambda \sum_{(i, j)} w_{ij} M_{ij} $$
+/// を最大化する．
+/// 他の系列の変化点を固定すると，系列$ i $の目的関数は変化点$ t $ごとに隣接する系列の近くの変化点の数に比例した報酬を加えたものとなり，
+/// 動的計画法で厳密に最大化できる．
+/// そこで平滑化のない系列ごとの検出結果から始め，各系列の変化点を順に更新する操作を変化がなくなるまで繰り返す．
+/// 各更新で目的関数は減少しないため，局所最適解に収束する．
+///
+/// # 引数
+/// * `panel` - パネル
+/// * `adjacency` - 系列間の隣接関係
+/// * `config` - 検出の設定
+pub fn detect_graph<C>(panel: &Panel<C>, adjacency: &Adjacency, config: &GraphConfig) -> Result<GraphResult, CalcDpError>
+where
+    C: SegmentCost,
+{
+    let series = panel.series();
+    if adjacency.n_series() != series.len() {
+        return Err(CalcDpError::new(format!("Adjacency has {} series, but the panel has {}.", adjacency.n_series(), series.len())));
+    }
+    if config.smoothing.is_nan() || config.smoothing < 0.0 {
+        return Err(CalcDpError::new(format!("Smoothing must be non-negative, but {} is given.", config.smoothing)));
+    }
+    if config.tolerance.checked_mul(2).is_none_or(|w| config.min_size <= w) {
+        return Err(CalcDpError::new(format!("min_size (= {}) must exceed twice the tolerance (= {}).", config.min_size, config.tolerance)));
+    }
+
+    let t_max = panel.t_max();
+    let penalty = Penalty::Linear(config.penalty);
+    let constraints = Constraints { min_size: Some(config.min_size), ..Default::default() };
+    let mut results = series.iter()
+                            .map(|cost| detect::detect(cost, config.method, &penalty, &constraints))
+                            .collect::<Result<Vec<DetectionResult>, CalcDpError>>()?;
+
+    let mut sweeps = 0;
+    let mut converged = config.smoothing == 0.0;
+    while !converged && sweeps < config.max_sweeps {
+        sweeps += 1;
+        converged = true;
+        for (i, cost) in series.iter().enumerate() {
+            let mut bonus = vec![0.0; t_max as usize + 1];
+            for &(j, w) in adjacency.neighbours(i) {
+                for &c in &results[j].change_points {
+                    let lo = c.saturating_sub(config.tolerance);
+                    let hi = c.saturating_add(config.tolerance).min(t_max);
+                    for t in lo..=hi {
+                        bonus[t as usize] += config.smoothing * w;
+                    }
+                }
+            }
+            let rewarded = Rewarded { cost, bonus };
+            let mut res = detect::detect(&rewarded, config.method, &penalty, &constraints)?;
+            if res.change_points != results[i].change_points {
+                converged = false;
+            }
+            res.value = segmentation_value(cost, &res.change_points)?;
+            results[i] = res;
+        }
+    }
+
+    let fit = results.iter()
+                     .map(|res| res.value - config.penalty * res.change_points.len() as f64)
+                     .sum::<f64>();
+    let matches = (0..series.len()).flat_map(|i| adjacency.neighbours(i).iter().map(move |(j, w)| (i, *j, *w)))
+                                   .filter(|(i, j, _)| i < j)
+                                   .map(|(i, j, w)| w * count_matches(&results[i].change_points, &results[j].change_points, config.tolerance) as f64)
+                                   .sum::<f64>();
+    Ok(GraphResult {
+        results,
+        objective: fit + config.smoothing * matches,
+        sweeps,
+        converged,
+    })
+}
+
+
+/// 時点の差が`tolerance`以下の変化点の組の数
+fn count_matches(a: &[Tau], b: &[Tau], tolerance: Tau) -> usize {
+    a.iter()
+     .map(|x| b.iter().filter(|y| x.abs_diff(**y) <= tolerance).count())
+     .sum()
+}
+
+
+/// 変化点で区切った区間の評価値の和
+fn segmentation_value<C: SegmentCost>(cost: &C, change_points: &[Tau]) -> Result<f64, CalcDpError> {
+    let bounds = std::iter::once(0).chain(change_points.iter().copied())
+                                   .chain(std::iter::once(cost.t_max()))
+                                   .collect::<Vec<Tau>>();
+    bounds.windows(2)
+          .map(|w| cost.segment_value(w[0], w[1]))
+          .sum()
+}
+
+
+/// 変化点$ t_k $（系列の最後の時期を除く）で終わる区間に報酬を加えたコスト関数
+struct Rewarded<'a, C> {
+    cost: &'a C,
+    bonus: Vec<f64>,
+}
+
+impl<C: SegmentCost> SegmentCost for Rewarded<'_, C> {
+    fn t_max(&self) -> Tau {
+        self.cost.t_max()
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let val = self.cost.segment_value(t_k_1, t_k)?;
+        if t_k == self.t_max() || val == f64::NEG_INFINITY {
+            Ok(val)
+        } else {
+            Ok(val + self.bonus[t_k as usize])
+        }
+    }
+
+
+    fn constant_time(&self) -> bool {
+        self.cost.constant_time()
+    }
+
+
+    fn n_parameters(&self) -> usize {
+        self.cost.n_parameters()
+    }
+
+
+    fn min_identifiable_size(&self) -> Tau {
+        self.cost.min_identifiable_size()
+    }
+
+
+    fn rejects_short_segments(&self) -> bool {
+        self.cost.rejects_short_segments()
+    }
+
+
+    /// 分割により報酬が加わるため，元のコスト関数の定数に報酬の最小値（正の場合は0）を加える
+    fn pruning_constant(&self) -> f64 {
+        let min_bonus = self.bonus.iter()
+                                  .take(self.t_max() as usize)
+                                  .skip(1)
+                                  .copied()
+                                  .fold(0.0, f64::min);
+        self.cost.pruning_constant() + min_bonus
+    }
+}
@@ -0,0 +1,16 @@
+//! 変化点検出の前処理
+//!
+//! 自己相関を持つ系列に対して独立性を仮定したコスト関数を適用すると，
+//! 滑らかな変動を平均の変化とみなし，多数の誤った変化点を検出する．
+//! 本moduleはその影響を補正するための処理を提供する．
+//! また，高次元の系列を少数の成分へ射影し，多変量のコスト関数を適用しやすくする処理（[`project`]）も提供する．
+//! さらに，自己相関とコスト関数の母数の個数から区間の最小の長さを提案する診断（[`suggest_min_gap`]）を提供する．
+
+mod autocorr;
+pub use autocorr::{autocorrelation_correction, lag1_autocorrelation, AutocorrelationCorrection, MAX_RHO};
+mod min_gap;
+pub use min_gap::{suggest_min_gap, MinGapSuggestion, MinGapWarning};
+mod prewhiten;
+pub use prewhiten::{prewhiten, ArmaOrder, Prewhitened};
+mod project;
+pub use project::{project, Projection, Projected, ProjectedDetection};
@@ -0,0 +1,93 @@
+//! 自己相関に基づく有効標本数の補正
+
+use crate::detect::Penalty;
+use crate::dp_tools::CalcDpError;
+
+
+/// 補正に用いる自己相関係数の上限
+///
+/// 自己相関係数が1に近いと補正係数が発散するため，この値で打ち切る．
+pub const MAX_RHO: f64 = 0.99;
+
+
+/// 自己相関に基づく補正
+///
+/// 系列をAR(1)過程とみなし，ラグ1の自己相関係数$ \rho $から補正係数
+/// $$ c = \frac{1 + \rho}{1 - \rho} $$
+/// を求める．$ c $は平均の推定量の分散が独立な場合の何倍となるかを表し，
+/// 有効標本数は$ n / c $となる．
+/// 正規分布の平均変化に対する評価値の増加量はおおよそ$ c $倍に膨らむため，
+/// ペナルティを$ c $倍する（または検定統計量を$ 1/c $倍する）ことで補正する．
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutocorrelationCorrection {
+    /// 補正に用いたラグ1の自己相関係数（$ [0, $ [`MAX_RHO`] $] $に制限した値）
+    pub rho: f64,
+    /// 補正係数$ c $
+    pub factor: f64,
+    /// 有効標本数$ n / c $
+    pub effective_sample_size: f64,
+}
+
+impl AutocorrelationCorrection {
+    /// ペナルティを補正する
+    ///
+    /// [`Penalty::Linear`]のペナルティを補正係数倍する．[`Penalty::NumChange`]は変更しない．
+    ///
+    /// # 引数
+    /// * `penalty` - 補正前のペナルティ
+    pub fn inflate(&self, penalty: &Penalty) -> Penalty {
+        match penalty {
+            Penalty::Linear(beta) => Penalty::Linear(beta * self.factor),
+            Penalty::NumChange(k) => Penalty::NumChange(*k),
+        }
+    }
+
+
+    /// 検定統計量を補正する
+    ///
+    /// # 引数
+    /// * `statistic` - 評価値の増加量等の検定統計量
+    pub fn deflate(&self, statistic: f64) -> f64 {
+        statistic / self.factor
+    }
+}
+
+
+/// ラグ1の自己相関係数の標本推定値
+///
+/// 平均の変化を含む系列では過大に推定されるため，可能であれば変化を含まない区間や残差を与える．
+///
+/// # 引数
+/// * `data` - 系列（長さ3以上）
+pub fn lag1_autocorrelation(data: &[f64]) -> Result<f64, CalcDpError> {
+    if data.len() < 3 {
+        return Err(CalcDpError::new(format!("At least 3 observations are required, but {} is given.", data.len())));
+    }
+    let n = data.len() as f64;
+    let mean = data.iter().sum::<f64>() / n;
+    let denom = data.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+    if denom == 0.0 {
+        return Ok(0.0);
+    }
+    let numer = data.windows(2)
+                    .map(|w| (w[0] - mean) * (w[1] - mean))
+                    .sum::<f64>();
+    Ok(numer / denom)
+}
+
+
+/// 系列の自己相関に基づく補正を求める
+///
+/// 負の自己相関はペナルティを緩める方向に働くため，保守的に0として扱う．
+///
+/// # 引数
+/// * `data` - 系列（長さ3以上）
+pub fn autocorrelation_correction(data: &[f64]) -> Result<AutocorrelationCorrection, CalcDpError> {
+    let rho = lag1_autocorrelation(data)?.clamp(0.0, MAX_RHO);
+    let factor = (1.0 + rho) / (1.0 - rho);
+    Ok(AutocorrelationCorrection {
+        rho,
+        factor,
+        effective_sample_size: data.len() as f64 / factor,
+    })
+}
@@ -0,0 +1,110 @@
+//! 区間の最小の長さの提案
+
+use super::autocorr::{lag1_autocorrelation, MAX_RHO};
+use crate::cost::SegmentCost;
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// 区間の最小の長さの提案
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinGapSuggestion {
+    /// 区間ごとに推定する母数の個数（[`SegmentCost::n_parameters`]）
+    pub n_parameters: usize,
+    /// 母数を識別できる最小の区間の長さ（[`SegmentCost::min_identifiable_size`]）
+    pub min_identifiable: Tau,
+    /// 1階差分から推定したラグ1の自己相関係数（$ [0, $ [`MAX_RHO`] $] $に制限した値）
+    pub rho: f64,
+    /// 有効標本数の補正係数$ c = (1 + \rho) / (1 - \rho) $
+    pub inflation: f64,
+    /// 提案する区間の最小の長さ
+    pub suggested: Tau,
+}
+
+impl MinGapSuggestion {
+    /// 指定した区間の最小の長さに対する警告
+    ///
+    /// 問題がなければ`None`を返す．
+    ///
+    /// # 引数
+    /// * `min_size` - 利用者が指定する区間の最小の長さ
+    pub fn check(&self, min_size: Tau) -> Option<MinGapWarning> {
+        if min_size < self.min_identifiable {
+            Some(MinGapWarning::Unidentifiable { min_size, required: self.min_identifiable })
+        } else if min_size < self.suggested {
+            Some(MinGapWarning::FewEffectiveObservations { min_size, suggested: self.suggested })
+        } else {
+            None
+        }
+    }
+}
+
+
+/// 区間の最小の長さに対する警告
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinGapWarning {
+    /// 区間ごとの母数を識別できない（例えば1点の区間の分散）
+    Unidentifiable {
+        /// 指定された最小の長さ
+        min_size: Tau,
+        /// 母数の識別に必要な長さ
+        required: Tau,
+    },
+    /// 母数は識別できるが，自己相関により区間あたりの有効標本数が母数の個数に満たない
+    FewEffectiveObservations {
+        /// 指定された最小の長さ
+        min_size: Tau,
+        /// 提案する最小の長さ
+        suggested: Tau,
+    },
+}
+
+impl std::fmt::Display for MinGapWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            MinGapWarning::Unidentifiable { min_size, required } =>
+                write!(f, "min_size (= {min_size}) makes per-segment estimates unidentifiable; at least {required} points are required."),
+            MinGapWarning::FewEffectiveObservations { min_size, suggested } =>
+                write!(f, "min_size (= {min_size}) leaves fewer effective observations than parameters under autocorrelation; {suggested} is suggested."),
+        }
+    }
+}
+
+
+/// 系列の自己相関とコスト関数の母数の個数から，区間の最小の長さを提案する
+///
+/// 自己相関係数$ \rho $は平均の変化の影響を受けにくい1階差分から推定する．
+/// AR(1)過程の1階差分のラグ1の自己相関係数は$ -(1 - \rho) / 2 $であるため，その推定値$ r_\Delta $から$ \rho = 1 + 2 r_\Delta $とする．
+/// 区間あたりの有効標本数$ n / c $が母数の個数と識別に必要な長さの大きい方を下回らないよう，
+/// $$ \left\lceil c \max(p, n_{\min}) \right\rceil $$
+/// を提案する（系列の長さを上限とする）．
+///
+/// # 引数
+/// * `data` - 系列（長さ4以上）．多変量のコスト関数では代表的な1系列を与える．
+/// * `cost` - 検出に用いるコスト関数
+pub fn suggest_min_gap<C: SegmentCost>(data: &[f64], cost: &C) -> Result<MinGapSuggestion, CalcDpError> {
+    if data.len() as Tau != cost.t_max() {
+        return Err(CalcDpError::new(format!("Length of data (= {}) must be equal to the length of the cost (= {}).", data.len(), cost.t_max())));
+    }
+    if data.len() < 4 {
+        return Err(CalcDpError::new(format!("At least 4 observations are required, but {} is given.", data.len())));
+    }
+    let diff = data.windows(2).map(|w| w[1] - w[0]).collect::<Vec<f64>>();
+    let rho = (1.0 + 2.0 * lag1_autocorrelation(&diff)?).clamp(0.0, MAX_RHO);
+    let inflation = (1.0 + rho) / (1.0 - rho);
+
+    let n_parameters = cost.n_parameters();
+    let min_identifiable = cost.min_identifiable_size();
+    let required = n_parameters.max(min_identifiable as usize) as f64;
+    let suggested = ((inflation * required).ceil() as Tau).max(min_identifiable)
+                                                          .min(cost.t_max());
+    Ok(MinGapSuggestion {
+        n_parameters,
+        min_identifiable,
+        rho,
+        inflation,
+        suggested,
+    })
+}
@@ -0,0 +1,150 @@
+//! ARMAモデルによる事前白色化
+
+use crate::cost::SegmentCost;
+use crate::detect::{self, Method, Penalty, Constraints, DetectionResult};
+use crate::dp_tools::CalcDpError;
+use crate::linalg;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// ARMAモデルの次数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArmaOrder {
+    /// 自己回帰(AR)の次数$ p $
+    pub ar: usize,
+    /// 移動平均(MA)の次数$ q $
+    pub ma: usize,
+}
+
+
+/// 事前白色化の結果
+///
+/// 系列全体に当てはめたARMA($ p $, $ q $)モデル
+/// $$ x_t - \mu = \sum_{i=1}^{p} \phi_i (x_{t-i} - \mu) + \sum_{j=1}^{q} \theta_j e_{t-j} + e_t $$
+/// から求めた革新系列$ e_t $を保持する．
+/// 革新系列は$ t = p+1, \dots, T $について計算するため，元の系列の最初の$ p $個の観測に対応する値はない．
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prewhitened {
+    /// 平均$ \mu $
+    pub mean: f64,
+    /// ARの係数$ \phi_1, \dots, \phi_p $
+    pub ar: Vec<f64>,
+    /// MAの係数$ \theta_1, \dots, \theta_q $
+    pub ma: Vec<f64>,
+    /// 革新系列$ e_{p+1}, \dots, e_T $
+    pub innovations: Vec<f64>,
+    /// 元の系列の長さ$ T $
+    pub t_max: Tau,
+}
+
+impl Prewhitened {
+    /// 革新系列における時点を元の系列の時点へ変換する
+    ///
+    /// # 引数
+    /// * `t` - 革新系列における時点
+    pub fn to_original(&self, t: Tau) -> Tau {
+        t + self.ar.len() as Tau
+    }
+
+
+    /// 革新系列から変化点を検出し，元の系列の時点で返す
+    ///
+    /// 平均の変化$ \delta $は，革新系列では変化直後の$ \delta $の跳びと，その後の$ (1 - \sum \phi_i) \delta $の水準の変化として現れる．
+    ///
+    /// # 引数
+    /// * `build` - 革新系列からコスト関数を作成する関数
+    /// * `method` - 変化点検出の手法
+    /// * `penalty` - 変化点個数の決め方
+    /// * `constraints` - 変化点検出における制約
+    pub fn detect<C, F>(&self, build: F, method: Method, penalty: &Penalty, constraints: &Constraints) -> Result<DetectionResult, CalcDpError>
+    where
+        C: SegmentCost,
+        F: FnOnce(&[f64]) -> Result<C, CalcDpError>,
+    {
+        let cost = build(&self.innovations)?;
+        let result = detect::detect(&cost, method, penalty, constraints)?;
+        Ok(DetectionResult {
+            change_points: result.change_points.iter().map(|t| self.to_original(*t)).collect(),
+            value: result.value,
+            start: 0,
+            t_max: self.t_max,
+            selection: result.selection,
+        })
+    }
+}
+
+
+/// ARMAモデルを系列全体に当てはめ，革新系列を求める
+///
+/// 係数はHannan–Rissanen法により推定する．
+/// まず高次のARモデルを最小二乗法で当てはめて革新系列を近似し，
+/// 次に過去の観測と近似した革新系列を説明変数とする回帰によりARおよびMAの係数を求める．
+/// 平均の変化を含む系列では自己相関が過大に推定されるため，変化の少ない区間で次数を選ぶことが望ましい．
+///
+/// # 引数
+/// * `data` - 系列
+/// * `order` - ARMAモデルの次数
+pub fn prewhiten(data: &[f64], order: ArmaOrder) -> Result<Prewhitened, CalcDpError> {
+    let n = data.len();
+    let ArmaOrder { ar: p, ma: q } = order;
+    // 高次ARの次数
+    let m = if q > 0 { (p + q).max((10.0 * (n as f64).log10()).ceil() as usize) } else { 0 };
+    let burn_in = p.max(m + q);
+    if n < burn_in + 2 * (p + q) + 2 {
+        return Err(CalcDpError::new(format!("Series of length {n} is too short to fit ARMA({p}, {q}).")));
+    }
+
+    let mean = data.iter().sum::<f64>() / n as f64;
+    let x = data.iter().map(|v| v - mean).collect::<Vec<f64>>();
+
+    // 高次ARによる革新系列の近似
+    let e_long = if q > 0 {
+        let phi = fit_regression(&x, m, m, |t, i| x[t - 1 - i])?;
+        let mut e = vec![0.0; n];
+        for t in m..n {
+            e[t] = x[t] - (0..m).map(|i| phi[i] * x[t - 1 - i]).sum::<f64>();
+        }
+        e
+    } else {
+        vec![0.0; n]
+    };
+
+    // ARおよびMAの係数
+    let coef = if p + q > 0 {
+        fit_regression(&x, burn_in, p + q, |t, i| if i < p { x[t - 1 - i] } else { e_long[t - 1 - (i - p)] })?
+    } else {
+        Vec::new()
+    };
+    let (ar, ma) = coef.split_at(p);
+
+    // 革新系列（MAの初期値は0とする）
+    let mut e = vec![0.0; n];
+    for t in p..n {
+        let ar_part = (0..p).map(|i| ar[i] * x[t - 1 - i]).sum::<f64>();
+        let ma_part = (0..q).filter(|j| t > p + *j)
+                            .map(|j| ma[j] * e[t - 1 - j])
+                            .sum::<f64>();
+        e[t] = x[t] - ar_part - ma_part;
+    }
+
+    Ok(Prewhitened {
+        mean,
+        ar: ar.to_vec(),
+        ma: ma.to_vec(),
+        innovations: e.split_off(p),
+        t_max: n as Tau,
+    })
+}
+
+
+/// 時点`start`以降の`x[t]`を目的変数とする回帰
+///
+/// `regressor(t, i)`は時点`t`における`i`番目の説明変数を返す．
+fn fit_regression<F: Fn(usize, usize) -> f64>(x: &[f64], start: usize, dim: usize, regressor: F) -> Result<Vec<f64>, CalcDpError> {
+    let design = (start..x.len()).flat_map(|t| (0..dim).map(move |i| (t, i)))
+                                 .map(|(t, i)| regressor(t, i))
+                                 .collect::<Vec<f64>>();
+    linalg::least_squares(&design, &x[start..], dim).ok_or_else(|| CalcDpError::new("Failed to estimate ARMA coefficients (singular normal equations)."))
+}
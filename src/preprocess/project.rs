@@ -0,0 +1,173 @@
+//! 高次元の系列の次元削減
+
+use crate::cost::SegmentCost;
+use crate::detect::{self, Method, Penalty, Constraints, DetectionResult};
+use crate::dp_tools::CalcDpError;
+use crate::index;
+use crate::linalg;
+use crate::rng::RngConfig;
+
+extern crate ndarray;
+use ndarray::{Array2, ArrayView1, Axis, s};
+
+extern crate rand_distr;
+use rand_distr::{Distribution, StandardNormal};
+
+
+/// 次元削減の方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    /// 主成分分析．共分散行列の固有値の大きい順に`k`個の主成分へ射影する．
+    Pca { k: usize },
+    /// ランダム射影．各成分が独立に$ N(0, 1/k) $に従う行列により`k`次元へ射影する．
+    ///
+    /// 次元が高く主成分分析の計算が重い場合に用いる．同じ`seed`からは同じ射影が得られる．
+    RandomProjection { k: usize, seed: u64 },
+}
+
+impl Projection {
+    /// 射影後の次元
+    pub fn k(&self) -> usize {
+        match self {
+            Projection::Pca { k } | Projection::RandomProjection { k, .. } => *k,
+        }
+    }
+}
+
+
+/// 次元削減の結果
+///
+/// 中心化した観測$ x_t - c $を負荷行列$ W $（$ d \times k $）により$ s_t = W^\top (x_t - c) $へ射影する．
+/// 射影後の変化$ \Delta s $は$ W \Delta s $により元の各次元（チャネル）の変化として解釈できる．
+/// 主成分分析では$ W $の列は正規直交であり，ランダム射影では$ E[W W^\top] = I $である．
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projected {
+    /// 射影後の系列（行が時期，列が成分）
+    pub scores: Array2<f64>,
+    /// 負荷行列$ W $（行が元の次元，列が成分）
+    pub loadings: Array2<f64>,
+    /// 中心化に用いた各次元の平均$ c $
+    pub center: Vec<f64>,
+    /// 各成分の分散．主成分分析では共分散行列の固有値に等しい．
+    pub variances: Vec<f64>,
+}
+
+impl Projected {
+    /// 射影後の系列から変化点を検出する
+    ///
+    /// # 引数
+    /// * `build` - 射影後の系列からコスト関数を作成する関数
+    /// * `method` - 変化点検出の手法
+    /// * `penalty` - 変化点個数の決め方
+    /// * `constraints` - 変化点検出における制約
+    pub fn detect<C, F>(&self, build: F, method: Method, penalty: &Penalty, constraints: &Constraints) -> Result<ProjectedDetection, CalcDpError>
+    where
+        C: SegmentCost,
+        F: FnOnce(&Array2<f64>) -> Result<C, CalcDpError>,
+    {
+        let cost = build(&self.scores)?;
+        let result = detect::detect(&cost, method, penalty, constraints)?;
+        let segment_means = result.segments()
+                                  .iter()
+                                  .map(|(t_k_1, t_k)| {
+                                      let rows = self.scores.slice(s![index::to_usize(*t_k_1)?..index::to_usize(*t_k)?, ..]);
+                                      Ok(rows.mean_axis(Axis(0)).map(|m| m.to_vec()).unwrap_or_default())
+                                  })
+                                  .collect::<Result<Vec<Vec<f64>>, CalcDpError>>()?;
+        Ok(ProjectedDetection {
+            result,
+            loadings: self.loadings.clone(),
+            segment_means,
+        })
+    }
+
+
+    /// 射影後の成分の変化を元の各次元の変化へ戻す
+    ///
+    /// # 引数
+    /// * `component` - 射影後の各成分の変化$ \Delta s $
+    pub fn to_channels(&self, component: &[f64]) -> Result<Vec<f64>, CalcDpError> {
+        back_project(&self.loadings, component)
+    }
+}
+
+
+/// 次元削減した系列からの変化点検出の結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectedDetection {
+    /// 変化点検出の結果
+    pub result: DetectionResult,
+    /// 射影に用いた負荷行列（[`Projected::loadings`]）
+    pub loadings: Array2<f64>,
+    /// 各区間における射影後の成分の平均
+    pub segment_means: Vec<Vec<f64>>,
+}
+
+impl ProjectedDetection {
+    /// 各変化点における元の各次元の平均の変化
+    ///
+    /// # 返り値
+    /// * `shifts` - 変化点ごとの，元の各次元の変化（後の区間の平均 - 前の区間の平均）
+    pub fn channel_shifts(&self) -> Result<Vec<Vec<f64>>, CalcDpError> {
+        self.segment_means.windows(2)
+                          .map(|pair| {
+                              let delta = pair[1].iter().zip(&pair[0]).map(|(a, b)| a - b).collect::<Vec<f64>>();
+                              back_project(&self.loadings, &delta)
+                          })
+                          .collect()
+    }
+}
+
+
+/// 系列の次元を削減する
+///
+/// # 引数
+/// * `data` - 系列．行が時期，列が次元に対応する．
+/// * `projection` - 次元削減の方法
+pub fn project(data: &Array2<f64>, projection: Projection) -> Result<Projected, CalcDpError> {
+    let (t_max, dim) = data.dim();
+    let k = projection.k();
+    if k == 0 || k > dim {
+        return Err(CalcDpError::new(format!("Projected dimension must be between 1 and {dim}, but {k} is given.")));
+    }
+    if t_max < 2 {
+        return Err(CalcDpError::new(format!("Series of length {t_max} is too short to project.")));
+    }
+    if let Some(v) = data.iter().find(|v| !v.is_finite()) {
+        return Err(CalcDpError::new(format!("Data must be finite, but {v} is given.")));
+    }
+
+    let center = data.mean_axis(Axis(0)).map(|m| m.to_vec()).unwrap_or_default();
+    let centered = data - &ArrayView1::from(&center);
+
+    let loadings = match projection {
+        Projection::Pca { k } => {
+            let cov = centered.t().dot(&centered) / t_max as f64;
+            let (_, vectors) = linalg::symmetric_eigen(&cov.iter().copied().collect::<Vec<f64>>(), dim);
+            Array2::from_shape_fn((dim, k), |(i, j)| vectors[i * dim + j])
+        },
+        Projection::RandomProjection { k, seed } => {
+            let mut rng = RngConfig::new(seed).rng();
+            let scale = 1.0 / (k as f64).sqrt();
+            Array2::from_shape_simple_fn((dim, k), || {
+                let z: f64 = StandardNormal.sample(&mut rng);
+                scale * z
+            })
+        },
+    };
+
+    let scores = centered.dot(&loadings);
+    let variances = scores.axis_iter(Axis(1))
+                          .map(|col| col.iter().map(|v| v * v).sum::<f64>() / t_max as f64)
+                          .collect();
+    Ok(Projected { scores, loadings, center, variances })
+}
+
+
+/// 負荷行列により成分の変化を元の次元へ戻す
+fn back_project(loadings: &Array2<f64>, component: &[f64]) -> Result<Vec<f64>, CalcDpError> {
+    if component.len() != loadings.ncols() {
+        return Err(CalcDpError::new(format!("Length of component ({}) differs from the projected dimension ({}).", component.len(), loadings.ncols())));
+    }
+    Ok(loadings.dot(&ArrayView1::from(component)).to_vec())
+}
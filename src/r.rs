@@ -0,0 +1,97 @@
+//! extendrによるRからの呼び出し
+//!
+//! `r` featureを有効にした場合のみ利用できる．
+//! Rパッケージの`src/rust`に置いたcrateから本crateを依存先として指定し，静的libraryとしてbuildする．
+//!
+//! # Rからの利用例
+//! ```r
+//! x <- c(rnorm(100, 0), rnorm(100, 2))
+//! cpd_detect(cbind(x, 1), "hetero_mean", "dp", 10)  # ペナルティ10で選択
+//! cpd_detect(cbind(x, 1), "hetero_mean", "dp", 1L)  # 変化点個数を1に指定
+//! ```
+
+use crate::cost::{CostRegistry, CostInput};
+use crate::detect::{self, Method, Penalty, Constraints};
+use crate::dp_tools::CalcDpError;
+
+use std::collections::BTreeMap;
+
+extern crate process_param;
+use process_param::NumChg;
+
+extern crate extendr_api;
+use extendr_api::prelude::*;
+
+
+/// 変化点を検出する
+///
+/// # 引数
+/// * `x` - 系列．数値ベクトルまたは列ごとにコスト関数へ渡す値を並べた行列．
+/// * `cost` - [`CostRegistry::with_builtins`]に登録されたコスト関数の名前
+/// * `method` - 変化点検出の手法（`"dp"`，`"dp2"`または`"auto"`）
+/// * `penalty` - 整数（例: `1L`）の場合は変化点個数，実数の場合は変化点1個あたりのペナルティ
+///
+/// # 返り値
+/// * `change_points` - 変化点（昇順）．各区間の最後の観測の位置であり，Rの1始まりの添字と一致する．
+#[extendr]
+fn cpd_detect(x: Robj, cost: &str, method: &str, penalty: Robj) -> extendr_api::Result<Vec<i32>> {
+    let columns = columns_of(&x)?;
+    let params = BTreeMap::new();
+    let input = CostInput {
+        columns: &columns,
+        params: &params,
+    };
+    let cost = CostRegistry::with_builtins().build(cost, &input).map_err(to_r_error)?;
+    let method = method.parse::<Method>().map_err(to_r_error)?;
+    let penalty = penalty_of(&penalty)?;
+
+    let result = detect::detect(&cost, method, &penalty, &Constraints::default()).map_err(to_r_error)?;
+    Ok(result.change_points.iter().map(|t| *t as i32).collect())
+}
+
+
+/// Rのオブジェクトを列ごとの値へ変換
+fn columns_of(x: &Robj) -> extendr_api::Result<Vec<Vec<f64>>> {
+    if x.is_matrix() {
+        let mat = RMatrix::<f64>::try_from(x.clone())?;
+        let nrows = mat.nrows();
+        if nrows == 0 {
+            return Ok(vec![Vec::new(); mat.ncols()]);
+        }
+        let data = x.as_real_slice().ok_or_else(|| Error::Other("x must be a numeric matrix.".to_owned()))?;
+        // Rの行列は列優先で格納される
+        Ok(data.chunks(nrows).map(|col| col.to_vec()).collect())
+    } else if let Some(v) = x.as_real_vector() {
+        Ok(vec![v])
+    } else if let Some(v) = x.as_integer_vector() {
+        Ok(vec![v.iter().map(|i| *i as f64).collect()])
+    } else {
+        Err(Error::Other("x must be a numeric vector or matrix.".to_owned()))
+    }
+}
+
+
+/// Rのオブジェクトを変化点個数の決め方へ変換
+fn penalty_of(penalty: &Robj) -> extendr_api::Result<Penalty> {
+    if let Some(k) = penalty.as_integer() {
+        if k < 0 {
+            return Err(Error::Other(format!("The number of change points must be non-negative, but {k} is given.")));
+        }
+        Ok(Penalty::NumChange(k as NumChg))
+    } else if let Some(beta) = penalty.as_real() {
+        Ok(Penalty::Linear(beta))
+    } else {
+        Err(Error::Other("penalty must be a single integer or number.".to_owned()))
+    }
+}
+
+
+fn to_r_error(e: CalcDpError) -> Error {
+    Error::Other(e.to_string())
+}
+
+
+extendr_module! {
+    mod cpd_tools;
+    fn cpd_detect;
+}
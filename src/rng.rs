@@ -0,0 +1,88 @@
+//! 乱数の生成に関する補助関数
+
+extern crate rand;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 確率的な処理に与える乱数の設定
+///
+/// Wild binary segmentation，ブートストラップ，並べ替え検定およびシミュレーション等の確率的な処理は，
+/// スレッドごとの乱数生成器を用いず，本設定のseedのみから乱数を生成する．
+/// このため同じ設定からは，計算機やスレッド数によらず同じ結果が得られる．
+///
+/// `u64`のseedからは[`From`]により変換できる．利用者の乱数生成器から設定を作る場合は[`RngConfig::from_rng`]を用いる．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct RngConfig {
+    /// 乱数のseed
+    pub seed: u64,
+}
+
+impl RngConfig {
+    /// seedを指定して設定を作成する
+    ///
+    /// # 引数
+    /// * `seed` - 乱数のseed
+    pub fn new(seed: u64) -> Self {
+        RngConfig { seed }
+    }
+
+
+    /// 乱数生成器から引いた値をseedとする設定を作成する
+    ///
+    /// # 引数
+    /// * `rng` - 乱数生成器
+    pub fn from_rng<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        RngConfig { seed: rng.gen() }
+    }
+
+
+    /// seedから作成した乱数生成器
+    ///
+    /// 1個の乱数列を順に用いる処理に用いる．
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed)
+    }
+
+
+    /// 試行ごとの乱数生成器
+    ///
+    /// 並列に実行する試行それぞれに独立な乱数列を与える．乱数列は試行番号のみから決まり，実行の順番によらない．
+    ///
+    /// # 引数
+    /// * `i` - 試行番号
+    pub fn trial(&self, i: u64) -> StdRng {
+        self.fork(i).rng()
+    }
+
+
+    /// 処理ごとに独立な乱数の設定
+    ///
+    /// 複数の系列等に対して確率的な処理を繰り返す場合に，それぞれへ異なる設定を与える．
+    ///
+    /// # 引数
+    /// * `i` - 処理の番号
+    pub fn fork(&self, i: u64) -> RngConfig {
+        RngConfig::new(sim_seed(self.seed, i))
+    }
+}
+
+impl From<u64> for RngConfig {
+    fn from(seed: u64) -> Self {
+        RngConfig::new(seed)
+    }
+}
+
+
+/// 試行ごとの乱数のseed
+///
+/// 隣接するseedから相関の強い乱数列が生成されることを避けるため，SplitMix64により攪拌する．
+pub(crate) fn sim_seed(seed: u64, i: u64) -> u64 {
+    let mut z = seed.wrapping_add(i.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
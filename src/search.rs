@@ -0,0 +1,18 @@
+//! 動的計画法以外の変化点探索手法
+//!
+//! 動的計画法は大域的な最適解を与えるが，系列長$ T $に対して$ O(T^2) $の評価値計算を要する．
+//! 本moduleの手法は近似解と引き換えに，または枝刈り（[`pelt`]）により計算量を抑える．
+//! 結果は[`crate::detect::DetectionResult`]として返すため，動的計画法の結果と同様に扱える．
+
+mod binseg;
+pub use binseg::binseg;
+pub(crate) use binseg::best_split;
+mod wbs;
+pub use wbs::wbs;
+mod smuce;
+pub use smuce::{smuce, smuce_quantile, SmuceResult};
+mod pelt;
+pub use pelt::{pelt, PeltResult, PruneStats};
+pub(crate) use pelt::pelt_until;
+mod sparse;
+pub use sparse::{sparse_binseg, sparse_threshold, SparseResult};
@@ -0,0 +1,142 @@
+//! 二分割法(Binary segmentation)による変化点探索
+
+use crate::cost::SegmentCost;
+use crate::detect::{Penalty, Constraints, DetectionResult};
+use crate::dp_tools::{AddContext, CalcDpError, ErrorContext};
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+
+/// 二分割法により変化点を探索する
+///
+/// 現在の各区間について，2区間へ分割した場合の評価値の増加量が最大となる分割点を求め，
+/// 増加量が最も大きい区間を分割する操作を繰り返す．
+/// 分割の停止条件は`penalty`に従う．
+/// * [`Penalty::NumChange`] - 指定した個数の変化点を得るまで分割する
+/// * [`Penalty::Linear`] - 評価値の増加量がペナルティ$ \beta $以下となるまで分割する
+///
+/// 貪欲法であるため，得られる変化点は動的計画法による最適解と一致するとは限らない．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `min_size` - 各区間の最小の長さ（1以上）
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+pub fn binseg<C: SegmentCost>(cost: &C, min_size: Tau, penalty: &Penalty, constraints: &Constraints) -> Result<DetectionResult, CalcDpError> {
+    let t_max = cost.t_max();
+    if t_max == 0 {
+        return Err(CalcDpError::new("Series must contain at least one point."));
+    }
+    if min_size == 0 {
+        return Err(CalcDpError::new("Minimum segment length must be at least 1."));
+    }
+
+    let (k_target, beta) = match penalty {
+        Penalty::NumChange(k) => {
+            if let Some(max_k) = constraints.max_k {
+                if *k > max_k {
+                    return Err(CalcDpError::new(format!("The number of change point k (= {k}) must not exceed max_k (= {max_k}).")));
+                }
+            }
+            (Some(*k), None)
+        },
+        Penalty::Linear(beta) => (None, Some(*beta)),
+    };
+    let k_lim = match (k_target, constraints.max_k) {
+        (Some(k), _) => k,
+        (None, Some(max_k)) => max_k,
+        (None, None) => NumChg::MAX,
+    };
+
+    let mut segments = vec![Segment::new(cost, 0, t_max, min_size)?];
+    let mut change_points = Vec::new();
+    while (change_points.len() as NumChg) < k_lim {
+        // 増加量が最大となる区間を選択
+        let best = segments.iter()
+                           .enumerate()
+                           .filter_map(|(i, seg)| seg.split.map(|(s, gain)| (i, s, gain)))
+                           .fold(None, |acc: Option<(usize, Tau, f64)>, cand| match acc {
+                               Some(a) if a.2 >= cand.2 => Some(a),
+                               _ => Some(cand),
+                           });
+        let (idx, s, gain) = match best {
+            Some(b) => b,
+            None => break,
+        };
+        if let Some(beta) = beta {
+            if gain <= beta {
+                break;
+            }
+        }
+
+        let seg = segments.swap_remove(idx);
+        segments.push(Segment::new(cost, seg.start, s, min_size)?);
+        segments.push(Segment::new(cost, s, seg.end, min_size)?);
+        change_points.push(s);
+    }
+
+    if let Some(k) = k_target {
+        if (change_points.len() as NumChg) < k {
+            return Err(CalcDpError::new(format!("Only {} change points can be found (k = {k}).", change_points.len())));
+        }
+    }
+
+    change_points.sort_unstable();
+    let value = segments.iter().map(|seg| seg.value).sum();
+    Ok(DetectionResult::new(change_points, value, t_max))
+}
+
+
+/// 探索中の区間$ (start, end] $
+struct Segment {
+    start: Tau,
+    end: Tau,
+    /// 区間全体の評価値
+    value: f64,
+    /// 最良の分割点とその評価値の増加量．分割できない場合は`None`．
+    split: Option<(Tau, f64)>,
+}
+
+impl Segment {
+    /// 区間の評価値と最良の分割点を計算
+    fn new<C: SegmentCost>(cost: &C, start: Tau, end: Tau, min_size: Tau) -> Result<Self, CalcDpError> {
+        let value = cost.segment_value(start, end)
+                        .add_context(|| ErrorContext::new("binary segmentation").segment(start, end).cost::<C>())?;
+        let split = best_split(cost, start, end, min_size)?;
+        Ok(Segment { start, end, value, split })
+    }
+}
+
+
+/// 区間$ (start, end] $を2区間へ分割した場合に評価値の増加量が最大となる分割点
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `start` - 区間の始点
+/// * `end` - 区間の終点
+/// * `min_size` - 各区間の最小の長さ
+///
+/// # 返り値
+/// * `split` - 分割点と評価値の増加量．分割できない場合は`None`．
+pub(crate) fn best_split<C: SegmentCost>(cost: &C, start: Tau, end: Tau, min_size: Tau) -> Result<Option<(Tau, f64)>, CalcDpError> {
+    let context = |t_k_1, t_k| ErrorContext::new("searching split point").segment(t_k_1, t_k).cost::<C>();
+    let value = cost.segment_value(start, end).add_context(|| context(start, end))?;
+    let mut split: Option<(Tau, f64)> = None;
+    if end - start >= 2 * min_size {
+        for s in (start + min_size)..=(end - min_size) {
+            let left = cost.segment_value(start, s).add_context(|| context(start, s))?;
+            let right = cost.segment_value(s, end).add_context(|| context(s, end))?;
+            // 評価値が定義できない区間を生じる分割は選ばない
+            if left == f64::NEG_INFINITY || right == f64::NEG_INFINITY {
+                continue;
+            }
+            let gain = left + right - value;
+            match split {
+                Some((_, g)) if g >= gain => (),
+                _ => split = Some((s, gain)),
+            }
+        }
+    }
+    Ok(split)
+}
@@ -0,0 +1,97 @@
+//! PELT(Pruned exact linear time)による変化点探索
+
+use crate::cost::SegmentCost;
+use crate::detect::DetectionResult;
+use crate::dp_tools::{dp_core, AddContext, CalcDpError, ErrorContext};
+
+use std::time::Instant;
+
+extern crate process_param;
+use process_param::Tau;
+
+
+/// PELTにおける候補の枝刈りの集計
+///
+/// 枝刈りの効果は系列によって大きく異なり，変化点が多いほど候補が減るため計算が速くなる．
+/// 実行時間が系列によって大きく変わる理由の確認に用いる．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PruneStats {
+    /// 評価値を計算した（直前の変化点の候補, 時点）の組の数
+    pub evaluations: u64,
+    /// 枝刈りにより除いた候補の数
+    pub pruned: u64,
+    /// 候補の数の最大値
+    pub max_candidates: usize,
+    /// 最後の時点における候補の数
+    pub final_candidates: usize,
+}
+
+
+/// PELTによる探索結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeltResult {
+    /// 検出結果
+    pub result: DetectionResult,
+    /// 枝刈りの集計
+    pub stats: PruneStats,
+}
+
+
+/// PELTによりペナルティ付きの最適な変化点を探索する
+///
+/// $ F(t) = \max_{s} \{ F(s) + f(s, t) \} - \beta $を$ t = 1, \dots, T $の順に計算し，
+/// $ \sum_k f(t_{k-1}, t_k) - \beta K $を最大とする変化点を求める．
+/// 時点$ t $において$ F(s) + f(s, t) < F(t) $となった候補$ s $は，以降の時点でも最適とならないため候補から除く．
+/// この枝刈りは区間を分割した際の評価値の変化の下限$ K $（[`SegmentCost::pruning_constant`]）を用い，
+/// $ F(s) + f(s, t) < F(t) + K $となった候補を除く．評価値が`f64::NEG_INFINITY`となった候補は除かない．
+/// 結果は[`crate::detect::Penalty::Linear`]による動的計画法と一致する．
+/// 計算は[`crate::dp_tools::calc_pelt::CalcPelt`]と共通である．
+///
+/// feature `trace`を有効にした場合，枝刈りした候補と用いた不等式を`log`のtraceレベルで出力する．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `min_size` - 各区間の最小の長さ（1以上）
+/// * `beta` - 変化点1個あたりのペナルティ$ \beta $
+pub fn pelt<C: SegmentCost>(cost: &C, min_size: Tau, beta: f64) -> Result<PeltResult, CalcDpError> {
+    Ok(pelt_until(cost, min_size, beta, None)?.expect("No deadline is given"))
+}
+
+
+/// 期限を指定してPELTにより探索する
+///
+/// 各時点の計算の前に期限を確認し，期限を過ぎた場合は`None`を返す．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `min_size` - 各区間の最小の長さ（1以上）
+/// * `beta` - 変化点1個あたりのペナルティ$ \beta $
+/// * `deadline` - 期限．`None`の場合は期限を設けない．
+pub(crate) fn pelt_until<C: SegmentCost>(cost: &C, min_size: Tau, beta: f64, deadline: Option<Instant>) -> Result<Option<PeltResult>, CalcDpError> {
+    let t_max = cost.t_max();
+    let pruning = dp_core::Pruning::new(cost.pruning_constant());
+    let search = dp_core::PenalizedSearch { min_size, pruning: Some(&pruning), deadline };
+    let batch = |pairs: &[(Tau, Tau)]| {
+        pairs.iter()
+             .map(|&(s, t)| cost.segment_value(s, t).add_context(|| ErrorContext::new("PELT").time(t).segment(s, t).cost::<C>()))
+             .collect()
+    };
+    let Some(found) = dp_core::penalized_search(t_max, &-beta, &search, "PELT", batch)? else {
+        return Ok(None);
+    };
+
+    // 最小の長さを満たす候補が無い時点は評価値が定義できない
+    let memo = found.memo
+                    .into_iter()
+                    .map(|entry| entry.unwrap_or((0, 0, f64::NEG_INFINITY)))
+                    .collect::<Vec<_>>();
+    let (change_points, f_max) = dp_core::penalized_change_points(t_max, &memo)?;
+    if f_max == f64::NEG_INFINITY {
+        return Err(CalcDpError::new("No segmentation with finite value exists."));
+    }
+    let value = f_max + beta * change_points.len() as f64;
+    Ok(Some(PeltResult {
+        result: DetectionResult::new(change_points, value, t_max),
+        stats: found.stats,
+    }))
+}
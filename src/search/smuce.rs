@@ -0,0 +1,272 @@
+//! 多重尺度検定に基づく変化点探索(SMUCE: Simultaneous multiscale change point estimator)
+
+use crate::detect::DetectionResult;
+use crate::dp_tools::CalcDpError;
+use crate::rng::RngConfig;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate rand_distr;
+use rand_distr::{Distribution, StandardNormal};
+
+extern crate rayon;
+use rayon::prelude::*;
+
+
+/// SMUCEによる検出結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmuceResult {
+    /// 検出結果．評価値は推定した区間ごとの値に対する正規分布の対数尤度（定数項は除く）．
+    pub result: DetectionResult,
+    /// 区間ごとの推定値
+    pub means: Vec<f64>,
+    /// 各変化点の信頼区間（変化点の候補の最小値と最大値）
+    pub change_point_intervals: Vec<(Tau, Tau)>,
+    /// 観測ごとの信頼帯の下限と上限（推定した変化点を所与とした値）
+    pub band: Vec<(f64, f64)>,
+    /// 多重尺度統計量の棄却限界$ q_{1-\alpha} $
+    pub quantile: f64,
+    /// 用いた標準偏差$ \sigma $
+    pub sigma: f64,
+}
+
+
+/// SMUCEにより正規分布の平均の変化点を検出する
+///
+/// 区間$ [i, j] $で一定の値$ \theta $をとる階段関数に対し，多重尺度統計量
+/// $$ T = \max_{[i, j]} \left\{ \frac{|\sum_{t=i}^{j} (y_t - \theta)|}{\sigma \sqrt{j - i + 1}} - \sqrt{2 \log \frac{e n}{j - i + 1}} \right\} $$
+/// が棄却限界$ q_{1-\alpha} $以下となるもののうち，変化点個数が最小のものを求める．
+/// 変化点個数が最小のものが複数ある場合は尤度が最大のものを選ぶ．
+/// これにより，変化点個数を過大に推定する確率は$ \alpha $以下となる．
+///
+/// 計算量は最悪で$ O(n^3) $であり，数千点程度までの系列を想定する．
+///
+/// # 引数
+/// * `data` - 系列
+/// * `alpha` - 変化点個数を過大に推定する確率の上限（0より大きく1未満）
+/// * `sigma` - 標準偏差．`None`の場合は階差のMADから推定する．
+/// * `n_sims` - 棄却限界を求めるための試行回数
+/// * `rng` - 乱数の設定（`u64`のseedも与えられる）
+pub fn smuce(data: &[f64], alpha: f64, sigma: Option<f64>, n_sims: usize, rng: impl Into<RngConfig>) -> Result<SmuceResult, CalcDpError> {
+    let n = data.len();
+    if n < 2 {
+        return Err(CalcDpError::new(format!("Series must contain at least 2 points, but {n} is given.")));
+    }
+    let sigma = match sigma {
+        Some(s) => s,
+        None => estimate_sigma(data),
+    };
+    if !(sigma.is_finite() && sigma > 0.0) {
+        return Err(CalcDpError::new(format!("Standard deviation must be positive and finite, but {sigma} is given.")));
+    }
+    let quantile = smuce_quantile(n, alpha, n_sims, rng)?;
+
+    let mut cumsum = vec![0.0; n + 1];
+    let mut cumsq = vec![0.0; n + 1];
+    for (t, y) in data.iter().enumerate() {
+        cumsum[t + 1] = cumsum[t] + y;
+        cumsq[t + 1] = cumsq[t] + y * y;
+    }
+    let bounds = Bounds { cumsum: &cumsum, cumsq: &cumsq, n, sigma, quantile };
+
+    // best[b]: (0, b]を被覆する最小の区間数，その2乗誤差の和，直前の変化点
+    let mut best: Vec<Option<(usize, f64, usize)>> = vec![None; n + 1];
+    best[0] = Some((0, 0.0, 0));
+    // a_min[b]: 区間(a, b]が制約を満たす最小のa
+    let mut a_min = vec![0; n + 1];
+    for b in 1..=n {
+        let mut lower = f64::NEG_INFINITY;
+        let mut upper = f64::INFINITY;
+        let mut a = b;
+        while a > 0 {
+            let (l, u) = bounds.extend_left(a - 1, b);
+            lower = lower.max(l);
+            upper = upper.min(u);
+            // 制約は区間を広げるほど増えるため，満たさなくなった時点で打ち切る
+            if lower > upper {
+                break;
+            }
+            a -= 1;
+            if let Some((k, sse, _)) = best[a] {
+                let cand = (k + 1, sse + bounds.sse(a, b, lower, upper));
+                match best[b] {
+                    Some((bk, bs, _)) if (bk, bs) <= cand => (),
+                    _ => best[b] = Some((cand.0, cand.1, a)),
+                }
+            }
+        }
+        a_min[b] = a;
+    }
+
+    // 変化点の復元
+    let mut change_points = Vec::new();
+    let mut b = n;
+    while b > 0 {
+        let (_, _, a) = best[b].expect("Every prefix can be covered by segments of length 1.");
+        if a > 0 {
+            change_points.push(a as Tau);
+        }
+        b = a;
+    }
+    change_points.reverse();
+
+    let result_k = change_points.len();
+    let ends = change_points.iter().map(|t| *t as usize).chain(std::iter::once(n)).collect::<Vec<usize>>();
+    let starts = std::iter::once(0).chain(change_points.iter().map(|t| *t as usize)).collect::<Vec<usize>>();
+    let mut means = Vec::with_capacity(result_k + 1);
+    let mut band = Vec::with_capacity(n);
+    let mut sse = 0.0;
+    for (a, b) in starts.iter().zip(ends.iter()) {
+        let (lower, upper) = bounds.segment(*a, *b);
+        let theta = bounds.mean(*a, *b).clamp(lower, upper);
+        sse += bounds.sse(*a, *b, lower, upper);
+        means.push(theta);
+        band.extend(std::iter::repeat_n((lower, upper), b - a));
+    }
+
+    let change_point_intervals = change_point_intervals(&best, &a_min, n, result_k);
+
+    Ok(SmuceResult {
+        result: DetectionResult::new(change_points, - 0.5 * sse / (sigma * sigma), n as Tau),
+        means,
+        change_point_intervals,
+        band,
+        quantile,
+        sigma,
+    })
+}
+
+
+/// 多重尺度統計量の帰無分布における$ 1 - \alpha $分位点
+///
+/// 標準正規分布に従う長さ`n`の系列を`n_sims`回生成して求める．
+/// 同じ系列長で繰り返し検出する場合は，本関数の結果を再利用できる．
+///
+/// # 引数
+/// * `n` - 系列の長さ
+/// * `alpha` - 有意水準（0より大きく1未満）
+/// * `n_sims` - 試行回数
+/// * `rng` - 乱数の設定（`u64`のseedも与えられる）
+pub fn smuce_quantile(n: usize, alpha: f64, n_sims: usize, rng: impl Into<RngConfig>) -> Result<f64, CalcDpError> {
+    let rng = rng.into();
+    if !(alpha > 0.0 && alpha < 1.0) {
+        return Err(CalcDpError::new(format!("alpha must be in (0, 1), but {alpha} is given.")));
+    }
+    if (n_sims as f64) * alpha < 1.0 {
+        return Err(CalcDpError::new(format!("n_sims (= {n_sims}) is too small for alpha = {alpha}; at least {} are required.", (1.0 / alpha).ceil())));
+    }
+    let stats = (0..n_sims).into_par_iter()
+                           .map(|i| {
+                               let mut rng = rng.trial(i as u64);
+                               let mut cumsum = vec![0.0; n + 1];
+                               for t in 0..n {
+                                   let z: f64 = StandardNormal.sample(&mut rng);
+                                   cumsum[t + 1] = cumsum[t] + z;
+                               }
+                               let mut stat = f64::NEG_INFINITY;
+                               for i in 0..n {
+                                   for j in (i + 1)..=n {
+                                       let m = (j - i) as f64;
+                                       let s = (cumsum[j] - cumsum[i]).abs() / m.sqrt() - scale_penalty(n, m);
+                                       stat = stat.max(s);
+                                   }
+                               }
+                               stat
+                           })
+                           .collect::<Vec<f64>>();
+    Ok(crate::calibrate::upper_quantile(stats, alpha))
+}
+
+
+/// 尺度に応じた補正項$ \sqrt{2 \log (e n / m)} $
+fn scale_penalty(n: usize, m: f64) -> f64 {
+    (2.0 * (1.0 + (n as f64 / m).ln())).sqrt()
+}
+
+
+/// 階差の中央絶対偏差(MAD)による標準偏差の推定値
+///
+/// 平均の変化の影響を受けにくい．
+fn estimate_sigma(data: &[f64]) -> f64 {
+    let mut diffs = data.windows(2).map(|w| (w[1] - w[0]).abs()).collect::<Vec<f64>>();
+    diffs.sort_by(|a, b| a.total_cmp(b));
+    let m = diffs.len();
+    let median = if m % 2 == 1 { diffs[m / 2] } else { 0.5 * (diffs[m / 2 - 1] + diffs[m / 2]) };
+    // 正規分布におけるMADと標準偏差の比，および階差による分散の倍化を補正
+    median / (0.674_489_750_196_081_7 * std::f64::consts::SQRT_2)
+}
+
+
+/// 各変化点の候補の範囲
+///
+/// 時点$ \tau $が$ k $番目の変化点となり得るのは，$ (0, \tau] $を$ k $個以下，
+/// $ (\tau, n] $を$ K + 1 - k $個以下の制約を満たす区間で被覆できる場合である．
+fn change_point_intervals(best: &[Option<(usize, f64, usize)>], a_min: &[usize], n: usize, k_hat: usize) -> Vec<(Tau, Tau)> {
+    let forward = best.iter().map(|b| b.map_or(usize::MAX, |(k, _, _)| k)).collect::<Vec<usize>>();
+    let mut backward = vec![usize::MAX; n + 1];
+    backward[n] = 0;
+    for a in (0..n).rev() {
+        backward[a] = ((a + 1)..=n).filter(|b| a_min[*b] <= a && backward[*b] < usize::MAX)
+                                   .map(|b| backward[b] + 1)
+                                   .min()
+                                   .unwrap_or(usize::MAX);
+    }
+    (1..=k_hat).map(|k| {
+                   let cands = (1..n).filter(|t| forward[*t] <= k && backward[*t] <= k_hat + 1 - k);
+                   let (lo, hi) = cands.fold((n, 0), |(lo, hi), t| (lo.min(t), hi.max(t)));
+                   (lo as Tau, hi as Tau)
+               })
+               .collect()
+}
+
+
+/// 区間に課される制約
+struct Bounds<'a> {
+    cumsum: &'a [f64],
+    cumsq: &'a [f64],
+    n: usize,
+    sigma: f64,
+    quantile: f64,
+}
+
+impl Bounds<'_> {
+    /// 区間(a, b]の標本平均
+    fn mean(&self, a: usize, b: usize) -> f64 {
+        (self.cumsum[b] - self.cumsum[a]) / (b - a) as f64
+    }
+
+
+    /// 区間(a, b]のうち左端がa+1である全ての部分区間から課される$ \theta $の範囲
+    fn extend_left(&self, a: usize, b: usize) -> (f64, f64) {
+        let mut lower = f64::NEG_INFINITY;
+        let mut upper = f64::INFINITY;
+        for j in (a + 1)..=b {
+            let m = (j - a) as f64;
+            let mean = self.mean(a, j);
+            let width = self.sigma * (self.quantile + scale_penalty(self.n, m)) / m.sqrt();
+            lower = lower.max(mean - width);
+            upper = upper.min(mean + width);
+        }
+        (lower, upper)
+    }
+
+
+    /// 区間(a, b]の全ての部分区間から課される$ \theta $の範囲
+    fn segment(&self, a: usize, b: usize) -> (f64, f64) {
+        (a..b).map(|i| self.extend_left(i, b))
+              .fold((f64::NEG_INFINITY, f64::INFINITY), |(l, u), (li, ui)| (l.max(li), u.min(ui)))
+    }
+
+
+    /// 区間(a, b]の値を制約の範囲内で推定した場合の2乗誤差の和
+    fn sse(&self, a: usize, b: usize, lower: f64, upper: f64) -> f64 {
+        let m = (b - a) as f64;
+        let sum = self.cumsum[b] - self.cumsum[a];
+        let mean = sum / m;
+        let theta = mean.clamp(lower, upper);
+        // 標本平均からの偏差平方和と，推定値のずれによる増分の和
+        let within = (self.cumsq[b] - self.cumsq[a] - sum * mean).max(0.0);
+        within + m * (mean - theta).powi(2)
+    }
+}
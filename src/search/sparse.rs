@@ -0,0 +1,245 @@
+//! 一部のチャネルのみが変化する高次元の系列に対する変化点探索
+
+use crate::detect::{Penalty, Constraints, DetectionResult};
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+extern crate ndarray;
+use ndarray::{Array2, ArrayView1, Axis};
+
+
+/// 疎な変化の探索結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseResult {
+    /// 変化点検出の結果．評価値は標準化した系列に対する，区間ごとに平均の異なる分散1の正規分布の対数尤度（定数項を除く）．
+    pub result: DetectionResult,
+    /// 変化点ごとの，変化したチャネルの番号（昇順）
+    pub affected: Vec<Vec<usize>>,
+    /// 各チャネルの標準化に用いた尺度
+    pub scales: Vec<f64>,
+    /// 用いた閾値
+    pub threshold: f64,
+}
+
+
+/// 閾値の既定値$ 2 \ln(d T) $
+///
+/// チャネルごとの増加量$ g_j $は，分割点を固定すれば，変化がない場合に自由度1の$ \chi^2 $分布の半分に従う．
+/// $ d T $個の増加量の最大値はおおよそ$ \ln(d T) $であるが，分割点の選択と二分割の繰り返しによる多重性を考慮して2倍とする．
+///
+/// # 引数
+/// * `t_max` - 系列の長さ$ T $
+/// * `dim` - チャネルの数$ d $
+pub fn sparse_threshold(t_max: Tau, dim: usize) -> f64 {
+    2.0 * ((t_max as f64) * (dim as f64)).max(1.0).ln()
+}
+
+
+/// 各変化点で一部のチャネルのみが変化する系列に対し，閾値付きの集約による二分割法で変化点を探索する
+///
+/// 各チャネルを頑健な尺度（1階差分の中央絶対偏差）で標準化した後，区間$ (a, b] $を$ s $で分割した場合の
+/// チャネル$ j $の対数尤度の増加量$ g_j(s) = \frac{n_l n_r}{2 n} (\bar{x}_{l,j} - \bar{x}_{r,j})^2 $を求める．
+/// 分割の評価は閾値$ \lambda $を超えた分の和$ \sum_j \max(g_j(s) - \lambda, 0) $とし，
+/// 変化していないチャネルの雑音が全チャネルの和に埋もれさせる影響を除く．
+/// 探索の終了後，前後の変化点に挟まれた区間において閾値を超えたチャネルを，その変化点で変化したチャネルとする．
+/// 分割の停止条件は[`super::binseg`]と同じく`penalty`に従い，[`Penalty::Linear`]では上記の評価と比較する．
+///
+/// # 引数
+/// * `data` - 系列データ．行が時期，列がチャネルに対応する．
+/// * `min_size` - 各区間の最小の長さ（1以上）
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+/// * `threshold` - チャネルごとの閾値$ \lambda $．`None`の場合は[`sparse_threshold`]．
+pub fn sparse_binseg(data: &Array2<f64>, min_size: Tau, penalty: &Penalty, constraints: &Constraints, threshold: Option<f64>) -> Result<SparseResult, CalcDpError> {
+    let (len, dim) = data.dim();
+    let t_max = Tau::try_from(len).map_err(|_| CalcDpError::new(format!("Series of length {len} is too long.")))?;
+    if t_max == 0 || dim == 0 {
+        return Err(CalcDpError::new("Series must contain at least one point and one channel."));
+    }
+    if min_size == 0 {
+        return Err(CalcDpError::new("Minimum segment length must be at least 1."));
+    }
+    if let Some(v) = data.iter().find(|v| !v.is_finite()) {
+        return Err(CalcDpError::new(format!("Data must be finite, but {v} is given.")));
+    }
+    let threshold = threshold.unwrap_or_else(|| sparse_threshold(t_max, dim));
+    if threshold.is_nan() || threshold < 0.0 {
+        return Err(CalcDpError::new(format!("Threshold must be non-negative, but {threshold} is given.")));
+    }
+
+    let (k_target, beta) = match penalty {
+        Penalty::NumChange(k) => {
+            if let Some(max_k) = constraints.max_k {
+                if *k > max_k {
+                    return Err(CalcDpError::new(format!("The number of change point k (= {k}) must not exceed max_k (= {max_k}).")));
+                }
+            }
+            (Some(*k), None)
+        },
+        Penalty::Linear(beta) => (None, Some(*beta)),
+    };
+    let k_lim = match (k_target, constraints.max_k) {
+        (Some(k), _) => k,
+        (None, Some(max_k)) => max_k,
+        (None, None) => NumChg::MAX,
+    };
+
+    let scales = data.axis_iter(Axis(1)).map(robust_scale).collect::<Vec<f64>>();
+    let stats = Cumulative::new(data, &scales);
+
+    let mut segments = vec![stats.segment(0, len, min_size as usize, threshold)];
+    let mut change_points: Vec<Tau> = Vec::new();
+    while (change_points.len() as NumChg) < k_lim {
+        let best = segments.iter()
+                           .enumerate()
+                           .filter_map(|(i, seg)| seg.split.as_ref().map(|split| (i, split)))
+                           .fold(None, |acc: Option<(usize, &Split)>, cand| match acc {
+                               Some(a) if a.1.score >= cand.1.score => Some(a),
+                               _ => Some(cand),
+                           });
+        let (idx, split) = match best {
+            Some((idx, split)) => (idx, *split),
+            None => break,
+        };
+        if let Some(beta) = beta {
+            if split.score <= beta {
+                break;
+            }
+        }
+
+        let seg = segments.swap_remove(idx);
+        segments.push(stats.segment(seg.start, split.at, min_size as usize, threshold));
+        segments.push(stats.segment(split.at, seg.end, min_size as usize, threshold));
+        change_points.push(split.at as Tau);
+    }
+
+    if let Some(k) = k_target {
+        if (change_points.len() as NumChg) < k {
+            return Err(CalcDpError::new(format!("Only {} change points can be found (k = {k}).", change_points.len())));
+        }
+    }
+
+    change_points.sort_unstable();
+    let value = segments.iter().map(|seg| stats.log_likelihood(seg.start, seg.end)).sum();
+    let bounds = std::iter::once(0).chain(change_points.iter().map(|t| *t as usize))
+                                   .chain(std::iter::once(len))
+                                   .collect::<Vec<usize>>();
+    let affected = bounds.windows(3)
+                         .map(|w| stats.exceeding(w[0], w[1], w[2], threshold))
+                         .collect();
+    Ok(SparseResult {
+        result: DetectionResult::new(change_points, value, t_max),
+        affected,
+        scales,
+        threshold,
+    })
+}
+
+
+/// 1階差分の中央絶対偏差による尺度の推定値．平均の変化の影響を受けにくい．
+///
+/// 推定値が0の場合（値がほとんど一定のチャネル）は1とする．
+fn robust_scale(column: ArrayView1<f64>) -> f64 {
+    let mut diffs = column.iter().zip(column.iter().skip(1)).map(|(a, b)| (b - a).abs()).collect::<Vec<f64>>();
+    if diffs.is_empty() {
+        return 1.0;
+    }
+    diffs.sort_by(f64::total_cmp);
+    let median = diffs[diffs.len() / 2];
+    // 正規分布の下で標準偏差の一致推定量となるよう補正する（差分の分散は2倍）
+    let scale = median / (0.6745 * std::f64::consts::SQRT_2);
+    if scale > 0.0 { scale } else { 1.0 }
+}
+
+
+/// 分割の候補
+#[derive(Debug, Clone, Copy)]
+struct Split {
+    at: usize,
+    score: f64,
+}
+
+
+/// 探索中の区間$ (start, end] $
+struct Segment {
+    start: usize,
+    end: usize,
+    split: Option<Split>,
+}
+
+
+/// 標準化した系列のチャネルごとの累積和
+struct Cumulative {
+    sum: Array2<f64>,
+    sum_sq: Array2<f64>,
+}
+
+impl Cumulative {
+    fn new(data: &Array2<f64>, scales: &[f64]) -> Self {
+        let (len, dim) = data.dim();
+        let mut sum = Array2::<f64>::zeros((len + 1, dim));
+        let mut sum_sq = Array2::<f64>::zeros((len + 1, dim));
+        for t in 0..len {
+            for j in 0..dim {
+                let z = data[[t, j]] / scales[j];
+                sum[[t + 1, j]] = sum[[t, j]] + z;
+                sum_sq[[t + 1, j]] = sum_sq[[t, j]] + z * z;
+            }
+        }
+        Cumulative { sum, sum_sq }
+    }
+
+
+    /// 区間`a..b`の全チャネルの対数尤度の和
+    fn log_likelihood(&self, a: usize, b: usize) -> f64 {
+        let n = (b - a) as f64;
+        (0..self.sum.ncols()).map(|j| {
+                                 let s = self.sum[[b, j]] - self.sum[[a, j]];
+                                 let ss = self.sum_sq[[b, j]] - self.sum_sq[[a, j]];
+                                 -0.5 * (ss - s * s / n)
+                             })
+                             .sum()
+    }
+
+
+    /// 区間`a..b`を`s`で分割した場合のチャネルごとの増加量
+    fn gains(&self, a: usize, s: usize, b: usize) -> Vec<f64> {
+        let n = (b - a) as f64;
+        let (n_l, n_r) = ((s - a) as f64, (b - s) as f64);
+        (0..self.sum.ncols()).map(|j| {
+                                 let m_l = (self.sum[[s, j]] - self.sum[[a, j]]) / n_l;
+                                 let m_r = (self.sum[[b, j]] - self.sum[[s, j]]) / n_r;
+                                 n_l * n_r / (2.0 * n) * (m_l - m_r).powi(2)
+                             })
+                             .collect()
+    }
+
+
+    /// 区間`a..b`を`s`で分割した場合に増加量が閾値を超えるチャネル
+    fn exceeding(&self, a: usize, s: usize, b: usize, threshold: f64) -> Vec<usize> {
+        self.gains(a, s, b).iter()
+                           .enumerate()
+                           .filter(|(_, g)| **g > threshold)
+                           .map(|(j, _)| j)
+                           .collect()
+    }
+
+
+    /// 区間`a..b`の最良の分割点を求める
+    fn segment(&self, a: usize, b: usize, min_size: usize, threshold: f64) -> Segment {
+        let mut split: Option<Split> = None;
+        if b - a >= 2 * min_size {
+            for s in (a + min_size)..=(b - min_size) {
+                let score = self.gains(a, s, b).iter()
+                                               .map(|g| (g - threshold).max(0.0))
+                                               .sum::<f64>();
+                if score > 0.0 && split.as_ref().is_none_or(|best| best.score < score) {
+                    split = Some(Split { at: s, score });
+                }
+            }
+        }
+        Segment { start: a, end: b, split }
+    }
+}
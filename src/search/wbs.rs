@@ -0,0 +1,132 @@
+//! Wild binary segmentationによる変化点探索
+
+use super::best_split;
+use crate::cost::SegmentCost;
+use crate::detect::{Penalty, Constraints, DetectionResult};
+use crate::dp_tools::CalcDpError;
+use crate::rng::RngConfig;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+extern crate rand;
+use rand::Rng;
+
+extern crate rayon;
+use rayon::prelude::*;
+
+
+/// Wild binary segmentationにより変化点を探索する
+///
+/// 系列から無作為に選んだ`n_intervals`個の区間それぞれについて，2区間へ分割した場合の評価値の増加量が最大となる分割点を求めておく．
+/// 現在の各区間について，その区間に含まれる無作為な区間と区間自身の中で増加量が最大となる分割点を候補とし，
+/// 候補の増加量が最も大きい区間を分割する操作を繰り返す．停止条件は[`super::binseg`]と同じく`penalty`に従う．
+///
+/// 変化点の近くのみを含む短い区間でも増加量を評価するため，
+/// 複数の変化点が近接して二分割法では見逃される場合にも検出しやすい．
+///
+/// # 引数
+/// * `cost` - コスト関数
+/// * `min_size` - 各区間の最小の長さ（1以上）
+/// * `penalty` - 変化点個数の決め方
+/// * `constraints` - 変化点検出における制約
+/// * `n_intervals` - 無作為に選ぶ区間の数
+/// * `rng` - 乱数の設定（`u64`のseedも与えられる）
+pub fn wbs<C>(cost: &C, min_size: Tau, penalty: &Penalty, constraints: &Constraints, n_intervals: usize, rng: impl Into<RngConfig>) -> Result<DetectionResult, CalcDpError>
+where
+    C: SegmentCost,
+{
+    let t_max = cost.t_max();
+    if t_max == 0 {
+        return Err(CalcDpError::new("Series must contain at least one point."));
+    }
+    if min_size == 0 {
+        return Err(CalcDpError::new("Minimum segment length must be at least 1."));
+    }
+    let (k_target, beta) = match penalty {
+        Penalty::NumChange(k) => {
+            if let Some(max_k) = constraints.max_k {
+                if *k > max_k {
+                    return Err(CalcDpError::new(format!("The number of change point k (= {k}) must not exceed max_k (= {max_k}).")));
+                }
+            }
+            (Some(*k), None)
+        },
+        Penalty::Linear(beta) => (None, Some(*beta)),
+    };
+    let k_lim = match (k_target, constraints.max_k) {
+        (Some(k), _) => k,
+        (None, Some(max_k)) => max_k,
+        (None, None) => NumChg::MAX,
+    };
+
+    // 分割できる長さの区間のみを選ぶ
+    let mut rng = rng.into().rng();
+    let intervals = if t_max >= 2 * min_size {
+        (0..n_intervals).map(|_| {
+                            let a = rng.gen_range(0..=(t_max - 2 * min_size));
+                            let b = rng.gen_range((a + 2 * min_size)..=t_max);
+                            (a, b)
+                        })
+                        .collect::<Vec<(Tau, Tau)>>()
+    } else {
+        Vec::new()
+    };
+    let splits = intervals.par_iter()
+                          .map(|(a, b)| Ok(best_split(cost, *a, *b, min_size)?.map(|split| (*a, *b, split))))
+                          .collect::<Result<Vec<Option<(Tau, Tau, (Tau, f64))>>, CalcDpError>>()?
+                          .into_iter()
+                          .flatten()
+                          .collect::<Vec<(Tau, Tau, (Tau, f64))>>();
+
+    // 区間(start, end]に含まれる無作為な区間と区間自身の中で最良の分割点
+    let candidate = |start: Tau, end: Tau| -> Result<Option<(Tau, f64)>, CalcDpError> {
+        let own = best_split(cost, start, end, min_size)?;
+        Ok(splits.iter()
+                 .filter(|(a, b, _)| *a >= start && *b <= end)
+                 .map(|(_, _, split)| *split)
+                 .chain(own)
+                 .fold(None, |acc: Option<(Tau, f64)>, cand| match acc {
+                     Some(a) if a.1 >= cand.1 => Some(a),
+                     _ => Some(cand),
+                 }))
+    };
+
+    let mut segments = vec![(0, t_max, candidate(0, t_max)?)];
+    let mut change_points = Vec::new();
+    while (change_points.len() as NumChg) < k_lim {
+        let best = segments.iter()
+                           .enumerate()
+                           .filter_map(|(i, (_, _, split))| split.map(|(s, gain)| (i, s, gain)))
+                           .fold(None, |acc: Option<(usize, Tau, f64)>, cand| match acc {
+                               Some(a) if a.2 >= cand.2 => Some(a),
+                               _ => Some(cand),
+                           });
+        let (idx, s, gain) = match best {
+            Some(b) => b,
+            None => break,
+        };
+        if let Some(beta) = beta {
+            if gain <= beta {
+                break;
+            }
+        }
+
+        let (start, end, _) = segments.swap_remove(idx);
+        segments.push((start, s, candidate(start, s)?));
+        segments.push((s, end, candidate(s, end)?));
+        change_points.push(s);
+    }
+
+    if let Some(k) = k_target {
+        if (change_points.len() as NumChg) < k {
+            return Err(CalcDpError::new(format!("Only {} change points can be found (k = {k}).", change_points.len())));
+        }
+    }
+
+    change_points.sort_unstable();
+    let value = segments.iter()
+                        .map(|(start, end, _)| cost.segment_value(*start, *end))
+                        .sum::<Result<f64, CalcDpError>>()?;
+    Ok(DetectionResult::new(change_points, value, t_max))
+}
@@ -0,0 +1,53 @@
+//! 検出した変化点および区間の解釈
+//!
+//! 変化点検出の結果に対し，変化の種類や区間ごとの特徴を求める．
+//! 区間ごとの特徴量は下流の機械学習で用いるため`.npy`形式等で出力できる（[`featureize`]）．
+
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+mod classify;
+pub use classify::{classify_changes, ChangeClassification, ChangeKind};
+mod report;
+pub use report::{segment_reports, SegmentReport, Direction};
+mod attribute;
+pub use attribute::{attribute_channels, ChannelAttribution};
+mod features;
+pub use features::{featureize, FeatureMatrix, FEATURE_NAMES};
+mod transition;
+pub use transition::{transition_matrix, TransitionModel};
+mod merge;
+pub use merge::{suggest_merges, MergeSuggestion};
+mod events;
+pub use events::{align_events, Event, EventMatch, EventAlignment};
+
+
+/// 変化点を検査し，系列の両端を加えた区間の境界を求める
+///
+/// # 引数
+/// * `change_points` - 昇順に並んだ変化点
+/// * `t_max` - 系列の長さ
+///
+/// # 返り値
+/// * `bounds` - $ 0, t_1, \dots, t_K, T $
+pub(crate) fn segment_bounds(change_points: &[Tau], t_max: usize) -> Result<Vec<usize>, CalcDpError> {
+    if t_max == 0 {
+        return Err(CalcDpError::new("Series must contain at least one point."));
+    }
+    let mut bounds = Vec::with_capacity(change_points.len() + 2);
+    bounds.push(0);
+    for &t in change_points {
+        let t = t as usize;
+        if t == 0 || t >= t_max {
+            return Err(CalcDpError::new(format!("Change point {t} is out of range (0, {t_max}).")));
+        }
+        if t <= bounds[bounds.len() - 1] {
+            return Err(CalcDpError::new(format!("Change points must be strictly increasing, but {change_points:?} is given.")));
+        }
+        bounds.push(t);
+    }
+    bounds.push(t_max);
+    Ok(bounds)
+}
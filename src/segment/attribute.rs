@@ -0,0 +1,89 @@
+//! 多変量の系列における変化の各次元（チャネル）への帰属
+
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate ndarray;
+use ndarray::{Array2, ArrayView1, Axis, s};
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 変化点ごとの各チャネルの寄与
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelAttribution {
+    /// 変化点
+    pub change_point: Tau,
+    /// 各チャネルの評価値の差．前後の変化点に挟まれた区間を変化点で分割することによる，正規分布の対数尤度の増分．
+    pub deltas: Vec<f64>,
+    /// 評価値の差の合計に対する各チャネルの割合
+    pub shares: Vec<f64>,
+    /// 各チャネルの平均の変化量（後の区間の平均 - 前の区間の平均）
+    pub mean_shifts: Vec<f64>,
+}
+
+impl ChannelAttribution {
+    /// 寄与の大きい順に並べたチャネルの番号
+    pub fn ranked(&self) -> Vec<usize> {
+        let mut order = (0..self.deltas.len()).collect::<Vec<usize>>();
+        order.sort_by(|i, j| self.deltas[*j].total_cmp(&self.deltas[*i]));
+        order
+    }
+}
+
+
+/// 各変化点における変化に対する各チャネルの寄与を求める
+///
+/// 各変化点について，前後の変化点に挟まれた区間をチャネルごとに1つの正規分布とみなした場合と，
+/// 変化点の前後で平均と分散の異なる正規分布とみなした場合の対数尤度の差を寄与とする．
+/// 多変量のコスト関数で検出した変化点について「どのセンサが実際に変化したか」を調べるために用いる．
+/// 区間内で値が一定のチャネルは分散を`f64::MIN_POSITIVE`として扱う．
+///
+/// # 引数
+/// * `data` - 系列データ．行が時期，列がチャネルに対応する．
+/// * `change_points` - 昇順に並んだ変化点
+///
+/// # 返り値
+/// * `attributions` - 変化点ごとの各チャネルの寄与
+pub fn attribute_channels(data: &Array2<f64>, change_points: &[Tau]) -> Result<Vec<ChannelAttribution>, CalcDpError> {
+    let bounds = super::segment_bounds(change_points, data.nrows())?;
+
+    Ok(bounds.windows(3)
+             .map(|w| attribute(data, w[0], w[1], w[2]))
+             .collect())
+}
+
+
+/// 区間`lo..hi`の変化点`t`における各チャネルの寄与
+fn attribute(data: &Array2<f64>, lo: usize, t: usize, hi: usize) -> ChannelAttribution {
+    let (deltas, mean_shifts): (Vec<f64>, Vec<f64>) = data.axis_iter(Axis(1))
+        .map(|column| {
+            let (ll_all, _) = profile(column.slice(s![lo..hi]));
+            let (ll_before, mean_before) = profile(column.slice(s![lo..t]));
+            let (ll_after, mean_after) = profile(column.slice(s![t..hi]));
+            ((ll_before + ll_after - ll_all).max(0.0), mean_after - mean_before)
+        })
+        .unzip();
+    let total = deltas.iter().sum::<f64>();
+    let shares = deltas.iter()
+                       .map(|d| if total > 0.0 { d / total } else { 0.0 })
+                       .collect();
+    ChannelAttribution {
+        change_point: t as Tau,
+        deltas,
+        shares,
+        mean_shifts,
+    }
+}
+
+
+/// 正規分布を当てはめた場合の対数尤度（定数項を除く）と平均
+fn profile(seg: ArrayView1<f64>) -> (f64, f64) {
+    let n = seg.len() as f64;
+    let mean = seg.sum() / n;
+    let variance = seg.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / n;
+    (-0.5 * n * variance.max(f64::MIN_POSITIVE).ln(), mean)
+}
@@ -0,0 +1,144 @@
+//! 変化の種類（急変と漸変）の判別
+
+use crate::cost::PrefixSum;
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 変化の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// 変化点で水準が急に変化する（故障等）
+    Step,
+    /// 変化点の前後で水準が徐々に変化する（摩耗等）
+    Ramp,
+}
+
+
+/// 変化点ごとの変化の種類の判別結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeClassification {
+    /// 変化点
+    pub change_point: Tau,
+    /// 判別した変化の種類
+    pub kind: ChangeKind,
+    /// 急変モデルに対する漸変モデルの対数尤度比からBICの罰則を除いた値．正であれば漸変と判別する．
+    pub score: f64,
+    /// 漸変モデルにおいて水準が変化する期間の長さ
+    pub ramp_length: usize,
+    /// 判別したモデルにおける変化の前後の水準の差
+    pub delta: f64,
+}
+
+
+/// 各変化点における変化が急変か漸変かを判別する
+///
+/// 各変化点について，前後の変化点に挟まれた区間に以下の2つのモデルを当てはめ，正規分布の下での対数尤度を比較する．
+/// * 急変モデル: 変化点の前後でそれぞれ一定の水準をとる
+/// * 漸変モデル: 変化点を中心とする期間で水準が直線的に変化し，その前後では一定の水準をとる
+///
+/// 漸変モデルは水準が変化する期間の長さを推定するため，BICに基づき1母数分の罰則を課す．
+///
+/// # 引数
+/// * `data` - 系列データ
+/// * `change_points` - 昇順に並んだ変化点
+///
+/// # 返り値
+/// * `classifications` - 変化点ごとの判別結果
+pub fn classify_changes(data: &[f64], change_points: &[Tau]) -> Result<Vec<ChangeClassification>, CalcDpError> {
+    let bounds = super::segment_bounds(change_points, data.len())?;
+
+    let stats = LocalStats::new(data);
+    Ok(bounds.windows(3)
+             .map(|w| stats.classify(w[0], w[1], w[2]))
+             .collect())
+}
+
+
+/// 区間の回帰に用いる累積和
+struct LocalStats {
+    sum_i: PrefixSum,
+    sum_ii: PrefixSum,
+    sum_y: PrefixSum,
+    sum_iy: PrefixSum,
+    sum_yy: PrefixSum,
+}
+
+impl LocalStats {
+    fn new(data: &[f64]) -> Self {
+        LocalStats {
+            sum_i: PrefixSum::new((0..data.len()).map(|i| i as f64)),
+            sum_ii: PrefixSum::new((0..data.len()).map(|i| (i * i) as f64)),
+            sum_y: PrefixSum::new(data.iter().copied()),
+            sum_iy: PrefixSum::new(data.iter().enumerate().map(|(i, y)| i as f64 * y)),
+            sum_yy: PrefixSum::new(data.iter().map(|y| y * y)),
+        }
+    }
+
+
+    /// 区間`lo..hi`に対し，`t`で水準が変化するモデルを当てはめ，残差平方和と水準の差を返す
+    ///
+    /// 水準は`t - half`から`t + half`の期間で直線的に変化する．`half`が0.5の場合は急変モデルとなる．
+    fn fit(&self, lo: usize, t: usize, hi: usize, half: f64) -> (f64, f64) {
+        let r_lo = (t as f64 - half).ceil() as usize;
+        let r_hi = (t as f64 + half).ceil() as usize;
+        let a = t as f64 - 0.5 - half;
+        let w = 2.0 * half;
+        let (r_lo_t, r_hi_t, hi_t) = (r_lo as Tau, r_hi as Tau, hi as Tau);
+
+        let m = (r_hi - r_lo) as f64;
+        let s_i = self.sum_i.range(r_lo_t, r_hi_t);
+        let s_ii = self.sum_ii.range(r_lo_t, r_hi_t);
+        let s_y_ramp = self.sum_y.range(r_lo_t, r_hi_t);
+        let s_iy = self.sum_iy.range(r_lo_t, r_hi_t);
+        let n_ones = (hi - r_hi) as f64;
+        let s_y_ones = self.sum_y.range(r_hi_t, hi_t);
+
+        let n = (hi - lo) as f64;
+        let sx = (s_i - m * a) / w + n_ones;
+        let sxx = (s_ii - 2.0 * a * s_i + m * a * a) / (w * w) + n_ones;
+        let sxy = (s_iy - a * s_y_ramp) / w + s_y_ones;
+        let sy = self.sum_y.range(lo as Tau, hi_t);
+        let syy = self.sum_yy.range(lo as Tau, hi_t);
+
+        let cxx = sxx - sx * sx / n;
+        let cxy = sxy - sx * sy / n;
+        let cyy = syy - sy * sy / n;
+        let slope = cxy / cxx;
+        ((cyy - slope * cxy).max(0.0), slope)
+    }
+
+
+    /// 区間`lo..hi`の変化点`t`を判別する
+    fn classify(&self, lo: usize, t: usize, hi: usize) -> ChangeClassification {
+        let n = (hi - lo) as f64;
+        let (sse_step, delta_step) = self.fit(lo, t, hi, 0.5);
+        let (sse_ramp, delta_ramp, half) = (1..=(t - lo).min(hi - t))
+            .map(|h| {
+                let (sse, delta) = self.fit(lo, t, hi, h as f64);
+                (sse, delta, h)
+            })
+            .fold((f64::INFINITY, 0.0, 0), |best, cur| if cur.0 < best.0 { cur } else { best });
+
+        let floor = f64::MIN_POSITIVE;
+        let score = 0.5 * n * (sse_step.max(floor) / sse_ramp.max(floor)).ln() - 0.5 * n.ln();
+        let (kind, delta) = if score > 0.0 {
+            (ChangeKind::Ramp, delta_ramp)
+        } else {
+            (ChangeKind::Step, delta_step)
+        };
+        ChangeClassification {
+            change_point: t as Tau,
+            kind,
+            score,
+            ramp_length: 2 * half,
+            delta,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! 変化点と外部の事象記録（レシピ変更，保守等）の照合
+
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 記録された事象
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    /// 事象の時点．変化点と同じく，事象の直前の観測の時点とする．
+    pub time: Tau,
+    /// 事象の名前
+    pub label: String,
+}
+
+
+/// 変化点と事象の対応
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventMatch {
+    /// 変化点
+    pub change_point: Tau,
+    /// 対応する事象
+    pub event: Event,
+    /// 事象から変化点までの遅れ（変化点 - 事象の時点）．負であれば事象より前に変化している．
+    pub lag: i64,
+}
+
+
+/// 変化点と事象の照合結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventAlignment {
+    /// 事象と対応付いた変化点（変化点の昇順）
+    pub matched: Vec<EventMatch>,
+    /// 対応する事象が無い変化点
+    pub unmatched_changes: Vec<Tau>,
+    /// 対応する変化点が無い事象
+    pub unmatched_events: Vec<Event>,
+}
+
+impl EventAlignment {
+    /// 事象と対応付いた変化点の割合．変化点が無い場合は`None`．
+    pub fn matched_fraction(&self) -> Option<f64> {
+        let n = self.matched.len() + self.unmatched_changes.len();
+        (n > 0).then(|| self.matched.len() as f64 / n as f64)
+    }
+}
+
+
+/// 各変化点を近くで記録された事象と照合する
+///
+/// 各変化点について，時点の差が`window`以下の事象のうち最も近いものを対応付ける．
+/// 差が等しい場合は先に記録された事象を選ぶ．1個の事象が複数の変化点に対応することもある．
+///
+/// # 引数
+/// * `change_points` - 昇順に並んだ変化点
+/// * `events` - 記録された事象（順不同）
+/// * `window` - 対応付ける時点の差の上限
+pub fn align_events(change_points: &[Tau], events: &[Event], window: Tau) -> Result<EventAlignment, CalcDpError> {
+    if change_points.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(CalcDpError::new(format!("Change points must be strictly increasing, but {change_points:?} is given.")));
+    }
+
+    let mut sorted = events.to_vec();
+    sorted.sort_by_key(|e| e.time);
+    let mut used = vec![false; sorted.len()];
+    let mut matched = Vec::new();
+    let mut unmatched_changes = Vec::new();
+    for &t in change_points {
+        let nearest = sorted.iter()
+                            .enumerate()
+                            .map(|(i, e)| (t.abs_diff(e.time), i))
+                            .filter(|(d, _)| *d <= window)
+                            .min();
+        match nearest {
+            Some((_, i)) => {
+                used[i] = true;
+                let event = sorted[i].clone();
+                let lag = t as i64 - event.time as i64;
+                matched.push(EventMatch { change_point: t, event, lag });
+            },
+            None => unmatched_changes.push(t),
+        }
+    }
+    let unmatched_events = sorted.into_iter()
+                                 .zip(used)
+                                 .filter(|(_, u)| !u)
+                                 .map(|(e, _)| e)
+                                 .collect();
+    Ok(EventAlignment { matched, unmatched_changes, unmatched_events })
+}
@@ -0,0 +1,186 @@
+//! 区間ごとの固定長の特徴量
+//!
+//! 検出した区間（状態）を下流の機械学習の分類器へ与えるため，区間の長さによらない次元の特徴量へ変換し，
+//! `.npy`形式またはCSV形式で出力する．
+
+use crate::dp_tools::CalcDpError;
+use crate::io;
+
+use std::path::Path;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate ndarray;
+use ndarray::{Array2, ArrayView1};
+
+
+/// 特徴量の名前（[`FeatureMatrix::values`]の列の順番）
+pub const FEATURE_NAMES: [&str; 13] = [
+    "length",
+    "mean",
+    "sd",
+    "skewness",
+    "kurtosis",
+    "min",
+    "max",
+    "slope",
+    "lag1_autocorrelation",
+    "dominant_frequency",
+    "spectral_entropy",
+    "low_frequency_power",
+    "high_frequency_power",
+];
+
+
+/// スペクトルを評価する周波数の個数．周波数は$ m / (2M) $（$ m = 1, \dots, M $）とする．
+const N_FREQUENCIES: usize = 32;
+
+
+/// 区間ごとの特徴量
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureMatrix {
+    /// 区間（前の変化点, 後ろの変化点）
+    pub segments: Vec<(Tau, Tau)>,
+    /// 特徴量（行が区間，列が[`FEATURE_NAMES`]に対応する）
+    pub values: Array2<f64>,
+}
+
+impl FeatureMatrix {
+    /// CSV形式の文字列．1列目と2列目は区間の範囲とする．
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("start,end,{}\n", FEATURE_NAMES.join(","));
+        for ((t_k_1, t_k), row) in self.segments.iter().zip(self.values.outer_iter()) {
+            let row = row.iter().map(|v| v.to_string()).collect::<Vec<String>>();
+            csv.push_str(&format!("{t_k_1},{t_k},{}\n", row.join(",")));
+        }
+        csv
+    }
+
+
+    /// NumPyの`.npy`形式（バージョン1.0，リトルエンディアンの`f64`，C順序）のバイト列
+    ///
+    /// 区間の範囲は含まない．
+    pub fn to_npy(&self) -> Vec<u8> {
+        let (rows, cols) = self.values.dim();
+        let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': ({rows}, {cols}), }}");
+        // 先頭の10バイトと合わせて64バイトの倍数となるよう空白で埋め，改行で終える
+        let total = 10 + header.len() + 1;
+        header.push_str(&" ".repeat((64 - total % 64) % 64));
+        header.push('\n');
+
+        let mut bytes = Vec::with_capacity(10 + header.len() + 8 * rows * cols);
+        bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        for v in self.values.iter() {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes
+    }
+
+
+    /// CSV形式でファイルへ書き込む
+    ///
+    /// # 引数
+    /// * `path` - 書き込み先のパス
+    pub fn write_csv(&self, path: &Path) -> Result<(), CalcDpError> {
+        io::write_text(path, &self.to_csv())
+    }
+
+
+    /// `.npy`形式でファイルへ書き込む
+    ///
+    /// # 引数
+    /// * `path` - 書き込み先のパス
+    pub fn write_npy(&self, path: &Path) -> Result<(), CalcDpError> {
+        io::write_bytes(path, &self.to_npy())
+    }
+}
+
+
+/// 区間ごとに固定長の特徴量を求める
+///
+/// 特徴量は[`FEATURE_NAMES`]の順に，区間の長さ，積率（平均，標準偏差，歪度，超過尖度），最小値，最大値，
+/// 時点に対する回帰直線の傾き，ラグ1の自己相関，およびスペクトルの要約である．
+/// スペクトルは区間の回帰直線を除いた残差のピリオドグラムを固定した周波数で評価し，
+/// 最大となる周波数，正規化したエントロピー（0から1），周波数0.1以下と0.25以上の成分の割合を求める．
+///
+/// 全ての特徴量は有限の値とする．分散が0の区間では，歪度，尖度，自己相関およびスペクトルの要約を0とする．
+///
+/// # 引数
+/// * `data` - 系列データ
+/// * `change_points` - 昇順に並んだ変化点
+pub fn featureize(data: &[f64], change_points: &[Tau]) -> Result<FeatureMatrix, CalcDpError> {
+    let t_max = data.len();
+    if let Some(v) = data.iter().find(|v| !v.is_finite()) {
+        return Err(CalcDpError::new(format!("Data must be finite, but {v} is given.")));
+    }
+    let segments = super::segment_bounds(change_points, t_max)?
+                       .windows(2)
+                       .map(|w| (w[0] as Tau, w[1] as Tau))
+                       .collect::<Vec<(Tau, Tau)>>();
+
+    let mut values = Array2::zeros((segments.len(), FEATURE_NAMES.len()));
+    for (mut row, (t_k_1, t_k)) in values.outer_iter_mut().zip(&segments) {
+        let features = segment_features(&data[(*t_k_1 as usize)..(*t_k as usize)]);
+        row.assign(&ArrayView1::from(&features));
+    }
+    Ok(FeatureMatrix { segments, values })
+}
+
+
+/// 1区間の特徴量
+fn segment_features(seg: &[f64]) -> [f64; FEATURE_NAMES.len()] {
+    let n = seg.len() as f64;
+    let mean = seg.iter().sum::<f64>() / n;
+    let central = |p: i32| seg.iter().map(|y| (y - mean).powi(p)).sum::<f64>() / n;
+    let variance = central(2);
+    let sd = variance.sqrt();
+    let min = seg.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = seg.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    // 時点に対する回帰直線
+    let t_mean = (n - 1.0) / 2.0;
+    let s_tt = seg.iter().enumerate().map(|(i, _)| (i as f64 - t_mean).powi(2)).sum::<f64>();
+    let s_ty = seg.iter().enumerate().map(|(i, y)| (i as f64 - t_mean) * (y - mean)).sum::<f64>();
+    let slope = if s_tt > 0.0 { s_ty / s_tt } else { 0.0 };
+
+    let mut features = [n, mean, sd, 0.0, 0.0, min, max, slope, 0.0, 0.0, 0.0, 0.0, 0.0];
+    if variance > 0.0 {
+        features[3] = central(3) / variance.powf(1.5);
+        features[4] = central(4) / (variance * variance) - 3.0;
+        features[8] = seg.windows(2).map(|w| (w[0] - mean) * (w[1] - mean)).sum::<f64>() / (n * variance);
+
+        let residuals = seg.iter()
+                           .enumerate()
+                           .map(|(i, y)| y - mean - slope * (i as f64 - t_mean))
+                           .collect::<Vec<f64>>();
+        let power = (1..=N_FREQUENCIES).map(|m| {
+                                           let omega = std::f64::consts::PI * m as f64 / N_FREQUENCIES as f64;
+                                           let (re, im) = residuals.iter()
+                                                                   .enumerate()
+                                                                   .fold((0.0, 0.0), |(re, im), (i, r)| {
+                                                                       let phase = omega * i as f64;
+                                                                       (re + r * phase.cos(), im - r * phase.sin())
+                                                                   });
+                                           re * re + im * im
+                                       })
+                                       .collect::<Vec<f64>>();
+        let total = power.iter().sum::<f64>();
+        if total > 0.0 {
+            let frequency = |m: usize| (m + 1) as f64 / (2 * N_FREQUENCIES) as f64;
+            let dominant = (0..N_FREQUENCIES).fold(0, |best, m| if power[m] > power[best] { m } else { best });
+            let entropy = power.iter()
+                               .map(|p| p / total)
+                               .filter(|p| *p > 0.0)
+                               .map(|p| -p * p.ln())
+                               .sum::<f64>();
+            features[9] = frequency(dominant);
+            features[10] = entropy / (N_FREQUENCIES as f64).ln();
+            features[11] = (0..N_FREQUENCIES).filter(|m| frequency(*m) <= 0.1).map(|m| power[m]).sum::<f64>() / total;
+            features[12] = (0..N_FREQUENCIES).filter(|m| frequency(*m) >= 0.25).map(|m| power[m]).sum::<f64>() / total;
+        }
+    }
+    features
+}
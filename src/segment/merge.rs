@@ -0,0 +1,56 @@
+//! 過剰に分割された区間の併合の提案
+
+use crate::cost::SegmentCost;
+use crate::detect::DetectionResult;
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 隣接する2区間の併合の提案
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeSuggestion {
+    /// 取り除く変化点
+    pub change_point: Tau,
+    /// 前の区間$ (t_{k-1}, t_k] $
+    pub left: (Tau, Tau),
+    /// 後ろの区間$ (t_k, t_{k+1}] $
+    pub right: (Tau, Tau),
+    /// 併合による評価値の減少量$ f(t_{k-1}, t_k) + f(t_k, t_{k+1}) - f(t_{k-1}, t_{k+1}) $
+    pub cost_increase: f64,
+}
+
+
+/// 併合による評価値の減少量が閾値未満となる隣接する区間の組を求める
+///
+/// 各変化点を1個ずつ取り除いた場合の評価値の減少量を求めるため，再度の変化点検出は行わない．
+/// 提案は互いに独立に評価しており，複数の提案を同時に適用した場合の減少量は各減少量の和と一致しない．
+///
+/// # 引数
+/// * `result` - 変化点検出の結果
+/// * `cost` - 評価値の計算に用いるコスト関数
+/// * `threshold` - 評価値の減少量の閾値
+///
+/// # 返り値
+/// * `suggestions` - 減少量の昇順に並んだ併合の提案
+pub fn suggest_merges<C: SegmentCost>(result: &DetectionResult, cost: &C, threshold: f64) -> Result<Vec<MergeSuggestion>, CalcDpError> {
+    let segments = result.segments();
+    let values = segments.iter()
+                         .map(|(t_k_1, t_k)| cost.segment_value(*t_k_1, *t_k))
+                         .collect::<Result<Vec<f64>, CalcDpError>>()?;
+    let mut suggestions = Vec::new();
+    for (seg, val) in segments.windows(2).zip(values.windows(2)) {
+        let (left, right) = (seg[0], seg[1]);
+        let merged = cost.segment_value(left.0, right.1)?;
+        let cost_increase = val[0] + val[1] - merged;
+        if cost_increase < threshold {
+            suggestions.push(MergeSuggestion { change_point: left.1, left, right, cost_increase });
+        }
+    }
+    suggestions.sort_by(|a, b| a.cost_increase.total_cmp(&b.cost_increase));
+    Ok(suggestions)
+}
@@ -0,0 +1,127 @@
+//! 区間ごとの要約と隣接する区間の間の変化量
+
+use crate::dp_tools::CalcDpError;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 変化の向き
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// 増加
+    Up,
+    /// 減少
+    Down,
+    /// 変化なし
+    Flat,
+}
+
+impl Direction {
+    fn of(delta: f64) -> Self {
+        if delta > 0.0 {
+            Direction::Up
+        } else if delta < 0.0 {
+            Direction::Down
+        } else {
+            Direction::Flat
+        }
+    }
+}
+
+
+/// 区間の要約
+///
+/// 変化量に関する値は直前の区間との比較であり，最初の区間では`None`となる．
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SegmentReport {
+    /// 前の変化点$ t_{k-1} $
+    pub start: Tau,
+    /// 後ろの変化点$ t_k $
+    pub end: Tau,
+    /// 平均
+    pub mean: f64,
+    /// 分散（最尤推定値）
+    pub variance: f64,
+    /// 直前の区間からの平均の変化量
+    pub delta_mean: Option<f64>,
+    /// 直前の区間からの分散の変化量
+    pub delta_variance: Option<f64>,
+    /// 平均の変化量を2区間の併合標準偏差で除した標準化効果量（Cohenの$ d $）
+    pub effect_size: Option<f64>,
+    /// 直前の区間に対する分散比の対数
+    pub log_variance_ratio: Option<f64>,
+    /// 平均の変化の向き
+    pub mean_direction: Option<Direction>,
+    /// 分散の変化の向き
+    pub variance_direction: Option<Direction>,
+}
+
+impl SegmentReport {
+    /// 直前の区間からの変化が実質的に意味のある大きさか判定する
+    ///
+    /// 最初の区間では`false`を返す．
+    ///
+    /// # 引数
+    /// * `min_effect_size` - 標準化効果量の絶対値の下限
+    /// * `min_log_variance_ratio` - 分散比の対数の絶対値の下限
+    pub fn is_substantial(&self, min_effect_size: f64, min_log_variance_ratio: f64) -> bool {
+        let mean_changed = self.effect_size.is_some_and(|d| d.abs() >= min_effect_size);
+        let variance_changed = self.log_variance_ratio.is_some_and(|r| r.abs() >= min_log_variance_ratio);
+        mean_changed || variance_changed
+    }
+}
+
+
+/// 区間ごとの要約と隣接する区間の間の変化量を求める
+///
+/// 分散が0の区間を含む場合，標準化効果量および分散比の対数は無限大または非数となる．
+///
+/// # 引数
+/// * `data` - 系列データ
+/// * `change_points` - 昇順に並んだ変化点
+///
+/// # 返り値
+/// * `reports` - 区間ごとの要約
+pub fn segment_reports(data: &[f64], change_points: &[Tau]) -> Result<Vec<SegmentReport>, CalcDpError> {
+    let bounds = super::segment_bounds(change_points, data.len())?;
+    let mut reports: Vec<SegmentReport> = Vec::with_capacity(bounds.len() - 1);
+    for w in bounds.windows(2) {
+        let (start, end) = (w[0], w[1]);
+        let seg = &data[start..end];
+        let n = seg.len() as f64;
+        let mean = seg.iter().sum::<f64>() / n;
+        let variance = seg.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / n;
+
+        let mut report = SegmentReport {
+            start: start as Tau,
+            end: end as Tau,
+            mean,
+            variance,
+            delta_mean: None,
+            delta_variance: None,
+            effect_size: None,
+            log_variance_ratio: None,
+            mean_direction: None,
+            variance_direction: None,
+        };
+        if let Some(prev) = reports.last() {
+            let n_prev = (prev.end - prev.start) as f64;
+            let pooled = (n_prev * prev.variance + n * variance) / (n_prev + n);
+            let delta_mean = mean - prev.mean;
+            let delta_variance = variance - prev.variance;
+            report.delta_mean = Some(delta_mean);
+            report.delta_variance = Some(delta_variance);
+            report.effect_size = Some(delta_mean / pooled.sqrt());
+            report.log_variance_ratio = Some((variance / prev.variance).ln());
+            report.mean_direction = Some(Direction::of(delta_mean));
+            report.variance_direction = Some(Direction::of(delta_variance));
+        }
+        reports.push(report);
+    }
+    Ok(reports)
+}
@@ -0,0 +1,114 @@
+//! 状態（レジーム）間の遷移の推定
+
+use crate::dp_tools::CalcDpError;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+
+
+/// 状態間の遷移確率と滞在時間の推定結果
+///
+/// 同じ状態が続く期間（滞在）を単位とするセミマルコフ過程として，ある状態から別の状態へ移る確率と，
+/// 各状態の滞在時間の分布を求める．系列の最初と最後の滞在は観測範囲外から続いている（打ち切られている）ため，
+/// 滞在時間の分布とは分けて保持する．
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransitionModel {
+    /// 状態の数
+    pub n_regimes: usize,
+    /// 状態$ i $の滞在の後に状態$ j $へ移った回数（行が遷移元，列が遷移先）
+    pub counts: Vec<Vec<u64>>,
+    /// 遷移確率（行が遷移元，列が遷移先）．対角成分は0．他の状態へ移ったことのない状態の行は全て0．
+    pub probabilities: Vec<Vec<f64>>,
+    /// 状態ごとの滞在時間（系列の端に接しない滞在のみ）
+    pub dwell_times: Vec<Vec<usize>>,
+    /// 系列の端に接する滞在の（状態, 観測された長さ）
+    pub censored_dwell_times: Vec<(usize, usize)>,
+}
+
+impl TransitionModel {
+    /// 状態ごとの平均滞在時間．系列の端に接しない滞在がない状態は`None`．
+    pub fn mean_dwell_times(&self) -> Vec<Option<f64>> {
+        self.dwell_times.iter()
+                        .map(|d| if d.is_empty() { None } else { Some(d.iter().sum::<usize>() as f64 / d.len() as f64) })
+                        .collect()
+    }
+
+
+    /// 状態`regime`の滞在時間の経験分布
+    ///
+    /// # 引数
+    /// * `regime` - 状態の番号
+    ///
+    /// # 返り値
+    /// * `distribution` - 滞在時間の昇順に並べた（滞在時間, 割合）
+    pub fn dwell_distribution(&self, regime: usize) -> Result<Vec<(usize, f64)>, CalcDpError> {
+        let dwell = self.dwell_times.get(regime).ok_or_else(|| CalcDpError::new(format!("Regime {regime} does not exist ({} regimes).", self.n_regimes)))?;
+        let mut sorted = dwell.clone();
+        sorted.sort_unstable();
+        let n = sorted.len() as f64;
+        let mut distribution: Vec<(usize, f64)> = Vec::new();
+        for d in sorted {
+            match distribution.last_mut() {
+                Some((last, p)) if *last == d => *p += 1.0 / n,
+                _ => distribution.push((d, 1.0 / n)),
+            }
+        }
+        Ok(distribution)
+    }
+}
+
+
+/// 時点ごとの状態の番号から，状態間の遷移確率と滞在時間の分布を推定する
+///
+/// 区間を状態へ分類した結果（区間ごとの状態の番号）は，区間の長さだけ繰り返して時点ごとの番号とする．
+/// 隣接する区間が同じ状態に分類された場合は，1回の滞在として扱う．
+///
+/// # 引数
+/// * `labels` - 時点ごとの状態の番号（0から始まる）
+pub fn transition_matrix(labels: &[usize]) -> Result<TransitionModel, CalcDpError> {
+    let n_regimes = match labels.iter().max() {
+        Some(m) => m + 1,
+        None => return Err(CalcDpError::new("Labels must contain at least one time point.")),
+    };
+
+    // 同じ状態が続く期間（状態, 長さ）
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for &label in labels {
+        match runs.last_mut() {
+            Some((last, len)) if *last == label => *len += 1,
+            _ => runs.push((label, 1)),
+        }
+    }
+
+    let mut counts = vec![vec![0_u64; n_regimes]; n_regimes];
+    for pair in runs.windows(2) {
+        counts[pair[0].0][pair[1].0] += 1;
+    }
+    let probabilities = counts.iter()
+                              .map(|row| {
+                                  let total = row.iter().sum::<u64>();
+                                  row.iter()
+                                     .map(|c| if total > 0 { *c as f64 / total as f64 } else { 0.0 })
+                                     .collect()
+                              })
+                              .collect();
+
+    let mut dwell_times = vec![Vec::new(); n_regimes];
+    let mut censored_dwell_times = Vec::new();
+    let last = runs.len() - 1;
+    for (idx, (label, len)) in runs.into_iter().enumerate() {
+        if idx == 0 || idx == last {
+            censored_dwell_times.push((label, len));
+        } else {
+            dwell_times[label].push(len);
+        }
+    }
+
+    Ok(TransitionModel {
+        n_regimes,
+        counts,
+        probabilities,
+        dwell_times,
+        censored_dwell_times,
+    })
+}
@@ -0,0 +1,37 @@
+//! コスト関数等の計算に用いる特殊関数
+
+
+/// Lanczos近似に用いる係数 (g = 7, n = 9)
+const LANCZOS_G: f64 = 7.0;
+const LANCZOS_COEF: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+
+/// ガンマ関数の対数$ \ln \Gamma(x) $
+///
+/// Lanczos近似により計算する．$ x < 0.5 $では相反公式を用いる．
+///
+/// # 引数
+/// * `x` - 正の実数
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        (pi / (pi * x).sin().abs()).ln() - ln_gamma(1.0 - x)
+    } else {
+        let x = x - 1.0;
+        let t = x + LANCZOS_G + 0.5;
+        let a = LANCZOS_COEF[1..].iter()
+                                 .enumerate()
+                                 .fold(LANCZOS_COEF[0], |acc, (i, c)| acc + c / (x + (i + 1) as f64));
+        0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + a.ln()
+    }
+}
@@ -0,0 +1,10 @@
+//! 検出結果に対する統計的推測
+//!
+//! 検出した変化点に基づく区間ごとの推定値の不確かさの評価や，検出力の見積もり，変化点の有無の検定を行う．
+
+mod segment_ci;
+pub use segment_ci::{segment_mean_cis, SegmentCi, BootstrapOptions};
+mod power;
+pub use power::{power_analysis, PowerCurve, ShiftModel};
+mod permutation;
+pub use permutation::{permutation_test, benjamini_hochberg, PermutationTest};
@@ -0,0 +1,91 @@
+//! 並べ替え検定と多重検定の補正
+
+use crate::calibrate::single_change_statistic;
+use crate::cost::SegmentCost;
+use crate::dp_tools::CalcDpError;
+use crate::rng::RngConfig;
+
+extern crate rand;
+use rand::seq::SliceRandom;
+
+extern crate rayon;
+use rayon::prelude::*;
+
+
+/// 並べ替え検定の結果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PermutationTest {
+    /// 観測された系列に対する変化点1個の検定統計量$ \Lambda $
+    pub statistic: f64,
+    /// p値
+    pub p_value: f64,
+}
+
+
+/// 変化点の有無に対する並べ替え検定
+///
+/// 帰無仮説「変化点が存在しない」の下では各時点の観測は交換可能であることを利用し，
+/// 時点の順序を無作為に並べ替えた系列に対する$ \Lambda $（[`single_change_statistic`]）と観測された系列に対する値を比較する．
+/// 全ての列は同じ順序で並べ替えるため，同じ時点における列の間の関係は保たれる．
+/// p値は$ (1 + \#\{\Lambda^* \ge \Lambda\}) / (1 + $ `n_perm` $) $とする．
+///
+/// # 引数
+/// * `columns` - 系列データの各列
+/// * `build` - 列からコスト関数を作成する関数
+/// * `n_perm` - 並べ替えの回数（1以上）
+/// * `rng` - 乱数の設定（`u64`のseedも与えられる）
+pub fn permutation_test<C, F>(columns: &[Vec<f64>], build: F, n_perm: usize, rng: impl Into<RngConfig>) -> Result<PermutationTest, CalcDpError>
+where
+    C: SegmentCost,
+    F: Fn(&[Vec<f64>]) -> Result<C, CalcDpError> + Sync,
+{
+    if n_perm == 0 {
+        return Err(CalcDpError::new("n_perm must be at least 1."));
+    }
+    let rng = rng.into();
+    let t_max = columns.first().map_or(0, |c| c.len());
+    let statistic = single_change_statistic(&build(columns)?)?;
+
+    let exceed = (0..n_perm).into_par_iter()
+                            .map(|i| {
+                                let mut rng = rng.trial(i as u64);
+                                let mut order = (0..t_max).collect::<Vec<usize>>();
+                                order.shuffle(&mut rng);
+                                let permuted = columns.iter()
+                                                      .map(|c| order.iter().map(|&j| c[j]).collect::<Vec<f64>>())
+                                                      .collect::<Vec<Vec<f64>>>();
+                                Ok(single_change_statistic(&build(&permuted)?)? >= statistic)
+                            })
+                            .collect::<Result<Vec<bool>, CalcDpError>>()?
+                            .into_iter()
+                            .filter(|e| *e)
+                            .count();
+    Ok(PermutationTest {
+        statistic,
+        p_value: (1 + exceed) as f64 / (1 + n_perm) as f64,
+    })
+}
+
+
+/// Benjamini–Hochberg法により調整したp値を求める
+///
+/// 調整したp値が$ q $以下の検定を棄却すると，偽発見率が$ q $以下に制御される．
+///
+/// # 引数
+/// * `p_values` - 各検定のp値
+///
+/// # 返り値
+/// * `adjusted` - `p_values`と同じ順序で並べた調整済みのp値
+pub fn benjamini_hochberg(p_values: &[f64]) -> Vec<f64> {
+    let m = p_values.len();
+    let mut order = (0..m).collect::<Vec<usize>>();
+    order.sort_by(|a, b| p_values[*a].total_cmp(&p_values[*b]));
+
+    let mut adjusted = vec![0.0; m];
+    let mut running_min: f64 = 1.0;
+    for (rank, &i) in order.iter().enumerate().rev() {
+        running_min = running_min.min(p_values[i] * m as f64 / (rank + 1) as f64);
+        adjusted[i] = running_min;
+    }
+    adjusted
+}
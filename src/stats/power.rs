@@ -0,0 +1,133 @@
+//! 検出力の見積もり
+
+use crate::calibrate::{calibrate_threshold, single_change_statistic, NullModel, GaussianMeanNull, UncorrelatedNull};
+use crate::cost::{HeteroscedasticMeanCost, CorrelationCost};
+use crate::dp_tools::CalcDpError;
+use crate::rng::RngConfig;
+
+extern crate process_param;
+use process_param::Tau;
+
+extern crate rand;
+use rand::rngs::StdRng;
+
+extern crate rand_distr;
+use rand_distr::{Distribution, StandardNormal};
+
+extern crate rayon;
+use rayon::prelude::*;
+
+
+/// 変化を含む系列の生成モデル
+pub trait ShiftModel: NullModel {
+    /// 長さ`segment_length`の2つの区間からなり，後半の区間が`shift`だけ変化した系列を生成し，そのコスト関数を作成する
+    ///
+    /// # 引数
+    /// * `segment_length` - 各区間の長さ
+    /// * `shift` - 変化量
+    /// * `rng` - 乱数生成器
+    fn simulate_shift(&self, segment_length: Tau, shift: f64, rng: &mut StdRng) -> Result<Self::Cost, CalcDpError>;
+}
+
+impl ShiftModel for GaussianMeanNull {
+    /// 後半の区間の平均が`shift`だけ変化する
+    fn simulate_shift(&self, segment_length: Tau, shift: f64, rng: &mut StdRng) -> Result<Self::Cost, CalcDpError> {
+        let len = 2 * segment_length as usize;
+        let data = (0..len).map(|t| {
+                               let z: f64 = StandardNormal.sample(rng);
+                               self.sd * z + if t < segment_length as usize { 0.0 } else { shift }
+                           })
+                           .collect::<Vec<f64>>();
+        HeteroscedasticMeanCost::new(&data, &vec![self.sd * self.sd; len])
+    }
+}
+
+impl ShiftModel for UncorrelatedNull {
+    /// 後半の区間の相関係数が`shift`となる（$ -1 < $ `shift` $ < 1 $）
+    fn simulate_shift(&self, segment_length: Tau, shift: f64, rng: &mut StdRng) -> Result<Self::Cost, CalcDpError> {
+        if !(shift > -1.0 && shift < 1.0) {
+            return Err(CalcDpError::new(format!("Correlation must be in (-1, 1), but {shift} is given.")));
+        }
+        let len = 2 * segment_length as usize;
+        let mut x = Vec::with_capacity(len);
+        let mut y = Vec::with_capacity(len);
+        for t in 0..len {
+            let rho = if t < segment_length as usize { 0.0 } else { shift };
+            let u: f64 = StandardNormal.sample(rng);
+            let v: f64 = StandardNormal.sample(rng);
+            x.push(u);
+            y.push(rho * u + (1.0 - rho * rho).sqrt() * v);
+        }
+        CorrelationCost::new(&x, &y)
+    }
+}
+
+
+/// 検出力曲線
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerCurve {
+    /// 各区間の長さ
+    pub segment_length: Tau,
+    /// 誤警報確率を有意水準とする閾値
+    pub threshold: f64,
+    /// 変化量と検出力の組（変化量の指定順）
+    pub points: Vec<(f64, f64)>,
+}
+
+impl PowerCurve {
+    /// 検出力が`target`以上となる最小の変化量（検出可能な最小の変化量）
+    ///
+    /// 該当する変化量がない場合は`None`を返す．
+    ///
+    /// # 引数
+    /// * `target` - 目標とする検出力（例: 0.8）
+    pub fn minimum_detectable_shift(&self, target: f64) -> Option<f64> {
+        self.points.iter()
+                   .filter(|(_, power)| *power >= target)
+                   .map(|(shift, _)| shift.abs())
+                   .min_by(|a, b| a.total_cmp(b))
+    }
+}
+
+
+/// 変化量ごとの検出力を見積もる
+///
+/// 長さ`2 * segment_length`の変化を含まない系列から有意水準`alpha`の閾値を較正した上で，
+/// 中央で各変化量だけ変化する系列を`n_sims`回ずつ生成し，
+/// 変化点1個の検定統計量（[`single_change_statistic`]）が閾値を超える割合を検出力とする．
+///
+/// # 引数
+/// * `model` - 系列の生成モデル
+/// * `segment_length` - 変化の前後の区間の長さ
+/// * `shift_grid` - 変化量
+/// * `alpha` - 有意水準（0より大きく1未満）
+/// * `n_sims` - 試行回数
+/// * `rng` - 乱数の設定（`u64`のseedも与えられる）
+pub fn power_analysis<M>(model: &M, segment_length: Tau, shift_grid: &[f64], alpha: f64, n_sims: usize, rng: impl Into<RngConfig>) -> Result<PowerCurve, CalcDpError>
+where
+    M: ShiftModel + Sync,
+{
+    if segment_length == 0 {
+        return Err(CalcDpError::new("Segment length must be at least 1."));
+    }
+    let rng = rng.into();
+    let threshold = calibrate_threshold(model, 2 * segment_length, alpha, n_sims, rng)?;
+
+    let points = shift_grid.iter()
+                           .enumerate()
+                           .map(|(g, shift)| {
+                               let detected = (0..n_sims).into_par_iter()
+                                                         .map(|i| {
+                                                             let idx = ((g + 1) * n_sims + i) as u64;
+                                                             let mut rng = rng.trial(idx);
+                                                             let cost = model.simulate_shift(segment_length, *shift, &mut rng)?;
+                                                             Ok(single_change_statistic(&cost)? > threshold)
+                                                         })
+                                                         .collect::<Result<Vec<bool>, CalcDpError>>()?;
+                               let power = detected.iter().filter(|d| **d).count() as f64 / n_sims as f64;
+                               Ok((*shift, power))
+                           })
+                           .collect::<Result<Vec<(f64, f64)>, CalcDpError>>()?;
+
+    Ok(PowerCurve { segment_length, threshold, points })
+}
@@ -0,0 +1,135 @@
+//! 区間ごとの平均の同時信頼区間
+
+use crate::calibrate::moving_block_resample;
+use crate::cost::HeteroscedasticMeanCost;
+use crate::detect::{self, Method, Penalty, Constraints};
+use crate::dp_tools::CalcDpError;
+use crate::rng::RngConfig;
+use crate::segment;
+
+extern crate process_param;
+use process_param::{Tau, NumChg};
+
+extern crate rayon;
+use rayon::prelude::*;
+
+
+/// ブートストラップの設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootstrapOptions {
+    /// ブートストラップの反復回数
+    pub n_boot: usize,
+    /// 残差を再抽出するブロックの長さ．1の場合は独立な再抽出となる．
+    /// 残差が自己相関を持つ場合は[`crate::calibrate::default_block_len`]等を目安に2以上とする．
+    pub block_len: usize,
+    /// 乱数の設定
+    pub rng: RngConfig,
+}
+
+impl Default for BootstrapOptions {
+    fn default() -> Self {
+        BootstrapOptions {
+            n_boot: 999,
+            block_len: 1,
+            rng: RngConfig::default(),
+        }
+    }
+}
+
+
+/// 区間の平均の信頼区間
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentCi {
+    /// 前の変化点$ t_{k-1} $
+    pub start: Tau,
+    /// 後ろの変化点$ t_k $
+    pub end: Tau,
+    /// 平均の推定値
+    pub mean: f64,
+    /// 信頼区間の下限
+    pub lower: f64,
+    /// 信頼区間の上限
+    pub upper: f64,
+}
+
+
+/// 区間ごとの平均の同時信頼区間を求める
+///
+/// 推定した区間ごとの平均に残差を再抽出して加えた系列を生成し，変化点を同じ個数だけ再推定した上で区間ごとの平均を求める．
+/// 変化点の推定の不確かさを含めた各区間の平均の標準誤差$ s_j $と，標準化した偏差の最大値
+/// $ \max_j |\bar{y}^*_j - \bar{y}_j| / s_j $の$ 1 - \alpha $分位点$ c $から，
+/// 全ての区間について同時に確率$ 1 - \alpha $で成り立つ信頼区間$ \bar{y}_j \pm c s_j $を求める．
+///
+/// # 引数
+/// * `data` - 系列
+/// * `change_points` - 変化点（昇順）
+/// * `alpha` - 有意水準（0より大きく1未満）
+/// * `options` - ブートストラップの設定
+pub fn segment_mean_cis(data: &[f64], change_points: &[Tau], alpha: f64, options: &BootstrapOptions) -> Result<Vec<SegmentCi>, CalcDpError> {
+    let n = data.len();
+    if !(alpha > 0.0 && alpha < 1.0) {
+        return Err(CalcDpError::new(format!("alpha must be in (0, 1), but {alpha} is given.")));
+    }
+    if options.n_boot < 2 || options.block_len == 0 || options.block_len > n {
+        return Err(CalcDpError::new(format!("Invalid bootstrap options: {options:?} (series length {n}).")));
+    }
+    let bounds = segment::segment_bounds(change_points, n)?;
+
+    let means = segment_means(data, &bounds);
+    let mut fitted = Vec::with_capacity(n);
+    for (j, w) in bounds.windows(2).enumerate() {
+        fitted.extend(std::iter::repeat_n(means[j], w[1] - w[0]));
+    }
+    let residuals = data.iter().zip(fitted.iter()).map(|(y, f)| y - f).collect::<Vec<f64>>();
+
+    let k = change_points.len() as NumChg;
+    let boot_means = (0..options.n_boot).into_par_iter()
+                                        .map(|i| {
+                                            let mut rng = options.rng.trial(i as u64);
+                                            let resampled = moving_block_resample(&residuals, n, options.block_len, &mut rng)?;
+                                            let y = fitted.iter().zip(resampled.iter()).map(|(f, e)| f + e).collect::<Vec<f64>>();
+                                            let cost = HeteroscedasticMeanCost::new(&y, &vec![1.0; n])?;
+                                            let res = detect::detect(&cost, Method::Dp, &Penalty::NumChange(k), &Constraints::default())?;
+                                            let b = std::iter::once(0)
+                                                        .chain(res.change_points.iter().map(|t| *t as usize))
+                                                        .chain(std::iter::once(n))
+                                                        .collect::<Vec<usize>>();
+                                            Ok(segment_means(&y, &b))
+                                        })
+                                        .collect::<Result<Vec<Vec<f64>>, CalcDpError>>()?;
+
+    // 区間ごとの標準誤差
+    let n_boot = boot_means.len() as f64;
+    let se = (0..means.len()).map(|j| {
+                                 let m = boot_means.iter().map(|b| b[j]).sum::<f64>() / n_boot;
+                                 (boot_means.iter().map(|b| (b[j] - m).powi(2)).sum::<f64>() / (n_boot - 1.0)).sqrt()
+                             })
+                             .collect::<Vec<f64>>();
+
+    // 標準化した偏差の最大値の分位点
+    let max_dev = boot_means.iter()
+                            .map(|b| (0..means.len()).filter(|j| se[*j] > 0.0)
+                                                     .map(|j| (b[j] - means[j]).abs() / se[j])
+                                                     .fold(0.0, f64::max))
+                            .collect::<Vec<f64>>();
+    let c = crate::calibrate::upper_quantile(max_dev, alpha);
+
+    Ok(bounds.windows(2)
+             .enumerate()
+             .map(|(j, w)| SegmentCi {
+                 start: w[0] as Tau,
+                 end: w[1] as Tau,
+                 mean: means[j],
+                 lower: means[j] - c * se[j],
+                 upper: means[j] + c * se[j],
+             })
+             .collect())
+}
+
+
+/// 区間ごとの標本平均
+fn segment_means(data: &[f64], bounds: &[usize]) -> Vec<f64> {
+    bounds.windows(2)
+          .map(|w| data[w[0]..w[1]].iter().sum::<f64>() / (w[1] - w[0]) as f64)
+          .collect()
+}
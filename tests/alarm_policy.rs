@@ -0,0 +1,100 @@
+//! 警報の出し方（[`AlarmPolicy`]）の確認
+
+use cpd_tools::online::{AlarmPolicy, OnlineDetector, PolicedDetector, Step};
+
+
+/// 観測値をそのまま検出統計量とし，1を超えた場合に閾値を超えたとする検出器
+#[derive(Debug, Clone, Default)]
+struct Echo {
+    resets: usize,
+}
+
+impl OnlineDetector for Echo {
+    fn update(&mut self, x: f64) -> Step {
+        Step { statistic: x, exceeded: x > 1.0, change_point: None }
+    }
+
+    fn reset(&mut self) {
+        self.resets += 1;
+    }
+}
+
+
+/// 観測を順に与え，警報を出した観測の番号（0始まり）を返す
+fn alarms(policy: AlarmPolicy, xs: &[f64]) -> Vec<usize> {
+    let mut detector = PolicedDetector::new(Echo::default(), policy).unwrap();
+    xs.iter()
+      .enumerate()
+      .filter(|(_, x)| detector.update(**x).exceeded)
+      .map(|(i, _)| i)
+      .collect()
+}
+
+
+#[test]
+fn default_policy_passes_through() {
+    let xs = [0.0, 2.0, 2.0, 0.5, 3.0];
+    assert_eq!(alarms(AlarmPolicy::default(), &xs), vec![1, 2, 4]);
+
+    // 検出統計量は元の検出器の値をそのまま返す
+    let mut detector = PolicedDetector::new(Echo::default(), AlarmPolicy { consecutive: 2, ..AlarmPolicy::default() }).unwrap();
+    let step = detector.update(2.0);
+    assert_eq!(step.statistic, 2.0);
+    assert!(!step.exceeded);
+}
+
+
+#[test]
+fn consecutive_exceedances_are_required() {
+    let policy = AlarmPolicy { consecutive: 3, ..AlarmPolicy::default() };
+    // 途中で閾値を下回ると数え直す
+    let xs = [2.0, 2.0, 0.0, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0];
+    assert_eq!(alarms(policy, &xs), vec![5, 8]);
+    assert!(PolicedDetector::new(Echo::default(), AlarmPolicy { consecutive: 0, ..AlarmPolicy::default() }).is_err());
+}
+
+
+#[test]
+fn rearm_interval_suppresses_alarms() {
+    let policy = AlarmPolicy { rearm_interval: 2, ..AlarmPolicy::default() };
+    let xs = [2.0; 7];
+    assert_eq!(alarms(policy, &xs), vec![0, 3, 6]);
+}
+
+
+#[test]
+fn release_level_adds_hysteresis() {
+    let policy = AlarmPolicy { release_level: Some(0.5), ..AlarmPolicy::default() };
+    // 警報の後は統計量が0.5を下回るまで警報を出さない
+    let xs = [2.0, 2.0, 0.8, 2.0, 0.2, 2.0];
+    assert_eq!(alarms(policy, &xs), vec![0, 5]);
+}
+
+
+#[test]
+fn rearm_keeps_policy_state() {
+    let policy = AlarmPolicy { rearm_interval: 2, ..AlarmPolicy::default() };
+    let mut detector = PolicedDetector::new(Echo::default(), policy).unwrap();
+    assert!(detector.update(2.0).exceeded);
+    // 警報の後の再開では，直前の警報からの観測数を保持する
+    detector.rearm();
+    assert_eq!(detector.detector().resets, 1);
+    assert!(!detector.update(2.0).exceeded);
+    assert!(!detector.update(2.0).exceeded);
+    assert!(detector.update(2.0).exceeded);
+
+    // 初期化では履歴も消去する
+    assert!(!detector.update(2.0).exceeded);
+    detector.reset();
+    assert!(detector.update(2.0).exceeded);
+    assert_eq!(detector.into_inner().resets, 2);
+}
+
+
+#[test]
+fn policy_from_partial_json() {
+    let policy: AlarmPolicy = serde_json::from_str(r#"{"consecutive": 2}"#).unwrap();
+    assert_eq!(policy, AlarmPolicy { consecutive: 2, ..AlarmPolicy::default() });
+    let back: AlarmPolicy = serde_json::from_str(&serde_json::to_string(&policy).unwrap()).unwrap();
+    assert_eq!(back, policy);
+}
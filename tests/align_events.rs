@@ -0,0 +1,30 @@
+//! 変化点と事象記録の照合の確認
+
+use cpd_tools::segment::{align_events, Event};
+
+use process_param::Tau;
+
+
+fn event(time: Tau, label: &str) -> Event {
+    Event { time, label: label.to_owned() }
+}
+
+
+#[test]
+fn changes_match_nearest_event() {
+    let events = vec![event(52, "maintenance"), event(10, "recipe B"), event(14, "recipe C"), event(90, "audit")];
+    let res = align_events(&[12, 50, 75], &events, 3).unwrap();
+
+    assert_eq!(res.matched.len(), 2);
+    // 10と14は等距離であり，先に記録された事象を選ぶ
+    assert_eq!((res.matched[0].change_point, res.matched[0].event.label.as_str(), res.matched[0].lag), (12, "recipe B", 2));
+    assert_eq!((res.matched[1].change_point, res.matched[1].event.label.as_str(), res.matched[1].lag), (50, "maintenance", -2));
+    assert_eq!(res.unmatched_changes, vec![75]);
+    assert_eq!(res.unmatched_events.iter().map(|e| e.label.as_str()).collect::<Vec<_>>(), vec!["recipe C", "audit"]);
+    assert_eq!(res.matched_fraction(), Some(2.0 / 3.0));
+
+    let none = align_events(&[], &events, 3).unwrap();
+    assert_eq!(none.matched_fraction(), None);
+    assert_eq!(none.unmatched_events.len(), 4);
+    assert!(align_events(&[5, 5], &events, 3).is_err());
+}
@@ -0,0 +1,68 @@
+//! 平均連長（ARL）の推定（[`estimate_arl`]）の確認
+
+use cpd_tools::online::{estimate_arl, AlarmPolicy, GaussianShift, OnlineDetector, PolicedDetector, Step, StreamDetector};
+
+
+/// 観測値が0以上であれば警報を出す検出器
+#[derive(Debug, Clone)]
+struct NonNegative;
+
+impl OnlineDetector for NonNegative {
+    fn update(&mut self, x: f64) -> Step {
+        Step { statistic: x, exceeded: x >= 0.0, change_point: None }
+    }
+
+    fn reset(&mut self) {}
+}
+
+
+#[test]
+fn geometric_run_length() {
+    // 管理状態では確率1/2，変化後はほぼ確率1で警報を出すため，ARLはそれぞれ2と1
+    let model = GaussianShift::new(0.0, 1.0, 20.0).unwrap();
+    let arl = estimate_arl(&NonNegative, &model, 4_000, 7).unwrap();
+    assert!((arl.in_control.mean - 2.0).abs() < 4.0 * arl.in_control.std_error, "{arl:?}");
+    assert_eq!(arl.out_of_control.mean, 1.0);
+    assert_eq!(arl.out_of_control.std_error, 0.0);
+    assert_eq!(arl.in_control.censored, 0);
+}
+
+
+#[test]
+fn cusum_matches_tabulated_arl() {
+    // 両側CUSUM（k = 0.5，h = 4）のARL0は約168，1標準偏差の変化に対するARL1は約8.4
+    let detector = StreamDetector::new(0.0, 1.0, 0.5, 4.0).unwrap();
+    let model = GaussianShift::new(0.0, 1.0, 1.0).unwrap();
+    let arl = estimate_arl(&detector, &model, 2_000, 3).unwrap();
+    assert!((arl.in_control.mean - 168.0).abs() < 25.0, "{arl:?}");
+    assert!((arl.out_of_control.mean - 8.4).abs() < 1.0, "{arl:?}");
+
+    // 連続して閾値を超えることを求めると検出は遅れる
+    let policed = PolicedDetector::new(detector.clone(), AlarmPolicy { consecutive: 3, ..AlarmPolicy::default() }).unwrap();
+    let delayed = estimate_arl(&policed, &model, 2_000, 3).unwrap();
+    assert!(delayed.out_of_control.mean > arl.out_of_control.mean + 1.5, "{delayed:?}");
+}
+
+
+#[test]
+fn same_seed_reproduces_estimate() {
+    let detector = StreamDetector::new(5.0, 2.0, 0.5, 3.0).unwrap();
+    let model = GaussianShift::new(5.0, 2.0, 2.0).unwrap();
+    let first = estimate_arl(&detector, &model, 200, 42).unwrap();
+    assert_eq!(first, estimate_arl(&detector, &model, 200, 42).unwrap());
+    assert_ne!(first, estimate_arl(&detector, &model, 200, 43).unwrap());
+    // 検出器の状態は試行ごとに初期化される
+    let mut used = detector.clone();
+    used.update(100.0);
+    assert_eq!(estimate_arl(&used, &model, 50, 1).unwrap(), estimate_arl(&detector, &model, 50, 1).unwrap());
+}
+
+
+#[test]
+fn rejects_invalid_input() {
+    let model = GaussianShift::new(0.0, 1.0, 1.0).unwrap();
+    assert!(estimate_arl(&NonNegative, &model, 1, 0).is_err());
+    assert!(GaussianShift::new(0.0, 0.0, 1.0).is_err());
+    assert!(GaussianShift::new(f64::NAN, 1.0, 1.0).is_err());
+    assert!(GaussianShift::new(0.0, 1.0, f64::INFINITY).is_err());
+}
@@ -0,0 +1,54 @@
+//! 多変量の系列における変化のチャネルへの帰属の確認
+
+use cpd_tools::cost::MultivariateNormalCost;
+use cpd_tools::detect::{self, Constraints, Method, Penalty};
+use cpd_tools::segment::attribute_channels;
+
+use ndarray::Array2;
+
+
+/// 4チャネルの系列．20時点目でチャネル2の平均が，40時点目でチャネル0の分散が変化する．
+fn series() -> Array2<f64> {
+    Array2::from_shape_fn((60, 4), |(t, j)| {
+        let noise = (((t * 31 + j * 17) as f64 * 12.9898).sin() * 43758.5453).fract() - 0.5;
+        match j {
+            2 if t >= 20 => 3.0 + noise,
+            0 if t >= 40 => 8.0 * noise,
+            _ => noise,
+        }
+    })
+}
+
+
+#[test]
+fn identifies_moved_channel() {
+    let data = series();
+    let cost = MultivariateNormalCost::new(&data).unwrap();
+    let constraints = Constraints::default().with_min_size(5);
+    let res = detect::detect(&cost, Method::Dp, &Penalty::NumChange(2), &constraints).unwrap();
+    assert_eq!(res.change_points, vec![20, 40]);
+
+    let attributions = attribute_channels(&data, &res.change_points).unwrap();
+    assert_eq!(attributions.len(), 2);
+    assert_eq!(attributions[0].change_point, 20);
+    assert_eq!(attributions[0].ranked()[0], 2);
+    assert!(attributions[0].shares[2] > 0.8, "{:?}", attributions[0].shares);
+    assert!((attributions[0].mean_shifts[2] - 3.0).abs() < 0.5);
+    assert_eq!(attributions[1].ranked()[0], 0);
+    for a in &attributions {
+        assert!(a.deltas.iter().all(|d| *d >= 0.0));
+        assert!((a.shares.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+}
+
+
+#[test]
+fn constant_channel_and_invalid_change_points() {
+    let data = Array2::from_shape_fn((10, 2), |(t, j)| if j == 0 { 1.0 } else if t < 5 { 0.0 } else { 1.0 + 0.1 * t as f64 });
+    let attributions = attribute_channels(&data, &[5]).unwrap();
+    assert_eq!(attributions[0].deltas[0], 0.0);
+    assert_eq!(attributions[0].ranked(), vec![1, 0]);
+    assert!(attribute_channels(&data, &[]).unwrap().is_empty());
+    assert!(attribute_channels(&data, &[5, 5]).is_err());
+    assert!(attribute_channels(&data, &[10]).is_err());
+}
@@ -0,0 +1,122 @@
+//! 探索手法の自動選択（[`Method::Auto`]）の確認
+
+mod common;
+use common::mean_cost;
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{choose_algorithm, detect, detect_auto, Algorithm, Constraints, DetectionResult, Method, Penalty};
+use cpd_tools::dp_tools::CalcDpError;
+
+use process_param::Tau;
+
+
+/// 評価値の計算量を明示しない（[`SegmentCost::constant_time`]が`false`の）コスト関数
+struct Opaque(HeteroscedasticMeanCost);
+
+impl SegmentCost for Opaque {
+    fn t_max(&self) -> Tau {
+        self.0.t_max()
+    }
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.0.segment_value(t_k_1, t_k)
+    }
+}
+
+
+/// 時点`n / 2`で平均が変化する長さ`n`の系列
+fn step(n: usize) -> HeteroscedasticMeanCost {
+    mean_cost(n, 0.25, |i| if i < n / 2 { 0.0 } else { 2.0 } + 0.3 * (i as f64 * 1.3).sin())
+}
+
+
+#[test]
+fn size_limits_choose_dp() {
+    let cost = step(40);
+    let constraints = Constraints::default().with_min_size(5);
+    let (algorithm, reason) = choose_algorithm(&cost, &Penalty::Linear(3.0), &constraints);
+    assert_eq!(algorithm, Algorithm::Dp);
+    assert!(reason.contains("min_size"), "{reason}");
+
+    let auto = detect_auto(&cost, &Penalty::Linear(3.0), &constraints).unwrap();
+    assert_eq!(auto.algorithm, Algorithm::Dp);
+    assert!(auto.exact);
+    assert_eq!(auto.result.change_points, vec![20]);
+}
+
+
+#[test]
+fn unbounded_penalty_chooses_pelt() {
+    // 評価値をO(1)で計算できる場合は系列が長くてもPELT
+    let long = step(5_000);
+    assert_eq!(choose_algorithm(&long, &Penalty::Linear(3.0), &Constraints::default()).0, Algorithm::Pelt);
+    // 評価値の計算量によらず，系列が短ければPELT
+    let short = Opaque(step(40));
+    assert_eq!(choose_algorithm(&short, &Penalty::Linear(3.0), &Constraints::default()).0, Algorithm::Pelt);
+
+    let auto = detect_auto(&short, &Penalty::Linear(3.0), &Constraints::default()).unwrap();
+    assert_eq!(auto.algorithm, Algorithm::Pelt);
+    assert!(auto.exact);
+    assert_eq!(auto.result.change_points, vec![20]);
+    // 変化点個数の上限を指定した場合はPELTを用いない
+    let bounded = Constraints::default().with_max_k(3);
+    assert_eq!(choose_algorithm(&short, &Penalty::Linear(3.0), &bounded).0, Algorithm::Dp);
+}
+
+
+#[test]
+fn affordable_search_chooses_dp() {
+    let cost = Opaque(step(60));
+    let (algorithm, reason) = choose_algorithm(&cost, &Penalty::NumChange(2), &Constraints::default());
+    assert_eq!(algorithm, Algorithm::Dp);
+    assert!(reason.contains("affordable"), "{reason}");
+
+    let auto = detect_auto(&cost, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(auto.algorithm, Algorithm::Dp);
+    let dp = detect(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(auto.result.change_points, dp.change_points);
+    assert_eq!(auto.result.value, dp.value);
+}
+
+
+#[test]
+fn expensive_search_chooses_binseg() {
+    // 評価値の計算量が区間の長さに依存し，系列が長い
+    let long = Opaque(step(3_000));
+    let (algorithm, reason) = choose_algorithm(&long, &Penalty::NumChange(1), &Constraints::default());
+    assert_eq!(algorithm, Algorithm::BinsegRefine);
+    assert!(reason.contains("too expensive"), "{reason}");
+    assert_eq!(choose_algorithm(&long, &Penalty::Linear(3.0), &Constraints::default()).0, Algorithm::BinsegRefine);
+
+    // 評価値をO(1)で計算できても，評価値計算の回数が上限を超える
+    let huge = step(20_000);
+    assert_eq!(choose_algorithm(&huge, &Penalty::NumChange(1), &Constraints::default()).0, Algorithm::BinsegRefine);
+
+    let auto = detect_auto(&long, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(auto.algorithm, Algorithm::BinsegRefine);
+    assert!(!auto.exact);
+    assert_eq!(auto.result.change_points, vec![1_500]);
+}
+
+
+#[test]
+fn detect_keeps_selection() {
+    let cost = Opaque(step(40));
+    let penalty = Penalty::Linear(3.0);
+    let result = detect(&cost, Method::Auto, &penalty, &Constraints::default()).unwrap();
+    let selection = result.selection.clone().unwrap();
+    let auto = detect_auto(&cost, &penalty, &Constraints::default()).unwrap();
+    assert_eq!(selection.algorithm, auto.algorithm);
+    assert_eq!(selection.reason, auto.reason);
+    assert_eq!(selection.exact, auto.exact);
+    assert_eq!(result, auto.result);
+
+    // 手法を指定した場合は選択の情報を持たない
+    assert!(detect(&cost, Method::Dp, &penalty, &Constraints::default()).unwrap().selection.is_none());
+
+    // 書き出しと読み込みで選択の情報が保たれ，持たない場合は書き出さない
+    let back: DetectionResult = serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+    assert_eq!(back, result);
+    let plain = DetectionResult::new(vec![20], 0.0, 40);
+    assert!(!serde_json::to_string(&plain).unwrap().contains("selection"));
+}
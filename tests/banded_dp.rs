@@ -0,0 +1,105 @@
+//! 区間の長さを制限した動的計画法の確認
+//!
+//! 短い系列に対して全ての変化点の組合せを調べ，制約を満たす最適解と一致することを確認する．
+
+mod common;
+use common::mean_cost;
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{self, Constraints, FitResult, Method, Penalty};
+
+use process_param::{Tau, NumChg};
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    mean_cost(14, 0.5, |i| if i < 4 { (i as f64 * 0.7).sin() } else if i < 9 { 2.0 + (i as f64 * 1.3).cos() } else { -1.0 + 0.1 * i as f64 })
+}
+
+
+/// 変化点個数ごとの制約を満たす評価値の最大値を全探索で求める
+fn brute_force(cost: &HeteroscedasticMeanCost, min_gap: Tau, min_size: Tau, max_size: Tau) -> Vec<f64> {
+    let t_max = cost.t_max();
+    let mut best = vec![f64::NEG_INFINITY; t_max as usize];
+    for mask in 0u32..(1 << (t_max - 1)) {
+        let cps = (1..t_max).filter(|t| mask & (1 << (t - 1)) != 0).collect::<Vec<Tau>>();
+        let bounds = std::iter::once(0).chain(cps.iter().copied()).chain(std::iter::once(t_max)).collect::<Vec<Tau>>();
+        let feasible = bounds.windows(2).enumerate().all(|(j, w)| {
+            let len = w[1] - w[0];
+            (j == 0 || len >= min_gap) && min_size <= len && len <= max_size
+        });
+        if !feasible {
+            continue;
+        }
+        let val = bounds.windows(2).map(|w| cost.segment_value(w[0], w[1]).unwrap()).sum::<f64>();
+        let k = cps.len();
+        best[k] = best[k].max(val);
+    }
+    best
+}
+
+
+fn check(method: Method, min_gap: Tau, min_size: Option<Tau>, max_size: Option<Tau>) {
+    let data = cost();
+    let t_max = data.t_max();
+    let mut constraints = Constraints::default();
+    constraints.min_size = min_size;
+    constraints.max_size = max_size;
+    let fit = FitResult::fit_constrained(&data, method, None, &constraints).unwrap();
+    let expected = brute_force(&data, min_gap, min_size.unwrap_or(1), max_size.unwrap_or(t_max));
+    for k in 0..=fit.k_max() {
+        let value = fit.value(k).unwrap();
+        let want = expected[k as usize];
+        if want == f64::NEG_INFINITY {
+            assert_eq!(value, f64::NEG_INFINITY, "k = {k}");
+            assert!(fit.result(k).is_err());
+            continue;
+        }
+        assert!((value - want).abs() < 1e-9, "k = {k}: {value} vs {want}");
+        let res = fit.result(k).unwrap();
+        assert_eq!(res.num_change(), k);
+        for (t_k_1, t_k) in res.segments() {
+            let len = t_k - t_k_1;
+            assert!(min_size.is_none_or(|m| len >= m) && max_size.is_none_or(|m| len <= m), "k = {k}: {:?}", res.segments());
+        }
+    }
+}
+
+
+#[test]
+fn banded_dp_matches_brute_force() {
+    check(Method::Dp, 1, Some(2), Some(5));
+    check(Method::Dp, 1, None, Some(4));
+    check(Method::Dp, 1, Some(3), None);
+    check(Method::Dp2, 2, Some(1), Some(6));
+    check(Method::Dp2, 2, Some(3), Some(3));
+}
+
+
+#[test]
+fn unconstrained_band_matches_plain_dp() {
+    let data = cost();
+    let t_max = data.t_max();
+    let plain = FitResult::fit(&data, Method::Dp, None).unwrap();
+    let constraints = Constraints::default().with_min_size(1).with_max_size(t_max);
+    let banded = FitResult::fit_constrained(&data, Method::Dp, None, &constraints).unwrap();
+    for k in 0..=plain.k_max() {
+        assert_eq!(plain.value(k).unwrap().to_bits(), banded.value(k).unwrap().to_bits());
+        assert_eq!(plain.change_points(k).unwrap(), banded.change_points(k).unwrap());
+    }
+}
+
+
+#[test]
+fn detect_respects_segment_length() {
+    let data = cost();
+    let constraints = Constraints::default().with_min_size(3).with_max_size(5);
+    for method in [Method::Dp, Method::Dp2, Method::Auto] {
+        let res = detect::detect(&data, method, &Penalty::Linear(1.0), &constraints).unwrap();
+        assert!(res.segments().iter().all(|(a, b)| (3..=5).contains(&(b - a))), "{method:?}: {:?}", res.segments());
+    }
+    // 14点を長さ3以上5以下の区間に分けるには2個以上4個以下の変化点が必要
+    let infeasible: NumChg = 1;
+    assert!(detect::detect(&data, Method::Dp, &Penalty::NumChange(infeasible), &constraints).is_err());
+    let invalid = Constraints::default().with_min_size(4).with_max_size(3);
+    assert!(detect::detect(&data, Method::Dp, &Penalty::NumChange(2), &invalid).is_err());
+}
@@ -0,0 +1,87 @@
+//! 区間の評価値をまとめて計算する経路の確認
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{FitResult, Method};
+use cpd_tools::dp_tools::CalcDpError;
+
+use process_param::Tau;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+
+/// まとめて計算した区間の数を数えるコスト関数
+struct Counting {
+    inner: HeteroscedasticMeanCost,
+    batched: AtomicUsize,
+}
+
+impl SegmentCost for Counting {
+    fn t_max(&self) -> Tau {
+        self.inner.t_max()
+    }
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.inner.segment_value(t_k_1, t_k)
+    }
+
+    fn segment_values(&self, pairs: &[(Tau, Tau)]) -> Result<Vec<f64>, CalcDpError> {
+        self.batched.fetch_add(pairs.len(), Ordering::Relaxed);
+        self.inner.segment_values(pairs)
+    }
+}
+
+
+fn data() -> Vec<f64> {
+    (0..600).map(|i| (i as f64 * 0.37).sin() + if (200..450).contains(&i) { 1.5 } else { 0.0 })
+            .collect()
+}
+
+
+#[test]
+fn dp_uses_batch_evaluation() {
+    let data = data();
+    let plain = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    let counting = Counting {
+        inner: HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap(),
+        batched: AtomicUsize::new(0),
+    };
+
+    for method in [Method::Dp, Method::Dp2] {
+        counting.batched.store(0, Ordering::Relaxed);
+        let expected = FitResult::fit(&plain, method, Some(3)).unwrap();
+        let fit = FitResult::fit(&counting, method, Some(3)).unwrap();
+        assert!(counting.batched.load(Ordering::Relaxed) > 0);
+        for k in 0..=3 {
+            assert_eq!(fit.value(k).unwrap().to_bits(), expected.value(k).unwrap().to_bits());
+            assert_eq!(fit.result(k).unwrap().change_points, expected.result(k).unwrap().change_points);
+        }
+    }
+}
+
+
+/// 返す評価値の個数が区間の数と一致しないコスト関数
+struct Short(HeteroscedasticMeanCost);
+
+impl SegmentCost for Short {
+    fn t_max(&self) -> Tau {
+        self.0.t_max()
+    }
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.0.segment_value(t_k_1, t_k)
+    }
+
+    fn segment_values(&self, pairs: &[(Tau, Tau)]) -> Result<Vec<f64>, CalcDpError> {
+        let mut vals = self.0.segment_values(pairs)?;
+        vals.pop();
+        Ok(vals)
+    }
+}
+
+
+#[test]
+fn mismatched_batch_length_is_an_error() {
+    let data = data();
+    let cost = Short(HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap());
+    assert!(FitResult::fit(&cost, Method::Dp, Some(2)).is_err());
+}
@@ -0,0 +1,71 @@
+//! 系列の端の扱いの確認
+
+mod common;
+use common::mean_cost;
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{self, Boundaries, Boundary, Constraints, FitResult, Method, Penalty, WithContext};
+
+
+fn cost(data: &[f64]) -> HeteroscedasticMeanCost {
+    mean_cost(data.len(), 0.25, |i| data[i])
+}
+
+
+/// 長さ3の短い区間から始まる系列
+fn short_head() -> Vec<f64> {
+    (0..40).map(|i| if i < 3 { 4.0 } else if i < 22 { 0.1 * (i as f64).sin() } else { 2.0 }).collect()
+}
+
+
+#[test]
+fn open_start_exempts_first_segment() {
+    let data = cost(&short_head());
+    let closed = Constraints::default().with_min_size(8);
+    let open = closed.with_boundary(Boundaries { start: Boundary::Open, ..Default::default() });
+
+    let res_closed = detect::detect(&data, Method::Dp, &Penalty::NumChange(2), &closed).unwrap();
+    assert!(res_closed.segments().iter().all(|(a, b)| b - a >= 8), "{:?}", res_closed.segments());
+
+    let res_open = detect::detect(&data, Method::Dp, &Penalty::NumChange(2), &open).unwrap();
+    assert_eq!(res_open.change_points, vec![3, 22]);
+    assert!(res_open.segments()[1..].iter().all(|(a, b)| b - a >= 8));
+    assert!(res_open.value > res_closed.value);
+}
+
+
+#[test]
+fn open_end_exempts_last_segment() {
+    let mut values = short_head();
+    values.reverse();
+    let data = cost(&values);
+    let open = Constraints::default().with_min_size(8)
+                                     .with_boundary(Boundaries { end: Boundary::Open, ..Default::default() });
+    let res = detect::detect(&data, Method::Dp2, &Penalty::NumChange(2), &open).unwrap();
+    assert_eq!(res.change_points, vec![18, 37]);
+}
+
+
+#[test]
+fn context_enters_edge_segments() {
+    // 観測範囲の前後に5個ずつのデータ
+    let full = (0..40).map(|i| if i < 15 { 0.0 } else { 3.0 } + 0.05 * (i as f64 * 2.1).cos()).collect::<Vec<f64>>();
+    let base = cost(&full);
+    let boundaries = Boundaries { start: Boundary::Context(5), end: Boundary::Context(5) };
+    let observed = WithContext::new(&base, &boundaries).unwrap();
+    assert_eq!(observed.t_max(), 30);
+    assert_eq!(observed.segment_value(0, 10).unwrap(), base.segment_value(0, 15).unwrap());
+    assert_eq!(observed.segment_value(10, 30).unwrap(), base.segment_value(15, 40).unwrap());
+    assert_eq!(observed.segment_value(4, 12).unwrap(), base.segment_value(9, 17).unwrap());
+
+    let constraints = Constraints::default().with_boundary(boundaries);
+    let res = detect::detect(&base, Method::Dp, &Penalty::NumChange(1), &constraints).unwrap();
+    assert_eq!(res.change_points, vec![10]);
+    assert_eq!(res.t_max, 30);
+    let direct = detect::detect(&observed, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(res, direct);
+
+    // 観測範囲外のデータはWithContextを介さずに与えられない
+    assert!(FitResult::fit_constrained(&base, Method::Dp, None, &constraints).is_err());
+    assert!(WithContext::new(&base, &Boundaries { start: Boundary::Context(20), end: Boundary::Context(20) }).is_err());
+}
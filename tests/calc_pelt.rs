@@ -0,0 +1,187 @@
+//! PELTによる枝刈りを用いたペナルティ付きの計算（`calc_pelt`）の確認
+
+mod common;
+use common::{mean_cost, Fit, Memo, PenalizedMemo};
+
+use cpd_tools::cost::{BoxedCost, Composite, CorrelationCost, HeteroscedasticMeanCost, SegmentCost, ZipCost};
+use cpd_tools::detect::ChangeCost;
+use cpd_tools::dp_tools::{calc_dp, calc_dp_penalized, calc_pelt::CalcPelt, CalcDpError};
+use cpd_tools::search;
+
+use process_param::Tau;
+
+
+type Dp = Fit<Memo>;
+
+
+/// コスト関数`C`に対するPELTの計算結果
+fn fit_pelt<C: SegmentCost>(data: &C, penalty: f64) -> Fit<PenalizedMemo, C> {
+    Fit::new(<Fit<PenalizedMemo, C> as CalcPelt<f64, C>>::calc_memo_all(data, &data.t_max(), &penalty).unwrap())
+}
+
+
+/// 枝刈りを控えめにするため，分割による評価値の変化の下限を小さく見積もったコスト関数
+struct Loose(HeteroscedasticMeanCost);
+
+impl SegmentCost for Loose {
+    fn t_max(&self) -> Tau {
+        self.0.t_max()
+    }
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.0.segment_value(t_k_1, t_k)
+    }
+
+    fn pruning_constant(&self) -> f64 {
+        -10.0
+    }
+}
+
+
+/// 1点のみの区間の評価値を`f64::NEG_INFINITY`とし，枝刈りの下限は有限とするコスト関数
+struct MinTwo(HeteroscedasticMeanCost);
+
+impl SegmentCost for MinTwo {
+    fn t_max(&self) -> Tau {
+        self.0.t_max()
+    }
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        if t_k - t_k_1 < 2 {
+            self.check_segment(t_k_1, t_k)?;
+            return Ok(f64::NEG_INFINITY);
+        }
+        self.0.segment_value(t_k_1, t_k)
+    }
+
+    fn min_identifiable_size(&self) -> Tau {
+        2
+    }
+
+    fn pruning_constant(&self) -> f64 {
+        -10.0
+    }
+}
+
+
+/// PELTと枝刈りを行わない計算の，全ての時点における変化点と評価値の一致を確認する
+fn assert_matches_exhaustive<C: SegmentCost>(data: &C, beta: f64) {
+    let t_max = data.t_max();
+    let exhaustive = Fit::<PenalizedMemo, C>::new(<Fit<PenalizedMemo, C> as calc_dp_penalized::CalcDpPenalized<f64, C>>::calc_memo_all(data, &t_max, &-beta).unwrap());
+    let pruned = fit_pelt(data, -beta);
+    for t in 1..=t_max {
+        let (cps, value) = <Fit<PenalizedMemo, C> as calc_dp_penalized::CalcDpPenalized<f64, C>>::get_change_points_with_value(&exhaustive, &t).unwrap();
+        assert_eq!(pruned.get_change_points(&t).unwrap(), cps, "beta = {beta}, t = {t}");
+        let pelt = pruned.get_value(&t).unwrap();
+        assert!(pelt == value || (pelt - value).abs() < 1e-9, "beta = {beta}, t = {t}: {pelt} != {value}");
+    }
+}
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    mean_cost(60, 0.3, |i| {
+        let level = match i { 0..=19 => 0.0, 20..=34 => 2.0, 35..=49 => -1.0, _ => 1.5 };
+        level + (i as f64 * 2.3).sin() * 0.6
+    })
+}
+
+
+#[test]
+fn matches_penalized_dp() {
+    let data = cost();
+    let t_max = data.t_max();
+    let dp = Dp::new(<Dp as calc_dp::CalcDP<f64, _>>::calc_memo_all(&data, &t_max).unwrap());
+    for beta in [0.5, 5.0, 30.0] {
+        let pelt = fit_pelt(&data, -beta);
+        let (cps, value) = pelt.get_change_points_with_value(&t_max).unwrap();
+
+        // 変化点個数ごとの最適値にペナルティを加えた値の最大値
+        let best = (0..=<Dp as calc_dp::CalcDP<f64, _>>::calc_max_k(&t_max))
+            .map(|k| <Dp as calc_dp::CalcDP<f64, _>>::get_value(&dp, &t_max, &k).unwrap() - beta * k as f64)
+            .fold(f64::NEG_INFINITY, f64::max);
+        assert!((value - best).abs() < 1e-9, "beta = {beta}");
+        assert_eq!(pelt.get_value(&t_max).unwrap(), value);
+        assert_eq!(pelt.0[t_max as usize].1 as usize, cps.len());
+
+        let expected = search::pelt(&data, 1, beta).unwrap().result;
+        assert_eq!(cps, expected.change_points);
+        assert_eq!(pelt.get_change_points(&t_max).unwrap(), cps);
+    }
+}
+
+
+#[test]
+fn smaller_pruning_constant_keeps_result() {
+    let data = cost();
+    let t_max = data.t_max();
+    let tight = fit_pelt(&data, -3.0);
+    let loose = fit_pelt(&Loose(data), -3.0);
+    assert_eq!(tight.get_change_points(&t_max).unwrap(), loose.get_change_points(&t_max).unwrap());
+    assert_eq!(tight.get_change_points(&t_max).unwrap(), vec![20, 35, 50]);
+}
+
+
+#[test]
+fn intermediate_time_and_errors() {
+    let data = cost();
+    let t_max = data.t_max();
+    let pelt = fit_pelt(&data, -3.0);
+    assert_eq!(pelt.get_change_points(&30).unwrap(), vec![20]);
+    assert_eq!(pelt.get_change_points(&0).unwrap(), Vec::<Tau>::new());
+    assert!(pelt.get_value(&(t_max + 1)).is_err());
+
+    let empty = HeteroscedasticMeanCost::new(&[], &[]).unwrap();
+    assert!(<Fit<PenalizedMemo> as CalcPelt<f64, _>>::calc_memo_all(&empty, &0, &-3.0).is_err());
+}
+
+
+#[test]
+fn pruning_constant_comes_from_cost() {
+    let data = cost();
+    assert_eq!(data.pruning_constant(), 0.0);
+    assert_eq!(Loose(cost()).pruning_constant(), -10.0);
+    // 変化のコストは分割により加わるため，その最大値だけ下限が小さくなる
+    assert_eq!(ChangeCost::new(&data, |t| if t == 7 { 4.0 } else { -1.0 }).pruning_constant(), -4.0);
+    let components: Vec<(f64, BoxedCost)> = vec![(2.0, Box::new(Loose(cost()))), (1.0, Box::new(cost()))];
+    assert_eq!(Composite::new(components).unwrap().pruning_constant(), -20.0);
+
+    // 下限を小さく見積もっても探索結果は変わらず，枝刈りが減る
+    let tight = search::pelt(&data, 1, 3.0).unwrap();
+    let loose = search::pelt(&Loose(cost()), 1, 3.0).unwrap();
+    assert_eq!(tight.result.change_points, loose.result.change_points);
+    assert!(tight.stats.pruned > loose.stats.pruned);
+}
+
+
+#[test]
+fn short_segments_do_not_prune_candidates() {
+    // 1点の区間が実行不可能でも，その候補は以降の時点で有限の評価値を持つため除かない
+    let data = MinTwo(cost());
+    for beta in [0.5, 3.0, 30.0] {
+        assert_matches_exhaustive(&data, beta);
+    }
+    assert_eq!(fit_pelt(&data, -3.0).get_change_points(&data.t_max()).unwrap(), vec![20, 35, 50]);
+}
+
+
+#[test]
+fn min_size_costs_match_exhaustive() {
+    // 相関の変化（区間の最小の長さ3）
+    let x = (0..40).map(|i| (i as f64 * 1.3).sin()).collect::<Vec<f64>>();
+    let y = x.iter()
+             .enumerate()
+             .map(|(i, v)| if i < 22 { *v } else { -*v } + 0.2 * (i as f64 * 2.9).cos())
+             .collect::<Vec<f64>>();
+    let correlation = CorrelationCost::new(&x, &y).unwrap();
+    assert_eq!(correlation.pruning_constant(), f64::NEG_INFINITY);
+    // 0の過剰の変化（区間の最小の長さ2）
+    let counts = (0..40).map(|i| if i < 20 { (i % 4) as u64 } else if i % 3 == 2 { 0 } else { 2 + (i % 5) as u64 })
+                        .collect::<Vec<u64>>();
+    let zip = ZipCost::new(&counts);
+    assert_eq!(zip.pruning_constant(), f64::NEG_INFINITY);
+
+    for beta in [0.5, 3.0, 10.0] {
+        assert_matches_exhaustive(&correlation, beta);
+        assert_matches_exhaustive(&zip, beta);
+    }
+}
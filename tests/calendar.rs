@@ -0,0 +1,72 @@
+//! 観測時刻に基づく暦の注釈と休止期間の確認
+
+use cpd_tools::calendar::{Calendar, Shift, Weekday};
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{self, Constraints, Method, Penalty};
+
+
+/// 2023年12月31日15時（UTC）から1時間ごとの時刻
+fn hourly(len: usize) -> Vec<i64> {
+    (0..len as i64).map(|i| 1_704_034_800 + 3600 * i).collect()
+}
+
+
+fn shifts() -> Vec<Shift> {
+    vec![
+        Shift { name: "night".to_owned(), start_minute: 22 * 60 },
+        Shift { name: "day".to_owned(), start_minute: 6 * 60 },
+        Shift { name: "evening".to_owned(), start_minute: 14 * 60 },
+    ]
+}
+
+
+#[test]
+fn annotation_uses_local_calendar() {
+    let calendar = Calendar::new(hourly(48), 9 * 3600).unwrap().with_shifts(shifts()).unwrap();
+    // 日本標準時で2024年1月1日0時（月曜日）
+    let a = calendar.annotate(1).unwrap();
+    assert_eq!((a.year, a.month, a.day, a.hour, a.minute), (2024, 1, 1, 1, 0));
+    assert_eq!(a.weekday, Weekday::Monday);
+    assert_eq!((a.iso_year, a.iso_week), (2024, 1));
+    assert_eq!(a.shift.as_deref(), Some("night"));
+    let b = calendar.annotate(15).unwrap();
+    assert_eq!((b.hour, b.shift.as_deref()), (15, Some("evening")));
+
+    let utc = Calendar::new(hourly(48), 0).unwrap();
+    let c = utc.annotate(1).unwrap();
+    assert_eq!((c.year, c.month, c.day, c.hour), (2023, 12, 31, 16));
+    assert_eq!(c.weekday, Weekday::Sunday);
+    assert_eq!((c.iso_year, c.iso_week), (2023, 52));
+    assert_eq!(c.shift, None);
+
+    // 2021年1月1日（金曜日）はISO 8601では2020年の第53週
+    let d = Calendar::new(vec![0, 1_609_459_200], 0).unwrap().annotate(1).unwrap();
+    assert_eq!((d.weekday, d.iso_year, d.iso_week), (Weekday::Friday, 2020, 53));
+
+    assert!(calendar.annotate(0).is_err());
+    assert!(calendar.annotate(48).is_err());
+    assert!(Calendar::new(vec![2, 1], 0).is_err());
+}
+
+
+#[test]
+fn blackout_forbids_change_points() {
+    let data = (0..48).map(|t| if t < 20 { 0.0 } else { 3.0 } + 0.2 * (t as f64 * 1.3).sin()).collect::<Vec<f64>>();
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    let ts = hourly(48);
+    let calendar = Calendar::new(ts.clone(), 9 * 3600).unwrap().with_blackout(ts[18], ts[23]).unwrap();
+    assert!(!calendar.is_blacked_out(17) && calendar.is_blacked_out(18) && calendar.is_blacked_out(22) && !calendar.is_blacked_out(23));
+
+    let constrained = calendar.blackout(&cost).unwrap();
+    for method in [Method::Dp, Method::Dp2] {
+        let res = detect::detect(&constrained, method, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+        assert!(res.change_points.iter().all(|t| !calendar.is_blacked_out(*t)), "{method:?}: {:?}", res.change_points);
+        let annotations = res.annotate(&calendar).unwrap();
+        assert!(annotations.iter().all(|a| !a.in_blackout));
+    }
+    let free = detect::detect(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(free.change_points, vec![20]);
+    assert!(free.annotate(&calendar).unwrap()[0].in_blackout);
+    assert_eq!(constrained.segment_value(0, 48).unwrap(), cost.segment_value(0, 48).unwrap());
+    assert!(Calendar::new(hourly(10), 0).unwrap().blackout(&cost).is_err());
+}
@@ -0,0 +1,138 @@
+//! 変化点の有無を判定する閾値の較正（[`calibrate_threshold`]）の確認
+
+use cpd_tools::calibrate::{calibrate_threshold, default_block_len, moving_block_resample, single_change_statistic, BlockBootstrap, GaussianMeanNull, NullModel, UncorrelatedNull};
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::CalcDpError;
+use cpd_tools::RngConfig;
+
+use process_param::Tau;
+
+use rand::rngs::StdRng;
+
+use rand_distr::Distribution;
+
+
+#[test]
+fn statistic_is_best_single_split() {
+    let data = (0..30).map(|i| if i < 12 { 0.0 } else { 1.5 } + 0.4 * (i as f64 * 1.9).sin()).collect::<Vec<f64>>();
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![1.0; 30]).unwrap();
+    let whole = cost.segment_value(0, 30).unwrap();
+    let best = (1..30).map(|s| cost.segment_value(0, s).unwrap() + cost.segment_value(s, 30).unwrap() - whole)
+                      .fold(f64::NEG_INFINITY, f64::max);
+    assert!((single_change_statistic(&cost).unwrap() - best).abs() < 1e-9);
+}
+
+
+#[test]
+fn threshold_achieves_false_alarm_rate() {
+    let null = GaussianMeanNull { sd: 1.5 };
+    let alpha = 0.1;
+    let threshold = calibrate_threshold(&null, 50, alpha, 2_000, 1).unwrap();
+    assert!(threshold > 0.0);
+
+    // 較正に用いていない系列で誤警報の割合を確認する
+    let check = RngConfig::new(99);
+    let n = 1_000;
+    let alarms = (0..n).filter(|i| {
+                            let cost = null.simulate(50, &mut check.trial(*i)).unwrap();
+                            single_change_statistic(&cost).unwrap() > threshold
+                        })
+                        .count();
+    let rate = alarms as f64 / n as f64;
+    assert!((rate - alpha).abs() < 0.03, "{rate}");
+
+    // 誤警報確率を小さくすると閾値は大きくなる
+    assert!(calibrate_threshold(&null, 50, 0.01, 2_000, 1).unwrap() > threshold);
+}
+
+
+#[test]
+fn fixed_seed_is_reproducible() {
+    let null = UncorrelatedNull;
+    let first = calibrate_threshold(&null, 40, 0.05, 200, 17).unwrap();
+    assert_eq!(first, calibrate_threshold(&null, 40, 0.05, 200, 17).unwrap());
+    assert_ne!(first, calibrate_threshold(&null, 40, 0.05, 200, 18).unwrap());
+
+    // 関数による生成モデルも同じ乱数列を受け取る
+    let closure = |t_max: Tau, rng: &mut StdRng| -> Result<HeteroscedasticMeanCost, CalcDpError> {
+        GaussianMeanNull { sd: 2.0 }.simulate(t_max, rng)
+    };
+    assert_eq!(calibrate_threshold(&closure, 40, 0.05, 200, 17).unwrap(),
+               calibrate_threshold(&GaussianMeanNull { sd: 2.0 }, 40, 0.05, 200, 17).unwrap());
+}
+
+
+#[test]
+fn rejects_invalid_input() {
+    let null = GaussianMeanNull { sd: 1.0 };
+    assert!(calibrate_threshold(&null, 1, 0.1, 100, 0).is_err());
+    assert!(calibrate_threshold(&null, 20, 0.0, 100, 0).is_err());
+    assert!(calibrate_threshold(&null, 20, 1.0, 100, 0).is_err());
+    // 上側分位点を求めるには1/alpha個以上の試行が必要
+    assert!(calibrate_threshold(&null, 20, 0.01, 50, 0).is_err());
+}
+
+
+/// 自己相関の強い（AR(1)，係数0.8）平均0の残差
+fn ar1_residuals(n: usize) -> Vec<f64> {
+    let mut rng = RngConfig::new(5).rng();
+    let mut e = 0.0;
+    let mut res = (0..n).map(|_| {
+                            let z: f64 = rand_distr::StandardNormal.sample(&mut rng);
+                            e = 0.8 * e + z;
+                            e
+                        })
+                        .collect::<Vec<f64>>();
+    let mean = res.iter().sum::<f64>() / n as f64;
+    res.iter_mut().for_each(|r| *r -= mean);
+    res
+}
+
+
+fn mean_cost(series: &[f64]) -> Result<HeteroscedasticMeanCost, CalcDpError> {
+    HeteroscedasticMeanCost::new(series, &vec![1.0; series.len()])
+}
+
+
+#[test]
+fn resample_joins_blocks_of_residuals() {
+    let residuals = (0..10).map(|i| i as f64).collect::<Vec<f64>>();
+    let mut rng = RngConfig::new(2).rng();
+    let series = moving_block_resample(&residuals, 23, 4, &mut rng).unwrap();
+    assert_eq!(series.len(), 23);
+    // 各ブロックは残差の連続した部分
+    for block in series.chunks(4) {
+        assert!(block.windows(2).all(|w| w[1] == w[0] + 1.0), "{series:?}");
+    }
+    // ブロックの長さが残差の長さと等しければ残差の繰り返し
+    let whole = moving_block_resample(&residuals, 25, 10, &mut rng).unwrap();
+    assert_eq!(whole, residuals.iter().cycle().take(25).copied().collect::<Vec<f64>>());
+    // ブロックの長さが0，残差より長い場合および残差が空の場合はエラー
+    assert!(moving_block_resample(&residuals, 5, 0, &mut rng).is_err());
+    assert!(moving_block_resample(&residuals, 5, 11, &mut rng).is_err());
+    assert!(moving_block_resample(&[], 5, 1, &mut rng).is_err());
+
+    assert_eq!(default_block_len(0), 1);
+    assert_eq!(default_block_len(8), 2);
+    assert_eq!(default_block_len(27), 3);
+    assert_eq!(default_block_len(28), 4);
+}
+
+
+#[test]
+fn block_bootstrap_keeps_autocorrelation() {
+    let residuals = ar1_residuals(400);
+    let block_len = default_block_len(residuals.len()) * 3;
+    let blocks = BlockBootstrap::new(residuals.clone(), block_len, mean_cost).unwrap();
+    assert_eq!(blocks.block_len(), block_len);
+    let threshold = calibrate_threshold(&blocks, 100, 0.1, 400, 8).unwrap();
+    assert_eq!(threshold, calibrate_threshold(&blocks, 100, 0.1, 400, 8).unwrap());
+
+    // 長さ1のブロックは自己相関を失うため，正の自己相関による見かけの変化を過小に見積もる
+    let iid = BlockBootstrap::new(residuals.clone(), 1, mean_cost).unwrap();
+    let naive = calibrate_threshold(&iid, 100, 0.1, 400, 8).unwrap();
+    assert!(threshold > 2.0 * naive, "{threshold}, {naive}");
+
+    assert!(BlockBootstrap::new(residuals.clone(), 0, mean_cost).is_err());
+    assert!(BlockBootstrap::new(residuals, 401, mean_cost).is_err());
+}
@@ -0,0 +1,56 @@
+//! 右打ち切りを含む指数分布に基づくコスト関数（[`CensoredExponentialCost`]）の確認
+
+use cpd_tools::cost::{CensoredExponentialCost, SegmentCost, SegmentParameter};
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+
+#[test]
+fn matches_hand_computation() {
+    let cost = CensoredExponentialCost::new(&[(2.0, true), (3.0, false), (5.0, true), (4.0, false)]).unwrap();
+    // 故障数2，総観測時間10より λ = 0.2
+    assert!((cost.rate(0, 3).unwrap() - 0.2).abs() < 1e-12);
+    assert!((cost.segment_value(0, 3).unwrap() - (2.0 * 0.2_f64.ln() - 2.0)).abs() < 1e-12);
+    assert_eq!(cost.segment_parameter(0, 3).unwrap(), vec![cost.rate(0, 3).unwrap()]);
+
+    // 打ち切りのみの区間は故障率0
+    assert_eq!(cost.rate(3, 4).unwrap(), 0.0);
+    assert_eq!(cost.segment_value(3, 4).unwrap(), 0.0);
+    assert_eq!(cost.segment_value(1, 2).unwrap(), 0.0);
+}
+
+
+#[test]
+fn censoring_lowers_the_rate() {
+    // 同じ観測時間でも，打ち切りを故障として扱うと故障率を過大に推定する
+    let censored = CensoredExponentialCost::new(&[(4.0, true), (6.0, false), (5.0, false)]).unwrap();
+    let all_events = CensoredExponentialCost::new(&[(4.0, true), (6.0, true), (5.0, true)]).unwrap();
+    assert!((censored.rate(0, 3).unwrap() - 1.0 / 15.0).abs() < 1e-12);
+    assert!((all_events.rate(0, 3).unwrap() - 3.0 / 15.0).abs() < 1e-12);
+}
+
+
+#[test]
+fn detects_change_in_failure_rate() {
+    // 前半は寿命が長く打ち切りが多い．後半は寿命が短い
+    let data = (0..40).map(|i| {
+                          let x = 1.0 + (i as f64 * 1.3).sin().abs();
+                          if i < 25 { (10.0 * x, i % 3 == 0) } else { (x, true) }
+                      })
+                      .collect::<Vec<(f64, bool)>>();
+    let cost = CensoredExponentialCost::new(&data).unwrap();
+    let result = detect(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(result.change_points, vec![25]);
+    assert!(cost.rate(0, 25).unwrap() < cost.rate(25, 40).unwrap());
+}
+
+
+#[test]
+fn rejects_invalid_input() {
+    for time in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert!(CensoredExponentialCost::new(&[(1.0, true), (time, false)]).is_err(), "time = {time}");
+    }
+    let cost = CensoredExponentialCost::new(&[(1.0, true), (2.0, false)]).unwrap();
+    assert_eq!(cost.t_max(), 2);
+    assert!(cost.segment_value(0, 3).is_err());
+    assert!(cost.rate(1, 1).is_err());
+}
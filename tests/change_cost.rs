@@ -0,0 +1,69 @@
+//! 変化点の位置に依存する変化のコストの確認
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{self, ChangeCost, Constraints, Method, Penalty};
+
+
+/// 12時点目と30時点目で平均が変化する系列
+fn two_shifts() -> HeteroscedasticMeanCost {
+    let data = (0..45).map(|t| match t {
+                          0..=11 => 0.0,
+                          12..=29 => 2.5,
+                          _ => 0.5,
+                      } + 0.2 * (t as f64 * 1.7).sin())
+                      .collect::<Vec<f64>>();
+    HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap()
+}
+
+
+#[test]
+fn uniform_cost_matches_linear_penalty() {
+    let cost = two_shifts();
+    let plain = detect::detect(&cost, Method::Dp, &Penalty::Linear(5.0), &Constraints::default()).unwrap();
+    let charged = detect::detect_with_change_cost(&cost, Method::Dp, &Penalty::Linear(0.0), &Constraints::default(), |_| 5.0).unwrap();
+    assert_eq!(plain.change_points, charged.change_points);
+    assert_eq!(plain.value, charged.value);
+}
+
+
+#[test]
+fn costly_locations_are_avoided() {
+    let cost = two_shifts();
+    let free = detect::detect(&cost, Method::Dp, &Penalty::NumChange(2), &Constraints::default()).unwrap();
+    assert_eq!(free.change_points, vec![12, 30]);
+
+    // 監査期間（25時点目から34時点目）には変化点を置かない
+    let audit = |t| if (25..35).contains(&t) { f64::INFINITY } else { 0.0 };
+    for method in [Method::Dp, Method::Dp2] {
+        let res = detect::detect_with_change_cost(&cost, method, &Penalty::NumChange(2), &Constraints::default(), audit).unwrap();
+        assert_eq!(res.change_points[0], 12, "{method:?}");
+        assert!(!(25..35).contains(&res.change_points[1]), "{method:?}: {:?}", res.change_points);
+        let raw = res.segments().iter().map(|(a, b)| cost.segment_value(*a, *b).unwrap()).sum::<f64>();
+        assert_eq!(res.value, raw);
+    }
+
+    let wrapped = ChangeCost::new(&cost, |t| t as f64);
+    assert_eq!(wrapped.segment_value(0, 10).unwrap(), cost.segment_value(0, 10).unwrap());
+    assert_eq!(wrapped.segment_value(10, 20).unwrap(), cost.segment_value(10, 20).unwrap() - 10.0);
+    assert_eq!(wrapped.segment_values(&[(0, 10), (10, 20)]).unwrap(), vec![wrapped.segment_value(0, 10).unwrap(), wrapped.segment_value(10, 20).unwrap()]);
+}
+
+
+#[test]
+fn prior_shifts_change_location() {
+    let cost = two_shifts();
+    let uniform = detect::detect_with_prior(&cost, Method::Dp, &Penalty::Linear(3.0), &Constraints::default(), &[2.0; 45]).unwrap();
+    let plain = detect::detect(&cost, Method::Dp, &Penalty::Linear(3.0), &Constraints::default()).unwrap();
+    assert_eq!(uniform, plain);
+
+    // 変化点は20時点目から39時点目にのみ起こりうる
+    let prior = (0..45).map(|t| if (20..40).contains(&t) { 1.0 } else { 0.0 }).collect::<Vec<f64>>();
+    let res = detect::detect_with_prior(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default(), &prior).unwrap();
+    assert_eq!(res.change_points, vec![30]);
+
+    assert!(detect::detect_with_prior(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default(), &[1.0; 10]).is_err());
+    assert!(detect::detect_with_prior(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default(), &[0.0; 45]).is_err());
+    let mut negative = vec![1.0; 45];
+    negative[3] = -1.0;
+    assert!(detect::detect_with_prior(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default(), &negative).is_err());
+}
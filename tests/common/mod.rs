@@ -0,0 +1,132 @@
+//! 結合テストで共有するメモの型とコスト関数
+//!
+//! 各テストは`mod common;`により読み込むため，一部のテストのみで用いる項目を許容する．
+#![allow(dead_code)]
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2, CalcDpError};
+use cpd_tools::dp_tools::calc_dp_penalized::CalcDpPenalized;
+use cpd_tools::dp_tools::calc_dp_spaced::CalcDpSpaced;
+use cpd_tools::dp_tools::calc_pelt::CalcPelt;
+
+use process_param::{Tau, NumChg};
+
+use std::marker::PhantomData;
+
+
+/// 変化点個数ごとのメモ
+pub type Memo = cpd_tools::dp_tools::Memo<f64>;
+
+/// 評価値の計算に用いる変数を持たないメモ
+pub type VariMemo = cpd_tools::dp_tools::VariMemo<(), f64>;
+
+/// 変化点個数を固定しないメモ
+pub type PenalizedMemo = Vec<(Tau, NumChg, f64)>;
+
+/// 任意の2個の変化点間の評価値を格納した2次元配列
+pub type Table = Vec<Vec<f64>>;
+
+
+/// 計算結果`S`を保持し，区間の評価値にコスト関数`C`の[`SegmentCost::segment_value`]を用いる型
+///
+/// `S`の型に応じて動的計画法の各traitを実装する．
+/// 複数のtraitが同名の関数を持つため，`<Fit<Memo> as calc_dp::CalcDP<f64, _>>::get_value(..)`の形で呼び出す．
+///
+/// * [`Memo`] - [`calc_dp::CalcDP`]，[`calc_dp_2::CalcDP`]，[`CalcDpSpaced`]
+/// * [`VariMemo`] - [`calc_dp::CalcDPWithVari`]，[`calc_dp_2::CalcDPWithVari`]
+/// * [`PenalizedMemo`] - [`CalcDpPenalized`]，[`CalcPelt`]
+/// * [`Table`] - [`calc_dp::DictTT`]，[`calc_dp_2::DictTT`]
+pub struct Fit<S, C = HeteroscedasticMeanCost>(pub S, PhantomData<C>);
+
+impl<S, C> Fit<S, C> {
+    pub fn new(saved: S) -> Self {
+        Fit(saved, PhantomData)
+    }
+}
+
+impl<S, C: SegmentCost> calc_dp::CalcTT<f64, C> for Fit<S, C> {
+    fn calc_value(data: &C, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        data.segment_value(t_k_1, t_k)
+    }
+}
+
+impl<S, C: SegmentCost> calc_dp_2::CalcTT<f64, C> for Fit<S, C> {
+    fn calc_value(data: &C, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        data.segment_value(t_k_1, t_k)
+    }
+}
+
+impl<C: SegmentCost> calc_dp::CalcDP<f64, C> for Fit<Memo, C> {
+    fn memo_all(&self) -> Memo {
+        self.0.clone()
+    }
+}
+
+impl<C: SegmentCost> calc_dp_2::CalcDP<f64, C> for Fit<Memo, C> {
+    fn memo_all(&self) -> Memo {
+        self.0.clone()
+    }
+}
+
+impl<C: SegmentCost, const M: usize> CalcDpSpaced<f64, C, M> for Fit<Memo, C> {
+    fn memo_all(&self) -> Memo {
+        self.0.clone()
+    }
+}
+
+macro_rules! impl_vari {
+    ($m:ident) => {
+        impl<C: SegmentCost> $m::CalcDPWithVari<f64, (), C> for Fit<VariMemo, C> {
+            fn calc_value(data: &C, t_k_1: &Tau, t_k: &Tau, _: &()) -> Result<((), f64), CalcDpError> {
+                Ok(((), data.segment_value(*t_k_1, *t_k)?))
+            }
+
+            fn calc_value_terminal(data: &C, t_k: &Tau) -> Result<((), f64), CalcDpError> {
+                Ok(((), data.segment_value(0, *t_k)?))
+            }
+
+            fn memo_all(&self) -> VariMemo {
+                self.0.clone()
+            }
+        }
+    };
+}
+
+impl_vari!(calc_dp);
+impl_vari!(calc_dp_2);
+
+impl<C: SegmentCost> CalcDpPenalized<f64, C> for Fit<PenalizedMemo, C> {
+    fn memo_all(&self) -> PenalizedMemo {
+        self.0.clone()
+    }
+}
+
+impl<C: SegmentCost> CalcPelt<f64, C> for Fit<PenalizedMemo, C> {
+    fn memo_all(&self) -> PenalizedMemo {
+        self.0.clone()
+    }
+}
+
+impl<C: SegmentCost> calc_dp::DictTT<f64, C> for Fit<Table, C> {
+    fn value_tt_all(&self) -> Table {
+        self.0.clone()
+    }
+}
+
+impl<C: SegmentCost> calc_dp_2::DictTT<f64, C> for Fit<Table, C> {
+    fn value_tt_all(&self) -> Table {
+        self.0.clone()
+    }
+}
+
+
+/// 時点`i`の観測値を`value(i)`とする長さ`n`の系列について，分散を一律`variance`とした平均のコスト関数
+///
+/// # 引数
+/// * `n` - 系列の長さ
+/// * `variance` - 各時点の分散
+/// * `value` - 時点（0始まり）から観測値を返す関数
+pub fn mean_cost(n: usize, variance: f64, value: impl Fn(usize) -> f64) -> HeteroscedasticMeanCost {
+    let data = (0..n).map(value).collect::<Vec<f64>>();
+    HeteroscedasticMeanCost::new(&data, &vec![variance; n]).unwrap()
+}
@@ -0,0 +1,70 @@
+//! 複数のコスト関数を組み合わせたコスト関数（[`Composite`]）の確認
+
+use cpd_tools::cost::{BoxedCost, Composite, CorrelationCost, HeteroscedasticMeanCost, NegBinomialCost, Dispersion, SegmentCost};
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+
+fn gaussian(data: &[f64]) -> HeteroscedasticMeanCost {
+    HeteroscedasticMeanCost::new(data, &vec![1.0; data.len()]).unwrap()
+}
+
+
+fn counts(data: &[u64]) -> NegBinomialCost {
+    NegBinomialCost::new(data, Dispersion::Global(2.0)).unwrap()
+}
+
+
+#[test]
+fn weighted_sum_of_components() {
+    let x = [0.5, 1.5, -0.5, 2.5];
+    let y = [1, 4, 0, 6];
+    let composite = Composite::new(vec![(2.0, Box::new(gaussian(&x))), (0.5, Box::new(counts(&y)))]).unwrap();
+    assert_eq!(composite.t_max(), 4);
+    assert_eq!(composite.weights(), vec![2.0, 0.5]);
+    for (t_k_1, t_k) in [(0, 4), (1, 3), (2, 3)] {
+        let values = composite.component_values(t_k_1, t_k).unwrap();
+        assert_eq!(values, vec![gaussian(&x).segment_value(t_k_1, t_k).unwrap(), counts(&y).segment_value(t_k_1, t_k).unwrap()]);
+        assert!((composite.segment_value(t_k_1, t_k).unwrap() - (2.0 * values[0] + 0.5 * values[1])).abs() < 1e-12);
+    }
+    // (0, 4]: 平均1の偏差平方和 5 より -2.5
+    assert!((composite.component_values(0, 4).unwrap()[0] + 2.5).abs() < 1e-12);
+    assert_eq!(composite.n_parameters(), 2);
+}
+
+
+#[test]
+fn zero_weight_ignores_undefined_component() {
+    let x = [0.1, 0.9, 0.3, 0.7];
+    // 相関係数は3点未満の区間で評価できない
+    let components: Vec<(f64, BoxedCost)> = vec![(1.0, Box::new(gaussian(&x))), (0.0, Box::new(CorrelationCost::new(&x, &x).unwrap()))];
+    let composite = Composite::new(components).unwrap();
+    assert_eq!(composite.component_values(0, 2).unwrap()[1], f64::NEG_INFINITY);
+    assert_eq!(composite.segment_value(0, 2).unwrap(), gaussian(&x).segment_value(0, 2).unwrap());
+    assert_eq!(composite.min_identifiable_size(), 3);
+}
+
+
+#[test]
+fn joint_channels_locate_a_shared_change() {
+    // 計測値と不良数が同じ時点で変化する
+    let x = (0..40).map(|i| if i < 22 { 0.0 } else { 0.8 } + (i as f64 * 1.7).sin()).collect::<Vec<f64>>();
+    let base = [1, 0, 2, 1, 3, 0, 1, 2];
+    let y = (0..40).map(|i| base[i % 8] + if i < 22 { 0 } else { 2 }).collect::<Vec<u64>>();
+    let composite = Composite::new(vec![(1.0, Box::new(gaussian(&x))), (1.0, Box::new(counts(&y)))]).unwrap();
+    let result = detect(&composite, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(result.change_points, vec![22]);
+}
+
+
+#[test]
+fn rejects_invalid_components() {
+    assert!(Composite::new(Vec::new()).is_err());
+    for weight in [-1.0, f64::NAN, f64::INFINITY] {
+        assert!(Composite::new(vec![(weight, Box::new(gaussian(&[1.0, 2.0])))]).is_err(), "weight = {weight}");
+    }
+    let components: Vec<(f64, BoxedCost)> = vec![(1.0, Box::new(gaussian(&[1.0, 2.0]))), (1.0, Box::new(counts(&[1, 2, 3])))];
+    assert!(Composite::new(components).is_err());
+    let composite = Composite::new(vec![(1.0, Box::new(gaussian(&[1.0, 2.0])))]).unwrap();
+    assert!(composite.segment_value(0, 3).is_err());
+    assert!(composite.component_values(1, 1).is_err());
+}
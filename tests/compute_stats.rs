@@ -0,0 +1,49 @@
+//! 動的計画法の計算量の記録の確認
+
+mod common;
+use common::mean_cost;
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{Constraints, FitResult, Method};
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    mean_cost(60, 1.0, |i| if i < 25 { (i as f64 * 0.3).sin() } else { 1.5 + (i as f64 * 0.9).cos() })
+}
+
+
+#[test]
+fn stats_count_cells_and_hits() {
+    let data = cost();
+    let t_max = data.t_max() as usize;
+    let fit = FitResult::fit(&data, Method::Dp, Some(4)).unwrap();
+    let stats = fit.compute_stats();
+    assert_eq!(stats.per_k.iter().map(|s| s.k).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+
+    // k = 0は期数Tの区間のみを計算する
+    let k0 = &stats.per_k[0];
+    assert_eq!((k0.cells, k0.segments, k0.lookups, k0.hits), (1, 1, 0, 0));
+    assert_eq!(k0.hit_rate(), None);
+
+    // k = 1では全ての期数のk = 0の要素が未計算である
+    let k1 = &stats.per_k[1];
+    assert_eq!((k1.cells, k1.lookups, k1.hits), (t_max, t_max - 1, 0));
+
+    // k = 2以降は下の行の要素を再利用する
+    for s in &stats.per_k[2..] {
+        assert!(s.hit_rate().unwrap() > 0.5, "{s:?}");
+        assert_eq!(s.lookups - s.hits, s.cells - 1);
+    }
+    assert_eq!(stats.cells(), stats.per_k.iter().map(|s| s.cells).sum::<usize>());
+    assert!(stats.hit_rate().unwrap() > 0.0);
+}
+
+
+#[test]
+fn band_reduces_evaluated_segments() {
+    let data = cost();
+    let full = FitResult::fit(&data, Method::Dp, Some(6)).unwrap();
+    let constraints = Constraints::default().with_min_size(5).with_max_size(15);
+    let banded = FitResult::fit_constrained(&data, Method::Dp, Some(6), &constraints).unwrap();
+    assert!(banded.compute_stats().segments() * 2 < full.compute_stats().segments());
+}
@@ -0,0 +1,67 @@
+//! 2系列間の相関の変化を検出するコスト関数（[`CorrelationCost`]）の確認
+
+use cpd_tools::cost::{CorrelationCost, SegmentCost, SegmentParameter};
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+
+#[test]
+fn matches_hand_computation() {
+    let x = [1.0, 2.0, 3.0, 4.0];
+    let y = [1.0, 3.0, 2.0, 4.0];
+    let cost = CorrelationCost::new(&x, &y).unwrap();
+
+    // (0, 3]: 偏差積和 1，偏差平方和 2, 2 より r = 0.5
+    assert!((cost.correlation(0, 3).unwrap() - 0.5).abs() < 1e-12);
+    assert!((cost.fisher_z(0, 3).unwrap() - 0.5 * 3.0_f64.ln()).abs() < 1e-12);
+    assert!((cost.segment_value(0, 3).unwrap() + 1.5 * 0.75_f64.ln()).abs() < 1e-12);
+
+    // (0, 4]: 偏差積和 4，偏差平方和 5, 5 より r = 0.8
+    assert!((cost.correlation(0, 4).unwrap() - 0.8).abs() < 1e-12);
+    assert!((cost.segment_value(0, 4).unwrap() + 2.0 * 0.36_f64.ln()).abs() < 1e-12);
+    assert_eq!(cost.segment_parameter(0, 4).unwrap(), vec![cost.correlation(0, 4).unwrap()]);
+}
+
+
+#[test]
+fn degenerate_segments() {
+    let x = [1.0, 2.0, 3.0, 5.0, 5.0, 5.0];
+    let cost = CorrelationCost::new(&x, &x).unwrap();
+    // 推定に3点を要する
+    assert_eq!(cost.segment_value(0, 2).unwrap(), f64::NEG_INFINITY);
+    // 完全相関でも評価値は有限
+    assert_eq!(cost.correlation(0, 3).unwrap(), 1.0);
+    assert!(cost.segment_value(0, 3).unwrap().is_finite());
+    assert!(cost.fisher_z(0, 3).unwrap().is_finite());
+    // 一定の系列の相関係数は0
+    assert_eq!(cost.correlation(3, 6).unwrap(), 0.0);
+    assert_eq!(cost.segment_value(3, 6).unwrap(), 0.0);
+}
+
+
+#[test]
+fn detects_sign_flip_with_stable_levels() {
+    let x = (0..60).map(|i| (i as f64 * 1.3).sin()).collect::<Vec<f64>>();
+    let y = x.iter()
+             .enumerate()
+             .map(|(i, v)| if i < 30 { *v } else { -*v } + 0.1 * (i as f64 * 2.7).cos())
+             .collect::<Vec<f64>>();
+    let cost = CorrelationCost::new(&x, &y).unwrap();
+    let result = detect(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    // 境界付近の1点は相関の推定への寄与が小さいため，前後1時点のずれを許容する
+    assert_eq!(result.change_points.len(), 1);
+    assert!(result.change_points[0].abs_diff(30) <= 1, "{:?}", result.change_points);
+    assert!(cost.correlation(0, 30).unwrap() > 0.9);
+    assert!(cost.correlation(30, 60).unwrap() < -0.9);
+}
+
+
+#[test]
+fn rejects_invalid_input() {
+    assert!(CorrelationCost::new(&[1.0, 2.0, 3.0], &[1.0, 2.0]).is_err());
+    assert!(CorrelationCost::new(&[1.0, f64::NAN, 3.0], &[1.0, 2.0, 3.0]).is_err());
+    assert!(CorrelationCost::new(&[1.0, 2.0, 3.0], &[1.0, f64::INFINITY, 3.0]).is_err());
+    let cost = CorrelationCost::new(&[1.0, 2.0, 3.0], &[3.0, 1.0, 2.0]).unwrap();
+    assert!(cost.segment_value(0, 4).is_err());
+    assert!(cost.segment_value(2, 2).is_err());
+    assert!(cost.correlation(2, 1).is_err());
+}
@@ -0,0 +1,189 @@
+//! 評価値のキャッシュ（[`CachedCost`]）の確認
+
+use cpd_tools::config::{self, RunSpec};
+use cpd_tools::cost::{cost_config, fingerprint, CacheKey, CachedCost, HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{detect, Constraints, Method, Penalty};
+use cpd_tools::dp_tools::CalcDpError;
+
+use process_param::Tau;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+
+/// 元のコスト関数による評価値の計算回数を数えるコスト関数
+struct Counting {
+    cost: HeteroscedasticMeanCost,
+    calls: AtomicUsize,
+}
+
+impl Counting {
+    fn new(data: &[f64]) -> Self {
+        Counting { cost: HeteroscedasticMeanCost::new(data, &vec![1.0; data.len()]).unwrap(), calls: AtomicUsize::new(0) }
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl SegmentCost for Counting {
+    fn t_max(&self) -> Tau {
+        self.cost.t_max()
+    }
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.cost.segment_value(t_k_1, t_k)
+    }
+}
+
+
+fn series() -> Vec<f64> {
+    (0..30).map(|i| if i < 18 { 0.0 } else { 3.0 } + 0.3 * (i as f64 * 1.7).sin()).collect()
+}
+
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cpd_cost_cache_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+
+fn key(data: &[f64], name: &str) -> CacheKey {
+    CacheKey { data: fingerprint(&[data.to_vec()]), config: cost_config(name, &BTreeMap::new()) }
+}
+
+
+#[test]
+fn hit_and_miss() {
+    let dir = temp_dir("hit");
+    let data = series();
+    let cached = CachedCost::open(Counting::new(&data), &dir, key(&data, "counting")).unwrap();
+    assert!(cached.is_empty());
+    // 計算していなければファイルを作成しない
+    cached.save().unwrap();
+    assert!(!cached.path().exists());
+
+    let first = cached.segment_value(0, 18).unwrap();
+    assert_eq!(cached.inner().calls(), 1);
+    assert_eq!(cached.segment_value(0, 18).unwrap(), first);
+    assert_eq!(cached.inner().calls(), 1);
+    let result = detect(&cached, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(result.change_points, vec![18]);
+    let computed = cached.inner().calls();
+    assert_eq!(cached.len(), computed);
+    cached.save().unwrap();
+    assert!(cached.path().exists());
+
+    // 同じキーでは保存した評価値を再利用する
+    let reopened = CachedCost::open(Counting::new(&data), &dir, key(&data, "counting")).unwrap();
+    assert_eq!(reopened.len(), computed);
+    assert_eq!(detect(&reopened, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap(), result);
+    assert_eq!(reopened.inner().calls(), 0);
+    assert_eq!(reopened.segment_value(0, 18).unwrap().to_bits(), first.to_bits());
+
+    // 入力データまたは設定が異なれば再利用しない
+    let mut changed = data.clone();
+    changed[3] += 1.0;
+    assert!(CachedCost::open(Counting::new(&changed), &dir, key(&changed, "counting")).unwrap().is_empty());
+    assert!(CachedCost::open(Counting::new(&data), &dir, key(&data, "other")).unwrap().is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+
+#[test]
+fn mismatched_or_broken_file_starts_empty() {
+    let dir = temp_dir("mismatch");
+    let data = series();
+    let cached = CachedCost::open(Counting::new(&data), &dir, key(&data, "a")).unwrap();
+    cached.segment_value(0, 10).unwrap();
+    cached.save().unwrap();
+
+    // ファイル名が衝突しても，ファイル内のキーと照合して別の設定の評価値は用いない
+    let other = key(&data, "b");
+    std::fs::copy(cached.path(), dir.join(other.file_name())).unwrap();
+    assert!(CachedCost::open(Counting::new(&data), &dir, other).unwrap().is_empty());
+
+    // 壊れたファイルは読み込まない
+    let bytes = std::fs::read(cached.path()).unwrap();
+    std::fs::write(cached.path(), &bytes[..bytes.len() - 5]).unwrap();
+    assert!(CachedCost::open(Counting::new(&data), &dir, key(&data, "a")).unwrap().is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+
+#[test]
+fn config_strings_do_not_collide() {
+    let empty = BTreeMap::new();
+    let params = |pairs: &[(&str, f64)]| pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect::<BTreeMap<String, f64>>();
+    let configs = [
+        cost_config("negbin1", &empty),
+        cost_config("negbin", &empty),
+        cost_config("negbin", &params(&[("1", 0.0)])),
+        cost_config("q{}", &empty),
+        cost_config("q", &params(&[("a", 1.0)])),
+        cost_config("q;1:a=", &empty),
+        cost_config("q", &params(&[("a", -0.0)])),
+        cost_config("q", &params(&[("a", 0.0)])),
+        cost_config("q", &params(&[("a", 0.0), ("b", 1.0)])),
+        cost_config("q", &params(&[("a=0;1:b", 1.0)])),
+    ];
+    for (i, a) in configs.iter().enumerate() {
+        for b in configs.iter().skip(i + 1) {
+            assert_ne!(a, b);
+        }
+    }
+    assert_eq!(cost_config("q", &params(&[("b", 1.0), ("a", 2.0)])), cost_config("q", &params(&[("a", 2.0), ("b", 1.0)])));
+}
+
+
+#[test]
+fn config_run_reuses_cache() {
+    let dir = temp_dir("config");
+    let data = series();
+    let csv = std::iter::once("value".to_owned()).chain(data.iter().map(|x| format!("{x}"))).collect::<Vec<String>>().join("\n");
+    std::fs::write(dir.join("series.csv"), csv).unwrap();
+    let spec_text = |q: f64| format!(r#"
+penalty = {{ num_change = 1 }}
+
+[data]
+path = "series.csv"
+columns = ["value"]
+
+[cost]
+name = "quantile"
+params = {{ q = {q} }}
+
+[cache]
+dir = "cache"
+"#);
+    std::fs::write(dir.join("spec.toml"), spec_text(0.5)).unwrap();
+    let spec = RunSpec::from_file(&dir.join("spec.toml")).unwrap();
+    let first = config::run(&spec).unwrap();
+    assert_eq!(first.change_points, vec![18]);
+
+    let columns = std::fs::read_to_string(dir.join("series.csv")).unwrap()
+                                                                  .lines()
+                                                                  .skip(1)
+                                                                  .map(|l| l.parse::<f64>().unwrap())
+                                                                  .collect::<Vec<f64>>();
+    let key = CacheKey { data: fingerprint(&[columns]), config: cost_config("quantile", &spec.cost.params) };
+    let path = dir.join("cache").join(key.file_name());
+    assert!(path.exists());
+    let saved = std::fs::read(&path).unwrap();
+    assert_eq!(config::run(&spec).unwrap(), first);
+    assert_eq!(std::fs::read(&path).unwrap(), saved);
+
+    // 設定値を変えると別のキャッシュファイルとなる
+    std::fs::write(dir.join("spec.toml"), spec_text(0.25)).unwrap();
+    config::run(&RunSpec::from_file(&dir.join("spec.toml")).unwrap()).unwrap();
+    assert_eq!(std::fs::read_dir(dir.join("cache")).unwrap().count(), 2);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
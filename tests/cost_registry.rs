@@ -0,0 +1,161 @@
+//! 名前からコスト関数を作成する登録簿（[`CostRegistry`]）の確認
+
+use cpd_tools::config::{self, RunSpec};
+use cpd_tools::cost::{BoxedCost, CorrelationCost, CostInput, CostRegistry, HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::CalcDpError;
+use cpd_tools::register_costs;
+
+use process_param::Tau;
+
+use std::collections::BTreeMap;
+
+
+/// 利用者が定義したコスト関数：区間内の値の範囲（最大値 - 最小値）の符号を反転したもの
+struct RangeCost(Vec<f64>);
+
+impl SegmentCost for RangeCost {
+    fn t_max(&self) -> Tau {
+        self.0.len() as Tau
+    }
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        let seg = &self.0[t_k_1 as usize..t_k as usize];
+        let max = seg.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let min = seg.iter().copied().fold(f64::INFINITY, f64::min);
+        Ok(min - max)
+    }
+}
+
+
+fn build_range(input: &CostInput) -> Result<BoxedCost, CalcDpError> {
+    Ok(Box::new(RangeCost(input.column(0)?.to_vec())))
+}
+
+
+#[test]
+fn builds_builtin_cost_by_name() {
+    let registry = CostRegistry::with_builtins();
+    for name in ["correlation", "regression", "hetero_mean", "l1", "quantile", "mv_normal", "zip", "negbin", "censored_exp"] {
+        assert!(registry.names().contains(&name), "{name}");
+    }
+
+    let columns = vec![vec![1.0, 2.0, 3.0, 4.0], vec![1.0, 3.0, 2.0, 4.0]];
+    let params = BTreeMap::new();
+    let input = CostInput { columns: &columns, params: &params };
+    let built = registry.build("correlation", &input).unwrap();
+    let direct = CorrelationCost::new(&columns[0], &columns[1]).unwrap();
+    assert_eq!(built.t_max(), 4);
+    assert_eq!(built.segment_value(0, 4).unwrap(), direct.segment_value(0, 4).unwrap());
+
+    let built = registry.build("hetero_mean", &input).unwrap();
+    let direct = HeteroscedasticMeanCost::new(&columns[0], &columns[1]).unwrap();
+    assert_eq!(built.segment_value(1, 4).unwrap(), direct.segment_value(1, 4).unwrap());
+
+    // 計数データとして扱えない列
+    let fractional = vec![vec![1.0, 2.5]];
+    assert!(registry.build("zip", &CostInput { columns: &fractional, params: &params }).is_err());
+    // 必要な列が無い
+    assert!(registry.build("correlation", &CostInput { columns: &columns[..1], params: &params }).is_err());
+}
+
+
+#[test]
+fn unknown_name_is_rejected() {
+    let columns = vec![vec![1.0, 2.0]];
+    let params = BTreeMap::new();
+    let err = match CostRegistry::with_builtins().build("no_such_cost", &CostInput { columns: &columns, params: &params }) {
+        Ok(_) => panic!("unknown cost was built"),
+        Err(e) => e.to_string(),
+    };
+    assert!(err.contains("no_such_cost"), "{err}");
+    assert!(err.contains("hetero_mean"), "{err}");
+    assert!(CostRegistry::new().names().is_empty());
+}
+
+
+#[test]
+fn user_cost_can_be_registered() {
+    let mut registry = CostRegistry::with_builtins();
+    register_costs!(registry, "range" => build_range, "range_alias" => build_range).unwrap();
+    assert!(registry.names().contains(&"range"));
+    // 同じ名前は登録できない
+    assert!(registry.register("range", build_range).is_err());
+    assert!(register_costs!(registry, "l1" => build_range).is_err());
+
+    let columns = vec![vec![3.0, 1.0, 4.0]];
+    let params = BTreeMap::new();
+    let cost = registry.build("range_alias", &CostInput { columns: &columns, params: &params }).unwrap();
+    assert_eq!(cost.segment_value(0, 3).unwrap(), -3.0);
+}
+
+
+#[test]
+fn config_runs_with_user_cost() {
+    let dir = std::env::temp_dir().join(format!("cpd_cost_registry_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut csv = "value\n".to_owned();
+    for t in 0..30 {
+        let level = if t < 12 { 0.0 } else { 5.0 };
+        csv.push_str(&format!("{}\n", level + 0.1 * (t % 3) as f64));
+    }
+    std::fs::write(dir.join("series.csv"), csv).unwrap();
+    std::fs::write(dir.join("spec.toml"), r#"
+penalty = { num_change = 1 }
+
+[data]
+path = "series.csv"
+columns = ["value"]
+
+[cost]
+name = "range"
+"#).unwrap();
+    let spec = RunSpec::from_file(&dir.join("spec.toml")).unwrap();
+
+    // 本crateのコスト関数のみの登録簿では作成できない
+    assert!(config::run(&spec).is_err());
+
+    let mut registry = CostRegistry::with_builtins();
+    registry.register("range", build_range).unwrap();
+    let result = config::run_with_registry(&spec, &registry).unwrap();
+    assert_eq!(result.change_points, vec![12]);
+    assert_eq!(result.t_max, 30);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+
+#[test]
+fn cli_runs_spec() {
+    let dir = std::env::temp_dir().join(format!("cpd_cost_registry_cli_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut csv = "value\n".to_owned();
+    for t in 0..40 {
+        let level = if t < 25 { 1.0 } else { 6.0 };
+        csv.push_str(&format!("{}\n", level + 0.2 * (t % 4) as f64));
+    }
+    std::fs::write(dir.join("series.csv"), csv).unwrap();
+    std::fs::write(dir.join("spec.toml"), r#"
+penalty = { num_change = 1 }
+
+[data]
+path = "series.csv"
+columns = ["value"]
+
+[cost]
+name = "l1"
+"#).unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cpd")).arg(dir.join("spec.toml")).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "25\n");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cpd")).arg("--list-costs").output().unwrap();
+    assert!(String::from_utf8(output.stdout).unwrap().lines().any(|name| name == "l1"));
+
+    // 設定ファイルが存在しない
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cpd")).arg(dir.join("missing.toml")).output().unwrap();
+    assert!(!output.status.success());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,62 @@
+//! 評価値の表を用いた動的計画法の確認
+
+mod common;
+use common::{mean_cost, Memo};
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2, CostTable, TableStrategy};
+
+use process_param::{Tau, NumChg};
+
+
+type Fit = common::Fit<Memo>;
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    mean_cost(60, 0.8, |i| (i as f64 * 0.9).sin() + if (20..45).contains(&i) { 2.0 } else { 0.0 })
+}
+
+
+fn table(cost: &HeteroscedasticMeanCost) -> CostTable<f64> {
+    CostTable::from_fn(cost.t_max(), TableStrategy::Parallel, |t_k_1, t_k| cost.segment_value(t_k_1, t_k)).unwrap()
+}
+
+
+fn to_bits(memo: &Memo) -> Vec<Vec<Option<(Tau, NumChg, u64)>>> {
+    memo.iter().map(|row| row.iter().map(|v| v.map(|(t, k, val)| (t, k, val.to_bits()))).collect()).collect()
+}
+
+
+#[test]
+fn table_memo_matches_direct_memo() {
+    let cost = cost();
+    let table = table(&cost);
+    let t_max = cost.t_max();
+
+    let direct = <Fit as calc_dp::CalcDP<f64, _>>::calc_memo_all(&cost, &t_max).unwrap();
+    let from_table = <Fit as calc_dp::CalcDP<f64, _>>::calc_memo_all_from_table(&table, &t_max).unwrap();
+    assert_eq!(to_bits(&direct), to_bits(&from_table));
+
+    let direct = <Fit as calc_dp_2::CalcDP<f64, _>>::calc_memo_all(&cost, &t_max).unwrap();
+    let from_table = <Fit as calc_dp_2::CalcDP<f64, _>>::calc_memo_all_from_table(&table, &t_max).unwrap();
+    assert_eq!(to_bits(&direct), to_bits(&from_table));
+}
+
+
+#[test]
+fn table_can_serve_a_shorter_prefix() {
+    let cost = cost();
+    let table = table(&cost);
+    assert!(<Fit as calc_dp::CalcDP<f64, _>>::calc_memo_all_from_table(&table, &30).is_ok());
+    assert!(<Fit as calc_dp::CalcDP<f64, _>>::calc_memo_all_from_table(&table, &(cost.t_max() + 1)).is_err());
+}
+
+
+#[test]
+fn malformed_table_is_rejected() {
+    assert!(CostTable::new(vec![vec![0.0, 1.0], vec![2.0, 3.0]]).is_err());
+    let table = CostTable::new(vec![vec![0.0, 1.0], vec![2.0]]).unwrap();
+    assert_eq!(table.t_max(), 2);
+    assert_eq!(*table.get(1, 2).unwrap(), 2.0);
+    assert!(table.get(2, 2).is_err());
+}
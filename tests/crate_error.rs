@@ -0,0 +1,69 @@
+//! 高水準の処理が返すError（`cpd_tools::Error`）の分類の確認
+
+use cpd_tools::config::{self, RunSpec};
+use cpd_tools::dp_tools::CalcDpError;
+use cpd_tools::Error;
+
+use std::error::Error as _;
+
+
+const SPEC: &str = r#"
+penalty = { num_change = 1 }
+
+[data]
+path = "series.csv"
+columns = [0]
+header = false
+
+[cost]
+name = "normal_mean"
+"#;
+
+
+#[test]
+fn missing_spec_file_is_io_error() {
+    let path = std::env::temp_dir().join(format!("cpd_crate_error_missing_{}.toml", std::process::id()));
+    match RunSpec::from_file(&path).unwrap_err() {
+        Error::Io { path: p, source } => {
+            assert_eq!(p, path);
+            assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+        },
+        e => panic!("unexpected error: {e:?}"),
+    }
+}
+
+
+#[test]
+fn malformed_spec_is_config_error() {
+    let err = RunSpec::from_toml_str("penalty = [").unwrap_err();
+    assert!(matches!(err, Error::Config(_)), "{err:?}");
+    assert!(err.to_string().starts_with("Failed to parse run specification"));
+    assert!(err.source().is_some());
+}
+
+
+#[test]
+fn unknown_cost_is_cost_error() {
+    let dir = std::env::temp_dir().join(format!("cpd_crate_error_cost_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("series.csv"), "0.0\n0.1\n5.0\n5.1\n").unwrap();
+    let mut spec = RunSpec::from_toml_str(SPEC).unwrap();
+    spec.data.path = dir.join("series.csv");
+    spec.cost.name = "no_such_cost".to_owned();
+
+    let err = config::run(&spec).unwrap_err();
+    std::fs::remove_dir_all(&dir).unwrap();
+    match &err {
+        Error::Cost { name, .. } => assert_eq!(name, "no_such_cost"),
+        e => panic!("unexpected error: {e:?}"),
+    }
+    assert!(err.source().is_some_and(|e| e.is::<CalcDpError>()));
+}
+
+
+#[test]
+fn dp_error_converts_transparently() {
+    let err = Error::from(CalcDpError::new("broken"));
+    assert!(matches!(err, Error::Dp(_)));
+    assert_eq!(err.to_string(), "broken");
+}
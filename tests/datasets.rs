@@ -0,0 +1,28 @@
+//! 公開データの取得（`datasets`）の確認
+//!
+//! ネットワークに接続しないよう，保存先に取得済みのファイルを置いて確認する．
+
+use cpd_tools::datasets;
+use cpd_tools::Error;
+
+
+#[test]
+fn cached_file_is_read_without_download() {
+    let dir = std::env::temp_dir().join(format!("cpd_datasets_cached_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("nile.csv"), "\"rownames\",\"time\",\"value\"\n\"1\",1871,1120\n\"2\",1872,1160\n\"3\",1873,963\n").unwrap();
+
+    let values = datasets::fetch_to("nile", &dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(values, vec![1120.0, 1160.0, 963.0]);
+}
+
+
+#[test]
+fn unknown_dataset_lists_available_names() {
+    assert!(datasets::list().iter().any(|info| info.name == "nile"));
+    match datasets::fetch_to("no_such_data", &std::env::temp_dir()).unwrap_err() {
+        Error::Dp(e) => assert!(e.message.contains("nile"), "{}", e.message),
+        e => panic!("unexpected error: {e:?}"),
+    }
+}
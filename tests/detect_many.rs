@@ -0,0 +1,37 @@
+//! 多数の系列に対する逐次的な一括検出の確認
+
+use cpd_tools::batch::{detect_many, DetectSpec};
+use cpd_tools::config::CostSpec;
+use cpd_tools::detect::{Constraints, Method, Penalty};
+
+use process_param::Tau;
+
+
+fn series(shift: usize) -> Vec<Vec<f64>> {
+    let x = (0..40).map(|i| if i < 10 + shift { 0.0 } else { 4.0 } + 0.1 * (i as f64 * 1.3).sin()).collect::<Vec<f64>>();
+    vec![x, vec![0.5; 40]]
+}
+
+
+#[test]
+fn results_stream_with_errors_isolated() {
+    let spec = DetectSpec {
+        cost: CostSpec { name: "hetero_mean".to_owned(), params: Default::default() },
+        method: Method::Dp,
+        penalty: Penalty::NumChange(1),
+        constraints: Constraints::default(),
+    };
+    // 3番目の系列は分散の列が無いため失敗する
+    let inputs = (0..8).map(|i| if i == 3 { vec![vec![1.0; 40]] } else { series(i) }).collect::<Vec<_>>();
+    let mut items = detect_many(inputs, &spec).collect::<Vec<_>>();
+    assert_eq!(items.len(), 8);
+    items.sort_by_key(|item| item.index);
+    for (i, item) in items.iter().enumerate() {
+        assert_eq!(item.index, i);
+        match &item.result {
+            Ok(res) => assert_eq!(res.change_points, vec![(10 + i) as Tau]),
+            Err(_) => assert_eq!(i, 3),
+        }
+    }
+    assert!(items[3].result.is_err());
+}
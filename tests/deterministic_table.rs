@@ -0,0 +1,75 @@
+//! 評価値の表の作成方法による結果の一致の確認
+//!
+//! [`TableStrategy`]のいずれの方法で作成した表も，ビット単位で一致することを確認する．
+
+mod common;
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2, TableStrategy};
+
+
+/// 検証に用いる作成方法
+const STRATEGIES: [TableStrategy; 5] = [
+    TableStrategy::Parallel,
+    TableStrategy::Serial,
+    TableStrategy::FixedChunks(1),
+    TableStrategy::FixedChunks(7),
+    TableStrategy::FixedChunks(10_000),
+];
+
+
+/// 表の作成に用いる型
+type Table = common::Fit<common::Table>;
+
+
+/// 乱数を用いずに作成した系列のコスト関数
+fn cost(n: usize) -> HeteroscedasticMeanCost {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut data = Vec::with_capacity(n);
+    let mut variances = Vec::with_capacity(n);
+    for i in 0..n {
+        state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        let noise = (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+        let level = if i < n / 3 { 0.0 } else if i < 2 * n / 3 { 1.7 } else { -0.4 };
+        data.push(level + (i as f64 * 0.37).sin() * 0.1 + noise);
+        variances.push(0.5 + (i % 5) as f64 * 0.1);
+    }
+    HeteroscedasticMeanCost::new(&data, &variances).unwrap()
+}
+
+
+fn to_bits(table: &[Vec<f64>]) -> Vec<Vec<u64>> {
+    table.iter().map(|row| row.iter().map(|v| v.to_bits()).collect()).collect()
+}
+
+
+#[test]
+fn calc_dp_tables_are_bit_identical() {
+    let data = cost(150);
+    let t_max = data.t_max();
+    let reference = to_bits(&<Table as calc_dp::DictTT<f64, _>>::calc_value_all(&data, &t_max).unwrap());
+    for strategy in STRATEGIES {
+        let table = <Table as calc_dp::DictTT<f64, _>>::calc_value_all_with(&data, &t_max, strategy).unwrap();
+        assert_eq!(to_bits(&table), reference, "{strategy:?}");
+    }
+}
+
+
+#[test]
+fn calc_dp_2_tables_are_bit_identical() {
+    let data = cost(150);
+    let t_max = data.t_max();
+    let reference = to_bits(&<Table as calc_dp_2::DictTT<f64, _>>::calc_value_all(&data, &t_max).unwrap());
+    for strategy in STRATEGIES {
+        let table = <Table as calc_dp_2::DictTT<f64, _>>::calc_value_all_with(&data, &t_max, strategy).unwrap();
+        assert_eq!(to_bits(&table), reference, "{strategy:?}");
+    }
+}
+
+
+#[test]
+fn zero_chunk_size_is_rejected() {
+    let data = cost(10);
+    let t_max = data.t_max();
+    assert!(<Table as calc_dp::DictTT<f64, _>>::calc_value_all_with(&data, &t_max, TableStrategy::FixedChunks(0)).is_err());
+}
@@ -0,0 +1,62 @@
+//! `CalcTT`のみを実装した型に対する評価値の表の確認
+
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2, CalcDpError};
+use calc_dp::{DictTT, DictToFunc};
+
+use process_param::Tau;
+
+
+/// 区間の和の2乗を区間の長さで除した値を評価値とする
+struct Square;
+
+impl Square {
+    fn value(data: &[f64], t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let seg = data.get(t_k_1 as usize..t_k as usize).ok_or_else(|| CalcDpError::new("out of range"))?;
+        Ok(seg.iter().sum::<f64>().powi(2) / seg.len() as f64)
+    }
+}
+
+impl calc_dp::CalcTT<f64, Vec<f64>> for Square {
+    fn calc_value(data: &Vec<f64>, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        Square::value(data, t_k_1, t_k)
+    }
+}
+
+impl calc_dp_2::CalcTT<f64, Vec<f64>> for Square {
+    fn calc_value(data: &Vec<f64>, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        Square::value(data, t_k_1, t_k)
+    }
+}
+
+
+fn data() -> Vec<f64> {
+    (0..12).map(|t| (t as f64 * 0.7).sin() + if t < 5 { 0.0 } else { 2.0 }).collect()
+}
+
+
+#[test]
+fn calc_value_alone_gives_table_and_evaluation() {
+    let data = data();
+    let dict = calc_dp::DictCache::<Square, f64>::new(&data, &12).unwrap();
+    for (t_k_1, t_k) in [(0, 1), (0, 12), (4, 9), (11, 12)] {
+        assert_eq!(dict.value_tt(t_k_1, t_k).unwrap(), Square::value(&data, t_k_1, t_k).unwrap());
+    }
+    let expected = Square::value(&data, 0, 5).unwrap() + Square::value(&data, 5, 8).unwrap() + Square::value(&data, 8, 12).unwrap();
+    assert_eq!(dict.evaluate(&[5, 8, 12]).unwrap(), expected);
+    assert_eq!(dict.evaluate(&[5, 8, 12]).unwrap(), dict.sum_frol_cp(&[5, 8, 12]).unwrap());
+    assert!(dict.value_tt(3, 13).is_err());
+}
+
+
+#[test]
+fn min_gap_2_table_uses_its_own_layout() {
+    use calc_dp_2::{DictTT, DictToFunc};
+
+    let data = data();
+    let dict = calc_dp_2::DictCache::<Square, f64>::new(&data, &12).unwrap();
+    for (t_k_1, t_k) in [(0, 2), (0, 12), (4, 9), (10, 12)] {
+        assert_eq!(dict.value_tt(t_k_1, t_k).unwrap(), Square::value(&data, t_k_1, t_k).unwrap());
+    }
+    let expected = Square::value(&data, 0, 5).unwrap() + Square::value(&data, 5, 12).unwrap();
+    assert_eq!(dict.evaluate(&[5, 12]).unwrap(), expected);
+}
@@ -0,0 +1,118 @@
+//! 拡張精度の累積による動的計画法の確認
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::{calc_dp, CalcDpError, F64x2};
+
+use process_param::{Tau, NumChg};
+
+
+/// 最初の区間の評価値が大きく，2個目以降の区間の評価値の差が`f64`の累積で失われる評価値
+///
+/// 変化点1個の最適解は$ t_1 = 6 $である．
+struct Offset;
+
+impl Offset {
+    fn value(t_k_1: Tau, t_k: Tau) -> f64 {
+        if t_k_1 == 0 {
+            1e16
+        } else {
+            -0.01 * (t_k_1 as f64 - 6.0).powi(2) - 0.001 * (t_k - t_k_1) as f64
+        }
+    }
+}
+
+type Memo<V> = Vec<Vec<Option<(Tau, NumChg, V)>>>;
+
+impl calc_dp::CalcTT<f64, Offset> for Offset {
+    fn calc_value(_: &Offset, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        Ok(Offset::value(t_k_1, t_k))
+    }
+}
+
+impl calc_dp::CalcDP<f64, Offset> for Offset {
+    fn memo_all(&self) -> Memo<f64> {
+        Vec::new()
+    }
+}
+
+impl calc_dp::CalcTT<F64x2, Offset> for Offset {
+    fn calc_value(_: &Offset, t_k_1: Tau, t_k: Tau) -> Result<F64x2, CalcDpError> {
+        Ok(F64x2::from(Offset::value(t_k_1, t_k)))
+    }
+}
+
+impl calc_dp::CalcDP<F64x2, Offset> for Offset {
+    fn memo_all(&self) -> Memo<F64x2> {
+        Vec::new()
+    }
+}
+
+
+/// メモを保持する型
+struct Fit<V>(Memo<V>);
+
+impl<V> calc_dp::CalcTT<V, HeteroscedasticMeanCost> for Fit<V>
+where
+    HeteroscedasticMeanCost: calc_dp::CalcTT<V, HeteroscedasticMeanCost>,
+{
+    fn calc_value(data: &HeteroscedasticMeanCost, t_k_1: Tau, t_k: Tau) -> Result<V, CalcDpError> {
+        <HeteroscedasticMeanCost as calc_dp::CalcTT<V, _>>::calc_value(data, t_k_1, t_k)
+    }
+}
+
+impl<V> calc_dp::CalcDP<V, HeteroscedasticMeanCost> for Fit<V>
+where
+    V: std::iter::Sum + PartialOrd + Clone + Send + std::fmt::Debug,
+    HeteroscedasticMeanCost: calc_dp::CalcTT<V, HeteroscedasticMeanCost>,
+{
+    fn memo_all(&self) -> Memo<V> {
+        self.0.clone()
+    }
+}
+
+
+/// 最後の時期，変化点個数1のメモの一つ前の変化点
+fn last_change<V: Clone>(memo: &Memo<V>) -> Tau {
+    memo[1].last().cloned().flatten().unwrap().0
+}
+
+
+#[test]
+fn addition_is_exact() {
+    let big = F64x2::from(1e16);
+    let sum = [big, F64x2::from(1.0), F64x2::from(1.0), F64x2::from(-1e16)].into_iter().sum::<F64x2>();
+    assert_eq!(sum.to_f64(), 2.0);
+    assert_eq!(1e16 + 1.0 + 1.0 - 1e16, 0.0);
+
+    let x = F64x2::new(1.0, 1e-20);
+    assert!(x > F64x2::from(1.0));
+    assert_eq!((x + F64x2::from(f64::NEG_INFINITY)).to_f64(), f64::NEG_INFINITY);
+    assert!(F64x2::from(f64::NAN).partial_cmp(&x).is_none());
+}
+
+
+#[test]
+fn extended_accumulation_keeps_optimum() {
+    let t_max = 12;
+    let plain = <Offset as calc_dp::CalcDP<f64, _>>::calc_memo_all(&Offset, &t_max).unwrap();
+    let extended = <Offset as calc_dp::CalcDP<F64x2, _>>::calc_memo_all(&Offset, &t_max).unwrap();
+    // f64の累積では全候補が同じ値に丸められ，最後の候補が選ばれる
+    assert_ne!(last_change(&plain), 6);
+    assert_eq!(last_change(&extended), 6);
+}
+
+
+#[test]
+fn built_in_cost_emits_f64x2() {
+    let data = (0..30).map(|t| if t < 17 { 1.0 } else { -1.0 } + 0.2 * (t as f64 * 2.3).sin()).collect::<Vec<f64>>();
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    let t_max = cost.t_max();
+    let plain = Fit(<Fit<f64> as calc_dp::CalcDP<f64, _>>::calc_memo_all(&cost, &t_max).unwrap());
+    let extended = Fit(<Fit<F64x2> as calc_dp::CalcDP<F64x2, _>>::calc_memo_all(&cost, &t_max).unwrap());
+    for k in 0..4 {
+        let a = calc_dp::CalcDP::get_value_history(&plain, &t_max, &k).unwrap();
+        let b = calc_dp::CalcDP::get_value_history(&extended, &t_max, &k).unwrap();
+        assert_eq!(a.iter().map(|e| e.0).collect::<Vec<_>>(), b.iter().map(|e| e.0).collect::<Vec<_>>());
+        assert!((a[0].2 - b[0].2.to_f64()).abs() <= 1e-9 * a[0].2.abs().max(1.0));
+    }
+}
@@ -0,0 +1,70 @@
+//! メモから最適な変化点を取得する機能の確認
+
+mod common;
+use common::{mean_cost, Memo};
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2};
+
+use process_param::{Tau, NumChg};
+
+
+type Fit = common::Fit<Memo>;
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    let data = [0.1, -0.2, 0.0, 3.1, 2.9, 3.0, 3.2, -1.0, -1.1, -0.9, 2.0, 2.1];
+    mean_cost(data.len(), 0.25, |i| data[i])
+}
+
+
+/// 変化点で区切った各区間の評価値の総和
+fn total(cost: &HeteroscedasticMeanCost, change_points: &[Tau], t: Tau) -> f64 {
+    let mut bounds = vec![0];
+    bounds.extend_from_slice(change_points);
+    bounds.push(t);
+    bounds.windows(2).map(|w| cost.segment_value(w[0], w[1]).unwrap()).sum()
+}
+
+
+#[test]
+fn change_points_gap1() {
+    let data = cost();
+    let t_max = data.t_max();
+    let fit = Fit::new(<Fit as calc_dp::CalcDP<f64, _>>::calc_memo_all(&data, &t_max).unwrap());
+    for k in 0..=<Fit as calc_dp::CalcDP<f64, _>>::calc_max_k(&t_max) {
+        let (cps, value) = <Fit as calc_dp::CalcDP<f64, _>>::get_change_points_with_value(&fit, &t_max, &k).unwrap();
+        assert_eq!(cps.len(), k as usize);
+        assert!(cps.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(cps, <Fit as calc_dp::CalcDP<f64, _>>::get_change_points(&fit, &t_max, &k).unwrap());
+        assert_eq!(value, <Fit as calc_dp::CalcDP<f64, _>>::get_value(&fit, &t_max, &k).unwrap());
+        assert!((value - total(&data, &cps, t_max)).abs() < 1e-9, "k = {k}");
+    }
+    assert_eq!(<Fit as calc_dp::CalcDP<f64, _>>::get_change_points(&fit, &t_max, &3).unwrap(), vec![3, 7, 10]);
+    // 途中の期数までの変化点
+    assert_eq!(<Fit as calc_dp::CalcDP<f64, _>>::get_change_points(&fit, &7, &1).unwrap(), vec![3]);
+}
+
+
+#[test]
+fn change_points_gap2() {
+    let data = cost();
+    let t_max = data.t_max();
+    let fit = Fit::new(<Fit as calc_dp_2::CalcDP<f64, _>>::calc_memo_all(&data, &t_max).unwrap());
+    for k in 0..=<Fit as calc_dp_2::CalcDP<f64, _>>::calc_max_k(&t_max) {
+        let (cps, value) = <Fit as calc_dp_2::CalcDP<f64, _>>::get_change_points_with_value(&fit, &t_max, &k).unwrap();
+        assert_eq!(cps.len(), k as usize);
+        assert!(cps.windows(2).all(|w| w[1] - w[0] >= 2));
+        assert!((value - total(&data, &cps, t_max)).abs() < 1e-9, "k = {k}");
+    }
+}
+
+
+#[test]
+fn change_points_out_of_range() {
+    let data = cost();
+    let t_max = data.t_max();
+    let fit = Fit::new(<Fit as calc_dp::CalcDP<f64, _>>::calc_memo_all(&data, &t_max).unwrap());
+    assert!(<Fit as calc_dp::CalcDP<f64, _>>::get_change_points(&fit, &t_max, &(t_max as NumChg)).is_err());
+    assert!(<Fit as calc_dp::CalcDP<f64, _>>::get_change_points(&fit, &0, &0).is_err());
+}
@@ -0,0 +1,101 @@
+//! 区間の最小の長さが異なる動的計画法の機能の一致の確認
+//!
+//! [`calc_dp`]および[`calc_dp_2`]の各traitが同じ機能を持ち，
+//! 評価値の計算に用いる変数を持たない場合に[`calc_dp::CalcDPWithVari`]等が`CalcDP`と一致することを確認する．
+//! また，添字を検査しないメモの参照が検査付きの参照と一致することを確認する．
+
+mod common;
+use common::{mean_cost, Fit, Memo, VariMemo};
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2};
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    mean_cost(40, 0.5, |i| if i < 15 { (i as f64 * 0.7).sin() } else if i < 28 { 2.0 + (i as f64 * 1.3).cos() } else { -1.0 })
+}
+
+
+macro_rules! check_parity {
+    ($m:ident) => {{
+        let data = cost();
+        let t_max = data.t_max();
+        let fit: Fit<Memo> = Fit::new(<Fit<Memo> as $m::CalcDP<f64, _>>::calc_memo_all(&data, &t_max).unwrap());
+        let vari: Fit<VariMemo> = Fit::new(<Fit<VariMemo> as $m::CalcDPWithVari<f64, (), _>>::calc_memo_all(&data, &t_max).unwrap());
+        let k_max = <Fit<Memo> as $m::CalcDP<f64, _>>::calc_max_k(&t_max);
+        assert_eq!(k_max, <Fit<VariMemo> as $m::CalcDPWithVari<f64, (), _>>::calc_max_k(&t_max));
+        for k in 0..=k_max {
+            let history = <Fit<Memo> as $m::CalcDP<f64, _>>::get_value_history(&fit, &t_max, &k).unwrap();
+            let history_vari = <Fit<VariMemo> as $m::CalcDPWithVari<f64, (), _>>::get_value_history(&vari, &t_max, &k).unwrap();
+            assert_eq!(history.len(), history_vari.len());
+            for (a, b) in history.iter().zip(history_vari.iter()) {
+                assert_eq!((a.0, a.1, a.2.to_bits()), (b.0, b.1, b.3.to_bits()));
+            }
+            let forward = <Fit<VariMemo> as $m::CalcDPWithVari<f64, (), _>>::get_value_history_forward(&vari, &t_max, &k).unwrap();
+            assert_eq!(forward.len(), history.len());
+            assert_eq!(forward.last().map(|v| v.0), Some(t_max));
+        }
+        for t in 1..=t_max {
+            for k in 0..=<Fit<Memo> as $m::CalcDP<f64, _>>::calc_max_k(&t) {
+                assert_eq!(<Fit<Memo> as $m::CalcDP<f64, _>>::get_from_memo_unchecked(&t, &k, &fit.0),
+                           <Fit<Memo> as $m::CalcDP<f64, _>>::get_from_memo(&t, &k, &fit.0).unwrap());
+                assert_eq!(<Fit<VariMemo> as $m::CalcDPWithVari<f64, (), _>>::get_from_memo_unchecked(&t, &k, &vari.0),
+                           <Fit<VariMemo> as $m::CalcDPWithVari<f64, (), _>>::get_from_memo(&t, &k, &vari.0).unwrap());
+            }
+        }
+        k_max
+    }};
+}
+
+
+#[test]
+fn calc_dp_variants_agree() {
+    assert_eq!(check_parity!(calc_dp), 39);
+}
+
+
+#[test]
+fn calc_dp_2_variants_agree() {
+    assert_eq!(check_parity!(calc_dp_2), 19);
+}
+
+
+/// 事前の検査を通過した添字に対して，検査しない参照が検査付きの参照と一致することを確認する
+macro_rules! check_unchecked {
+    ($m:ident) => {{
+        let data = cost();
+        let t_max = data.t_max();
+        let fit: Fit<Memo> = Fit::new(<Fit<Memo> as $m::CalcDP<f64, _>>::calc_memo_all(&data, &t_max).unwrap());
+        let vari: Fit<VariMemo> = Fit::new(<Fit<VariMemo> as $m::CalcDPWithVari<f64, (), _>>::calc_memo_all(&data, &t_max).unwrap());
+        let k_max = <Fit<Memo> as $m::CalcDP<f64, _>>::calc_max_k(&t_max);
+        let mut n_checked = 0;
+        for t in 0..=(t_max + 1) {
+            for k in 0..=(k_max + 1) {
+                match <Fit<Memo> as $m::CalcDP<f64, _>>::check_idx_memo(&t, &k, &fit.0) {
+                    Ok(()) => {
+                        n_checked += 1;
+                        assert_eq!(<Fit<Memo> as $m::CalcDP<f64, _>>::get_from_memo_unchecked(&t, &k, &fit.0),
+                                   <Fit<Memo> as $m::CalcDP<f64, _>>::get_from_memo(&t, &k, &fit.0).unwrap());
+                    },
+                    Err(_) => assert!(<Fit<Memo> as $m::CalcDP<f64, _>>::get_from_memo(&t, &k, &fit.0).is_err()),
+                }
+                match <Fit<VariMemo> as $m::CalcDPWithVari<f64, (), _>>::check_idx_memo(&t, &k, &vari.0) {
+                    Ok(()) => assert_eq!(<Fit<VariMemo> as $m::CalcDPWithVari<f64, (), _>>::get_from_memo_unchecked(&t, &k, &vari.0),
+                                         <Fit<VariMemo> as $m::CalcDPWithVari<f64, (), _>>::get_from_memo(&t, &k, &vari.0).unwrap()),
+                    Err(_) => assert!(<Fit<VariMemo> as $m::CalcDPWithVari<f64, (), _>>::get_from_memo(&t, &k, &vari.0).is_err()),
+                }
+            }
+        }
+        n_checked
+    }};
+}
+
+
+#[test]
+fn unchecked_access_after_check() {
+    // 検査を通過する添字は，期数tと変化点個数kの実現可能な組み合わせのみ
+    let n_gap1 = (1..=40).sum::<usize>();
+    let n_gap2 = (1..=40).map(|t: usize| t.div_ceil(2)).sum::<usize>();
+    assert_eq!(check_unchecked!(calc_dp), n_gap1);
+    assert_eq!(check_unchecked!(calc_dp_2), n_gap2);
+}
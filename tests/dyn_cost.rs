@@ -0,0 +1,81 @@
+//! トレイトオブジェクトとして扱えるコスト関数の確認
+
+use cpd_tools::cost::{CalcTTCost, DynCost, DynSegment, SegmentCost};
+use cpd_tools::detect::{self, Constraints, FitResult, Method, Penalty};
+use cpd_tools::dp_tools::{calc_dp, CalcDpError, SeriesData};
+
+use process_param::{Tau, NumChg};
+
+
+/// 区間の平均からの残差平方和の符号を反転した評価値
+struct NegSse;
+
+impl calc_dp::CalcTT<f64, SeriesData<f64>> for NegSse {
+    fn calc_value(data: &SeriesData<f64>, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let seg = data.segment(t_k_1, t_k)?;
+        let mean = seg.iter().sum::<f64>() / seg.len() as f64;
+        Ok(-seg.iter().map(|x| (x - mean).powi(2)).sum::<f64>())
+    }
+}
+
+impl calc_dp::CalcDP<f64, SeriesData<f64>> for NegSse {
+    fn memo_all(&self) -> Vec<Vec<Option<(Tau, NumChg, f64)>>> {
+        Vec::new()
+    }
+}
+
+
+/// 動的計画法のメモ
+struct Memo(Vec<Vec<Option<(Tau, NumChg, f64)>>>);
+
+impl calc_dp::CalcTT<f64, SeriesData<f64>> for Memo {
+    fn calc_value(data: &SeriesData<f64>, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        NegSse::calc_value(data, t_k_1, t_k)
+    }
+}
+
+impl calc_dp::CalcDP<f64, SeriesData<f64>> for Memo {
+    fn memo_all(&self) -> Vec<Vec<Option<(Tau, NumChg, f64)>>> {
+        self.0.clone()
+    }
+}
+
+
+/// 設定の名前からコスト関数を選ぶ
+fn select(name: &str) -> Box<dyn DynCost> {
+    match name {
+        "sse" => CalcTTCost::<NegSse>::boxed(),
+        _ => Box::new(|data: &SeriesData<f64>, t_k_1: Tau, t_k: Tau| -> Result<f64, CalcDpError> {
+            let seg = data.segment(t_k_1, t_k)?;
+            Ok(-seg.iter().map(|x| x.abs()).sum::<f64>())
+        }),
+    }
+}
+
+
+#[test]
+fn runtime_selected_cost_detects_changes() {
+    let values = (0..40).map(|t| if t < 15 { 0.0 } else { 3.0 } + 0.1 * (t as f64 * 1.3).sin()).collect::<Vec<f64>>();
+    let data = SeriesData::new(values).unwrap();
+    let cost = select("sse");
+    let segment = DynSegment::new(cost.as_ref(), &data);
+    assert_eq!(segment.t_max(), 40);
+    assert_eq!(segment.segment_value(3, 9).unwrap(), <NegSse as calc_dp::CalcTT<f64, _>>::calc_value(&data, 3, 9).unwrap());
+    assert!(segment.segment_value(9, 3).is_err());
+
+    for method in [Method::Dp, Method::Dp2] {
+        let res = detect::detect(&segment, method, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+        assert_eq!(res.change_points, vec![15], "{method:?}");
+    }
+
+    // CalcTTを直接用いた動的計画法と一致する
+    let direct = Memo(<NegSse as calc_dp::CalcDP<f64, _>>::calc_memo_all(&data, &40).unwrap());
+    let fit = FitResult::fit(&segment, Method::Dp, Some(3)).unwrap();
+    for k in 0..=3 {
+        assert_eq!(fit.value(k).unwrap(), calc_dp::CalcDP::get_value(&direct, &40, &k).unwrap());
+    }
+
+    let other = select("abs");
+    let res = detect::detect(&DynSegment::new(other.as_ref(), &data), Method::Dp, &Penalty::NumChange(0), &Constraints::default()).unwrap();
+    assert_eq!(res.value, -data.values().iter().map(|x| x.abs()).sum::<f64>());
+}
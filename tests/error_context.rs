@@ -0,0 +1,66 @@
+//! エラーに付加される計算の段階と位置の確認
+
+use cpd_tools::cost::SegmentCost;
+use cpd_tools::detect::{self, Constraints, Method, Penalty};
+use cpd_tools::dp_tools::{CalcDpError, ErrorContext};
+use cpd_tools::search;
+
+use process_param::Tau;
+
+
+/// 時点7で終わる区間でエラーとなるコスト関数
+struct Broken;
+
+impl SegmentCost for Broken {
+    fn t_max(&self) -> Tau {
+        12
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        if t_k == 7 {
+            Err(CalcDpError::new("broken segment"))
+        } else {
+            Ok(-((t_k - t_k_1) as f64))
+        }
+    }
+}
+
+
+#[test]
+fn display_lists_context_from_inner_to_outer() {
+    let err = CalcDpError::new("bad value")
+        .with_context(ErrorContext::new("filling DP memo").at(7, 1))
+        .with_context(ErrorContext::new("fitting change points").segment(3, 7).cost::<Broken>());
+    assert_eq!(err.message, "bad value");
+    assert_eq!(err.to_string(),
+               format!("bad value (while filling DP memo at t = 7, k = 1) (while fitting change points at segment (3, 7], cost {})",
+                       std::any::type_name::<Broken>()));
+    assert_eq!(CalcDpError::new("plain").to_string(), "plain");
+}
+
+
+#[test]
+fn dp_attaches_cell_and_cost() {
+    for method in [Method::Dp, Method::Dp2] {
+        let err = detect::detect(&Broken, method, &Penalty::NumChange(2), &Constraints::default()).unwrap_err();
+        assert_eq!(err.message, "broken segment");
+        let fill = err.context.iter().find(|c| c.stage == "filling DP memo").expect("no DP context");
+        assert_eq!(fill.t, Some(7));
+        assert!(fill.k.is_some());
+        assert!(err.context.iter().any(|c| c.cost == Some(std::any::type_name::<Broken>())), "{err}");
+    }
+}
+
+
+#[test]
+fn search_attaches_segment() {
+    let err = search::binseg(&Broken, 1, &Penalty::NumChange(2), &Constraints::default()).unwrap_err();
+    assert!(err.context.iter().any(|c| c.segment.is_some_and(|(_, t_k)| t_k == 7)), "{err}");
+
+    let err = search::pelt(&Broken, 1, 1.0).unwrap_err();
+    let pelt = err.context.iter().find(|c| c.stage == "PELT").expect("no PELT context");
+    assert_eq!(pelt.t, Some(7));
+    assert_eq!(pelt.segment.map(|(_, t_k)| t_k), Some(7));
+}
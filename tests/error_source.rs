@@ -0,0 +1,45 @@
+//! エラーの原因の連鎖（`source`）の確認
+
+use cpd_tools::dp_tools::CalcDpError;
+use cpd_tools::io::{self, ColumnRef};
+
+use std::error::Error;
+
+
+#[test]
+fn missing_file_keeps_io_error() {
+    let path = std::env::temp_dir().join(format!("cpd_error_source_missing_{}.csv", std::process::id()));
+    let err = io::read_csv_columns(&path, &[ColumnRef::Index(0)], false, ',').unwrap_err();
+    let source = err.source().expect("no source");
+    let io_err = source.downcast_ref::<std::io::Error>().expect("source is not an I/O error");
+    assert_eq!(io_err.kind(), std::io::ErrorKind::NotFound);
+}
+
+
+#[test]
+fn parse_error_is_chained_through_file_error() {
+    let path = std::env::temp_dir().join(format!("cpd_error_source_parse_{}.csv", std::process::id()));
+    std::fs::write(&path, "1.0\nabc\n").unwrap();
+    let err = io::read_csv_columns(&path, &[ColumnRef::Index(0)], false, ',').unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(err.message.contains("Line 2"), "{}", err.message);
+    let inner = err.source().and_then(|e| e.downcast_ref::<CalcDpError>()).expect("source is not the parse error");
+    assert!(inner.source().is_some_and(|e| e.is::<std::num::ParseFloatError>()));
+}
+
+
+#[test]
+fn from_conversions_keep_source() {
+    fn parse(s: &str) -> Result<f64, CalcDpError> {
+        Ok(s.parse::<f64>()?)
+    }
+    let err = parse("x").unwrap_err();
+    assert!(err.source().is_some_and(|e| e.is::<std::num::ParseFloatError>()));
+
+    let err = CalcDpError::from(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied"));
+    assert!(err.message.contains("denied"));
+    assert!(err.source().is_some_and(|e| e.is::<std::io::Error>()));
+
+    assert!(CalcDpError::new("plain").source().is_none());
+}
@@ -0,0 +1,59 @@
+//! 複数の変化点群に対する評価関数の一括計算の確認
+
+mod common;
+use common::mean_cost;
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2};
+
+use process_param::Tau;
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    mean_cost(30, 1.0, |t| if t < 12 { 0.0 } else { 1.5 } + 0.3 * (t as f64 * 2.3).sin())
+}
+
+
+/// 変化点群の候補（最後の要素は系列の長さ）
+fn candidates(t_max: Tau) -> Vec<Vec<Tau>> {
+    let mut cands = (2..t_max - 1).step_by(3).map(|t| vec![t, t_max]).collect::<Vec<_>>();
+    cands.push(vec![t_max]);
+    cands.push(vec![6, 12, 20, t_max]);
+    cands
+}
+
+
+#[test]
+fn batch_matches_individual_evaluation() {
+    use calc_dp::DictToFunc;
+
+    let cost = cost();
+    let t_max = cost.t_max();
+    let dict = calc_dp::DictCache::<HeteroscedasticMeanCost, f64>::new(&cost, &t_max).unwrap();
+    let cands = candidates(t_max);
+    let batch = dict.evaluate_batch(&cands).unwrap();
+    assert_eq!(batch.len(), cands.len());
+    for (cps, val) in cands.iter().zip(&batch) {
+        assert_eq!(*val, dict.evaluate(cps).unwrap());
+    }
+    assert!(dict.evaluate_batch(&[]).unwrap().is_empty());
+
+    let mut invalid = cands.clone();
+    invalid.push(vec![20, 10]);
+    assert!(dict.evaluate_batch(&invalid).is_err());
+}
+
+
+#[test]
+fn min_gap_2_batch_matches_individual_evaluation() {
+    use calc_dp_2::DictToFunc;
+
+    let cost = cost();
+    let t_max = cost.t_max();
+    let dict = calc_dp_2::DictCache::<HeteroscedasticMeanCost, f64>::new(&cost, &t_max).unwrap();
+    let cands = candidates(t_max);
+    let batch = dict.evaluate_batch(&cands).unwrap();
+    for (cps, val) in cands.iter().zip(&batch) {
+        assert_eq!(*val, dict.evaluate(cps).unwrap());
+    }
+}
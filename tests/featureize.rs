@@ -0,0 +1,72 @@
+//! 区間ごとの固定長の特徴量の確認
+
+use cpd_tools::segment::{featureize, FEATURE_NAMES};
+
+
+fn column(name: &str) -> usize {
+    FEATURE_NAMES.iter().position(|n| *n == name).unwrap()
+}
+
+
+#[test]
+fn features_describe_each_regime() {
+    // 平坦な区間，上昇する区間，周期0.25の振動
+    let data = (0..120).map(|t| {
+                           let t = t as f64;
+                           if t < 40.0 { 1.0 + 0.01 * (t * 1.3).sin() } else if t < 80.0 { 0.5 * (t - 40.0) } else { (std::f64::consts::FRAC_PI_2 * t).sin() }
+                       })
+                       .collect::<Vec<f64>>();
+    let features = featureize(&data, &[40, 80]).unwrap();
+    assert_eq!(features.values.dim(), (3, FEATURE_NAMES.len()));
+    assert_eq!(features.segments, vec![(0, 40), (40, 80), (80, 120)]);
+    assert!(features.values.iter().all(|v| v.is_finite()));
+
+    assert_eq!(features.values[[0, column("length")]], 40.0);
+    assert!((features.values[[0, column("mean")]] - 1.0).abs() < 0.01);
+    assert!((features.values[[1, column("slope")]] - 0.5).abs() < 1e-9);
+    assert!((features.values[[2, column("dominant_frequency")]] - 0.25).abs() < 1e-9);
+    assert!(features.values[[2, column("high_frequency_power")]] > 0.5, "{}", features.values[[2, column("high_frequency_power")]]);
+    assert!(features.values[[2, column("low_frequency_power")]] < 0.1, "{}", features.values[[2, column("low_frequency_power")]]);
+    assert!(features.values[[2, column("lag1_autocorrelation")]].abs() < 0.1);
+}
+
+
+#[test]
+fn constant_segment_has_finite_features() {
+    let features = featureize(&[2.0; 10], &[3]).unwrap();
+    assert!(features.values.iter().all(|v| v.is_finite()));
+    assert_eq!(features.values[[1, column("sd")]], 0.0);
+    assert!(featureize(&[1.0, f64::NAN], &[]).is_err());
+    assert!(featureize(&[1.0; 5], &[5]).is_err());
+}
+
+
+#[test]
+fn npy_and_csv_layouts() {
+    let data = (0..30).map(|t| (t as f64 * 0.7).cos()).collect::<Vec<f64>>();
+    let features = featureize(&data, &[10]).unwrap();
+
+    let npy = features.to_npy();
+    assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = std::str::from_utf8(&npy[10..(10 + header_len)]).unwrap();
+    assert!(header.contains(&format!("'shape': (2, {}),", FEATURE_NAMES.len())));
+    assert!(header.ends_with('\n'));
+    let body = &npy[(10 + header_len)..];
+    assert_eq!(body.len(), 8 * 2 * FEATURE_NAMES.len());
+    let first = f64::from_le_bytes(body[..8].try_into().unwrap());
+    assert_eq!(first, 10.0);
+
+    let csv = features.to_csv();
+    let lines = csv.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("start,end,length,mean"));
+    assert!(lines[2].starts_with("10,30,20,"));
+
+    let dir = std::env::temp_dir().join(format!("cpd_featureize_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    features.write_npy(&dir.join("features.npy")).unwrap();
+    assert_eq!(std::fs::read(dir.join("features.npy")).unwrap(), npy);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
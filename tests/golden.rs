@@ -0,0 +1,65 @@
+//! 参照データセットに対する検出結果の回帰テスト
+//!
+//! `testdata/golden/`の各設定ファイルに記載した`[expected]`の変化点および評価値を，
+//! 全ての動的計画法の手法で再現できることを確認する．
+
+use cpd_tools::config::{self, RunSpec};
+use cpd_tools::detect::Method;
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+
+/// 評価値の許容誤差（相対）
+const VALUE_TOLERANCE: f64 = 1e-9;
+
+
+#[derive(Deserialize)]
+struct GoldenCase {
+    expected: Expected,
+}
+
+#[derive(Deserialize)]
+struct Expected {
+    change_points: Vec<u32>,
+    value: f64,
+}
+
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata").join("golden")
+}
+
+
+fn check_case(name: &str) {
+    let path = golden_dir().join(format!("{name}.toml"));
+    let text = std::fs::read_to_string(&path).unwrap();
+    let expected = toml::from_str::<GoldenCase>(&text).unwrap().expected;
+    let mut spec = RunSpec::from_file(&path).unwrap();
+    spec.output.path = None;
+
+    for method in [Method::Dp, Method::Dp2] {
+        spec.method = method;
+        let result = config::run(&spec).unwrap();
+        assert_eq!(result.change_points, expected.change_points, "{name} ({method:?})");
+        assert!((result.value - expected.value).abs() <= VALUE_TOLERANCE * expected.value.abs().max(1.0),
+                "{name} ({method:?}): value {} differs from {}", result.value, expected.value);
+    }
+}
+
+
+#[test]
+fn nile() {
+    check_case("nile");
+}
+
+#[test]
+fn sim_mean_shift() {
+    check_case("sim_mean_shift");
+}
+
+#[test]
+fn sim_correlation() {
+    check_case("sim_correlation");
+}
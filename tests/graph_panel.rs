@@ -0,0 +1,61 @@
+//! 系列間の隣接関係を考慮したパネルの変化点検出の確認
+
+use cpd_tools::cost::HeteroscedasticMeanCost;
+use cpd_tools::panel::{detect_graph, Adjacency, GraphConfig, Panel};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand_distr::{Distribution, StandardNormal};
+
+
+/// 直列に並ぶ6台の設備．いずれも40時点目で平均が0.9だけ変化する．
+fn line(seed: u64) -> Vec<HeteroscedasticMeanCost> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..6).map(|_| {
+              let data = (0..80).map(|t| {
+                                    let z: f64 = StandardNormal.sample(&mut rng);
+                                    z + if t >= 40 { 0.9 } else { 0.0 }
+                                })
+                                .collect::<Vec<f64>>();
+              HeteroscedasticMeanCost::new(&data, &vec![1.0; 80]).unwrap()
+          })
+          .collect()
+}
+
+
+#[test]
+fn smoothing_aligns_neighbouring_change_points() {
+    let costs = line(11);
+    let panel = Panel::new(&costs).unwrap();
+    let adjacency = Adjacency::new(6, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5)]).unwrap();
+
+    let independent = GraphConfig { penalty: 8.0, tolerance: 2, min_size: 5, ..Default::default() };
+    let base = detect_graph(&panel, &adjacency, &independent).unwrap();
+    assert_eq!(base.sweeps, 0);
+    assert!(base.converged);
+
+    let smoothed = GraphConfig { smoothing: 3.0, ..independent };
+    let res = detect_graph(&panel, &adjacency, &smoothed).unwrap();
+    assert!(res.converged);
+    assert!(res.sweeps >= 1);
+    assert!(res.objective >= base.objective);
+    let near = |r: &cpd_tools::detect::DetectionResult| r.change_points.iter().filter(|t| t.abs_diff(40) <= 3).count();
+    let found_base = base.results.iter().map(near).sum::<usize>();
+    let found = res.results.iter().map(near).sum::<usize>();
+    assert!(found > found_base, "{found} <= {found_base}");
+    assert!(res.results.iter().all(|r| r.change_points.len() <= 1), "{:?}", res.results.iter().map(|r| &r.change_points).collect::<Vec<_>>());
+}
+
+
+#[test]
+fn invalid_adjacency_is_an_error() {
+    let costs = line(12);
+    let panel = Panel::new(&costs).unwrap();
+    assert!(Adjacency::new(6, &[(0, 6)]).is_err());
+    assert!(Adjacency::new(6, &[(2, 2)]).is_err());
+    assert!(Adjacency::with_weights(6, &[(0, 1, -1.0)]).is_err());
+    let small = Adjacency::new(5, &[(0, 1)]).unwrap();
+    assert!(detect_graph(&panel, &small, &GraphConfig::default()).is_err());
+    let line = Adjacency::new(6, &[(0, 1)]).unwrap();
+    assert!(detect_graph(&panel, &line, &GraphConfig { tolerance: 2, min_size: 4, ..Default::default() }).is_err());
+}
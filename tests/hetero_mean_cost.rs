@@ -0,0 +1,61 @@
+//! 観測ごとに既知の分散を持つ正規分布の平均変化のコスト関数（[`HeteroscedasticMeanCost`]）の確認
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost, SegmentParameter};
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+
+#[test]
+fn matches_hand_computation() {
+    let cost = HeteroscedasticMeanCost::new(&[1.0, 3.0, 2.0], &[1.0, 4.0, 0.5]).unwrap();
+    // 重み (1, 0.25) より平均 (1 + 0.75) / 1.25 = 1.4
+    assert!((cost.mean(0, 2).unwrap() - 1.4).abs() < 1e-12);
+    assert_eq!(cost.segment_parameter(0, 2).unwrap(), vec![cost.mean(0, 2).unwrap()]);
+    // -0.5 * (1 * 0.4^2 + 0.25 * 1.6^2) = -0.4
+    assert!((cost.segment_value(0, 2).unwrap() + 0.4).abs() < 1e-12);
+    // 1点のみの区間は平均と一致するため0
+    assert_eq!(cost.segment_value(2, 3).unwrap(), 0.0);
+    assert_eq!(cost.mean(2, 3).unwrap(), 2.0);
+}
+
+
+#[test]
+fn equal_variances_give_scaled_squared_error() {
+    let data = [0.5, -1.0, 2.0, 0.0, 1.5];
+    let cost = HeteroscedasticMeanCost::new(&data, &[2.0; 5]).unwrap();
+    let mean = data.iter().sum::<f64>() / 5.0;
+    let sse = data.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+    assert!((cost.segment_value(0, 5).unwrap() + sse / 4.0).abs() < 1e-12);
+}
+
+
+#[test]
+fn noisy_sensor_is_down_weighted() {
+    // 20時点目以降で平均が上昇するが，10時点目付近の分散の大きい観測に外れ値がある
+    let mut data = (0..40).map(|i| if i < 20 { 0.0 } else { 1.0 } + 0.1 * (i as f64 * 1.9).sin()).collect::<Vec<f64>>();
+    let mut variances = vec![0.05; 40];
+    for i in 9..12 {
+        data[i] = 6.0;
+        variances[i] = 100.0;
+    }
+    let weighted = HeteroscedasticMeanCost::new(&data, &variances).unwrap();
+    let result = detect(&weighted, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(result.change_points, vec![20]);
+
+    // 分散を一律とすると外れ値を変化点とする
+    let unweighted = HeteroscedasticMeanCost::new(&data, &[0.05; 40]).unwrap();
+    let result = detect(&unweighted, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_ne!(result.change_points, vec![20]);
+}
+
+
+#[test]
+fn rejects_invalid_input() {
+    assert!(HeteroscedasticMeanCost::new(&[1.0, 2.0], &[1.0]).is_err());
+    for v in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert!(HeteroscedasticMeanCost::new(&[1.0, 2.0], &[1.0, v]).is_err(), "variance = {v}");
+    }
+    assert!(HeteroscedasticMeanCost::new(&[f64::NAN, 2.0], &[1.0, 1.0]).is_err());
+    let cost = HeteroscedasticMeanCost::new(&[1.0, 2.0], &[1.0, 1.0]).unwrap();
+    assert!(cost.segment_value(0, 3).is_err());
+    assert!(cost.mean(1, 1).is_err());
+}
@@ -0,0 +1,78 @@
+//! 隠れマルコフモデルによる照合の確認
+
+use cpd_tools::cost::HeteroscedasticMeanCost;
+use cpd_tools::detect::{self, Constraints, DetectionResult, Method, Penalty};
+use cpd_tools::hmm::{change_points_of, cross_check, Emission, Hmm};
+
+extern crate rand;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+extern crate rand_distr;
+use rand_distr::{Distribution, Poisson, StandardNormal};
+
+
+/// 平均が0, 4, 0, 4と切り替わる正規分布の系列
+fn switching() -> Vec<f64> {
+    let mut rng = StdRng::seed_from_u64(472);
+    (0..200).map(|t| {
+                let mean = if (t / 50) % 2 == 1 { 4.0 } else { 0.0 };
+                let e: f64 = StandardNormal.sample(&mut rng);
+                mean + e
+            })
+            .collect()
+}
+
+
+#[test]
+fn viterbi_agrees_with_dp() {
+    let data = switching();
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    let res = detect::detect(&cost, Method::Dp, &Penalty::Linear(20.0), &Constraints::default()).unwrap();
+    assert_eq!(Emission::for_cost("hetero_mean"), Some(Emission::Gaussian));
+
+    let check = cross_check(&data, &res, Emission::Gaussian, 2, 2).unwrap();
+    assert!(check.agrees(), "dp {:?}, hmm {:?}", res.change_points, check.hmm_change_points);
+    assert_eq!(check.matched.len(), 3);
+    let mut means = check.hmm.means.clone();
+    means.sort_by(f64::total_cmp);
+    assert!(means[0].abs() < 0.5 && (means[1] - 4.0).abs() < 0.5, "{means:?}");
+    for row in &check.hmm.transition {
+        assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+}
+
+
+#[test]
+fn flags_disagreements() {
+    let data = switching();
+    // 誤った位置の変化点と欠けた変化点
+    let res = DetectionResult::new(vec![30, 150], 0.0, 200);
+    let check = cross_check(&data, &res, Emission::Gaussian, 2, 2).unwrap();
+    assert!(!check.agrees());
+    assert_eq!(check.matched.iter().map(|(dp, _)| *dp).collect::<Vec<_>>(), vec![150]);
+    assert_eq!(check.only_dp, vec![30]);
+    assert_eq!(check.only_hmm.len(), 2);
+
+    let short = DetectionResult::new(vec![], 0.0, 100);
+    assert!(cross_check(&data, &short, Emission::Gaussian, 2, 2).is_err());
+}
+
+
+#[test]
+fn poisson_emission_recovers_rates() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let data = (0..300).map(|t| {
+                           let rate = if (100..200).contains(&t) { 12.0 } else { 2.0 };
+                           Poisson::new(rate).unwrap().sample(&mut rng)
+                       })
+                       .collect::<Vec<f64>>();
+    let hmm = Hmm::fit(&data, 2, Emission::Poisson, 200).unwrap();
+    let states = hmm.viterbi(&data);
+    let cps = change_points_of(&states);
+    assert_eq!(cps.len(), 2, "{cps:?}");
+    assert!(cps[0].abs_diff(100) <= 2 && cps[1].abs_diff(200) <= 2, "{cps:?}");
+
+    assert!(Hmm::fit(&[1.0, 2.5], 1, Emission::Poisson, 10).is_err());
+    assert!(Hmm::fit(&[1.0, 2.0], 3, Emission::Gaussian, 10).is_err());
+}
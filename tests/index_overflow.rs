@@ -0,0 +1,25 @@
+//! 添字の検査付き計算の確認
+
+use cpd_tools::index;
+
+
+#[test]
+fn underflow_is_an_error() {
+    assert!(index::sub(0u32, 1u8).is_err());
+    assert_eq!(index::sub(5u32, 2u8).unwrap(), 3);
+}
+
+
+#[test]
+fn overflow_is_an_error() {
+    assert!(index::add(u32::MAX, 1u8).is_err());
+    assert!(index::mul(u32::MAX / 2 + 1, 2u8).is_err());
+    assert_eq!(index::mul(u32::MAX / 2, 2u8).unwrap(), u32::MAX - 1);
+}
+
+
+#[test]
+fn wide_operands_are_narrowed_to_the_left_type() {
+    assert!(index::sub(3u8, 300u32).is_err());
+    assert_eq!(index::add(3u64, u32::MAX).unwrap(), u32::MAX as u64 + 3);
+}
@@ -0,0 +1,67 @@
+//! 既知の変化点を固定した変化点検出の確認
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{self, Constraints, Method, Penalty};
+
+
+fn steps() -> HeteroscedasticMeanCost {
+    let data = (0..45).map(|t| match t {
+                          0..=9 => 0.0,
+                          10..=24 => 2.0,
+                          25..=34 => -1.0,
+                          _ => 3.0,
+                      } + 0.2 * (t as f64 * 1.9).sin())
+                      .collect::<Vec<f64>>();
+    HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap()
+}
+
+
+#[test]
+fn known_points_are_kept_and_rest_optimized() {
+    let cost = steps();
+    for method in [Method::Dp, Method::Dp2] {
+        let res = detect::detect_with_known(&cost, method, &[25], 2).unwrap();
+        assert_eq!(res.change_points, vec![10, 25, 35], "{method:?}");
+        let segments = res.segments();
+        let total = segments.iter().map(|(a, b)| cost.segment_value(*a, *b).unwrap()).sum::<f64>();
+        assert!((res.value - total).abs() <= 1e-9);
+    }
+
+    // 既知の変化点が最適な変化点に含まれる場合は通常の検出と一致する
+    let free = detect::detect(&cost, Method::Dp, &Penalty::NumChange(3), &Constraints::default()).unwrap();
+    let fixed = detect::detect_with_known(&cost, Method::Dp, &[10], 2).unwrap();
+    assert_eq!(free.change_points, fixed.change_points);
+    assert!((free.value - fixed.value).abs() <= 1e-9);
+}
+
+
+#[test]
+fn misplaced_known_point_is_respected() {
+    let cost = steps();
+    let res = detect::detect_with_known(&cost, Method::Dp, &[20], 1).unwrap();
+    assert!(res.change_points.contains(&20));
+    assert_eq!(res.num_change(), 2);
+    let best = (1..45).filter(|t| *t != 20)
+                      .map(|t| {
+                          let mut cps = [20, t];
+                          cps.sort();
+                          cost.segment_value(0, cps[0]).unwrap()
+                              + cost.segment_value(cps[0], cps[1]).unwrap()
+                              + cost.segment_value(cps[1], 45).unwrap()
+                      })
+                      .fold(f64::NEG_INFINITY, f64::max);
+    assert!((res.value - best).abs() <= 1e-9);
+
+    let none = detect::detect_with_known(&cost, Method::Dp, &[10, 25], 0).unwrap();
+    assert_eq!(none.change_points, vec![10, 25]);
+}
+
+
+#[test]
+fn invalid_known_points_are_rejected() {
+    let cost = steps();
+    assert!(detect::detect_with_known(&cost, Method::Dp, &[0], 1).is_err());
+    assert!(detect::detect_with_known(&cost, Method::Dp, &[45], 1).is_err());
+    assert!(detect::detect_with_known(&cost, Method::Dp, &[25, 10], 1).is_err());
+    assert!(detect::detect_with_known(&cost, Method::Dp, &[1, 2], 60).is_err());
+}
@@ -0,0 +1,73 @@
+//! 中央値からの絶対偏差に基づくコスト関数（[`L1Cost`]）の確認
+
+use cpd_tools::cost::{CostInput, CostRegistry, HeteroscedasticMeanCost, L1Cost, SegmentCost, SegmentParameter};
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+use std::collections::BTreeMap;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand_distr::{Distribution, StandardNormal};
+
+
+/// 区間の値を並べ替えて求めた中央値（小さい方）と絶対偏差の総和
+fn naive(data: &[f64], t_k_1: usize, t_k: usize) -> (f64, f64) {
+    let mut seg = data[t_k_1..t_k].to_vec();
+    seg.sort_by(f64::total_cmp);
+    let median = seg[(seg.len() - 1) / 2];
+    (median, seg.iter().map(|x| (x - median).abs()).sum())
+}
+
+
+#[test]
+fn matches_naive_computation() {
+    let mut rng = StdRng::seed_from_u64(7);
+    // 同じ値を含む系列
+    let data = (0..37).map(|_| StandardNormal.sample(&mut rng))
+                      .map(|z: f64| (z * 4.0).round())
+                      .collect::<Vec<f64>>();
+    let cost = L1Cost::new(&data).unwrap();
+    for t_k_1 in 0..data.len() {
+        for t_k in (t_k_1 + 1)..=data.len() {
+            let (median, dev) = naive(&data, t_k_1, t_k);
+            assert_eq!(cost.median(t_k_1 as _, t_k as _).unwrap(), median);
+            assert_eq!(cost.segment_parameter(t_k_1 as _, t_k as _).unwrap(), vec![median]);
+            assert!((cost.segment_value(t_k_1 as _, t_k as _).unwrap() + dev).abs() < 1e-9, "({t_k_1}, {t_k}]");
+        }
+    }
+}
+
+
+#[test]
+fn robust_to_outliers() {
+    let mut data = vec![0.0, 0.2, -0.1, 0.1, -0.2, 0.0, 0.1, -0.1, 0.2, 0.0,
+                        1.0, 1.1, 0.9, 1.0, 1.2, 0.8, 1.0, 1.1, 0.9, 1.0];
+    data[4] = 30.0;
+    let constraints = Constraints::default();
+    let l1 = detect(&L1Cost::new(&data).unwrap(), Method::Dp, &Penalty::NumChange(1), &constraints).unwrap();
+    assert_eq!(l1.change_points, vec![10]);
+
+    // 二乗誤差では外れ値を孤立させる変化点が選ばれる
+    let l2 = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    let l2 = detect(&l2, Method::Dp, &Penalty::NumChange(1), &constraints).unwrap();
+    assert_ne!(l2.change_points, vec![10]);
+}
+
+
+#[test]
+fn rejects_non_finite_data() {
+    assert!(L1Cost::new(&[0.0, f64::NAN]).is_err());
+    assert!(L1Cost::new(&[f64::INFINITY]).is_err());
+    let cost = L1Cost::new(&[1.0, 2.0]).unwrap();
+    assert!(cost.segment_value(0, 3).is_err());
+    assert_eq!(cost.segment_value(1, 2).unwrap(), 0.0);
+}
+
+
+#[test]
+fn registered_as_builtin() {
+    let columns = vec![vec![1.0, 5.0, 2.0]];
+    let params = BTreeMap::new();
+    let cost = CostRegistry::with_builtins().build("l1", &CostInput { columns: &columns, params: &params }).unwrap();
+    assert_eq!(cost.segment_value(0, 3).unwrap(), -4.0);
+}
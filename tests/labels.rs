@@ -0,0 +1,22 @@
+//! 検出結果の区間番号と変化の指示ベクトルの確認
+
+use cpd_tools::detect::DetectionResult;
+
+
+#[test]
+fn labels_follow_change_points() {
+    let res = DetectionResult::new(vec![2, 5], 0.0, 7);
+    assert_eq!(res.labels(7), vec![0, 0, 1, 1, 1, 2, 2]);
+    assert_eq!(res.change_indicator(7), vec![0, 0, 1, 0, 0, 1, 0]);
+    // 区間番号の変わり目と指示ベクトルは一致する
+    let labels = res.labels(7);
+    let diffs = labels.windows(2).map(|w| (w[1] - w[0]) as u8).collect::<Vec<u8>>();
+    assert_eq!(diffs, res.change_indicator(7)[1..]);
+
+    assert_eq!(res.labels(4), vec![0, 0, 1, 1]);
+    assert_eq!(res.change_indicator(4), vec![0, 0, 1, 0]);
+
+    let none = DetectionResult::new(vec![], 0.0, 3);
+    assert_eq!(none.labels(3), vec![0, 0, 0]);
+    assert_eq!(none.change_indicator(3), vec![0, 0, 0]);
+}
@@ -0,0 +1,61 @@
+//! メモの格納方法による計算結果の一致の確認
+//!
+//! [`FitResult`]は値の種類ごとに配列を分けたメモを，[`calc_dp::CalcDP`]等は`Vec<Vec<Option<_>>>`のメモを用いる．
+//! 同じコスト関数に対し，両者の評価値（ビット単位），変化点および変化点個数ごとの評価値が一致することを確認する．
+
+mod common;
+use common::{mean_cost, Fit, Memo};
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{FitResult, Method};
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2};
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    mean_cost(45, 0.6, |i| {
+        let level = match i { 0..=13 => 0.0, 14..=30 => 1.8, _ => -0.7 };
+        level + (i as f64 * 1.1).sin() * 0.5
+    })
+}
+
+
+macro_rules! check_storage {
+    ($m:ident, $method:expr) => {{
+        let data = cost();
+        let t_max = data.t_max();
+        let fit: Fit<Memo> = Fit::new(<Fit<Memo> as $m::CalcDP<f64, _>>::calc_memo_all(&data, &t_max).unwrap());
+        let soa = FitResult::fit(&data, $method, None).unwrap();
+        let k_max = <Fit<Memo> as $m::CalcDP<f64, _>>::calc_max_k(&t_max);
+        assert_eq!(soa.k_max(), k_max);
+
+        let values = soa.values_by_k().unwrap();
+        assert_eq!(values.len(), k_max as usize + 1);
+        for k in 0..=k_max {
+            let (cps, value) = <Fit<Memo> as $m::CalcDP<f64, _>>::get_change_points_with_value(&fit, &t_max, &k).unwrap();
+            assert_eq!(soa.value(k).unwrap().to_bits(), value.to_bits(), "k = {k}");
+            assert_eq!(values[k as usize].to_bits(), value.to_bits(), "k = {k}");
+            assert_eq!(soa.change_points(k).unwrap(), cps, "k = {k}");
+        }
+        assert!(soa.value(k_max + 1).is_err());
+
+        // 上限より少ない変化点個数の行のみを確保した場合
+        let limited = FitResult::fit(&data, $method, Some(3)).unwrap();
+        assert_eq!(limited.values_by_k().unwrap(), values[..4].to_vec());
+        for k in 0..=3 {
+            assert_eq!(limited.change_points(k).unwrap(), soa.change_points(k).unwrap());
+        }
+        assert!(limited.value(4).is_err());
+    }};
+}
+
+
+#[test]
+fn soa_memo_matches_vec_memo_gap1() {
+    check_storage!(calc_dp, Method::Dp);
+}
+
+
+#[test]
+fn soa_memo_matches_vec_memo_gap2() {
+    check_storage!(calc_dp_2, Method::Dp2);
+}
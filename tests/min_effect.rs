@@ -0,0 +1,123 @@
+//! 隣接する区間の母数の差に下限を課した変化点検出（[`detect_with_min_effect`]）の確認
+
+mod common;
+use common::mean_cost;
+
+use cpd_tools::calendar::Calendar;
+use cpd_tools::cost::{MultivariateNormalCost, SegmentParameter};
+use cpd_tools::detect::{detect, detect_with_min_effect, Boundaries, Boundary, ChangeCost, Constraints, FitResult, Method, Penalty, Window, WithContext};
+
+use ndarray::Array2;
+
+
+/// 0から2.0への大きな変化（時点20）と，2.0から2.2への微小な変化（時点40）を持つ系列
+fn micro_shift() -> cpd_tools::cost::HeteroscedasticMeanCost {
+    mean_cost(60, 0.01, |i| {
+        let level = match i { 0..=19 => 0.0, 20..=39 => 2.0, _ => 2.2 };
+        level + 0.02 * (i as f64 * 1.7).sin()
+    })
+}
+
+
+#[test]
+fn micro_shift_is_dropped() {
+    let cost = micro_shift();
+    let plain = detect(&cost, Method::Dp, &Penalty::NumChange(2), &Constraints::default()).unwrap();
+    assert_eq!(plain.change_points, vec![20, 40]);
+    // 下限が微小な変化より小さければ結果は変わらない
+    let loose = detect_with_min_effect(&cost, Method::Dp, &Penalty::NumChange(2), &Constraints::default(), 0.1).unwrap();
+    assert_eq!(loose, plain);
+
+    // 微小な変化を除き，変化点個数1の最適解を改めて求める
+    let strict = detect_with_min_effect(&cost, Method::Dp, &Penalty::NumChange(2), &Constraints::default(), 1.0).unwrap();
+    let single = detect(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(strict, single);
+    assert_eq!(strict.change_points, vec![20]);
+
+    // 全ての変化が下限未満であれば変化点を置かない
+    let none = detect_with_min_effect(&cost, Method::Dp, &Penalty::NumChange(2), &Constraints::default(), 5.0).unwrap();
+    assert!(none.change_points.is_empty());
+}
+
+
+#[test]
+fn linear_penalty_selects_among_valid_counts() {
+    let cost = micro_shift();
+    let penalty = Penalty::Linear(1.0);
+    let plain = detect(&cost, Method::Dp, &penalty, &Constraints::default()).unwrap();
+    assert_eq!(plain.change_points, vec![20, 40]);
+
+    let fit = FitResult::fit(&cost, Method::Dp, None).unwrap();
+    let strict = fit.select_with_min_effect(&penalty, 1.0).unwrap();
+    assert_eq!(strict.change_points, vec![20]);
+    assert_eq!(strict, detect_with_min_effect(&cost, Method::Dp, &penalty, &Constraints::default(), 1.0).unwrap());
+    // 下限0では制約を課さない場合と一致する
+    assert_eq!(fit.select_with_min_effect(&penalty, 0.0).unwrap(), plain);
+
+    // 計算した上限を超える変化点個数は指定できない
+    let limited = FitResult::fit(&cost, Method::Dp, Some(2)).unwrap();
+    assert!(limited.select_with_min_effect(&Penalty::NumChange(3), 1.0).is_err());
+    let constraints = Constraints::default().with_max_k(1);
+    assert!(detect_with_min_effect(&cost, Method::Dp, &Penalty::NumChange(2), &constraints, 1.0).is_err());
+}
+
+
+#[test]
+fn multivariate_effect_is_largest_component() {
+    // 1次元目は時点15で0.3，2次元目は時点30で2.0変化する
+    let data = Array2::from_shape_fn((45, 2), |(i, j)| {
+        let level = match j {
+            0 => if i < 15 { 0.0 } else { 0.3 },
+            _ => if i < 30 { 0.0 } else { 2.0 },
+        };
+        level + 0.02 * ((i * (j + 2)) as f64).sin()
+    });
+    let cost = MultivariateNormalCost::new(&data).unwrap();
+    let mean = cost.segment_parameter(30, 45).unwrap();
+    assert_eq!(mean.len(), 2);
+    let expected = (30..45).map(|i| data[[i, 1]]).sum::<f64>() / 15.0;
+    assert!((mean[1] - expected).abs() < 1e-12);
+
+    let constraints = Constraints::default().with_min_size(5);
+    let plain = detect(&cost, Method::Dp, &Penalty::NumChange(2), &constraints).unwrap();
+    assert_eq!(plain.change_points, vec![15, 30]);
+    let strict = detect_with_min_effect(&cost, Method::Dp, &Penalty::NumChange(2), &constraints, 1.0).unwrap();
+    assert_eq!(strict.change_points, vec![30]);
+}
+
+
+#[test]
+fn wrappers_delegate_segment_parameter() {
+    let cost = micro_shift();
+
+    let window = Window::new(&cost, 10..50).unwrap();
+    assert_eq!(window.segment_parameter(0, 10).unwrap(), cost.segment_parameter(10, 20).unwrap());
+    assert!(window.segment_parameter(0, 41).is_err());
+
+    let charged = ChangeCost::new(&cost, |_| 1.0);
+    assert_eq!(charged.segment_parameter(20, 40).unwrap(), cost.segment_parameter(20, 40).unwrap());
+
+    // 端に接する区間は観測範囲外のデータを含めて推定する
+    let boundaries = Boundaries { start: Boundary::Context(5), end: Boundary::Context(5) };
+    let context = WithContext::new(&cost, &boundaries).unwrap();
+    assert_eq!(context.segment_parameter(0, 10).unwrap(), cost.segment_parameter(0, 15).unwrap());
+    assert_eq!(context.segment_parameter(10, 20).unwrap(), cost.segment_parameter(15, 25).unwrap());
+    assert_eq!(context.segment_parameter(40, 50).unwrap(), cost.segment_parameter(45, 60).unwrap());
+
+    let calendar = Calendar::new((0..60).map(|t| t * 60).collect(), 0).unwrap()
+                            .with_blackout(600, 900).unwrap();
+    let blackout = calendar.blackout(&cost).unwrap();
+    assert_eq!(blackout.segment_parameter(0, 12).unwrap(), cost.segment_parameter(0, 12).unwrap());
+}
+
+
+#[test]
+fn context_is_used_for_effect() {
+    // 観測範囲は時点5から55．最初の区間の平均は観測範囲外の値を含む
+    let cost = micro_shift();
+    let constraints = Constraints::default().with_boundary(Boundaries { start: Boundary::Context(5), end: Boundary::Context(5) });
+    let plain = detect(&cost, Method::Dp, &Penalty::NumChange(2), &constraints).unwrap();
+    assert_eq!(plain.change_points, vec![15, 35]);
+    let strict = detect_with_min_effect(&cost, Method::Dp, &Penalty::NumChange(2), &constraints, 1.0).unwrap();
+    assert_eq!(strict.change_points, vec![15]);
+}
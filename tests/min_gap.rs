@@ -0,0 +1,66 @@
+//! 区間の最小の長さの提案の確認
+
+use cpd_tools::cost::{CorrelationCost, HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::preprocess::{suggest_min_gap, MinGapWarning};
+
+extern crate rand;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+extern crate rand_distr;
+use rand_distr::{Distribution, StandardNormal};
+
+
+/// ラグ1の自己相関係数`phi`のAR(1)過程に平均の変化を加えた系列
+fn ar1(phi: f64, seed: u64) -> Vec<f64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut x = 0.0;
+    (0..2000).map(|t| {
+                 let e: f64 = StandardNormal.sample(&mut rng);
+                 x = phi * x + e;
+                 x + if t >= 1000 { 5.0 } else { 0.0 }
+             })
+             .collect()
+}
+
+
+#[test]
+fn independent_series_keeps_identifiable_minimum() {
+    let data = ar1(0.0, 1);
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    let suggestion = suggest_min_gap(&data, &cost).unwrap();
+    assert_eq!(suggestion.n_parameters, 1);
+    assert!(suggestion.rho < 0.1, "{}", suggestion.rho);
+    assert!(suggestion.suggested <= 2, "{}", suggestion.suggested);
+    assert_eq!(suggestion.check(2), None);
+}
+
+
+#[test]
+fn autocorrelation_lengthens_suggestion() {
+    // 平均の変化があっても差分から推定するため自己相関係数は過大とならない
+    let data = ar1(0.6, 2);
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    let suggestion = suggest_min_gap(&data, &cost).unwrap();
+    assert!((suggestion.rho - 0.6).abs() < 0.1, "{}", suggestion.rho);
+    assert!((suggestion.inflation - (1.0 + suggestion.rho) / (1.0 - suggestion.rho)).abs() < 1e-12);
+    assert_eq!(suggestion.suggested as f64, suggestion.inflation.ceil());
+    assert!(matches!(suggestion.check(1), Some(MinGapWarning::FewEffectiveObservations { .. })));
+    assert_eq!(suggestion.check(suggestion.suggested), None);
+}
+
+
+#[test]
+fn warns_on_unidentifiable_segments() {
+    let x = ar1(0.0, 3);
+    let y = ar1(0.0, 4);
+    let cost = CorrelationCost::new(&x, &y).unwrap();
+    assert_eq!(cost.min_identifiable_size(), 3);
+    let suggestion = suggest_min_gap(&x, &cost).unwrap();
+    assert!(suggestion.suggested >= 5);
+    let warning = suggestion.check(1).unwrap();
+    assert_eq!(warning, MinGapWarning::Unidentifiable { min_size: 1, required: 3 });
+    assert!(warning.to_string().contains("unidentifiable"));
+
+    assert!(suggest_min_gap(&x[..100], &cost).is_err());
+}
@@ -0,0 +1,63 @@
+//! 多変量正規分布のコスト関数における特異に近い共分散行列の扱いの確認
+
+use cpd_tools::cost::{CostInput, CostRegistry, MultivariateNormalCost, Regularization, SegmentCost};
+use cpd_tools::detect::{self, Constraints, Method, Penalty};
+
+use std::collections::BTreeMap;
+
+use ndarray::Array2;
+
+
+/// 5次元の系列．30時点目で平均と共分散行列が変化する．
+fn series() -> Array2<f64> {
+    Array2::from_shape_fn((60, 5), |(t, j)| {
+        let noise = (((t * 31 + j * 17) as f64 * 12.9898).sin() * 43758.5453).fract();
+        if t < 30 { noise } else { 3.0 + 0.2 * noise + 0.5 * ((t * 3) as f64 * 0.37).cos() }
+    })
+}
+
+
+#[test]
+fn short_segments_are_infeasible_not_nan() {
+    let data = series();
+    let cost = MultivariateNormalCost::new(&data).unwrap();
+    for (t_k_1, t_k) in [(0, 1), (10, 12), (29, 32), (0, 60)] {
+        let val = cost.segment_value(t_k_1, t_k).unwrap();
+        assert!(!val.is_nan(), "({t_k_1}, {t_k}]");
+    }
+    // 次元以下の観測しか含まない区間は，リッジ項がなければ共分散行列が特異となる
+    let plain = MultivariateNormalCost::with_regularization(&data, Regularization { ridge: 0.0, ..Default::default() }).unwrap();
+    assert_eq!(plain.segment_value(10, 13).unwrap(), f64::NEG_INFINITY);
+    assert!(plain.segment_value(0, 30).unwrap().is_finite());
+    // 条件数の上限を厳しくすると，短い区間は実行不可能となる
+    let strict = MultivariateNormalCost::with_regularization(&data, Regularization { max_condition: 1e3, ..Default::default() }).unwrap();
+    assert_eq!(strict.segment_value(10, 13).unwrap(), f64::NEG_INFINITY);
+}
+
+
+#[test]
+fn detects_change_in_mean_and_covariance() {
+    let cost = MultivariateNormalCost::new(&series()).unwrap();
+    let constraints = Constraints::default().with_min_size(10);
+    let res = detect::detect(&cost, Method::Dp, &Penalty::NumChange(1), &constraints).unwrap();
+    assert_eq!(res.change_points, vec![30]);
+    assert!(res.value.is_finite());
+}
+
+
+#[test]
+fn registry_builds_with_parameters() {
+    let data = series();
+    let columns = data.columns().into_iter().map(|c| c.to_vec()).collect::<Vec<Vec<f64>>>();
+    let registry = CostRegistry::with_builtins();
+    let mut params = BTreeMap::new();
+    params.insert("ridge".to_owned(), 1e-3);
+    let built = registry.build("mv_normal", &CostInput { columns: &columns, params: &params }).unwrap();
+    let direct = MultivariateNormalCost::with_regularization(&data, Regularization { ridge: 1e-3, ..Default::default() }).unwrap();
+    assert_eq!(built.segment_value(5, 40).unwrap(), direct.segment_value(5, 40).unwrap());
+
+    params.insert("max_condition".to_owned(), 0.5);
+    assert!(registry.build("mv_normal", &CostInput { columns: &columns, params: &params }).is_err());
+    assert!(MultivariateNormalCost::with_regularization(&data, Regularization { ridge: -1.0, ..Default::default() }).is_err());
+    assert!(MultivariateNormalCost::new(&Array2::from_elem((10, 2), f64::NAN)).is_err());
+}
@@ -0,0 +1,80 @@
+//! 評価値がNaN等となる区間を動的計画法が検出することの確認
+
+use cpd_tools::cost::SegmentCost;
+use cpd_tools::detect::{self, Constraints, Method, Penalty};
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2, CalcDpError};
+
+use process_param::{Tau, NumChg};
+
+
+/// 区間$ (3, 7] $のみ指定した値を返すコスト関数
+struct Faulty(f64);
+
+impl SegmentCost for Faulty {
+    fn t_max(&self) -> Tau {
+        12
+    }
+
+
+    fn segment_value(&self, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        self.check_segment(t_k_1, t_k)?;
+        Ok(if (t_k_1, t_k) == (3, 7) { self.0 } else { -((t_k - t_k_1) as f64) })
+    }
+}
+
+
+type Memo = Vec<Vec<Option<(Tau, NumChg, f64)>>>;
+
+struct Fit;
+
+impl calc_dp::CalcTT<f64, Faulty> for Fit {
+    fn calc_value(data: &Faulty, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        data.segment_value(t_k_1, t_k)
+    }
+}
+
+impl calc_dp::CalcDP<f64, Faulty> for Fit {
+    fn memo_all(&self) -> Memo {
+        Vec::new()
+    }
+}
+
+impl calc_dp_2::CalcTT<f64, Faulty> for Fit {
+    fn calc_value(data: &Faulty, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        data.segment_value(t_k_1, t_k)
+    }
+}
+
+impl calc_dp_2::CalcDP<f64, Faulty> for Fit {
+    fn memo_all(&self) -> Memo {
+        Vec::new()
+    }
+}
+
+
+#[test]
+fn nan_reports_offending_segment() {
+    for method in [Method::Dp, Method::Dp2] {
+        let err = detect::detect(&Faulty(f64::NAN), method, &Penalty::NumChange(2), &Constraints::default()).unwrap_err();
+        assert!(err.message.contains("(3, 7]"), "{method:?}: {}", err.message);
+    }
+    let err = <Fit as calc_dp::CalcDP<f64, Faulty>>::calc_memo_all(&Faulty(f64::NAN), &12).unwrap_err();
+    assert!(err.message.contains("NaN") && err.message.contains("(3, 7]"), "{}", err.message);
+    let err = <Fit as calc_dp_2::CalcDP<f64, Faulty>>::calc_memo_all(&Faulty(f64::NAN), &12).unwrap_err();
+    assert!(err.message.contains("(3, 7]"), "{}", err.message);
+}
+
+
+#[test]
+fn positive_infinity_is_an_error() {
+    let err = detect::detect(&Faulty(f64::INFINITY), Method::Dp, &Penalty::Linear(1.0), &Constraints::default()).unwrap_err();
+    assert!(err.message.contains("+inf") && err.message.contains("(3, 7]"), "{}", err.message);
+}
+
+
+#[test]
+fn negative_infinity_marks_infeasible_segment() {
+    let res = detect::detect(&Faulty(f64::NEG_INFINITY), Method::Dp, &Penalty::NumChange(2), &Constraints::default()).unwrap();
+    assert!(res.value.is_finite());
+    assert!(!res.change_points.windows(2).any(|w| w == [3, 7]));
+}
@@ -0,0 +1,75 @@
+//! 負の二項分布に基づくコスト関数（[`NegBinomialCost`]）の確認
+
+use cpd_tools::cost::{Dispersion, NegBinomialCost, SegmentCost, SegmentParameter};
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+
+#[test]
+fn global_dispersion_matches_hand_computation() {
+    // r = 1 は幾何分布であり，ln Γ(y + 1) - ln Γ(1) = ln y!
+    let cost = NegBinomialCost::new(&[0, 1, 3], Dispersion::Global(1.0)).unwrap();
+    assert_eq!(cost.dispersion(), Dispersion::Global(1.0));
+    assert_eq!(cost.estimate(0, 3).unwrap(), (4.0 / 3.0, 1.0));
+    let expected = 6.0_f64.ln() + 3.0 * (3.0_f64 / 7.0).ln() + 4.0 * (4.0_f64 / 7.0).ln();
+    assert!((cost.segment_value(0, 3).unwrap() - expected).abs() < 1e-10);
+    assert_eq!(cost.segment_parameter(0, 3).unwrap(), vec![4.0 / 3.0]);
+    assert_eq!(cost.n_parameters(), 1);
+}
+
+
+#[test]
+fn large_dispersion_approaches_poisson() {
+    let data = [2, 5, 3, 0, 4];
+    let cost = NegBinomialCost::new(&data, Dispersion::Global(1e8)).unwrap();
+    let s = data.iter().sum::<u64>() as f64;
+    let mu = s / data.len() as f64;
+    assert!((cost.segment_value(0, 5).unwrap() - (s * mu.ln() - s)).abs() < 1e-4);
+}
+
+
+#[test]
+fn per_segment_dispersion_is_maximum_likelihood() {
+    let data = [0, 7, 1, 0, 12, 2, 0, 9, 0, 3];
+    let cost = NegBinomialCost::new(&data, Dispersion::PerSegment).unwrap();
+    assert_eq!(cost.n_parameters(), 2);
+    assert_eq!(cost.min_identifiable_size(), 2);
+    let (mu, r) = cost.estimate(0, 10).unwrap();
+    assert_eq!(mu, 3.4);
+    let value = cost.segment_value(0, 10).unwrap();
+    // 分散パラメータを固定した場合の対数尤度を上回る
+    for r_fixed in [0.1, 0.5, 1.0, 2.0, 10.0, 100.0] {
+        let fixed = NegBinomialCost::new(&data, Dispersion::Global(r_fixed)).unwrap();
+        assert!(fixed.segment_value(0, 10).unwrap() <= value + 1e-9, "r = {r_fixed}");
+    }
+    let at_r = NegBinomialCost::new(&data, Dispersion::Global(r)).unwrap();
+    assert!((at_r.segment_value(0, 10).unwrap() - value).abs() < 1e-9);
+
+    // 全て0の区間
+    let zeros = NegBinomialCost::new(&[0, 0, 0], Dispersion::PerSegment).unwrap();
+    assert_eq!(zeros.segment_value(0, 3).unwrap(), 0.0);
+    assert_eq!(zeros.estimate(0, 3).unwrap().0, 0.0);
+}
+
+
+#[test]
+fn detects_mean_shift_in_overdispersed_counts() {
+    let low = [0, 3, 1, 0, 5, 1, 0, 2];
+    let high = [9, 2, 14, 6, 0, 11, 8, 17];
+    let data = (0..48).map(|i| if i < 24 { low[i % 8] } else { high[i % 8] }).collect::<Vec<u64>>();
+    for dispersion in [Dispersion::Global(1.5), Dispersion::PerSegment] {
+        let cost = NegBinomialCost::new(&data, dispersion).unwrap();
+        let result = detect(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+        assert_eq!(result.change_points, vec![24], "{dispersion:?}");
+    }
+}
+
+
+#[test]
+fn rejects_invalid_input() {
+    for r in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert!(NegBinomialCost::new(&[1, 2], Dispersion::Global(r)).is_err(), "r = {r}");
+    }
+    let cost = NegBinomialCost::new(&[1, 2], Dispersion::PerSegment).unwrap();
+    assert!(cost.segment_value(0, 3).is_err());
+    assert!(cost.estimate(1, 1).is_err());
+}
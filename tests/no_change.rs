@@ -0,0 +1,47 @@
+//! 変化点個数0の検出結果の確認
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost, SegmentParameter};
+use cpd_tools::detect::{self, Constraints, FitResult, Method, Penalty};
+use cpd_tools::search;
+use cpd_tools::segment::{classify_changes, segment_reports};
+
+
+fn flat() -> (Vec<f64>, HeteroscedasticMeanCost) {
+    let data = (0..50).map(|i| 3.0 + 0.1 * (i as f64 * 1.7).sin()).collect::<Vec<f64>>();
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    (data, cost)
+}
+
+
+#[test]
+fn penalty_selects_no_change() {
+    let (data, cost) = flat();
+    for method in [Method::Dp, Method::Dp2, Method::Auto] {
+        let res = detect::detect(&cost, method, &Penalty::Linear(10.0), &Constraints::default()).unwrap();
+        assert!(res.is_no_change(), "{method:?}: {:?}", res.change_points);
+        assert_eq!(res.segments(), vec![(0, 50)]);
+        assert_eq!(res.value, cost.segment_value(0, 50).unwrap());
+    }
+    let pelt = search::pelt(&cost, 1, 10.0).unwrap().result;
+    assert!(pelt.is_no_change());
+    let binseg = search::binseg(&cost, 1, &Penalty::Linear(10.0), &Constraints::default()).unwrap();
+    assert!(binseg.is_no_change());
+
+    let res = FitResult::fit(&cost, Method::Dp, Some(3)).unwrap().no_change().unwrap();
+    assert!(res.is_no_change());
+    assert_eq!(res.parameters(&cost).unwrap(), vec![cost.segment_parameter(0, 50).unwrap()]);
+    assert_eq!(segment_reports(&data, &res.change_points).unwrap().len(), 1);
+    assert!(classify_changes(&data, &res.change_points).unwrap().is_empty());
+    assert_eq!(res.to_csv(&cost).unwrap().lines().count(), 2);
+    assert!(res.render_ascii(&data, 10).is_ok());
+}
+
+
+#[test]
+fn num_change_zero_is_accepted() {
+    let (_, cost) = flat();
+    for method in [Method::Dp, Method::Dp2] {
+        let res = detect::detect(&cost, method, &Penalty::NumChange(0), &Constraints::default().with_max_k(0)).unwrap();
+        assert!(res.is_no_change());
+    }
+}
@@ -0,0 +1,114 @@
+//! 逐次的な変化点検出（[`StreamDetector`]）の確認
+
+mod common;
+use common::mean_cost;
+
+use cpd_tools::detect::{detect, Constraints, DetectionResult, Method, Penalty};
+use cpd_tools::online::{OnlineDetector, StreamDetector};
+
+use process_param::Tau;
+
+
+/// 時点50の後に平均が10から12へ変化する系列
+fn series() -> Vec<f64> {
+    (0..80).map(|i| if i < 50 { 10.0 } else { 12.0 } + 0.8 * (i as f64 * 2.3).sin()).collect()
+}
+
+
+/// 系列全体から両側CUSUMの検出統計量を一括で計算する
+fn batch_cusum(data: &[f64], mean: f64, sd: f64, drift: f64) -> Vec<f64> {
+    let (mut pos, mut neg) = (0.0_f64, 0.0_f64);
+    data.iter()
+        .map(|x| {
+            let z = (x - mean) / sd;
+            pos = (pos + z - drift).max(0.0);
+            neg = (neg - z - drift).max(0.0);
+            pos.max(neg)
+        })
+        .collect()
+}
+
+
+#[test]
+fn incremental_matches_batch() {
+    let data = series();
+    let mut detector = StreamDetector::new(10.0, 1.0, 0.5, 1.0e9).unwrap();
+    let steps = data.iter().map(|x| detector.update(*x)).collect::<Vec<_>>();
+    let batch = batch_cusum(&data, 10.0, 1.0, 0.5);
+    for (t, (step, stat)) in steps.iter().zip(batch.iter()).enumerate() {
+        assert!((step.statistic - stat).abs() < 1e-12, "t = {t}");
+        assert!(!step.exceeded);
+        assert_eq!(step.change_point.is_some(), *stat > 0.0, "t = {t}");
+    }
+
+    // 変化後の累積和は変化点の直後から増加し続ける
+    let cp = steps.last().unwrap().change_point.unwrap();
+    assert!(cp.abs_diff(50) <= 2, "{cp}");
+    let offline = detect(&mean_cost(data.len(), 1.0, |i| data[i]), Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert!((offline.change_points[0] as u64).abs_diff(cp) <= 2, "{:?}, {cp}", offline.change_points);
+
+    // 初期化後は作成直後と同じ結果となる
+    detector.reset();
+    let again = data.iter().map(|x| detector.update(*x)).collect::<Vec<_>>();
+    assert_eq!(again, steps);
+}
+
+
+#[test]
+fn alarm_and_rearm_in_jsonl() {
+    let data = series();
+    let input = data.iter()
+                    .enumerate()
+                    .map(|(i, x)| format!("{{\"t\": \"s{}\", \"x\": {x}}}\n", i + 1))
+                    .collect::<String>();
+    let mut detector = StreamDetector::new(10.0, 1.0, 0.5, 4.0).unwrap();
+    let mut output = Vec::new();
+    detector.run_jsonl(input.as_bytes(), &mut output).unwrap();
+    let lines = String::from_utf8(output).unwrap()
+                                          .lines()
+                                          .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+                                          .collect::<Vec<_>>();
+    assert_eq!(lines.len(), data.len());
+
+    // 警報を出すまでは1つずつ与えた場合と一致する
+    let mut reference = StreamDetector::new(10.0, 1.0, 0.5, 4.0).unwrap();
+    let first = data.iter().position(|x| reference.update(*x).exceeded).unwrap();
+    assert!(first >= 50, "{first}");
+    let mut reference = StreamDetector::new(10.0, 1.0, 0.5, 4.0).unwrap();
+    for (i, line) in lines.iter().take(first + 1).enumerate() {
+        let step = reference.update(data[i]);
+        // 観測値と統計量は10進数の文字列を経由するため，丸め誤差を許容する
+        assert!((line["statistic"].as_f64().unwrap() - step.statistic).abs() < 1e-9, "t = {i}");
+        assert_eq!(line["alarm"].as_bool().unwrap(), i == first);
+    }
+    // 変化点は入力の`t`で出力する
+    let cp = lines[first]["cp"].as_str().unwrap();
+    let cp = cp.trim_start_matches('s').parse::<u64>().unwrap();
+    assert!(cp.abs_diff(50) <= 2, "{cp}");
+    assert!(lines[..first].iter().all(|l| l["cp"].is_null()));
+
+    // 警報の後は統計量を初期化して監視を再開する
+    assert!(lines[first + 1]["statistic"].as_f64().unwrap() < lines[first]["statistic"].as_f64().unwrap());
+
+    // 不正な行は行番号を含むエラーとなる
+    let err = detector.run_jsonl("{\"x\": 1.0}\n\nnot json\n".as_bytes(), Vec::new()).unwrap_err();
+    assert!(err.to_string().contains("Line 3"), "{err}");
+}
+
+
+#[test]
+fn resumes_from_offline_fit() {
+    let data = series();
+    let result = DetectionResult::new(vec![50], 0.0, data.len() as Tau);
+    let detector = StreamDetector::from_fit(&result, &data, 0.5, 5.0).unwrap();
+    let last_mean = data[50..].iter().sum::<f64>() / 30.0;
+    assert!((detector.mean() - last_mean).abs() < 1e-12);
+    assert!(detector.sd() > 0.4 && detector.sd() < 0.7, "{}", detector.sd());
+    assert_eq!(detector.origin(), 80);
+    assert_eq!(detector.last_change_point(), Some(50));
+
+    assert!(StreamDetector::from_fit(&result, &data[..79], 0.5, 5.0).is_err());
+    assert!(StreamDetector::new(0.0, 0.0, 0.5, 5.0).is_err());
+    assert!(StreamDetector::new(0.0, 1.0, -0.5, 5.0).is_err());
+    assert!(StreamDetector::new(0.0, 1.0, 0.5, 0.0).is_err());
+}
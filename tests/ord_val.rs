@@ -0,0 +1,90 @@
+//! 全順序を持つ評価値による動的計画法の確認
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::{calc_dp, CalcDpError, OrdVal};
+
+use process_param::{Tau, NumChg};
+
+
+/// 評価値の型`V`でメモを保持する型
+struct Fit<V>(Vec<Vec<Option<(Tau, NumChg, V)>>>);
+
+impl calc_dp::CalcTT<f64, HeteroscedasticMeanCost> for Fit<f64> {
+    fn calc_value(data: &HeteroscedasticMeanCost, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        <HeteroscedasticMeanCost as calc_dp::CalcTT<f64, _>>::calc_value(data, t_k_1, t_k)
+    }
+}
+
+impl calc_dp::CalcDP<f64, HeteroscedasticMeanCost> for Fit<f64> {
+    fn memo_all(&self) -> Vec<Vec<Option<(Tau, NumChg, f64)>>> {
+        self.0.clone()
+    }
+}
+
+impl calc_dp::CalcTT<OrdVal<f64>, HeteroscedasticMeanCost> for Fit<OrdVal<f64>> {
+    fn calc_value(data: &HeteroscedasticMeanCost, t_k_1: Tau, t_k: Tau) -> Result<OrdVal<f64>, CalcDpError> {
+        <HeteroscedasticMeanCost as calc_dp::CalcTT<OrdVal<f64>, _>>::calc_value(data, t_k_1, t_k)
+    }
+}
+
+impl calc_dp::CalcDP<OrdVal<f64>, HeteroscedasticMeanCost> for Fit<OrdVal<f64>> {
+    fn memo_all(&self) -> Vec<Vec<Option<(Tau, NumChg, OrdVal<f64>)>>> {
+        self.0.clone()
+    }
+}
+
+
+/// 特定の区間のみNaNを返す評価値
+struct NanAt(Tau, Tau);
+
+impl calc_dp::CalcTT<OrdVal<f64>, NanAt> for NanAt {
+    fn calc_value(data: &NanAt, t_k_1: Tau, t_k: Tau) -> Result<OrdVal<f64>, CalcDpError> {
+        Ok(OrdVal(if (t_k_1, t_k) == (data.0, data.1) { f64::NAN } else { -((t_k - t_k_1) as f64).powi(2) }))
+    }
+}
+
+impl calc_dp::CalcDP<OrdVal<f64>, NanAt> for NanAt {
+    fn memo_all(&self) -> Vec<Vec<Option<(Tau, NumChg, OrdVal<f64>)>>> {
+        Vec::new()
+    }
+}
+
+
+#[test]
+fn total_order_ranks_nan() {
+    let mut vals = [OrdVal(1.0), OrdVal(f64::NAN), OrdVal(f64::NEG_INFINITY), OrdVal(f64::INFINITY), OrdVal(-f64::NAN)];
+    vals.sort();
+    assert!(vals[0].0.is_nan() && vals[0].0.is_sign_negative());
+    assert_eq!(vals[1].0, f64::NEG_INFINITY);
+    assert!(vals[4].0.is_nan());
+    assert_eq!(OrdVal(f64::NAN), OrdVal(f64::NAN));
+    assert!(OrdVal(-0.0) < OrdVal(0.0));
+    assert_eq!([OrdVal(1.5), OrdVal(2.0)].into_iter().sum::<OrdVal<f64>>().into_inner(), 3.5);
+}
+
+
+#[test]
+fn built_in_cost_emits_ord_val() {
+    let data = (0..30).map(|t| if t < 12 { 0.0 } else { 2.0 } + 0.1 * (t as f64 * 1.7).sin()).collect::<Vec<f64>>();
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    let t_max = cost.t_max();
+    let plain = Fit(<Fit<f64> as calc_dp::CalcDP<f64, _>>::calc_memo_all(&cost, &t_max).unwrap());
+    let ord = Fit(<Fit<OrdVal<f64>> as calc_dp::CalcDP<OrdVal<f64>, _>>::calc_memo_all(&cost, &t_max).unwrap());
+    for k in 0..4 {
+        let a = calc_dp::CalcDP::get_value_history(&plain, &t_max, &k).unwrap();
+        let b = calc_dp::CalcDP::get_value_history(&ord, &t_max, &k).unwrap();
+        assert_eq!(a.iter().map(|e| (e.0, e.1)).collect::<Vec<_>>(), b.iter().map(|e| (e.0, e.1)).collect::<Vec<_>>());
+        assert_eq!(a[0].2, b[0].2.0);
+    }
+}
+
+
+#[test]
+fn nan_does_not_break_selection() {
+    // NaNは全順序で最大となり，含む区間の選択は候補の順序によらず定まる
+    let data = NanAt(4, 9);
+    let memo = <NanAt as calc_dp::CalcDP<OrdVal<f64>, _>>::calc_memo_all(&data, &12).unwrap();
+    let memo = Fit(memo);
+    let history = calc_dp::CalcDP::get_value_history(&memo, &12, &2).unwrap();
+    assert!(history.iter().any(|e| e.0 == 4) && history.iter().any(|e| e.0 == 9), "{history:?}");
+}
@@ -0,0 +1,70 @@
+//! パネルに共通する変化点の検出の確認
+
+use cpd_tools::cost::HeteroscedasticMeanCost;
+use cpd_tools::detect::{self, Constraints, Method, Penalty};
+use cpd_tools::panel::{detect_panel, group_penalty, Aggregation, Panel};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand_distr::{Distribution, StandardNormal};
+
+
+/// 標準正規分布の雑音に，`change`以降`shift`だけ平均が変化する系列を`n_series`本作る
+fn panel(n_series: usize, len: usize, change: usize, shift: f64, seed: u64) -> Vec<HeteroscedasticMeanCost> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n_series).map(|_| {
+                     let data = (0..len).map(|t| {
+                                           let z: f64 = StandardNormal.sample(&mut rng);
+                                           z + if t >= change { shift } else { 0.0 }
+                                       })
+                                       .collect::<Vec<f64>>();
+                     HeteroscedasticMeanCost::new(&data, &vec![1.0; len]).unwrap()
+                 })
+                 .collect()
+}
+
+
+#[test]
+fn weak_synchronized_change_is_detected() {
+    // 1系列あたりの増加量の期待値はおよそ 50 * 50 / 100 / 2 * 0.3^2 = 1.1 であり，単独では検出できない
+    let costs = panel(60, 100, 50, 0.3, 1);
+    let single = Panel::new(&costs[..1]).unwrap();
+    let alone = detect_panel(&single, Aggregation::L2, 5, &Penalty::Linear(group_penalty(1, 100, Aggregation::L2)), &Constraints::default()).unwrap();
+    assert!(alone.result.is_no_change());
+
+    let all = Panel::new(&costs).unwrap();
+    let beta = group_penalty(60, 100, Aggregation::L2);
+    let res = detect_panel(&all, Aggregation::L2, 5, &Penalty::Linear(beta), &Constraints::default()).unwrap();
+    assert_eq!(res.result.change_points.len(), 1);
+    assert!((res.result.change_points[0] as i64 - 50).abs() <= 3, "{:?}", res.result.change_points);
+    assert!(res.statistics[0] > beta);
+    assert_eq!(res.gains[0].len(), 60);
+
+    // 和による集約は動的計画法でも同じ変化点を与える
+    let dp = detect::detect(&all, Method::Dp, &Penalty::Linear(beta), &Constraints::default()).unwrap();
+    assert_eq!(dp.change_points, res.result.change_points);
+    assert_eq!(dp.value, res.result.value);
+}
+
+
+#[test]
+fn linf_needs_a_strong_single_series() {
+    let mut costs = panel(20, 80, 80, 0.0, 2);
+    costs.extend(panel(1, 80, 40, 3.0, 3));
+    let all = Panel::new(&costs).unwrap();
+    let beta = group_penalty(21, 80, Aggregation::LInf);
+    let res = detect_panel(&all, Aggregation::LInf, 5, &Penalty::Linear(beta), &Constraints::default()).unwrap();
+    assert_eq!(res.result.change_points.len(), 1);
+    assert!((res.result.change_points[0] as i64 - 40).abs() <= 2, "{:?}", res.result.change_points);
+    let strongest = res.gains[0].iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+    assert_eq!(strongest, 20);
+}
+
+
+#[test]
+fn mismatched_lengths_are_an_error() {
+    let mut costs = panel(2, 30, 15, 1.0, 4);
+    costs.extend(panel(1, 31, 15, 1.0, 5));
+    assert!(Panel::new(&costs).is_err());
+    assert!(Panel::<HeteroscedasticMeanCost>::new(&[]).is_err());
+}
@@ -0,0 +1,92 @@
+//! 区間の評価値を並列に計算する動的計画法（`calc_memo_all_par`）の確認
+//!
+//! 一つ前の変化点の候補が並列化の閾値（256個）を上回る期数と下回る期数の両方を含む系列について，
+//! 逐次に計算したメモと評価値が等しい候補の選び方まで一致することを確認する．
+
+mod common;
+use common::{mean_cost, Fit, Memo, VariMemo};
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2, CalcDpError};
+
+use process_param::Tau;
+
+
+/// 候補が並列化の閾値を上回る期数を含む系列の長さ
+const T_MAX: Tau = 270;
+
+
+/// 全ての区間の評価値が等しいデータ
+struct Flat;
+
+impl SegmentCost for Flat {
+    fn t_max(&self) -> Tau {
+        T_MAX
+    }
+
+    fn segment_value(&self, _t_k_1: Tau, _t_k: Tau) -> Result<f64, CalcDpError> {
+        Ok(0.0)
+    }
+}
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    mean_cost(T_MAX as usize, 0.4, |i| {
+        let level = match i { 0..=89 => 0.0, 90..=179 => 1.5, _ => -0.5 };
+        level + (i as f64 * 1.9).sin() * 0.8
+    })
+}
+
+
+/// メモの要素を評価値のビット列を含めて比較できる形にする
+fn bits(memo: &VariMemo) -> cpd_tools::dp_tools::Memo<u64> {
+    memo.iter()
+        .map(|row| row.iter().map(|v| v.as_ref().map(|v| (v.0, v.1, v.3.to_bits()))).collect())
+        .collect()
+}
+
+
+macro_rules! check_par {
+    ($m:ident, $data:expr) => {{
+        let data = $data;
+        let serial = <Fit<Memo, _> as $m::CalcDP<f64, _>>::calc_memo_all(&data, &T_MAX).unwrap();
+        let par = <Fit<Memo, _> as $m::CalcDP<f64, _>>::calc_memo_all_par(&data, &T_MAX).unwrap();
+        assert_eq!(serial.len(), par.len());
+        for (row_s, row_p) in serial.iter().zip(par.iter()) {
+            assert_eq!(row_s.len(), row_p.len());
+            for (s, p) in row_s.iter().zip(row_p.iter()) {
+                assert_eq!(s.map(|v| (v.0, v.1, v.2.to_bits())), p.map(|v| (v.0, v.1, v.2.to_bits())));
+            }
+        }
+
+        let serial_vari = <Fit<VariMemo, _> as $m::CalcDPWithVari<f64, (), _>>::calc_memo_all(&data, &T_MAX).unwrap();
+        let par_vari = <Fit<VariMemo, _> as $m::CalcDPWithVari<f64, (), _>>::calc_memo_all_par(&data, &T_MAX).unwrap();
+        assert_eq!(bits(&serial_vari), bits(&par_vari));
+        Fit::new(par)
+    }};
+}
+
+
+#[test]
+fn parallel_memo_matches_serial() {
+    let fit = check_par!(calc_dp, cost());
+    assert_eq!(<Fit<Memo> as calc_dp::CalcDP<f64, HeteroscedasticMeanCost>>::get_change_points(&fit, &T_MAX, &2).unwrap(), vec![90, 180]);
+    let fit = check_par!(calc_dp_2, cost());
+    assert_eq!(<Fit<Memo> as calc_dp_2::CalcDP<f64, HeteroscedasticMeanCost>>::get_change_points(&fit, &T_MAX, &2).unwrap(), vec![90, 180]);
+}
+
+
+#[test]
+fn parallel_memo_keeps_tie_breaking() {
+    // 全ての候補の評価値が等しいため，一つ前の変化点は常に候補のうち最も後ろのものとなる
+    let fit = check_par!(calc_dp, Flat);
+    for k in [1, 2, 5] {
+        let expected = ((T_MAX - k)..T_MAX).collect::<Vec<Tau>>();
+        assert_eq!(<Fit<Memo, Flat> as calc_dp::CalcDP<f64, Flat>>::get_change_points(&fit, &T_MAX, &k).unwrap(), expected);
+    }
+    let fit = check_par!(calc_dp_2, Flat);
+    for k in [1, 2, 5] {
+        let expected = (1..=k).rev().map(|i| T_MAX - 2 * i).collect::<Vec<Tau>>();
+        assert_eq!(<Fit<Memo, Flat> as calc_dp_2::CalcDP<f64, Flat>>::get_change_points(&fit, &T_MAX, &k).unwrap(), expected);
+    }
+}
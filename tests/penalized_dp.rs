@@ -0,0 +1,69 @@
+//! 変化点個数を固定しないペナルティ付きの動的計画法（`calc_dp_penalized`）の確認
+
+mod common;
+use common::{mean_cost, Fit, Memo, PenalizedMemo};
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::calc_dp;
+use cpd_tools::dp_tools::calc_dp_penalized::CalcDpPenalized;
+use cpd_tools::dp_tools::calc_pelt::CalcPelt;
+
+
+type Dp = Fit<Memo>;
+type Penalized = Fit<PenalizedMemo>;
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    mean_cost(40, 0.2, |i| {
+        let level = if (12..25).contains(&i) { 1.5 } else if i >= 31 { -1.0 } else { 0.0 };
+        level + (i as f64 * 1.7).cos() * 0.5
+    })
+}
+
+
+#[test]
+fn matches_best_over_k() {
+    let data = cost();
+    let t_max = data.t_max();
+    let dp = Dp::new(<Dp as calc_dp::CalcDP<f64, _>>::calc_memo_all(&data, &t_max).unwrap());
+    let k_max = <Dp as calc_dp::CalcDP<f64, _>>::calc_max_k(&t_max);
+    for beta in [0.0, 1.0, 4.0, 20.0, 1e6] {
+        let fit = Penalized::new(<Penalized as CalcDpPenalized<f64, _>>::calc_memo_all(&data, &t_max, &-beta).unwrap());
+        let (cps, value) = <Penalized as CalcDpPenalized<f64, _>>::get_change_points_with_value(&fit, &t_max).unwrap();
+
+        let (best_k, best) = (0..=k_max)
+            .map(|k| (k, <Dp as calc_dp::CalcDP<f64, _>>::get_value(&dp, &t_max, &k).unwrap() - beta * k as f64))
+            .fold((0, f64::NEG_INFINITY), |acc, v| if v.1 > acc.1 { v } else { acc });
+        assert!((value - best).abs() < 1e-9, "beta = {beta}");
+        assert_eq!(<Penalized as CalcDpPenalized<f64, _>>::get_num_changes(&fit, &t_max).unwrap(), best_k, "beta = {beta}");
+        assert_eq!(cps, <Dp as calc_dp::CalcDP<f64, _>>::get_change_points(&dp, &t_max, &best_k).unwrap(), "beta = {beta}");
+    }
+}
+
+
+#[test]
+fn matches_pelt() {
+    let data = cost();
+    let t_max = data.t_max();
+    for beta in [0.5, 3.0, 10.0] {
+        let exhaustive = Penalized::new(<Penalized as CalcDpPenalized<f64, _>>::calc_memo_all(&data, &t_max, &-beta).unwrap());
+        let pruned = Penalized::new(<Penalized as CalcPelt<f64, _>>::calc_memo_all(&data, &t_max, &-beta).unwrap());
+        for t in 1..=t_max {
+            assert_eq!(<Penalized as CalcDpPenalized<f64, _>>::get_change_points(&exhaustive, &t).unwrap(),
+                       <Penalized as CalcPelt<f64, _>>::get_change_points(&pruned, &t).unwrap());
+            assert!((<Penalized as CalcDpPenalized<f64, _>>::get_value(&exhaustive, &t).unwrap()
+                     - <Penalized as CalcPelt<f64, _>>::get_value(&pruned, &t).unwrap()).abs() < 1e-9);
+        }
+    }
+}
+
+
+#[test]
+fn large_penalty_gives_no_change() {
+    let data = cost();
+    let t_max = data.t_max();
+    let fit = Penalized::new(<Penalized as CalcDpPenalized<f64, _>>::calc_memo_all(&data, &t_max, &-1e9).unwrap());
+    assert!(<Penalized as CalcDpPenalized<f64, _>>::get_change_points(&fit, &t_max).unwrap().is_empty());
+    assert_eq!(<Penalized as CalcDpPenalized<f64, _>>::get_value(&fit, &t_max).unwrap(), data.segment_value(0, t_max).unwrap());
+    assert!(<Penalized as CalcDpPenalized<f64, _>>::get_value(&fit, &(t_max + 1)).is_err());
+}
@@ -0,0 +1,67 @@
+//! 高次元の系列の次元削減の確認
+
+use cpd_tools::cost::MultivariateNormalCost;
+use cpd_tools::detect::{Constraints, Method, Penalty};
+use cpd_tools::preprocess::{project, Projection};
+
+use ndarray::Array2;
+
+
+/// 12次元の系列．25時点目で0番目から2番目の次元の平均が同じ向きに変化する．
+fn series() -> Array2<f64> {
+    Array2::from_shape_fn((50, 12), |(t, j)| {
+        let noise = 0.3 * (((t * 31 + j * 17) as f64 * 12.9898).sin() * 43758.5453).fract();
+        noise + if t >= 25 && j < 3 { 4.0 } else { 0.0 }
+    })
+}
+
+
+#[test]
+fn pca_attributes_change_to_channels() {
+    let data = series();
+    let projected = project(&data, Projection::Pca { k: 2 }).unwrap();
+    assert_eq!(projected.scores.dim(), (50, 2));
+    assert_eq!(projected.loadings.dim(), (12, 2));
+    // 主成分の負荷は正規直交
+    let gram = projected.loadings.t().dot(&projected.loadings);
+    for ((i, j), v) in gram.indexed_iter() {
+        assert!((v - if i == j { 1.0 } else { 0.0 }).abs() < 1e-9, "({i}, {j}): {v}");
+    }
+    assert!(projected.variances[0] >= projected.variances[1]);
+
+    let constraints = Constraints::default().with_min_size(5);
+    let res = projected.detect(MultivariateNormalCost::new, Method::Dp, &Penalty::NumChange(1), &constraints).unwrap();
+    assert_eq!(res.result.change_points, vec![25]);
+    let shifts = res.channel_shifts().unwrap();
+    assert_eq!(shifts.len(), 1);
+    for (j, shift) in shifts[0].iter().enumerate() {
+        if j < 3 {
+            assert!((shift - 4.0).abs() < 0.5, "channel {j}: {shift}");
+        } else {
+            assert!(shift.abs() < 0.5, "channel {j}: {shift}");
+        }
+    }
+}
+
+
+#[test]
+fn random_projection_is_reproducible() {
+    let data = series();
+    let a = project(&data, Projection::RandomProjection { k: 4, seed: 7 }).unwrap();
+    let b = project(&data, Projection::RandomProjection { k: 4, seed: 7 }).unwrap();
+    assert_eq!(a, b);
+    assert_ne!(a, project(&data, Projection::RandomProjection { k: 4, seed: 8 }).unwrap());
+    let res = a.detect(MultivariateNormalCost::new, Method::Dp, &Penalty::NumChange(1), &Constraints::default().with_min_size(5)).unwrap();
+    assert_eq!(res.result.change_points, vec![25]);
+}
+
+
+#[test]
+fn invalid_dimension_is_an_error() {
+    let data = series();
+    assert!(project(&data, Projection::Pca { k: 0 }).is_err());
+    assert!(project(&data, Projection::Pca { k: 13 }).is_err());
+    let projected = project(&data, Projection::Pca { k: 2 }).unwrap();
+    assert!(projected.to_channels(&[1.0]).is_err());
+    assert_eq!(projected.to_channels(&[1.0, 0.0]).unwrap().len(), 12);
+}
@@ -0,0 +1,92 @@
+//! 分位点の変化を検出するコスト関数（[`QuantileCost`]）の確認
+
+use cpd_tools::cost::{CostInput, CostRegistry, L1Cost, QuantileCost, SegmentCost, SegmentParameter};
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+use std::collections::BTreeMap;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand_distr::{Distribution, StandardNormal};
+
+
+/// 区間の値を並べ替えて求めた分位点と損失の総和
+fn naive(data: &[f64], q: f64, t_k_1: usize, t_k: usize) -> (f64, f64) {
+    let mut seg = data[t_k_1..t_k].to_vec();
+    seg.sort_by(f64::total_cmp);
+    let rank = ((q * seg.len() as f64).ceil() as usize).max(1) - 1;
+    let theta = seg[rank];
+    let loss = seg.iter()
+                  .map(|x| x - theta)
+                  .map(|u| if u < 0.0 { (q - 1.0) * u } else { q * u })
+                  .sum();
+    (theta, loss)
+}
+
+
+#[test]
+fn matches_naive_computation() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let data = (0..29).map(|_| StandardNormal.sample(&mut rng))
+                      .map(|z: f64| (z * 3.0).round())
+                      .collect::<Vec<f64>>();
+    for q in [0.05, 0.3, 0.5, 0.95] {
+        let cost = QuantileCost::new(&data, q).unwrap();
+        assert_eq!(cost.q(), q);
+        for t_k_1 in 0..data.len() {
+            for t_k in (t_k_1 + 1)..=data.len() {
+                let (theta, loss) = naive(&data, q, t_k_1, t_k);
+                assert_eq!(cost.quantile(t_k_1 as _, t_k as _).unwrap(), theta);
+                assert_eq!(cost.segment_parameter(t_k_1 as _, t_k as _).unwrap(), vec![theta]);
+                assert!((cost.segment_value(t_k_1 as _, t_k as _).unwrap() + loss).abs() < 1e-9, "q = {q}, ({t_k_1}, {t_k}]");
+            }
+        }
+    }
+}
+
+
+#[test]
+fn median_is_half_of_l1() {
+    let data = [3.0, -1.0, 4.0, 1.0, -5.0, 9.0, 2.0, 6.0];
+    let quantile = QuantileCost::new(&data, 0.5).unwrap();
+    let l1 = L1Cost::new(&data).unwrap();
+    for t_k in 1..=data.len() as _ {
+        assert!((2.0 * quantile.segment_value(0, t_k).unwrap() - l1.segment_value(0, t_k).unwrap()).abs() < 1e-12);
+    }
+}
+
+
+#[test]
+fn detects_upper_tail_shift_without_mean_change() {
+    // 前半は±1，後半は平均が等しいまま上側の裾が重くなる
+    let mut data = (0..100).map(|i| if i % 2 == 0 { -1.0 } else { 1.0 }).collect::<Vec<f64>>();
+    data.extend((0..100).map(|i| if i % 10 == 0 { 4.5 } else { -0.5 }));
+    assert_eq!(data[100..].iter().sum::<f64>(), 0.0);
+
+    let cost = QuantileCost::new(&data, 0.95).unwrap();
+    let result = detect(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(result.change_points, vec![100]);
+    assert_eq!(cost.quantile(0, 100).unwrap(), 1.0);
+    assert_eq!(cost.quantile(100, 200).unwrap(), 4.5);
+}
+
+
+#[test]
+fn rejects_invalid_level() {
+    for q in [0.0, 1.0, -0.1, f64::NAN] {
+        assert!(QuantileCost::new(&[1.0, 2.0], q).is_err());
+    }
+    assert!(QuantileCost::new(&[f64::NAN], 0.5).is_err());
+}
+
+
+#[test]
+fn registered_as_builtin() {
+    let columns = vec![vec![1.0, 5.0, 2.0, 8.0]];
+    let registry = CostRegistry::with_builtins();
+    let mut params = BTreeMap::new();
+    assert!(registry.build("quantile", &CostInput { columns: &columns, params: &params }).is_err());
+    params.insert("q".to_owned(), 0.75);
+    let cost = registry.build("quantile", &CostInput { columns: &columns, params: &params }).unwrap();
+    assert!((cost.segment_value(0, 4).unwrap() + naive(&columns[0], 0.75, 0, 4).1).abs() < 1e-12);
+}
@@ -0,0 +1,53 @@
+//! 範囲外の問い合わせの扱い（`QueryPolicy`）の確認
+
+mod common;
+use common::mean_cost;
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{Session, Method, Penalty, Constraints};
+use cpd_tools::dp_tools::{CostTable, QueryPolicy, TableStrategy};
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    let data = [0.0, 0.2, -0.1, 0.1, 4.0, 4.2, 3.9, 4.1];
+    mean_cost(data.len(), 1.0, |i| data[i])
+}
+
+
+#[test]
+fn apply_clamps_only_in_lenient_mode() {
+    assert_eq!(QueryPolicy::default(), QueryPolicy::Strict);
+    assert_eq!(QueryPolicy::Strict.apply(3, 1, 8).unwrap(), 3);
+    assert!(QueryPolicy::Strict.apply(9, 1, 8).is_err());
+    assert_eq!(QueryPolicy::ClampToRange.apply(9, 1, 8).unwrap(), 8);
+    assert_eq!(QueryPolicy::ClampToRange.apply(0, 1, 8).unwrap(), 1);
+    assert!(QueryPolicy::ClampToRange.apply(0, 1, 0).is_err());
+}
+
+
+#[test]
+fn table_returns_nearest_cell() {
+    let cost = cost();
+    let table = CostTable::from_fn(cost.t_max(), TableStrategy::Serial, |t_k_1, t_k| cost.segment_value(t_k_1, t_k)).unwrap();
+
+    assert!(table.get_with(4, 10, QueryPolicy::Strict).is_err());
+    assert_eq!(table.get_with(4, 10, QueryPolicy::ClampToRange).unwrap(), table.get(4, 8).unwrap());
+    assert_eq!(table.get_with(12, 10, QueryPolicy::ClampToRange).unwrap(), table.get(7, 8).unwrap());
+    assert_eq!(table.get_with(2, 5, QueryPolicy::ClampToRange).unwrap(), table.get(2, 5).unwrap());
+}
+
+
+#[test]
+fn session_clamps_ranges_beyond_series() {
+    let strict = Session::new(cost(), Method::Dp);
+    assert!(strict.segment_value(2..10).is_err());
+    assert!(strict.detect_window(0..10, &Penalty::NumChange(1), &Constraints::default()).is_err());
+
+    let lenient = Session::new(cost(), Method::Dp).with_query_policy(QueryPolicy::ClampToRange);
+    assert_eq!(lenient.query_policy(), QueryPolicy::ClampToRange);
+    assert_eq!(lenient.segment_value(2..10).unwrap(), strict.segment_value(2..8).unwrap());
+    let result = lenient.detect_window(0..10, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(result, strict.detect_window(.., &Penalty::NumChange(1), &Constraints::default()).unwrap());
+    let (start, end) = (5, 3);
+    assert!(lenient.segment_value(start..end).is_err());
+}
@@ -0,0 +1,51 @@
+//! 定期的な再解析における変化点の安定化の確認
+
+use cpd_tools::detect::{reconcile, DetectionResult};
+
+use process_param::Tau;
+
+
+fn result(change_points: Vec<Tau>, t_max: Tau) -> DetectionResult {
+    DetectionResult::new(change_points, -1.0, t_max)
+}
+
+
+#[test]
+fn small_shifts_keep_previous_points() {
+    let previous = result(vec![30, 70], 100);
+    let new = result(vec![31, 69, 104], 110);
+    let rec = reconcile(&previous, &new, 2);
+    assert_eq!(rec.result.change_points, vec![30, 70, 104]);
+    assert_eq!(rec.result.t_max, 110);
+    assert_eq!(rec.result.value, new.value);
+    assert_eq!(rec.kept, vec![(30, 31), (70, 69)]);
+    assert_eq!(rec.added, vec![104]);
+    assert!(rec.withdrawn.is_empty());
+    assert!(rec.is_changed());
+
+    let again = reconcile(&rec.result, &result(vec![29, 71, 105], 111), 2);
+    assert_eq!(again.result.change_points, vec![30, 70, 104]);
+    assert!(!again.is_changed());
+}
+
+
+#[test]
+fn contradicted_points_are_withdrawn() {
+    let previous = result(vec![30, 70], 100);
+    let new = result(vec![30, 80], 110);
+    let rec = reconcile(&previous, &new, 3);
+    assert_eq!(rec.result.change_points, vec![30, 80]);
+    assert_eq!(rec.withdrawn, vec![70]);
+    assert_eq!(rec.added, vec![80]);
+}
+
+
+#[test]
+fn matching_is_one_to_one_and_ordered() {
+    // 前回の1個の変化点に今回の2個が近い場合，近い方のみ対応付ける
+    let previous = result(vec![13], 20);
+    let new = result(vec![10, 11, 12], 25);
+    let rec = reconcile(&previous, &new, 3);
+    assert_eq!(rec.kept, vec![(13, 12)]);
+    assert_eq!(rec.result.change_points, vec![10, 11, 13]);
+}
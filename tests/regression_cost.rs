@@ -0,0 +1,90 @@
+//! 共変量で調整した回帰モデルに基づくコスト関数（[`RegressionCost`]）の確認
+
+use cpd_tools::cost::{RegressionCost, SegmentCost, SegmentParameter};
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+use ndarray::{Array2, array};
+
+
+/// 切片と1個の共変量からなる共変量行列
+fn with_intercept(x: &[f64]) -> Array2<f64> {
+    Array2::from_shape_fn((x.len(), 2), |(i, j)| if j == 0 { 1.0 } else { x[i] })
+}
+
+
+#[test]
+fn matches_hand_computation() {
+    let cost = RegressionCost::new(&[1.0, 3.0, 2.0, 5.0], with_intercept(&[0.0, 1.0, 2.0, 3.0])).unwrap();
+    assert_eq!(cost.t_max(), 4);
+
+    // 偏差平方和 5，偏差積和 5.5 より傾き1.1，切片 2.75 - 1.5 * 1.1 = 1.1
+    let beta = cost.coefficients(0, 4).unwrap();
+    assert!((beta[0] - 1.1).abs() < 1e-8 && (beta[1] - 1.1).abs() < 1e-8, "{beta:?}");
+    assert_eq!(cost.segment_parameter(0, 4).unwrap(), beta);
+    // 残差 (-0.1, 0.8, -1.3, 0.6) より RSS = 2.7
+    assert!((cost.segment_value(0, 4).unwrap() + 2.0 * (2.7_f64 / 4.0).ln()).abs() < 1e-8);
+
+    // 共変量の個数以下の区間は評価できない
+    assert_eq!(cost.segment_value(0, 2).unwrap(), f64::NEG_INFINITY);
+}
+
+
+#[test]
+fn intercept_only_is_gaussian_profile() {
+    let y = [2.0, 4.0, 3.0, 7.0, 4.0];
+    let cost = RegressionCost::new(&y, Array2::ones((y.len(), 1))).unwrap();
+    let mean = y.iter().sum::<f64>() / 5.0;
+    let rss = y.iter().map(|v| (v - mean).powi(2)).sum::<f64>();
+    assert!((cost.coefficients(0, 5).unwrap()[0] - mean).abs() < 1e-8);
+    assert!((cost.segment_value(0, 5).unwrap() + 2.5 * (rss / 5.0).ln()).abs() < 1e-8);
+}
+
+
+#[test]
+fn covariate_effect_is_not_a_change() {
+    // 共変量（温度）が途中で上昇するが，応答との関係は一定
+    let temp = (0..40).map(|i| if i < 20 { 10.0 } else { 25.0 } + (i as f64 * 0.9).sin()).collect::<Vec<f64>>();
+    let y = temp.iter().enumerate().map(|(i, t)| 1.0 + 0.5 * t + 0.05 * (i as f64 * 2.1).cos()).collect::<Vec<f64>>();
+
+    // 切片のみでは温度の上昇を変化点とする
+    let level = RegressionCost::new(&y, Array2::ones((40, 1))).unwrap();
+    let result = detect(&level, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(result.change_points, vec![20]);
+
+    // 共変量で調整すると，分割による評価値の改善は小さい
+    let adjusted = RegressionCost::new(&y, with_intercept(&temp)).unwrap();
+    let whole = adjusted.segment_value(0, 40).unwrap();
+    let split = adjusted.segment_value(0, 20).unwrap() + adjusted.segment_value(20, 40).unwrap();
+    let whole_level = level.segment_value(0, 40).unwrap();
+    let split_level = level.segment_value(0, 20).unwrap() + level.segment_value(20, 40).unwrap();
+    assert!(split - whole < 0.1 * (split_level - whole_level));
+    let beta = adjusted.coefficients(0, 40).unwrap();
+    assert!((beta[1] - 0.5).abs() < 0.01, "{beta:?}");
+}
+
+
+#[test]
+fn detects_change_in_relationship() {
+    let x = (0..30).map(|i| (i as f64 * 1.7).sin() * 3.0).collect::<Vec<f64>>();
+    let y = x.iter()
+             .enumerate()
+             .map(|(i, v)| if i < 18 { 1.0 + 2.0 * v } else { 1.0 - v } + 0.1 * (i as f64 * 2.3).cos())
+             .collect::<Vec<f64>>();
+    let cost = RegressionCost::new(&y, with_intercept(&x)).unwrap();
+    let result = detect(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(result.change_points, vec![18]);
+    let beta = cost.coefficients(18, 30).unwrap();
+    assert!((beta[0] - 1.0).abs() < 0.1 && (beta[1] + 1.0).abs() < 0.05, "{beta:?}");
+}
+
+
+#[test]
+fn rejects_invalid_input() {
+    assert!(RegressionCost::new(&[1.0, 2.0], Array2::ones((3, 1))).is_err());
+    assert!(RegressionCost::new(&[1.0, 2.0], Array2::zeros((2, 0))).is_err());
+    assert!(RegressionCost::new(&[1.0, f64::NAN], Array2::ones((2, 1))).is_err());
+    assert!(RegressionCost::new(&[1.0, 2.0], array![[1.0], [f64::INFINITY]]).is_err());
+    let cost = RegressionCost::new(&[1.0, 2.0, 4.0], Array2::ones((3, 1))).unwrap();
+    assert!(cost.segment_value(0, 4).is_err());
+    assert!(cost.coefficients(2, 2).is_err());
+}
@@ -0,0 +1,50 @@
+//! 乱数の設定（`RngConfig`）による確率的な処理の再現性の確認
+
+use cpd_tools::calibrate::{calibrate_threshold, GaussianMeanNull};
+use cpd_tools::cost::HeteroscedasticMeanCost;
+use cpd_tools::detect::{Penalty, Constraints};
+use cpd_tools::search::wbs;
+use cpd_tools::stats::{segment_mean_cis, BootstrapOptions};
+use cpd_tools::RngConfig;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+
+fn series() -> Vec<f64> {
+    (0..60).map(|i| if i < 30 { (i % 5) as f64 * 0.1 } else { 3.0 + (i % 7) as f64 * 0.1 }).collect()
+}
+
+
+#[test]
+fn seed_and_config_are_interchangeable() {
+    let null = GaussianMeanNull { sd: 1.0 };
+    let by_seed = calibrate_threshold(&null, 40, 0.1, 50, 11).unwrap();
+    let by_config = calibrate_threshold(&null, 40, 0.1, 50, RngConfig::new(11)).unwrap();
+    assert_eq!(by_seed, by_config);
+    assert_ne!(by_seed, calibrate_threshold(&null, 40, 0.1, 50, 12).unwrap());
+}
+
+
+#[test]
+fn config_from_user_rng_is_reproducible() {
+    let a = RngConfig::from_rng(&mut StdRng::seed_from_u64(5));
+    let b = RngConfig::from_rng(&mut StdRng::seed_from_u64(5));
+    assert_eq!(a, b);
+    assert_ne!(a.fork(0), a.fork(1));
+
+    let data = series();
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    let run = |rng: RngConfig| wbs(&cost, 2, &Penalty::NumChange(1), &Constraints::default(), 20, rng).unwrap();
+    assert_eq!(run(a), run(b));
+}
+
+
+#[test]
+fn bootstrap_options_carry_config() {
+    let data = series();
+    let options = BootstrapOptions { n_boot: 50, rng: RngConfig::new(3), ..BootstrapOptions::default() };
+    let first = segment_mean_cis(&data, &[30], 0.1, &options).unwrap();
+    let second = segment_mean_cis(&data, &[30], 0.1, &options).unwrap();
+    assert_eq!(first, second);
+}
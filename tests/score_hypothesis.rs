@@ -0,0 +1,55 @@
+//! 利用者が与えた変化点（仮説）の評価の確認
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{FitResult, Method};
+
+
+fn shifted() -> HeteroscedasticMeanCost {
+    let data = (0..40).map(|t| if t < 14 { 0.0 } else { 3.0 } + 0.2 * (t as f64 * 1.3).sin()).collect::<Vec<f64>>();
+    HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap()
+}
+
+
+#[test]
+fn optimal_hypothesis_has_zero_gap() {
+    let cost = shifted();
+    let fit = FitResult::fit(&cost, Method::Dp, Some(3)).unwrap();
+    let score = fit.score_hypothesis(&[14]).unwrap();
+    assert_eq!(score.hypothesis.segments(), vec![(0, 14), (14, 40)]);
+    assert_eq!(score.hypothesis.value, cost.segment_value(0, 14).unwrap() + cost.segment_value(14, 40).unwrap());
+    assert_eq!(score.gaps.iter().map(|g| g.0).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+    for (k, optimum, gap) in &score.gaps {
+        assert_eq!(*optimum, fit.value(*k).unwrap());
+        assert_eq!(*gap, optimum - score.hypothesis.value);
+    }
+    assert!(score.gap().unwrap().abs() <= 1e-9);
+    assert!(score.is_optimal(1e-9));
+}
+
+
+#[test]
+fn wrong_hypothesis_has_positive_gap() {
+    let cost = shifted();
+    let fit = FitResult::fit(&cost, Method::Dp2, Some(2)).unwrap();
+    let score = fit.score_hypothesis(&[20]).unwrap();
+    assert!(score.gap().unwrap() > 1.0);
+    assert!(!score.is_optimal(1e-9));
+
+    let none = fit.score_hypothesis(&[]).unwrap();
+    assert_eq!(none.hypothesis.value, cost.segment_value(0, 40).unwrap());
+    assert!(none.gap().unwrap().abs() <= 1e-9);
+
+    let beyond = fit.score_hypothesis(&[5, 10, 14, 30]).unwrap();
+    assert_eq!(beyond.gap(), None);
+}
+
+
+#[test]
+fn invalid_hypothesis_is_rejected() {
+    let cost = shifted();
+    let fit = FitResult::fit(&cost, Method::Dp, Some(2)).unwrap();
+    assert!(fit.score_hypothesis(&[0]).unwrap_err().message.contains("out of range"));
+    assert!(fit.score_hypothesis(&[40]).is_err());
+    assert!(fit.score_hypothesis(&[20, 14]).unwrap_err().message.contains("strictly increasing"));
+    assert!(fit.score_hypothesis(&[14, 14]).is_err());
+}
@@ -0,0 +1,59 @@
+//! 区間の前後の余白を参照する系列データの確認
+
+use cpd_tools::dp_tools::{calc_dp, CalcDpError, SeriesData};
+
+use process_param::{Tau, NumChg};
+
+
+type Memo = Vec<Vec<Option<(Tau, NumChg, f64)>>>;
+
+
+/// 直前の観測との差の二乗和に基づく評価値．区間の最初の観測も直前の観測（余白）との差を用いる．
+struct Diff(Memo);
+
+impl calc_dp::CalcTT<f64, SeriesData<f64>> for Diff {
+    fn calc_value(data: &SeriesData<f64>, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let slice = data.with_margin(t_k_1, t_k)?;
+        let prev = slice.before.last().copied().unwrap_or(slice.segment[0]);
+        let mean = slice.segment.iter().sum::<f64>() / slice.segment.len() as f64;
+        let sq = std::iter::once(prev).chain(slice.segment.iter().copied())
+                                      .collect::<Vec<f64>>()
+                                      .windows(2)
+                                      .map(|w| (w[1] - w[0]).powi(2) + (w[1] - mean).powi(2))
+                                      .sum::<f64>();
+        Ok(-sq)
+    }
+}
+
+impl calc_dp::CalcDP<f64, SeriesData<f64>> for Diff {
+    fn memo_all(&self) -> Memo {
+        self.0.clone()
+    }
+}
+
+
+#[test]
+fn margin_is_clipped_at_the_edges() {
+    let data = SeriesData::with_context((0..10).map(f64::from).collect(), 3).unwrap();
+    let head = data.with_margin(0, 2).unwrap();
+    assert_eq!((head.before, head.segment, head.after), (&[][..], &[0.0, 1.0][..], &[2.0, 3.0, 4.0][..]));
+    let mid = data.with_margin(4, 6).unwrap();
+    assert_eq!((mid.before, mid.after), (&[1.0, 2.0, 3.0][..], &[6.0, 7.0, 8.0][..]));
+    assert_eq!(mid.iter().count(), mid.len());
+    let tail = data.with_margin(8, 10).unwrap();
+    assert_eq!((tail.before.len(), tail.after.len()), (3, 0));
+    assert_eq!(data.segment(2, 5).unwrap(), &[2.0, 3.0, 4.0]);
+    assert!(data.with_margin(5, 11).is_err());
+    assert!(data.segment(5, 5).is_err());
+}
+
+
+#[test]
+fn calc_dp_uses_margin() {
+    let values = (0..30).map(|i| if i < 12 { 0.0 } else { 5.0 } + 0.1 * (i as f64 * 1.9).sin()).collect::<Vec<f64>>();
+    let data = SeriesData::with_context(values, 1).unwrap();
+    let t_max = data.t_max();
+    let fit = Diff(<Diff as calc_dp::CalcDP<f64, _>>::calc_memo_all(&data, &t_max).unwrap());
+    let history = <Diff as calc_dp::CalcDP<f64, _>>::get_value_history(&fit, &t_max, &1).unwrap();
+    assert_eq!(history[0].0, 12);
+}
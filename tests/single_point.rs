@@ -0,0 +1,79 @@
+//! 1点のみの区間で定義されないコスト関数に対する最小間隔1の動的計画法の確認
+
+use cpd_tools::cost::{MultivariateNormalCost, SegmentCost};
+use cpd_tools::detect::{self, Constraints, Method, Penalty};
+use cpd_tools::dp_tools::{calc_dp, CalcDpError};
+
+use process_param::{Tau, NumChg};
+
+extern crate ndarray;
+use ndarray::Array2;
+
+
+/// 区間ごとに分散を推定する正規分布の対数尤度（定数項は除く）
+struct Variance(Vec<f64>);
+
+impl calc_dp::CalcTT<f64, Variance> for Variance {
+    fn calc_value(data: &Variance, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        let seg = &data.0[(t_k_1 as usize)..(t_k as usize)];
+        let n = seg.len() as f64;
+        let mean = seg.iter().sum::<f64>() / n;
+        let var = seg.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / n;
+        Ok(-n / 2.0 * var.ln())
+    }
+
+    fn check_single_point(_data: &Variance) -> Result<(), CalcDpError> {
+        Err(CalcDpError::new("Variance is not defined on a single point; use calc_dp_2."))
+    }
+}
+
+impl calc_dp::CalcDP<f64, Variance> for Variance {
+    fn memo_all(&self) -> Vec<Vec<Option<(Tau, NumChg, f64)>>> {
+        Vec::new()
+    }
+}
+
+
+fn series(dim: usize) -> Array2<f64> {
+    Array2::from_shape_fn((40, dim), |(t, j)| ((t * 7 + j * 3) as f64 * 0.37).sin() + if t >= 20 { 3.0 } else { 0.0 })
+}
+
+
+#[test]
+fn custom_cost_rejects_single_points() {
+    let data = Variance((0..20).map(|t| (t as f64 * 1.3).sin()).collect());
+    let err = <Variance as calc_dp::CalcDP<f64, Variance>>::calc_memo_all(&data, &20).unwrap_err();
+    assert!(err.message.contains("calc_dp_2"), "{}", err.message);
+}
+
+
+#[test]
+fn degenerate_cost_requires_min_size() {
+    let cost = MultivariateNormalCost::new(&series(2)).unwrap();
+    assert!(!cost.rejects_short_segments());
+    let err = detect::detect(&cost, Method::Dp, &Penalty::Linear(5.0), &Constraints::default()).unwrap_err();
+    assert!(err.message.contains("min_size to at least 3"), "{}", err.message);
+    assert!(detect::detect(&cost, Method::Dp2, &Penalty::Linear(5.0), &Constraints::default()).is_err());
+
+    let constraints = Constraints::default().with_min_size(3);
+    let res = detect::detect(&cost, Method::Dp, &Penalty::NumChange(1), &constraints).unwrap();
+    assert_eq!(res.change_points, vec![20]);
+}
+
+
+#[test]
+fn gap_two_does_not_cover_first_segment() {
+    // Method::Dp2でも最初の区間は1点となり得るため，区間の最小の長さの指定が必要となる
+    let cost = MultivariateNormalCost::new(&series(1)).unwrap();
+    assert_eq!(cost.min_identifiable_size(), 2);
+    for method in [Method::Dp, Method::Dp2] {
+        let err = detect::detect(&cost, method, &Penalty::NumChange(1), &Constraints::default()).unwrap_err();
+        assert!(err.message.contains("min_size to at least 2"), "{}", err.message);
+        assert!(!err.message.contains("Method::Dp2"), "{}", err.message);
+    }
+    let constraints = Constraints::default().with_min_size(2);
+    for method in [Method::Dp, Method::Dp2] {
+        let res = detect::detect(&cost, method, &Penalty::NumChange(1), &constraints).unwrap();
+        assert_eq!(res.change_points, vec![20]);
+    }
+}
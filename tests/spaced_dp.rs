@@ -0,0 +1,99 @@
+//! 最低間隔を型引数とする動的計画法（`calc_dp_spaced`）の確認
+
+mod common;
+use common::{mean_cost, Memo};
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2, calc_dp_spaced, CostTable, TableStrategy};
+use cpd_tools::dp_tools::calc_dp_spaced::CalcDpSpaced;
+
+use process_param::Tau;
+
+
+type Fit = common::Fit<Memo>;
+
+
+fn cost(n: usize) -> HeteroscedasticMeanCost {
+    mean_cost(n, 0.5, |i| if i < n / 3 { (i as f64 * 0.7).sin() } else { 2.0 + (i as f64 * 1.3).cos() })
+}
+
+
+/// 最初の区間の長さ1以上，以降の区間の長さ`gap`以上の全ての変化点の組における評価値の最大値
+fn brute_force(cost: &HeteroscedasticMeanCost, k: usize, gap: Tau) -> f64 {
+    fn search(cost: &HeteroscedasticMeanCost, prev: Tau, k: usize, gap: Tau) -> f64 {
+        let t_max = cost.t_max();
+        if k == 0 {
+            return if prev == 0 || t_max - prev >= gap { cost.segment_value(prev, t_max).unwrap() } else { f64::NEG_INFINITY };
+        }
+        let start = if prev == 0 { 1 } else { prev + gap };
+        (start..t_max).map(|t| cost.segment_value(prev, t).unwrap() + search(cost, t, k - 1, gap))
+                      .fold(f64::NEG_INFINITY, f64::max)
+    }
+    search(cost, 0, k, gap)
+}
+
+
+#[test]
+fn spacing_one_matches_calc_dp() {
+    let data = cost(30);
+    let t_max = data.t_max();
+    let memo = <Fit as CalcDpSpaced<f64, _, 1>>::calc_memo_all(&data, &t_max).unwrap();
+    assert_eq!(memo, <Fit as calc_dp::CalcDP<f64, _>>::calc_memo_all(&data, &t_max).unwrap());
+    assert_eq!(<Fit as CalcDpSpaced<f64, _, 1>>::calc_max_k(&t_max), <Fit as calc_dp::CalcDP<f64, _>>::calc_max_k(&t_max));
+}
+
+
+#[test]
+fn spacing_two_matches_calc_dp_2() {
+    let data = cost(30);
+    let t_max = data.t_max();
+    let spaced = Fit::new(<Fit as CalcDpSpaced<f64, _, 2>>::calc_memo_all(&data, &t_max).unwrap());
+    let dp2 = Fit::new(<Fit as calc_dp_2::CalcDP<f64, _>>::calc_memo_all(&data, &t_max).unwrap());
+    // メモの配置も`calc_dp_2`と一致する
+    assert_eq!(spaced.0, dp2.0);
+    assert_eq!(<Fit as CalcDpSpaced<f64, _, 2>>::calc_memo_all_par(&data, &t_max).unwrap(), dp2.0);
+    let table = CostTable::from_fn(t_max, TableStrategy::Serial, |t_k_1, t_k| data.segment_value(t_k_1, t_k)).unwrap();
+    assert_eq!(<Fit as CalcDpSpaced<f64, _, 2>>::calc_memo_all_from_table(&table, &t_max).unwrap(), dp2.0);
+    let k_max = <Fit as CalcDpSpaced<f64, _, 2>>::calc_max_k(&t_max);
+    assert_eq!(k_max, <Fit as calc_dp_2::CalcDP<f64, _>>::calc_max_k(&t_max));
+    for k in 0..=k_max {
+        assert_eq!(<Fit as CalcDpSpaced<f64, _, 2>>::get_value_history(&spaced, &t_max, &k).unwrap(),
+                   <Fit as calc_dp_2::CalcDP<f64, _>>::get_value_history(&dp2, &t_max, &k).unwrap());
+    }
+}
+
+
+#[test]
+fn spacing_three_is_optimal() {
+    let data = cost(14);
+    let t_max = data.t_max();
+    let fit = Fit::new(<Fit as CalcDpSpaced<f64, _, 3>>::calc_memo_all(&data, &t_max).unwrap());
+    let k_max = <Fit as CalcDpSpaced<f64, _, 3>>::calc_max_k(&t_max);
+    assert_eq!(k_max, 4);
+    for k in 0..=k_max {
+        let value = <Fit as CalcDpSpaced<f64, _, 3>>::get_value(&fit, &t_max, &k).unwrap();
+        assert!((value - brute_force(&data, k as usize, 3)).abs() < 1e-9, "k = {k}");
+
+        let history = <Fit as CalcDpSpaced<f64, _, 3>>::get_value_history(&fit, &t_max, &k).unwrap();
+        let mut bounds = history.iter().map(|v| v.0).collect::<Vec<Tau>>();
+        bounds.push(t_max);
+        bounds.sort_unstable();
+        bounds.dedup();
+        assert_eq!(bounds.len(), k as usize + 2);
+        for w in bounds.windows(2) {
+            calc_dp_spaced::order_change_point::<3>(&w[0], &w[1]).unwrap();
+        }
+    }
+    assert!(<Fit as CalcDpSpaced<f64, _, 3>>::check_idx_memo(&6, &2, &fit.0).is_err());
+    assert!(<Fit as CalcDpSpaced<f64, _, 3>>::check_idx_memo(&7, &2, &fit.0).is_ok());
+}
+
+
+#[test]
+fn order_change_point_uses_spacing() {
+    assert!(calc_dp_spaced::order_change_point::<3>(&0, &1).is_ok());
+    assert!(calc_dp_spaced::order_change_point::<3>(&2, &4).is_err());
+    assert!(calc_dp_spaced::order_change_point::<3>(&2, &5).is_ok());
+    assert!(calc_dp_spaced::order_change_point::<3>(&5, &2).is_err());
+    assert!(calc_dp_spaced::order_change_point::<1>(&2, &3).is_ok());
+}
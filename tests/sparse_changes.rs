@@ -0,0 +1,58 @@
+//! 一部のチャネルのみが変化する系列に対する変化点探索の確認
+
+use cpd_tools::detect::{Constraints, Penalty};
+use cpd_tools::search::{sparse_binseg, sparse_threshold};
+
+use ndarray::Array2;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand_distr::{Distribution, StandardNormal};
+
+
+/// 標準正規分布に従う雑音
+fn noise(len: usize, dim: usize, seed: u64) -> Array2<f64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    Array2::from_shape_simple_fn((len, dim), || StandardNormal.sample(&mut rng))
+}
+
+
+/// 40チャネルの系列．30時点目でチャネル3と7，70時点目でチャネル20のみの平均が変化する．
+fn series() -> Array2<f64> {
+    let mut data = noise(100, 40, 1);
+    for ((t, j), v) in data.indexed_iter_mut() {
+        *v += match j {
+            3 | 7 if t >= 30 => 2.0,
+            20 if t >= 70 => -2.0,
+            _ => 0.0,
+        };
+    }
+    data
+}
+
+
+#[test]
+fn finds_times_and_affected_channels() {
+    let data = series();
+    let res = sparse_binseg(&data, 5, &Penalty::Linear(0.0), &Constraints::default(), None).unwrap();
+    assert_eq!(res.result.change_points, vec![30, 70]);
+    assert_eq!(res.affected, vec![vec![3, 7], vec![20]]);
+    assert_eq!(res.threshold, sparse_threshold(100, 40));
+    assert_eq!(res.scales.len(), 40);
+    assert!(res.result.value.is_finite());
+
+    let fixed = sparse_binseg(&data, 5, &Penalty::NumChange(1), &Constraints::default(), None).unwrap();
+    assert_eq!(fixed.result.change_points.len(), 1);
+    assert_eq!(fixed.affected.len(), 1);
+}
+
+
+#[test]
+fn no_change_without_signal() {
+    let data = noise(60, 10, 2);
+    let res = sparse_binseg(&data, 2, &Penalty::Linear(0.0), &Constraints::default(), None).unwrap();
+    assert!(res.result.is_no_change());
+    assert!(res.affected.is_empty());
+    assert!(sparse_binseg(&data, 2, &Penalty::NumChange(1), &Constraints::default(), None).is_err());
+    assert!(sparse_binseg(&data, 0, &Penalty::Linear(0.0), &Constraints::default(), None).is_err());
+    assert!(sparse_binseg(&data, 2, &Penalty::Linear(0.0), &Constraints::default(), Some(-1.0)).is_err());
+}
@@ -0,0 +1,48 @@
+//! 公開する結果および設定の型の作成とJSONによる往復の確認
+
+use cpd_tools::detect::{Boundaries, Boundary, Constraints, DetectionResult, Method, Penalty};
+
+
+#[test]
+fn builders_set_fields() {
+    let constraints = Constraints::default().with_max_k(4)
+                                            .with_min_size(3)
+                                            .with_max_size(20)
+                                            .with_boundary(Boundaries { start: Boundary::Open, ..Default::default() });
+    assert_eq!(constraints.max_k, Some(4));
+    assert_eq!(constraints.min_size, Some(3));
+    assert_eq!(constraints.max_size, Some(20));
+    assert_eq!(constraints.boundary.start, Boundary::Open);
+    assert_eq!(constraints.boundary.end, Boundary::Closed);
+
+    let res = DetectionResult::new(vec![5, 12], -3.5, 30).with_start(2);
+    assert_eq!(res.change_points, vec![5, 12]);
+    assert_eq!((res.value, res.start, res.t_max), (-3.5, 2, 30));
+    assert_eq!(res.segments(), vec![(2, 5), (5, 12), (12, 30)]);
+}
+
+
+#[test]
+fn public_types_round_trip_through_json() {
+    let res = DetectionResult::new(vec![5, 12], -3.5, 30).with_start(2);
+    let back: DetectionResult = serde_json::from_str(&serde_json::to_string(&res).unwrap()).unwrap();
+    assert_eq!(back, res);
+
+    let constraints = Constraints::default().with_min_size(3).with_boundary(Boundaries { end: Boundary::Context(4), ..Default::default() });
+    let back: Constraints = serde_json::from_str(&serde_json::to_string(&constraints).unwrap()).unwrap();
+    assert_eq!(back, constraints);
+    // 省略した制約は既定値となる
+    let partial: Constraints = serde_json::from_str(r#"{"max_k": 2}"#).unwrap();
+    assert_eq!(partial, Constraints::default().with_max_k(2));
+
+    for method in [Method::Dp, Method::Dp2, Method::Auto] {
+        let back: Method = serde_json::from_str(&serde_json::to_string(&method).unwrap()).unwrap();
+        assert_eq!(back, method);
+    }
+    assert_eq!(serde_json::to_string(&Method::Dp2).unwrap(), r#""dp2""#);
+
+    for penalty in [Penalty::NumChange(3), Penalty::Linear(2.5)] {
+        let back: Penalty = serde_json::from_str(&serde_json::to_string(&penalty).unwrap()).unwrap();
+        assert_eq!(back, penalty);
+    }
+}
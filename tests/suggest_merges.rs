@@ -0,0 +1,33 @@
+//! 区間の併合の提案の確認
+
+use cpd_tools::cost::{HeteroscedasticMeanCost, SegmentCost};
+use cpd_tools::detect::{self, Constraints, Method, Penalty};
+use cpd_tools::segment::suggest_merges;
+
+
+#[test]
+fn spurious_change_is_suggested_first() {
+    let data = (0..40).map(|t| if t < 20 { 0.0 } else { 4.0 } + 0.3 * (t as f64 * 2.1).sin()).collect::<Vec<f64>>();
+    let cost = HeteroscedasticMeanCost::new(&data, &vec![1.0; data.len()]).unwrap();
+    let res = detect::detect(&cost, Method::Dp, &Penalty::NumChange(3), &Constraints::default()).unwrap();
+    assert!(res.change_points.contains(&20));
+
+    let all = suggest_merges(&res, &cost, f64::INFINITY).unwrap();
+    assert_eq!(all.len(), 3);
+    assert!(all.windows(2).all(|w| w[0].cost_increase <= w[1].cost_increase));
+    assert_eq!(all.last().unwrap().change_point, 20);
+    for s in &all {
+        let expected = cost.segment_value(s.left.0, s.left.1).unwrap()
+            + cost.segment_value(s.right.0, s.right.1).unwrap()
+            - cost.segment_value(s.left.0, s.right.1).unwrap();
+        assert_eq!(s.cost_increase, expected);
+        assert_eq!((s.left.1, s.right.0), (s.change_point, s.change_point));
+    }
+
+    let few = suggest_merges(&res, &cost, all.last().unwrap().cost_increase).unwrap();
+    assert_eq!(few.len(), 2);
+    assert!(few.iter().all(|s| s.change_point != 20));
+
+    let none = detect::detect(&cost, Method::Dp, &Penalty::NumChange(0), &Constraints::default()).unwrap();
+    assert!(suggest_merges(&none, &cost, f64::INFINITY).unwrap().is_empty());
+}
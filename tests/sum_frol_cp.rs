@@ -0,0 +1,63 @@
+//! 変化点群に対する評価値の合計の確認
+
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2, CalcDpError};
+
+use process_param::Tau;
+
+
+/// 区間の長さに依存する評価値
+struct Length;
+
+impl calc_dp::CalcTT<f64, Tau> for Length {
+    fn calc_value(_: &Tau, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        Ok(((t_k - t_k_1) as f64).ln() + 0.1 * t_k_1 as f64)
+    }
+}
+
+impl calc_dp_2::CalcTT<f64, Tau> for Length {
+    fn calc_value(_: &Tau, t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+        Ok(((t_k - t_k_1) as f64).ln() + 0.1 * t_k_1 as f64)
+    }
+}
+
+
+fn expected(change_points: &[Tau]) -> f64 {
+    let mut prev = 0;
+    let mut total = 0.0;
+    for &t in change_points {
+        total += ((t - prev) as f64).ln() + 0.1 * prev as f64;
+        prev = t;
+    }
+    total
+}
+
+
+#[test]
+fn serial_and_parallel_sums_agree() {
+    use calc_dp::DictToFunc;
+
+    let t_max = 200;
+    let dict = calc_dp::DictCache::<Length, f64>::new(&t_max, &t_max).unwrap();
+    let many = (1..=t_max).collect::<Vec<Tau>>();
+    for cps in [vec![], vec![t_max], vec![50, 120, t_max], many] {
+        let serial = dict.sum_frol_cp(&cps).unwrap();
+        assert_eq!(serial, expected(&cps));
+        assert_eq!(dict.sum_frol_cp_par(&cps).unwrap(), serial);
+    }
+    assert!(dict.sum_frol_cp(&[50, 40]).is_err());
+    assert!(dict.sum_frol_cp_par(&[50, 40]).is_err());
+}
+
+
+#[test]
+fn min_gap_2_sums_agree() {
+    use calc_dp_2::DictToFunc;
+
+    let t_max = 100;
+    let dict = calc_dp_2::DictCache::<Length, f64>::new(&t_max, &t_max).unwrap();
+    let many = (1..=t_max / 2).map(|i| 2 * i).collect::<Vec<Tau>>();
+    for cps in [vec![t_max], many] {
+        assert_eq!(dict.sum_frol_cp(&cps).unwrap(), expected(&cps));
+        assert_eq!(dict.sum_frol_cp_par(&cps).unwrap(), expected(&cps));
+    }
+}
@@ -0,0 +1,104 @@
+//! 評価値の表の作成におけるエラーの扱いの確認
+//!
+//! 最初のエラーで計算を打ち切ること，および全てのエラーを区間とともに列挙できることを確認する．
+
+use cpd_tools::dp_tools::{calc_dp, calc_dp_2, CalcDpError, TableStrategy};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use process_param::Tau;
+
+
+/// 系列の長さ
+const T_MAX: Tau = 300;
+
+
+/// 長さ7の倍数の区間でエラーとなる評価値
+fn faulty(t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+    if (t_k - t_k_1).is_multiple_of(7) {
+        Err(CalcDpError::new(format!("bad segment ({t_k_1}, {t_k}]")))
+    } else {
+        Ok((t_k - t_k_1) as f64)
+    }
+}
+
+
+macro_rules! counting_table {
+    ($name:ident, $calls:ident) => {
+        static $calls: AtomicUsize = AtomicUsize::new(0);
+
+        struct $name;
+
+        impl calc_dp::CalcTT<f64, ()> for $name {
+            fn calc_value(_: &(), t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+                $calls.fetch_add(1, Ordering::Relaxed);
+                faulty(t_k_1, t_k)
+            }
+        }
+
+        impl calc_dp::DictTT<f64, ()> for $name {
+            fn value_tt_all(&self) -> Vec<Vec<f64>> {
+                Vec::new()
+            }
+        }
+
+        impl calc_dp_2::CalcTT<f64, ()> for $name {
+            fn calc_value(_: &(), t_k_1: Tau, t_k: Tau) -> Result<f64, CalcDpError> {
+                $calls.fetch_add(1, Ordering::Relaxed);
+                faulty(t_k_1, t_k)
+            }
+        }
+
+        impl calc_dp_2::DictTT<f64, ()> for $name {
+            fn value_tt_all(&self) -> Vec<Vec<f64>> {
+                Vec::new()
+            }
+        }
+    };
+}
+
+counting_table!(SerialTable, SERIAL_CALLS);
+counting_table!(ParallelTable, PARALLEL_CALLS);
+counting_table!(ChunkTable, CHUNK_CALLS);
+counting_table!(ErrorsTable, ERRORS_CALLS);
+
+
+/// 表の区間の個数
+fn n_cells() -> usize {
+    (T_MAX as usize) * (T_MAX as usize + 1) / 2
+}
+
+
+#[test]
+fn serial_stops_at_first_error() {
+    let err = <SerialTable as calc_dp::DictTT<f64, ()>>::calc_value_all_with(&(), &T_MAX, TableStrategy::Serial).unwrap_err();
+    assert_eq!(err.message, "bad segment (0, 7]");
+    assert_eq!(SERIAL_CALLS.load(Ordering::Relaxed), 7);
+}
+
+
+#[test]
+fn parallel_stops_early() {
+    assert!(<ParallelTable as calc_dp::DictTT<f64, ()>>::calc_value_all(&(), &T_MAX).is_err());
+    assert!(PARALLEL_CALLS.load(Ordering::Relaxed) < n_cells() / 2);
+    assert!(<ChunkTable as calc_dp_2::DictTT<f64, ()>>::calc_value_all_with(&(), &T_MAX, TableStrategy::FixedChunks(5)).is_err());
+    assert!(CHUNK_CALLS.load(Ordering::Relaxed) < n_cells() / 2);
+}
+
+
+#[test]
+fn all_errors_are_listed_with_segments() {
+    let errors = <ErrorsTable as calc_dp::DictTT<f64, ()>>::calc_value_errors(&(), &T_MAX).unwrap();
+    assert_eq!(ERRORS_CALLS.load(Ordering::Relaxed), n_cells());
+    let expected = (0..T_MAX).flat_map(|t_k_1| ((t_k_1 + 1)..=T_MAX).map(move |t_k| (t_k_1, t_k)))
+                             .filter(|(t_k_1, t_k)| (t_k - t_k_1).is_multiple_of(7))
+                             .collect::<Vec<(Tau, Tau)>>();
+    assert_eq!(errors.iter().map(|(t_k_1, t_k, _)| (*t_k_1, *t_k)).collect::<Vec<_>>(), expected);
+    for (t_k_1, t_k, e) in &errors {
+        assert_eq!(e.message, format!("bad segment ({t_k_1}, {t_k}]"));
+    }
+
+    // 区間の最小の長さが2の場合は長さ1の区間を含まない
+    let errors_2 = <ErrorsTable as calc_dp_2::DictTT<f64, ()>>::calc_value_errors(&(), &T_MAX).unwrap();
+    assert_eq!(errors_2.len(), expected.len());
+}
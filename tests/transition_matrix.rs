@@ -0,0 +1,42 @@
+//! 状態間の遷移の推定の確認
+
+use cpd_tools::segment::transition_matrix;
+
+
+/// 区間ごとの状態と長さを時点ごとの状態へ展開する
+fn expand(runs: &[(usize, usize)]) -> Vec<usize> {
+    runs.iter().flat_map(|(label, len)| std::iter::repeat_n(*label, *len)).collect()
+}
+
+
+#[test]
+fn estimates_jump_chain_and_dwell_times() {
+    // 0 -> 1 -> 0 -> 2 -> 0 -> 1（隣接する同じ状態の区間は1回の滞在とする）
+    let labels = expand(&[(0, 5), (1, 3), (0, 2), (0, 2), (2, 6), (0, 4), (1, 7)]);
+    let model = transition_matrix(&labels).unwrap();
+    assert_eq!(model.n_regimes, 3);
+    assert_eq!(model.counts, vec![vec![0, 2, 1], vec![1, 0, 0], vec![1, 0, 0]]);
+    assert!((model.probabilities[0][1] - 2.0 / 3.0).abs() < 1e-12);
+    assert_eq!(model.probabilities[1], vec![1.0, 0.0, 0.0]);
+    for row in &model.probabilities {
+        assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+
+    assert_eq!(model.dwell_times, vec![vec![4, 4], vec![3], vec![6]]);
+    assert_eq!(model.censored_dwell_times, vec![(0, 5), (1, 7)]);
+    assert_eq!(model.mean_dwell_times(), vec![Some(4.0), Some(3.0), Some(6.0)]);
+    assert_eq!(model.dwell_distribution(0).unwrap(), vec![(4, 1.0)]);
+    assert!(model.dwell_distribution(3).is_err());
+}
+
+
+#[test]
+fn single_regime_has_no_transitions() {
+    let model = transition_matrix(&[2; 10]).unwrap();
+    assert_eq!(model.n_regimes, 3);
+    assert!(model.probabilities.iter().flatten().all(|p| *p == 0.0));
+    assert!(model.dwell_times.iter().all(|d| d.is_empty()));
+    assert_eq!(model.censored_dwell_times, vec![(2, 10)]);
+    assert_eq!(model.mean_dwell_times(), vec![None, None, None]);
+    assert!(transition_matrix(&[]).is_err());
+}
@@ -0,0 +1,59 @@
+//! 変化点個数ごとの評価値の一括取得の確認
+
+mod common;
+use common::mean_cost;
+
+use cpd_tools::cost::HeteroscedasticMeanCost;
+use cpd_tools::detect::{Constraints, FitResult, Method, Penalty};
+
+use process_param::NumChg;
+
+
+fn cost() -> HeteroscedasticMeanCost {
+    mean_cost(60, 0.5, |t| match t {
+                  0..=19 => 0.0,
+                  20..=39 => 2.0,
+                  _ => -1.0,
+              } + 0.4 * (t as f64 * 1.7).sin())
+}
+
+
+#[test]
+fn values_by_k_match_value() {
+    let cost = cost();
+    for method in [Method::Dp, Method::Dp2] {
+        for k_max in [None, Some(4)] {
+            let fit = FitResult::fit(&cost, method, k_max).unwrap();
+            let values = fit.values_by_k().unwrap();
+            assert_eq!(values.len(), fit.k_max() as usize + 1);
+            for (k, val) in values.iter().enumerate() {
+                assert_eq!(val.to_bits(), fit.value(k as NumChg).unwrap().to_bits());
+            }
+        }
+    }
+}
+
+
+#[test]
+fn infeasible_counts_are_negative_infinity() {
+    let cost = cost();
+    let constraints = Constraints::default().with_min_size(15);
+    let fit = FitResult::fit_constrained(&cost, Method::Dp, Some(5), &constraints).unwrap();
+    let values = fit.values_by_k().unwrap();
+    assert!(values[..=3].iter().all(|v| v.is_finite()));
+    assert!(values[4..].iter().all(|v| *v == f64::NEG_INFINITY));
+}
+
+
+#[test]
+fn linear_penalty_selects_argmax() {
+    let cost = cost();
+    let fit = FitResult::fit(&cost, Method::Dp, Some(8)).unwrap();
+    let values = fit.values_by_k().unwrap();
+    for beta in [0.0, 1.0, 5.0, 50.0, 1e6] {
+        let penalized = values.iter().enumerate().map(|(k, v)| v - beta * k as f64).collect::<Vec<f64>>();
+        let best = penalized.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let selected = fit.select(&Penalty::Linear(beta)).unwrap();
+        assert_eq!(penalized[selected.change_points.len()], best);
+    }
+}
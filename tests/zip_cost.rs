@@ -0,0 +1,78 @@
+//! ゼロ過剰ポアソン分布に基づくコスト関数（[`ZipCost`]）の確認
+
+use cpd_tools::cost::{SegmentCost, SegmentParameter, ZipCost};
+use cpd_tools::detect::{detect, Method, Penalty, Constraints};
+
+
+/// ゼロ過剰ポアソン分布の対数尤度（$ \ln y_i! $の項は除く）
+fn loglik(data: &[u64], pi: f64, lambda: f64) -> f64 {
+    data.iter()
+        .map(|y| match y {
+            0 => (pi + (1.0 - pi) * (-lambda).exp()).ln(),
+            _ => (1.0 - pi).ln() + *y as f64 * lambda.ln() - lambda,
+        })
+        .sum()
+}
+
+
+#[test]
+fn excess_zeros_are_estimated() {
+    let data = [0, 0, 0, 0, 2, 4];
+    let cost = ZipCost::new(&data);
+    let (pi, lambda) = cost.estimate(0, 6).unwrap();
+    // 正の観測値の平均3について λ / (1 - e^{-λ}) = 3
+    assert!((lambda / (1.0 - (-lambda).exp()) - 3.0).abs() < 1e-9);
+    // 0の割合 2/3 を再現する
+    assert!((pi + (1.0 - pi) * (-lambda).exp() - 4.0 / 6.0).abs() < 1e-9);
+
+    let value = cost.segment_value(0, 6).unwrap();
+    assert!((value - loglik(&data, pi, lambda)).abs() < 1e-9);
+    // 最尤推定値であるため，母数をずらすと対数尤度は減少する
+    for (dp, dl) in [(0.01, 0.0), (-0.01, 0.0), (0.0, 0.05), (0.0, -0.05)] {
+        assert!(loglik(&data, pi + dp, lambda + dl) < value);
+    }
+    assert!((cost.segment_parameter(0, 6).unwrap()[0] - (1.0 - pi) * lambda).abs() < 1e-12);
+}
+
+
+#[test]
+fn reduces_to_poisson_without_excess_zeros() {
+    // 0を含まない
+    let cost = ZipCost::new(&[1, 2, 3]);
+    assert_eq!(cost.estimate(0, 3).unwrap(), (0.0, 2.0));
+    assert!((cost.segment_value(0, 3).unwrap() - (6.0 * 2.0_f64.ln() - 6.0)).abs() < 1e-12);
+
+    // 正の観測値が全て1
+    let cost = ZipCost::new(&[0, 1, 1]);
+    assert_eq!(cost.estimate(0, 3).unwrap().0, 0.0);
+    assert!((cost.segment_value(0, 3).unwrap() - (2.0 * (2.0_f64 / 3.0).ln() - 2.0)).abs() < 1e-12);
+
+    // 全て0
+    let cost = ZipCost::new(&[0, 0, 0]);
+    assert_eq!(cost.estimate(0, 3).unwrap(), (1.0, 0.0));
+    assert_eq!(cost.segment_value(0, 3).unwrap(), 0.0);
+}
+
+
+#[test]
+fn detects_change_in_zero_inflation() {
+    // 正の観測値の分布は等しく，後半のみ構造的な0が加わる
+    let positive = [2, 3, 4, 3, 2, 5, 3, 1, 4, 3];
+    let mut data = (0..40).map(|i| positive[i % positive.len()]).collect::<Vec<u64>>();
+    data.extend((0..40).map(|i| if i % 3 == 2 { positive[i % positive.len()] } else { 0 }));
+    let cost = ZipCost::new(&data);
+    let result = detect(&cost, Method::Dp, &Penalty::NumChange(1), &Constraints::default()).unwrap();
+    assert_eq!(result.change_points, vec![40]);
+    assert_eq!(cost.estimate(0, 40).unwrap().0, 0.0);
+    assert!(cost.estimate(40, 80).unwrap().0 > 0.5);
+}
+
+
+#[test]
+fn rejects_invalid_segment() {
+    let cost = ZipCost::new(&[0, 1, 2]);
+    assert_eq!(cost.t_max(), 3);
+    assert!(cost.segment_value(0, 4).is_err());
+    assert!(cost.estimate(2, 2).is_err());
+    assert!(cost.segment_value(2, 1).is_err());
+}